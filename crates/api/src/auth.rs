@@ -0,0 +1,89 @@
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::{header, request::Parts, HeaderMap},
+};
+
+use crate::{error::ApiError, AppState};
+
+/// Extractor guarding endpoints that expose raw provider payloads. Accepts the `API_AUTH_KEY`
+/// value as `x-api-key: <key>` or `Authorization: Bearer <key>`.
+pub struct RequireApiKey;
+
+#[async_trait]
+impl FromRequestParts<AppState> for RequireApiKey {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, ApiError> {
+        let Some(expected) = state.api_key.as_deref() else {
+            return Err(ApiError::auth_not_configured("API_AUTH_KEY"));
+        };
+
+        match presented_key(&parts.headers) {
+            Some(key) if constant_time_eq(key.as_bytes(), expected.as_bytes()) => Ok(RequireApiKey),
+            _ => Err(ApiError::unauthorized()),
+        }
+    }
+}
+
+/// Extractor for `/admin/*`: the presented key (same headers as [`RequireApiKey`]) must be one
+/// of `ADMIN_API_KEYS`. The regular `API_AUTH_KEY` gets 403, anything else 401.
+pub struct RequireAdminKey;
+
+#[async_trait]
+impl FromRequestParts<AppState> for RequireAdminKey {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, ApiError> {
+        if state.admin_keys.is_empty() {
+            return Err(ApiError::auth_not_configured("ADMIN_API_KEYS"));
+        }
+        let Some(key) = presented_key(&parts.headers) else {
+            return Err(ApiError::unauthorized());
+        };
+
+        // Check every key so timing doesn't reveal which one matched.
+        let is_admin = state.admin_keys.iter().fold(false, |hit, k| {
+            constant_time_eq(key.as_bytes(), k.as_bytes()) | hit
+        });
+        if is_admin {
+            return Ok(RequireAdminKey);
+        }
+        match state.api_key.as_deref() {
+            Some(api_key) if constant_time_eq(key.as_bytes(), api_key.as_bytes()) => {
+                Err(ApiError::forbidden())
+            }
+            _ => Err(ApiError::unauthorized()),
+        }
+    }
+}
+
+/// Whether `key` is `API_AUTH_KEY` or one of `ADMIN_API_KEYS`.
+pub(crate) fn is_known_key(state: &AppState, key: &str) -> bool {
+    state
+        .api_key
+        .iter()
+        .map(|k| k.as_ref())
+        .chain(state.admin_keys.iter().map(String::as_str))
+        .fold(false, |hit, k| {
+            constant_time_eq(key.as_bytes(), k.as_bytes()) | hit
+        })
+}
+
+/// `x-api-key: <key>` or `Authorization: Bearer <key>`, trimmed.
+pub(crate) fn presented_key(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get("x-api-key")
+        .and_then(|v| v.to_str().ok())
+        .or_else(|| {
+            headers
+                .get(header::AUTHORIZATION)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.strip_prefix("Bearer "))
+        })
+        .map(str::trim)
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
@@ -1,20 +1,52 @@
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
-    routing::get,
-    Json, Router,
+    extract::{
+        rejection::{JsonRejection, QueryRejection},
+        MatchedPath, Path, Query, Request, State,
+    },
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode, Uri},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router, ServiceExt,
 };
 use chrono::{DateTime, NaiveDate, Utc};
-use serde::Serialize;
+use metrics_exporter_prometheus::PrometheusHandle;
+use serde::{Deserialize, Serialize};
 use sqlx::postgres::PgConnectOptions;
 use sqlx::PgPool;
+use std::collections::BTreeMap;
 use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+use tower_http::compression::CompressionLayer;
+use tower_http::normalize_path::NormalizePath;
 use tower_http::trace::TraceLayer;
 use tracing_subscriber::EnvFilter;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 use uuid::Uuid;
 
+use tootoo_core::domain::diff::{diff_snapshots, item_movements, Movement, SnapshotDiff};
 use tootoo_core::domain::recommendation::{RecommendationItem, RecommendationSnapshot};
+use tootoo_core::ingest::types::DailyFeatureItem;
+use tootoo_core::metrics::{
+    API_DB_ERRORS_TOTAL, API_REQUESTS_TOTAL, API_REQUEST_DURATION_SECONDS, API_SNAPSHOT_FETCH_TOTAL,
+};
+use tootoo_core::storage::recommendations::{
+    InvalidateOutcome, ItemFilter, ItemPage, LatestRun, SnapshotHeader, SnapshotPage,
+    SnapshotRangeRow, StoredSnapshot, DEFAULT_ITEMS_LIMIT, DEFAULT_LIST_LIMIT, MAX_RANGE_DAYS,
+};
+use tootoo_core::storage::stock_features::{
+    IngestRun, IngestRunSummary, DEFAULT_INGEST_RUNS_LIMIT, MAX_FEATURE_BATCH,
+};
+
+mod auth;
+mod error;
+mod openapi;
+mod rate_limit;
+mod reconnect;
+
+use auth::{RequireAdminKey, RequireApiKey};
+use error::{ApiError, ErrorBody};
+use reconnect::DbStatus;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -28,61 +60,80 @@ async fn main() -> anyhow::Result<()> {
         .with(tracing_subscriber::fmt::layer())
         .with(sentry_tracing::layer())
         .init();
+    let mut db_error = None;
+    let mut connect_options = None;
     let pool: Option<PgPool> = match settings.require_database_url() {
-        Ok(db_url) => {
-            let connect_options = match PgConnectOptions::from_str(db_url) {
-                Ok(v) => v.statement_cache_capacity(0),
-                Err(e) => {
-                    let err = anyhow::Error::new(e).context("parse DATABASE_URL failed");
-                    sentry_anyhow::capture_anyhow(&err);
-                    tracing::error!(error = %err, "db connect failed; starting API in degraded mode");
-                    return Ok(());
-                }
-            };
-
-            match sqlx::postgres::PgPoolOptions::new()
-                .max_connections(5)
-                .connect_with(connect_options)
-                .await
-            {
-                Ok(pool) => match tootoo_core::storage::migrate(&pool).await {
-                    Ok(()) => Some(pool),
+        Ok(db_url) => match PgConnectOptions::from_str(db_url) {
+            Ok(v) => {
+                let options = v.statement_cache_capacity(0);
+                connect_options = Some(options.clone());
+                match connect_and_migrate(options).await {
+                    Ok(pool) => Some(pool),
                     Err(e) => {
                         sentry_anyhow::capture_anyhow(&e);
                         tracing::error!(
-                            error = %e,
-                            "db migrations failed; starting API in degraded mode"
+                            error = %format!("{e:#}"),
+                            "db unavailable; starting API in degraded mode and retrying in the background"
                         );
+                        db_error = Some(e);
                         None
                     }
-                },
-                Err(e) => {
-                    let err = anyhow::Error::new(e);
-                    sentry_anyhow::capture_anyhow(&err);
-                    tracing::error!(error = %err, "db connect failed; starting API in degraded mode");
-                    None
                 }
             }
-        }
+            Err(e) => {
+                // A malformed URL won't fix itself; stay degraded without retrying.
+                let err = anyhow::Error::new(e).context("parse DATABASE_URL failed");
+                sentry_anyhow::capture_anyhow(&err);
+                tracing::error!(error = %err, "db connect failed; starting API in degraded mode");
+                db_error = Some(err);
+                None
+            }
+        },
         Err(e) => {
             sentry_anyhow::capture_anyhow(&e);
             tracing::error!(error = %e, "DATABASE_URL missing; starting API in degraded mode");
+            db_error = Some(e);
             None
         }
     };
 
-    let state = AppState { pool };
+    let metrics = match tootoo_core::metrics::install_prometheus_recorder() {
+        Ok(handle) => Some(handle),
+        Err(e) => {
+            tracing::warn!(error = %e, "metrics recorder install failed; /metrics disabled");
+            None
+        }
+    };
 
-    let app = Router::new()
-        .route("/healthz", get(healthz))
-        .route("/snapshots/latest", get(get_latest_snapshot))
-        .route("/snapshots/:as_of_date", get(get_snapshot_by_date))
-        .route(
-            "/items/:as_of_date/:ticker",
-            get(get_item_by_date_and_ticker),
-        )
-        .with_state(state)
-        .layer(TraceLayer::new_for_http());
+    let degraded = pool.is_none();
+    let state = AppState::new(pool)
+        .with_metrics(metrics)
+        .with_api_key(settings.api_auth_key.clone())
+        .with_admin_keys(settings.admin_api_keys.clone());
+    if let Some(e) = db_error {
+        state
+            .db_status
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .last_error = Some(format!("{e:#}"));
+    }
+    if let Some(options) = connect_options.filter(|_| degraded) {
+        tokio::spawn(reconnect::reconnect(
+            state.store.clone(),
+            state.db_status.clone(),
+            reconnect::BASE_BACKOFF,
+            reconnect::MAX_BACKOFF,
+            move || {
+                let options = options.clone();
+                async move {
+                    connect_and_migrate(options)
+                        .await
+                        .map(|pool| Arc::new(pool) as Arc<dyn Store>)
+                }
+            },
+        ));
+    }
+    let app = app(state, RouterOptions::from_env());
 
     let port: u16 = std::env::var("PORT")
         .ok()
@@ -93,6 +144,9 @@ async fn main() -> anyhow::Result<()> {
     tracing::info!(%addr, "api listening");
 
     let listener = tokio::net::TcpListener::bind(addr).await?;
+    // The peer address is the rate limiter's fallback client key.
+    let app =
+        ServiceExt::<Request>::into_make_service_with_connect_info::<std::net::SocketAddr>(app);
     axum::serve(listener, app)
         .with_graceful_shutdown(shutdown_signal())
         .await?;
@@ -100,253 +154,3063 @@ async fn main() -> anyhow::Result<()> {
     Ok(())
 }
 
+async fn connect_and_migrate(options: PgConnectOptions) -> anyhow::Result<PgPool> {
+    let pool = sqlx::postgres::PgPoolOptions::new()
+        .max_connections(5)
+        .connect_with(options)
+        .await
+        .map_err(|e| anyhow::Error::new(e).context("db connect failed"))?;
+    tootoo_core::storage::migrate(&pool)
+        .await
+        .map_err(|e| e.context("db migrations failed"))?;
+    Ok(pool)
+}
+
+const DEFAULT_RATE_LIMIT_PER_MIN: u32 = 60;
+
+#[derive(Debug, Clone)]
+struct RouterOptions {
+    /// gzip/brotli responses; off with `API_DISABLE_COMPRESSION=true`.
+    compress: bool,
+    /// Per-client requests per minute (`API_RATE_LIMIT_PER_MIN`, `0` disables).
+    rate_limit_per_min: Option<u32>,
+    /// Swagger UI at `/docs` (`API_ENABLE_DOCS=true`); `/openapi.json` is always served.
+    docs: bool,
+    /// Peers whose `X-Forwarded-For` the rate limiter believes (`API_TRUSTED_PROXIES`).
+    trusted_proxies: Arc<[std::net::IpAddr]>,
+}
+
+impl RouterOptions {
+    fn from_env() -> Self {
+        let compress = !std::env::var("API_DISABLE_COMPRESSION")
+            .map(|s| matches!(s.trim().to_ascii_lowercase().as_str(), "1" | "true"))
+            .unwrap_or(false);
+        let per_min = std::env::var("API_RATE_LIMIT_PER_MIN")
+            .ok()
+            .and_then(|s| s.trim().parse::<u32>().ok())
+            .unwrap_or(DEFAULT_RATE_LIMIT_PER_MIN);
+        let docs = std::env::var("API_ENABLE_DOCS")
+            .map(|s| matches!(s.trim().to_ascii_lowercase().as_str(), "1" | "true"))
+            .unwrap_or(false);
+        Self {
+            compress,
+            rate_limit_per_min: (per_min > 0).then_some(per_min),
+            docs,
+            trusted_proxies: rate_limit::trusted_proxies_from_env(),
+        }
+    }
+}
+
+/// [`router`] with trailing slashes trimmed (`/snapshots/latest/` -> `/snapshots/latest`).
+/// Routing happens inside the `Router`, so the rewrite has to wrap it from the outside.
+fn app(state: AppState, opts: RouterOptions) -> NormalizePath<Router> {
+    NormalizePath::trim_trailing_slash(router(state, opts))
+}
+
+fn router(state: AppState, opts: RouterOptions) -> Router {
+    let mut api = Router::new()
+        .route("/snapshots", get(list_snapshots))
+        .route("/runs/latest", get(get_latest_run))
+        .route("/snapshots/latest", get(get_latest_snapshot))
+        .route("/snapshots/range", get(get_snapshot_range))
+        .route("/snapshots/:as_of_date", get(get_snapshot_by_date))
+        .route("/snapshots/:as_of_date/diff", get(get_snapshot_diff))
+        .route("/snapshots/id/:snapshot_id", get(get_snapshot_by_id))
+        .route("/items", get(list_items))
+        .route(
+            "/items/:as_of_date/:ticker",
+            get(get_item_by_date_and_ticker),
+        )
+        .route("/features/:as_of_date", get(get_features_batch))
+        .route("/features/:as_of_date/:ticker", get(get_features))
+        .route("/ingest/runs", get(list_ingest_runs))
+        .route(
+            "/admin/snapshots/:snapshot_id/invalidate",
+            post(invalidate_snapshot),
+        )
+        .route("/ingest/runs/:id", get(get_ingest_run))
+        .route("/openapi.json", get(openapi::openapi_json));
+    if opts.docs {
+        api = api.route("/docs", get(openapi::swagger_ui));
+    }
+    // Keep this the outermost layer on `api`: anything that hashes response bodies (ETags) must
+    // be layered before it so it sees the uncompressed bytes.
+    if opts.compress {
+        api = api.layer(CompressionLayer::new().gzip(true).br(true));
+    }
+    // Outside compression so rejected requests cost as little as possible.
+    if let Some(per_min) = opts.rate_limit_per_min {
+        let limiter = rate_limit::RateLimitState {
+            limiter: Arc::new(rate_limit::RateLimiter::per_minute(per_min)),
+            app: state.clone(),
+            trusted_proxies: opts.trusted_proxies.clone(),
+        };
+        api = api.layer(middleware::from_fn_with_state(
+            limiter,
+            rate_limit::rate_limit,
+        ));
+    }
+
+    // Health, readiness and metrics are exempt from rate limiting and stay uncompressed: uptime
+    // checks, probes and Prometheus scrapers poll them and they are tiny.
+    Router::new()
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .route("/metrics", get(render_metrics))
+        .merge(api)
+        .route_layer(middleware::from_fn(track_metrics))
+        .fallback(route_not_found)
+        .method_not_allowed_fallback(method_not_allowed)
+        .with_state(state)
+        .layer(TraceLayer::new_for_http())
+}
+
+/// Unknown paths get the JSON error shape instead of axum's empty 404.
+async fn route_not_found(uri: Uri) -> ApiError {
+    ApiError::route_not_found(uri.path())
+}
+
+/// Known path, wrong method. axum still adds the `Allow` header.
+async fn method_not_allowed(method: Method, uri: Uri) -> ApiError {
+    ApiError::method_not_allowed(method.as_str(), uri.path())
+}
+
+/// Liveness probe; never touches the database or the LLM.
+#[utoipa::path(get, path = "/healthz", tag = "ops",
+    responses((status = 200, description = "Always `ok`", body = String, content_type = "text/plain")))]
 async fn healthz() -> &'static str {
     "ok"
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+struct ApiReadiness {
+    /// `ready` or `degraded` (no database; a background task keeps reconnecting).
+    status: &'static str,
+    /// Latest connect/migrate failure, secrets scrubbed and truncated.
+    last_error: Option<String>,
+    /// Background reconnect attempts so far.
+    retry_count: u32,
+}
+
+/// Readiness probe: whether the database pool is available. Reads in-process state only.
+#[utoipa::path(get, path = "/readyz", tag = "ops",
+    responses(
+        (status = 200, description = "Database pool available", body = ApiReadiness),
+        (status = 503, description = "Degraded; reconnecting in the background", body = ApiReadiness),
+    ))]
+async fn readyz(State(state): State<AppState>) -> (StatusCode, Json<ApiReadiness>) {
+    let ready = state.store().is_ok();
+    let db = state
+        .db_status
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+    let body = ApiReadiness {
+        status: if ready { "ready" } else { "degraded" },
+        last_error: db
+            .last_error
+            .as_deref()
+            .map(|e| tootoo_core::llm::redact::error_summary(e, RUN_ERROR_SUMMARY_CHARS)),
+        retry_count: db.retry_count,
+    };
+    let code = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (code, Json(body))
+}
+
+/// Prometheus text exposition; 404 when the recorder isn't installed.
+#[utoipa::path(get, path = "/metrics", tag = "ops",
+    responses(
+        (status = 200, description = "Prometheus text format", body = String, content_type = "text/plain"),
+        (status = 404, description = "Metrics recorder not installed"),
+    ))]
+async fn render_metrics(State(state): State<AppState>) -> Response {
+    match &state.metrics {
+        Some(handle) => handle.render().into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+/// Per-route request count and latency. Labelled by the route pattern, not the raw path, so
+/// `/snapshots/:as_of_date` stays one series.
+async fn track_metrics(req: Request, next: Next) -> Response {
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|p| p.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+    let method = req.method().to_string();
+    let started = std::time::Instant::now();
+
+    let response = next.run(req).await;
+
+    let labels = [
+        ("method", method),
+        ("route", route),
+        ("status", response.status().as_u16().to_string()),
+    ];
+    metrics::counter!(API_REQUESTS_TOTAL, &labels).increment(1);
+    metrics::histogram!(API_REQUEST_DURATION_SECONDS, &labels)
+        .record(started.elapsed().as_secs_f64());
+    response
+}
+
+fn count_snapshot_fetch<T>(found: Option<T>) -> Option<T> {
+    let result = if found.is_some() { "hit" } else { "miss" };
+    metrics::counter!(API_SNAPSHOT_FETCH_TOTAL, "result" => result).increment(1);
+    found
+}
+
+#[derive(Clone)]
 struct AppState {
-    pool: Option<PgPool>,
+    /// Database access; a seam so handlers can be tested without Postgres. Read per request:
+    /// empty while degraded until the background reconnect fills it.
+    store: Arc<RwLock<Option<Arc<dyn Store>>>>,
+    /// Reported by `/readyz` while the store is missing.
+    db_status: Arc<RwLock<DbStatus>>,
+    metrics: Option<PrometheusHandle>,
+    /// `API_AUTH_KEY`; endpoints taking [`RequireApiKey`] are closed when unset.
+    api_key: Option<Arc<str>>,
+    /// `ADMIN_API_KEYS`; endpoints taking [`RequireAdminKey`] are closed when empty.
+    admin_keys: Arc<[String]>,
 }
 
-#[derive(Debug, Serialize)]
-struct ApiSnapshot {
-    snapshot_id: Uuid,
-    provider: String,
-    snapshot: RecommendationSnapshot,
+impl AppState {
+    fn new(pool: Option<PgPool>) -> Self {
+        let store = pool.map(|p| Arc::new(p) as Arc<dyn Store>);
+        Self {
+            store: Arc::new(RwLock::new(store)),
+            db_status: Arc::default(),
+            metrics: None,
+            api_key: None,
+            admin_keys: Arc::from([]),
+        }
+    }
+
+    fn with_admin_keys(mut self, admin_keys: Vec<String>) -> Self {
+        self.admin_keys = admin_keys.into();
+        self
+    }
+
+    fn with_api_key(mut self, api_key: Option<String>) -> Self {
+        self.api_key = api_key.map(Arc::from);
+        self
+    }
+
+    fn with_metrics(mut self, metrics: Option<PrometheusHandle>) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    fn store(&self) -> Result<Arc<dyn Store>, ApiError> {
+        self.store
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+            .ok_or_else(ApiError::db_unavailable)
+    }
 }
 
-async fn get_latest_snapshot(
-    State(state): State<AppState>,
-) -> Result<Json<ApiSnapshot>, StatusCode> {
-    let Some(pool) = &state.pool else {
-        return Err(StatusCode::SERVICE_UNAVAILABLE);
-    };
+#[async_trait::async_trait]
+trait Store: Send + Sync {
+    async fn list_snapshots(
+        &self,
+        status: Option<&str>,
+        limit: i64,
+        offset: i64,
+    ) -> anyhow::Result<SnapshotPage>;
+
+    /// Latest successful snapshot, or the one for `as_of_date` when given, with its items.
+    async fn fetch_snapshot(
+        &self,
+        as_of_date: Option<NaiveDate>,
+    ) -> anyhow::Result<Option<(SnapshotHeader, Vec<RecommendationItem>)>>;
+
+    /// Snapshot of any status by primary key.
+    async fn fetch_snapshot_by_id(
+        &self,
+        snapshot_id: Uuid,
+    ) -> anyhow::Result<Option<StoredSnapshot>>;
+
+    /// Most recent successful snapshot strictly before `as_of_date`.
+    async fn fetch_previous_snapshot(
+        &self,
+        as_of_date: NaiveDate,
+    ) -> anyhow::Result<Option<RecommendationSnapshot>>;
+
+    /// `ticker -> rank` of that same previous snapshot; empty when there is none.
+    async fn fetch_previous_ranks(
+        &self,
+        as_of_date: NaiveDate,
+    ) -> anyhow::Result<BTreeMap<String, i32>>;
 
-    let (snapshot_id, provider, snapshot) = fetch_snapshot(pool, None)
+    /// `ticker` from the successful snapshot for `as_of_date` (pinned by `snapshot_id` when
+    /// given), resolved together with the snapshot. `Some((id, None))`: snapshot without ticker.
+    async fn fetch_item(
+        &self,
+        as_of_date: NaiveDate,
+        snapshot_id: Option<Uuid>,
+        ticker: &str,
+    ) -> anyhow::Result<Option<(Uuid, Option<RecommendationItem>)>>;
+
+    async fn list_items(
+        &self,
+        filter: &ItemFilter,
+        limit: i64,
+        offset: i64,
+    ) -> anyhow::Result<ItemPage>;
+
+    async fn fetch_latest_run(&self) -> anyhow::Result<Option<LatestRun>>;
+
+    async fn list_snapshot_range(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> anyhow::Result<Vec<SnapshotRangeRow>>;
+
+    async fn fetch_features(
+        &self,
+        as_of_date: NaiveDate,
+        ticker: &str,
+    ) -> anyhow::Result<Option<DailyFeatureItem>>;
+
+    async fn fetch_features_batch(
+        &self,
+        as_of_date: NaiveDate,
+        tickers: &[String],
+    ) -> anyhow::Result<Vec<DailyFeatureItem>>;
+
+    async fn list_ingest_runs(
+        &self,
+        provider: Option<&str>,
+        status: Option<&str>,
+        limit: i64,
+    ) -> anyhow::Result<Vec<IngestRunSummary>>;
+
+    async fn fetch_ingest_run(&self, id: Uuid) -> anyhow::Result<Option<IngestRun>>;
+
+    async fn invalidate_snapshot(
+        &self,
+        snapshot_id: Uuid,
+        reason: &str,
+    ) -> anyhow::Result<InvalidateOutcome>;
+}
+
+#[async_trait::async_trait]
+impl Store for PgPool {
+    async fn list_snapshots(
+        &self,
+        status: Option<&str>,
+        limit: i64,
+        offset: i64,
+    ) -> anyhow::Result<SnapshotPage> {
+        tootoo_core::storage::recommendations::list_snapshots(self, status, limit, offset)
+            .await
+            .inspect_err(|_| count_db_error())
+    }
+
+    async fn fetch_snapshot(
+        &self,
+        as_of_date: Option<NaiveDate>,
+    ) -> anyhow::Result<Option<(SnapshotHeader, Vec<RecommendationItem>)>> {
+        match as_of_date {
+            Some(d) => tootoo_core::storage::recommendations::fetch_success_by_date(self, d).await,
+            None => tootoo_core::storage::recommendations::fetch_latest_success(self).await,
+        }
+        .inspect_err(|_| count_db_error())
+    }
+
+    async fn fetch_snapshot_by_id(
+        &self,
+        snapshot_id: Uuid,
+    ) -> anyhow::Result<Option<StoredSnapshot>> {
+        tootoo_core::storage::recommendations::fetch_snapshot_by_id(self, snapshot_id)
+            .await
+            .inspect_err(|_| count_db_error())
+    }
+
+    async fn fetch_previous_snapshot(
+        &self,
+        as_of_date: NaiveDate,
+    ) -> anyhow::Result<Option<RecommendationSnapshot>> {
+        tootoo_core::storage::recommendations::fetch_latest_success_before(self, as_of_date)
+            .await
+            .inspect_err(|_| count_db_error())
+    }
+
+    async fn fetch_previous_ranks(
+        &self,
+        as_of_date: NaiveDate,
+    ) -> anyhow::Result<BTreeMap<String, i32>> {
+        tootoo_core::storage::recommendations::fetch_previous_ranks(self, as_of_date)
+            .await
+            .inspect_err(|_| count_db_error())
+    }
+
+    async fn fetch_item(
+        &self,
+        as_of_date: NaiveDate,
+        snapshot_id: Option<Uuid>,
+        ticker: &str,
+    ) -> anyhow::Result<Option<(Uuid, Option<RecommendationItem>)>> {
+        tootoo_core::storage::recommendations::fetch_item_by_ticker(
+            self,
+            as_of_date,
+            snapshot_id,
+            ticker,
+        )
         .await
-        .map_err(|e| {
-            sentry_anyhow::capture_anyhow(&e);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?
-        .ok_or(StatusCode::NOT_FOUND)?;
+        .inspect_err(|_| count_db_error())
+    }
 
-    Ok(Json(ApiSnapshot {
-        snapshot_id,
-        provider,
-        snapshot,
-    }))
+    async fn list_items(
+        &self,
+        filter: &ItemFilter,
+        limit: i64,
+        offset: i64,
+    ) -> anyhow::Result<ItemPage> {
+        tootoo_core::storage::recommendations::list_items(self, filter, limit, offset)
+            .await
+            .inspect_err(|_| count_db_error())
+    }
+
+    async fn fetch_latest_run(&self) -> anyhow::Result<Option<LatestRun>> {
+        tootoo_core::storage::recommendations::fetch_latest_run(self)
+            .await
+            .inspect_err(|_| count_db_error())
+    }
+
+    async fn list_snapshot_range(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> anyhow::Result<Vec<SnapshotRangeRow>> {
+        tootoo_core::storage::recommendations::list_snapshot_range(self, from, to)
+            .await
+            .inspect_err(|_| count_db_error())
+    }
+
+    async fn fetch_features(
+        &self,
+        as_of_date: NaiveDate,
+        ticker: &str,
+    ) -> anyhow::Result<Option<DailyFeatureItem>> {
+        tootoo_core::storage::stock_features::fetch_daily_features(self, as_of_date, ticker)
+            .await
+            .inspect_err(|_| count_db_error())
+    }
+
+    async fn fetch_features_batch(
+        &self,
+        as_of_date: NaiveDate,
+        tickers: &[String],
+    ) -> anyhow::Result<Vec<DailyFeatureItem>> {
+        tootoo_core::storage::stock_features::fetch_daily_features_batch(self, as_of_date, tickers)
+            .await
+            .inspect_err(|_| count_db_error())
+    }
+
+    async fn list_ingest_runs(
+        &self,
+        provider: Option<&str>,
+        status: Option<&str>,
+        limit: i64,
+    ) -> anyhow::Result<Vec<IngestRunSummary>> {
+        tootoo_core::storage::stock_features::list_ingest_runs(self, provider, status, limit)
+            .await
+            .inspect_err(|_| count_db_error())
+    }
+
+    async fn fetch_ingest_run(&self, id: Uuid) -> anyhow::Result<Option<IngestRun>> {
+        tootoo_core::storage::stock_features::fetch_ingest_run(self, id)
+            .await
+            .inspect_err(|_| count_db_error())
+    }
+
+    async fn invalidate_snapshot(
+        &self,
+        snapshot_id: Uuid,
+        reason: &str,
+    ) -> anyhow::Result<InvalidateOutcome> {
+        tootoo_core::storage::recommendations::invalidate_snapshot(self, snapshot_id, reason)
+            .await
+            .inspect_err(|_| count_db_error())
+    }
 }
 
-async fn get_snapshot_by_date(
+fn count_db_error() {
+    metrics::counter!(API_DB_ERRORS_TOTAL).increment(1);
+}
+
+#[derive(Debug, Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+struct ListSnapshotsQuery {
+    /// Page size; default 20, capped at 100.
+    #[param(minimum = 1)]
+    limit: Option<i64>,
+    #[param(minimum = 0)]
+    offset: Option<i64>,
+    /// `success` or `error`; both when omitted.
+    #[param(pattern = "^(success|error)$")]
+    status: Option<String>,
+}
+
+/// `GET /snapshots?limit=&offset=&status=`: history (newest first) for the frontend.
+#[utoipa::path(get, path = "/snapshots", tag = "snapshots", params(ListSnapshotsQuery),
+    responses(
+        (status = 200, body = SnapshotPage),
+        (status = 400, description = "Invalid query parameter", body = ErrorBody),
+        (status = 503, description = "Database unavailable", body = ErrorBody),
+    ))]
+async fn list_snapshots(
     State(state): State<AppState>,
-    Path(as_of_date): Path<String>,
-) -> Result<Json<ApiSnapshot>, StatusCode> {
-    let Some(pool) = &state.pool else {
-        return Err(StatusCode::SERVICE_UNAVAILABLE);
-    };
+    query: Result<Query<ListSnapshotsQuery>, QueryRejection>,
+) -> Result<Json<SnapshotPage>, ApiError> {
+    let Query(q) = query.map_err(|e| ApiError::invalid_query(e.body_text()))?;
 
-    let as_of_date =
-        NaiveDate::parse_from_str(&as_of_date, "%Y-%m-%d").map_err(|_| StatusCode::BAD_REQUEST)?;
+    let limit = q.limit.unwrap_or(DEFAULT_LIST_LIMIT);
+    if limit < 1 {
+        return Err(ApiError::invalid_query(format!(
+            "limit must be at least 1 (got {limit})"
+        )));
+    }
+    let offset = q.offset.unwrap_or(0);
+    if offset < 0 {
+        return Err(ApiError::invalid_query(format!(
+            "offset must not be negative (got {offset})"
+        )));
+    }
+    let status = status_filter(q.status.as_deref())?;
 
-    let (snapshot_id, provider, snapshot) = fetch_snapshot(pool, Some(as_of_date))
-        .await
-        .map_err(|e| {
-            sentry_anyhow::capture_anyhow(&e);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?
-        .ok_or(StatusCode::NOT_FOUND)?;
+    let page = state.store()?.list_snapshots(status, limit, offset).await?;
+    Ok(Json(page))
+}
 
-    Ok(Json(ApiSnapshot {
-        snapshot_id,
-        provider,
-        snapshot,
-    }))
+#[derive(Debug, Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+struct ListItemsQuery {
+    ticker: Option<String>,
+    /// First day (inclusive), `YYYY-MM-DD`.
+    #[param(value_type = Option<String>, format = Date)]
+    from: Option<String>,
+    /// Last day (inclusive), `YYYY-MM-DD`.
+    #[param(value_type = Option<String>, format = Date)]
+    to: Option<String>,
+    #[param(minimum = 0.0, maximum = 1.0)]
+    min_confidence: Option<f64>,
+    /// Page size; default 50, capped at 200.
+    #[param(minimum = 1)]
+    limit: Option<i64>,
+    #[param(minimum = 0)]
+    offset: Option<i64>,
 }
 
-async fn get_item_by_date_and_ticker(
+/// `GET /items?ticker=&from=&to=&min_confidence=&limit=&offset=`: items of successful snapshots
+/// across dates, ordered by `as_of_date` then rank.
+#[utoipa::path(get, path = "/items", tag = "snapshots", params(ListItemsQuery),
+    responses(
+        (status = 200, body = ItemPage),
+        (status = 400, description = "Invalid filter or paging parameter", body = ErrorBody),
+        (status = 503, description = "Database unavailable", body = ErrorBody),
+    ))]
+async fn list_items(
     State(state): State<AppState>,
-    Path((as_of_date, ticker)): Path<(String, String)>,
-) -> Result<Json<RecommendationItem>, StatusCode> {
-    let Some(pool) = &state.pool else {
-        return Err(StatusCode::SERVICE_UNAVAILABLE);
+    query: Result<Query<ListItemsQuery>, QueryRejection>,
+) -> Result<Json<ItemPage>, ApiError> {
+    let Query(q) = query.map_err(|e| ApiError::invalid_query(e.body_text()))?;
+
+    let limit = q.limit.unwrap_or(DEFAULT_ITEMS_LIMIT);
+    if limit < 1 {
+        return Err(ApiError::invalid_query(format!(
+            "limit must be at least 1 (got {limit})"
+        )));
+    }
+    let offset = q.offset.unwrap_or(0);
+    if offset < 0 {
+        return Err(ApiError::invalid_query(format!(
+            "offset must not be negative (got {offset})"
+        )));
+    }
+    let from = q.from.as_deref().map(parse_as_of_date).transpose()?;
+    let to = q.to.as_deref().map(parse_as_of_date).transpose()?;
+    if let (Some(from), Some(to)) = (from, to) {
+        if from > to {
+            return Err(ApiError::invalid_query(format!(
+                "from ({from}) must not be after to ({to})"
+            )));
+        }
+    }
+    if let Some(c) = q.min_confidence {
+        if !(0.0..=1.0).contains(&c) {
+            return Err(ApiError::invalid_query(format!(
+                "min_confidence must be between 0 and 1 (got {c})"
+            )));
+        }
+    }
+
+    let filter = ItemFilter {
+        ticker: q
+            .ticker
+            .filter(|t| !t.trim().is_empty())
+            .map(|t| normalize_ticker(&t)),
+        from,
+        to,
+        min_confidence: q.min_confidence,
     };
+    let page = state.store()?.list_items(&filter, limit, offset).await?;
+    Ok(Json(page))
+}
 
-    let as_of_date =
-        NaiveDate::parse_from_str(&as_of_date, "%Y-%m-%d").map_err(|_| StatusCode::BAD_REQUEST)?;
+/// `status` query parameter shared by the history endpoints; empty means "any".
+fn status_filter(raw: Option<&str>) -> Result<Option<&str>, ApiError> {
+    match raw {
+        None | Some("") => Ok(None),
+        Some(s @ ("success" | "error")) => Ok(Some(s)),
+        Some(other) => Err(ApiError::invalid_query(format!(
+            "status must be \"success\" or \"error\" (got {other:?})"
+        ))),
+    }
+}
 
-    let (snapshot_id, _, _) = fetch_snapshot(pool, Some(as_of_date))
-        .await
-        .map_err(|e| {
-            sentry_anyhow::capture_anyhow(&e);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?
-        .ok_or(StatusCode::NOT_FOUND)?;
+/// [`status_filter`] for ingest runs, which may also be `skipped_duplicate`, `running` or
+/// `abandoned`.
+fn ingest_status_filter(raw: Option<&str>) -> Result<Option<&str>, ApiError> {
+    match raw {
+        Some(s @ ("skipped_duplicate" | "running" | "abandoned")) => Ok(Some(s)),
+        Some(other) => status_filter(Some(other)).map_err(|_| {
+            ApiError::invalid_query(format!(
+                "status must be \"success\", \"error\", \"skipped_duplicate\", \"running\" or \"abandoned\" (got {other:?})"
+            ))
+        }),
+        None => Ok(None),
+    }
+}
 
-    let item = fetch_item(pool, snapshot_id, &ticker)
-        .await
-        .map_err(|e| {
-            sentry_anyhow::capture_anyhow(&e);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?
-        .ok_or(StatusCode::NOT_FOUND)?;
+#[derive(Debug, Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+struct ListIngestRunsQuery {
+    /// Default 20, capped at 100.
+    #[param(minimum = 1)]
+    limit: Option<i64>,
+    /// e.g. `kis`, `external`.
+    provider: Option<String>,
+    /// `success`, `error`, `skipped_duplicate` (the provider's payload was unchanged),
+    /// `running` (still fetching) or `abandoned` (the worker died mid-run); all when omitted.
+    #[param(pattern = "^(success|error|skipped_duplicate|running|abandoned)$")]
+    status: Option<String>,
+}
 
-    Ok(Json(item))
+/// `GET /ingest/runs?limit=&provider=&status=`: recent feature ingest runs, newest first, with
+/// their start/finish times and duration.
+#[utoipa::path(get, path = "/ingest/runs", tag = "ingest", params(ListIngestRunsQuery),
+    responses(
+        (status = 200, description = "`error` is cut to 500 characters", body = Vec<IngestRunSummary>),
+        (status = 400, description = "Invalid query parameter", body = ErrorBody),
+        (status = 503, description = "Database unavailable", body = ErrorBody),
+    ))]
+async fn list_ingest_runs(
+    State(state): State<AppState>,
+    query: Result<Query<ListIngestRunsQuery>, QueryRejection>,
+) -> Result<Json<Vec<IngestRunSummary>>, ApiError> {
+    let Query(q) = query.map_err(|e| ApiError::invalid_query(e.body_text()))?;
+
+    let limit = q.limit.unwrap_or(DEFAULT_INGEST_RUNS_LIMIT);
+    if limit < 1 {
+        return Err(ApiError::invalid_query(format!(
+            "limit must be at least 1 (got {limit})"
+        )));
+    }
+    let provider = q.provider.as_deref().filter(|p| !p.is_empty());
+    let status = ingest_status_filter(q.status.as_deref())?;
+
+    let runs = state
+        .store()?
+        .list_ingest_runs(provider, status, limit)
+        .await?;
+    Ok(Json(runs))
 }
 
-async fn fetch_snapshot(
-    pool: &PgPool,
-    as_of_date: Option<NaiveDate>,
-) -> anyhow::Result<Option<(Uuid, String, RecommendationSnapshot)>> {
-    let row = match as_of_date {
-        Some(d) => {
-            sqlx::query_as::<_, (Uuid, NaiveDate, DateTime<Utc>, String)>(
-                "SELECT id, as_of_date, generated_at, provider \
-                 FROM recommendation_snapshots \
-                 WHERE status = 'success' AND as_of_date = $1 \
-                 ORDER BY generated_at DESC \
-                 LIMIT 1",
-            )
-            .persistent(false)
-            .bind(d)
-            .fetch_optional(pool)
-            .await?
-        }
-        None => {
-            sqlx::query_as::<_, (Uuid, NaiveDate, DateTime<Utc>, String)>(
-                "SELECT id, as_of_date, generated_at, provider \
-                 FROM recommendation_snapshots \
-                 WHERE status = 'success' \
-                 ORDER BY as_of_date DESC, generated_at DESC \
-                 LIMIT 1",
-            )
-            .persistent(false)
-            .fetch_optional(pool)
-            .await?
+/// `GET /ingest/runs/:id`: the full row including `raw_response`; requires the API key.
+#[utoipa::path(get, path = "/ingest/runs/{id}", tag = "ingest",
+    params(("id" = Uuid, Path, description = "Ingest run id")),
+    security(("api_key" = []), ("bearer" = [])),
+    responses(
+        (status = 200, body = IngestRun),
+        (status = 400, description = "`id` is not a UUID", body = ErrorBody),
+        (status = 401, description = "Missing or invalid API key", body = ErrorBody),
+        (status = 404, body = ErrorBody),
+        (status = 503, description = "`API_AUTH_KEY` unset or database unavailable", body = ErrorBody),
+    ))]
+async fn get_ingest_run(
+    _auth: RequireApiKey,
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<IngestRun>, ApiError> {
+    let id = parse_id(&id, "ingest run")?;
+
+    let run = state.store()?.fetch_ingest_run(id).await?.ok_or_else(|| {
+        ApiError::not_found("ingest_run_not_found", format!("no ingest run {id}"))
+    })?;
+    Ok(Json(run))
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+struct ApiSnapshot {
+    snapshot_id: Uuid,
+    provider: String,
+    snapshot: ApiSnapshotBody,
+}
+
+/// `RecommendationSnapshot` with items cut by `?top=` and `?fields=`.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+struct ApiSnapshotBody {
+    as_of_date: NaiveDate,
+    generated_at: DateTime<Utc>,
+    items: Vec<ApiSnapshotItem>,
+}
+
+/// `RecommendationItem` where each field can be left out. Nullable fields are doubly optional
+/// so a selected-but-empty `risk_notes` still serializes as `null`.
+///
+/// Every field is present unless excluded via `?fields=`.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+struct ApiSnapshotItem {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(required = false)]
+    rank: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(required = false)]
+    ticker: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(required = false)]
+    name: Option<String>,
+    /// English company name; `null` when the ingest source had none.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<String>, required = false)]
+    name_en: Option<Option<String>>,
+    /// Exactly 3 lines.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(required = false, min_items = 3, max_items = 3)]
+    rationale: Option<[String; 3]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<String>, required = false)]
+    risk_notes: Option<Option<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<f64>, required = false, minimum = 0.0, maximum = 1.0)]
+    confidence: Option<Option<f64>>,
+    /// Rank in the previous successful snapshot; only with `?with_movement=true`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<i32>, required = false)]
+    previous_rank: Option<Option<i32>>,
+    /// Only with `?with_movement=true`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(required = false)]
+    movement: Option<Movement>,
+}
+
+const ITEM_FIELDS: [&str; 7] = [
+    "rank",
+    "ticker",
+    "name",
+    "name_en",
+    "rationale",
+    "risk_notes",
+    "confidence",
+];
+
+#[derive(Debug, Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+struct SnapshotQuery {
+    /// Keep only the first N items by rank.
+    #[param(minimum = 1)]
+    top: Option<usize>,
+    /// Comma-separated subset of `rank,ticker,name,name_en,rationale,risk_notes,confidence`;
+    /// all fields when absent. Unknown names are rejected with 400.
+    #[param(example = "rank,ticker,name")]
+    fields: Option<String>,
+    /// Add `previous_rank` and `movement` to each item, compared with the previous successful
+    /// snapshot (one extra query).
+    with_movement: Option<bool>,
+}
+
+/// `(previous_rank, movement)` from [`item_movements`].
+type ItemMovement = (Option<i32>, Movement);
+
+impl SnapshotQuery {
+    /// Ranks to compare against when `with_movement=true`, else `None`.
+    async fn movement_baseline(
+        &self,
+        store: &dyn Store,
+        as_of_date: NaiveDate,
+    ) -> Result<Option<BTreeMap<String, i32>>, ApiError> {
+        if self.with_movement != Some(true) {
+            return Ok(None);
         }
-    };
+        Ok(Some(store.fetch_previous_ranks(as_of_date).await?))
+    }
 
-    let Some((id, as_of_date, generated_at, provider)) = row else {
-        return Ok(None);
-    };
+    fn apply(
+        &self,
+        snapshot: RecommendationSnapshot,
+        previous_ranks: Option<&BTreeMap<String, i32>>,
+    ) -> Result<ApiSnapshotBody, ApiError> {
+        if self.top == Some(0) {
+            return Err(ApiError::invalid_query("top must be at least 1"));
+        }
+        let fields: Vec<&str> = match self.fields.as_deref() {
+            None => ITEM_FIELDS.to_vec(),
+            Some(raw) => {
+                let fields: Vec<&str> = raw
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|f| !f.is_empty())
+                    .collect();
+                if let Some(bad) = fields.iter().find(|f| !ITEM_FIELDS.contains(f)) {
+                    return Err(ApiError::invalid_query(format!(
+                        "unknown field {bad:?}; expected any of {}",
+                        ITEM_FIELDS.join(",")
+                    )));
+                }
+                if fields.is_empty() {
+                    return Err(ApiError::invalid_query("fields must not be empty"));
+                }
+                fields
+            }
+        };
+        let has = |f: &str| fields.contains(&f);
+
+        let mut items: Vec<(RecommendationItem, Option<ItemMovement>)> = match previous_ranks {
+            Some(ranks) => {
+                let movements = item_movements(&snapshot, ranks);
+                snapshot
+                    .items
+                    .into_iter()
+                    .zip(movements.into_iter().map(Some))
+                    .collect()
+            }
+            None => snapshot.items.into_iter().map(|i| (i, None)).collect(),
+        };
+        items.sort_by_key(|(i, _)| i.rank);
+        items.truncate(self.top.unwrap_or(usize::MAX));
 
-    let items = fetch_items(pool, id).await?;
+        Ok(ApiSnapshotBody {
+            as_of_date: snapshot.as_of_date,
+            generated_at: snapshot.generated_at,
+            items: items
+                .into_iter()
+                .map(|(i, movement)| ApiSnapshotItem {
+                    rank: has("rank").then_some(i.rank),
+                    ticker: has("ticker").then_some(i.ticker),
+                    name: has("name").then_some(i.name),
+                    name_en: has("name_en").then_some(i.name_en),
+                    rationale: has("rationale").then_some(i.rationale),
+                    risk_notes: has("risk_notes").then_some(i.risk_notes),
+                    confidence: has("confidence").then_some(i.confidence),
+                    previous_rank: movement.map(|(previous_rank, _)| previous_rank),
+                    movement: movement.map(|(_, movement)| movement),
+                })
+                .collect(),
+        })
+    }
+}
 
-    Ok(Some((
-        id,
-        provider,
-        RecommendationSnapshot {
-            as_of_date,
-            generated_at,
-            items,
-        },
-    )))
+/// Validators for a snapshot response: `Last-Modified` from the stored `generated_at` (what the
+/// CDN revalidates with) and a weak `ETag` naming the snapshot served and, with `with_movement`,
+/// the baseline its movement was computed against. An invalidation changes the tag even when the
+/// fallback generation is older, so `If-None-Match` wins over `If-Modified-Since` when both are
+/// sent (RFC 9110 §13.2.2). GET and HEAD go through the same handler and this one check, so they
+/// cannot disagree on a 304.
+struct Conditional {
+    etag: HeaderValue,
+    last_modified: HeaderValue,
+    not_modified: bool,
 }
 
-async fn fetch_items(pool: &PgPool, snapshot_id: Uuid) -> anyhow::Result<Vec<RecommendationItem>> {
-    let rows = sqlx::query_as::<
-        _,
+impl Conditional {
+    fn evaluate(
+        headers: &HeaderMap,
+        snapshot_id: Uuid,
+        generated_at: DateTime<Utc>,
+        previous_ranks: Option<&BTreeMap<String, i32>>,
+    ) -> Self {
+        // Weak: the compression layer serves the same representation with different bytes.
+        let etag = match previous_ranks {
+            None => format!("W/\"{snapshot_id}\""),
+            Some(ranks) => {
+                let baseline = serde_json::to_vec(ranks).unwrap_or_default();
+                let baseline = tootoo_core::llm::sha256_hex(&baseline);
+                format!("W/\"{snapshot_id}-{}\"", &baseline[..16])
+            }
+        };
+        let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+        let mut if_none_match = headers
+            .get_all(header::IF_NONE_MATCH)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .peekable();
+        let not_modified = if if_none_match.peek().is_some() {
+            if_none_match.any(|tag| tag.trim() == "*" || opaque(tag) == opaque(&etag))
+        } else {
+            // HTTP dates have whole seconds; compare at that precision so our own header
+            // round-trips.
+            headers
+                .get(header::IF_MODIFIED_SINCE)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| DateTime::parse_from_rfc2822(v).ok())
+                .is_some_and(|since| generated_at.timestamp() <= since.timestamp())
+        };
+        let http_date = generated_at.format("%a, %d %b %Y %H:%M:%S GMT").to_string();
+        Self {
+            etag: HeaderValue::from_str(&etag).expect("ETag is ASCII"),
+            last_modified: HeaderValue::from_str(&http_date).expect("HTTP date is ASCII"),
+            not_modified,
+        }
+    }
+
+    fn not_modified_response(self) -> Response {
         (
-            i32,
-            String,
-            String,
-            Vec<String>,
-            Option<String>,
-            Option<f64>,
-        ),
-    >(
-        "SELECT rank, ticker, name, rationale, risk_notes, confidence \
-         FROM recommendation_items \
-         WHERE snapshot_id = $1 \
-         ORDER BY rank ASC",
-    )
-    .persistent(false)
-    .bind(snapshot_id)
-    .fetch_all(pool)
-    .await?;
-
-    let mut out = Vec::with_capacity(rows.len());
-    for (rank, ticker, name, rationale, risk_notes, confidence) in rows {
-        anyhow::ensure!(
-            rationale.len() == 3,
-            "invalid rationale length in DB for snapshot_id={snapshot_id}, ticker={ticker}"
-        );
-        out.push(RecommendationItem {
-            rank,
-            ticker,
-            name,
-            rationale: [
-                rationale[0].clone(),
-                rationale[1].clone(),
-                rationale[2].clone(),
+            StatusCode::NOT_MODIFIED,
+            [
+                (header::ETAG, self.etag),
+                (header::LAST_MODIFIED, self.last_modified),
             ],
-            risk_notes,
-            confidence,
-        });
+        )
+            .into_response()
     }
-    Ok(out)
-}
 
-async fn fetch_item(
-    pool: &PgPool,
-    snapshot_id: Uuid,
-    ticker: &str,
-) -> anyhow::Result<Option<RecommendationItem>> {
-    let row = sqlx::query_as::<
-        _,
+    fn respond(self, body: impl IntoResponse) -> Response {
         (
-            i32,
-            String,
-            String,
-            Vec<String>,
-            Option<String>,
-            Option<f64>,
-        ),
-    >(
-        "SELECT rank, ticker, name, rationale, risk_notes, confidence \
-         FROM recommendation_items \
-         WHERE snapshot_id = $1 AND ticker = $2 \
-         LIMIT 1",
-    )
-    .persistent(false)
-    .bind(snapshot_id)
-    .bind(ticker)
-    .fetch_optional(pool)
-    .await?;
-
-    let Some((rank, ticker, name, rationale, risk_notes, confidence)) = row else {
-        return Ok(None);
+            [
+                (header::ETAG, self.etag),
+                (header::LAST_MODIFIED, self.last_modified),
+            ],
+            body,
+        )
+            .into_response()
+    }
+}
+
+/// `GET`/`HEAD /snapshots/latest?top=&fields=&with_movement=`
+#[utoipa::path(get, path = "/snapshots/latest", tag = "snapshots",
+    params(
+        SnapshotQuery,
+        ("if-none-match" = Option<String>, Header,
+            description = "ETags from earlier responses; 304 when one still matches"),
+        ("if-modified-since" = Option<String>, Header,
+            description = "HTTP date; 304 when the snapshot was generated at or before it (ignored when `If-None-Match` is sent)"),
+    ),
+    responses(
+        (status = 200, description = "Latest successful snapshot", body = ApiSnapshot,
+            headers(
+                ("etag" = String, description = "Weak tag over the snapshot id and, with `with_movement`, its baseline"),
+                ("last-modified" = String, description = "The snapshot's `generated_at`"),
+            )),
+        (status = 304, description = "`If-None-Match` still matches, or not modified since `If-Modified-Since`"),
+        (status = 400, description = "Invalid `top`/`fields`", body = ErrorBody),
+        (status = 404, body = ErrorBody),
+        (status = 503, description = "Database unavailable", body = ErrorBody),
+    ))]
+async fn get_latest_snapshot(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    query: Result<Query<SnapshotQuery>, QueryRejection>,
+) -> Result<Response, ApiError> {
+    let Query(q) = query.map_err(|e| ApiError::invalid_query(e.body_text()))?;
+
+    let store = state.store()?;
+    let found = count_snapshot_fetch(store.fetch_snapshot(None).await?);
+    let (header, items) = found
+        .ok_or_else(|| ApiError::not_found("snapshot_not_found", "no successful snapshot yet"))?;
+    let previous_ranks = q
+        .movement_baseline(store.as_ref(), header.as_of_date)
+        .await?;
+    let conditional = Conditional::evaluate(
+        &headers,
+        header.snapshot_id,
+        header.generated_at,
+        previous_ranks.as_ref(),
+    );
+    if conditional.not_modified {
+        return Ok(conditional.not_modified_response());
+    }
+
+    Ok(conditional.respond(Json(ApiSnapshot {
+        snapshot_id: header.snapshot_id,
+        snapshot: q.apply(header.snapshot(items), previous_ranks.as_ref())?,
+        provider: header.provider,
+    })))
+}
+
+#[derive(Debug, Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+struct SnapshotRangeQuery {
+    /// First day (inclusive), `YYYY-MM-DD`.
+    #[param(required = true, value_type = String, format = Date)]
+    from: Option<String>,
+    /// Last day (inclusive), `YYYY-MM-DD`; at most 370 days after `from`.
+    #[param(required = true, value_type = String, format = Date)]
+    to: Option<String>,
+}
+
+/// `GET /snapshots/range?from=&to=`: per-day summary of successful snapshots, ascending.
+#[utoipa::path(get, path = "/snapshots/range", tag = "snapshots", params(SnapshotRangeQuery),
+    responses(
+        (status = 200, description = "Days without a snapshot are absent", body = Vec<SnapshotRangeRow>),
+        (status = 400, description = "Missing/invalid dates or range too long", body = ErrorBody),
+        (status = 503, description = "Database unavailable", body = ErrorBody),
+    ))]
+async fn get_snapshot_range(
+    State(state): State<AppState>,
+    query: Result<Query<SnapshotRangeQuery>, QueryRejection>,
+) -> Result<Json<Vec<SnapshotRangeRow>>, ApiError> {
+    let Query(q) = query.map_err(|e| ApiError::invalid_query(e.body_text()))?;
+    let (Some(from), Some(to)) = (q.from.as_deref(), q.to.as_deref()) else {
+        return Err(ApiError::invalid_query("from and to are required"));
     };
+    let (from, to) = (parse_as_of_date(from)?, parse_as_of_date(to)?);
+    if from > to {
+        return Err(ApiError::invalid_query(format!(
+            "from ({from}) must not be after to ({to})"
+        )));
+    }
+    if (to - from).num_days() > MAX_RANGE_DAYS {
+        return Err(ApiError::invalid_query(format!(
+            "range {from}..{to} is longer than {MAX_RANGE_DAYS} days"
+        )));
+    }
 
-    if rationale.len() != 3 {
-        return Ok(None);
-    }
-
-    Ok(Some(RecommendationItem {
-        rank,
-        ticker,
-        name,
-        rationale: [
-            rationale[0].clone(),
-            rationale[1].clone(),
-            rationale[2].clone(),
-        ],
-        risk_notes,
-        confidence,
-    }))
+    let rows = state.store()?.list_snapshot_range(from, to).await?;
+    Ok(Json(rows))
 }
 
-async fn shutdown_signal() {
-    let _ = tokio::signal::ctrl_c().await;
+/// Longest `error` returned by `/runs/latest`.
+const RUN_ERROR_SUMMARY_CHARS: usize = 200;
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+struct ApiRun {
+    snapshot_id: Uuid,
+    as_of_date: NaiveDate,
+    generated_at: DateTime<Utc>,
+    provider: String,
+    status: String,
+    error_kind: Option<String>,
+    /// First line of the stored error, secrets scrubbed and truncated.
+    error: Option<String>,
 }
 
-fn init_sentry(settings: &tootoo_core::config::Settings) -> Option<sentry::ClientInitGuard> {
-    let dsn = settings.sentry_dsn.as_deref()?;
-    Some(sentry::init((
-        dsn,
+/// `GET /runs/latest`: the newest run of any status, so clients can tell "today's picks are
+/// delayed" apart from "nothing changed".
+#[utoipa::path(get, path = "/runs/latest", tag = "snapshots",
+    responses(
+        (status = 200, body = ApiRun),
+        (status = 404, body = ErrorBody),
+        (status = 503, description = "Database unavailable", body = ErrorBody),
+    ))]
+async fn get_latest_run(State(state): State<AppState>) -> Result<Json<ApiRun>, ApiError> {
+    let run = state
+        .store()?
+        .fetch_latest_run()
+        .await?
+        .ok_or_else(|| ApiError::not_found("run_not_found", "no recommendation runs yet"))?;
+
+    Ok(Json(ApiRun {
+        snapshot_id: run.snapshot_id,
+        as_of_date: run.as_of_date,
+        generated_at: run.generated_at,
+        provider: run.provider,
+        status: run.status,
+        error_kind: run.error_kind,
+        error: run
+            .error
+            .as_deref()
+            .map(|e| tootoo_core::llm::redact::error_summary(e, RUN_ERROR_SUMMARY_CHARS)),
+    }))
+}
+
+/// `GET`/`HEAD /snapshots/:as_of_date?top=&fields=&with_movement=`
+#[utoipa::path(get, path = "/snapshots/{as_of_date}", tag = "snapshots",
+    params(
+        ("as_of_date" = NaiveDate, Path, description = "Market date, `YYYY-MM-DD`"),
+        SnapshotQuery,
+        ("if-none-match" = Option<String>, Header,
+            description = "ETags from earlier responses; 304 when one still matches"),
+        ("if-modified-since" = Option<String>, Header,
+            description = "HTTP date; 304 when the snapshot was generated at or before it (ignored when `If-None-Match` is sent)"),
+    ),
+    responses(
+        (status = 200, description = "Successful snapshot for that date", body = ApiSnapshot,
+            headers(
+                ("etag" = String, description = "Weak tag over the snapshot id and, with `with_movement`, its baseline"),
+                ("last-modified" = String, description = "The snapshot's `generated_at`"),
+            )),
+        (status = 304, description = "`If-None-Match` still matches, or not modified since `If-Modified-Since`"),
+        (status = 400, description = "Invalid date or `top`/`fields`", body = ErrorBody),
+        (status = 404, body = ErrorBody),
+        (status = 503, description = "Database unavailable", body = ErrorBody),
+    ))]
+async fn get_snapshot_by_date(
+    State(state): State<AppState>,
+    Path(as_of_date): Path<String>,
+    headers: HeaderMap,
+    query: Result<Query<SnapshotQuery>, QueryRejection>,
+) -> Result<Response, ApiError> {
+    let as_of_date = parse_as_of_date(&as_of_date)?;
+    let Query(q) = query.map_err(|e| ApiError::invalid_query(e.body_text()))?;
+
+    let store = state.store()?;
+    let found = count_snapshot_fetch(store.fetch_snapshot(Some(as_of_date)).await?);
+    let (header, items) = found.ok_or_else(|| snapshot_not_found(as_of_date))?;
+    let previous_ranks = q.movement_baseline(store.as_ref(), as_of_date).await?;
+    let conditional = Conditional::evaluate(
+        &headers,
+        header.snapshot_id,
+        header.generated_at,
+        previous_ranks.as_ref(),
+    );
+    if conditional.not_modified {
+        return Ok(conditional.not_modified_response());
+    }
+
+    Ok(conditional.respond(Json(ApiSnapshot {
+        snapshot_id: header.snapshot_id,
+        snapshot: q.apply(header.snapshot(items), previous_ranks.as_ref())?,
+        provider: header.provider,
+    })))
+}
+
+/// `GET /snapshots/id/:snapshot_id?top=&fields=&with_movement=`: a snapshot by primary key, so
+/// deep links keep pointing at the same generation when a date has several.
+#[utoipa::path(get, path = "/snapshots/id/{snapshot_id}", tag = "snapshots",
+    params(("snapshot_id" = Uuid, Path), SnapshotQuery),
+    responses(
+        (status = 200, description = "Successful snapshot", body = ApiSnapshot),
+        (status = 400, description = "Invalid id or `top`/`fields`", body = ErrorBody),
+        (status = 404, body = ErrorBody),
+        (status = 410, description = "The snapshot is a failed run (`snapshot_failed`) or was invalidated (`snapshot_invalidated`)", body = ErrorBody),
+        (status = 503, description = "Database unavailable", body = ErrorBody),
+    ))]
+async fn get_snapshot_by_id(
+    State(state): State<AppState>,
+    Path(snapshot_id): Path<String>,
+    query: Result<Query<SnapshotQuery>, QueryRejection>,
+) -> Result<Json<ApiSnapshot>, ApiError> {
+    let snapshot_id = parse_id(&snapshot_id, "snapshot")?;
+    let Query(q) = query.map_err(|e| ApiError::invalid_query(e.body_text()))?;
+
+    let store = state.store()?;
+    let found = count_snapshot_fetch(store.fetch_snapshot_by_id(snapshot_id).await?);
+    let stored = found.ok_or_else(|| {
+        ApiError::not_found("snapshot_not_found", format!("no snapshot {snapshot_id}"))
+    })?;
+    if let Some(at) = stored.invalidated_at {
+        return Err(ApiError::new(
+            StatusCode::GONE,
+            "snapshot_invalidated",
+            format!(
+                "snapshot {snapshot_id} was invalidated at {at}: {}",
+                stored.invalidated_reason.as_deref().unwrap_or_default()
+            ),
+        ));
+    }
+    if stored.status != "success" {
+        return Err(ApiError::new(
+            StatusCode::GONE,
+            "snapshot_failed",
+            format!(
+                "snapshot {snapshot_id} is a failed run ({})",
+                stored.error_kind.as_deref().unwrap_or("unknown error")
+            ),
+        ));
+    }
+
+    let previous_ranks = q
+        .movement_baseline(store.as_ref(), stored.snapshot.as_of_date)
+        .await?;
+
+    Ok(Json(ApiSnapshot {
+        snapshot_id,
+        provider: stored.provider,
+        snapshot: q.apply(stored.snapshot, previous_ranks.as_ref())?,
+    }))
+}
+
+/// `GET /snapshots/:as_of_date/diff`: added/removed names and rank moves vs the previous
+/// successful snapshot (whatever trading day that was).
+#[utoipa::path(get, path = "/snapshots/{as_of_date}/diff", tag = "snapshots",
+    params(("as_of_date" = NaiveDate, Path, description = "Market date, `YYYY-MM-DD`")),
+    responses(
+        (status = 200, body = SnapshotDiff),
+        (status = 400, description = "Invalid date", body = ErrorBody),
+        (status = 404, body = ErrorBody),
+        (status = 503, description = "Database unavailable", body = ErrorBody),
+    ))]
+async fn get_snapshot_diff(
+    State(state): State<AppState>,
+    Path(as_of_date): Path<String>,
+) -> Result<Json<SnapshotDiff>, ApiError> {
+    let as_of_date = parse_as_of_date(&as_of_date)?;
+    let store = state.store()?;
+
+    let found = count_snapshot_fetch(store.fetch_snapshot(Some(as_of_date)).await?);
+    let (header, items) = found.ok_or_else(|| snapshot_not_found(as_of_date))?;
+    let previous = store.fetch_previous_snapshot(as_of_date).await?;
+
+    Ok(Json(diff_snapshots(
+        &header.snapshot(items),
+        previous.as_ref(),
+    )))
+}
+
+/// Response header naming the snapshot an item was read from.
+const SNAPSHOT_ID_HEADER: &str = "x-snapshot-id";
+
+#[derive(Debug, Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+struct ItemQuery {
+    /// Pin the lookup to this snapshot (the `snapshot_id` of a snapshot response).
+    #[param(value_type = Option<Uuid>)]
+    snapshot_id: Option<String>,
+}
+
+/// `GET /items/:as_of_date/:ticker?snapshot_id=`: one item from that day's successful snapshot.
+/// The snapshot and item are resolved in one lookup, so they always belong together.
+#[utoipa::path(get, path = "/items/{as_of_date}/{ticker}", tag = "snapshots",
+    params(
+        ("as_of_date" = NaiveDate, Path, description = "Market date, `YYYY-MM-DD`"),
+        ("ticker" = String, Path, description = "`KRX:005930`; a bare `005930` gets the `KRX:` prefix"),
+        ItemQuery,
+    ),
+    responses(
+        (status = 200, body = RecommendationItem,
+            headers(("x-snapshot-id" = Uuid, description = "Snapshot the item belongs to"))),
+        (status = 400, description = "Invalid date or `snapshot_id`", body = ErrorBody),
+        (status = 404, body = ErrorBody),
+        (status = 503, description = "Database unavailable", body = ErrorBody),
+    ))]
+async fn get_item_by_date_and_ticker(
+    State(state): State<AppState>,
+    Path((as_of_date, ticker)): Path<(String, String)>,
+    query: Result<Query<ItemQuery>, QueryRejection>,
+) -> Result<impl IntoResponse, ApiError> {
+    let as_of_date = parse_as_of_date(&as_of_date)?;
+    let ticker = normalize_ticker(&ticker);
+    let Query(q) = query.map_err(|e| ApiError::invalid_query(e.body_text()))?;
+    let pinned = q
+        .snapshot_id
+        .as_deref()
+        .map(|id| parse_id(id, "snapshot"))
+        .transpose()?;
+
+    let found = state
+        .store()?
+        .fetch_item(as_of_date, pinned, &ticker)
+        .await?;
+    let (snapshot_id, item) = count_snapshot_fetch(found).ok_or_else(|| match pinned {
+        Some(id) => ApiError::not_found(
+            "snapshot_not_found",
+            format!("no successful snapshot {id} for {as_of_date}"),
+        ),
+        None => snapshot_not_found(as_of_date),
+    })?;
+    let item = item.ok_or_else(|| {
+        ApiError::not_found(
+            "item_not_found",
+            format!("ticker {ticker:?} is not in the snapshot for {as_of_date}"),
+        )
+    })?;
+
+    Ok(([(SNAPSHOT_ID_HEADER, snapshot_id.to_string())], Json(item)))
+}
+
+/// `GET /features/:as_of_date/:ticker`: the `stock_features_daily` row the model saw.
+#[utoipa::path(get, path = "/features/{as_of_date}/{ticker}", tag = "features",
+    params(
+        ("as_of_date" = NaiveDate, Path, description = "Market date, `YYYY-MM-DD`"),
+        ("ticker" = String, Path, description = "`KRX:005930`; a bare `005930` gets the `KRX:` prefix"),
+    ),
+    responses(
+        (status = 200, description = "Non-numeric feature values are omitted", body = DailyFeatureItem),
+        (status = 400, description = "Invalid date", body = ErrorBody),
+        (status = 404, description = "No row for that date and ticker", body = ErrorBody),
+        (status = 503, description = "Database unavailable", body = ErrorBody),
+    ))]
+async fn get_features(
+    State(state): State<AppState>,
+    Path((as_of_date, ticker)): Path<(String, String)>,
+) -> Result<Json<DailyFeatureItem>, ApiError> {
+    let as_of_date = parse_as_of_date(&as_of_date)?;
+    let ticker = normalize_ticker(&ticker);
+
+    let item = state
+        .store()?
+        .fetch_features(as_of_date, &ticker)
+        .await?
+        .ok_or_else(|| {
+            ApiError::not_found(
+                "features_not_found",
+                format!("no features for ticker {ticker:?} on {as_of_date}"),
+            )
+        })?;
+    Ok(Json(item))
+}
+
+#[derive(Debug, Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+struct FeaturesBatchQuery {
+    /// Comma-separated tickers, at most 50.
+    #[param(required = true)]
+    tickers: Option<String>,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+struct ApiFeaturesBatch {
+    as_of_date: NaiveDate,
+    /// Ordered by ticker.
+    items: Vec<DailyFeatureItem>,
+    /// Requested tickers without a row for that date.
+    missing: Vec<String>,
+}
+
+/// `GET /features/:as_of_date?tickers=a,b,c`: bounded batch of feature rows.
+#[utoipa::path(get, path = "/features/{as_of_date}", tag = "features",
+    params(("as_of_date" = NaiveDate, Path, description = "Market date, `YYYY-MM-DD`"), FeaturesBatchQuery),
+    responses(
+        (status = 200, body = ApiFeaturesBatch),
+        (status = 400, description = "Invalid date, or `tickers` missing or over 50", body = ErrorBody),
+        (status = 503, description = "Database unavailable", body = ErrorBody),
+    ))]
+async fn get_features_batch(
+    State(state): State<AppState>,
+    Path(as_of_date): Path<String>,
+    query: Result<Query<FeaturesBatchQuery>, QueryRejection>,
+) -> Result<Json<ApiFeaturesBatch>, ApiError> {
+    let as_of_date = parse_as_of_date(&as_of_date)?;
+    let Query(q) = query.map_err(|e| ApiError::invalid_query(e.body_text()))?;
+
+    let mut tickers: Vec<String> = Vec::new();
+    for ticker in q.tickers.as_deref().unwrap_or_default().split(',') {
+        if ticker.trim().is_empty() {
+            continue;
+        }
+        let ticker = normalize_ticker(ticker);
+        if !tickers.contains(&ticker) {
+            tickers.push(ticker);
+        }
+    }
+    if tickers.is_empty() {
+        return Err(ApiError::invalid_query("tickers is required"));
+    }
+    if tickers.len() > MAX_FEATURE_BATCH {
+        return Err(ApiError::invalid_query(format!(
+            "at most {MAX_FEATURE_BATCH} tickers per request (got {})",
+            tickers.len()
+        )));
+    }
+
+    let items = state
+        .store()?
+        .fetch_features_batch(as_of_date, &tickers)
+        .await?;
+    let missing = tickers
+        .into_iter()
+        .filter(|t| !items.iter().any(|i| &i.ticker == t))
+        .collect();
+    Ok(Json(ApiFeaturesBatch {
+        as_of_date,
+        items,
+        missing,
+    }))
+}
+
+/// Longest accepted `reason` for an invalidation.
+const MAX_INVALIDATION_REASON_CHARS: usize = 500;
+
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+struct InvalidateRequest {
+    /// Why the snapshot is hidden; required, at most 500 characters.
+    reason: String,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+struct ApiInvalidation {
+    snapshot_id: Uuid,
+    invalidated_at: DateTime<Utc>,
+    invalidated_reason: String,
+}
+
+/// `POST /admin/snapshots/:snapshot_id/invalidate`: hide a bad successful snapshot from every
+/// read endpoint and let the worker regenerate its date. Requires an `ADMIN_API_KEYS` key.
+#[utoipa::path(post, path = "/admin/snapshots/{snapshot_id}/invalidate", tag = "admin",
+    params(("snapshot_id" = Uuid, Path)),
+    request_body = InvalidateRequest,
+    security(("api_key" = []), ("bearer" = [])),
+    responses(
+        (status = 200, body = ApiInvalidation),
+        (status = 400, description = "Invalid id, or missing/empty `reason`", body = ErrorBody),
+        (status = 401, description = "Missing or unknown key", body = ErrorBody),
+        (status = 403, description = "Key is not an admin key", body = ErrorBody),
+        (status = 404, body = ErrorBody),
+        (status = 409, description = "Not a successful snapshot, or already invalidated", body = ErrorBody),
+        (status = 503, description = "`ADMIN_API_KEYS` unset or database unavailable", body = ErrorBody),
+    ))]
+async fn invalidate_snapshot(
+    _admin: RequireAdminKey,
+    State(state): State<AppState>,
+    Path(snapshot_id): Path<String>,
+    body: Result<Json<InvalidateRequest>, JsonRejection>,
+) -> Result<Json<ApiInvalidation>, ApiError> {
+    let snapshot_id = parse_id(&snapshot_id, "snapshot")?;
+    let Json(body) = body.map_err(|e| ApiError::invalid_body(e.body_text()))?;
+    let reason = body.reason.trim();
+    if reason.is_empty() {
+        return Err(ApiError::invalid_body("reason must not be empty"));
+    }
+    if reason.chars().count() > MAX_INVALIDATION_REASON_CHARS {
+        return Err(ApiError::invalid_body(format!(
+            "reason must be at most {MAX_INVALIDATION_REASON_CHARS} characters"
+        )));
+    }
+
+    match state
+        .store()?
+        .invalidate_snapshot(snapshot_id, reason)
+        .await?
+    {
+        InvalidateOutcome::Invalidated(invalidated_at) => {
+            tracing::warn!(%snapshot_id, reason, "snapshot invalidated by admin");
+            Ok(Json(ApiInvalidation {
+                snapshot_id,
+                invalidated_at,
+                invalidated_reason: reason.to_string(),
+            }))
+        }
+        InvalidateOutcome::NotFound => Err(ApiError::not_found(
+            "snapshot_not_found",
+            format!("no snapshot {snapshot_id}"),
+        )),
+        InvalidateOutcome::NotSuccess => Err(ApiError::conflict(
+            "snapshot_not_success",
+            format!("snapshot {snapshot_id} is a failed run; only successful snapshots can be invalidated"),
+        )),
+        InvalidateOutcome::AlreadyInvalidated(at) => Err(ApiError::conflict(
+            "already_invalidated",
+            format!("snapshot {snapshot_id} was already invalidated at {at}"),
+        )),
+    }
+}
+
+fn parse_as_of_date(raw: &str) -> Result<NaiveDate, ApiError> {
+    NaiveDate::parse_from_str(raw, "%Y-%m-%d").map_err(|_| ApiError::invalid_date(raw))
+}
+
+fn parse_id(raw: &str, what: &str) -> Result<Uuid, ApiError> {
+    Uuid::parse_str(raw).map_err(|_| {
+        ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "invalid_id",
+            format!("invalid {what} id {raw:?}; expected a UUID"),
+        )
+    })
+}
+
+/// Canonical ticker for lookups: a bare 6-digit code gets the `KRX:` prefix stored tickers use,
+/// and a colon left percent-encoded (`KRX%3A005930`, e.g. double-encoded by a client) is decoded.
+fn normalize_ticker(raw: &str) -> String {
+    let ticker = raw.trim().replace("%3A", ":").replace("%3a", ":");
+    if ticker.len() == 6 && ticker.bytes().all(|b| b.is_ascii_digit()) {
+        format!("KRX:{ticker}")
+    } else {
+        ticker
+    }
+}
+
+fn snapshot_not_found(as_of_date: NaiveDate) -> ApiError {
+    ApiError::not_found(
+        "snapshot_not_found",
+        format!("no successful snapshot for {as_of_date}"),
+    )
+}
+
+async fn shutdown_signal() {
+    let _ = tokio::signal::ctrl_c().await;
+}
+
+fn init_sentry(settings: &tootoo_core::config::Settings) -> Option<sentry::ClientInitGuard> {
+    let dsn = settings.sentry_dsn.as_deref()?;
+    Some(sentry::init((
+        dsn,
         sentry::ClientOptions {
             release: sentry::release_name!(),
             ..Default::default()
         },
     )))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Datelike, TimeZone};
+    use tootoo_core::storage::recommendations::{
+        DatedItem, DegradedItem, SnapshotSummary, MAX_LIST_LIMIT,
+    };
+
+    /// In-memory history of `total` snapshots, newest first; every 3rd one failed.
+    /// Only 2026-12-31 has stored snapshots (see `fake_snapshots`).
+    struct FakeStore {
+        total: i64,
+        /// Snapshots hidden through `invalidate_snapshot`.
+        invalidated: std::sync::Mutex<Vec<Uuid>>,
+    }
+
+    impl FakeStore {
+        fn new(total: i64) -> Self {
+            Self {
+                total,
+                invalidated: Default::default(),
+            }
+        }
+
+        /// `fake_snapshots` minus invalidated ones.
+        fn snapshots(&self) -> Vec<(Uuid, String, RecommendationSnapshot)> {
+            let invalidated = self.invalidated.lock().unwrap();
+            fake_snapshots()
+                .into_iter()
+                .filter(|(id, _, _)| !invalidated.contains(id))
+                .collect()
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl Store for FakeStore {
+        async fn list_snapshots(
+            &self,
+            status: Option<&str>,
+            limit: i64,
+            offset: i64,
+        ) -> anyhow::Result<SnapshotPage> {
+            let limit = limit.clamp(1, MAX_LIST_LIMIT);
+            let base = NaiveDate::from_ymd_opt(2026, 12, 31).unwrap();
+            let all: Vec<SnapshotSummary> = (0..self.total)
+                .map(|i| {
+                    let failed = i % 3 == 2;
+                    SnapshotSummary {
+                        snapshot_id: Uuid::from_u128(i as u128),
+                        as_of_date: base - chrono::Duration::days(i),
+                        generated_at: Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap(),
+                        provider: "anthropic".to_string(),
+                        status: if failed { "error" } else { "success" }.to_string(),
+                        error_kind: failed.then(|| "parse_error".to_string()),
+                        item_count: if failed { 0 } else { 20 },
+                    }
+                })
+                .filter(|s| status.is_none_or(|st| s.status == st))
+                .collect();
+            let total = all.len() as i64;
+            let items = all
+                .into_iter()
+                .skip(offset as usize)
+                .take(limit as usize)
+                .collect();
+            Ok(SnapshotPage::new(items, total, offset))
+        }
+
+        /// 2026-12-29's stored snapshot has an unreadable item.
+        async fn fetch_snapshot(
+            &self,
+            as_of_date: Option<NaiveDate>,
+        ) -> anyhow::Result<Option<(SnapshotHeader, Vec<RecommendationItem>)>> {
+            if as_of_date == NaiveDate::from_ymd_opt(2026, 12, 29) {
+                return Err(DegradedItem {
+                    snapshot_id: Uuid::from_u128(29),
+                    ticker: "KRX:000001".to_string(),
+                    reason: "rationale has 2 lines, expected 3".to_string(),
+                }
+                .into());
+            }
+            Ok(self
+                .snapshots()
+                .into_iter()
+                .find(|(_, _, s)| as_of_date.is_none_or(|d| s.as_of_date == d))
+                .map(|(snapshot_id, provider, s)| {
+                    let header = SnapshotHeader {
+                        snapshot_id,
+                        as_of_date: s.as_of_date,
+                        generated_at: s.generated_at,
+                        provider,
+                    };
+                    (header, s.items)
+                }))
+        }
+
+        /// The latest snapshot is `Uuid::nil()`; `Uuid::from_u128(2)` is a failed run.
+        async fn fetch_snapshot_by_id(
+            &self,
+            snapshot_id: Uuid,
+        ) -> anyhow::Result<Option<StoredSnapshot>> {
+            if snapshot_id == Uuid::from_u128(2) {
+                return Ok(Some(StoredSnapshot {
+                    snapshot_id,
+                    provider: "anthropic".to_string(),
+                    status: "error".to_string(),
+                    error_kind: Some("parse_error".to_string()),
+                    invalidated_at: None,
+                    invalidated_reason: None,
+                    snapshot: RecommendationSnapshot {
+                        as_of_date: NaiveDate::from_ymd_opt(2026, 12, 29).unwrap(),
+                        generated_at: Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap(),
+                        items: Vec::new(),
+                    },
+                }));
+            }
+            let invalidated = self.invalidated.lock().unwrap().contains(&snapshot_id);
+            Ok(fake_snapshots()
+                .into_iter()
+                .find(|(id, _, _)| *id == snapshot_id)
+                .map(|(snapshot_id, provider, snapshot)| StoredSnapshot {
+                    snapshot_id,
+                    provider,
+                    status: "success".to_string(),
+                    error_kind: None,
+                    invalidated_at: invalidated
+                        .then(|| Utc.with_ymd_and_hms(2027, 1, 2, 0, 0, 0).unwrap()),
+                    invalidated_reason: invalidated.then(|| "stale features".to_string()),
+                    snapshot,
+                }))
+        }
+
+        async fn fetch_previous_snapshot(
+            &self,
+            as_of_date: NaiveDate,
+        ) -> anyhow::Result<Option<RecommendationSnapshot>> {
+            // 2026-12-31's predecessor is the (non-adjacent) 2026-12-28; nothing before that.
+            let previous = NaiveDate::from_ymd_opt(2026, 12, 28).unwrap();
+            if as_of_date <= previous {
+                return Ok(None);
+            }
+            Ok(Some(RecommendationSnapshot {
+                as_of_date: previous,
+                generated_at: Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap(),
+                items: Vec::new(),
+            }))
+        }
+
+        async fn fetch_previous_ranks(
+            &self,
+            as_of_date: NaiveDate,
+        ) -> anyhow::Result<BTreeMap<String, i32>> {
+            // Same predecessor as `fetch_previous_snapshot`, with a few overlapping names.
+            if as_of_date <= NaiveDate::from_ymd_opt(2026, 12, 28).unwrap() {
+                return Ok(BTreeMap::new());
+            }
+            Ok(BTreeMap::from([
+                ("KRX:000001".to_string(), 1),
+                ("KRX:000002".to_string(), 5),
+                ("KRX:000003".to_string(), 1),
+            ]))
+        }
+
+        async fn fetch_item(
+            &self,
+            as_of_date: NaiveDate,
+            snapshot_id: Option<Uuid>,
+            ticker: &str,
+        ) -> anyhow::Result<Option<(Uuid, Option<RecommendationItem>)>> {
+            let found = self.snapshots().into_iter().find(|(id, _, s)| {
+                s.as_of_date == as_of_date && snapshot_id.is_none_or(|pin| *id == pin)
+            });
+            Ok(found.map(|(id, _, s)| (id, s.items.into_iter().find(|i| i.ticker == ticker))))
+        }
+
+        async fn list_items(
+            &self,
+            filter: &ItemFilter,
+            limit: i64,
+            offset: i64,
+        ) -> anyhow::Result<ItemPage> {
+            let limit = limit.clamp(1, tootoo_core::storage::recommendations::MAX_ITEMS_LIMIT);
+            let mut all: Vec<DatedItem> = self
+                .snapshots()
+                .into_iter()
+                .flat_map(|(snapshot_id, _, s)| {
+                    s.items.into_iter().map(move |item| DatedItem {
+                        as_of_date: s.as_of_date,
+                        snapshot_id,
+                        item,
+                    })
+                })
+                .filter(|d| filter.ticker.as_ref().is_none_or(|t| &d.item.ticker == t))
+                .filter(|d| filter.from.is_none_or(|f| d.as_of_date >= f))
+                .filter(|d| filter.to.is_none_or(|t| d.as_of_date <= t))
+                .filter(|d| {
+                    filter
+                        .min_confidence
+                        .is_none_or(|c| d.item.confidence.is_some_and(|x| x >= c))
+                })
+                .collect();
+            all.sort_by_key(|d| (d.as_of_date, d.item.rank));
+            let total = all.len() as i64;
+            let items = all
+                .into_iter()
+                .skip(offset as usize)
+                .take(limit as usize)
+                .collect();
+            Ok(ItemPage::new(items, total, offset))
+        }
+
+        async fn fetch_latest_run(&self) -> anyhow::Result<Option<LatestRun>> {
+            Ok(Some(LatestRun {
+                snapshot_id: Uuid::from_u128(7),
+                as_of_date: NaiveDate::from_ymd_opt(2027, 1, 4).unwrap(),
+                generated_at: Utc.with_ymd_and_hms(2027, 1, 4, 8, 0, 0).unwrap(),
+                provider: "anthropic".to_string(),
+                status: "error".to_string(),
+                error_kind: Some("http_error".to_string()),
+                error: Some(format!(
+                    "anthropic http 401 (x-api-key: sk-ant-api03-leaked) {}\n{{\"raw\": \"llm text\"}}",
+                    "z".repeat(400)
+                )),
+            }))
+        }
+
+        async fn list_snapshot_range(
+            &self,
+            from: NaiveDate,
+            to: NaiveDate,
+        ) -> anyhow::Result<Vec<SnapshotRangeRow>> {
+            // Weekdays only, like a trading calendar.
+            Ok(from
+                .iter_days()
+                .take_while(|d| *d <= to)
+                .filter(|d| d.weekday().number_from_monday() <= 5)
+                .map(|d| SnapshotRangeRow {
+                    as_of_date: d,
+                    snapshot_id: Uuid::nil(),
+                    provider: "anthropic".to_string(),
+                    item_count: 20,
+                    avg_confidence: Some(0.5),
+                    top_ticker: Some("005930".to_string()),
+                })
+                .collect())
+        }
+
+        async fn fetch_features(
+            &self,
+            as_of_date: NaiveDate,
+            ticker: &str,
+        ) -> anyhow::Result<Option<DailyFeatureItem>> {
+            Ok(fake_features(as_of_date)
+                .into_iter()
+                .find(|f| f.ticker == ticker))
+        }
+
+        async fn fetch_features_batch(
+            &self,
+            as_of_date: NaiveDate,
+            tickers: &[String],
+        ) -> anyhow::Result<Vec<DailyFeatureItem>> {
+            Ok(fake_features(as_of_date)
+                .into_iter()
+                .filter(|f| tickers.contains(&f.ticker))
+                .collect())
+        }
+
+        async fn list_ingest_runs(
+            &self,
+            provider: Option<&str>,
+            status: Option<&str>,
+            limit: i64,
+        ) -> anyhow::Result<Vec<IngestRunSummary>> {
+            Ok(fake_ingest_runs()
+                .into_iter()
+                .filter(|r| provider.is_none_or(|p| r.provider == p))
+                .filter(|r| status.is_none_or(|s| r.status == s))
+                .take(limit as usize)
+                .map(|r| IngestRunSummary {
+                    id: r.id,
+                    as_of_date: r.as_of_date,
+                    generated_at: r.generated_at,
+                    provider: r.provider,
+                    status: r.status,
+                    error: r.error.map(|e| e.chars().take(500).collect()),
+                    started_at: r.started_at,
+                    finished_at: r.finished_at,
+                    duration_ms: r.duration_ms,
+                })
+                .collect())
+        }
+
+        async fn fetch_ingest_run(&self, id: Uuid) -> anyhow::Result<Option<IngestRun>> {
+            Ok(fake_ingest_runs().into_iter().find(|r| r.id == id))
+        }
+
+        async fn invalidate_snapshot(
+            &self,
+            snapshot_id: Uuid,
+            _reason: &str,
+        ) -> anyhow::Result<InvalidateOutcome> {
+            let at = Utc.with_ymd_and_hms(2027, 1, 2, 0, 0, 0).unwrap();
+            if snapshot_id == Uuid::from_u128(2) {
+                return Ok(InvalidateOutcome::NotSuccess);
+            }
+            if !fake_snapshots().iter().any(|(id, _, _)| *id == snapshot_id) {
+                return Ok(InvalidateOutcome::NotFound);
+            }
+            let mut invalidated = self.invalidated.lock().unwrap();
+            if invalidated.contains(&snapshot_id) {
+                return Ok(InvalidateOutcome::AlreadyInvalidated(at));
+            }
+            invalidated.push(snapshot_id);
+            Ok(InvalidateOutcome::Invalidated(at))
+        }
+    }
+
+    /// Two successful generations for 2026-12-31, newest first: `Uuid::nil()` ranks
+    /// `{rank:06}` at `rank`; the older `Uuid::from_u128(31)` has the ranks reversed.
+    fn fake_snapshots() -> Vec<(Uuid, String, RecommendationSnapshot)> {
+        let day = NaiveDate::from_ymd_opt(2026, 12, 31).unwrap();
+        let snapshot = |generated_hour, reversed: bool| RecommendationSnapshot {
+            as_of_date: day,
+            generated_at: Utc
+                .with_ymd_and_hms(2026, 1, 1, generated_hour, 0, 0)
+                .unwrap(),
+            // Stored out of rank order on purpose.
+            items: (1..=20)
+                .rev()
+                .map(|n| RecommendationItem {
+                    rank: if reversed { 21 - n } else { n },
+                    ticker: format!("KRX:{n:06}"),
+                    name: format!("Stock {n}"),
+                    name_en: (n == 1).then(|| "Stock One".to_string()),
+                    rationale: ["a".into(), "b".into(), "c".into()],
+                    risk_notes: None,
+                    confidence: Some(0.5),
+                })
+                .collect(),
+        };
+        vec![
+            (Uuid::nil(), "anthropic".to_string(), snapshot(1, false)),
+            (Uuid::from_u128(31), "gemini".to_string(), snapshot(0, true)),
+        ]
+    }
+
+    /// Features for 000001..=000003 on 2026-12-31 only; stored JSON had a non-numeric entry.
+    fn fake_features(as_of_date: NaiveDate) -> Vec<DailyFeatureItem> {
+        if as_of_date != NaiveDate::from_ymd_opt(2026, 12, 31).unwrap() {
+            return Vec::new();
+        }
+        (1..=3)
+            .map(|i| DailyFeatureItem {
+                ticker: format!("KRX:{i:06}"),
+                name: format!("Stock {i}"),
+                name_en: None,
+                instrument_type: Some("stock".to_string()),
+                sector: None,
+                trading_value: Some(1e9 * i as f64),
+                close: Some(10_000.0 + i as f64),
+                features: tootoo_core::storage::stock_features::json_to_feature_map(
+                    serde_json::json!({"ret_1d": 0.01 * i as f64, "per": 12.5, "sector": "IT"}),
+                ),
+            })
+            .collect()
+    }
+
+    /// A failed KIS run (long error) followed by an older successful external run that took
+    /// 90 seconds.
+    fn fake_ingest_runs() -> Vec<IngestRun> {
+        let day = NaiveDate::from_ymd_opt(2026, 12, 30).unwrap();
+        vec![
+            IngestRun {
+                id: Uuid::from_u128(1),
+                as_of_date: day,
+                generated_at: Utc.with_ymd_and_hms(2026, 12, 30, 12, 0, 0).unwrap(),
+                provider: "kis".to_string(),
+                status: "error".to_string(),
+                error: Some("x".repeat(2000)),
+                started_at: None,
+                finished_at: None,
+                duration_ms: None,
+                raw_response: None,
+            },
+            IngestRun {
+                id: Uuid::from_u128(2),
+                as_of_date: day,
+                generated_at: Utc.with_ymd_and_hms(2026, 12, 30, 11, 0, 0).unwrap(),
+                provider: "external".to_string(),
+                status: "success".to_string(),
+                error: None,
+                started_at: Some(Utc.with_ymd_and_hms(2026, 12, 30, 10, 58, 30).unwrap()),
+                finished_at: Some(Utc.with_ymd_and_hms(2026, 12, 30, 11, 0, 0).unwrap()),
+                duration_ms: Some(90_000),
+                raw_response: Some(serde_json::json!({"items": []})),
+            },
+        ]
+    }
+
+    async fn serve(state: AppState) -> String {
+        serve_with(
+            state,
+            RouterOptions {
+                compress: true,
+                rate_limit_per_min: None,
+                docs: true,
+                trusted_proxies: Arc::from([]),
+            },
+        )
+        .await
+    }
+
+    async fn serve_with(state: AppState, opts: RouterOptions) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(
+                listener,
+                ServiceExt::<Request>::into_make_service_with_connect_info::<std::net::SocketAddr>(
+                    app(state, opts),
+                ),
+            )
+            .await
+            .unwrap();
+        });
+        format!("http://{addr}")
+    }
+
+    fn fake_state(total: i64) -> AppState {
+        AppState {
+            store: Arc::new(RwLock::new(Some(Arc::new(FakeStore::new(total))))),
+            db_status: Arc::default(),
+            metrics: None,
+            api_key: Some(Arc::from("secret")),
+            admin_keys: Arc::from(["admin-1".to_string(), "admin-2".to_string()]),
+        }
+    }
+
+    async fn get_json(url: String) -> (reqwest::StatusCode, serde_json::Value) {
+        let res = reqwest::get(url).await.unwrap();
+        let status = res.status();
+        (status, res.json().await.unwrap_or(serde_json::Value::Null))
+    }
+
+    #[tokio::test]
+    async fn lists_snapshots_with_pagination() {
+        let base = serve(fake_state(250)).await;
+
+        let (status, body) = get_json(format!("{base}/snapshots")).await;
+        assert_eq!(status, 200);
+        assert_eq!(body["items"].as_array().unwrap().len(), 20);
+        assert_eq!(body["total"], 250);
+        assert_eq!(body["next_offset"], 20);
+        let first = &body["items"][0];
+        assert_eq!(first["as_of_date"], "2026-12-31");
+        assert_eq!(first["item_count"], 20);
+        assert!(first["snapshot_id"].is_string());
+
+        // limit is capped at 100.
+        let (_, body) = get_json(format!("{base}/snapshots?limit=500&offset=200")).await;
+        assert_eq!(body["items"].as_array().unwrap().len(), 50);
+        assert!(body["next_offset"].is_null());
+        let (_, body) = get_json(format!("{base}/snapshots?limit=500")).await;
+        assert_eq!(body["items"].as_array().unwrap().len(), 100);
+        assert_eq!(body["next_offset"], 100);
+
+        let (_, body) = get_json(format!("{base}/snapshots?status=error&limit=2")).await;
+        assert_eq!(body["total"], 83);
+        assert_eq!(body["items"][0]["status"], "error");
+        assert_eq!(body["items"][0]["error_kind"], "parse_error");
+    }
+
+    #[tokio::test]
+    async fn rejects_bad_params_and_reports_degraded_mode() {
+        let base = serve(fake_state(5)).await;
+        for query in ["status=pending", "limit=0", "offset=-1", "limit=abc"] {
+            let (status, body) = get_json(format!("{base}/snapshots?{query}")).await;
+            assert_eq!(status, 400, "{query}");
+            assert_eq!(body["error"]["code"], "invalid_query", "{query}");
+        }
+
+        let base = serve(AppState::new(None)).await;
+        let (status, body) = get_json(format!("{base}/snapshots")).await;
+        assert_eq!(status, 503);
+        assert_eq!(body["error"]["code"], "db_unavailable");
+    }
+
+    #[tokio::test]
+    async fn background_reconnect_swaps_the_store_in() {
+        use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+        let state = AppState::new(None);
+        let db_up = Arc::new(AtomicBool::new(false));
+        let calls = Arc::new(AtomicU32::new(0));
+        let connector = {
+            let (db_up, calls) = (db_up.clone(), calls.clone());
+            move || {
+                let (db_up, calls) = (db_up.clone(), calls.clone());
+                async move {
+                    calls.fetch_add(1, Ordering::SeqCst);
+                    if db_up.load(Ordering::SeqCst) {
+                        Ok(Arc::new(FakeStore::new(5)) as Arc<dyn Store>)
+                    } else {
+                        Err(anyhow::anyhow!(
+                            "connect to postgres://user:hunter2@db:5432/app refused"
+                        ))
+                    }
+                }
+            }
+        };
+        tokio::spawn(reconnect::reconnect(
+            state.store.clone(),
+            state.db_status.clone(),
+            std::time::Duration::from_millis(1),
+            std::time::Duration::from_millis(5),
+            connector,
+        ));
+        let base = serve(state).await;
+
+        while calls.load(Ordering::SeqCst) < 3 {
+            tokio::time::sleep(std::time::Duration::from_millis(2)).await;
+        }
+        let (status, body) = get_json(format!("{base}/readyz")).await;
+        assert_eq!(status, 503);
+        assert_eq!(body["status"], "degraded");
+        assert!(body["retry_count"].as_u64().unwrap() >= 2);
+        let last_error = body["last_error"].as_str().unwrap();
+        assert!(last_error.contains("refused"), "{last_error}");
+        assert!(!last_error.contains("hunter2"), "{last_error}");
+        let (status, _) = get_json(format!("{base}/snapshots")).await;
+        assert_eq!(status, 503);
+
+        db_up.store(true, Ordering::SeqCst);
+        let body = loop {
+            let (status, body) = get_json(format!("{base}/readyz")).await;
+            if status == 200 {
+                break body;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(2)).await;
+        };
+        assert_eq!(body["status"], "ready");
+        assert!(body["last_error"].is_null());
+        let (status, body) = get_json(format!("{base}/snapshots")).await;
+        assert_eq!(status, 200);
+        assert_eq!(body["total"], 5);
+
+        // The loop stops once connected.
+        let settled = calls.load(Ordering::SeqCst);
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        assert_eq!(calls.load(Ordering::SeqCst), settled);
+    }
+
+    #[tokio::test]
+    async fn invalid_date_renders_json_error_with_the_bad_value() {
+        let base = serve(fake_state(5)).await;
+        for path in ["/snapshots/2026-13-01", "/items/20261231/005930"] {
+            let (status, body) = get_json(format!("{base}{path}")).await;
+            assert_eq!(status, 400, "{path}");
+            assert_eq!(body["error"]["code"], "invalid_date", "{path}");
+            let message = body["error"]["message"].as_str().unwrap();
+            let bad = path.split('/').nth(2).unwrap();
+            assert!(message.contains(bad), "{message}");
+        }
+    }
+
+    #[tokio::test]
+    async fn metrics_endpoint_reports_routes_and_snapshot_misses() {
+        static HANDLE: std::sync::OnceLock<PrometheusHandle> = std::sync::OnceLock::new();
+        let handle = HANDLE
+            .get_or_init(|| tootoo_core::metrics::install_prometheus_recorder().unwrap())
+            .clone();
+        let base = serve(fake_state(5).with_metrics(Some(handle))).await;
+
+        let (status, _) = get_json(format!("{base}/snapshots/2026-01-02")).await;
+        assert_eq!(status, 404);
+
+        let res = reqwest::get(format!("{base}/metrics")).await.unwrap();
+        assert_eq!(res.status(), 200);
+        let text = res.text().await.unwrap();
+        assert!(text.contains(r#"route="/snapshots/:as_of_date""#), "{text}");
+        assert!(text.contains(API_REQUEST_DURATION_SECONDS), "{text}");
+        assert!(
+            text.contains(r#"tootoo_api_snapshot_fetch_total{result="miss"}"#),
+            "{text}"
+        );
+
+        let res = reqwest::get(format!("{}/metrics", serve(fake_state(5)).await))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 404);
+    }
+
+    #[tokio::test]
+    async fn lists_ingest_runs_with_truncated_errors() {
+        let base = serve(fake_state(5)).await;
+
+        let (status, body) = get_json(format!("{base}/ingest/runs")).await;
+        assert_eq!(status, 200);
+        let runs = body.as_array().unwrap();
+        assert_eq!(runs.len(), 2);
+        assert_eq!(runs[0]["error"].as_str().unwrap().chars().count(), 500);
+        assert!(runs[0].get("raw_response").is_none());
+
+        let (_, body) = get_json(format!("{base}/ingest/runs?provider=external")).await;
+        assert_eq!(body.as_array().unwrap().len(), 1);
+        assert_eq!(body[0]["duration_ms"], 90_000);
+        assert_eq!(body[0]["started_at"], "2026-12-30T10:58:30Z");
+        assert!(runs[0]["finished_at"].is_null());
+        let (_, body) = get_json(format!("{base}/ingest/runs?status=error&limit=1")).await;
+        assert_eq!(body[0]["provider"], "kis");
+        let (status, body) = get_json(format!("{base}/ingest/runs?status=skipped_duplicate")).await;
+        assert_eq!(status, 200);
+        assert!(body.as_array().unwrap().is_empty());
+        let (status, _) = get_json(format!("{base}/ingest/runs?status=abandoned")).await;
+        assert_eq!(status, 200);
+        let (status, body) = get_json(format!("{base}/ingest/runs?status=bogus")).await;
+        assert_eq!(status, 400);
+        assert_eq!(body["error"]["code"], "invalid_query");
+        let message = body["error"]["message"].as_str().unwrap();
+        assert!(message.contains("skipped_duplicate"), "{message}");
+    }
+
+    #[tokio::test]
+    async fn ingest_run_detail_requires_api_key() {
+        let base = serve(fake_state(5)).await;
+        let url = format!("{base}/ingest/runs/{}", Uuid::from_u128(2));
+        let client = reqwest::Client::new();
+
+        let res = client.get(&url).send().await.unwrap();
+        assert_eq!(res.status(), 401);
+        let res = client
+            .get(&url)
+            .header("x-api-key", "wrong")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 401);
+
+        let res = client
+            .get(&url)
+            .header("x-api-key", "secret")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 200);
+        let body: serde_json::Value = res.json().await.unwrap();
+        assert_eq!(body["raw_response"], serde_json::json!({"items": []}));
+
+        let res = client
+            .get(format!("{base}/ingest/runs/{}", Uuid::from_u128(9)))
+            .bearer_auth("secret")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 404);
+
+        // No key configured: the endpoint stays closed.
+        let mut state = fake_state(5);
+        state.api_key = None;
+        let base = serve(state).await;
+        let (status, body) = get_json(format!("{base}/ingest/runs/{}", Uuid::from_u128(2))).await;
+        assert_eq!(status, 503);
+        assert_eq!(body["error"]["code"], "auth_not_configured");
+    }
+
+    #[tokio::test]
+    async fn diff_compares_against_the_previous_success() {
+        let base = serve(fake_state(5)).await;
+
+        let (status, body) = get_json(format!("{base}/snapshots/2026-12-31/diff")).await;
+        assert_eq!(status, 200);
+        assert_eq!(body["as_of_date"], "2026-12-31");
+        assert_eq!(body["previous_as_of_date"], "2026-12-28");
+        for key in ["added", "removed", "rank_changes"] {
+            assert!(body[key].is_array(), "{key}");
+        }
+
+        let (status, body) = get_json(format!("{base}/snapshots/2026-12-30/diff")).await;
+        assert_eq!(status, 404);
+        assert_eq!(body["error"]["code"], "snapshot_not_found");
+        let (status, _) = get_json(format!("{base}/snapshots/yesterday/diff")).await;
+        assert_eq!(status, 400);
+    }
+
+    #[tokio::test]
+    async fn degraded_items_have_their_own_error_code() {
+        let base = serve(fake_state(5)).await;
+
+        for path in ["/snapshots/2026-12-29", "/snapshots/2026-12-29/diff"] {
+            let (status, body) = get_json(format!("{base}{path}")).await;
+            assert_eq!(status, 500, "{path}");
+            assert_eq!(body["error"]["code"], "snapshot_degraded", "{path}");
+            let message = body["error"]["message"].as_str().unwrap();
+            assert!(
+                message.contains(&Uuid::from_u128(29).to_string()),
+                "{message}"
+            );
+        }
+    }
+
+    /// Round trip through the real queries against `--seed-dev` data. Needs a disposable local
+    /// Postgres in `TEST_DATABASE_URL`; skipped when unset.
+    #[tokio::test]
+    async fn serves_seeded_dev_data() {
+        let Ok(url) = std::env::var("TEST_DATABASE_URL") else {
+            // CI provides a database and sets this, so a lost URL fails instead of passing.
+            assert!(
+                std::env::var_os("TEST_DATABASE_REQUIRED").is_none(),
+                "TEST_DATABASE_REQUIRED is set but TEST_DATABASE_URL is not"
+            );
+            eprintln!("TEST_DATABASE_URL unset; skipping seeded round trip");
+            return;
+        };
+        assert!(
+            tootoo_core::dev_seed::is_local_database_url(&url),
+            "TEST_DATABASE_URL must be local"
+        );
+        let pool = connect_and_migrate(PgConnectOptions::from_str(&url).unwrap())
+            .await
+            .unwrap();
+        // Mon 2027-03-15: the seeded snapshots are Fri 03-12 and Mon 03-15.
+        let as_of = NaiveDate::from_ymd_opt(2027, 3, 15).unwrap();
+        let summary = tootoo_core::dev_seed::seed_dev(&pool, as_of).await.unwrap();
+        assert_eq!(summary.dates.len(), tootoo_core::dev_seed::SEED_DAYS);
+        let previous = summary.snapshots[0].0;
+        assert_eq!(previous, NaiveDate::from_ymd_opt(2027, 3, 12).unwrap());
+        // Seeding is idempotent.
+        let again = tootoo_core::dev_seed::seed_dev(&pool, as_of).await.unwrap();
+        assert_eq!(again.feature_rows, 0);
+        assert!(again.snapshots.iter().all(|(_, id)| id.is_none()));
+
+        let base = serve(AppState::new(Some(pool))).await;
+
+        let (status, body) = get_json(format!("{base}/snapshots/2027-03-15")).await;
+        assert_eq!(status, 200);
+        let items = body["snapshot"]["items"].as_array().unwrap();
+        assert_eq!(items.len(), 20);
+        let top = items[0]["ticker"].as_str().unwrap().to_string();
+
+        let (status, body) = get_json(format!("{base}/snapshots/2027-03-15/diff")).await;
+        assert_eq!(status, 200);
+        assert_eq!(body["previous_as_of_date"], "2027-03-12");
+
+        let (status, body) = get_json(format!("{base}/features/2027-03-15/{top}")).await;
+        assert_eq!(status, 200);
+        assert_eq!(body["ticker"], top);
+
+        let (status, body) = get_json(format!("{base}/items?ticker={top}")).await;
+        assert_eq!(status, 200);
+        assert!(body["total"].as_i64().unwrap() >= 1);
+    }
+
+    #[tokio::test]
+    async fn latest_run_reports_failures_with_a_sanitized_error() {
+        let base = serve(fake_state(5)).await;
+
+        let (status, body) = get_json(format!("{base}/runs/latest")).await;
+        assert_eq!(status, 200);
+        assert_eq!(body["status"], "error");
+        assert_eq!(body["as_of_date"], "2027-01-04");
+        assert_eq!(body["error_kind"], "http_error");
+        let error = body["error"].as_str().unwrap();
+        assert!(error.starts_with("anthropic http 401"), "{error}");
+        assert!(!error.contains("sk-ant"), "{error}");
+        assert!(!error.contains("llm text"), "{error}");
+        assert_eq!(error.chars().count(), RUN_ERROR_SUMMARY_CHARS);
+    }
+
+    #[tokio::test]
+    async fn snapshot_items_can_be_sliced_and_trimmed() {
+        let base = serve(fake_state(5)).await;
+
+        let (status, body) = get_json(format!("{base}/snapshots/2026-12-31")).await;
+        assert_eq!(status, 200);
+        let items = body["snapshot"]["items"].as_array().unwrap();
+        assert_eq!(items.len(), 20);
+        assert_eq!(items[0]["rank"], 1);
+        assert!(items[0]["risk_notes"].is_null());
+        assert!(items[0].as_object().unwrap().contains_key("risk_notes"));
+        assert_eq!(items[0]["name_en"], "Stock One");
+        assert!(items[1]["name_en"].is_null());
+
+        let (status, body) = get_json(format!(
+            "{base}/snapshots/latest?top=5&fields=rank,ticker,name"
+        ))
+        .await;
+        assert_eq!(status, 200);
+        let items = body["snapshot"]["items"].as_array().unwrap();
+        let ranks: Vec<_> = items.iter().map(|i| i["rank"].as_i64().unwrap()).collect();
+        assert_eq!(ranks, vec![1, 2, 3, 4, 5]);
+        let mut keys: Vec<_> = items[0].as_object().unwrap().keys().cloned().collect();
+        keys.sort();
+        assert_eq!(keys, vec!["name", "rank", "ticker"]);
+
+        let (_, body) = get_json(format!("{base}/snapshots/latest?fields=risk_notes")).await;
+        assert!(body["snapshot"]["items"][0]["risk_notes"].is_null());
+        assert!(body["snapshot"]["items"][0].get("rank").is_none());
+
+        for query in ["fields=rank,price", "top=0", "top=-1", "fields=,"] {
+            let (status, body) = get_json(format!("{base}/snapshots/2026-12-31?{query}")).await;
+            assert_eq!(status, 400, "{query}");
+            assert_eq!(body["error"]["code"], "invalid_query", "{query}");
+        }
+    }
+
+    #[tokio::test]
+    async fn with_movement_adds_previous_rank_and_direction() {
+        let base = serve(fake_state(5)).await;
+
+        let (status, body) = get_json(format!("{base}/snapshots/latest?top=4")).await;
+        assert_eq!(status, 200);
+        let item = body["snapshot"]["items"][0].as_object().unwrap();
+        assert!(!item.contains_key("movement") && !item.contains_key("previous_rank"));
+
+        for path in [
+            "/snapshots/latest?top=4&with_movement=true",
+            "/snapshots/2026-12-31?top=4&with_movement=true&fields=ticker",
+        ] {
+            let (status, body) = get_json(format!("{base}{path}")).await;
+            assert_eq!(status, 200, "{path}");
+            let moves: Vec<_> = body["snapshot"]["items"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|i| (i["previous_rank"].clone(), i["movement"].clone()))
+                .collect();
+            assert_eq!(
+                moves,
+                vec![
+                    (serde_json::json!(1), serde_json::json!("same")),
+                    (serde_json::json!(5), serde_json::json!("up")),
+                    (serde_json::json!(1), serde_json::json!("down")),
+                    (serde_json::Value::Null, serde_json::json!("new")),
+                ],
+                "{path}"
+            );
+        }
+
+        let (status, body) = get_json(format!("{base}/snapshots/latest?with_movement=yes")).await;
+        assert_eq!(status, 400);
+        assert_eq!(body["error"]["code"], "invalid_query");
+    }
+
+    #[tokio::test]
+    async fn snapshot_routes_answer_conditional_get_and_head() {
+        let base = serve(fake_state(5)).await;
+        let client = reqwest::Client::new();
+        let etag = format!("W/\"{}\"", Uuid::nil());
+        // `generated_at` of the newest 2026-12-31 generation.
+        let last_modified = "Thu, 01 Jan 2026 01:00:00 GMT";
+
+        for path in ["/snapshots/latest", "/snapshots/2026-12-31"] {
+            let url = format!("{base}{path}");
+            let other = format!("W/\"{}\"", Uuid::from_u128(31));
+            let unquoted_weak = etag.trim_start_matches("W/").to_string();
+            let listed = format!("{other}, {etag}");
+            for (if_none_match, expected) in [
+                (None, 200),
+                (Some(etag.as_str()), 304),
+                (Some(unquoted_weak.as_str()), 304),
+                (Some(listed.as_str()), 304),
+                (Some("*"), 304),
+                (Some(other.as_str()), 200),
+                (Some("not a tag"), 200),
+            ] {
+                let mut req = client.get(&url);
+                if let Some(tag) = if_none_match {
+                    req = req.header("if-none-match", tag);
+                }
+                let res = req.send().await.unwrap();
+                assert_eq!(res.status(), expected, "{path} {if_none_match:?}");
+                assert_eq!(res.headers()["etag"], etag.as_str(), "{path}");
+                assert_eq!(res.headers()["last-modified"], last_modified, "{path}");
+                let body = res.bytes().await.unwrap();
+                assert_eq!(body.is_empty(), expected == 304, "{path} {if_none_match:?}");
+            }
+
+            for (since, expected) in [
+                (Some(last_modified), 304),
+                (Some("Fri, 02 Jan 2026 00:00:00 GMT"), 304),
+                (Some("Thu, 01 Jan 2026 00:59:59 GMT"), 200),
+                (Some("not a date"), 200),
+            ] {
+                for method in [Method::GET, Method::HEAD] {
+                    let mut req = client.request(method.clone(), &url);
+                    if let Some(since) = since {
+                        req = req.header("if-modified-since", since);
+                    }
+                    let res = req.send().await.unwrap();
+                    assert_eq!(res.status(), expected, "{method} {path} {since:?}");
+                    assert_eq!(res.headers()["last-modified"], last_modified, "{path}");
+                    assert_eq!(res.headers()["etag"], etag.as_str(), "{path}");
+                    let body = res.bytes().await.unwrap();
+                    let expect_body = expected == 200 && method == Method::GET;
+                    assert_eq!(!body.is_empty(), expect_body, "{method} {path} {since:?}");
+                }
+            }
+
+            // A tag that no longer matches wins over a date that still would.
+            let res = client
+                .get(&url)
+                .header("if-none-match", &other)
+                .header("if-modified-since", last_modified)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(res.status(), 200, "{path}");
+
+            let res = client.head(&url).send().await.unwrap();
+            assert_eq!(res.status(), 200, "{path}");
+            assert_eq!(res.headers()["etag"], etag.as_str());
+            assert_eq!(res.headers()["last-modified"], last_modified);
+            assert_eq!(res.headers()["content-type"], "application/json");
+            assert!(res.bytes().await.unwrap().is_empty());
+
+            let res = client
+                .head(&url)
+                .header("if-none-match", &etag)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(res.status(), 304, "{path}");
+
+            // Movement depends on the baseline too, so it is a different representation.
+            let res = client
+                .get(format!("{url}?with_movement=true"))
+                .header("if-none-match", &etag)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(res.status(), 200, "{path}");
+            let movement_etag = res.headers()["etag"].to_str().unwrap().to_string();
+            assert!(
+                movement_etag.starts_with(&format!("W/\"{}-", Uuid::nil())),
+                "{movement_etag}"
+            );
+            let res = client
+                .get(format!("{url}?with_movement=true"))
+                .header("if-none-match", &movement_etag)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(res.status(), 304, "{path}");
+        }
+    }
+
+    #[tokio::test]
+    async fn invalidating_the_served_snapshot_changes_its_etag() {
+        let base = serve(fake_state(5)).await;
+        let client = reqwest::Client::new();
+        let etag = format!("W/\"{}\"", Uuid::nil());
+
+        let res = client
+            .post(format!("{base}/admin/snapshots/{}/invalidate", Uuid::nil()))
+            .header("x-api-key", "admin-1")
+            .json(&serde_json::json!({ "reason": "stale features" }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 200);
+
+        // The fallback generation is older by `generated_at`; it must still not be a 304.
+        for path in ["/snapshots/latest", "/snapshots/2026-12-31"] {
+            let res = client
+                .get(format!("{base}{path}"))
+                .header("if-none-match", &etag)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(res.status(), 200, "{path}");
+            assert_eq!(
+                res.headers()["etag"],
+                format!("W/\"{}\"", Uuid::from_u128(31)).as_str(),
+                "{path}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn range_lists_trading_days_ascending_and_validates_bounds() {
+        let base = serve(fake_state(5)).await;
+
+        // Fri 2026-10-09 .. Tue 2026-10-13: the weekend is absent, not null-filled.
+        let (status, body) = get_json(format!(
+            "{base}/snapshots/range?from=2026-10-09&to=2026-10-13"
+        ))
+        .await;
+        assert_eq!(status, 200);
+        let dates: Vec<_> = body
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| r["as_of_date"].as_str().unwrap().to_string())
+            .collect();
+        assert_eq!(dates, vec!["2026-10-09", "2026-10-12", "2026-10-13"]);
+        assert_eq!(body[0]["avg_confidence"], 0.5);
+        assert_eq!(body[0]["top_ticker"], "005930");
+
+        for (query, code) in [
+            ("from=2026-10-13&to=2026-10-09", "invalid_query"),
+            ("from=2025-01-01&to=2026-10-09", "invalid_query"),
+            ("from=2026-10-09", "invalid_query"),
+            ("from=2026-10-09&to=10/13", "invalid_date"),
+        ] {
+            let (status, body) = get_json(format!("{base}/snapshots/range?{query}")).await;
+            assert_eq!(status, 400, "{query}");
+            assert_eq!(body["error"]["code"], code, "{query}");
+        }
+    }
+
+    #[tokio::test]
+    async fn responses_are_gzipped_on_request_except_metrics() {
+        use std::io::Read;
+
+        let base = serve(
+            fake_state(5).with_metrics(Some(
+                metrics_exporter_prometheus::PrometheusBuilder::new()
+                    .build_recorder()
+                    .handle(),
+            )),
+        )
+        .await;
+        let client = reqwest::Client::new();
+        let url = format!("{base}/snapshots/2026-12-31");
+
+        let plain: serde_json::Value = client.get(&url).send().await.unwrap().json().await.unwrap();
+
+        let res = client
+            .get(&url)
+            .header("accept-encoding", "gzip")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.headers()["content-encoding"], "gzip");
+        let compressed = res.bytes().await.unwrap();
+        let mut decoded = String::new();
+        flate2::read::GzDecoder::new(&compressed[..])
+            .read_to_string(&mut decoded)
+            .unwrap();
+        assert!(compressed.len() < decoded.len());
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&decoded).unwrap(),
+            plain
+        );
+
+        let res = client
+            .get(format!("{base}/metrics"))
+            .header("accept-encoding", "gzip, br")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 200);
+        assert!(res.headers().get("content-encoding").is_none());
+    }
+
+    #[tokio::test]
+    async fn rate_limit_returns_429_with_retry_after_per_key() {
+        let base = serve_with(
+            fake_state(5),
+            RouterOptions {
+                compress: false,
+                rate_limit_per_min: Some(3),
+                docs: false,
+                trusted_proxies: Arc::from([]),
+            },
+        )
+        .await;
+        let client = reqwest::Client::new();
+        let get = |key: &'static str, path: &'static str| {
+            client
+                .get(format!("{base}{path}"))
+                .header("x-api-key", key)
+                .send()
+        };
+
+        for _ in 0..3 {
+            assert_eq!(get("secret", "/snapshots").await.unwrap().status(), 200);
+        }
+        let res = get("secret", "/snapshots").await.unwrap();
+        assert_eq!(res.status(), 429);
+        let retry_after: u64 = res.headers()["retry-after"]
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!((1..=20).contains(&retry_after), "{retry_after}");
+        let body: serde_json::Value = res.json().await.unwrap();
+        assert_eq!(body["error"]["code"], "rate_limited");
+
+        // Separate key, separate bucket; health checks are never limited.
+        assert_eq!(get("admin-1", "/snapshots").await.unwrap().status(), 200);
+        for _ in 0..5 {
+            assert_eq!(get("secret", "/healthz").await.unwrap().status(), 200);
+        }
+    }
+
+    #[tokio::test]
+    async fn rate_limit_ignores_spoofed_keys_and_forwarded_for() {
+        let base = serve_with(
+            fake_state(5),
+            RouterOptions {
+                compress: false,
+                rate_limit_per_min: Some(3),
+                docs: false,
+                trusted_proxies: Arc::from([]),
+            },
+        )
+        .await;
+        let client = reqwest::Client::new();
+        let mut statuses = Vec::new();
+        for i in 0..5 {
+            let res = client
+                .get(format!("{base}/snapshots"))
+                .header("x-api-key", format!("made-up-{i}"))
+                .header("x-forwarded-for", format!("198.51.100.{i}"))
+                .send()
+                .await
+                .unwrap();
+            statuses.push(res.status().as_u16());
+        }
+        // Every request shares the peer's bucket.
+        assert_eq!(statuses, [200, 200, 200, 429, 429]);
+        // A configured key still has its own.
+        let res = client
+            .get(format!("{base}/snapshots"))
+            .header("x-api-key", "secret")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 200);
+    }
+
+    /// Every `$ref` in `value` must point at an existing `#/components/...` entry.
+    fn assert_refs_resolve(spec: &serde_json::Value, value: &serde_json::Value) {
+        match value {
+            serde_json::Value::Object(map) => {
+                if let Some(r) = map.get("$ref").and_then(|r| r.as_str()) {
+                    let pointer = r.strip_prefix('#').expect("local ref");
+                    assert!(spec.pointer(pointer).is_some(), "dangling $ref {r}");
+                }
+                map.values().for_each(|v| assert_refs_resolve(spec, v));
+            }
+            serde_json::Value::Array(items) => {
+                items.iter().for_each(|v| assert_refs_resolve(spec, v))
+            }
+            _ => {}
+        }
+    }
+
+    #[tokio::test]
+    async fn openapi_spec_is_well_formed_and_covers_every_route() {
+        let base = serve(fake_state(5)).await;
+        let (status, spec) = get_json(format!("{base}/openapi.json")).await;
+        assert_eq!(status, 200);
+        assert!(spec["openapi"].as_str().unwrap().starts_with("3."));
+        assert_refs_resolve(&spec, &spec);
+
+        let paths = spec["paths"].as_object().unwrap();
+        let mut listed: Vec<&str> = paths.keys().map(String::as_str).collect();
+        listed.sort();
+        assert_eq!(
+            listed,
+            vec![
+                "/admin/snapshots/{snapshot_id}/invalidate",
+                "/docs",
+                "/features/{as_of_date}",
+                "/features/{as_of_date}/{ticker}",
+                "/healthz",
+                "/ingest/runs",
+                "/ingest/runs/{id}",
+                "/items",
+                "/items/{as_of_date}/{ticker}",
+                "/metrics",
+                "/openapi.json",
+                "/readyz",
+                "/runs/latest",
+                "/snapshots",
+                "/snapshots/id/{snapshot_id}",
+                "/snapshots/latest",
+                "/snapshots/range",
+                "/snapshots/{as_of_date}",
+                "/snapshots/{as_of_date}/diff",
+            ]
+        );
+
+        for (path, item) in paths {
+            let (method, op) = item.as_object().unwrap().iter().next().unwrap();
+            assert!(
+                op["responses"]["200"].is_object(),
+                "{path}: no 200 response"
+            );
+            // Every `{param}` in the template is declared as a path parameter.
+            let declared: Vec<&str> = op["parameters"]
+                .as_array()
+                .map(|ps| {
+                    ps.iter()
+                        .filter(|p| p["in"] == "path")
+                        .map(|p| p["name"].as_str().unwrap())
+                        .collect()
+                })
+                .unwrap_or_default();
+            for segment in path.split('/').filter(|s| s.starts_with('{')) {
+                let name = segment.trim_matches(|c| c == '{' || c == '}');
+                assert!(declared.contains(&name), "{path}: undeclared {name}");
+            }
+
+            // The route is actually registered: an unmatched path or method would hit the
+            // `route_not_found` / `method_not_allowed` fallbacks.
+            let concrete = path
+                .replace("{as_of_date}", "2026-12-31")
+                .replace("{ticker}", "000001")
+                .replace("{id}", &Uuid::from_u128(2).to_string())
+                .replace("{snapshot_id}", &Uuid::nil().to_string());
+            let method = reqwest::Method::from_bytes(method.to_uppercase().as_bytes()).unwrap();
+            let res = reqwest::Client::new()
+                .request(method, format!("{base}{concrete}"))
+                .header("x-api-key", "secret")
+                .send()
+                .await
+                .unwrap();
+            let status = res.status();
+            let body = res.text().await.unwrap();
+            assert!(
+                status != 405 && !body.contains("route_not_found"),
+                "{path} is documented but not routed"
+            );
+        }
+
+        let params = spec["paths"]["/snapshots/{as_of_date}"]["get"]["parameters"]
+            .as_array()
+            .unwrap();
+        let names: Vec<_> = params.iter().map(|p| p["name"].as_str().unwrap()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "as_of_date",
+                "top",
+                "fields",
+                "with_movement",
+                "if-none-match",
+                "if-modified-since"
+            ]
+        );
+        assert_eq!(params[0]["schema"]["format"], "date");
+        let security = &spec["paths"]["/ingest/runs/{id}"]["get"]["security"];
+        assert!(security.to_string().contains("api_key"), "{security}");
+        assert!(spec["components"]["securitySchemes"]["api_key"].is_object());
+    }
+
+    #[tokio::test]
+    async fn snapshot_by_id_distinguishes_missing_and_failed_runs() {
+        let base = serve(fake_state(5)).await;
+
+        let (status, body) = get_json(format!("{base}/snapshots/id/{}?top=3", Uuid::nil())).await;
+        assert_eq!(status, 200);
+        assert_eq!(body["snapshot_id"], Uuid::nil().to_string());
+        assert_eq!(body["snapshot"]["items"].as_array().unwrap().len(), 3);
+
+        let (status, body) = get_json(format!("{base}/snapshots/id/{}", Uuid::from_u128(2))).await;
+        assert_eq!(status, 410);
+        assert_eq!(body["error"]["code"], "snapshot_failed");
+        assert!(body["error"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("parse_error"));
+
+        let (status, body) = get_json(format!("{base}/snapshots/id/{}", Uuid::from_u128(9))).await;
+        assert_eq!(status, 404);
+        assert_eq!(body["error"]["code"], "snapshot_not_found");
+
+        let (status, body) = get_json(format!("{base}/snapshots/id/2026-12-31")).await;
+        assert_eq!(status, 400);
+        assert_eq!(body["error"]["code"], "invalid_id");
+    }
+
+    #[tokio::test]
+    async fn ticker_spellings_and_trailing_slashes_resolve_the_same_item() {
+        let base = serve(fake_state(5)).await;
+
+        for path in [
+            "/items/2026-12-31/KRX:000001",
+            "/items/2026-12-31/KRX%3A000001",
+            "/items/2026-12-31/000001",
+            "/items/2026-12-31/KRX:000001/",
+            "/features/2026-12-31/KRX%3A000001",
+            "/features/2026-12-31/000001/",
+        ] {
+            let (status, body) = get_json(format!("{base}{path}")).await;
+            assert_eq!(status, 200, "{path}");
+            assert_eq!(body["ticker"], "KRX:000001", "{path}");
+        }
+        let (status, body) = get_json(format!("{base}/snapshots/latest/")).await;
+        assert_eq!(status, 200);
+        assert!(body["snapshot_id"].is_string());
+
+        let (status, body) = get_json(format!("{base}/items?ticker=KRX%3A000002")).await;
+        assert_eq!(status, 200);
+        assert_eq!(body["total"], 2);
+
+        let (status, body) = get_json(format!("{base}/nope/items")).await;
+        assert_eq!(status, 404);
+        assert_eq!(body["error"]["code"], "route_not_found");
+        assert!(body["error"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("/nope/items"));
+
+        let res = reqwest::Client::new()
+            .delete(format!("{base}/snapshots/latest"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 405);
+        assert!(res.headers()["allow"].to_str().unwrap().contains("GET"));
+        let body: serde_json::Value = res.json().await.unwrap();
+        assert_eq!(body["error"]["code"], "method_not_allowed");
+    }
+
+    #[tokio::test]
+    async fn features_lookup_single_and_batch() {
+        let base = serve(fake_state(5)).await;
+
+        let (status, body) = get_json(format!("{base}/features/2026-12-31/000002")).await;
+        assert_eq!(status, 200);
+        assert_eq!(body["name"], "Stock 2");
+        assert_eq!(body["close"], 10_002.0);
+        assert_eq!(
+            body["features"],
+            serde_json::json!({"per": 12.5, "ret_1d": 0.02})
+        );
+
+        let (status, body) = get_json(format!("{base}/features/2026-12-30/000002")).await;
+        assert_eq!(status, 404);
+        assert_eq!(body["error"]["code"], "features_not_found");
+
+        let (status, body) = get_json(format!(
+            "{base}/features/2026-12-31?tickers=000003,%20000001,000009,000001"
+        ))
+        .await;
+        assert_eq!(status, 200);
+        let tickers: Vec<_> = body["items"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|i| i["ticker"].as_str().unwrap())
+            .collect();
+        assert_eq!(tickers, vec!["KRX:000001", "KRX:000003"]);
+        assert_eq!(body["missing"], serde_json::json!(["KRX:000009"]));
+
+        let (status, _) = get_json(format!("{base}/features/2026-12-31")).await;
+        assert_eq!(status, 400);
+        let many: Vec<String> = (0..51).map(|i| format!("{i:06}")).collect();
+        let (status, body) = get_json(format!(
+            "{base}/features/2026-12-31?tickers={}",
+            many.join(",")
+        ))
+        .await;
+        assert_eq!(status, 400);
+        assert_eq!(body["error"]["code"], "invalid_query");
+    }
+
+    #[tokio::test]
+    async fn item_lookup_stays_consistent_with_duplicate_generations() {
+        let base = serve(fake_state(5)).await;
+        let client = reqwest::Client::new();
+
+        let (_, snapshot) = get_json(format!("{base}/snapshots/2026-12-31")).await;
+        let newest = snapshot["snapshot_id"].as_str().unwrap().to_string();
+        let res = client
+            .get(format!("{base}/items/2026-12-31/000001"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.headers()[SNAPSHOT_ID_HEADER], newest.as_str());
+        let item: serde_json::Value = res.json().await.unwrap();
+        assert_eq!(item["rank"], snapshot["snapshot"]["items"][0]["rank"]);
+        assert_eq!(item["rank"], 1);
+
+        // Pinned to the older generation of the same date.
+        let older = Uuid::from_u128(31);
+        let res = client
+            .get(format!(
+                "{base}/items/2026-12-31/000001?snapshot_id={older}"
+            ))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(
+            res.headers()[SNAPSHOT_ID_HEADER],
+            older.to_string().as_str()
+        );
+        let item: serde_json::Value = res.json().await.unwrap();
+        assert_eq!(item["rank"], 20);
+
+        let (status, body) = get_json(format!(
+            "{base}/items/2026-12-30/000001?snapshot_id={older}"
+        ))
+        .await;
+        assert_eq!(status, 404);
+        assert_eq!(body["error"]["code"], "snapshot_not_found");
+        let (status, body) =
+            get_json(format!("{base}/items/2026-12-31/000001?snapshot_id=latest")).await;
+        assert_eq!(status, 400);
+        assert_eq!(body["error"]["code"], "invalid_id");
+    }
+
+    #[tokio::test]
+    async fn lists_items_across_snapshots_with_filters() {
+        let base = serve(fake_state(5)).await;
+
+        let (status, body) = get_json(format!(
+            "{base}/items?ticker=000001&from=2026-12-01&to=2026-12-31&limit=1"
+        ))
+        .await;
+        assert_eq!(status, 200);
+        assert_eq!(body["total"], 2);
+        assert_eq!(body["next_offset"], 1);
+        let item = &body["items"][0];
+        assert_eq!(item["as_of_date"], "2026-12-31");
+        assert_eq!(item["ticker"], "KRX:000001");
+        assert!(item["snapshot_id"].is_string());
+        assert!(item["rationale"].is_array());
+
+        let (_, body) = get_json(format!("{base}/items?min_confidence=0.6")).await;
+        assert_eq!(body["total"], 0);
+        let (_, body) = get_json(format!("{base}/items?to=2026-12-30")).await;
+        assert_eq!(body["total"], 0);
+
+        for bad in [
+            "from=2026-12-31&to=2026-12-01",
+            "from=2026-13-01",
+            "min_confidence=1.5",
+            "min_confidence=high",
+            "limit=0",
+        ] {
+            let (status, body) = get_json(format!("{base}/items?{bad}")).await;
+            assert_eq!(status, 400, "{bad}");
+            assert!(body["error"]["code"]
+                .as_str()
+                .unwrap()
+                .starts_with("invalid_"));
+        }
+    }
+
+    #[tokio::test]
+    async fn admin_invalidation_hides_the_snapshot_from_reads() {
+        let base = serve(fake_state(5)).await;
+        let client = reqwest::Client::new();
+        let url = format!("{base}/admin/snapshots/{}/invalidate", Uuid::nil());
+        let reason = serde_json::json!({"reason": "stale features"});
+
+        // Regular API key, no key, unknown key.
+        let res = client
+            .post(&url)
+            .header("x-api-key", "secret")
+            .json(&reason)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 403);
+        let res = client.post(&url).json(&reason).send().await.unwrap();
+        assert_eq!(res.status(), 401);
+        let res = client
+            .post(&url)
+            .bearer_auth("admin-3")
+            .json(&reason)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 401);
+
+        for body in [serde_json::json!({"reason": "  "}), serde_json::json!({})] {
+            let res = client
+                .post(&url)
+                .header("x-api-key", "admin-2")
+                .json(&body)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(res.status(), 400);
+            let err: serde_json::Value = res.json().await.unwrap();
+            assert_eq!(err["error"]["code"], "invalid_body");
+        }
+
+        let res = client
+            .post(&url)
+            .header("x-api-key", "admin-2")
+            .json(&reason)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 200);
+        let body: serde_json::Value = res.json().await.unwrap();
+        assert_eq!(body["invalidated_reason"], "stale features");
+
+        // The older generation of the same date takes over; the invalidated one is gone.
+        let (_, snapshot) = get_json(format!("{base}/snapshots/2026-12-31")).await;
+        assert_eq!(snapshot["snapshot_id"], Uuid::from_u128(31).to_string());
+        let (status, body) = get_json(format!("{base}/snapshots/id/{}", Uuid::nil())).await;
+        assert_eq!(status, 410);
+        assert_eq!(body["error"]["code"], "snapshot_invalidated");
+
+        let res = client
+            .post(&url)
+            .header("x-api-key", "admin-1")
+            .json(&reason)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 409);
+        let res = client
+            .post(format!(
+                "{base}/admin/snapshots/{}/invalidate",
+                Uuid::from_u128(2)
+            ))
+            .header("x-api-key", "admin-1")
+            .json(&reason)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 409);
+
+        // No admin keys configured: closed.
+        let mut state = fake_state(5);
+        state.admin_keys = Arc::from([]);
+        let base = serve(state).await;
+        let res = client
+            .post(format!("{base}/admin/snapshots/{}/invalidate", Uuid::nil()))
+            .header("x-api-key", "admin-1")
+            .json(&reason)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 503);
+    }
+
+    #[tokio::test]
+    async fn not_found_renders_json_error() {
+        let base = serve(fake_state(5)).await;
+
+        let (status, body) = get_json(format!("{base}/snapshots/2026-01-02")).await;
+        assert_eq!(status, 404);
+        assert_eq!(body["error"]["code"], "snapshot_not_found");
+        assert!(body["error"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("2026-01-02"));
+
+        let (status, body) = get_json(format!("{base}/items/2026-12-31/005930")).await;
+        assert_eq!(status, 404);
+        assert_eq!(body["error"]["code"], "item_not_found");
+        assert!(body["error"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("005930"));
+
+        let (status, body) = get_json(format!("{base}/snapshots/2026-12-31")).await;
+        assert_eq!(status, 200);
+        assert_eq!(body["snapshot"]["as_of_date"], "2026-12-31");
+    }
+}
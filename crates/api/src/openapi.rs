@@ -0,0 +1,91 @@
+use axum::{response::Html, Json};
+use utoipa::openapi::security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi};
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "tootoo API",
+        description = "Daily KRX stock recommendation snapshots. Errors use the `ErrorBody` shape."
+    ),
+    paths(
+        crate::healthz,
+        crate::readyz,
+        crate::render_metrics,
+        openapi_json,
+        swagger_ui,
+        crate::list_snapshots,
+        crate::get_latest_snapshot,
+        crate::get_snapshot_range,
+        crate::get_snapshot_by_date,
+        crate::get_snapshot_by_id,
+        crate::get_snapshot_diff,
+        crate::get_item_by_date_and_ticker,
+        crate::list_items,
+        crate::get_latest_run,
+        crate::get_features,
+        crate::get_features_batch,
+        crate::list_ingest_runs,
+        crate::get_ingest_run,
+        crate::invalidate_snapshot,
+    ),
+    components(schemas(crate::error::ErrorBody)),
+    modifiers(&Security),
+    tags(
+        (name = "snapshots", description = "Recommendation snapshots and run status"),
+        (name = "features", description = "Daily stock features fed to the model"),
+        (name = "ingest", description = "Feature ingest run history"),
+        (name = "admin", description = "Operator actions; require an `ADMIN_API_KEYS` key"),
+        (name = "ops", description = "Health, metrics and this spec"),
+    )
+)]
+pub struct ApiDoc;
+
+/// `x-api-key` header or bearer token carrying `API_AUTH_KEY`.
+struct Security;
+
+impl Modify for Security {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "api_key",
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new("x-api-key"))),
+        );
+        components.add_security_scheme(
+            "bearer",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+        );
+    }
+}
+
+/// OpenAPI 3.1 document for every route.
+#[utoipa::path(get, path = "/openapi.json", tag = "ops",
+    responses((status = 200, description = "This document", body = Object)))]
+pub async fn openapi_json() -> Json<utoipa::openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
+
+/// Swagger UI for `/openapi.json`; only registered when `API_ENABLE_DOCS=true`. Assets load
+/// from a CDN so nothing is bundled into the binary.
+#[utoipa::path(get, path = "/docs", tag = "ops",
+    responses((status = 200, description = "Swagger UI page", body = String, content_type = "text/html")))]
+pub async fn swagger_ui() -> Html<&'static str> {
+    Html(SWAGGER_UI_HTML)
+}
+
+const SWAGGER_UI_HTML: &str = r##"<!doctype html>
+<html>
+<head>
+  <meta charset="utf-8">
+  <title>tootoo API</title>
+  <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5/swagger-ui.css">
+</head>
+<body>
+  <div id="swagger-ui"></div>
+  <script src="https://unpkg.com/swagger-ui-dist@5/swagger-ui-bundle.js"></script>
+  <script>
+    window.ui = SwaggerUIBundle({ url: "/openapi.json", dom_id: "#swagger-ui" });
+  </script>
+</body>
+</html>
+"##;
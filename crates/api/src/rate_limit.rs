@@ -0,0 +1,234 @@
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{header, HeaderMap, HeaderValue},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use dashmap::DashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::auth::{is_known_key, presented_key};
+use crate::error::ApiError;
+use crate::AppState;
+
+/// An untouched bucket refills completely within a minute, so after this long an idle bucket
+/// is indistinguishable from a fresh one and can be dropped.
+const IDLE_EVICT_AFTER: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// In-memory token bucket per client key: `per_min` requests of burst, refilled continuously at
+/// `per_min / 60` tokens per second. State is per process; fine for a single API instance.
+#[derive(Debug)]
+pub struct RateLimiter {
+    capacity: f64,
+    refill_per_sec: f64,
+    buckets: DashMap<String, Bucket>,
+    last_sweep: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    pub fn per_minute(per_min: u32) -> Self {
+        Self {
+            capacity: per_min as f64,
+            refill_per_sec: per_min as f64 / 60.0,
+            buckets: DashMap::new(),
+            last_sweep: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Takes one token for `key`, or returns how long until one is available.
+    pub fn check(&self, key: &str, now: Instant) -> Result<(), Duration> {
+        self.maybe_evict(now);
+
+        let mut bucket = self.buckets.entry(key.to_string()).or_insert(Bucket {
+            tokens: self.capacity,
+            updated: now,
+        });
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64(
+                (1.0 - bucket.tokens) / self.refill_per_sec,
+            ))
+        }
+    }
+
+    fn maybe_evict(&self, now: Instant) {
+        let mut last_sweep = self.last_sweep.lock().unwrap_or_else(|e| e.into_inner());
+        if now.saturating_duration_since(*last_sweep) < IDLE_EVICT_AFTER {
+            return;
+        }
+        *last_sweep = now;
+        drop(last_sweep);
+        let before = self.buckets.len();
+        self.buckets
+            .retain(|_, b| now.saturating_duration_since(b.updated) < IDLE_EVICT_AFTER);
+        tracing::debug!(
+            evicted = before.saturating_sub(self.buckets.len()),
+            remaining = self.buckets.len(),
+            "rate limiter sweep"
+        );
+    }
+}
+
+/// What [`rate_limit`] needs besides the buckets: the configured keys, and which peers are
+/// proxies whose `X-Forwarded-For` is believed.
+#[derive(Clone)]
+pub struct RateLimitState {
+    pub limiter: Arc<RateLimiter>,
+    pub app: AppState,
+    /// `API_TRUSTED_PROXIES`.
+    pub trusted_proxies: Arc<[IpAddr]>,
+}
+
+/// `API_TRUSTED_PROXIES`: comma-separated proxy IPs; entries that do not parse are skipped with
+/// a warning.
+pub fn trusted_proxies_from_env() -> Arc<[IpAddr]> {
+    let raw = std::env::var("API_TRUSTED_PROXIES").unwrap_or_default();
+    raw.split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .filter_map(|s| match s.parse() {
+            Ok(ip) => Some(ip),
+            Err(_) => {
+                tracing::warn!(entry = s, "ignoring unparseable API_TRUSTED_PROXIES entry");
+                None
+            }
+        })
+        .collect()
+}
+
+/// A configured API key when one is presented, so each key gets its own bucket. Otherwise the
+/// client address: the peer itself, or, when the peer is a trusted proxy, the rightmost
+/// `X-Forwarded-For` hop that is not one. Unknown keys and forwarded-for headers from untrusted
+/// peers are ignored, so rotating them does not buy fresh buckets.
+fn client_key(
+    headers: &HeaderMap,
+    peer: Option<IpAddr>,
+    is_known_key: impl Fn(&str) -> bool,
+    trusted_proxies: &[IpAddr],
+) -> String {
+    if let Some(key) = presented_key(headers).filter(|k| is_known_key(k)) {
+        return format!("key:{key}");
+    }
+    let Some(peer) = peer else {
+        return "anonymous".to_string();
+    };
+    if !trusted_proxies.contains(&peer) {
+        return format!("ip:{peer}");
+    }
+    let forwarded = headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|v: &HeaderValue| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .filter_map(|hop| hop.trim().parse::<IpAddr>().ok())
+        .collect::<Vec<_>>();
+    let client = forwarded
+        .into_iter()
+        .rev()
+        .find(|ip| !trusted_proxies.contains(ip))
+        .unwrap_or(peer);
+    format!("ip:{client}")
+}
+
+pub async fn rate_limit(State(state): State<RateLimitState>, req: Request, next: Next) -> Response {
+    let peer = req
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip());
+    let key = client_key(
+        req.headers(),
+        peer,
+        |k| is_known_key(&state.app, k),
+        &state.trusted_proxies,
+    );
+    match state.limiter.check(&key, Instant::now()) {
+        Ok(()) => next.run(req).await,
+        Err(retry_after) => {
+            let secs = retry_after.as_secs_f64().ceil().max(1.0) as u64;
+            let mut res = ApiError::rate_limited(secs).into_response();
+            res.headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(secs));
+            res
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn refills_over_time_and_evicts_idle_buckets() {
+        let limiter = RateLimiter::per_minute(2);
+        let t0 = Instant::now();
+
+        assert!(limiter.check("a", t0).is_ok());
+        assert!(limiter.check("a", t0).is_ok());
+        let wait = limiter.check("a", t0).unwrap_err();
+        assert_eq!(wait.as_secs(), 30);
+        // Other keys have their own bucket.
+        assert!(limiter.check("b", t0).is_ok());
+
+        assert!(limiter.check("a", t0 + Duration::from_secs(31)).is_ok());
+        assert!(limiter.check("a", t0 + Duration::from_secs(31)).is_err());
+
+        assert_eq!(limiter.buckets.len(), 2);
+        assert!(limiter.check("c", t0 + Duration::from_secs(200)).is_ok());
+        assert_eq!(limiter.buckets.len(), 1);
+    }
+
+    #[test]
+    fn only_configured_keys_and_trusted_proxies_pick_the_bucket() {
+        let headers = |pairs: &[(&'static str, &'static str)]| {
+            let mut map = HeaderMap::new();
+            for (name, value) in pairs {
+                map.append(*name, HeaderValue::from_static(value));
+            }
+            map
+        };
+        let known = |k: &str| k == "secret";
+        let peer: IpAddr = "203.0.113.9".parse().unwrap();
+        let proxy: IpAddr = "10.0.0.2".parse().unwrap();
+
+        let key = |h: &HeaderMap, peer, proxies: &[IpAddr]| client_key(h, peer, known, proxies);
+        assert_eq!(
+            key(&headers(&[("x-api-key", "secret")]), Some(peer), &[]),
+            "key:secret"
+        );
+        assert_eq!(
+            key(
+                &headers(&[("authorization", "Bearer secret")]),
+                Some(peer),
+                &[]
+            ),
+            "key:secret"
+        );
+        assert_eq!(
+            key(&headers(&[("x-api-key", "made-up")]), Some(peer), &[]),
+            "ip:203.0.113.9"
+        );
+        // Forwarded-for is only believed from a trusted proxy.
+        let forwarded = headers(&[("x-forwarded-for", "198.51.100.7, 10.0.0.2")]);
+        assert_eq!(key(&forwarded, Some(peer), &[]), "ip:203.0.113.9");
+        assert_eq!(key(&forwarded, Some(proxy), &[proxy]), "ip:198.51.100.7");
+        // A client-supplied leftmost hop does not override what the proxy appended.
+        let spoofed = headers(&[("x-forwarded-for", "1.2.3.4, 198.51.100.7")]);
+        assert_eq!(key(&spoofed, Some(proxy), &[proxy]), "ip:198.51.100.7");
+        assert_eq!(key(&headers(&[]), Some(proxy), &[proxy]), "ip:10.0.0.2");
+        assert_eq!(key(&headers(&[]), None, &[]), "anonymous");
+    }
+}
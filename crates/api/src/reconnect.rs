@@ -0,0 +1,81 @@
+use serde::Serialize;
+use std::future::Future;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+/// First retry delay; doubles per attempt up to [`MAX_BACKOFF`].
+pub const BASE_BACKOFF: Duration = Duration::from_secs(1);
+pub const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// What `/readyz` reports about the database while (and after) the pool is being retried.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DbStatus {
+    /// Error of the most recent failed attempt; cleared once connected.
+    pub last_error: Option<String>,
+    /// Background attempts made so far (the startup attempt is not counted).
+    pub retry_count: u32,
+}
+
+/// Delay before background attempt `attempt` (0-based): `base * 2^attempt`, capped at `max`.
+pub fn backoff_delay(base: Duration, max: Duration, attempt: u32) -> Duration {
+    base.saturating_mul(2u32.saturating_pow(attempt)).min(max)
+}
+
+/// Calls `connect` with exponential backoff until it succeeds, then swaps the result into
+/// `slot`. Handlers read `slot` per request, so the API leaves degraded mode without a restart.
+pub async fn reconnect<T, F, Fut>(
+    slot: Arc<RwLock<Option<T>>>,
+    status: Arc<RwLock<DbStatus>>,
+    base: Duration,
+    max: Duration,
+    mut connect: F,
+) where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = anyhow::Result<T>>,
+{
+    let mut attempt = 0u32;
+    loop {
+        tokio::time::sleep(backoff_delay(base, max, attempt)).await;
+        attempt = attempt.saturating_add(1);
+
+        match connect().await {
+            Ok(value) => {
+                *slot.write().unwrap_or_else(|e| e.into_inner()) = Some(value);
+                let mut status = status.write().unwrap_or_else(|e| e.into_inner());
+                status.last_error = None;
+                status.retry_count = attempt;
+                tracing::info!(attempt, "db reconnected; leaving degraded mode");
+                return;
+            }
+            Err(e) => {
+                let next = backoff_delay(base, max, attempt);
+                tracing::warn!(
+                    attempt,
+                    error = %format!("{e:#}"),
+                    retry_in_ms = next.as_millis() as u64,
+                    "db reconnect failed"
+                );
+                let mut status = status.write().unwrap_or_else(|e| e.into_inner());
+                status.last_error = Some(format!("{e:#}"));
+                status.retry_count = attempt;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_and_caps() {
+        let delays: Vec<u64> = (0..8)
+            .map(|n| backoff_delay(BASE_BACKOFF, MAX_BACKOFF, n).as_secs())
+            .collect();
+        assert_eq!(delays, vec![1, 2, 4, 8, 16, 32, 60, 60]);
+        assert_eq!(
+            backoff_delay(BASE_BACKOFF, MAX_BACKOFF, u32::MAX),
+            MAX_BACKOFF
+        );
+    }
+}
@@ -0,0 +1,22 @@
+-- Audit trail of every LLM call made for a run (initial, max_tokens retry, repairs).
+-- `run_id` groups the attempts of one worker run; `snapshot_id` is attached once the run persists.
+
+CREATE TABLE IF NOT EXISTS llm_attempts (
+  id uuid PRIMARY KEY DEFAULT gen_random_uuid(),
+  run_id uuid NOT NULL,
+  snapshot_id uuid REFERENCES recommendation_snapshots (id) ON DELETE RESTRICT,
+  as_of_date date NOT NULL,
+  provider text NOT NULL,
+  model text NOT NULL,
+  attempt_no int NOT NULL,
+  stage text NOT NULL,
+  raw_response jsonb,
+  error text,
+  created_at timestamptz NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS llm_attempts_run_id_idx
+  ON llm_attempts (run_id, attempt_no);
+
+CREATE INDEX IF NOT EXISTS llm_attempts_as_of_date_idx
+  ON llm_attempts (as_of_date);
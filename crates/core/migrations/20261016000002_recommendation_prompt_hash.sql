@@ -0,0 +1,4 @@
+-- SHA-256 of the system prompt template used for the run (NULL for rows written before this).
+
+ALTER TABLE recommendation_snapshots
+  ADD COLUMN IF NOT EXISTS prompt_hash text;
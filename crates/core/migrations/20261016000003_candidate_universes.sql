@@ -0,0 +1,10 @@
+-- Exact candidate universe sent to the LLM, for reproducing a run (opt-in via PERSIST_UNIVERSE).
+
+CREATE TABLE IF NOT EXISTS candidate_universes (
+  as_of_date date NOT NULL,
+  digest text NOT NULL,
+  universe_size int NOT NULL,
+  candidates jsonb NOT NULL,
+  created_at timestamptz NOT NULL DEFAULT now(),
+  PRIMARY KEY (as_of_date, digest)
+);
@@ -0,0 +1,9 @@
+-- Coarse failure cause for error snapshots (see LlmFailureKind). NULL for successes, non-LLM
+-- failures, and rows written before this.
+
+ALTER TABLE recommendation_snapshots
+  ADD COLUMN IF NOT EXISTS error_kind text;
+
+CREATE INDEX IF NOT EXISTS recommendation_snapshots_error_kind_idx
+  ON recommendation_snapshots (error_kind)
+  WHERE status = 'error';
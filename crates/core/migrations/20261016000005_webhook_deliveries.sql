@@ -0,0 +1,17 @@
+-- One row per webhook POST attempt made after a snapshot is persisted (see SNAPSHOT_WEBHOOK_URLS).
+-- `url` keeps only scheme/host/port; webhook paths often embed credentials.
+
+CREATE TABLE IF NOT EXISTS webhook_deliveries (
+  id uuid PRIMARY KEY DEFAULT gen_random_uuid(),
+  snapshot_id uuid NOT NULL REFERENCES recommendation_snapshots (id) ON DELETE CASCADE,
+  url_index int NOT NULL,
+  url text NOT NULL,
+  attempt_no int NOT NULL,
+  status_code int,
+  error text,
+  delivered boolean NOT NULL,
+  created_at timestamptz NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS webhook_deliveries_snapshot_id_idx
+  ON webhook_deliveries (snapshot_id);
@@ -0,0 +1,8 @@
+-- Per-ticker item history (`GET /items?ticker=`) joins items to snapshots by snapshot_id;
+-- the composite index covers both the ticker filter and the join key, superseding the
+-- ticker-only index.
+
+CREATE INDEX IF NOT EXISTS recommendation_items_ticker_snapshot_idx
+  ON recommendation_items (ticker, snapshot_id);
+
+DROP INDEX IF EXISTS recommendation_items_ticker_idx;
@@ -0,0 +1,13 @@
+-- Admin-invalidated snapshots stay in the table (append-only) but are hidden from reads.
+-- The one-success-per-date guarantee only applies to rows that are still valid, so a rerun
+-- can regenerate an invalidated day.
+
+ALTER TABLE recommendation_snapshots
+  ADD COLUMN IF NOT EXISTS invalidated_at timestamptz,
+  ADD COLUMN IF NOT EXISTS invalidated_reason text;
+
+DROP INDEX IF EXISTS recommendation_snapshots_success_unique;
+
+CREATE UNIQUE INDEX IF NOT EXISTS recommendation_snapshots_valid_success_unique
+  ON recommendation_snapshots (as_of_date)
+  WHERE status = 'success' AND invalidated_at IS NULL;
@@ -0,0 +1,9 @@
+-- English company names for display. Filled by KIS ingest when the master file has one;
+-- recommendation items copy it from stock_features_daily at persist time. Historical rows stay
+-- NULL.
+
+ALTER TABLE stock_features_daily
+  ADD COLUMN IF NOT EXISTS name_en text;
+
+ALTER TABLE recommendation_items
+  ADD COLUMN IF NOT EXISTS name_en text;
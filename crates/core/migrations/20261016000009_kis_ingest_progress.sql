@@ -0,0 +1,9 @@
+-- Tickers a resumable KIS ingest (`--resume`) has already upserted into stock_features_daily for
+-- a date, so a restarted run skips them. Cleared once the date's ingest run succeeds.
+
+CREATE TABLE IF NOT EXISTS kis_ingest_progress (
+  as_of_date date NOT NULL,
+  ticker text NOT NULL,
+  completed_at timestamptz NOT NULL DEFAULT now(),
+  PRIMARY KEY (as_of_date, ticker)
+);
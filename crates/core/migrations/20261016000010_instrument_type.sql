@@ -0,0 +1,6 @@
+-- Instrument type from the KIS master file's security group code (stock, preferred, etf, etn,
+-- ...). The candidate universe excludes ETFs/ETNs by it; NULL rows (historical or from providers
+-- without a type) fall back to the name heuristic.
+
+ALTER TABLE stock_features_daily
+  ADD COLUMN IF NOT EXISTS instrument_type text;
@@ -0,0 +1,5 @@
+-- Market cap (KRW) mirrored from the `market_cap` feature so the candidate universe can filter on
+-- it in SQL (UNIVERSE_MIN_MARKET_CAP). Historical rows stay NULL.
+
+ALTER TABLE stock_features_daily
+  ADD COLUMN IF NOT EXISTS market_cap double precision;
@@ -0,0 +1,6 @@
+-- Exchange flags (managed, halted, warning) derived from each row's features at ingest. The
+-- candidate universe excludes flagged rows unless UNIVERSE_INCLUDE_FLAGGED=true; rows stay stored.
+-- Historical rows get an empty array (unknown, not excluded).
+
+ALTER TABLE stock_features_daily
+  ADD COLUMN IF NOT EXISTS status_flags text[] NOT NULL DEFAULT '{}';
@@ -0,0 +1,12 @@
+-- Daily KOSPI/KOSDAQ index closes fetched once per KIS ingest. The worker passes each date's
+-- returns to the LLM as market context (`kospi_ret_1d`, `kosdaq_ret_1d`).
+
+CREATE TABLE IF NOT EXISTS market_index_daily (
+  as_of_date date NOT NULL,
+  market text NOT NULL,
+  close double precision NOT NULL,
+  ret_1d double precision,
+  source text NOT NULL,
+  updated_at timestamptz NOT NULL DEFAULT now(),
+  PRIMARY KEY (as_of_date, market)
+);
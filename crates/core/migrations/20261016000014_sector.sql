@@ -0,0 +1,5 @@
+-- Korean sector name per row, resolved at ingest from the KIS master file's industry codes.
+-- NULL when the source has none (other providers, KONEX, unknown codes, historical rows).
+
+ALTER TABLE stock_features_daily
+  ADD COLUMN IF NOT EXISTS sector text;
@@ -0,0 +1,13 @@
+-- Earnings announcement dates per ticker, bulk-loaded from CSV (worker --load-earnings-calendar).
+-- Ingest derives the days_to_earnings feature from them; tickers without rows just lack it.
+
+CREATE TABLE IF NOT EXISTS earnings_calendar (
+  ticker text NOT NULL,
+  announce_date date NOT NULL,
+  source text NOT NULL,
+  loaded_at timestamptz NOT NULL DEFAULT now(),
+  PRIMARY KEY (ticker, announce_date)
+);
+
+CREATE INDEX IF NOT EXISTS earnings_calendar_announce_date_idx
+  ON earnings_calendar (announce_date);
@@ -0,0 +1,4 @@
+-- Fingerprint of the payload a run ingested, so an identical republished payload can skip the
+-- upsert (`skipped_duplicate` runs). `payload_etag` is the provider's ETag, when it sends one.
+ALTER TABLE stock_features_ingest_runs ADD COLUMN IF NOT EXISTS payload_digest text;
+ALTER TABLE stock_features_ingest_runs ADD COLUMN IF NOT EXISTS payload_etag text;
@@ -0,0 +1,5 @@
+-- When a stock_features_daily row was last written. Incremental upserts leave rows whose values
+-- did not change alone, so this only moves when the data did. Existing rows get the migration
+-- time (a constant default, so adding the column does not rewrite the table).
+ALTER TABLE stock_features_daily
+  ADD COLUMN IF NOT EXISTS updated_at timestamptz NOT NULL DEFAULT now();
@@ -0,0 +1,12 @@
+-- Ingest runs are inserted as `running` before the fetch and finished afterwards, so a crashed
+-- ingest leaves a row (flipped to `abandoned` by --prune) and durations can be tracked. Runs
+-- from before this were recorded once, at the end: their finish time is `generated_at` and
+-- their start is unknown.
+ALTER TABLE stock_features_ingest_runs ADD COLUMN IF NOT EXISTS started_at timestamptz;
+ALTER TABLE stock_features_ingest_runs ADD COLUMN IF NOT EXISTS finished_at timestamptz;
+
+UPDATE stock_features_ingest_runs SET finished_at = generated_at
+ WHERE finished_at IS NULL AND started_at IS NULL;
+
+CREATE INDEX IF NOT EXISTS stock_features_ingest_runs_running_idx
+  ON stock_features_ingest_runs (started_at) WHERE status = 'running';
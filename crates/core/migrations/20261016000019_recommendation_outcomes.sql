@@ -0,0 +1,21 @@
+-- Forward returns of recommended items (worker --evaluate): the close on the snapshot's
+-- as_of_date against the close `horizon_days` trading days later, both from the `close` feature
+-- of stock_features_daily. A missing close (delisted, halted, not ingested) leaves return_pct
+-- null with a reason code.
+
+CREATE TABLE IF NOT EXISTS recommendation_outcomes (
+  snapshot_id uuid NOT NULL REFERENCES recommendation_snapshots (id) ON DELETE RESTRICT,
+  ticker text NOT NULL,
+  horizon_days int NOT NULL,
+  exit_date date NOT NULL,
+  entry_close double precision,
+  exit_close double precision,
+  return_pct double precision,
+  missing_reason text,
+  computed_at timestamptz NOT NULL DEFAULT now(),
+  PRIMARY KEY (snapshot_id, horizon_days, ticker),
+  CONSTRAINT recommendation_outcomes_horizon_positive CHECK (horizon_days > 0),
+  CONSTRAINT recommendation_outcomes_return_or_reason CHECK (
+    (return_pct IS NULL) = (missing_reason IS NOT NULL)
+  )
+);
@@ -0,0 +1,9 @@
+-- Closing price per row, for forward-return evaluation, price filters and charts. Nullable:
+-- providers without it leave it empty. Rows that carried it as a `close` feature move it here.
+ALTER TABLE stock_features_daily
+  ADD COLUMN IF NOT EXISTS close double precision;
+
+UPDATE stock_features_daily
+SET close = (features->>'close')::double precision,
+    features = features - 'close'
+WHERE features ? 'close' AND jsonb_typeof(features->'close') = 'number';
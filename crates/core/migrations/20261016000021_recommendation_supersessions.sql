@@ -0,0 +1,31 @@
+-- Forced reruns (worker --force) supersede a date's success snapshot without mutating it: the
+-- new snapshot is inserted and the replacement is recorded here. Reads treat a superseded
+-- snapshot like an invalidated one.
+
+CREATE TABLE IF NOT EXISTS recommendation_supersessions (
+  snapshot_id uuid PRIMARY KEY REFERENCES recommendation_snapshots (id) ON DELETE RESTRICT,
+  superseded_by uuid NOT NULL UNIQUE REFERENCES recommendation_snapshots (id) ON DELETE RESTRICT,
+  reason text NOT NULL,
+  created_at timestamptz NOT NULL DEFAULT now()
+);
+
+-- Snapshots that are neither invalidated nor superseded. Every "current" read goes through this
+-- view rather than repeating the predicate.
+CREATE OR REPLACE VIEW current_recommendation_snapshots AS
+  SELECT s.*
+  FROM recommendation_snapshots s
+  WHERE s.invalidated_at IS NULL
+    AND NOT EXISTS (SELECT 1 FROM recommendation_supersessions x WHERE x.snapshot_id = s.id);
+
+-- A superseded snapshot keeps `invalidated_at` null, and a partial index cannot look at another
+-- table, so the old partial unique index would reject every forced rerun. One current success
+-- per date is enforced instead by:
+--   * `persist_success` checking for a current success and inserting in one SERIALIZABLE
+--     transaction, so two racing persists end in a serialization failure (retried, then found);
+--   * the primary key and the unique `superseded_by` above, which make supersession one-to-one,
+--     so two racing forced reruns cannot both replace the same snapshot.
+DROP INDEX IF EXISTS recommendation_snapshots_valid_success_unique;
+
+CREATE INDEX IF NOT EXISTS recommendation_snapshots_valid_success_idx
+  ON recommendation_snapshots (as_of_date)
+  WHERE status = 'success' AND invalidated_at IS NULL;
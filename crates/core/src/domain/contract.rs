@@ -2,7 +2,12 @@ use crate::domain::recommendation::{RecommendationItem, RecommendationSnapshot};
 use anyhow::{bail, ensure};
 use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+const DEFAULT_RATIONALE_MAX_CHARS: usize = 120;
+const DEFAULT_RATIONALE_MIN_CHARS: usize = 10;
+const DEFAULT_RATIONALE_MAX_DUPLICATES: usize = 3;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LlmRecommendationSnapshot {
@@ -21,10 +26,58 @@ pub struct LlmRecommendationItem {
     pub confidence: Option<f64>,
 }
 
+/// Optional, stricter checks on top of the base snapshot contract. `Default` disables them all;
+/// `from_env` applies the production defaults.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ValidationOptions {
+    /// Every rationale line must contain Hangul (the app displays rationale in Korean).
+    pub require_korean_rationale: bool,
+    /// Maximum rationale line length, in characters.
+    pub rationale_max_chars: Option<usize>,
+    /// Minimum rationale line length, in characters.
+    pub rationale_min_chars: Option<usize>,
+    /// A normalized rationale line may appear in at most this many items.
+    pub rationale_max_duplicates: Option<usize>,
+}
+
+impl ValidationOptions {
+    /// Reads `LLM_REQUIRE_KOREAN_RATIONALE` and `LLM_RATIONALE_{MAX_CHARS,MIN_CHARS,MAX_DUPLICATES}`
+    /// (defaults 120 / 10 / 3; `0` disables a check).
+    pub fn from_env() -> Self {
+        fn limit(key: &str, default: usize) -> Option<usize> {
+            let n = std::env::var(key)
+                .ok()
+                .and_then(|s| s.trim().parse::<usize>().ok())
+                .unwrap_or(default);
+            (n > 0).then_some(n)
+        }
+
+        Self {
+            require_korean_rationale: std::env::var("LLM_REQUIRE_KOREAN_RATIONALE")
+                .map(|s| matches!(s.trim().to_ascii_lowercase().as_str(), "1" | "true"))
+                .unwrap_or(false),
+            rationale_max_chars: limit("LLM_RATIONALE_MAX_CHARS", DEFAULT_RATIONALE_MAX_CHARS),
+            rationale_min_chars: limit("LLM_RATIONALE_MIN_CHARS", DEFAULT_RATIONALE_MIN_CHARS),
+            rationale_max_duplicates: limit(
+                "LLM_RATIONALE_MAX_DUPLICATES",
+                DEFAULT_RATIONALE_MAX_DUPLICATES,
+            ),
+        }
+    }
+}
+
 impl LlmRecommendationSnapshot {
     pub fn validate_and_into_snapshot(
         self,
         expected_as_of_date: NaiveDate,
+    ) -> anyhow::Result<RecommendationSnapshot> {
+        self.validate_and_into_snapshot_with(expected_as_of_date, ValidationOptions::default())
+    }
+
+    pub fn validate_and_into_snapshot_with(
+        self,
+        expected_as_of_date: NaiveDate,
+        opts: ValidationOptions,
     ) -> anyhow::Result<RecommendationSnapshot> {
         ensure!(
             self.as_of_date == expected_as_of_date,
@@ -40,8 +93,31 @@ impl LlmRecommendationSnapshot {
 
         let mut seen_ranks = BTreeSet::<i32>::new();
         let mut items = Vec::with_capacity(self.items.len());
+        // Language violations are collected across items so one repair can fix them all.
+        let mut non_korean = Vec::new();
         for item in self.items {
-            items.push(item.validate_and_into_item(&mut seen_ranks)?);
+            match item.validate_and_into_item(&mut seen_ranks, opts) {
+                Ok(item) => items.push(item),
+                Err(err) => match err.downcast::<NonKoreanRationaleError>() {
+                    Ok(lang) => non_korean.extend(lang.tickers),
+                    Err(err) => return Err(err),
+                },
+            }
+        }
+        if !non_korean.is_empty() {
+            return Err(NonKoreanRationaleError {
+                tickers: non_korean,
+            }
+            .into());
+        }
+
+        let mut seen_tickers = BTreeSet::<&str>::new();
+        for item in &items {
+            ensure!(
+                seen_tickers.insert(item.ticker.as_str()),
+                "duplicate ticker: {}",
+                item.ticker
+            );
         }
 
         // Ensure ranks are contiguous 1..=20.
@@ -51,6 +127,11 @@ impl LlmRecommendationSnapshot {
             }
         }
 
+        let violations = rationale_violations(&items, opts);
+        if !violations.is_empty() {
+            return Err(RationaleConstraintError { violations }.into());
+        }
+
         Ok(RecommendationSnapshot {
             as_of_date: self.as_of_date,
             generated_at: self.generated_at,
@@ -63,6 +144,7 @@ impl LlmRecommendationItem {
     fn validate_and_into_item(
         self,
         seen_ranks: &mut BTreeSet<i32>,
+        opts: ValidationOptions,
     ) -> anyhow::Result<RecommendationItem> {
         ensure!(
             (1..=20).contains(&self.rank),
@@ -101,6 +183,14 @@ impl LlmRecommendationItem {
             );
         }
 
+        // Checked last so structural errors take precedence in the repair prompt.
+        if opts.require_korean_rationale && ![&r0, &r1, &r2].iter().all(|l| contains_hangul(l)) {
+            return Err(NonKoreanRationaleError {
+                tickers: vec![ticker],
+            }
+            .into());
+        }
+
         let risk_notes = self
             .risk_notes
             .map(|s| s.trim().to_string())
@@ -110,9 +200,190 @@ impl LlmRecommendationItem {
             rank: self.rank,
             ticker,
             name,
+            // Not part of the model contract; the worker fills it from the features table.
+            name_en: None,
             rationale: [r0, r1, r2],
             risk_notes,
             confidence: self.confidence,
         })
     }
 }
+
+/// LLM output referenced tickers that were not in the candidate universe.
+#[derive(Debug, Clone)]
+pub struct UnknownTickersError {
+    pub tickers: Vec<String>,
+}
+
+impl fmt::Display for UnknownTickersError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "LLM output contains tickers not in the candidate universe: {}",
+            self.tickers.join(", ")
+        )
+    }
+}
+
+impl std::error::Error for UnknownTickersError {}
+
+/// Rationale lines without any Hangul, per offending ticker.
+#[derive(Debug, Clone)]
+pub struct NonKoreanRationaleError {
+    pub tickers: Vec<String>,
+}
+
+impl fmt::Display for NonKoreanRationaleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "rationale must be written in Korean (non-Korean lines for: {})",
+            self.tickers.join(", ")
+        )
+    }
+}
+
+impl std::error::Error for NonKoreanRationaleError {}
+
+/// One rationale length/duplication problem, with the offending line for the repair prompt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RationaleViolation {
+    TooLong {
+        ticker: String,
+        line: String,
+        chars: usize,
+        max: usize,
+    },
+    TooShort {
+        ticker: String,
+        line: String,
+        chars: usize,
+        min: usize,
+    },
+    Duplicated {
+        line: String,
+        tickers: Vec<String>,
+        max: usize,
+    },
+}
+
+impl fmt::Display for RationaleViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RationaleViolation::TooLong {
+                ticker,
+                line,
+                chars,
+                max,
+            } => write!(
+                f,
+                "{ticker}: line too long ({chars} > {max} chars): {line:?}"
+            ),
+            RationaleViolation::TooShort {
+                ticker,
+                line,
+                chars,
+                min,
+            } => write!(
+                f,
+                "{ticker}: line too short ({chars} < {min} chars): {line:?}"
+            ),
+            RationaleViolation::Duplicated { line, tickers, max } => write!(
+                f,
+                "line repeated in {} items (max {max}): {line:?} ({})",
+                tickers.len(),
+                tickers.join(", ")
+            ),
+        }
+    }
+}
+
+/// Rationale lines outside the configured length bounds or repeated across too many items.
+#[derive(Debug, Clone)]
+pub struct RationaleConstraintError {
+    pub violations: Vec<RationaleViolation>,
+}
+
+impl fmt::Display for RationaleConstraintError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "rationale constraints violated ({} issues)",
+            self.violations.len()
+        )?;
+        if let Some(first) = self.violations.first() {
+            write!(f, "; first: {first}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for RationaleConstraintError {}
+
+fn rationale_violations(
+    items: &[RecommendationItem],
+    opts: ValidationOptions,
+) -> Vec<RationaleViolation> {
+    let mut out = Vec::new();
+    // normalized line -> (first spelling seen, tickers using it)
+    let mut by_line = BTreeMap::<String, (&str, Vec<&str>)>::new();
+    for item in items {
+        for line in &item.rationale {
+            let chars = line.chars().count();
+            if let Some(max) = opts.rationale_max_chars.filter(|&max| chars > max) {
+                out.push(RationaleViolation::TooLong {
+                    ticker: item.ticker.clone(),
+                    line: line.clone(),
+                    chars,
+                    max,
+                });
+            }
+            if let Some(min) = opts.rationale_min_chars.filter(|&min| chars < min) {
+                out.push(RationaleViolation::TooShort {
+                    ticker: item.ticker.clone(),
+                    line: line.clone(),
+                    chars,
+                    min,
+                });
+            }
+            let entry = by_line
+                .entry(normalize_rationale(line))
+                .or_insert((line.as_str(), Vec::new()));
+            if entry.1.last() != Some(&item.ticker.as_str()) {
+                entry.1.push(item.ticker.as_str());
+            }
+        }
+    }
+    if let Some(max) = opts.rationale_max_duplicates {
+        for (line, tickers) in by_line.into_values() {
+            if tickers.len() > max {
+                out.push(RationaleViolation::Duplicated {
+                    line: line.to_string(),
+                    tickers: tickers.into_iter().map(str::to_string).collect(),
+                    max,
+                });
+            }
+        }
+    }
+    out
+}
+
+/// Case-, whitespace- and trailing-punctuation-insensitive form used for duplicate detection.
+fn normalize_rationale(line: &str) -> String {
+    line.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+        .trim_end_matches(['.', '!', '。', '…'])
+        .to_string()
+}
+
+fn contains_hangul(s: &str) -> bool {
+    s.chars().any(|c| {
+        matches!(c,
+            '\u{AC00}'..='\u{D7A3}' // syllables
+            | '\u{1100}'..='\u{11FF}' // jamo
+            | '\u{3130}'..='\u{318F}' // compatibility jamo
+        )
+    })
+}
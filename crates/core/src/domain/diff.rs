@@ -0,0 +1,256 @@
+use crate::domain::recommendation::RecommendationSnapshot;
+use chrono::NaiveDate;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Day-over-day change between two successful snapshots.
+#[derive(Debug, Clone, PartialEq, Serialize, utoipa::ToSchema)]
+pub struct SnapshotDiff {
+    pub as_of_date: NaiveDate,
+    /// The snapshot compared against; the most recent success before `as_of_date`, which may be
+    /// several calendar days earlier across weekends/holidays.
+    pub previous_as_of_date: Option<NaiveDate>,
+    /// No earlier snapshot exists, so every item is reported as `added`.
+    pub no_previous: bool,
+    /// In today's list only, by today's rank.
+    pub added: Vec<DiffEntry>,
+    /// In the previous list only, by previous rank.
+    pub removed: Vec<DiffEntry>,
+    /// In both lists, biggest moves first.
+    pub rank_changes: Vec<RankChange>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, utoipa::ToSchema)]
+pub struct DiffEntry {
+    pub ticker: String,
+    pub name: String,
+    pub rank: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, utoipa::ToSchema)]
+pub struct RankChange {
+    pub ticker: String,
+    pub name: String,
+    pub previous_rank: i32,
+    pub rank: i32,
+    /// `previous_rank - rank`: positive means the name moved up.
+    pub delta: i32,
+}
+
+/// Where an item stands relative to the previous snapshot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Movement {
+    /// Not in the previous snapshot (or there is none).
+    New,
+    Up,
+    Down,
+    Same,
+}
+
+impl Movement {
+    /// Lower rank is better, so a smaller `rank` than `previous_rank` is `Up`.
+    pub fn between(previous_rank: Option<i32>, rank: i32) -> Self {
+        match previous_rank {
+            None => Self::New,
+            Some(prev) if rank < prev => Self::Up,
+            Some(prev) if rank > prev => Self::Down,
+            Some(_) => Self::Same,
+        }
+    }
+}
+
+/// `(previous_rank, movement)` for each item of `current`, in item order. `previous_ranks` maps
+/// ticker to rank in the previous snapshot; empty when there is none, so everything is `New`.
+pub fn item_movements(
+    current: &RecommendationSnapshot,
+    previous_ranks: &BTreeMap<String, i32>,
+) -> Vec<(Option<i32>, Movement)> {
+    current
+        .items
+        .iter()
+        .map(|i| {
+            let previous_rank = previous_ranks.get(&i.ticker).copied();
+            (previous_rank, Movement::between(previous_rank, i.rank))
+        })
+        .collect()
+}
+
+pub fn diff_snapshots(
+    current: &RecommendationSnapshot,
+    previous: Option<&RecommendationSnapshot>,
+) -> SnapshotDiff {
+    let prev_by_ticker: BTreeMap<&str, (i32, &str)> = previous
+        .map(|p| {
+            p.items
+                .iter()
+                .map(|i| (i.ticker.as_str(), (i.rank, i.name.as_str())))
+                .collect()
+        })
+        .unwrap_or_default();
+    let current_tickers: BTreeMap<&str, i32> = current
+        .items
+        .iter()
+        .map(|i| (i.ticker.as_str(), i.rank))
+        .collect();
+
+    let mut added = Vec::new();
+    let mut rank_changes = Vec::new();
+    let previous_ranks: BTreeMap<String, i32> = prev_by_ticker
+        .iter()
+        .map(|(ticker, &(rank, _))| (ticker.to_string(), rank))
+        .collect();
+    // `New` items are `added`; everything else has a previous rank and is a rank change.
+    let movements = item_movements(current, &previous_ranks);
+    for (item, (previous_rank, _)) in current.items.iter().zip(movements) {
+        match previous_rank {
+            Some(previous_rank) => rank_changes.push(RankChange {
+                ticker: item.ticker.clone(),
+                name: item.name.clone(),
+                previous_rank,
+                rank: item.rank,
+                delta: previous_rank - item.rank,
+            }),
+            None => added.push(DiffEntry {
+                ticker: item.ticker.clone(),
+                name: item.name.clone(),
+                rank: item.rank,
+            }),
+        }
+    }
+
+    let mut removed: Vec<DiffEntry> = prev_by_ticker
+        .iter()
+        .filter(|(ticker, _)| !current_tickers.contains_key(*ticker))
+        .map(|(ticker, &(rank, name))| DiffEntry {
+            ticker: ticker.to_string(),
+            name: name.to_string(),
+            rank,
+        })
+        .collect();
+
+    added.sort_by_key(|e| e.rank);
+    removed.sort_by_key(|e| e.rank);
+    rank_changes.sort_by_key(|c| (std::cmp::Reverse(c.delta.abs()), c.rank));
+
+    SnapshotDiff {
+        as_of_date: current.as_of_date,
+        previous_as_of_date: previous.map(|p| p.as_of_date),
+        no_previous: previous.is_none(),
+        added,
+        removed,
+        rank_changes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::recommendation::RecommendationItem;
+
+    fn snapshot(date: &str, tickers: &[&str]) -> RecommendationSnapshot {
+        RecommendationSnapshot {
+            as_of_date: date.parse().unwrap(),
+            generated_at: chrono::Utc::now(),
+            items: tickers
+                .iter()
+                .enumerate()
+                .map(|(i, t)| RecommendationItem {
+                    rank: i as i32 + 1,
+                    ticker: t.to_string(),
+                    name: format!("{t} Co"),
+                    name_en: None,
+                    rationale: ["a".into(), "b".into(), "c".into()],
+                    risk_notes: None,
+                    confidence: None,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn reports_added_removed_and_biggest_moves_first() {
+        // Friday -> Monday: the gap days don't matter, only the two snapshots do.
+        let prev = snapshot("2026-10-09", &["A", "B", "C", "D"]);
+        let cur = snapshot("2026-10-12", &["D", "A", "E", "B"]);
+
+        let diff = diff_snapshots(&cur, Some(&prev));
+
+        assert_eq!(diff.previous_as_of_date, "2026-10-09".parse().ok());
+        assert!(!diff.no_previous);
+        assert_eq!(
+            diff.added,
+            vec![DiffEntry {
+                ticker: "E".into(),
+                name: "E Co".into(),
+                rank: 3
+            }]
+        );
+        assert_eq!(diff.removed.len(), 1);
+        assert_eq!(
+            (diff.removed[0].ticker.as_str(), diff.removed[0].rank),
+            ("C", 3)
+        );
+
+        let moves: Vec<_> = diff
+            .rank_changes
+            .iter()
+            .map(|c| (c.ticker.as_str(), c.previous_rank, c.rank, c.delta))
+            .collect();
+        assert_eq!(
+            moves,
+            vec![("D", 4, 1, 3), ("B", 2, 4, -2), ("A", 1, 2, -1)]
+        );
+    }
+
+    #[test]
+    fn movements_compare_ranks_with_the_previous_snapshot() {
+        let prev = snapshot("2026-10-09", &["A", "B", "C"]);
+        let cur = snapshot("2026-10-12", &["B", "A", "C", "E"]);
+        let previous_ranks: BTreeMap<String, i32> = prev
+            .items
+            .iter()
+            .map(|i| (i.ticker.clone(), i.rank))
+            .collect();
+
+        assert_eq!(
+            item_movements(&cur, &previous_ranks),
+            vec![
+                (Some(2), Movement::Up),
+                (Some(1), Movement::Down),
+                (Some(3), Movement::Same),
+                (None, Movement::New),
+            ]
+        );
+    }
+
+    #[test]
+    fn without_previous_every_item_is_new() {
+        let cur = snapshot("2026-10-12", &["B", "A"]);
+
+        let movements = item_movements(&cur, &BTreeMap::new());
+
+        assert_eq!(
+            movements,
+            vec![(None, Movement::New), (None, Movement::New)]
+        );
+        assert_eq!(
+            serde_json::to_value(Movement::New).unwrap(),
+            serde_json::json!("new")
+        );
+    }
+
+    #[test]
+    fn without_previous_everything_is_added() {
+        let cur = snapshot("2026-10-12", &["B", "A"]);
+
+        let diff = diff_snapshots(&cur, None);
+
+        assert!(diff.no_previous);
+        assert_eq!(diff.previous_as_of_date, None);
+        let added: Vec<_> = diff.added.iter().map(|e| e.ticker.as_str()).collect();
+        assert_eq!(added, vec!["B", "A"]);
+        assert!(diff.removed.is_empty());
+        assert!(diff.rank_changes.is_empty());
+    }
+}
@@ -1,2 +1,4 @@
 pub mod contract;
+pub mod diff;
 pub mod recommendation;
+pub mod universe;
@@ -9,11 +9,15 @@ pub struct RecommendationSnapshot {
     pub items: Vec<RecommendationItem>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
 pub struct RecommendationItem {
     pub rank: i32,
     pub ticker: String,
     pub name: String,
+    /// English company name, copied from `stock_features_daily` at persist time (never taken
+    /// from the model). `None` when the ingest source had none.
+    #[serde(default)]
+    pub name_en: Option<String>,
     pub rationale: [String; 3],
     pub risk_notes: Option<String>,
     pub confidence: Option<f64>,
@@ -23,5 +27,13 @@ pub struct RecommendationItem {
 pub struct Candidate {
     pub ticker: String,
     pub name: String,
+    /// Display-only; kept out of the prompt and the universe digest.
+    #[serde(default, skip_serializing)]
+    pub name_en: Option<String>,
+    /// Korean sector name from `stock_features_daily.sector`. Shown to the model next to the
+    /// name; never a feature, so prompt budgeting cannot drop it. Left out of the JSON when
+    /// unknown, which keeps digests of sector-less universes unchanged.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sector: Option<String>,
     pub features: BTreeMap<String, f64>,
 }
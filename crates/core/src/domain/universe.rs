@@ -0,0 +1,157 @@
+//! Serialized form of a candidate universe: worker `--export-universe` writes it to disk and
+//! `candidate_universes` stores its candidate list.
+
+use crate::domain::recommendation::Candidate;
+use crate::llm::GenerateInput;
+use anyhow::Context;
+use chrono::NaiveDate;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::path::Path;
+
+/// File format of an export, chosen by the path's extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Json,
+    Csv,
+}
+
+impl ExportFormat {
+    pub fn from_path(path: &Path) -> anyhow::Result<Self> {
+        match path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_ascii_lowercase)
+            .as_deref()
+        {
+            Some("json") => Ok(Self::Json),
+            Some("csv") => Ok(Self::Csv),
+            _ => anyhow::bail!(
+                "{}: universe export path must end in .json or .csv",
+                path.display()
+            ),
+        }
+    }
+}
+
+/// The universe behind one `GenerateInput`, with the options that built it.
+#[derive(Debug, Serialize)]
+pub struct UniverseExport<'a> {
+    pub as_of_date: NaiveDate,
+    pub digest: String,
+    pub universe_size: usize,
+    /// Builder settings (size, oversample, ...); opaque to core.
+    pub options: serde_json::Value,
+    /// Serialized exactly as the prompt sees them, so `digest` can be recomputed from the file.
+    pub candidates: &'a [Candidate],
+}
+
+impl<'a> UniverseExport<'a> {
+    pub fn from_input(input: &'a GenerateInput, options: serde_json::Value) -> Self {
+        Self {
+            as_of_date: input.as_of_date,
+            digest: input.universe_digest(),
+            universe_size: input.universe_size(),
+            options,
+            candidates: &input.candidates,
+        }
+    }
+
+    pub fn render(&self, format: ExportFormat) -> anyhow::Result<String> {
+        match format {
+            ExportFormat::Json => {
+                serde_json::to_string_pretty(self).context("serialize universe export failed")
+            }
+            ExportFormat::Csv => Ok(candidates_csv(self.candidates)),
+        }
+    }
+}
+
+/// `candidate_universes.candidates` column value.
+pub fn candidates_json(candidates: &[Candidate]) -> anyhow::Result<serde_json::Value> {
+    serde_json::to_value(candidates).context("serialize candidates failed")
+}
+
+/// One row per candidate in universe order: `ticker,name,<feature>...` with a column for every
+/// feature key seen in the universe (sorted); missing features are left empty.
+pub fn candidates_csv(candidates: &[Candidate]) -> String {
+    let keys: BTreeSet<&str> = candidates
+        .iter()
+        .flat_map(|c| c.features.keys().map(String::as_str))
+        .collect();
+
+    let mut header = vec!["ticker".to_string(), "name".to_string()];
+    header.extend(keys.iter().map(|k| csv_field(k)));
+    let mut out = header.join(",");
+    out.push('\n');
+
+    for c in candidates {
+        let mut row = vec![csv_field(&c.ticker), csv_field(&c.name)];
+        row.extend(
+            keys.iter()
+                .map(|k| c.features.get(*k).map(f64::to_string).unwrap_or_default()),
+        );
+        out.push_str(&row.join(","));
+        out.push('\n');
+    }
+    out
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn candidate(ticker: &str, name: &str, features: &[(&str, f64)]) -> Candidate {
+        Candidate {
+            ticker: ticker.to_string(),
+            name: name.to_string(),
+            name_en: Some("ignored".to_string()),
+            sector: None,
+            features: features
+                .iter()
+                .map(|(k, v)| (k.to_string(), *v))
+                .collect::<BTreeMap<_, _>>(),
+        }
+    }
+
+    #[test]
+    fn format_follows_extension() {
+        assert_eq!(
+            ExportFormat::from_path(Path::new("out/u.JSON")).unwrap(),
+            ExportFormat::Json
+        );
+        assert_eq!(
+            ExportFormat::from_path(Path::new("u.csv")).unwrap(),
+            ExportFormat::Csv
+        );
+        let err = ExportFormat::from_path(Path::new("u.txt")).unwrap_err();
+        assert!(err.to_string().contains(".json or .csv"), "{err}");
+        assert!(ExportFormat::from_path(Path::new("universe")).is_err());
+    }
+
+    #[test]
+    fn csv_unions_feature_columns_and_quotes_names() {
+        let csv = candidates_csv(&[
+            candidate(
+                "KRX:005930",
+                "Samsung",
+                &[("ret_1d", 0.01), ("vol_20d", 0.2)],
+            ),
+            candidate("KRX:000660", "SK \"Hynix\", Inc", &[("mom_5d", 1.5)]),
+        ]);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "ticker,name,mom_5d,ret_1d,vol_20d");
+        assert_eq!(lines[1], "KRX:005930,Samsung,,0.01,0.2");
+        assert_eq!(lines[2], "KRX:000660,\"SK \"\"Hynix\"\", Inc\",1.5,,");
+        assert_eq!(lines.len(), 3);
+    }
+}
@@ -0,0 +1,353 @@
+use crate::ingest::error::IngestError;
+use crate::ingest::provider::{validate_item, DataProviderClient};
+use crate::ingest::types::{DailyFeatureItem, DailyFeaturesResponse, SCHEMA_VERSION};
+use anyhow::Context;
+use chrono::NaiveDate;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Malformed rows skipped per file before the file fails, unless
+/// `DATA_PROVIDER_CSV_MAX_BAD_ROWS` says otherwise.
+const DEFAULT_MAX_BAD_ROWS: usize = 10;
+/// Skipped rows listed (with their error) in the raw JSON; the count covers all of them.
+const MAX_LISTED_BAD_ROWS: usize = 20;
+
+/// Reads bulk-history CSVs from a directory, one `YYYY-MM-DD.csv` per date, for backfills.
+/// Columns are `ticker,name,trading_value` and then any number of numeric feature columns, each
+/// becoming a feature key (a `close` column fills the item's close instead); an empty cell leaves
+/// that feature out.
+#[derive(Debug, Clone)]
+pub struct CsvFileDataProvider {
+    dir: PathBuf,
+    max_bad_rows: usize,
+}
+
+/// A parsed CSV file: its items plus the rows skipped as malformed (`line N: reason`).
+#[derive(Debug)]
+struct CsvFile {
+    rows: usize,
+    items: Vec<DailyFeatureItem>,
+    bad_rows: Vec<String>,
+}
+
+impl CsvFileDataProvider {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        let max_bad_rows = std::env::var("DATA_PROVIDER_CSV_MAX_BAD_ROWS")
+            .ok()
+            .and_then(|s| s.parse::<usize>().ok())
+            .unwrap_or(DEFAULT_MAX_BAD_ROWS);
+        Self {
+            dir: dir.into(),
+            max_bad_rows,
+        }
+    }
+
+    pub fn with_max_bad_rows(mut self, max_bad_rows: usize) -> Self {
+        self.max_bad_rows = max_bad_rows;
+        self
+    }
+
+    fn path_for(&self, as_of_date: NaiveDate) -> PathBuf {
+        self.dir.join(format!("{as_of_date}.csv"))
+    }
+
+    /// Parses one file, skipping malformed rows (with a warning each) until more than
+    /// `max_bad_rows` have been seen.
+    fn parse(&self, path: &Path, text: &str) -> Result<CsvFile, IngestError> {
+        let file_name = path.display();
+        let mut lines = text
+            .trim_start_matches('\u{feff}')
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty());
+        let Some((_, header)) = lines.next() else {
+            return Err(IngestError::Parse(anyhow::anyhow!(
+                "{file_name}: empty file (expected a ticker,name,trading_value,... header)"
+            )));
+        };
+        let feature_keys = parse_header(header)
+            .with_context(|| format!("{file_name}: bad header"))
+            .map_err(IngestError::Parse)?;
+
+        let mut file = CsvFile {
+            rows: 0,
+            items: Vec::new(),
+            bad_rows: Vec::new(),
+        };
+        let mut tickers = HashSet::new();
+        for (idx, line) in lines {
+            file.rows += 1;
+            let lineno = idx + 1;
+            let item = parse_row(line, &feature_keys).and_then(|item| {
+                if tickers.insert(item.ticker.clone()) {
+                    Ok(item)
+                } else {
+                    Err(format!("duplicate ticker {}", item.ticker))
+                }
+            });
+            match item {
+                Ok(item) => file.items.push(item),
+                Err(err) => {
+                    let bad = format!("line {lineno}: {err}");
+                    file.bad_rows.push(bad.clone());
+                    if file.bad_rows.len() > self.max_bad_rows {
+                        return Err(IngestError::Validation(anyhow::anyhow!(
+                            "{file_name}: more than {} malformed rows (DATA_PROVIDER_CSV_MAX_BAD_ROWS); \
+                             last: {bad}",
+                            self.max_bad_rows
+                        )));
+                    }
+                    tracing::warn!(
+                        path = %file_name,
+                        line = lineno,
+                        skipped = file.bad_rows.len(),
+                        error = %err,
+                        "skipping malformed CSV row"
+                    );
+                }
+            }
+        }
+        Ok(file)
+    }
+}
+
+#[async_trait::async_trait]
+impl DataProviderClient for CsvFileDataProvider {
+    fn provider_name(&self) -> &'static str {
+        "csv"
+    }
+
+    async fn fetch_daily_features(
+        &self,
+        as_of_date: NaiveDate,
+    ) -> Result<(DailyFeaturesResponse, Value), IngestError> {
+        let path = self.path_for(as_of_date);
+        let text = std::fs::read_to_string(&path)
+            .with_context(|| format!("failed to read provider CSV {}", path.display()))
+            .map_err(IngestError::Io)?;
+        let file = self.parse(&path, &text)?;
+
+        let raw = serde_json::json!({
+            "source": "csv",
+            "path": path.display().to_string(),
+            "as_of_date": as_of_date,
+            "rows": file.rows,
+            "items": file.items.len(),
+            "skipped_rows": file.bad_rows.len(),
+            "skipped": file.bad_rows.iter().take(MAX_LISTED_BAD_ROWS).collect::<Vec<_>>(),
+        });
+        let resp = DailyFeaturesResponse {
+            schema_version: SCHEMA_VERSION,
+            as_of_date,
+            items: file.items,
+        };
+        Ok((resp, raw))
+    }
+}
+
+/// The feature keys: every column after `ticker,name,trading_value`, which must be unique and
+/// non-empty.
+fn parse_header(line: &str) -> anyhow::Result<Vec<String>> {
+    let columns = split_line(line).map_err(anyhow::Error::msg)?;
+    let fixed: Vec<String> = columns
+        .iter()
+        .take(3)
+        .map(|c| c.trim().to_ascii_lowercase())
+        .collect();
+    anyhow::ensure!(
+        fixed == ["ticker", "name", "trading_value"],
+        "expected ticker,name,trading_value first, got {:?}",
+        columns.iter().take(3).collect::<Vec<_>>()
+    );
+    let mut keys: Vec<String> = Vec::new();
+    for key in columns.iter().skip(3).map(|c| c.trim()) {
+        anyhow::ensure!(!key.is_empty(), "empty feature column name");
+        anyhow::ensure!(
+            !keys.iter().any(|k| k == key),
+            "duplicate feature column {key:?}"
+        );
+        keys.push(key.to_string());
+    }
+    Ok(keys)
+}
+
+fn parse_row(line: &str, feature_keys: &[String]) -> Result<DailyFeatureItem, String> {
+    let fields = split_line(line)?;
+    if fields.len() != feature_keys.len() + 3 {
+        return Err(format!(
+            "expected {} columns, got {}",
+            feature_keys.len() + 3,
+            fields.len()
+        ));
+    }
+    let trading_value = parse_number("trading_value", &fields[2])?;
+    let mut features = BTreeMap::new();
+    for (key, cell) in feature_keys.iter().zip(&fields[3..]) {
+        if let Some(v) = parse_number(key, cell)? {
+            features.insert(key.clone(), v);
+        }
+    }
+    let item = DailyFeatureItem {
+        ticker: fields[0].trim().to_string(),
+        name: fields[1].trim().to_string(),
+        name_en: None,
+        instrument_type: None,
+        sector: None,
+        trading_value,
+        close: features.remove("close"),
+        features,
+    };
+    validate_item(&item).map_err(|e| e.to_string())?;
+    Ok(item)
+}
+
+/// An empty cell is `None`; anything else must be a finite number.
+fn parse_number(column: &str, cell: &str) -> Result<Option<f64>, String> {
+    let cell = cell.trim();
+    if cell.is_empty() {
+        return Ok(None);
+    }
+    match cell.parse::<f64>() {
+        Ok(v) if v.is_finite() => Ok(Some(v)),
+        _ => Err(format!("{column} is not a number: {cell:?}")),
+    }
+}
+
+/// Splits one CSV line. Fields may be double-quoted (`""` for a literal quote) to hold commas;
+/// quoted newlines are not supported.
+fn split_line(line: &str) -> Result<Vec<String>, String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut chars = line.chars().peekable();
+    loop {
+        if field.is_empty() && chars.peek() == Some(&'"') {
+            chars.next();
+            loop {
+                match chars.next() {
+                    Some('"') if chars.peek() == Some(&'"') => {
+                        chars.next();
+                        field.push('"');
+                    }
+                    Some('"') => break,
+                    Some(c) => field.push(c),
+                    None => return Err("unterminated quoted field".to_string()),
+                }
+            }
+            if !matches!(chars.peek(), None | Some(',')) {
+                return Err("text after a closing quote".to_string());
+            }
+        }
+        match chars.next() {
+            Some(',') => fields.push(std::mem::take(&mut field)),
+            Some(c) => field.push(c),
+            None => {
+                fields.push(field);
+                return Ok(fields);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixtures() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata/csv")
+    }
+
+    fn d(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn splits_quoted_fields() {
+        assert_eq!(
+            split_line(r#"KRX:005930,"Samsung, ""SEC""",1.5,"#).unwrap(),
+            ["KRX:005930", "Samsung, \"SEC\"", "1.5", ""]
+        );
+        assert!(split_line(r#"a,"b"#).is_err());
+        assert!(split_line(r#"a,"b"c"#).is_err());
+    }
+
+    #[tokio::test]
+    async fn reads_the_file_for_the_date() {
+        let provider = CsvFileDataProvider::new(fixtures()).with_max_bad_rows(0);
+        let (resp, raw) = provider
+            .fetch_daily_features(d("2026-01-27"))
+            .await
+            .unwrap();
+        assert_eq!(resp.as_of_date, d("2026-01-27"));
+        let tickers: Vec<&str> = resp.items.iter().map(|i| i.ticker.as_str()).collect();
+        assert_eq!(tickers, ["KRX:005930", "KRX:000660", "KRX:035420"]);
+
+        let samsung = &resp.items[0];
+        assert_eq!(samsung.name, "Samsung Electronics Co., Ltd.");
+        assert_eq!(samsung.trading_value, Some(1.25e12));
+        assert_eq!(samsung.features["ret_1d"], 0.012);
+        assert_eq!(samsung.features["mom_20d"], -0.034);
+        // Empty cells leave the feature (and trading value) out.
+        assert!(!resp.items[1].features.contains_key("mom_20d"));
+        assert_eq!(resp.items[2].trading_value, None);
+
+        assert_eq!(raw["source"], "csv");
+        assert_eq!(raw["rows"], 3);
+        assert_eq!(raw["items"], 3);
+        assert_eq!(raw["skipped_rows"], 0);
+        assert!(raw["path"].as_str().unwrap().ends_with("2026-01-27.csv"));
+    }
+
+    #[tokio::test]
+    async fn malformed_rows_are_skipped_up_to_the_tolerance() {
+        let as_of = d("2026-01-28");
+        let (resp, raw) = CsvFileDataProvider::new(fixtures())
+            .with_max_bad_rows(4)
+            .fetch_daily_features(as_of)
+            .await
+            .unwrap();
+        assert_eq!(resp.items.len(), 2);
+        assert_eq!(raw["rows"], 6);
+        assert_eq!(raw["skipped_rows"], 4);
+        assert_eq!(
+            raw["skipped"],
+            serde_json::json!([
+                "line 4: ret_1d is not a number: \"n/a\"",
+                "line 5: expected 4 columns, got 3",
+                "line 6: features must be non-empty",
+                "line 7: duplicate ticker KRX:005930",
+            ])
+        );
+
+        let err = CsvFileDataProvider::new(fixtures())
+            .with_max_bad_rows(3)
+            .fetch_daily_features(as_of)
+            .await
+            .unwrap_err();
+        assert_eq!(err.variant_name(), "Validation");
+        assert!(
+            err.to_string().contains("more than 3 malformed rows"),
+            "{err}"
+        );
+    }
+
+    #[tokio::test]
+    async fn missing_file_and_bad_header() {
+        let provider = CsvFileDataProvider::new(fixtures());
+        let err = provider
+            .fetch_daily_features(d("2026-01-02"))
+            .await
+            .unwrap_err();
+        assert_eq!(err.variant_name(), "Io");
+
+        let err = provider
+            .parse(
+                Path::new("x.csv"),
+                "code,name,trading_value,ret_1d\nKRX:1,a,1,1",
+            )
+            .unwrap_err();
+        assert_eq!(err.variant_name(), "Parse");
+        assert!(err.to_string().contains("x.csv: bad header"), "{err}");
+        assert!(parse_header("ticker,name,trading_value,ret_1d,ret_1d").is_err());
+    }
+}
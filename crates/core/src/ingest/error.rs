@@ -0,0 +1,235 @@
+//! [`IngestError`]: what a [`DataProviderClient`](crate::ingest::provider::DataProviderClient)
+//! fetch fails with. Callers match on the variant to decide whether to retry or skip, and the
+//! ingest run's `error` column stores [`IngestError::run_error`] (`Variant: message`) so failure
+//! classes can be aggregated in SQL.
+
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::StatusCode;
+use std::time::Duration;
+
+/// Each variant keeps the underlying error, context chain included; it displays as that chain.
+#[derive(Debug, thiserror::Error)]
+pub enum IngestError {
+    /// Credentials were rejected (token issuance, HTTP 401/403); every date will fail alike.
+    #[error("{0:#}")]
+    Auth(anyhow::Error),
+    /// HTTP 429, with the provider's `Retry-After` when it sent one.
+    #[error("{error:#}")]
+    RateLimited {
+        retry_after: Option<Duration>,
+        error: anyhow::Error,
+    },
+    /// Any other non-2xx status.
+    #[error("{error:#}")]
+    Http { status: u16, error: anyhow::Error },
+    /// A body that is not valid JSON or does not have the expected shape.
+    #[error("{0:#}")]
+    Parse(anyhow::Error),
+    /// The provider answered but with too little data (e.g. a KIS run below its coverage or
+    /// failure-rate limits).
+    #[error("{0:#}")]
+    MissingData(anyhow::Error),
+    /// Data that parsed but breaks the contract: wrong as_of_date, empty tickers or features.
+    #[error("{0:#}")]
+    Validation(anyhow::Error),
+    /// Transport, timeout, database and file errors, and anything not classified above.
+    #[error("{0:#}")]
+    Io(anyhow::Error),
+}
+
+impl IngestError {
+    /// The variant's name, the prefix of [`Self::run_error`].
+    pub fn variant_name(&self) -> &'static str {
+        match self {
+            Self::Auth(_) => "Auth",
+            Self::RateLimited { .. } => "RateLimited",
+            Self::Http { .. } => "Http",
+            Self::Parse(_) => "Parse",
+            Self::MissingData(_) => "MissingData",
+            Self::Validation(_) => "Validation",
+            Self::Io(_) => "Io",
+        }
+    }
+
+    /// The underlying error.
+    pub fn inner(&self) -> &anyhow::Error {
+        match self {
+            Self::Auth(e)
+            | Self::RateLimited { error: e, .. }
+            | Self::Http { error: e, .. }
+            | Self::Parse(e)
+            | Self::MissingData(e)
+            | Self::Validation(e)
+            | Self::Io(e) => e,
+        }
+    }
+
+    /// Whether fetching again may succeed: rate limits, transport errors and 5xx. Bad
+    /// credentials, bad payloads and short data come back the same.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::RateLimited { .. } | Self::Io(_) => true,
+            Self::Http { status, .. } => *status >= 500,
+            Self::Auth(_) | Self::Parse(_) | Self::MissingData(_) | Self::Validation(_) => false,
+        }
+    }
+
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Self::RateLimited { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+
+    /// `Variant: message`, for the ingest run's `error` column.
+    pub fn run_error(&self) -> String {
+        format!("{}: {self}", self.variant_name())
+    }
+
+    /// The variant for a non-2xx `status`.
+    pub fn from_status(
+        status: StatusCode,
+        retry_after: Option<Duration>,
+        error: anyhow::Error,
+    ) -> Self {
+        match status {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Self::Auth(error),
+            StatusCode::TOO_MANY_REQUESTS => Self::RateLimited { retry_after, error },
+            _ => Self::Http {
+                status: status.as_u16(),
+                error,
+            },
+        }
+    }
+
+    /// Classifies an untyped error by what its chain contains. An `IngestError` already in the
+    /// chain keeps its variant; reqwest errors go by status, serde_json errors are `Parse`, and
+    /// the rest `Io`. The whole chain stays the message.
+    pub fn classify(error: anyhow::Error) -> Self {
+        let mut build: Option<Builder> = None;
+        for cause in error.chain() {
+            if let Some(e) = cause.downcast_ref::<IngestError>() {
+                build = Some(e.same_variant());
+            } else if let Some(e) = cause.downcast_ref::<reqwest::Error>() {
+                build = match e.status() {
+                    Some(status) => Some(Box::new(move |e| Self::from_status(status, None, e))),
+                    None if e.is_decode() => Some(Box::new(Self::Parse)),
+                    None => None,
+                };
+            } else if cause.is::<serde_json::Error>() {
+                build = Some(Box::new(Self::Parse));
+            } else {
+                continue;
+            }
+            break;
+        }
+        match build {
+            Some(build) => build(error),
+            None => Self::Io(error),
+        }
+    }
+
+    /// Builds this variant (same status and `Retry-After`) around another error.
+    fn same_variant(&self) -> Builder {
+        match *self {
+            Self::Auth(_) => Box::new(Self::Auth),
+            Self::RateLimited { retry_after, .. } => {
+                Box::new(move |error| Self::RateLimited { retry_after, error })
+            }
+            Self::Http { status, .. } => Box::new(move |error| Self::Http { status, error }),
+            Self::Parse(_) => Box::new(Self::Parse),
+            Self::MissingData(_) => Box::new(Self::MissingData),
+            Self::Validation(_) => Box::new(Self::Validation),
+            Self::Io(_) => Box::new(Self::Io),
+        }
+    }
+}
+
+type Builder = Box<dyn FnOnce(anyhow::Error) -> IngestError>;
+
+/// A `Retry-After` given in seconds; HTTP-date values are ignored (callers fall back to backoff).
+pub fn parse_retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let secs = headers
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse::<f64>()
+        .ok()?;
+    (secs.is_finite() && secs >= 0.0).then(|| Duration::from_secs_f64(secs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn run_error_is_variant_then_chain() {
+        let err = IngestError::from_status(
+            StatusCode::BAD_GATEWAY,
+            None,
+            anyhow::anyhow!("upstream down").context("data provider HTTP 502"),
+        );
+        assert_eq!(err.variant_name(), "Http");
+        assert_eq!(
+            err.run_error(),
+            "Http: data provider HTTP 502: upstream down"
+        );
+        assert!(err.is_retryable());
+    }
+
+    #[test]
+    fn statuses_map_to_variants() {
+        let e = || anyhow::anyhow!("x");
+        let wait = Some(Duration::from_secs(3));
+        assert!(matches!(
+            IngestError::from_status(StatusCode::UNAUTHORIZED, None, e()),
+            IngestError::Auth(_)
+        ));
+        let limited = IngestError::from_status(StatusCode::TOO_MANY_REQUESTS, wait, e());
+        assert_eq!(limited.retry_after(), wait);
+        assert!(limited.is_retryable());
+        let not_found = IngestError::from_status(StatusCode::NOT_FOUND, wait, e());
+        assert!(matches!(not_found, IngestError::Http { status: 404, .. }));
+        assert!(!not_found.is_retryable());
+        assert_eq!(not_found.retry_after(), None);
+    }
+
+    #[test]
+    fn classify_keeps_a_typed_variant_under_context() {
+        let typed: anyhow::Error = IngestError::Auth(anyhow::anyhow!("KIS token HTTP 403")).into();
+        let err = IngestError::classify(typed.context("fetch KIS universe"));
+        assert_eq!(err.variant_name(), "Auth");
+        assert_eq!(
+            err.run_error(),
+            "Auth: fetch KIS universe: KIS token HTTP 403"
+        );
+        assert!(!err.is_retryable());
+    }
+
+    #[test]
+    fn classify_untyped_errors() {
+        let json = serde_json::from_str::<serde_json::Value>("{").unwrap_err();
+        let err = IngestError::classify(anyhow::Error::new(json).context("parse master"));
+        assert_eq!(err.variant_name(), "Parse");
+
+        let io = std::io::Error::new(std::io::ErrorKind::NotFound, "gone");
+        let err = IngestError::classify(Err::<(), _>(io).context("read").unwrap_err());
+        assert_eq!(err.variant_name(), "Io");
+        assert!(err.is_retryable());
+    }
+
+    #[test]
+    fn retry_after_seconds_only() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(parse_retry_after(&headers), None);
+        headers.insert(RETRY_AFTER, "2".parse().unwrap());
+        assert_eq!(parse_retry_after(&headers), Some(Duration::from_secs(2)));
+        headers.insert(
+            RETRY_AFTER,
+            "Wed, 21 Oct 2026 07:28:00 GMT".parse().unwrap(),
+        );
+        assert_eq!(parse_retry_after(&headers), None);
+    }
+}
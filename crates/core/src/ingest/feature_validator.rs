@@ -0,0 +1,484 @@
+//! [`FeatureValidator`]: sanity checks on provider feature values before they are stored.
+//! Non-finite values are always dropped; keys in [`RULES`] must also fall in a plausible range,
+//! and an out-of-range value is either clamped to the bound or dropped, per rule. A payload with
+//! too many offending rows (`FEATURE_MAX_VIOLATION_RATE`) fails the ingest as a whole.
+
+use crate::ingest::types::DailyFeatureItem;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Share of a payload's rows that may need a clamp or rejection when
+/// `FEATURE_MAX_VIOLATION_RATE` is unset.
+pub const DEFAULT_MAX_VIOLATION_RATE: f64 = 0.05;
+
+/// Offending values listed in the summary, beyond which only the counts grow.
+const MAX_EXAMPLES: usize = 20;
+
+/// What happens to a value outside its rule's range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnViolation {
+    /// Pinned to the bound it crossed.
+    Clamp,
+    /// Dropped from the row.
+    Reject,
+}
+
+/// The plausible range of one feature key; either bound may be infinite.
+#[derive(Debug, Clone, Copy)]
+pub struct FeatureRule {
+    pub key: &'static str,
+    pub min: f64,
+    pub max: f64,
+    pub below: OnViolation,
+    pub above: OnViolation,
+    /// Rows flagged with a nonzero `is_new_listing` feature skip this rule (a listing-day
+    /// return is measured from the offer price and is not capped like later days).
+    pub skip_new_listings: bool,
+}
+
+/// The registry. `trading_value` also covers [`DailyFeatureItem::trading_value`].
+pub const RULES: &[FeatureRule] = &[
+    // KRX caps daily moves at 30%; past 50% the previous close is wrong (a missed split, say).
+    FeatureRule {
+        key: "ret_1d",
+        min: -0.5,
+        max: 0.5,
+        below: OnViolation::Reject,
+        above: OnViolation::Reject,
+        skip_new_listings: true,
+    },
+    // Loss-makers have no PER rather than a negative one; huge ratios carry no more signal.
+    FeatureRule {
+        key: "per",
+        min: 0.0,
+        max: 10_000.0,
+        below: OnViolation::Reject,
+        above: OnViolation::Clamp,
+        skip_new_listings: false,
+    },
+    FeatureRule {
+        key: "pbr",
+        min: 0.0,
+        max: 1_000.0,
+        below: OnViolation::Reject,
+        above: OnViolation::Clamp,
+        skip_new_listings: false,
+    },
+    FeatureRule {
+        key: "trading_value",
+        min: 0.0,
+        max: f64::INFINITY,
+        below: OnViolation::Reject,
+        above: OnViolation::Reject,
+        skip_new_listings: false,
+    },
+    FeatureRule {
+        key: "volume",
+        min: 0.0,
+        max: f64::INFINITY,
+        below: OnViolation::Reject,
+        above: OnViolation::Reject,
+        skip_new_listings: false,
+    },
+];
+
+/// The outcome for one value.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Verdict {
+    Keep,
+    Clamp(f64),
+    Reject,
+}
+
+/// Checks one value against `rule` (or only for finiteness without one).
+pub fn check_value(rule: Option<&FeatureRule>, value: f64, new_listing: bool) -> Verdict {
+    if !value.is_finite() {
+        return Verdict::Reject;
+    }
+    let Some(rule) = rule.filter(|r| !(r.skip_new_listings && new_listing)) else {
+        return Verdict::Keep;
+    };
+    let (bound, action) = if value < rule.min {
+        (rule.min, rule.below)
+    } else if value > rule.max {
+        (rule.max, rule.above)
+    } else {
+        return Verdict::Keep;
+    };
+    match action {
+        OnViolation::Clamp => Verdict::Clamp(bound),
+        OnViolation::Reject => Verdict::Reject,
+    }
+}
+
+/// Clamps and rejections per key (`trading_value` counts the field and the feature together).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct KeyViolations {
+    pub clamped: usize,
+    pub rejected: usize,
+}
+
+/// What [`FeatureValidator::apply`] changed; stored in the ingest run's raw JSON as
+/// `feature_validation`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ValidationSummary {
+    pub rows: usize,
+    /// Rows with at least one clamped or rejected value.
+    pub rows_with_violations: usize,
+    pub clamped: usize,
+    pub rejected: usize,
+    pub by_key: BTreeMap<String, KeyViolations>,
+    /// The first offending values, as `ticker key=value (clamped|rejected)`.
+    pub examples: Vec<String>,
+    pub max_violation_rate: f64,
+}
+
+impl ValidationSummary {
+    pub fn violation_rate(&self) -> f64 {
+        if self.rows == 0 {
+            0.0
+        } else {
+            self.rows_with_violations as f64 / self.rows as f64
+        }
+    }
+
+    fn record(&mut self, ticker: &str, key: &str, value: f64, verdict: Verdict) {
+        let clamped = match verdict {
+            Verdict::Keep => return,
+            Verdict::Clamp(_) => true,
+            Verdict::Reject => false,
+        };
+        let counts = self.by_key.entry(key.to_string()).or_default();
+        let label = if clamped {
+            self.clamped += 1;
+            counts.clamped += 1;
+            "clamped"
+        } else {
+            self.rejected += 1;
+            counts.rejected += 1;
+            "rejected"
+        };
+        if self.examples.len() < MAX_EXAMPLES {
+            self.examples
+                .push(format!("{ticker} {key}={value} ({label})"));
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FeatureValidator {
+    rules: &'static [FeatureRule],
+    max_violation_rate: f64,
+}
+
+impl Default for FeatureValidator {
+    fn default() -> Self {
+        Self {
+            rules: RULES,
+            max_violation_rate: DEFAULT_MAX_VIOLATION_RATE,
+        }
+    }
+}
+
+impl FeatureValidator {
+    /// [`RULES`] with `FEATURE_MAX_VIOLATION_RATE` (default 0.05).
+    pub fn from_env() -> Self {
+        let max_violation_rate = std::env::var("FEATURE_MAX_VIOLATION_RATE")
+            .ok()
+            .and_then(|s| s.parse::<f64>().ok())
+            .filter(|r| (0.0..=1.0).contains(r))
+            .unwrap_or(DEFAULT_MAX_VIOLATION_RATE);
+        Self::default().with_max_violation_rate(max_violation_rate)
+    }
+
+    pub fn with_max_violation_rate(mut self, max_violation_rate: f64) -> Self {
+        self.max_violation_rate = max_violation_rate;
+        self
+    }
+
+    fn rule(&self, key: &str) -> Option<&FeatureRule> {
+        self.rules.iter().find(|r| r.key == key)
+    }
+
+    /// Clamps or drops offending values in place and counts what it did.
+    pub fn apply(&self, items: &mut [DailyFeatureItem]) -> ValidationSummary {
+        let mut summary = ValidationSummary {
+            rows: items.len(),
+            max_violation_rate: self.max_violation_rate,
+            ..Default::default()
+        };
+        for item in items.iter_mut() {
+            let new_listing = item
+                .features
+                .get("is_new_listing")
+                .is_some_and(|v| *v != 0.0);
+            let before = summary.clamped + summary.rejected;
+
+            if let Some(value) = item.trading_value {
+                let verdict = check_value(self.rule("trading_value"), value, new_listing);
+                summary.record(&item.ticker, "trading_value", value, verdict);
+                item.trading_value = match verdict {
+                    Verdict::Keep => Some(value),
+                    Verdict::Clamp(v) => Some(v),
+                    Verdict::Reject => None,
+                };
+            }
+            let ticker = item.ticker.as_str();
+            item.features.retain(|key, value| {
+                let verdict = check_value(self.rule(key), *value, new_listing);
+                summary.record(ticker, key, *value, verdict);
+                match verdict {
+                    Verdict::Keep => true,
+                    Verdict::Clamp(v) => {
+                        *value = v;
+                        true
+                    }
+                    Verdict::Reject => false,
+                }
+            });
+
+            if summary.clamped + summary.rejected > before {
+                summary.rows_with_violations += 1;
+            }
+        }
+        summary
+    }
+
+    /// Errors when more than the allowed share of rows needed a fix.
+    pub fn check(&self, summary: &ValidationSummary) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            summary.violation_rate() <= self.max_violation_rate,
+            "{} of {} rows ({:.1}%) had implausible feature values, above \
+             FEATURE_MAX_VIOLATION_RATE={} ({} clamped, {} rejected; e.g. {})",
+            summary.rows_with_violations,
+            summary.rows,
+            summary.violation_rate() * 100.0,
+            self.max_violation_rate,
+            summary.clamped,
+            summary.rejected,
+            summary
+                .examples
+                .iter()
+                .take(3)
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        Ok(())
+    }
+
+    /// [`Self::apply`] then [`Self::check`], recording the summary under `feature_validation`
+    /// in `raw` (when it is an object) and logging any fixes.
+    pub fn validate(
+        &self,
+        items: &mut [DailyFeatureItem],
+        raw: &mut serde_json::Value,
+    ) -> anyhow::Result<ValidationSummary> {
+        let summary = self.apply(items);
+        if let Some(obj) = raw.as_object_mut() {
+            obj.insert(
+                "feature_validation".to_string(),
+                serde_json::to_value(&summary).unwrap_or_default(),
+            );
+        }
+        if summary.rows_with_violations > 0 {
+            tracing::warn!(
+                rows = summary.rows,
+                rows_with_violations = summary.rows_with_violations,
+                clamped = summary.clamped,
+                rejected = summary.rejected,
+                examples = ?summary.examples,
+                "implausible feature values at ingest"
+            );
+        }
+        self.check(&summary)?;
+        Ok(summary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn rule(key: &str) -> Option<&'static FeatureRule> {
+        RULES.iter().find(|r| r.key == key)
+    }
+
+    fn item(
+        ticker: &str,
+        trading_value: Option<f64>,
+        features: &[(&str, f64)],
+    ) -> DailyFeatureItem {
+        DailyFeatureItem {
+            ticker: ticker.to_string(),
+            name: ticker.to_string(),
+            name_en: None,
+            instrument_type: None,
+            sector: None,
+            trading_value,
+            close: None,
+            features: features.iter().map(|(k, v)| (k.to_string(), *v)).collect(),
+        }
+    }
+
+    #[test]
+    fn non_finite_values_are_always_rejected() {
+        for value in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            for key in ["ret_1d", "per", "mom_5d"] {
+                assert_eq!(check_value(rule(key), value, false), Verdict::Reject);
+                assert_eq!(check_value(rule(key), value, true), Verdict::Reject);
+            }
+            assert_eq!(check_value(None, value, false), Verdict::Reject);
+        }
+    }
+
+    #[test]
+    fn unruled_keys_keep_any_finite_value() {
+        assert_eq!(rule("mom_5d").map(|r| r.key), None);
+        for value in [-1e300, -3.0, 0.0, 42.0, 1e300] {
+            assert_eq!(check_value(None, value, false), Verdict::Keep);
+        }
+    }
+
+    #[test]
+    fn ret_1d_is_bounded_except_for_new_listings() {
+        let r = rule("ret_1d");
+        for value in [-0.5, -0.3, 0.0, 0.3, 0.5] {
+            assert_eq!(check_value(r, value, false), Verdict::Keep);
+        }
+        assert_eq!(check_value(r, 0.5001, false), Verdict::Reject);
+        assert_eq!(check_value(r, -0.51, false), Verdict::Reject);
+        assert_eq!(check_value(r, 3.0, true), Verdict::Keep);
+        assert_eq!(check_value(r, -0.9, true), Verdict::Keep);
+    }
+
+    #[test]
+    fn valuation_ratios_reject_negatives_and_clamp_at_the_cap() {
+        let per = rule("per");
+        assert_eq!(check_value(per, 0.0, false), Verdict::Keep);
+        assert_eq!(check_value(per, 12.5, false), Verdict::Keep);
+        assert_eq!(check_value(per, 10_000.0, false), Verdict::Keep);
+        assert_eq!(check_value(per, -3.2, false), Verdict::Reject);
+        assert_eq!(check_value(per, 250_000.0, false), Verdict::Clamp(10_000.0));
+        // The new-listing flag only lifts ret_1d.
+        assert_eq!(check_value(per, -3.2, true), Verdict::Reject);
+
+        let pbr = rule("pbr");
+        assert_eq!(check_value(pbr, 0.8, false), Verdict::Keep);
+        assert_eq!(check_value(pbr, -0.1, false), Verdict::Reject);
+        assert_eq!(check_value(pbr, 5_000.0, false), Verdict::Clamp(1_000.0));
+    }
+
+    #[test]
+    fn trading_value_and_volume_must_be_non_negative() {
+        for key in ["trading_value", "volume"] {
+            assert_eq!(check_value(rule(key), 0.0, false), Verdict::Keep);
+            assert_eq!(check_value(rule(key), 1e15, false), Verdict::Keep);
+            assert_eq!(check_value(rule(key), -1.0, false), Verdict::Reject);
+        }
+    }
+
+    #[test]
+    fn rules_have_unique_keys_and_ordered_bounds() {
+        let mut keys: Vec<&str> = RULES.iter().map(|r| r.key).collect();
+        keys.sort_unstable();
+        keys.dedup();
+        assert_eq!(keys.len(), RULES.len());
+        assert!(RULES.iter().all(|r| r.min <= r.max));
+    }
+
+    #[test]
+    fn apply_fixes_rows_in_place_and_counts() {
+        let mut items = vec![
+            item("KRX:000001", Some(1e9), &[("ret_1d", 0.01), ("per", 12.0)]),
+            item(
+                "KRX:000002",
+                Some(-5.0),
+                &[("ret_1d", 0.9), ("per", 1e6), ("mom_5d", f64::NAN)],
+            ),
+            item(
+                "KRX:000003",
+                None,
+                &[("ret_1d", 1.5), ("is_new_listing", 1.0)],
+            ),
+            item("KRX:000004", Some(2e9), &[("volume", -1.0)]),
+        ];
+        let summary = FeatureValidator::default()
+            .with_max_violation_rate(1.0)
+            .apply(&mut items);
+
+        assert_eq!(items[0].features["per"], 12.0);
+        assert_eq!(items[0].trading_value, Some(1e9));
+        assert_eq!(items[1].trading_value, None);
+        assert_eq!(
+            items[1].features,
+            BTreeMap::from([("per".to_string(), 10_000.0)])
+        );
+        assert_eq!(items[2].features["ret_1d"], 1.5);
+        assert!(items[3].features.is_empty());
+
+        assert_eq!(summary.rows, 4);
+        assert_eq!(summary.rows_with_violations, 2);
+        assert_eq!((summary.clamped, summary.rejected), (1, 4));
+        assert_eq!(
+            summary.by_key["per"],
+            KeyViolations {
+                clamped: 1,
+                rejected: 0
+            }
+        );
+        assert_eq!(summary.by_key["trading_value"].rejected, 1);
+        assert_eq!(summary.by_key["mom_5d"].rejected, 1);
+        // Keys that were fine are not listed.
+        assert!(!summary.by_key.contains_key("is_new_listing"));
+        assert_eq!(
+            summary.examples[0],
+            "KRX:000002 trading_value=-5 (rejected)"
+        );
+        assert_eq!(summary.violation_rate(), 0.5);
+    }
+
+    #[test]
+    fn the_violation_rate_decides_whether_the_payload_fails() {
+        let mut rows: Vec<DailyFeatureItem> = (0..20)
+            .map(|i| item(&format!("KRX:{i:06}"), Some(1.0), &[("ret_1d", 0.01)]))
+            .collect();
+        rows[0].features.insert("ret_1d".to_string(), f64::INFINITY);
+        let mut raw = json!({"as_of_date": "2026-01-27"});
+
+        // 1 of 20 rows is exactly the default 5%.
+        let summary = FeatureValidator::default()
+            .validate(&mut rows.clone(), &mut raw)
+            .unwrap();
+        assert_eq!(summary.rows_with_violations, 1);
+        assert_eq!(raw["feature_validation"]["rejected"], 1);
+        assert_eq!(raw["feature_validation"]["max_violation_rate"], 0.05);
+
+        rows[1].features.insert("per".to_string(), -1.0);
+        let err = FeatureValidator::default()
+            .validate(&mut rows.clone(), &mut raw)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("2 of 20 rows (10.0%)"), "{err}");
+        assert!(err.contains("FEATURE_MAX_VIOLATION_RATE=0.05"), "{err}");
+        assert!(err.contains("KRX:000000 ret_1d=inf (rejected)"), "{err}");
+        // The summary is recorded even when the payload fails.
+        assert_eq!(raw["feature_validation"]["rows_with_violations"], 2);
+
+        FeatureValidator::default()
+            .with_max_violation_rate(0.1)
+            .validate(&mut rows, &mut raw)
+            .unwrap();
+        assert!(!rows[1].features.contains_key("per"));
+    }
+
+    #[test]
+    fn empty_payloads_pass() {
+        let summary = FeatureValidator::default()
+            .with_max_violation_rate(0.0)
+            .validate(&mut [], &mut json!(null))
+            .unwrap();
+        assert_eq!(summary.violation_rate(), 0.0);
+    }
+}
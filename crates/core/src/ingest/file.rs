@@ -0,0 +1,124 @@
+use crate::ingest::error::IngestError;
+use crate::ingest::provider::{check_schema_version, validate_item, DataProviderClient};
+use crate::ingest::types::DailyFeaturesResponse;
+use anyhow::Context;
+use chrono::NaiveDate;
+use serde_json::Value;
+use std::path::PathBuf;
+
+/// Reads a saved `DailyFeaturesResponse` JSON payload from disk, for replaying historical
+/// provider responses (e.g. in staging).
+#[derive(Debug, Clone)]
+pub struct FileDataProvider {
+    path: PathBuf,
+}
+
+impl FileDataProvider {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    fn parse(
+        &self,
+        text: &str,
+        as_of_date: NaiveDate,
+    ) -> Result<(DailyFeaturesResponse, Value), IngestError> {
+        let path = self.path.display();
+        let raw_json = serde_json::from_str::<Value>(text)
+            .with_context(|| format!("{path}: not valid JSON"))
+            .map_err(IngestError::Parse)?;
+        let parsed = serde_json::from_value::<DailyFeaturesResponse>(raw_json.clone())
+            .with_context(|| format!("{path}: does not match DailyFeaturesResponse"))
+            .map_err(IngestError::Parse)?;
+
+        check_schema_version(parsed.schema_version)
+            .with_context(|| path.to_string())
+            .map_err(IngestError::Validation)?;
+        if parsed.as_of_date != as_of_date {
+            return Err(IngestError::Validation(anyhow::anyhow!(
+                "{path}: as_of_date mismatch: expected {as_of_date}, got {}",
+                parsed.as_of_date
+            )));
+        }
+        for (idx, item) in parsed.items.iter().enumerate() {
+            validate_item(item)
+                .with_context(|| format!("{path}: items[{idx}] (ticker {:?})", item.ticker))
+                .map_err(IngestError::Validation)?;
+        }
+
+        Ok((parsed, raw_json))
+    }
+}
+
+#[async_trait::async_trait]
+impl DataProviderClient for FileDataProvider {
+    fn provider_name(&self) -> &'static str {
+        "file"
+    }
+
+    async fn fetch_daily_features(
+        &self,
+        as_of_date: NaiveDate,
+    ) -> Result<(DailyFeaturesResponse, Value), IngestError> {
+        let text = std::fs::read_to_string(&self.path)
+            .with_context(|| format!("failed to read provider file {}", self.path.display()))
+            .map_err(IngestError::Io)?;
+        self.parse(&text, as_of_date)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn names_file_and_item_index_on_validation_errors() {
+        let as_of = NaiveDate::from_ymd_opt(2026, 1, 27).unwrap();
+        let provider = FileDataProvider::new("replay/2026-01-27.json");
+        let payload = json!({
+            "as_of_date": as_of,
+            "items": [
+                {"ticker": "KRX:005930", "name": "Samsung", "trading_value": 1.0, "features": {"ret_1d": 0.01}},
+                {"ticker": "KRX:000660", "name": " ", "trading_value": 1.0, "features": {"ret_1d": 0.02}}
+            ]
+        })
+        .to_string();
+
+        let err = provider.parse(&payload, as_of).unwrap_err();
+        assert_eq!(err.variant_name(), "Validation");
+        let msg = format!("{err:#}");
+        assert!(msg.contains("replay/2026-01-27.json"), "{msg}");
+        assert!(msg.contains("items[1]"), "{msg}");
+        assert!(msg.contains("name must be non-empty"), "{msg}");
+
+        let other_day = NaiveDate::from_ymd_opt(2026, 1, 28).unwrap();
+        let err = provider.parse(&payload, other_day).unwrap_err();
+        assert!(err.to_string().contains("as_of_date mismatch"), "{err}");
+
+        let mut v99: serde_json::Value = serde_json::from_str(&payload).unwrap();
+        v99["schema_version"] = json!("99.0");
+        let err = provider.parse(&v99.to_string(), as_of).unwrap_err();
+        assert_eq!(err.variant_name(), "Validation");
+        assert!(err.to_string().contains("schema_version 99.0"), "{err}");
+
+        let err = provider.parse("{", as_of).unwrap_err();
+        assert!(matches!(err, IngestError::Parse(_)), "{err}");
+    }
+
+    #[test]
+    fn parses_a_valid_payload() {
+        let as_of = NaiveDate::from_ymd_opt(2026, 1, 27).unwrap();
+        let payload = json!({
+            "as_of_date": as_of,
+            "items": [
+                {"ticker": "KRX:005930", "name": "Samsung", "trading_value": 1.0, "features": {"ret_1d": 0.01}}
+            ]
+        });
+        let (resp, raw) = FileDataProvider::new("x.json")
+            .parse(&payload.to_string(), as_of)
+            .unwrap();
+        assert_eq!(resp.items.len(), 1);
+        assert_eq!(raw, payload);
+    }
+}
@@ -0,0 +1,260 @@
+//! Multi-day features from a stock's recent daily closes: `mom_5d`, `mom_20d`, `vol_20d` and
+//! `ma20_gap`. Windows count bars, not calendar days, so market holidays and missing days just
+//! stretch them; halted days are dropped first because their flat, volume-less bars would read as
+//! zero returns. `pct_off_52w_high`/`pct_off_52w_low` come from the quote's 52-week range
+//! instead, since the bar window is far shorter than a year.
+
+use chrono::NaiveDate;
+use std::collections::BTreeMap;
+
+/// Calendar days of history requested per stock: enough for 21 bars (a 20-day return) across a
+/// long holiday.
+pub const HISTORY_CALENDAR_DAYS: i64 = 45;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DailyClose {
+    pub date: NaiveDate,
+    pub close: f64,
+    /// `None` when the source did not report it.
+    pub volume: Option<f64>,
+}
+
+/// Bars up to and including `as_of_date`, oldest first, without halted days (zero volume) or
+/// non-positive closes. One bar per date; a duplicate keeps the last one given.
+pub fn trading_closes(bars: &[DailyClose], as_of_date: NaiveDate) -> Vec<DailyClose> {
+    let mut by_date = BTreeMap::new();
+    for bar in bars {
+        if bar.date <= as_of_date && bar.close > 0.0 && bar.volume != Some(0.0) {
+            by_date.insert(bar.date, *bar);
+        }
+    }
+    by_date.into_values().collect()
+}
+
+/// History features for `as_of_date`. Each is present only when the as-of bar exists and there are
+/// enough earlier bars for it, so recent listings get a subset (or nothing).
+pub fn history_features(bars: &[DailyClose], as_of_date: NaiveDate) -> BTreeMap<String, f64> {
+    let mut out = BTreeMap::new();
+    let series = trading_closes(bars, as_of_date);
+    if series.last().map(|b| b.date) != Some(as_of_date) {
+        return out;
+    }
+    let closes: Vec<f64> = series.iter().map(|b| b.close).collect();
+
+    if let Some(v) = momentum(&closes, 5) {
+        out.insert("mom_5d".to_string(), v);
+    }
+    if let Some(v) = momentum(&closes, 20) {
+        out.insert("mom_20d".to_string(), v);
+    }
+    if let Some(v) = volatility(&closes, 20) {
+        out.insert("vol_20d".to_string(), v);
+    }
+    if let Some(v) = ma_gap(&closes, 20) {
+        out.insert("ma20_gap".to_string(), v);
+    }
+    out
+}
+
+/// Distance of `close` from the 52-week high and low: `close / high - 1` (at most 0) and
+/// `close / low - 1` (at least 0). A close outside the reported range (a backfill against a
+/// later quote) counts as the new extreme. A missing or non-positive bound leaves its key out;
+/// a recent listing's range simply covers the days since listing.
+pub fn week52_features(close: f64, high: Option<f64>, low: Option<f64>) -> BTreeMap<String, f64> {
+    let mut out = BTreeMap::new();
+    if !(close.is_finite() && close > 0.0) {
+        return out;
+    }
+    if let Some(high) = high.filter(|v| v.is_finite() && *v > 0.0) {
+        out.insert(
+            "pct_off_52w_high".to_string(),
+            (close / high - 1.0).min(0.0),
+        );
+    }
+    if let Some(low) = low.filter(|v| v.is_finite() && *v > 0.0) {
+        out.insert("pct_off_52w_low".to_string(), (close / low - 1.0).max(0.0));
+    }
+    out
+}
+
+/// Return over the last `days` bars: `close[t] / close[t - days] - 1`.
+pub fn momentum(closes: &[f64], days: usize) -> Option<f64> {
+    let last = *closes.last()?;
+    let base = *closes.get(closes.len().checked_sub(days + 1)?)?;
+    Some(last / base - 1.0)
+}
+
+/// Sample standard deviation of the last `days` close-to-close returns (daily, not annualized).
+pub fn volatility(closes: &[f64], days: usize) -> Option<f64> {
+    if days < 2 {
+        return None;
+    }
+    let window = &closes[closes.len().checked_sub(days + 1)?..];
+    let returns: Vec<f64> = window.windows(2).map(|w| w[1] / w[0] - 1.0).collect();
+    let mean = returns.iter().sum::<f64>() / days as f64;
+    let var = returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (days - 1) as f64;
+    Some(var.sqrt())
+}
+
+/// Distance of the last close from the mean of the last `days` closes: `close / ma - 1`.
+pub fn ma_gap(closes: &[f64], days: usize) -> Option<f64> {
+    if days == 0 {
+        return None;
+    }
+    let window = &closes[closes.len().checked_sub(days)?..];
+    let ma = window.iter().sum::<f64>() / days as f64;
+    Some(closes.last()? / ma - 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn d(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 3, 1).unwrap() + chrono::Duration::days(day as i64)
+    }
+
+    /// One bar per listed day offset, with volume.
+    fn bars(days_and_closes: &[(u32, f64)]) -> Vec<DailyClose> {
+        days_and_closes
+            .iter()
+            .map(|&(day, close)| DailyClose {
+                date: d(day),
+                close,
+                volume: Some(1_000.0),
+            })
+            .collect()
+    }
+
+    fn approx(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-12
+    }
+
+    #[test]
+    fn momentum_counts_bars_across_gaps() {
+        // Days 3 and 4 are missing (weekend); the window still spans six bars.
+        let series = bars(&[
+            (0, 100.0),
+            (1, 101.0),
+            (2, 102.0),
+            (5, 103.0),
+            (6, 104.0),
+            (7, 110.0),
+        ]);
+        let f = history_features(&series, d(7));
+        assert!(approx(f["mom_5d"], 0.1));
+        assert!(!f.contains_key("mom_20d"));
+        assert!(!f.contains_key("vol_20d"));
+        assert!(!f.contains_key("ma20_gap"));
+    }
+
+    #[test]
+    fn full_window_yields_all_features() {
+        // 21 bars alternating +1%/-1% around 100, newest first as KIS returns them.
+        let mut closes = vec![100.0];
+        for i in 0..20 {
+            let last = *closes.last().unwrap();
+            closes.push(if i % 2 == 0 { last * 1.01 } else { last * 0.99 });
+        }
+        let mut series: Vec<_> = closes
+            .iter()
+            .enumerate()
+            .map(|(i, c)| DailyClose {
+                date: d(i as u32),
+                close: *c,
+                volume: Some(1.0),
+            })
+            .collect();
+        series.reverse();
+
+        let f = history_features(&series, d(20));
+        assert!(approx(f["mom_20d"], closes[20] / closes[0] - 1.0));
+        assert!(approx(f["mom_5d"], closes[20] / closes[15] - 1.0));
+        // Returns are exactly +-1%: mean 0, sample std sqrt(20 * 0.0001 / 19).
+        assert!(approx(f["vol_20d"], (20.0 * 0.0001_f64 / 19.0).sqrt()));
+        let ma: f64 = closes[1..].iter().sum::<f64>() / 20.0;
+        assert!(approx(f["ma20_gap"], closes[20] / ma - 1.0));
+    }
+
+    #[test]
+    fn halted_days_are_dropped() {
+        let mut series = bars(&[(0, 100.0), (1, 100.0), (2, 100.0), (3, 100.0), (4, 100.0)]);
+        // Halted for two days at the last close, then a real bar.
+        for day in [5, 6] {
+            series.push(DailyClose {
+                date: d(day),
+                close: 100.0,
+                volume: Some(0.0),
+            });
+        }
+        series.extend(bars(&[(7, 120.0)]));
+
+        let kept: Vec<_> = trading_closes(&series, d(7))
+            .iter()
+            .map(|b| b.date)
+            .collect();
+        assert_eq!(kept, [d(0), d(1), d(2), d(3), d(4), d(7)]);
+        assert!(approx(history_features(&series, d(7))["mom_5d"], 0.2));
+    }
+
+    #[test]
+    fn recent_listing_or_missing_as_of_bar_yields_nothing() {
+        let series = bars(&[(0, 100.0), (1, 101.0)]);
+        assert!(history_features(&series, d(1)).is_empty());
+        // No bar for the as-of date (halted today): no stale features.
+        let series = bars(&[
+            (0, 100.0),
+            (1, 101.0),
+            (2, 102.0),
+            (3, 103.0),
+            (4, 104.0),
+            (5, 105.0),
+        ]);
+        assert!(history_features(&series, d(6)).is_empty());
+        // Bars after the as-of date are ignored.
+        assert!(history_features(&series, d(4)).is_empty());
+        assert!(history_features(&series, d(5)).contains_key("mom_5d"));
+    }
+
+    #[test]
+    fn week52_distance_from_the_range() {
+        let f = week52_features(80.0, Some(100.0), Some(50.0));
+        assert!(approx(f["pct_off_52w_high"], -0.2));
+        assert!(approx(f["pct_off_52w_low"], 0.6));
+
+        // At the high; a recent listing whose range is a single price.
+        let f = week52_features(100.0, Some(100.0), Some(50.0));
+        assert_eq!(f["pct_off_52w_high"], 0.0);
+        let f = week52_features(100.0, Some(100.0), Some(100.0));
+        assert_eq!(f["pct_off_52w_high"], 0.0);
+        assert_eq!(f["pct_off_52w_low"], 0.0);
+
+        // A close above a stale quote's high is the new high.
+        assert_eq!(
+            week52_features(120.0, Some(100.0), Some(50.0))["pct_off_52w_high"],
+            0.0
+        );
+    }
+
+    #[test]
+    fn week52_missing_or_zero_bounds_are_left_out() {
+        let f = week52_features(80.0, None, Some(0.0));
+        assert!(f.is_empty());
+        let f = week52_features(80.0, Some(100.0), None);
+        assert_eq!(f.keys().collect::<Vec<_>>(), ["pct_off_52w_high"]);
+        assert!(week52_features(0.0, Some(100.0), Some(50.0)).is_empty());
+        assert!(week52_features(80.0, Some(f64::NAN), Some(f64::INFINITY)).is_empty());
+    }
+
+    #[test]
+    fn helpers_need_enough_bars() {
+        assert!(approx(momentum(&[100.0, 110.0], 1).unwrap(), 0.1));
+        assert_eq!(momentum(&[100.0], 1), None);
+        assert_eq!(volatility(&[100.0, 101.0], 1), None);
+        assert_eq!(ma_gap(&[100.0, 110.0], 3), None);
+        assert!(approx(
+            ma_gap(&[100.0, 110.0], 2).unwrap(),
+            110.0 / 105.0 - 1.0
+        ));
+    }
+}
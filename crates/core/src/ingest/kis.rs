@@ -1,16 +1,49 @@
 use crate::config::Settings;
-use crate::ingest::types::{DailyFeatureItem, DailyFeaturesResponse};
+use crate::ingest::error::IngestError;
+use crate::ingest::feature_validator::FeatureValidator;
+use crate::ingest::history::{
+    history_features, week52_features, DailyClose, HISTORY_CALENDAR_DAYS,
+};
+use crate::ingest::rate_limit::RateLimiter;
+use crate::ingest::types::{DailyFeatureItem, DailyFeaturesResponse, SCHEMA_VERSION};
+use crate::storage::market_index::MarketIndexDaily;
 use anyhow::{Context, Result};
 use chrono::{Datelike, NaiveDate, TimeZone, Utc};
 use encoding_rs::EUC_KR;
+use futures::StreamExt;
 use reqwest::header::{HeaderMap, HeaderValue};
 use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 use std::time::Duration;
 
 const PROD_BASE_URL: &str = "https://openapi.koreainvestment.com:9443";
+/// Per-stock requests per second when neither `KIS_RATE_LIMIT_PER_SEC` nor `KIS_REQ_DELAY_MS` is
+/// set (KIS allows 20/s on production accounts).
+const DEFAULT_RATE_LIMIT_PER_SEC: f64 = 10.0;
+/// Per-attempt quotation timeout when `KIS_PER_REQUEST_TIMEOUT_MS` is unset; the HTTP client's
+/// 30s timeout stays as a backstop.
+const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_millis(8000);
+/// Share of a run's stocks that may fail (after the retry pass) when `KIS_MAX_FAILURE_RATE` is
+/// unset.
+const DEFAULT_MAX_FAILURE_RATE: f64 = 0.05;
+/// Wait before re-fetching stocks whose as-of bar was missing when `KIS_LATE_DATA_RETRY_DELAY_SECS`
+/// is unset.
+const DEFAULT_LATE_DATA_RETRY_DELAY: Duration = Duration::from_secs(300);
+/// Daily-chart pages fetched per stock when `KIS_CHART_MAX_PAGES` is unset.
+const DEFAULT_CHART_MAX_PAGES: usize = 5;
+/// Sector index codes charted once per ingest for the LLM's market context.
+const MARKET_INDICES: [(&str, &str); 2] = [("KOSPI", "0001"), ("KOSDAQ", "1001")];
+/// Calendar days of index bars requested: enough to find the previous close across a long
+/// holiday.
+const INDEX_CALENDAR_DAYS: i64 = 10;
+/// How long a worker waits for another process to finish issuing a token: a bit over KIS's
+/// one-issuance-per-minute limit.
+const TOKEN_LOCK_WAIT: Duration = Duration::from_secs(90);
+const TOKEN_LOCK_POLL: Duration = Duration::from_millis(250);
 
 const KOSPI_MASTER_ZIP: &str =
     "https://new.real.download.dws.co.kr/common/master/kospi_code.mst.zip";
@@ -25,8 +58,32 @@ pub struct KisClient {
     base_url: String,
     appkey: String,
     appsecret: String,
-    req_delay: Duration,
+    // Token bucket shared by all concurrent per-stock fetches (KIS_RATE_LIMIT_PER_SEC).
+    limiter: Arc<RateLimiter>,
+    // Per-stock fetches in flight at once (KIS_CONCURRENCY).
+    concurrency: usize,
+    // Per-attempt limit on a quotation request (KIS_PER_REQUEST_TIMEOUT_MS).
+    request_timeout: Duration,
+    // Quotation attempts that hit `request_timeout` since the client was built; a fetch reports
+    // the ones it added.
+    request_timeouts: AtomicUsize,
+    // Above this share of failed stocks the ingest errors instead of returning (KIS_MAX_FAILURE_RATE).
+    max_failure_rate: f64,
+    // Range checks on the fetched features (FEATURE_MAX_VIOLATION_RATE).
+    feature_validator: FeatureValidator,
+    // Wait before re-fetching stocks missing the as-of bar; zero disables that pass
+    // (KIS_LATE_DATA_RETRY_DELAY_SECS).
+    late_data_retry_delay: Duration,
+    // Fewer rows than this for a market fail the ingest (KIS_MIN_ITEMS_KOSPI/_KOSDAQ).
+    min_items: BTreeMap<KisMarket, usize>,
+    // Most daily-chart pages followed per stock (KIS_CHART_MAX_PAGES).
+    chart_max_pages: usize,
+    // Also fetch foreign/institutional net buying per stock (KIS_FETCH_INVESTOR_FLOWS); doubles
+    // the request count.
+    investor_flows: bool,
     markets: Vec<KisMarket>,
+    // Cap on tickers fetched (from KIS_MAX_TICKERS, or `with_max_tickers`).
+    max_tickers: Option<usize>,
 
     // Cache token within a single process run to avoid repeated token issuance.
     token_cache: tokio::sync::Mutex<Option<CachedToken>>,
@@ -34,6 +91,9 @@ pub struct KisClient {
     // Optional persistent token cache in DB (recommended for CI runners).
     db_pool: Option<sqlx::PgPool>,
     token_env_key: String,
+
+    // Checkpoint progress in `kis_ingest_progress` and skip tickers a previous run finished.
+    resume: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -42,25 +102,127 @@ struct CachedToken {
     fetched_at: chrono::DateTime<chrono::Utc>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum KisMarket {
     Kospi,
     Kosdaq,
     Konex,
 }
 
+impl KisMarket {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Kospi => "KOSPI",
+            Self::Kosdaq => "KOSDAQ",
+            Self::Konex => "KONEX",
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl crate::ingest::provider::DataProviderClient for KisClient {
+    fn provider_name(&self) -> &'static str {
+        "kis"
+    }
+
+    /// An access token, cached or freshly issued: proves the host is up and the keys work.
+    async fn health_check(&self) -> Result<(), IngestError> {
+        self.get_access_token_cached()
+            .await
+            .map(drop)
+            .map_err(ingest_error)
+    }
+
+    async fn fetch_daily_features(
+        &self,
+        as_of_date: NaiveDate,
+    ) -> Result<(DailyFeaturesResponse, Value), IngestError> {
+        let (mut resp, mut raw) = self
+            .fetch_daily_features_krx(as_of_date)
+            .await
+            .map_err(ingest_error)?;
+        self.feature_validator
+            .validate(&mut resp.items, &mut raw)
+            .map_err(IngestError::Validation)?;
+        Ok((resp, raw))
+    }
+}
+
+/// [`IngestError`] for a failed KIS ingest: an incomplete run is `MissingData` (the worker takes
+/// the partial [`KisIngestIncomplete`] back out of it); the rest is classified by its chain.
+fn ingest_error(err: anyhow::Error) -> IngestError {
+    if err.downcast_ref::<KisIngestIncomplete>().is_some() {
+        IngestError::MissingData(err)
+    } else {
+        IngestError::classify(err)
+    }
+}
+
 impl KisClient {
     pub fn from_settings_prod(_settings: &Settings) -> Result<Self> {
-        let appkey = std::env::var("KIS_APPKEY").context("KIS_APPKEY is required")?;
-        let appsecret = std::env::var("KIS_APPSECRET").context("KIS_APPSECRET is required")?;
+        let appkey = crate::config::env_secret("KIS_APPKEY")?
+            .context("KIS_APPKEY (or KIS_APPKEY_FILE) is required")?;
+        let appsecret = crate::config::env_secret("KIS_APPSECRET")?
+            .context("KIS_APPSECRET (or KIS_APPSECRET_FILE) is required")?;
 
         let base_url = std::env::var("KIS_BASE_URL").unwrap_or_else(|_| PROD_BASE_URL.to_string());
-        let req_delay_ms = std::env::var("KIS_REQ_DELAY_MS")
+        // KIS_REQ_DELAY_MS (the old fixed spacing) still sets the rate when the limit is unset.
+        let rate_per_sec = std::env::var("KIS_RATE_LIMIT_PER_SEC")
+            .ok()
+            .and_then(|s| s.parse::<f64>().ok())
+            .or_else(|| {
+                std::env::var("KIS_REQ_DELAY_MS")
+                    .ok()
+                    .and_then(|s| s.parse::<u64>().ok())
+                    .filter(|ms| *ms > 0)
+                    .map(|ms| 1000.0 / ms as f64)
+            })
+            .filter(|r| r.is_finite() && *r > 0.0)
+            .unwrap_or(DEFAULT_RATE_LIMIT_PER_SEC);
+        let concurrency = std::env::var("KIS_CONCURRENCY")
+            .ok()
+            .and_then(|s| s.parse::<usize>().ok())
+            .unwrap_or(4)
+            .max(1);
+        let request_timeout = std::env::var("KIS_PER_REQUEST_TIMEOUT_MS")
             .ok()
             .and_then(|s| s.parse::<u64>().ok())
-            .unwrap_or(150);
+            .filter(|ms| *ms > 0)
+            .map(Duration::from_millis)
+            .unwrap_or(DEFAULT_REQUEST_TIMEOUT);
+        let max_failure_rate = std::env::var("KIS_MAX_FAILURE_RATE")
+            .ok()
+            .and_then(|s| s.trim().parse::<f64>().ok())
+            .filter(|r| (0.0..=1.0).contains(r))
+            .unwrap_or(DEFAULT_MAX_FAILURE_RATE);
+        let late_data_retry_delay = std::env::var("KIS_LATE_DATA_RETRY_DELAY_SECS")
+            .ok()
+            .and_then(|s| s.trim().parse::<u64>().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_LATE_DATA_RETRY_DELAY);
+        let mut min_items = BTreeMap::new();
+        for market in [KisMarket::Kospi, KisMarket::Kosdaq] {
+            let min = std::env::var(format!("KIS_MIN_ITEMS_{}", market.label()))
+                .ok()
+                .and_then(|s| s.trim().parse::<usize>().ok());
+            if let Some(min) = min {
+                min_items.insert(market, min);
+            }
+        }
+        let chart_max_pages = std::env::var("KIS_CHART_MAX_PAGES")
+            .ok()
+            .and_then(|s| s.trim().parse::<usize>().ok())
+            .filter(|n| *n > 0)
+            .unwrap_or(DEFAULT_CHART_MAX_PAGES);
+        let investor_flows = std::env::var("KIS_FETCH_INVESTOR_FLOWS")
+            .ok()
+            .map(|s| matches!(s.trim().to_ascii_lowercase().as_str(), "1" | "true"))
+            .unwrap_or(false);
 
         let markets = parse_markets(std::env::var("KIS_MARKETS").ok());
+        let max_tickers = std::env::var("KIS_MAX_TICKERS")
+            .ok()
+            .and_then(|s| s.parse::<usize>().ok());
 
         let http = reqwest::Client::builder()
             .timeout(Duration::from_secs(30))
@@ -72,103 +234,402 @@ impl KisClient {
             base_url,
             appkey,
             appsecret,
-            req_delay: Duration::from_millis(req_delay_ms),
+            limiter: Arc::new(RateLimiter::new(rate_per_sec)),
+            concurrency,
+            request_timeout,
+            request_timeouts: AtomicUsize::new(0),
+            max_failure_rate,
+            feature_validator: FeatureValidator::from_env(),
+            late_data_retry_delay,
+            min_items,
+            chart_max_pages,
+            investor_flows,
             markets,
+            max_tickers,
             token_cache: tokio::sync::Mutex::new(None),
             db_pool: None,
             token_env_key: "prod".to_string(),
+            resume: false,
         })
     }
 
+    /// Caps the universe at `max` tickers, overriding `KIS_MAX_TICKERS`.
+    pub fn with_max_tickers(mut self, max: usize) -> Self {
+        self.max_tickers = Some(max);
+        self
+    }
+
     pub fn with_db_pool(mut self, pool: sqlx::PgPool) -> Self {
         self.db_pool = Some(pool);
         self
     }
 
+    /// Makes the fetch resumable (needs [`Self::with_db_pool`]): tickers checkpointed for the date
+    /// by an earlier run are skipped, and fetched items are run through the feature validator,
+    /// upserted into stock_features_daily and checkpointed every `KIS_CHECKPOINT_EVERY` (default
+    /// 100) items. The returned response holds only this run's items; `raw["resumed"]` counts the
+    /// skipped tickers.
+    pub fn with_resume(mut self) -> Self {
+        self.resume = true;
+        self
+    }
+
     pub async fn fetch_daily_features_krx(
         &self,
         as_of_date: NaiveDate,
     ) -> Result<(DailyFeaturesResponse, Value)> {
         let token = self.get_access_token_cached().await?;
-
-        let mut items = Vec::new();
-        let mut failures: usize = 0;
-        let mut logged_failures: usize = 0;
         let mut universe = self.fetch_master_universe().await?;
+        let mut coverage: BTreeMap<KisMarket, MarketCoverage> = self
+            .markets
+            .iter()
+            .map(|m| (*m, MarketCoverage::default()))
+            .collect();
+        for stock in &universe {
+            coverage.entry(stock.market).or_default().master += 1;
+        }
 
-        let max_tickers = std::env::var("KIS_MAX_TICKERS")
-            .ok()
-            .and_then(|s| s.parse::<usize>().ok());
-        if let Some(max) = max_tickers {
+        if let Some(max) = self.max_tickers {
             if universe.len() > max {
                 universe.truncate(max);
             }
         }
 
+        let checkpoint = match (self.resume, self.db_pool.as_ref()) {
+            (false, _) => None,
+            (true, Some(pool)) => Some(pool),
+            (true, None) => anyhow::bail!("resumable KIS ingest needs a database pool"),
+        };
+        let mut resumed: usize = 0;
+        if let Some(pool) = checkpoint {
+            let before = universe.clone();
+            resumed = skip_checkpointed(pool, as_of_date, &mut universe).await?;
+            let remaining: std::collections::HashSet<&str> =
+                universe.iter().map(|s| s.code.as_str()).collect();
+            for stock in before
+                .iter()
+                .filter(|s| !remaining.contains(s.code.as_str()))
+            {
+                coverage.entry(stock.market).or_default().resumed += 1;
+            }
+            tracing::info!(
+                %as_of_date,
+                resumed,
+                remaining = universe.len(),
+                "resuming KIS ingest from checkpoint"
+            );
+        }
+
+        let total = universe.len();
+        let market_of: std::collections::HashMap<String, KisMarket> = universe
+            .iter()
+            .map(|s| (s.code.clone(), s.market))
+            .collect();
+        let (items, stats) = self
+            .fetch_universe_daily(&token, universe, as_of_date, checkpoint)
+            .await;
+        let indices = self.fetch_market_indices(&token, as_of_date).await;
+        if let (Some(pool), false) = (self.db_pool.as_ref(), indices.is_empty()) {
+            if let Err(e) = crate::storage::market_index::upsert(pool, &indices, "kis").await {
+                tracing::warn!(%as_of_date, error = %e, "failed to store market index closes");
+            }
+        }
+
+        for item in &items {
+            let code = item.ticker.trim_start_matches("KRX:");
+            if let Some(market) = market_of.get(code) {
+                coverage.entry(*market).or_default().items += 1;
+            }
+        }
+        for failed in &stats.failed {
+            if let Some(market) = market_of.get(&failed.ticker) {
+                coverage.entry(*market).or_default().failures += 1;
+            }
+        }
+        let shortfalls = coverage_shortfalls(&coverage, &self.min_items);
+        for (market, c) in &coverage {
+            tracing::info!(
+                %as_of_date,
+                market = market.label(),
+                master = c.master,
+                resumed = c.resumed,
+                items = c.items,
+                failures = c.failures,
+                "KIS ingest market coverage"
+            );
+        }
+
+        let raw = serde_json::json!({
+            "source": "kis",
+            "base_url": self.base_url,
+            "as_of_date": as_of_date,
+            "items": items.len(),
+            "failures": stats.failures,
+            "timeouts": stats.timeouts,
+            "timeout_failures": stats.timeout_failures,
+            "no_data": stats.no_data,
+            "retried": stats.retried,
+            "recovered": stats.recovered,
+            "late_data_retried": stats.late_retried,
+            "late_data_recovered": stats.late_recovered,
+            "failed": stats.failed,
+            "max_failure_rate": self.max_failure_rate,
+            "markets": by_label(&coverage),
+            "min_items": by_label(&self.min_items),
+            "resumed": resumed,
+            "market_indices": indices,
+            "generated_at": Utc::now(),
+        });
+
+        let reason = if !shortfalls.is_empty() {
+            Some(IncompleteReason::Coverage(shortfalls))
+        } else if exceeds_failure_rate(stats.failures, total, self.max_failure_rate) {
+            Some(IncompleteReason::FailureRate {
+                total,
+                max: self.max_failure_rate,
+            })
+        } else {
+            None
+        };
+        if let Some(reason) = reason {
+            return Err(KisIngestIncomplete {
+                as_of_date,
+                reason,
+                failures: stats.failures,
+                items,
+                raw,
+            }
+            .into());
+        }
+
+        let resp = DailyFeaturesResponse {
+            schema_version: SCHEMA_VERSION,
+            as_of_date,
+            items,
+        };
+        Ok((resp, raw))
+    }
+
+    /// Fetches every stock in `universe`, up to `concurrency` at a time, then re-attempts the
+    /// failed ones once more and, after `KIS_LATE_DATA_RETRY_DELAY_SECS`, the ones whose as-of bar
+    /// was missing. Returns the items sorted by ticker plus counts of the stocks
+    /// skipped. With `checkpoint`, items are also upserted and checkpointed in batches as they
+    /// arrive.
+    async fn fetch_universe_daily(
+        &self,
+        token: &KisToken,
+        universe: Vec<KisMasterRecord>,
+        as_of_date: NaiveDate,
+        checkpoint: Option<&sqlx::PgPool>,
+    ) -> (Vec<DailyFeatureItem>, FetchStats) {
+        let mut items = Vec::new();
+        let mut stats = FetchStats::default();
+        let mut logged_failures: usize = 0;
+        let timeouts_before = self.request_timeouts.load(Ordering::Relaxed);
+        let checkpoint_every = std::env::var("KIS_CHECKPOINT_EVERY")
+            .ok()
+            .and_then(|s| s.parse::<usize>().ok())
+            .unwrap_or(100)
+            .max(1);
+        let mut unsaved: usize = 0;
+
         let total = universe.len();
         let progress_every = std::env::var("KIS_PROGRESS_EVERY")
             .ok()
             .and_then(|s| s.parse::<usize>().ok())
             .unwrap_or(200);
 
-        // Fetch previous business day as start date to compute ret_1d.
-        let start_date = previous_business_day(as_of_date);
-        let start = start_date.format("%Y%m%d").to_string();
+        // One request per stock covers ret_1d (previous business day) and the multi-day history
+        // features (the endpoint returns up to 100 bars).
+        let prev_date = previous_business_day(as_of_date);
+        let start = (as_of_date - chrono::Duration::days(HISTORY_CALENDAR_DAYS))
+            .format("%Y%m%d")
+            .to_string();
         let end = as_of_date.format("%Y%m%d").to_string();
 
-        for (idx, stock) in universe.into_iter().enumerate() {
-            if idx != 0 {
-                tokio::time::sleep(self.req_delay).await;
-            }
-
-            match self
-                .fetch_one_stock_daily_features(
-                    &token, &stock, &start, &end, start_date, as_of_date,
-                )
-                .await
-            {
-                Ok(item) => items.push(item),
-                Err(err) => {
-                    failures += 1;
-                    if logged_failures < 10 {
-                        tracing::warn!(
-                            idx,
-                            ticker = %stock.code,
-                            name = %stock.name,
-                            failure_count = failures,
-                            error = %err,
-                            "KIS daily fetch failed; skipping stock"
-                        );
-                        logged_failures += 1;
+        // Failed stocks by code, with their latest failure; the retry pass starts from these.
+        let mut failed: BTreeMap<String, (KisMasterRecord, FailedTicker)> = BTreeMap::new();
+        // Stocks missing the as-of bar, held for the late-data pass while it is enabled.
+        let mut late: BTreeMap<String, (KisMasterRecord, FailedTicker)> = BTreeMap::new();
+        let late_delay = self.late_data_retry_delay;
+        let mut pending = universe;
+        for pass in [Pass::Sweep, Pass::Retry, Pass::LateData] {
+            match pass {
+                Pass::Sweep => {}
+                Pass::Retry => {
+                    if failed.is_empty() {
+                        continue;
                     }
+                    pending = failed.values().map(|(stock, _)| stock.clone()).collect();
+                    stats.retried = pending.len();
+                    tracing::info!(%as_of_date, retrying = pending.len(), "retrying failed KIS stocks once");
                 }
-            }
-
-            if progress_every != 0 {
-                let n = idx + 1;
-                if n == 1 || n == total || (n % progress_every == 0) {
+                Pass::LateData => {
+                    if late.is_empty() {
+                        continue;
+                    }
+                    pending = late.values().map(|(stock, _)| stock.clone()).collect();
+                    stats.late_retried = pending.len();
                     tracing::info!(
-                        processed = n,
-                        total,
-                        items = items.len(),
-                        failures,
                         %as_of_date,
-                        "KIS ingest progress"
+                        retrying = pending.len(),
+                        delay_secs = late_delay.as_secs(),
+                        "waiting to re-fetch KIS stocks missing the as-of bar"
                     );
+                    tokio::time::sleep(late_delay).await;
+                }
+            }
+
+            let mut fetches =
+                futures::stream::iter(std::mem::take(&mut pending).into_iter().enumerate())
+                    .map(|(idx, stock)| {
+                        let (start, end) = (&start, &end);
+                        async move {
+                            let result = self
+                                .fetch_one_stock_daily_features(
+                                    token, &stock, start, end, prev_date, as_of_date,
+                                )
+                                .await;
+                            (idx, stock, result)
+                        }
+                    })
+                    .buffer_unordered(self.concurrency);
+
+            let mut processed: usize = 0;
+            while let Some((idx, stock, result)) = fetches.next().await {
+                processed += 1;
+                let earlier = failed
+                    .remove(&stock.code)
+                    .or_else(|| late.remove(&stock.code))
+                    .map(|(_, f)| f.attempts)
+                    .unwrap_or(0);
+                match result {
+                    Ok(item) => {
+                        match pass {
+                            Pass::Sweep => {}
+                            Pass::Retry => stats.recovered += 1,
+                            Pass::LateData => stats.late_recovered += 1,
+                        }
+                        items.push(item);
+                        unsaved += 1;
+                        if let Some(pool) = checkpoint.filter(|_| unsaved >= checkpoint_every) {
+                            let batch = &items[items.len() - unsaved..];
+                            self.save_checkpoint(pool, as_of_date, batch).await;
+                            unsaved = 0;
+                        }
+                    }
+                    Err(err) if err.downcast_ref::<KisNoData>().is_some() => {
+                        stats.no_data += 1;
+                        tracing::debug!(ticker = %stock.code, error = %err, "KIS has no data; skipping stock");
+                    }
+                    Err(err) => {
+                        let (class, attempts) = classify_failure(&err);
+                        let failure = FailedTicker {
+                            ticker: stock.code.clone(),
+                            class,
+                            attempts: earlier + attempts,
+                        };
+                        let late_data = class == FailureClass::MissingAsOfBar
+                            && pass != Pass::LateData
+                            && !late_delay.is_zero();
+                        if late_data {
+                            tracing::debug!(ticker = %stock.code, "KIS has no as-of bar yet; re-fetching after the late-data delay");
+                            late.insert(stock.code.clone(), (stock, failure));
+                        } else {
+                            if pass == Pass::Sweep && logged_failures < 10 {
+                                tracing::warn!(
+                                    idx,
+                                    ticker = %stock.code,
+                                    name = %stock.name,
+                                    failure_count = failed.len() + 1,
+                                    ?class,
+                                    error = %err,
+                                    "KIS daily fetch failed; retrying after the sweep"
+                                );
+                                logged_failures += 1;
+                            } else if pass != Pass::Sweep {
+                                tracing::debug!(ticker = %stock.code, ?class, error = %err, "KIS daily fetch failed again; skipping stock");
+                            }
+                            failed.insert(stock.code.clone(), (stock, failure));
+                        }
+                    }
+                }
+
+                if pass == Pass::Sweep && progress_every != 0 {
+                    let n = processed;
+                    if n == 1 || n == total || n.is_multiple_of(progress_every) {
+                        let rate_per_sec = (self.limiter.rate().await * 10.0).round() / 10.0;
+                        tracing::info!(
+                            processed = n,
+                            total,
+                            items = items.len(),
+                            failures = failed.len(),
+                            late_data = late.len(),
+                            no_data = stats.no_data,
+                            timeouts = self.request_timeouts.load(Ordering::Relaxed)
+                                - timeouts_before,
+                            rate_per_sec,
+                            %as_of_date,
+                            "KIS ingest progress"
+                        );
+                    }
                 }
             }
         }
 
-        let raw = serde_json::json!({
-            "source": "kis",
-            "base_url": self.base_url,
-            "as_of_date": as_of_date,
-            "items": items.len(),
-            "failures": failures,
-            "generated_at": Utc::now(),
-        });
+        if let Some(pool) = checkpoint {
+            self.save_checkpoint(pool, as_of_date, &items[items.len() - unsaved..])
+                .await;
+        }
+
+        stats.failed = failed.into_values().map(|(_, f)| f).collect();
+        stats.failures = stats.failed.len();
+        stats.timeouts = self.request_timeouts.load(Ordering::Relaxed) - timeouts_before;
+        stats.timeout_failures = stats
+            .failed
+            .iter()
+            .filter(|f| f.class == FailureClass::Timeout)
+            .count();
+        if stats.retried > 0 || stats.late_retried > 0 {
+            tracing::info!(
+                %as_of_date,
+                retried = stats.retried,
+                recovered = stats.recovered,
+                late_data_retried = stats.late_retried,
+                late_data_recovered = stats.late_recovered,
+                failures = stats.failures,
+                timeouts = stats.timeouts,
+                timeout_failures = stats.timeout_failures,
+                "KIS retry passes finished"
+            );
+        }
 
-        Ok((DailyFeaturesResponse { as_of_date, items }, raw))
+        // Completion order is arbitrary with concurrent fetches.
+        items.sort_by(|a, b| a.ticker.cmp(&b.ticker));
+        (items, stats)
+    }
+
+    /// Checkpoints a batch with the feature validator's clamps and rejections applied, since a
+    /// resumed run skips the batch's tickers and the final validation never sees them again.
+    /// The batch itself is left as fetched: the whole payload's violation rate is still checked
+    /// once the fetch returns. A failed checkpoint only costs resumability (the items are still
+    /// returned and upserted by the caller), so it is logged rather than failing the ingest.
+    async fn save_checkpoint(
+        &self,
+        pool: &sqlx::PgPool,
+        as_of_date: NaiveDate,
+        items: &[DailyFeatureItem],
+    ) {
+        let mut items = items.to_vec();
+        self.feature_validator.apply(&mut items);
+        match crate::storage::kis_progress::checkpoint(pool, as_of_date, &items).await {
+            Ok(_) => {
+                tracing::debug!(%as_of_date, items = items.len(), "KIS ingest checkpoint saved")
+            }
+            Err(err) => {
+                tracing::warn!(%as_of_date, items = items.len(), error = %err, "KIS ingest checkpoint failed")
+            }
+        }
     }
 
     async fn get_access_token_cached(&self) -> Result<KisToken> {
@@ -194,19 +655,96 @@ impl KisClient {
         }
 
         let fetched_at = chrono::Utc::now();
-        let token = self.fetch_access_token().await?;
+        let token = match self.db_pool.as_ref() {
+            Some(pool) => self.issue_token_locked(pool).await?,
+            None => self.fetch_access_token().await?,
+        };
         *guard = Some(CachedToken {
             token: token.clone(),
             fetched_at,
         });
+        Ok(token)
+    }
 
-        if let Some(pool) = self.db_pool.as_ref() {
+    /// Issues a token under the `kis_token` advisory lock, so concurrent workers sharing the DB
+    /// cache do not invalidate each other's tokens (KIS allows one issuance per minute per app
+    /// key). Whoever waited re-reads the row the lock holder saved instead of issuing again.
+    async fn issue_token_locked(&self, pool: &sqlx::PgPool) -> Result<KisToken> {
+        let env = self.token_env_key.as_str();
+        let mut conn = pool
+            .acquire()
+            .await
+            .context("acquire connection for KIS token lock failed")?;
+        let deadline = tokio::time::Instant::now() + TOKEN_LOCK_WAIT;
+        while !crate::storage::lock::try_acquire_kis_token_lock_conn(&mut conn, env).await? {
+            anyhow::ensure!(
+                tokio::time::Instant::now() < deadline,
+                "timed out after {TOKEN_LOCK_WAIT:?} waiting for the KIS token lock (env={env})"
+            );
+            tokio::time::sleep(TOKEN_LOCK_POLL).await;
+        }
+
+        let result = async {
+            if let Some(tok) = load_token_from_db(pool, env).await? {
+                if !tok.is_expired_or_stale(chrono::Utc::now()) {
+                    tracing::debug!(env, "reusing KIS token issued by another process");
+                    return Ok(tok);
+                }
+            }
+            let token = self.fetch_access_token().await?;
             // Best-effort: do not fail ingestion if token persistence fails.
-            if let Err(err) = save_token_to_db(pool, &self.token_env_key, &token).await {
+            if let Err(err) = save_token_to_db(pool, env, &token).await {
                 tracing::warn!(error = %err, "failed to persist KIS access token to DB");
             }
+            Ok(token)
         }
-        Ok(token)
+        .await;
+
+        if let Err(err) = crate::storage::lock::release_kis_token_lock_conn(&mut conn, env).await {
+            // The lock goes with the session; make sure the connection is not reused holding it.
+            tracing::warn!(error = %err, "failed to release KIS token lock; closing connection");
+            let _ = conn.detach();
+        }
+        result
+    }
+
+    /// Revokes the current token (`/oauth2/revokeP`) and forgets it, in memory and in the DB
+    /// cache. A no-op when there is no token.
+    pub async fn revoke_token(&self) -> Result<()> {
+        let mut guard = self.token_cache.lock().await;
+        let mut token = guard.take().map(|cached| cached.token);
+        if let Some(pool) = self.db_pool.as_ref() {
+            if token.is_none() {
+                token = load_token_from_db(pool, &self.token_env_key).await?;
+            }
+            delete_token_from_db(pool, &self.token_env_key).await?;
+        }
+        let Some(token) = token else {
+            return Ok(());
+        };
+
+        let url = format!("{}/oauth2/revokeP", self.base_url.trim_end_matches('/'));
+        let res = self
+            .http
+            .post(url)
+            .header("Content-Type", "application/json")
+            .json(&serde_json::json!({
+                "appkey": self.appkey,
+                "appsecret": self.appsecret,
+                "token": token.access_token,
+            }))
+            .send()
+            .await
+            .context("KIS token revoke request failed")?;
+        let status = res.status();
+        let text = res
+            .text()
+            .await
+            .context("failed to read KIS token revoke response")?;
+        if !status.is_success() {
+            anyhow::bail!("KIS token revoke HTTP {status}: {text}");
+        }
+        Ok(())
     }
 
     async fn fetch_access_token(&self) -> Result<KisToken> {
@@ -234,7 +772,21 @@ impl KisClient {
             .await
             .context("failed to read KIS token response")?;
         if !status.is_success() {
-            anyhow::bail!("KIS token HTTP {status}: {text}");
+            let err = anyhow::anyhow!("KIS token HTTP {status}: {text}");
+            return Err(IngestError::from_status(status, None, err).into());
+        }
+        let kis_status = serde_json::from_str::<KisStatus>(&text).unwrap_or_default();
+        let err = || anyhow::anyhow!("KIS token error {}", kis_status.describe());
+        match kis_status.outcome() {
+            KisOutcome::Ok => {}
+            KisOutcome::RateLimited => {
+                return Err(IngestError::RateLimited {
+                    retry_after: None,
+                    error: err(),
+                }
+                .into())
+            }
+            KisOutcome::NoData | KisOutcome::Failed => return Err(IngestError::Auth(err()).into()),
         }
 
         serde_json::from_str::<KisToken>(&text).context("failed to parse KIS token response")
@@ -248,23 +800,41 @@ impl KisClient {
                 KisMarket::Kosdaq => KOSDAQ_MASTER_ZIP,
                 KisMarket::Konex => KONEX_MASTER_ZIP,
             };
-            out.extend(fetch_and_parse_master_zip(&self.http, url).await?);
+            out.extend(fetch_and_parse_master_zip(&self.http, url, *market).await?);
         }
         Ok(out)
     }
 
-    async fn fetch_one_stock_daily_features(
+    /// GETs `/uapi/domestic-stock/v1/quotations/{endpoint}` through the shared rate limiter,
+    /// retrying transport errors, 5xx and unparsable bodies with exponential backoff. A 429
+    /// throttles every task instead (see [`RateLimiter::throttle`]).
+    async fn get_quotation<T: serde::de::DeserializeOwned>(
         &self,
         token: &KisToken,
-        stock: &KisMasterRecord,
-        start: &str,
-        end: &str,
-        prev_date: NaiveDate,
-        as_of_date: NaiveDate,
-    ) -> Result<DailyFeatureItem> {
-        // Daily item chart price (OHLCV + trading value + PER/PBR/EPS) endpoint.
+        endpoint: &str,
+        tr_id: &'static str,
+        params: &[(&str, &str)],
+        ticker: &str,
+    ) -> Result<T> {
+        let (body, _more) = self
+            .get_quotation_page(token, endpoint, tr_id, params, ticker, "")
+            .await?;
+        Ok(body)
+    }
+
+    /// One page of a quotation: `tr_cont` is `""` for the first page and `"N"` for the ones
+    /// after. Also returns whether the response's `tr_cont` header says more pages follow.
+    async fn get_quotation_page<T: serde::de::DeserializeOwned>(
+        &self,
+        token: &KisToken,
+        endpoint: &str,
+        tr_id: &'static str,
+        params: &[(&str, &str)],
+        ticker: &str,
+        tr_cont: &'static str,
+    ) -> Result<(T, bool)> {
         let url = format!(
-            "{}/uapi/domestic-stock/v1/quotations/inquire-daily-itemchartprice",
+            "{}/uapi/domestic-stock/v1/quotations/{endpoint}",
             self.base_url.trim_end_matches('/')
         );
 
@@ -275,127 +845,367 @@ impl KisClient {
         );
         headers.insert("appkey", HeaderValue::from_str(&self.appkey)?);
         headers.insert("appsecret", HeaderValue::from_str(&self.appsecret)?);
-        headers.insert("tr_id", HeaderValue::from_static("FHKST03010100"));
+        headers.insert("tr_id", HeaderValue::from_static(tr_id));
         headers.insert("custtype", HeaderValue::from_static("P"));
-        headers.insert("tr_cont", HeaderValue::from_static(""));
+        headers.insert("tr_cont", HeaderValue::from_static(tr_cont));
         headers.insert("Content-Type", HeaderValue::from_static("application/json"));
         headers.insert("Accept", HeaderValue::from_static("text/plain"));
         headers.insert("charset", HeaderValue::from_static("UTF-8"));
 
-        let params = [
-            ("FID_COND_MRKT_DIV_CODE", "J"),
-            ("FID_INPUT_ISCD", stock.code.as_str()),
-            ("FID_INPUT_DATE_1", start),
-            ("FID_INPUT_DATE_2", end),
-            ("FID_PERIOD_DIV_CODE", "D"),
-            ("FID_ORG_ADJ_PRC", "1"),
-        ];
-
         let max_attempts: u32 = 3;
         let mut attempt: u32 = 0;
-        let body = loop {
+        loop {
             attempt += 1;
-
-            let res = self
-                .http
-                .get(url.clone())
-                .headers(headers.clone())
-                .query(&params)
-                .send()
-                .await;
-
-            let res = match res {
-                Ok(r) => r,
-                Err(err) => {
+            self.limiter.acquire().await;
+
+            // The client's own timeout is only a backstop; a hung response should cost one
+            // attempt, not the whole client timeout.
+            let sent = tokio::time::timeout(self.request_timeout, async {
+                let res = self
+                    .http
+                    .get(url.clone())
+                    .headers(headers.clone())
+                    .query(params)
+                    .send()
+                    .await?;
+                let status = res.status();
+                let more =
+                    has_more_pages(res.headers().get("tr_cont").and_then(|v| v.to_str().ok()));
+                Ok::<_, reqwest::Error>((status, more, res.text().await?))
+            })
+            .await;
+
+            let (status, more, text) = match sent {
+                Ok(Ok(res)) => res,
+                Ok(Err(err)) => {
                     if attempt >= max_attempts {
-                        return Err(err).context("KIS daily itemchartprice request failed");
+                        let err = anyhow::Error::new(err)
+                            .context(format!("KIS {endpoint} request failed"));
+                        return Err(request_error(FailureClass::Transport, attempt, err));
                     }
                     let backoff = Duration::from_secs(1 << (attempt - 1));
                     tracing::warn!(
                         attempt,
                         ?backoff,
-                        ticker = %stock.code,
+                        endpoint,
+                        ticker,
                         error = %err,
-                        "KIS daily request failed; retrying"
+                        "KIS request failed; retrying"
+                    );
+                    tokio::time::sleep(backoff).await;
+                    continue;
+                }
+                Err(_elapsed) => {
+                    self.request_timeouts.fetch_add(1, Ordering::Relaxed);
+                    if attempt >= max_attempts {
+                        let err = KisTimeout {
+                            endpoint: endpoint.to_string(),
+                            ticker: ticker.to_string(),
+                            timeout: self.request_timeout,
+                            attempts: attempt,
+                        };
+                        return Err(request_error(FailureClass::Timeout, attempt, err.into()));
+                    }
+                    let backoff = Duration::from_secs(1 << (attempt - 1));
+                    tracing::warn!(
+                        attempt,
+                        ?backoff,
+                        endpoint,
+                        ticker,
+                        timeout_ms = self.request_timeout.as_millis() as u64,
+                        "KIS request timed out; retrying"
                     );
                     tokio::time::sleep(backoff).await;
                     continue;
                 }
             };
 
-            let status = res.status();
-            let text = res
-                .text()
-                .await
-                .context("failed to read KIS daily response")?;
-
             if !status.is_success() {
                 let retryable = status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error();
                 if retryable && attempt < max_attempts {
                     let backoff = Duration::from_secs(1 << (attempt - 1));
+                    if status == StatusCode::TOO_MANY_REQUESTS {
+                        self.throttle_after_rate_limit(attempt, backoff, endpoint, ticker)
+                            .await;
+                        continue;
+                    }
                     tracing::warn!(
                         attempt,
                         ?backoff,
-                        ticker = %stock.code,
+                        endpoint,
+                        ticker,
                         http_status = %status,
-                        "KIS daily HTTP error; retrying"
+                        "KIS HTTP error; retrying"
                     );
                     tokio::time::sleep(backoff).await;
                     continue;
                 }
-                anyhow::bail!("KIS daily itemchartprice HTTP {status}: {text}");
+                let class = if status == StatusCode::TOO_MANY_REQUESTS {
+                    FailureClass::RateLimited
+                } else {
+                    FailureClass::Http
+                };
+                let err = anyhow::anyhow!("KIS {endpoint} HTTP {status}: {text}");
+                return Err(request_error(class, attempt, err));
+            }
+
+            // KIS reports many errors as HTTP 200 with a non-zero `rt_cd`.
+            let kis_status = serde_json::from_str::<KisStatus>(&text).unwrap_or_default();
+            match kis_status.outcome() {
+                KisOutcome::Ok => {}
+                KisOutcome::RateLimited if attempt < max_attempts => {
+                    let backoff = Duration::from_secs(1 << (attempt - 1));
+                    self.throttle_after_rate_limit(attempt, backoff, endpoint, ticker)
+                        .await;
+                    continue;
+                }
+                KisOutcome::NoData => {
+                    return Err(KisNoData {
+                        endpoint: endpoint.to_string(),
+                        ticker: ticker.to_string(),
+                        message: kis_status.describe(),
+                    }
+                    .into());
+                }
+                outcome @ (KisOutcome::RateLimited | KisOutcome::Failed) => {
+                    let class = if outcome == KisOutcome::RateLimited {
+                        FailureClass::RateLimited
+                    } else {
+                        FailureClass::Rejected
+                    };
+                    let err = anyhow::anyhow!("KIS {endpoint} error {}", kis_status.describe());
+                    return Err(request_error(class, attempt, err));
+                }
             }
 
-            match serde_json::from_str::<KisDailyItemChartPriceResponse>(&text) {
-                Ok(body) => break body,
+            match serde_json::from_str::<T>(&text) {
+                Ok(body) => return Ok((body, more)),
                 Err(err) => {
                     if attempt >= max_attempts {
-                        return Err(err)
-                            .context("failed to parse KIS daily itemchartprice response");
+                        let err = anyhow::Error::new(err)
+                            .context(format!("failed to parse KIS {endpoint} response"));
+                        return Err(request_error(FailureClass::Parse, attempt, err));
                     }
                     let backoff = Duration::from_secs(1 << (attempt - 1));
                     tracing::warn!(
                         attempt,
                         ?backoff,
-                        ticker = %stock.code,
+                        endpoint,
+                        ticker,
                         error = %err,
-                        "KIS daily response parse failed; retrying"
+                        "KIS response parse failed; retrying"
                     );
                     tokio::time::sleep(backoff).await;
                     continue;
                 }
             }
-        };
+        }
+    }
 
-        // Find prev and as-of records.
-        let prev_ymd = prev_date.format("%Y%m%d").to_string();
-        let asof_ymd = as_of_date.format("%Y%m%d").to_string();
+    /// Every task is over the same limit: slow them all down instead of letting each back off and
+    /// retry on its own.
+    async fn throttle_after_rate_limit(
+        &self,
+        attempt: u32,
+        backoff: Duration,
+        endpoint: &str,
+        ticker: &str,
+    ) {
+        self.limiter.throttle(backoff).await;
+        let rate_per_sec = self.limiter.rate().await;
+        tracing::warn!(
+            attempt,
+            ?backoff,
+            endpoint,
+            ticker,
+            rate_per_sec,
+            "KIS rate limited; pausing all requests and halving the rate"
+        );
+    }
 
-        let mut prev_close: Option<f64> = None;
-        let mut asof: Option<&KisDailyBar> = None;
-        for bar in &body.output2 {
-            if bar.stck_bsop_date == prev_ymd {
-                prev_close = parse_num(&bar.stck_clpr);
-            }
-            if bar.stck_bsop_date == asof_ymd {
-                asof = Some(bar);
+    /// KOSPI/KOSDAQ closes for `as_of_date`. Index data is context only, so an index that fails
+    /// or has no as-of bar is logged and left out.
+    async fn fetch_market_indices(
+        &self,
+        token: &KisToken,
+        as_of_date: NaiveDate,
+    ) -> Vec<MarketIndexDaily> {
+        let mut out = Vec::new();
+        for (market, code) in MARKET_INDICES {
+            match self
+                .fetch_index_daily(token, market, code, as_of_date)
+                .await
+            {
+                Ok(Some(index)) => out.push(index),
+                Ok(None) => {
+                    tracing::warn!(%as_of_date, market, "no KIS index bar for the date")
+                }
+                Err(e) => {
+                    tracing::warn!(%as_of_date, market, error = %e, "KIS index fetch failed")
+                }
             }
         }
+        out
+    }
 
-        let asof = asof.context("missing as-of bar in KIS response")?;
-
-        let close = parse_num(&asof.stck_clpr).context("missing close")?;
-        let trading_value = parse_num(&asof.acml_tr_pbmn);
-        let volume = parse_num(&asof.acml_vol);
+    async fn fetch_index_daily(
+        &self,
+        token: &KisToken,
+        market: &str,
+        code: &str,
+        as_of_date: NaiveDate,
+    ) -> Result<Option<MarketIndexDaily>> {
+        let start = (as_of_date - chrono::Duration::days(INDEX_CALENDAR_DAYS))
+            .format("%Y%m%d")
+            .to_string();
+        let end = as_of_date.format("%Y%m%d").to_string();
+        let params = [
+            ("FID_COND_MRKT_DIV_CODE", "U"),
+            ("FID_INPUT_ISCD", code),
+            ("FID_INPUT_DATE_1", start.as_str()),
+            ("FID_INPUT_DATE_2", end.as_str()),
+            ("FID_PERIOD_DIV_CODE", "D"),
+        ];
+        let body: KisIndexChartResponse = self
+            .get_quotation(
+                token,
+                "inquire-daily-indexchartprice",
+                "FHKUP03500100",
+                &params,
+                code,
+            )
+            .await?;
+        Ok(index_daily(market, &body.output2, as_of_date))
+    }
 
-        let ret_1d = prev_close.map(|p| (close / p) - 1.0);
+    /// Foreign/institutional net-buy features for `as_of_date` (see [`investor_flow_features`]).
+    async fn fetch_investor_flows(
+        &self,
+        token: &KisToken,
+        stock: &KisMasterRecord,
+        as_of_date: NaiveDate,
+    ) -> Result<BTreeMap<String, f64>> {
+        let params = [
+            ("FID_COND_MRKT_DIV_CODE", "J"),
+            ("FID_INPUT_ISCD", stock.code.as_str()),
+        ];
+        let body: KisInvestorResponse = self
+            .get_quotation(
+                token,
+                "inquire-investor",
+                "FHKST01010900",
+                &params,
+                &stock.code,
+            )
+            .await?;
+        Ok(investor_flow_features(&body.output, as_of_date))
+    }
 
-        let mut features = BTreeMap::<String, f64>::new();
-        if let Some(v) = ret_1d {
-            features.insert("ret_1d".to_string(), v);
-        }
-        if let Some(v) = trading_value {
-            features.insert("trading_value".to_string(), v);
+    /// Daily item chart price (OHLCV + trading value + PER/PBR/EPS) for `start..=end`. A window
+    /// longer than one response is followed page by page (`tr_cont`), each page ending the day
+    /// before the oldest bar so far, up to `chart_max_pages`. Bars come back merged, one per
+    /// date, newest first; `output1` is the first page's.
+    async fn fetch_daily_chart(
+        &self,
+        token: &KisToken,
+        stock: &KisMasterRecord,
+        start: &str,
+        end: &str,
+    ) -> Result<KisDailyItemChartPriceResponse> {
+        let mut page_end = end.to_string();
+        let mut tr_cont = "";
+        let mut first: Option<KisDailyItemChartPriceResponse> = None;
+        let mut pages = Vec::new();
+        for page in 1..=self.chart_max_pages {
+            let params = [
+                ("FID_COND_MRKT_DIV_CODE", "J"),
+                ("FID_INPUT_ISCD", stock.code.as_str()),
+                ("FID_INPUT_DATE_1", start),
+                ("FID_INPUT_DATE_2", page_end.as_str()),
+                ("FID_PERIOD_DIV_CODE", "D"),
+                ("FID_ORG_ADJ_PRC", "1"),
+            ];
+            let (mut body, more): (KisDailyItemChartPriceResponse, bool) = self
+                .get_quotation_page(
+                    token,
+                    "inquire-daily-itemchartprice",
+                    "FHKST03010100",
+                    &params,
+                    &stock.code,
+                    tr_cont,
+                )
+                .await?;
+            let next = next_page_end(&body.output2, start);
+            pages.push(std::mem::take(&mut body.output2));
+            if first.is_none() {
+                first = Some(body);
+            }
+            let Some(next) = next.filter(|_| more) else {
+                break;
+            };
+            if page == self.chart_max_pages {
+                tracing::warn!(
+                    ticker = %stock.code,
+                    pages = page,
+                    oldest = %next,
+                    "KIS daily chart page cap reached; older bars left out"
+                );
+                break;
+            }
+            page_end = next;
+            tr_cont = "N";
+        }
+        let mut body = first.expect("at least one chart page is fetched");
+        body.output2 = merge_bars(pages);
+        Ok(body)
+    }
+
+    async fn fetch_one_stock_daily_features(
+        &self,
+        token: &KisToken,
+        stock: &KisMasterRecord,
+        start: &str,
+        end: &str,
+        prev_date: NaiveDate,
+        as_of_date: NaiveDate,
+    ) -> Result<DailyFeatureItem> {
+        let body = self.fetch_daily_chart(token, stock, start, end).await?;
+
+        // Find prev and as-of records.
+        let prev_ymd = prev_date.format("%Y%m%d").to_string();
+        let asof_ymd = as_of_date.format("%Y%m%d").to_string();
+
+        let mut prev_close: Option<f64> = None;
+        let mut asof: Option<&KisDailyBar> = None;
+        for bar in &body.output2 {
+            if bar.stck_bsop_date == prev_ymd {
+                prev_close = parse_num(&bar.stck_clpr);
+            }
+            if bar.stck_bsop_date == asof_ymd {
+                asof = Some(bar);
+            }
+        }
+
+        let asof = asof.ok_or_else(|| KisStockError::MissingAsOfBar {
+            ticker: stock.code.clone(),
+            as_of_date,
+        })?;
+
+        let close = parse_num(&asof.stck_clpr).ok_or_else(|| KisStockError::MissingClose {
+            ticker: stock.code.clone(),
+            as_of_date,
+        })?;
+        let trading_value = parse_num(&asof.acml_tr_pbmn);
+        let volume = parse_num(&asof.acml_vol);
+
+        let ret_1d = prev_close.map(|p| (close / p) - 1.0);
+
+        let mut features = BTreeMap::<String, f64>::new();
+        if let Some(v) = ret_1d {
+            features.insert("ret_1d".to_string(), v);
+        }
+        if let Some(v) = trading_value {
+            features.insert("trading_value".to_string(), v);
         }
         if let Some(v) = volume {
             features.insert("volume".to_string(), v);
@@ -411,15 +1221,85 @@ impl KisClient {
             features.insert("eps".to_string(), v);
         }
 
+        let history: Vec<DailyClose> = body
+            .output2
+            .iter()
+            .filter_map(|bar| {
+                Some(DailyClose {
+                    date: NaiveDate::parse_from_str(&bar.stck_bsop_date, "%Y%m%d").ok()?,
+                    close: parse_num(&bar.stck_clpr)?,
+                    volume: parse_num(&bar.acml_vol),
+                })
+            })
+            .collect();
+        features.extend(history_features(&history, as_of_date));
+
+        let quote = body.output1.clone().unwrap_or_default();
+        let shares = parse_num(&quote.lstn_stcn).filter(|n| *n > 0.0);
+        if let Some(v) = shares {
+            features.insert("shares_outstanding".to_string(), v);
+        }
+        if let Some(v) = market_cap(close, shares, parse_num(&quote.hts_avls)) {
+            features.insert("market_cap".to_string(), v);
+        }
+        features.extend(week52_features(
+            close,
+            parse_num(&quote.w52_hgpr),
+            parse_num(&quote.w52_lwpr),
+        ));
+        if let Some(v) = dividend_yield(parse_num(&quote.divi_rate)) {
+            features.insert("dividend_yield".to_string(), v);
+        }
+
+        if self.investor_flows {
+            match self.fetch_investor_flows(token, stock, as_of_date).await {
+                Ok(flows) => features.extend(flows),
+                // Flows are optional: keep the stock without them.
+                Err(err) => tracing::debug!(
+                    ticker = %stock.code,
+                    error = %err,
+                    "KIS investor flows unavailable; skipping them"
+                ),
+            }
+        }
+
+        // Exchange flags from the master file; only set ones are recorded.
+        if stock.market_warning > 0 {
+            features.insert("market_warning".to_string(), stock.market_warning.into());
+        }
+        if stock.administrative {
+            features.insert("is_administrative".to_string(), 1.0);
+        }
+        // A halt the master file predates still shows as a volume-less as-of bar.
+        if stock.halted || volume == Some(0.0) {
+            features.insert("is_halted".to_string(), 1.0);
+        }
+
         Ok(DailyFeatureItem {
             ticker: format!("KRX:{}", stock.code),
             name: stock.name.clone(),
+            name_en: stock.name_en.clone(),
+            instrument_type: stock.instrument_type().map(str::to_string),
+            sector: stock.sector.map(str::to_string),
             trading_value,
+            close: Some(close),
             features,
         })
     }
 }
 
+/// Drops stocks a previous run already checkpointed for `as_of_date`; returns how many.
+async fn skip_checkpointed(
+    pool: &sqlx::PgPool,
+    as_of_date: NaiveDate,
+    universe: &mut Vec<KisMasterRecord>,
+) -> Result<usize> {
+    let done = crate::storage::kis_progress::completed_tickers(pool, as_of_date).await?;
+    let before = universe.len();
+    universe.retain(|stock| !done.contains(&format!("KRX:{}", stock.code)));
+    Ok(before - universe.len())
+}
+
 #[derive(Debug, Serialize)]
 struct KisTokenRequest<'a> {
     grant_type: &'a str,
@@ -478,6 +1358,16 @@ async fn load_token_from_db(pool: &sqlx::PgPool, env: &str) -> Result<Option<Kis
     }))
 }
 
+async fn delete_token_from_db(pool: &sqlx::PgPool, env: &str) -> Result<()> {
+    sqlx::query("DELETE FROM kis_access_tokens WHERE env = $1")
+        .persistent(false)
+        .bind(env)
+        .execute(pool)
+        .await
+        .context("delete kis_access_tokens failed")?;
+    Ok(())
+}
+
 async fn save_token_to_db(pool: &sqlx::PgPool, env: &str, tok: &KisToken) -> Result<()> {
     sqlx::query(
         "INSERT INTO kis_access_tokens (env, access_token, access_token_token_expired, expires_in, issued_at, updated_at) \
@@ -535,12 +1425,370 @@ mod token_tests {
     }
 }
 
+/// Business-level result KIS includes in quotation (and some token) responses. An empty `rt_cd`
+/// (field absent) counts as success.
+#[derive(Debug, Clone, Default, Deserialize)]
+struct KisStatus {
+    #[serde(default)]
+    rt_cd: String,
+    #[serde(default)]
+    msg_cd: String,
+    #[serde(default)]
+    msg1: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum KisOutcome {
+    Ok,
+    /// Over the per-second transaction limit; retry after backing off.
+    RateLimited,
+    /// Nothing to return for the request (e.g. no trading for the stock); not a failure.
+    NoData,
+    Failed,
+}
+
+/// `msg_cd` of "초당 거래건수를 초과하였습니다".
+const RATE_LIMIT_MSG_CODES: &[&str] = &["EGW00201"];
+/// `msg_cd` of "조회할 자료가 없습니다"-style replies.
+const NO_DATA_MSG_CODES: &[&str] = &["KIOK0560"];
+
+impl KisStatus {
+    fn outcome(&self) -> KisOutcome {
+        let rt_cd = self.rt_cd.trim();
+        if rt_cd.is_empty() || rt_cd == "0" {
+            return KisOutcome::Ok;
+        }
+        let msg_cd = self.msg_cd.trim();
+        // Codes first; the message text catches codes we have not seen.
+        if RATE_LIMIT_MSG_CODES.contains(&msg_cd) || self.msg1.contains("초당 거래건수") {
+            KisOutcome::RateLimited
+        } else if NO_DATA_MSG_CODES.contains(&msg_cd)
+            || self.msg1.contains("자료가 없습니다")
+            || self.msg1.contains("내용이 없습니다")
+        {
+            KisOutcome::NoData
+        } else {
+            KisOutcome::Failed
+        }
+    }
+
+    fn describe(&self) -> String {
+        format!(
+            "rt_cd={} msg_cd={}: {}",
+            self.rt_cd.trim(),
+            self.msg_cd.trim(),
+            self.msg1.trim()
+        )
+    }
+}
+
+/// Stocks [`KisClient::fetch_universe_daily`] skipped.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct FetchStats {
+    /// Stocks whose fetch failed, timeouts included.
+    failures: usize,
+    /// Quotation attempts that timed out, including those a later attempt or pass recovered.
+    timeouts: usize,
+    /// Failures where every attempt timed out.
+    timeout_failures: usize,
+    /// Stocks KIS had no data for; not failures.
+    no_data: usize,
+    /// Stocks re-attempted by the retry pass, and how many of them then succeeded.
+    retried: usize,
+    recovered: usize,
+    /// Likewise for the late-data pass over stocks that were missing the as-of bar.
+    late_retried: usize,
+    late_recovered: usize,
+    /// The final failures, by ticker.
+    failed: Vec<FailedTicker>,
+}
+
+/// A stock the ingest gave up on, as recorded in the ingest run's raw JSON.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct FailedTicker {
+    ticker: String,
+    class: FailureClass,
+    /// Requests made for the stock across the sweep and the retry pass.
+    attempts: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum FailureClass {
+    Timeout,
+    /// Connection or body read errors.
+    Transport,
+    /// A non-2xx status other than 429.
+    Http,
+    /// Still rate limited (HTTP 429 or a rate-limit `msg_cd`) after the last attempt.
+    RateLimited,
+    /// A non-zero `rt_cd` KIS does not treat as no data.
+    Rejected,
+    /// An unparsable response body, or an as-of bar without a close.
+    Parse,
+    /// No bar for the as-of date (still missing after the late-data pass, if enabled).
+    MissingAsOfBar,
+    /// Anything not raised by a quotation request.
+    Other,
+}
+
+/// A quotation request that failed for good. Displays as the underlying error.
+#[derive(Debug)]
+struct KisRequestError {
+    class: FailureClass,
+    attempts: u32,
+    error: anyhow::Error,
+}
+
+impl std::fmt::Display for KisRequestError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if f.alternate() {
+            write!(f, "{:#}", self.error)
+        } else {
+            write!(f, "{}", self.error)
+        }
+    }
+}
+
+impl std::error::Error for KisRequestError {}
+
+fn request_error(class: FailureClass, attempts: u32, error: anyhow::Error) -> anyhow::Error {
+    KisRequestError {
+        class,
+        attempts,
+        error,
+    }
+    .into()
+}
+
+/// A stock's quotation came back but cannot be turned into an item.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum KisStockError {
+    /// No bar for the as-of date yet; KIS publishes some (new listings, thin KONEX names) late.
+    MissingAsOfBar {
+        ticker: String,
+        as_of_date: NaiveDate,
+    },
+    MissingClose {
+        ticker: String,
+        as_of_date: NaiveDate,
+    },
+}
+
+impl std::fmt::Display for KisStockError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingAsOfBar { ticker, as_of_date } => write!(
+                f,
+                "missing as-of bar in KIS response for {ticker} ({as_of_date})"
+            ),
+            Self::MissingClose { ticker, as_of_date } => write!(
+                f,
+                "missing close in KIS as-of bar for {ticker} ({as_of_date})"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for KisStockError {}
+
+/// The class and request count of a failed stock fetch.
+fn classify_failure(err: &anyhow::Error) -> (FailureClass, u32) {
+    if let Some(e) = err.downcast_ref::<KisRequestError>() {
+        return (e.class, e.attempts);
+    }
+    match err.downcast_ref::<KisStockError>() {
+        Some(KisStockError::MissingAsOfBar { .. }) => (FailureClass::MissingAsOfBar, 1),
+        Some(KisStockError::MissingClose { .. }) => (FailureClass::Parse, 1),
+        None => (FailureClass::Other, 1),
+    }
+}
+
+/// The passes of [`KisClient::fetch_universe_daily`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Pass {
+    /// Every stock.
+    Sweep,
+    /// Failed stocks, right after the sweep.
+    Retry,
+    /// Stocks missing the as-of bar, after `KIS_LATE_DATA_RETRY_DELAY_SECS`.
+    LateData,
+}
+
+/// Per-market counts of a KIS ingest, for the raw JSON and the coverage check.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+struct MarketCoverage {
+    /// Records in the master file (before `KIS_MAX_TICKERS`).
+    master: usize,
+    /// Skipped as already checkpointed (`--resume`).
+    resumed: usize,
+    items: usize,
+    failures: usize,
+}
+
+/// `market -> value` keyed by the market's label, for the raw JSON.
+fn by_label<T: Copy>(m: &BTreeMap<KisMarket, T>) -> BTreeMap<&'static str, T> {
+    m.iter().map(|(k, v)| (k.label(), *v)).collect()
+}
+
+/// Markets whose rows for the date (this run's items plus resumed ones) fall below their
+/// minimum. A market with a minimum that was not ingested at all counts as zero rows.
+fn coverage_shortfalls(
+    coverage: &BTreeMap<KisMarket, MarketCoverage>,
+    min_items: &BTreeMap<KisMarket, usize>,
+) -> Vec<MarketShortfall> {
+    min_items
+        .iter()
+        .filter_map(|(market, min)| {
+            let got = coverage
+                .get(market)
+                .map(|c| c.items + c.resumed)
+                .unwrap_or(0);
+            (got < *min).then_some(MarketShortfall {
+                market: *market,
+                min: *min,
+                got,
+            })
+        })
+        .collect()
+}
+
+/// Whether more than `max_rate` of the `total` stocks failed.
+fn exceeds_failure_rate(failures: usize, total: usize, max_rate: f64) -> bool {
+    total > 0 && failures as f64 / total as f64 > max_rate
+}
+
+/// A KIS ingest too incomplete to use: too many stocks failed, or a market came up short of its
+/// minimum. Carries what was fetched so the worker can record the partial run.
+#[derive(Debug)]
+pub struct KisIngestIncomplete {
+    pub as_of_date: NaiveDate,
+    pub reason: IncompleteReason,
+    /// Stocks that failed (after the retry passes).
+    pub failures: usize,
+    /// The stocks fetched before giving up (already checkpointed with `--resume`).
+    pub items: Vec<DailyFeatureItem>,
+    /// The run's raw JSON, failure breakdown and market coverage included, for the `error`
+    /// ingest run.
+    pub raw: Value,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum IncompleteReason {
+    /// More than `max` (`KIS_MAX_FAILURE_RATE`) of the run's `total` stocks failed.
+    FailureRate { total: usize, max: f64 },
+    /// Markets with fewer rows than their `KIS_MIN_ITEMS_*`.
+    Coverage(Vec<MarketShortfall>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MarketShortfall {
+    pub market: KisMarket,
+    pub min: usize,
+    pub got: usize,
+}
+
+impl std::fmt::Display for KisIngestIncomplete {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.reason {
+            IncompleteReason::FailureRate { total, max } => write!(
+                f,
+                "KIS ingest for {} failed for {} of {} stocks ({:.1}%), above KIS_MAX_FAILURE_RATE={}",
+                self.as_of_date,
+                self.failures,
+                total,
+                self.failures as f64 / (*total).max(1) as f64 * 100.0,
+                max
+            ),
+            IncompleteReason::Coverage(shortfalls) => {
+                let parts: Vec<String> = shortfalls
+                    .iter()
+                    .map(|s| {
+                        let market = s.market.label();
+                        format!("{market} has {} rows (KIS_MIN_ITEMS_{market}={})", s.got, s.min)
+                    })
+                    .collect();
+                write!(
+                    f,
+                    "KIS ingest for {} is below the minimum coverage: {}",
+                    self.as_of_date,
+                    parts.join(", ")
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for KisIngestIncomplete {}
+
+/// Every attempt at a request hit `KIS_PER_REQUEST_TIMEOUT_MS`.
+#[derive(Debug)]
+struct KisTimeout {
+    endpoint: String,
+    ticker: String,
+    timeout: Duration,
+    attempts: u32,
+}
+
+impl std::fmt::Display for KisTimeout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "KIS {} for {} timed out after {:?} ({} attempts)",
+            self.endpoint, self.ticker, self.timeout, self.attempts
+        )
+    }
+}
+
+impl std::error::Error for KisTimeout {}
+
+/// KIS had no data for the request; the stock is skipped without counting as a failure.
+#[derive(Debug)]
+struct KisNoData {
+    endpoint: String,
+    ticker: String,
+    message: String,
+}
+
+impl std::fmt::Display for KisNoData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "KIS {} has no data for {} ({})",
+            self.endpoint, self.ticker, self.message
+        )
+    }
+}
+
+impl std::error::Error for KisNoData {}
+
 #[derive(Debug, Clone, Deserialize)]
 struct KisDailyItemChartPriceResponse {
+    /// Current quote summary; absent from some error-ish responses.
+    #[serde(default)]
+    output1: Option<KisDailyQuote>,
     #[serde(default)]
     output2: Vec<KisDailyBar>,
 }
 
+#[derive(Debug, Clone, Default, Deserialize)]
+struct KisDailyQuote {
+    /// Listed shares.
+    #[serde(default)]
+    lstn_stcn: String,
+    /// Market cap in 100 million KRW (억원), at the latest price.
+    #[serde(default)]
+    hts_avls: String,
+    /// 52-week high and low prices.
+    #[serde(default)]
+    w52_hgpr: String,
+    #[serde(default)]
+    w52_lwpr: String,
+    /// Dividend yield in percent, when KIS includes it.
+    #[serde(default)]
+    divi_rate: String,
+}
+
 #[derive(Debug, Clone, Deserialize)]
 struct KisDailyBar {
     #[serde(default)]
@@ -559,12 +1807,199 @@ struct KisDailyBar {
     eps: String,
 }
 
+#[derive(Debug, Clone, Deserialize)]
+struct KisIndexChartResponse {
+    #[serde(default)]
+    output2: Vec<KisIndexBar>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct KisIndexBar {
+    #[serde(default)]
+    stck_bsop_date: String,
+    /// Index close.
+    #[serde(default)]
+    bstp_nmix_prpr: String,
+}
+
+/// The as-of close of `market` and its return over the latest earlier bar; `None` without an
+/// as-of bar.
+fn index_daily(
+    market: &str,
+    bars: &[KisIndexBar],
+    as_of_date: NaiveDate,
+) -> Option<MarketIndexDaily> {
+    let mut closes: Vec<(NaiveDate, f64)> = bars
+        .iter()
+        .filter_map(|b| {
+            let date = NaiveDate::parse_from_str(&b.stck_bsop_date, "%Y%m%d").ok()?;
+            let close = parse_num(&b.bstp_nmix_prpr).filter(|c| *c > 0.0)?;
+            (date <= as_of_date).then_some((date, close))
+        })
+        .collect();
+    closes.sort_by_key(|c| std::cmp::Reverse(c.0));
+    let (date, close) = *closes.first()?;
+    if date != as_of_date {
+        return None;
+    }
+    Some(MarketIndexDaily {
+        as_of_date,
+        market: market.to_string(),
+        close,
+        ret_1d: closes.get(1).map(|(_, prev)| close / prev - 1.0),
+    })
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct KisInvestorResponse {
+    /// Recent trading days (about 30), newest first.
+    #[serde(default)]
+    output: Vec<KisInvestorDay>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct KisInvestorDay {
+    #[serde(default)]
+    stck_bsop_date: String,
+    /// Foreign net buying value, in million KRW; blank while the day is in progress.
+    #[serde(default)]
+    frgn_ntby_tr_pbmn: String,
+    /// Institutional net buying value, in million KRW.
+    #[serde(default)]
+    orgn_ntby_tr_pbmn: String,
+}
+
+/// `frg_net_buy_1d`/`inst_net_buy_1d` (the as-of day) and `frg_net_buy_5d`/`inst_net_buy_5d` (the
+/// five trading days ending on it), in KRW. A key is absent when the as-of day is not in `days` or
+/// any day it covers has no value, so a backfill older than the endpoint's window gets none.
+fn investor_flow_features(days: &[KisInvestorDay], as_of_date: NaiveDate) -> BTreeMap<String, f64> {
+    type FlowRow = (NaiveDate, Option<f64>, Option<f64>);
+    let mut rows: Vec<FlowRow> = days
+        .iter()
+        .filter_map(|d| {
+            let date = NaiveDate::parse_from_str(&d.stck_bsop_date, "%Y%m%d").ok()?;
+            (date <= as_of_date).then_some((
+                date,
+                parse_num(&d.frgn_ntby_tr_pbmn),
+                parse_num(&d.orgn_ntby_tr_pbmn),
+            ))
+        })
+        .collect();
+    rows.sort_by_key(|r| std::cmp::Reverse(r.0));
+    rows.dedup_by_key(|r| r.0);
+
+    let mut out = BTreeMap::new();
+    if rows.first().map(|r| r.0) != Some(as_of_date) {
+        return out;
+    }
+    let foreign: fn(&FlowRow) -> Option<f64> = |r| r.1;
+    let institutional: fn(&FlowRow) -> Option<f64> = |r| r.2;
+    let total = |days: usize, pick: fn(&FlowRow) -> Option<f64>| {
+        let window = rows.get(..days)?;
+        // Million KRW -> KRW.
+        window
+            .iter()
+            .map(pick)
+            .sum::<Option<f64>>()
+            .map(|v| v * 1.0e6)
+    };
+    for (key, days, pick) in [
+        ("frg_net_buy_1d", 1, foreign),
+        ("inst_net_buy_1d", 1, institutional),
+        ("frg_net_buy_5d", 5, foreign),
+        ("inst_net_buy_5d", 5, institutional),
+    ] {
+        if let Some(v) = total(days, pick) {
+            out.insert(key.to_string(), v);
+        }
+    }
+    out
+}
+
 #[derive(Debug, Clone)]
 struct KisMasterRecord {
+    market: KisMarket,
     code: String,
     name: String,
+    name_en: Option<String>,
+    /// Security group code opening the fixed-width tail (`ST` stock, `EF` ETF, `EN` ETN, ...);
+    /// `None` when the line has no recognizable tail.
+    group_code: Option<String>,
+    preferred: bool,
+    halted: bool,
+    /// Designated for administrative supervision (관리종목).
+    administrative: bool,
+    /// Market warning level: 0 none, 1 caution, 2 warning, 3 risk.
+    market_warning: u8,
+    /// Korean sector name from the industry codes (see [`crate::ingest::sector`]).
+    sector: Option<&'static str>,
+}
+
+impl KisMasterRecord {
+    fn new(market: KisMarket, code: String, name: String) -> Self {
+        Self {
+            market,
+            code,
+            name,
+            name_en: None,
+            group_code: None,
+            preferred: false,
+            halted: false,
+            administrative: false,
+            market_warning: 0,
+            sector: None,
+        }
+    }
+
+    /// `stock_features_daily.instrument_type` for this record.
+    fn instrument_type(&self) -> Option<&'static str> {
+        Some(match self.group_code.as_deref()? {
+            "ST" if self.preferred => "preferred",
+            "ST" => "stock",
+            "EF" | "FE" => "etf",
+            "EN" => "etn",
+            "RT" => "reit",
+            "MF" | "IF" | "BC" => "fund",
+            "DR" => "dr",
+            _ => "other",
+        })
+    }
 }
 
+/// Security group codes that can open a master record's fixed-width tail.
+const GROUP_CODES: &[&[u8; 2]] = &[
+    b"ST", b"EF", b"EN", b"FE", b"FS", b"RT", b"MF", b"IF", b"BC", b"DR", b"SC", b"EW", b"SW",
+    b"SR",
+];
+
+/// Offsets, counted from the group code, of the tail fields we read. They follow the field specs
+/// of the KIS sample master parsers; the KONEX layout is not mapped, so only its group code is
+/// read.
+struct TailLayout {
+    halted: usize,
+    administrative: usize,
+    market_warning: usize,
+    preferred: usize,
+}
+
+const KOSPI_TAIL: TailLayout = TailLayout {
+    halted: 60,
+    administrative: 62,
+    market_warning: 63,
+    preferred: 158,
+};
+
+/// Large/mid/small industry index codes, 4 bytes each, right after the group code and cap-size
+/// class in both the KOSPI and KOSDAQ tails.
+const INDUSTRY_CODES: usize = 3;
+
+const KOSDAQ_TAIL: TailLayout = TailLayout {
+    halted: 55,
+    administrative: 57,
+    market_warning: 58,
+    preferred: 153,
+};
+
 fn parse_markets(v: Option<String>) -> Vec<KisMarket> {
     let Some(v) = v else {
         return vec![KisMarket::Kospi, KisMarket::Kosdaq];
@@ -590,11 +2025,55 @@ fn previous_business_day(d: NaiveDate) -> NaiveDate {
     // we keep this minimal.
     let mut cur = d - chrono::Duration::days(1);
     while matches!(cur.weekday(), chrono::Weekday::Sat | chrono::Weekday::Sun) {
-        cur = cur - chrono::Duration::days(1);
+        cur -= chrono::Duration::days(1);
     }
     cur
 }
 
+/// `dividend_yield` as a fraction (like the returns) from KIS's percent figure. Zero is kept: a
+/// stock that pays nothing. Negative or non-finite figures are dropped.
+fn dividend_yield(divi_rate: Option<f64>) -> Option<f64> {
+    divi_rate
+        .filter(|v| v.is_finite() && *v >= 0.0)
+        .map(|v| v / 100.0)
+}
+
+/// Whether a response's `tr_cont` header says more pages follow (`F`/`M`; `D`/`E` end it).
+fn has_more_pages(tr_cont: Option<&str>) -> bool {
+    matches!(tr_cont.map(str::trim), Some("F" | "M"))
+}
+
+/// `FID_INPUT_DATE_2` for the page after `bars`: the day before the oldest bar, unless that is
+/// already before `start` (or the page had no dated bars).
+fn next_page_end(bars: &[KisDailyBar], start: &str) -> Option<String> {
+    let oldest = bars
+        .iter()
+        .filter_map(|b| NaiveDate::parse_from_str(&b.stck_bsop_date, "%Y%m%d").ok())
+        .min()?;
+    let start = NaiveDate::parse_from_str(start, "%Y%m%d").ok()?;
+    let next = oldest.pred_opt()?;
+    (next >= start).then(|| next.format("%Y%m%d").to_string())
+}
+
+/// Concatenates chart pages (newest page first) into one bar per date, newest first. Pages may
+/// overlap at their edges; the bar from the earlier page wins.
+fn merge_bars(pages: Vec<Vec<KisDailyBar>>) -> Vec<KisDailyBar> {
+    let mut by_date = BTreeMap::new();
+    for bar in pages.into_iter().flatten() {
+        by_date.entry(bar.stck_bsop_date.clone()).or_insert(bar);
+    }
+    by_date.into_values().rev().collect()
+}
+
+/// Market cap in KRW at the as-of close. The quote's `hts_avls` is at the latest price, so it is
+/// only used (converted from 억원) when the listed share count is missing.
+fn market_cap(close: f64, shares: Option<f64>, hts_avls: Option<f64>) -> Option<f64> {
+    match shares {
+        Some(shares) => Some(close * shares),
+        None => hts_avls.filter(|v| *v > 0.0).map(|v| v * 1.0e8),
+    }
+}
+
 fn parse_num(s: &str) -> Option<f64> {
     let t = s.trim();
     if t.is_empty() {
@@ -606,6 +2085,7 @@ fn parse_num(s: &str) -> Option<f64> {
 async fn fetch_and_parse_master_zip(
     http: &reqwest::Client,
     url: &str,
+    market: KisMarket,
 ) -> Result<Vec<KisMasterRecord>> {
     let res = http
         .get(url)
@@ -615,22 +2095,26 @@ async fn fetch_and_parse_master_zip(
     let status = res.status();
     let bytes = res.bytes().await.context("read master zip bytes failed")?;
     if !status.is_success() {
-        anyhow::bail!("master zip HTTP {status}");
+        return Err(IngestError::Http {
+            status: status.as_u16(),
+            error: anyhow::anyhow!("master zip HTTP {status}"),
+        }
+        .into());
     }
 
     let bytes_vec = bytes.to_vec();
-    let records = tokio::task::spawn_blocking(move || unzip_and_parse_master(&bytes_vec))
+    let records = tokio::task::spawn_blocking(move || unzip_and_parse_master(&bytes_vec, market))
         .await
         .context("join unzip task failed")??;
     Ok(records)
 }
 
-fn unzip_and_parse_master(zip_bytes: &[u8]) -> Result<Vec<KisMasterRecord>> {
+fn unzip_and_parse_master(zip_bytes: &[u8], market: KisMarket) -> Result<Vec<KisMasterRecord>> {
     use std::io::{Cursor, Read};
 
     let reader = Cursor::new(zip_bytes);
     let mut zip = zip::ZipArchive::new(reader).context("open zip archive failed")?;
-    anyhow::ensure!(zip.len() >= 1, "zip has no entries");
+    anyhow::ensure!(!zip.is_empty(), "zip has no entries");
 
     let mut mst_idx: Option<usize> = None;
     for i in 0..zip.len() {
@@ -650,10 +2134,10 @@ fn unzip_and_parse_master(zip_bytes: &[u8]) -> Result<Vec<KisMasterRecord>> {
     file.read_to_end(&mut buf)
         .context("read zip entry failed")?;
 
-    parse_master_lines(&buf)
+    parse_master_lines(&buf, market)
 }
 
-fn parse_master_lines(buf: &[u8]) -> Result<Vec<KisMasterRecord>> {
+fn parse_master_lines(buf: &[u8], market: KisMarket) -> Result<Vec<KisMasterRecord>> {
     let mut out = Vec::new();
     for line in buf.split(|b| *b == b'\n') {
         let line = if line.last().copied() == Some(b'\r') {
@@ -671,7 +2155,8 @@ fn parse_master_lines(buf: &[u8]) -> Result<Vec<KisMasterRecord>> {
         }
         let code = std::str::from_utf8(code_bytes).unwrap_or("").to_string();
 
-        // After the 6-digit code, expect spaces, then ISIN, then name, then a market marker (ST...).
+        // After the 6-digit code, expect spaces, then ISIN, then name, then the fixed-width tail
+        // opened by the security group code (ST, EF, EN, ...).
         let mut i = 6;
         while i < line.len() && line[i].is_ascii_whitespace() {
             i += 1;
@@ -694,29 +2179,83 @@ fn parse_master_lines(buf: &[u8]) -> Result<Vec<KisMasterRecord>> {
         }
 
         let after_name = &line[name_start..];
-        let st_pos = find_st_marker(after_name).unwrap_or(after_name.len());
-        let name = decode_euc_kr_trim(&after_name[..st_pos]);
+        let tail_pos = find_group_code(after_name).unwrap_or(after_name.len());
+        let (name_bytes, name_en) = split_english_name(&after_name[..tail_pos]);
+        let name = decode_euc_kr_trim(name_bytes);
         if name.is_empty() {
             continue;
         }
 
-        out.push(KisMasterRecord { code, name });
+        let mut record = KisMasterRecord {
+            name_en,
+            ..KisMasterRecord::new(market, code, name)
+        };
+        parse_master_tail(&after_name[tail_pos..], market, &mut record);
+        out.push(record);
     }
     Ok(out)
 }
 
-fn find_st_marker(bytes: &[u8]) -> Option<usize> {
-    let mut i = 0;
-    while i + 1 < bytes.len() {
-        if bytes[i] == b'S' && bytes[i + 1] == b'T' {
-            // Heuristic: require preceding space.
-            if i == 0 || bytes[i - 1].is_ascii_whitespace() {
-                return Some(i);
-            }
-        }
-        i += 1;
+/// Reads the group code and exchange flags from `tail` (empty when the line had none). Fields
+/// past the end of a truncated tail keep their defaults.
+fn parse_master_tail(tail: &[u8], market: KisMarket, record: &mut KisMasterRecord) {
+    let Some(group) = tail.get(..2) else {
+        return;
+    };
+    record.group_code = Some(String::from_utf8_lossy(group).into_owned());
+
+    let layout = match market {
+        KisMarket::Kospi => &KOSPI_TAIL,
+        KisMarket::Kosdaq => &KOSDAQ_TAIL,
+        KisMarket::Konex => return,
+    };
+    let flag = |at: usize| tail.get(at) == Some(&b'Y');
+    record.halted = flag(layout.halted);
+    record.administrative = flag(layout.administrative);
+    record.market_warning = tail
+        .get(layout.market_warning..layout.market_warning + 2)
+        .and_then(|b| std::str::from_utf8(b).ok())
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(0);
+    // `0` is a common share; any other digit is a preferred-share class.
+    record.preferred = tail
+        .get(layout.preferred)
+        .is_some_and(|b| b.is_ascii_digit() && *b != b'0');
+    let industry = |level: usize| {
+        let at = INDUSTRY_CODES + level * 4;
+        tail.get(at..at + 4)
+            .and_then(|b| std::str::from_utf8(b).ok())
+            .unwrap_or("")
+    };
+    record.sector = crate::ingest::sector::sector(market, [industry(0), industry(1), industry(2)]);
+}
+
+/// Splits the name field into the Korean name and, when the master carries one, the English name
+/// that follows it as a separate column (two or more spaces apart, ASCII only). Master files
+/// without that column yield `None`.
+fn split_english_name(field: &[u8]) -> (&[u8], Option<String>) {
+    let Some(gap) = field.windows(2).position(|w| w == b"  ") else {
+        return (field, None);
+    };
+    let (korean, rest) = field.split_at(gap);
+    let english = String::from_utf8_lossy(rest).trim().to_string();
+    if english.is_empty()
+        || !english.is_ascii()
+        || !english.chars().any(|c| c.is_ascii_alphabetic())
+    {
+        return (field, None);
     }
-    None
+    (korean, Some(english))
+}
+
+/// Position of the group code opening the tail: a known code after whitespace, followed by the
+/// one-digit market-cap class.
+fn find_group_code(bytes: &[u8]) -> Option<usize> {
+    (0..bytes.len().saturating_sub(2)).find(|&i| {
+        (i == 0 || bytes[i - 1].is_ascii_whitespace())
+            && GROUP_CODES.iter().any(|code| bytes[i..i + 2] == code[..])
+            && bytes[i + 2].is_ascii_digit()
+    })
 }
 
 fn decode_euc_kr_trim(bytes: &[u8]) -> String {
@@ -747,8 +2286,1066 @@ mod tests {
         line.extend_from_slice(&name_bytes);
         line.extend_from_slice(b"                ST1002700\n");
 
-        let parsed = parse_master_lines(&line).unwrap();
+        let parsed = parse_master_lines(&line, KisMarket::Kospi).unwrap();
         assert_eq!(parsed.len(), 1);
         assert_eq!(parsed[0].code, "005930");
+        assert_eq!(parsed[0].name, "삼성전자");
+        assert_eq!(parsed[0].name_en, None);
+    }
+
+    #[test]
+    fn parses_english_name_column_when_present() {
+        let mut line = b"005930   KR7005930003".to_vec();
+        let (name_bytes, _, _) = EUC_KR.encode("삼성전자");
+        line.extend_from_slice(&name_bytes);
+        line.extend_from_slice(b"    Samsung Electronics    ST1002700\n");
+
+        let parsed = parse_master_lines(&line, KisMarket::Kospi).unwrap();
+        assert_eq!(parsed[0].name, "삼성전자");
+        assert_eq!(parsed[0].name_en.as_deref(), Some("Samsung Electronics"));
+    }
+
+    /// A master line with a full-width tail: `tail_fields` are (offset from the group code,
+    /// value) pairs written over a blank tail that starts with `group` and cap class `1`.
+    fn master_line(code: &str, name: &str, group: &str, tail_fields: &[(usize, &str)]) -> Vec<u8> {
+        let mut line = format!("{code}   KR7{code}003").into_bytes();
+        let (name_bytes, _, _) = EUC_KR.encode(name);
+        line.extend_from_slice(&name_bytes);
+        line.resize(line.len() + 40 - name_bytes.len(), b' ');
+        let mut tail = vec![b' '; 227];
+        tail[..3].copy_from_slice(format!("{group}1").as_bytes());
+        for (at, value) in tail_fields {
+            tail[*at..*at + value.len()].copy_from_slice(value.as_bytes());
+        }
+        line.extend_from_slice(&tail);
+        line.push(b'\n');
+        line
+    }
+
+    #[test]
+    fn parses_instrument_type_from_the_group_code() {
+        let mut buf = master_line("005930", "삼성전자", "ST", &[(63, "00"), (158, "0")]);
+        buf.extend(master_line("005935", "삼성전자우", "ST", &[(158, "1")]));
+        buf.extend(master_line("069500", "KODEX 200", "EF", &[]));
+        buf.extend(master_line(
+            "580001",
+            "삼성 레버리지 WTI원유 선물 ETN",
+            "EN",
+            &[],
+        ));
+
+        let parsed = parse_master_lines(&buf, KisMarket::Kospi).unwrap();
+        let types: Vec<_> = parsed
+            .iter()
+            .map(|r| (r.code.as_str(), r.name.as_str(), r.instrument_type()))
+            .collect();
+        assert_eq!(
+            types,
+            [
+                ("005930", "삼성전자", Some("stock")),
+                ("005935", "삼성전자우", Some("preferred")),
+                ("069500", "KODEX 200", Some("etf")),
+                ("580001", "삼성 레버리지 WTI원유 선물 ETN", Some("etn")),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_warning_flags_per_market_layout() {
+        let kospi = master_line(
+            "000001",
+            "가나다",
+            "ST",
+            &[(60, "Y"), (62, "Y"), (63, "02")],
+        );
+        let r = &parse_master_lines(&kospi, KisMarket::Kospi).unwrap()[0];
+        assert!(r.halted && r.administrative);
+        assert_eq!(r.sector, None);
+        assert_eq!(r.market_warning, 2);
+        assert!(!r.preferred);
+
+        let kosdaq = master_line(
+            "000002",
+            "라마바",
+            "ST",
+            &[(57, "Y"), (58, "03"), (153, "2")],
+        );
+        let r = &parse_master_lines(&kosdaq, KisMarket::Kosdaq).unwrap()[0];
+        assert!(!r.halted && r.administrative);
+        assert_eq!(r.market_warning, 3);
+        assert_eq!(r.instrument_type(), Some("preferred"));
+
+        // KONEX flags are not mapped; the group code still is.
+        let r = &parse_master_lines(&kosdaq, KisMarket::Konex).unwrap()[0];
+        assert_eq!(
+            (r.administrative, r.instrument_type()),
+            (false, Some("stock"))
+        );
+    }
+
+    #[test]
+    fn parses_the_sector_from_the_industry_codes() {
+        let kospi = master_line("005930", "삼성전자", "ST", &[(3, "002700130000")]);
+        let r = &parse_master_lines(&kospi, KisMarket::Kospi).unwrap()[0];
+        assert_eq!(r.sector, Some("전기전자"));
+
+        let kosdaq = master_line("000990", "반도체회사", "ST", &[(3, "101510431159")]);
+        let r = &parse_master_lines(&kosdaq, KisMarket::Kosdaq).unwrap()[0];
+        assert_eq!(r.sector, Some("반도체"));
+    }
+
+    #[test]
+    fn line_without_a_tail_has_no_instrument_type() {
+        let mut line = b"005930   KR7005930003".to_vec();
+        let (name_bytes, _, _) = EUC_KR.encode("삼성전자");
+        line.extend_from_slice(&name_bytes);
+        line.push(b'\n');
+
+        let parsed = parse_master_lines(&line, KisMarket::Kospi).unwrap();
+        assert_eq!(parsed[0].name, "삼성전자");
+        assert_eq!(parsed[0].instrument_type(), None);
+    }
+
+    fn stub_client(base_url: String, concurrency: usize) -> KisClient {
+        KisClient {
+            http: reqwest::Client::new(),
+            base_url,
+            appkey: "key".to_string(),
+            appsecret: "secret".to_string(),
+            limiter: Arc::new(RateLimiter::new(1000.0)),
+            concurrency,
+            request_timeout: DEFAULT_REQUEST_TIMEOUT,
+            request_timeouts: AtomicUsize::new(0),
+            max_failure_rate: DEFAULT_MAX_FAILURE_RATE,
+            feature_validator: FeatureValidator::default(),
+            late_data_retry_delay: Duration::ZERO,
+            min_items: BTreeMap::new(),
+            chart_max_pages: DEFAULT_CHART_MAX_PAGES,
+            investor_flows: false,
+            markets: vec![KisMarket::Kospi],
+            max_tickers: None,
+            token_cache: tokio::sync::Mutex::new(None),
+            db_pool: None,
+            token_env_key: "test".to_string(),
+            resume: false,
+        }
+    }
+
+    fn stock(code: &str) -> KisMasterRecord {
+        KisMasterRecord::new(KisMarket::Kospi, code.to_string(), format!("Stock {code}"))
+    }
+
+    struct DailyChartStub {
+        base_url: String,
+        max_in_flight: std::sync::Arc<std::sync::atomic::AtomicUsize>,
+        requested: std::sync::Arc<std::sync::Mutex<Vec<String>>>,
+    }
+
+    /// Stub daily-chart endpoint that takes 50ms per request and records the requested codes and
+    /// the most requests it saw in flight at once. It returns a weekday bar for every day of the
+    /// requested window, newest first, closing at 100 except 110 on the last day. Ticker `000404`
+    /// gets a 404, `000204` a "no data" reply and `000500` another business error (HTTP 200). The investor endpoint has five days up to 2026-01-06 (404 for `000003`).
+    async fn serve_daily_chart() -> DailyChartStub {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let max_in_flight = Arc::new(AtomicUsize::new(0));
+        let requested = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = max_in_flight.clone();
+        let log = requested.clone();
+        let app =
+            axum::Router::new()
+                .route(
+                    "/uapi/domestic-stock/v1/quotations/inquire-daily-itemchartprice",
+                    axum::routing::get(
+                        move |headers: axum::http::HeaderMap,
+                              axum::extract::Query(q): axum::extract::Query<
+                            BTreeMap<String, String>,
+                        >| {
+                            let in_flight = in_flight.clone();
+                            let seen = seen.clone();
+                            let log = log.clone();
+                            async move {
+                                let earlier = {
+                                    let mut log = log.lock().unwrap();
+                                    let earlier =
+                                        log.iter().filter(|t| **t == q["FID_INPUT_ISCD"]).count();
+                                    log.push(q["FID_INPUT_ISCD"].clone());
+                                    earlier
+                                };
+                                let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                                seen.fetch_max(now, Ordering::SeqCst);
+                                tokio::time::sleep(Duration::from_millis(50)).await;
+                                in_flight.fetch_sub(1, Ordering::SeqCst);
+                                let business_error = |msg_cd: &str, msg1: &str| {
+                                    Ok((
+                                        [("tr_cont", "")],
+                                        axum::Json(serde_json::json!({
+                                            "rt_cd": "1", "msg_cd": msg_cd, "msg1": msg1, "output2": [],
+                                        })),
+                                    ))
+                                };
+                                match q["FID_INPUT_ISCD"].as_str() {
+                                    "000404" => return Err(StatusCode::NOT_FOUND),
+                                    // Fails once, then works.
+                                    "000409" if earlier == 0 => return Err(StatusCode::CONFLICT),
+                                    "000408" => tokio::time::sleep(Duration::from_secs(5)).await,
+                                    // Hangs once, then works.
+                                    "000407" if earlier == 0 => {
+                                        tokio::time::sleep(Duration::from_secs(5)).await
+                                    }
+                                    "000204" => {
+                                        return business_error("KIOK0560", "조회할 자료가 없습니다")
+                                    }
+                                    "000500" => {
+                                        return business_error(
+                                            "EGW00123",
+                                            "기간이 만료된 token 입니다",
+                                        )
+                                    }
+                                    _ => {}
+                                }
+                                let ymd =
+                                    |k: &str| NaiveDate::parse_from_str(&q[k], "%Y%m%d").unwrap();
+                                let (from, to) = (ymd("FID_INPUT_DATE_1"), ymd("FID_INPUT_DATE_2"));
+                                // 000410's as-of bar is published late: missing on the first request.
+                                let late = q["FID_INPUT_ISCD"] == "000410" && earlier == 0;
+                                // 000600 pages 10 bars at a time; a continued page repeats the bar
+                                // after its end date.
+                                let paged = q["FID_INPUT_ISCD"] == "000600";
+                                // 000700's as-of close is triple the day before's: an implausible
+                                // ret_1d.
+                                let jump = q["FID_INPUT_ISCD"] == "000700";
+                                let continued = headers
+                                    .get("tr_cont")
+                                    .is_some_and(|v| v.as_bytes() == b"N");
+                                let newest = if continued { to.succ_opt().unwrap() } else { to };
+                                let mut bars: Vec<_> = newest
+                                    .iter_days()
+                                    .rev()
+                                    .take_while(|d| *d >= from)
+                                    .filter(|d| d.weekday().number_from_monday() <= 5)
+                                    .filter(|d| !(late && *d == to))
+                                    .map(|d| {
+                                        serde_json::json!({
+                                            "stck_bsop_date": d.format("%Y%m%d").to_string(),
+                                            "stck_clpr": match (d == to && !continued, jump) {
+                                                (true, true) => "300",
+                                                (true, false) => "110",
+                                                (false, _) => "100",
+                                            },
+                                            "acml_tr_pbmn": "1000000", "acml_vol": "100",
+                                        })
+                                    })
+                                    .collect();
+                                let mut tr_cont = "D";
+                                if paged && bars.len() > 10 {
+                                    bars.truncate(10);
+                                    tr_cont = "M";
+                                }
+                                Ok(([("tr_cont", tr_cont)], axum::Json(serde_json::json!({
+                                    "output1": {
+                                        "lstn_stcn": "1000000", "hts_avls": "99",
+                                        "w52_hgpr": "125", "w52_lwpr": "0", "divi_rate": "2.5",
+                                    },
+                                    "output2": bars,
+                                }))))
+                            }
+                        },
+                    ),
+                )
+                .route(
+                    "/uapi/domestic-stock/v1/quotations/inquire-investor",
+                    axum::routing::get(
+                        |axum::extract::Query(q): axum::extract::Query<
+                            BTreeMap<String, String>,
+                        >| async move {
+                            if q["FID_INPUT_ISCD"] == "000003" {
+                                return Err(StatusCode::NOT_FOUND);
+                            }
+                            let day = |date: &str| {
+                                serde_json::json!({
+                                    "stck_bsop_date": date,
+                                    "frgn_ntby_tr_pbmn": "1500",
+                                    "orgn_ntby_tr_pbmn": "-200",
+                                })
+                            };
+                            let days = ["20260106", "20260105", "20260102", "20251231", "20251230"];
+                            Ok(axum::Json(serde_json::json!({ "output": days.map(day) })))
+                        },
+                    ),
+                )
+                .route(
+                    "/uapi/domestic-stock/v1/quotations/inquire-daily-indexchartprice",
+                    axum::routing::get(
+                        |axum::extract::Query(q): axum::extract::Query<
+                            BTreeMap<String, String>,
+                        >| async move {
+                            // KOSPI only; KOSDAQ (1001) is unavailable.
+                            if q["FID_INPUT_ISCD"] != "0001" {
+                                return Err(StatusCode::NOT_FOUND);
+                            }
+                            Ok(axum::Json(serde_json::json!({
+                                "output2": [
+                                    {"stck_bsop_date": "20260106", "bstp_nmix_prpr": "2425.00"},
+                                    {"stck_bsop_date": "20260105", "bstp_nmix_prpr": "2500.00"},
+                                ],
+                            })))
+                        },
+                    ),
+                );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        DailyChartStub {
+            base_url: format!("http://{addr}"),
+            max_in_flight,
+            requested,
+        }
+    }
+
+    fn token() -> KisToken {
+        KisToken {
+            access_token: "token".to_string(),
+            access_token_token_expired: String::new(),
+            expires_in: 86400,
+        }
+    }
+
+    #[tokio::test]
+    async fn fetches_overlap_up_to_the_concurrency_limit() {
+        let stub = serve_daily_chart().await;
+        let client = stub_client(stub.base_url, 4);
+        let universe = [
+            "000008", "000404", "000001", "000006", "000003", "000007", "000002",
+        ]
+        .map(stock)
+        .to_vec();
+        let as_of = NaiveDate::from_ymd_opt(2026, 1, 6).unwrap();
+
+        let (items, stats) = client
+            .fetch_universe_daily(&token(), universe, as_of, None)
+            .await;
+
+        let peak = stub.max_in_flight.load(std::sync::atomic::Ordering::SeqCst);
+        assert!((2..=4).contains(&peak), "peak in-flight requests: {peak}");
+        assert_eq!(stats.failures, 1);
+        let tickers: Vec<&str> = items.iter().map(|i| i.ticker.as_str()).collect();
+        assert_eq!(
+            tickers,
+            [
+                "KRX:000001",
+                "KRX:000002",
+                "KRX:000003",
+                "KRX:000006",
+                "KRX:000007",
+                "KRX:000008"
+            ]
+        );
+        assert_eq!(items[0].close, Some(110.0));
+        let features = &items[0].features;
+        assert!((features["ret_1d"] - 0.1).abs() < 1e-9);
+        assert!((features["mom_20d"] - 0.1).abs() < 1e-9);
+        assert!(features["vol_20d"] > 0.0);
+        assert!(features["ma20_gap"] > 0.0);
+        assert_eq!(features["shares_outstanding"], 1_000_000.0);
+        assert_eq!(features["market_cap"], 110.0 * 1_000_000.0);
+        assert!((features["pct_off_52w_high"] - (110.0 / 125.0 - 1.0)).abs() < 1e-9);
+        assert!(!features.contains_key("pct_off_52w_low"));
+        assert!((features["dividend_yield"] - 0.025).abs() < 1e-12);
+    }
+
+    #[test]
+    fn dividend_yield_is_a_fraction() {
+        assert_eq!(dividend_yield(Some(2.5)), Some(0.025));
+        assert_eq!(dividend_yield(Some(0.0)), Some(0.0));
+        assert_eq!(dividend_yield(Some(-1.0)), None);
+        assert_eq!(dividend_yield(Some(f64::NAN)), None);
+        assert_eq!(dividend_yield(None), None);
+    }
+
+    #[tokio::test]
+    async fn daily_chart_pages_are_followed_and_merged() {
+        let stub = serve_daily_chart().await;
+        let mut client = stub_client(stub.base_url, 1);
+        let as_of = NaiveDate::from_ymd_opt(2026, 1, 6).unwrap();
+        let universe = vec![stock("000600")];
+
+        let (items, stats) = client
+            .fetch_universe_daily(&token(), universe.clone(), as_of, None)
+            .await;
+        assert_eq!(stats.failures, 0);
+        // 32 weekday bars in the 45-day window: four pages of up to 10, overlaps dropped.
+        assert_eq!(stub.requested.lock().unwrap().len(), 4);
+        let features = &items[0].features;
+        assert!((features["ret_1d"] - 0.1).abs() < 1e-9);
+        assert!((features["mom_20d"] - 0.1).abs() < 1e-9);
+
+        // Capped at two pages: 20 bars, too few for the 20-day return.
+        stub.requested.lock().unwrap().clear();
+        client.chart_max_pages = 2;
+        let (items, _) = client
+            .fetch_universe_daily(&token(), universe.clone(), as_of, None)
+            .await;
+        assert_eq!(stub.requested.lock().unwrap().len(), 2);
+        assert!(items[0].features.contains_key("mom_5d"));
+        assert!(!items[0].features.contains_key("mom_20d"));
+    }
+
+    fn bar(date: &str, close: &str) -> KisDailyBar {
+        serde_json::from_value(serde_json::json!({"stck_bsop_date": date, "stck_clpr": close}))
+            .unwrap()
+    }
+
+    #[test]
+    fn chart_pages_merge_one_bar_per_date_newest_first() {
+        let pages = vec![
+            vec![bar("20260106", "110"), bar("20260105", "100")],
+            // Overlaps the first page at its edge; the first page's bar is kept.
+            vec![bar("20260105", "99"), bar("20260102", "98")],
+            vec![],
+        ];
+        let merged: Vec<(String, String)> = merge_bars(pages)
+            .into_iter()
+            .map(|b| (b.stck_bsop_date, b.stck_clpr))
+            .collect();
+        assert_eq!(
+            merged,
+            [
+                ("20260106".to_string(), "110".to_string()),
+                ("20260105".to_string(), "100".to_string()),
+                ("20260102".to_string(), "98".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn chart_continuation_keys() {
+        assert!(has_more_pages(Some("M")));
+        assert!(has_more_pages(Some("F ")));
+        assert!(!has_more_pages(Some("D")));
+        assert!(!has_more_pages(Some("E")));
+        assert!(!has_more_pages(None));
+
+        let page = [bar("20260106", "110"), bar("20260105", "100")];
+        assert_eq!(
+            next_page_end(&page, "20251201").as_deref(),
+            Some("20260104")
+        );
+        // Already at the start of the window, or nothing dated to continue from.
+        assert_eq!(next_page_end(&page, "20260105"), None);
+        assert_eq!(next_page_end(&[bar("", "1")], "20251201"), None);
+    }
+
+    #[tokio::test]
+    async fn hung_requests_time_out_per_attempt_and_are_counted() {
+        let stub = serve_daily_chart().await;
+        let mut client = stub_client(stub.base_url, 2);
+        client.request_timeout = Duration::from_millis(100);
+        let universe = ["000001", "000407", "000408"].map(stock).to_vec();
+        let as_of = NaiveDate::from_ymd_opt(2026, 1, 6).unwrap();
+
+        let started = std::time::Instant::now();
+        let (items, stats) = client
+            .fetch_universe_daily(&token(), universe, as_of, None)
+            .await;
+
+        assert_eq!(items.len(), 2);
+        // 000407's one timeout counts although its second attempt succeeded.
+        assert_eq!(
+            (stats.failures, stats.timeouts, stats.timeout_failures),
+            (1, 7, 1)
+        );
+        assert_eq!(stats.failed[0].class, FailureClass::Timeout);
+        let attempts = stub
+            .requested
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|t| *t == "000408")
+            .count();
+        // Three attempts in the sweep and three more in the retry pass.
+        assert_eq!((attempts, stats.failed[0].attempts), (6, 6));
+        // Each pass is three 100ms attempts plus 1s and 2s of backoff, not two 5s hangs.
+        assert!(started.elapsed() < Duration::from_secs(9));
+    }
+
+    #[tokio::test]
+    async fn failed_stocks_are_retried_once_after_the_sweep() {
+        let stub = serve_daily_chart().await;
+        let client = stub_client(stub.base_url, 2);
+        let universe = ["000001", "000409", "000404"].map(stock).to_vec();
+        let as_of = NaiveDate::from_ymd_opt(2026, 1, 6).unwrap();
+
+        let (items, stats) = client
+            .fetch_universe_daily(&token(), universe, as_of, None)
+            .await;
+
+        let tickers: Vec<&str> = items.iter().map(|i| i.ticker.as_str()).collect();
+        assert_eq!(tickers, ["KRX:000001", "KRX:000409"]);
+        assert_eq!((stats.retried, stats.recovered), (2, 1));
+        assert_eq!(
+            stats.failed,
+            [FailedTicker {
+                ticker: "000404".to_string(),
+                class: FailureClass::Http,
+                attempts: 2,
+            }]
+        );
+        assert_eq!(
+            serde_json::to_value(&stats.failed).unwrap(),
+            serde_json::json!([{"ticker": "000404", "class": "http", "attempts": 2}])
+        );
+    }
+
+    #[tokio::test]
+    async fn stocks_missing_the_as_of_bar_are_refetched_after_the_delay() {
+        let stub = serve_daily_chart().await;
+        let mut client = stub_client(stub.base_url, 2);
+        client.late_data_retry_delay = Duration::from_millis(50);
+        let universe = ["000001", "000410", "000404"].map(stock).to_vec();
+        let as_of = NaiveDate::from_ymd_opt(2026, 1, 6).unwrap();
+
+        let (items, stats) = client
+            .fetch_universe_daily(&token(), universe, as_of, None)
+            .await;
+
+        assert_eq!(items.len(), 2);
+        assert_eq!((stats.retried, stats.recovered), (1, 0));
+        assert_eq!((stats.late_retried, stats.late_recovered), (1, 1));
+        assert_eq!(stats.failures, 1);
+        assert_eq!(stats.failed[0].ticker, "000404");
+    }
+
+    #[tokio::test]
+    async fn late_data_pass_is_skipped_with_a_zero_delay() {
+        let stub = serve_daily_chart().await;
+        let client = stub_client(stub.base_url, 1);
+        assert!(client.late_data_retry_delay.is_zero());
+        let as_of = NaiveDate::from_ymd_opt(2026, 1, 6).unwrap();
+
+        let (items, stats) = client
+            .fetch_universe_daily(&token(), vec![stock("000410")], as_of, None)
+            .await;
+
+        // The missing bar is an ordinary failure, picked up by the immediate retry pass.
+        assert_eq!(items.len(), 1);
+        assert_eq!((stats.retried, stats.recovered), (1, 1));
+        assert_eq!(stats.late_retried, 0);
+        let err = KisStockError::MissingAsOfBar {
+            ticker: "000410".to_string(),
+            as_of_date: as_of,
+        };
+        assert_eq!(
+            classify_failure(&err.into()),
+            (FailureClass::MissingAsOfBar, 1)
+        );
+    }
+
+    #[test]
+    fn failure_rate_threshold() {
+        assert!(!exceeds_failure_rate(0, 0, 0.05));
+        assert!(!exceeds_failure_rate(5, 100, 0.05));
+        assert!(exceeds_failure_rate(6, 100, 0.05));
+        assert!(exceeds_failure_rate(1, 2_700, 0.0));
+        assert!(!exceeds_failure_rate(800, 2_700, 1.0));
+
+        let err = KisIngestIncomplete {
+            as_of_date: NaiveDate::from_ymd_opt(2026, 1, 6).unwrap(),
+            reason: IncompleteReason::FailureRate {
+                total: 2_700,
+                max: 0.05,
+            },
+            failures: 800,
+            items: Vec::new(),
+            raw: Value::Null,
+        };
+        assert_eq!(
+            err.to_string(),
+            "KIS ingest for 2026-01-06 failed for 800 of 2700 stocks (29.6%), above KIS_MAX_FAILURE_RATE=0.05"
+        );
+    }
+
+    #[test]
+    fn markets_below_their_minimum_are_reported() {
+        let coverage = BTreeMap::from([
+            (
+                KisMarket::Kospi,
+                MarketCoverage {
+                    master: 960,
+                    resumed: 300,
+                    items: 600,
+                    failures: 60,
+                },
+            ),
+            (
+                KisMarket::Kosdaq,
+                MarketCoverage {
+                    master: 1_750,
+                    resumed: 0,
+                    items: 400,
+                    failures: 1_350,
+                },
+            ),
+        ]);
+        // Resumed rows count toward the minimum: KOSPI has 900.
+        let min = BTreeMap::from([(KisMarket::Kospi, 900), (KisMarket::Kosdaq, 1_400)]);
+        let shortfalls = coverage_shortfalls(&coverage, &min);
+        assert_eq!(
+            shortfalls,
+            [MarketShortfall {
+                market: KisMarket::Kosdaq,
+                min: 1_400,
+                got: 400
+            }]
+        );
+        // No minimum, no check; a market with a minimum that was not ingested has zero rows.
+        assert!(coverage_shortfalls(&coverage, &BTreeMap::new()).is_empty());
+        let konex = BTreeMap::from([(KisMarket::Konex, 1)]);
+        assert_eq!(coverage_shortfalls(&coverage, &konex)[0].got, 0);
+
+        let err = KisIngestIncomplete {
+            as_of_date: NaiveDate::from_ymd_opt(2026, 1, 6).unwrap(),
+            reason: IncompleteReason::Coverage(shortfalls),
+            failures: 1_410,
+            items: Vec::new(),
+            raw: Value::Null,
+        };
+        assert_eq!(
+            err.to_string(),
+            "KIS ingest for 2026-01-06 is below the minimum coverage: KOSDAQ has 400 rows (KIS_MIN_ITEMS_KOSDAQ=1400)"
+        );
+    }
+
+    #[test]
+    fn market_cap_uses_the_as_of_close() {
+        assert_eq!(
+            market_cap(70_000.0, Some(1_000.0), Some(4_000.0)),
+            Some(70_000_000.0)
+        );
+        // Without a share count, fall back to the quote's 억원 figure.
+        assert_eq!(market_cap(70_000.0, None, Some(4_000.0)), Some(4.0e11));
+        assert_eq!(market_cap(70_000.0, None, Some(0.0)), None);
+        assert_eq!(market_cap(70_000.0, None, None), None);
+    }
+
+    #[tokio::test]
+    async fn concurrency_one_fetches_sequentially() {
+        let stub = serve_daily_chart().await;
+        let client = stub_client(stub.base_url, 1);
+        let universe = ["000001", "000002", "000003"].map(stock).to_vec();
+        let as_of = NaiveDate::from_ymd_opt(2026, 1, 6).unwrap();
+
+        let (items, stats) = client
+            .fetch_universe_daily(&token(), universe, as_of, None)
+            .await;
+
+        assert_eq!(
+            stub.max_in_flight.load(std::sync::atomic::Ordering::SeqCst),
+            1
+        );
+        assert_eq!((items.len(), stats.failures), (3, 0));
+    }
+
+    #[tokio::test]
+    async fn no_data_replies_are_skipped_and_other_business_errors_fail() {
+        let stub = serve_daily_chart().await;
+        let client = stub_client(stub.base_url, 2);
+        let universe = ["000001", "000204", "000500"].map(stock).to_vec();
+        let as_of = NaiveDate::from_ymd_opt(2026, 1, 6).unwrap();
+
+        let (items, stats) = client
+            .fetch_universe_daily(&token(), universe, as_of, None)
+            .await;
+
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].ticker, "KRX:000001");
+        assert_eq!(
+            stats,
+            FetchStats {
+                failures: 1,
+                timeouts: 0,
+                timeout_failures: 0,
+                no_data: 1,
+                retried: 1,
+                recovered: 0,
+                late_retried: 0,
+                late_recovered: 0,
+                failed: vec![FailedTicker {
+                    ticker: "000500".to_string(),
+                    class: FailureClass::Rejected,
+                    attempts: 2,
+                }],
+            }
+        );
+
+        let err = client
+            .get_quotation::<KisDailyItemChartPriceResponse>(
+                &token(),
+                "inquire-daily-itemchartprice",
+                "FHKST03010100",
+                &[("FID_INPUT_ISCD", "000500")],
+                "000500",
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "KIS inquire-daily-itemchartprice error rt_cd=1 msg_cd=EGW00123: 기간이 만료된 token 입니다"
+        );
+    }
+
+    #[test]
+    fn rt_cd_payloads_map_to_outcomes() {
+        let status =
+            |body: serde_json::Value| -> KisStatus { serde_json::from_value(body).unwrap() };
+        assert_eq!(
+            status(serde_json::json!({"rt_cd": "0", "msg_cd": "MCA00000", "msg1": "정상처리 되었습니다."}))
+                .outcome(),
+            KisOutcome::Ok
+        );
+        // No status fields at all (stubs, some endpoints).
+        assert_eq!(
+            status(serde_json::json!({"output2": []})).outcome(),
+            KisOutcome::Ok
+        );
+        assert_eq!(
+            status(serde_json::json!({"rt_cd": "1", "msg_cd": "EGW00201", "msg1": "초당 거래건수를 초과하였습니다."}))
+                .outcome(),
+            KisOutcome::RateLimited
+        );
+        // Unknown code, known message.
+        assert_eq!(
+            status(serde_json::json!({"rt_cd": "1", "msg_cd": "EGW09999", "msg1": "초당 거래건수를 초과하였습니다."}))
+                .outcome(),
+            KisOutcome::RateLimited
+        );
+        assert_eq!(
+            status(serde_json::json!({"rt_cd": "1", "msg_cd": "KIOK0560", "msg1": "조회할 자료가 없습니다"}))
+                .outcome(),
+            KisOutcome::NoData
+        );
+        assert_eq!(
+            status(serde_json::json!({"rt_cd": "7", "msg_cd": "XXXX0000", "msg1": "조회할 내용이 없습니다"}))
+                .outcome(),
+            KisOutcome::NoData
+        );
+        let failed = status(
+            serde_json::json!({"rt_cd": "1", "msg_cd": "EGW00123", "msg1": "기간이 만료된 token 입니다"}),
+        );
+        assert_eq!(failed.outcome(), KisOutcome::Failed);
+        assert_eq!(
+            failed.describe(),
+            "rt_cd=1 msg_cd=EGW00123: 기간이 만료된 token 입니다"
+        );
+    }
+
+    #[tokio::test]
+    async fn investor_flows_are_merged_when_enabled() {
+        let stub = serve_daily_chart().await;
+        let mut client = stub_client(stub.base_url, 2);
+        client.investor_flows = true;
+        let universe = ["000001", "000003"].map(stock).to_vec();
+        let as_of = NaiveDate::from_ymd_opt(2026, 1, 6).unwrap();
+
+        let (items, stats) = client
+            .fetch_universe_daily(&token(), universe, as_of, None)
+            .await;
+
+        assert_eq!((items.len(), stats.failures), (2, 0));
+        let flows = &items[0].features;
+        assert_eq!(flows["frg_net_buy_1d"], 1.5e9);
+        assert_eq!(flows["inst_net_buy_1d"], -2.0e8);
+        assert_eq!(flows["frg_net_buy_5d"], 7.5e9);
+        assert_eq!(flows["inst_net_buy_5d"], -1.0e9);
+        // A failed flows request drops only the flow features.
+        let degraded = &items[1].features;
+        assert!(degraded.contains_key("ret_1d"));
+        assert!(!degraded.keys().any(|k| k.contains("net_buy")));
+    }
+
+    #[tokio::test]
+    async fn unavailable_indices_are_left_out() {
+        let stub = serve_daily_chart().await;
+        let client = stub_client(stub.base_url, 1);
+        let as_of = NaiveDate::from_ymd_opt(2026, 1, 6).unwrap();
+
+        let indices = client.fetch_market_indices(&token(), as_of).await;
+        assert_eq!(
+            indices,
+            [MarketIndexDaily {
+                as_of_date: as_of,
+                market: "KOSPI".to_string(),
+                close: 2425.0,
+                ret_1d: Some(2425.0 / 2500.0 - 1.0),
+            }]
+        );
+    }
+
+    #[test]
+    fn index_daily_needs_the_as_of_bar() {
+        let bar = |date: &str, close: &str| KisIndexBar {
+            stck_bsop_date: date.to_string(),
+            bstp_nmix_prpr: close.to_string(),
+        };
+        let as_of = NaiveDate::from_ymd_opt(2026, 1, 5).unwrap();
+        // Oldest first, with a bar after the as-of date and a blank close.
+        let bars = [
+            bar("20251231", "800.0"),
+            bar("20260102", ""),
+            bar("20260105", "792.0"),
+            bar("20260106", "700.0"),
+        ];
+        let index = index_daily("KOSDAQ", &bars, as_of).unwrap();
+        assert_eq!(index.close, 792.0);
+        assert!((index.ret_1d.unwrap() - (-0.01)).abs() < 1e-12);
+
+        // Only the as-of bar: a close but no return.
+        assert_eq!(
+            index_daily("KOSDAQ", &bars[2..3], as_of).unwrap().ret_1d,
+            None
+        );
+        // Holiday or missing day: nothing.
+        let holiday = NaiveDate::from_ymd_opt(2026, 1, 1).unwrap();
+        assert_eq!(index_daily("KOSDAQ", &bars, holiday), None);
+    }
+
+    #[test]
+    fn investor_flow_features_need_the_as_of_day_and_a_full_window() {
+        let day = |date: &str, frg: &str, inst: &str| KisInvestorDay {
+            stck_bsop_date: date.to_string(),
+            frgn_ntby_tr_pbmn: frg.to_string(),
+            orgn_ntby_tr_pbmn: inst.to_string(),
+        };
+        let as_of = NaiveDate::from_ymd_opt(2026, 1, 6).unwrap();
+        let days = [
+            // In progress after the as-of date: ignored.
+            day("20260107", "", ""),
+            day("20260106", "10", "-1"),
+            day("20260105", "20", ""),
+            day("20260102", "30", "3"),
+            day("20251231", "40", "4"),
+            day("20251230", "50", "5"),
+            day("20251229", "60", "6"),
+        ];
+        let f = investor_flow_features(&days, as_of);
+        assert_eq!(f["frg_net_buy_1d"], 1.0e7);
+        assert_eq!(f["inst_net_buy_1d"], -1.0e6);
+        assert_eq!(f["frg_net_buy_5d"], 1.5e8);
+        // A blank institutional value inside the window drops that 5d sum.
+        assert!(!f.contains_key("inst_net_buy_5d"));
+
+        // Only four days up to the as-of date: 1d only.
+        let f = investor_flow_features(&days[..5], as_of);
+        assert_eq!(f.len(), 2);
+        // As-of day missing (backfill past the endpoint's window): nothing.
+        assert!(investor_flow_features(&days[2..], as_of).is_empty());
+    }
+
+    /// Token endpoints that count issuances (each taking 200ms) and revocations.
+    async fn serve_oauth() -> (
+        String,
+        std::sync::Arc<std::sync::atomic::AtomicUsize>,
+        std::sync::Arc<std::sync::atomic::AtomicUsize>,
+    ) {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let issued = Arc::new(AtomicUsize::new(0));
+        let revoked = Arc::new(AtomicUsize::new(0));
+        let (issue_count, revoke_count) = (issued.clone(), revoked.clone());
+        let app = axum::Router::new()
+            .route(
+                "/oauth2/tokenP",
+                axum::routing::post(move || {
+                    let issued = issue_count.clone();
+                    async move {
+                        tokio::time::sleep(Duration::from_millis(200)).await;
+                        let n = issued.fetch_add(1, Ordering::SeqCst) + 1;
+                        axum::Json(serde_json::json!({
+                            "access_token": format!("token-{n}"),
+                            "access_token_token_expired": "2099-01-01 00:00:00",
+                            "expires_in": 86400,
+                        }))
+                    }
+                }),
+            )
+            .route(
+                "/oauth2/revokeP",
+                axum::routing::post(move || {
+                    let revoked = revoke_count.clone();
+                    async move {
+                        revoked.fetch_add(1, Ordering::SeqCst);
+                        axum::Json(serde_json::json!({"code": 200, "message": "ok"}))
+                    }
+                }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        (format!("http://{addr}"), issued, revoked)
+    }
+
+    #[tokio::test]
+    async fn rejected_credentials_are_an_auth_error() {
+        use crate::ingest::provider::DataProviderClient;
+
+        let app = axum::Router::new().route(
+            "/oauth2/tokenP",
+            axum::routing::post(|| async {
+                (
+                    axum::http::StatusCode::FORBIDDEN,
+                    axum::Json(serde_json::json!({"error_code": "EGW00103"})),
+                )
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        let client = stub_client(format!("http://{addr}"), 1);
+        // The preflight check fails the same way, before any quotation request.
+        let err = client.health_check().await.unwrap_err();
+        assert!(matches!(err, IngestError::Auth(_)), "{err:?}");
+
+        let as_of = NaiveDate::from_ymd_opt(2026, 1, 27).unwrap();
+        let err = client.fetch_daily_features(as_of).await.unwrap_err();
+        assert!(matches!(err, IngestError::Auth(_)), "{err:?}");
+        assert!(
+            err.run_error().starts_with("Auth: KIS token HTTP 403"),
+            "{}",
+            err.run_error()
+        );
+    }
+
+    #[test]
+    fn incomplete_ingests_are_missing_data() {
+        let err = ingest_error(
+            KisIngestIncomplete {
+                as_of_date: NaiveDate::from_ymd_opt(2026, 1, 27).unwrap(),
+                reason: IncompleteReason::FailureRate {
+                    total: 10,
+                    max: 0.1,
+                },
+                failures: 5,
+                items: Vec::new(),
+                raw: Value::Null,
+            }
+            .into(),
+        );
+        assert_eq!(err.variant_name(), "MissingData");
+        assert_eq!(
+            err.inner()
+                .downcast_ref::<KisIngestIncomplete>()
+                .map(|p| p.failures),
+            Some(5)
+        );
+    }
+
+    #[tokio::test]
+    async fn concurrent_clients_sharing_a_db_issue_one_token() {
+        use std::sync::atomic::Ordering;
+
+        let Some(pool) = crate::storage::test_support::database("KIS token lock test").await else {
+            return;
+        };
+        let env = format!("test-token-lock-{}", uuid::Uuid::new_v4());
+
+        let (base_url, issued, revoked) = serve_oauth().await;
+        let client = || KisClient {
+            db_pool: Some(pool.clone()),
+            token_env_key: env.clone(),
+            ..stub_client(base_url.clone(), 1)
+        };
+        let (a, b) = (client(), client());
+
+        let (ta, tb) = tokio::join!(a.get_access_token_cached(), b.get_access_token_cached());
+        let (ta, tb) = (ta.unwrap(), tb.unwrap());
+        assert_eq!(issued.load(Ordering::SeqCst), 1);
+        assert_eq!(ta.access_token, tb.access_token);
+
+        a.revoke_token().await.unwrap();
+        assert_eq!(revoked.load(Ordering::SeqCst), 1);
+        assert!(load_token_from_db(&pool, &env).await.unwrap().is_none());
+        // Nothing left to revoke.
+        a.revoke_token().await.unwrap();
+        assert_eq!(revoked.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn resume_skips_checkpointed_tickers_and_checkpoints_the_rest() {
+        use crate::storage::kis_progress;
+
+        let Some(pool) = crate::storage::test_support::database("resumable ingest test").await
+        else {
+            return;
+        };
+        let as_of = NaiveDate::from_ymd_opt(2031, 3, 4).unwrap();
+        kis_progress::clear(&pool, as_of).await.unwrap();
+
+        // A previous run got as far as 000001.
+        let stub = serve_daily_chart().await;
+        let client = stub_client(stub.base_url.clone(), 2);
+        let (first, _) = client
+            .fetch_universe_daily(&token(), vec![stock("000001")], as_of, Some(&pool))
+            .await;
+        assert_eq!(first.len(), 1);
+
+        let mut universe = ["000001", "000002", "000404", "000003", "000700"]
+            .map(stock)
+            .to_vec();
+        let resumed = skip_checkpointed(&pool, as_of, &mut universe)
+            .await
+            .unwrap();
+        assert_eq!(resumed, 1);
+        let (items, stats) = client
+            .fetch_universe_daily(&token(), universe, as_of, Some(&pool))
+            .await;
+        assert_eq!((items.len(), stats.failures), (3, 1));
+        // Returned as fetched, for the whole-payload check...
+        let jumped = items.iter().find(|i| i.ticker == "KRX:000700").unwrap();
+        assert!((jumped.features["ret_1d"] - 2.0).abs() < 1e-9);
+        // ...but checkpointed validated, since a resumed run will not fetch it again.
+        let stored: bool = sqlx::query_scalar(
+            "SELECT features ? 'ret_1d' FROM stock_features_daily \
+             WHERE as_of_date = $1 AND ticker = 'KRX:000700'",
+        )
+        .bind(as_of)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert!(!stored);
+
+        let mut requested = stub.requested.lock().unwrap().clone();
+        requested.sort();
+        // 000404 fails in the sweep and again in the retry pass.
+        assert_eq!(
+            requested,
+            ["000001", "000002", "000003", "000404", "000404", "000700"]
+        );
+        let mut done: Vec<String> = kis_progress::completed_tickers(&pool, as_of)
+            .await
+            .unwrap()
+            .into_iter()
+            .collect();
+        done.sort();
+        // The failed ticker is left for the next attempt.
+        assert_eq!(
+            done,
+            ["KRX:000001", "KRX:000002", "KRX:000003", "KRX:000700"]
+        );
+        let rows = crate::storage::stock_features::count_features_by_date(&pool, as_of, as_of)
+            .await
+            .unwrap();
+        assert_eq!(rows.get(&as_of), Some(&4));
+
+        assert_eq!(kis_progress::clear(&pool, as_of).await.unwrap(), 4);
     }
 }
@@ -1,3 +1,95 @@
+pub mod csv;
+pub mod error;
+pub mod feature_validator;
+pub mod file;
+pub mod history;
 pub mod kis;
+pub mod object_storage;
 pub mod provider;
+pub mod rate_limit;
+pub mod sector;
+pub mod stub;
 pub mod types;
+
+use crate::config::Settings;
+use provider::DataProviderClient;
+
+/// Which [`DataProviderClient`] implementation `--ingest-external` uses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProviderKind {
+    HttpJson,
+    Kis,
+    File,
+    Csv,
+    ObjectStore,
+}
+
+impl ProviderKind {
+    pub fn parse(s: &str) -> anyhow::Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "http-json" | "http_json" => Ok(ProviderKind::HttpJson),
+            "kis" => Ok(ProviderKind::Kis),
+            "file" => Ok(ProviderKind::File),
+            "csv" => Ok(ProviderKind::Csv),
+            "object-store" | "object_store" => Ok(ProviderKind::ObjectStore),
+            other => anyhow::bail!(
+                "unknown data provider: {other} (expected http-json|kis|file|csv|object-store)"
+            ),
+        }
+    }
+}
+
+/// Builds the data provider for `kind`. `file` and `csv` need `file_path`: a saved JSON payload,
+/// or a directory of per-date CSVs (a directory given to `file` is read as CSVs too). `pool` is
+/// used by KIS for its persistent token cache. `object-store` reads `DATA_PROVIDER_OBJECT_URL`.
+pub fn data_provider(
+    settings: &Settings,
+    kind: ProviderKind,
+    file_path: Option<&std::path::Path>,
+    pool: Option<sqlx::PgPool>,
+) -> anyhow::Result<Box<dyn DataProviderClient>> {
+    Ok(match kind {
+        ProviderKind::HttpJson => {
+            Box::new(provider::HttpJsonDataProvider::from_settings(settings)?)
+        }
+        ProviderKind::Kis => {
+            let client = kis::KisClient::from_settings_prod(settings)?;
+            Box::new(match pool {
+                Some(pool) => client.with_db_pool(pool),
+                None => client,
+            })
+        }
+        ProviderKind::ObjectStore => Box::new(object_storage::ObjectStoreDataProvider::from_env()?),
+        ProviderKind::File | ProviderKind::Csv => {
+            let path = file_path.ok_or_else(|| {
+                anyhow::anyhow!("the file and csv data providers require --provider-file")
+            })?;
+            if kind == ProviderKind::Csv || path.is_dir() {
+                Box::new(csv::CsvFileDataProvider::new(path))
+            } else {
+                Box::new(file::FileDataProvider::new(path))
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_provider_kinds() {
+        assert_eq!(
+            ProviderKind::parse("http-json").unwrap(),
+            ProviderKind::HttpJson
+        );
+        assert_eq!(ProviderKind::parse(" KIS ").unwrap(), ProviderKind::Kis);
+        assert_eq!(ProviderKind::parse("file").unwrap(), ProviderKind::File);
+        assert_eq!(ProviderKind::parse("csv").unwrap(), ProviderKind::Csv);
+        assert_eq!(
+            ProviderKind::parse("object-store").unwrap(),
+            ProviderKind::ObjectStore
+        );
+        assert!(ProviderKind::parse("parquet").is_err());
+    }
+}
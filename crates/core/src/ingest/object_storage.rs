@@ -0,0 +1,252 @@
+//! [`ObjectStoreDataProvider`]: reads the vendor's daily payload from an object store bucket
+//! (`s3://`, `gs://`, or `file://` for local replays) at `{prefix}/{as_of_date}.json`. The
+//! payload is the same `DailyFeaturesResponse` JSON the HTTP provider serves and goes through
+//! the same checks.
+
+use crate::ingest::error::IngestError;
+use crate::ingest::feature_validator::FeatureValidator;
+use crate::ingest::provider::{validate_response, DataProviderClient, Fetched};
+use crate::ingest::types::DailyFeaturesResponse;
+use anyhow::Context;
+use chrono::NaiveDate;
+use object_store::aws::AmazonS3Builder;
+use object_store::gcp::GoogleCloudStorageBuilder;
+use object_store::local::LocalFileSystem;
+use object_store::path::Path;
+use object_store::{GetOptions, ObjectStore, ObjectStoreScheme};
+use serde_json::Value;
+use std::sync::Arc;
+
+#[derive(Debug, Clone)]
+pub struct ObjectStoreDataProvider {
+    store: Arc<dyn ObjectStore>,
+    /// `DATA_PROVIDER_OBJECT_URL`, for messages.
+    url: String,
+    prefix: Path,
+    feature_validator: FeatureValidator,
+}
+
+impl ObjectStoreDataProvider {
+    /// From `DATA_PROVIDER_OBJECT_URL`.
+    pub fn from_env() -> anyhow::Result<Self> {
+        let url = std::env::var("DATA_PROVIDER_OBJECT_URL")
+            .ok()
+            .filter(|s| !s.trim().is_empty())
+            .context("DATA_PROVIDER_OBJECT_URL is required for --provider object-store")?;
+        Self::from_url(url.trim())
+    }
+
+    /// `s3://bucket/prefix` (credentials and region from the standard `AWS_*` variables),
+    /// `gs://bucket/prefix` (`GOOGLE_*`), or `file:///dir`.
+    pub fn from_url(url: &str) -> anyhow::Result<Self> {
+        let parsed = reqwest::Url::parse(url)
+            .with_context(|| format!("DATA_PROVIDER_OBJECT_URL {url:?} is not a URL"))?;
+        let (scheme, prefix) = ObjectStoreScheme::parse(&parsed)
+            .with_context(|| format!("unrecognised DATA_PROVIDER_OBJECT_URL {url:?}"))?;
+        let store: Arc<dyn ObjectStore> = match scheme {
+            ObjectStoreScheme::AmazonS3 => Arc::new(
+                AmazonS3Builder::from_env()
+                    .with_url(url)
+                    .build()
+                    .context("failed to configure the S3 object store")?,
+            ),
+            ObjectStoreScheme::GoogleCloudStorage => Arc::new(
+                GoogleCloudStorageBuilder::from_env()
+                    .with_url(url)
+                    .build()
+                    .context("failed to configure the GCS object store")?,
+            ),
+            ObjectStoreScheme::Local => Arc::new(LocalFileSystem::new()),
+            other => anyhow::bail!(
+                "unsupported DATA_PROVIDER_OBJECT_URL scheme {other:?} (expected s3://, gs:// or file://)"
+            ),
+        };
+        Ok(Self {
+            store,
+            url: url.trim_end_matches('/').to_string(),
+            prefix,
+            feature_validator: FeatureValidator::from_env(),
+        })
+    }
+
+    fn key(&self, as_of_date: NaiveDate) -> Path {
+        self.prefix.child(format!("{as_of_date}.json"))
+    }
+
+    fn parse(
+        &self,
+        bytes: &[u8],
+        as_of_date: NaiveDate,
+    ) -> Result<(DailyFeaturesResponse, Value), IngestError> {
+        let object = format!("{}/{as_of_date}.json", self.url);
+        let mut raw_json = serde_json::from_slice::<Value>(bytes)
+            .with_context(|| format!("{object}: not valid JSON"))
+            .map_err(IngestError::Parse)?;
+        let mut parsed = serde_json::from_value::<DailyFeaturesResponse>(raw_json.clone())
+            .with_context(|| format!("{object}: does not match DailyFeaturesResponse"))
+            .map_err(IngestError::Parse)?;
+        validate_response(&parsed, as_of_date)
+            .with_context(|| object.clone())
+            .map_err(IngestError::Validation)?;
+        self.feature_validator
+            .validate(&mut parsed.items, &mut raw_json)
+            .with_context(|| object.clone())
+            .map_err(IngestError::Validation)?;
+        Ok((parsed, raw_json))
+    }
+}
+
+/// The [`IngestError`] for a failed object read; a missing object means the vendor has not
+/// dropped the day's file (yet).
+fn read_error(err: object_store::Error, object: &str) -> IngestError {
+    match err {
+        object_store::Error::NotFound { .. } => IngestError::MissingData(anyhow::anyhow!(
+            "no payload at {object}: the provider has not published this date"
+        )),
+        object_store::Error::Unauthenticated { .. }
+        | object_store::Error::PermissionDenied { .. } => {
+            IngestError::Auth(anyhow::Error::new(err).context(format!("read {object}")))
+        }
+        err => IngestError::Io(anyhow::Error::new(err).context(format!("read {object}"))),
+    }
+}
+
+#[async_trait::async_trait]
+impl DataProviderClient for ObjectStoreDataProvider {
+    fn provider_name(&self) -> &'static str {
+        "object_store"
+    }
+
+    async fn fetch_daily_features(
+        &self,
+        as_of_date: NaiveDate,
+    ) -> Result<(DailyFeaturesResponse, Value), IngestError> {
+        match self
+            .fetch_daily_features_if_changed(as_of_date, None)
+            .await?
+        {
+            Fetched::Modified { resp, raw, .. } => Ok((resp, raw)),
+            Fetched::NotModified => Err(IngestError::Io(anyhow::anyhow!(
+                "object store reported not-modified for an unconditional read"
+            ))),
+        }
+    }
+
+    /// Reads with `If-None-Match: <etag>` and returns the object's ETag.
+    async fn fetch_daily_features_if_changed(
+        &self,
+        as_of_date: NaiveDate,
+        etag: Option<&str>,
+    ) -> Result<Fetched, IngestError> {
+        let object = format!("{}/{as_of_date}.json", self.url);
+        let options = GetOptions {
+            if_none_match: etag.map(str::to_string),
+            ..Default::default()
+        };
+        let res = match self.store.get_opts(&self.key(as_of_date), options).await {
+            Ok(res) => res,
+            Err(object_store::Error::NotModified { .. }) if etag.is_some() => {
+                return Ok(Fetched::NotModified)
+            }
+            Err(err) => return Err(read_error(err, &object)),
+        };
+        let etag = res.meta.e_tag.clone();
+        let bytes = res.bytes().await.map_err(|e| read_error(e, &object))?;
+        let (resp, raw) = self.parse(&bytes, as_of_date)?;
+        Ok(Fetched::Modified { resp, raw, etag })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn payload(as_of: &str, ret_1d: f64) -> Vec<u8> {
+        serde_json::to_vec(&json!({
+            "schema_version": "1.0",
+            "as_of_date": as_of,
+            "items": [
+                {"ticker": "KRX:005930", "name": "Samsung", "trading_value": 1.0,
+                 "features": {"ret_1d": ret_1d}}
+            ]
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn reads_dated_objects_from_a_local_bucket() {
+        let dir = tempfile::tempdir().unwrap();
+        let prefix = dir.path().join("vendor/daily");
+        std::fs::create_dir_all(&prefix).unwrap();
+        std::fs::write(prefix.join("2026-01-27.json"), payload("2026-01-27", 0.01)).unwrap();
+        std::fs::write(prefix.join("2026-01-28.json"), payload("2026-01-27", 0.01)).unwrap();
+        std::fs::write(prefix.join("2026-01-29.json"), payload("2026-01-29", 0.9)).unwrap();
+
+        let url = format!("file://{}/", prefix.display());
+        let provider = ObjectStoreDataProvider::from_url(&url).unwrap();
+        let d = |s: &str| NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap();
+
+        let Fetched::Modified { resp, raw, etag } = provider
+            .fetch_daily_features_if_changed(d("2026-01-27"), None)
+            .await
+            .unwrap()
+        else {
+            panic!("expected a payload");
+        };
+        assert_eq!(resp.items[0].ticker, "KRX:005930");
+        assert_eq!(raw["feature_validation"]["rows"], 1);
+        let etag = etag.expect("local objects have an ETag");
+
+        let again = provider
+            .fetch_daily_features_if_changed(d("2026-01-27"), Some(&etag))
+            .await
+            .unwrap();
+        assert!(matches!(again, Fetched::NotModified));
+
+        let err = provider
+            .fetch_daily_features(d("2026-01-30"))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, IngestError::MissingData(_)), "{err:?}");
+        assert!(
+            err.to_string().contains("2026-01-30.json"),
+            "{}",
+            err.run_error()
+        );
+
+        // The same checks as the HTTP provider.
+        let err = provider
+            .fetch_daily_features(d("2026-01-28"))
+            .await
+            .unwrap_err();
+        assert_eq!(err.variant_name(), "Validation");
+        assert!(err.to_string().contains("as_of_date mismatch"), "{err}");
+        let err = provider
+            .fetch_daily_features(d("2026-01-29"))
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("FEATURE_MAX_VIOLATION_RATE"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn rejects_unsupported_urls() {
+        let err = ObjectStoreDataProvider::from_url("ftp://vendor/daily").unwrap_err();
+        assert!(format!("{err:#}").contains("ftp://vendor/daily"), "{err:#}");
+        assert!(ObjectStoreDataProvider::from_url("vendor/daily").is_err());
+    }
+
+    #[test]
+    fn s3_prefix_excludes_the_bucket() {
+        let provider = ObjectStoreDataProvider::from_url("s3://vendor-drop/kr/daily/").unwrap();
+        assert_eq!(
+            provider
+                .key(NaiveDate::from_ymd_opt(2026, 1, 27).unwrap())
+                .as_ref(),
+            "kr/daily/2026-01-27.json"
+        );
+    }
+}
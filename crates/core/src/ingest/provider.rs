@@ -1,23 +1,64 @@
 use crate::config::Settings;
-use crate::ingest::types::{DailyFeatureItem, DailyFeaturesResponse};
+use crate::ingest::error::{parse_retry_after, IngestError};
+use crate::ingest::feature_validator::FeatureValidator;
+use crate::ingest::types::{
+    DailyFeatureItem, DailyFeaturesResponse, SchemaVersion, SCHEMA_VERSION,
+};
 use anyhow::{Context, Result};
 use chrono::NaiveDate;
-use reqwest::header::{HeaderMap, HeaderValue};
+use reqwest::header::{HeaderMap, HeaderValue, ETAG, IF_NONE_MATCH};
+use reqwest::StatusCode;
 use serde_json::Value;
 use std::time::Duration;
 
 const DEFAULT_TIMEOUT_SECS: u64 = 30;
 const DEFAULT_PATH: &str = "/v1/stock_features_daily";
 const DEFAULT_RETRIES: u32 = 3;
+const DEFAULT_HEALTH_PATH: &str = "/healthz";
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
 
 #[async_trait::async_trait]
 pub trait DataProviderClient: Send + Sync {
     fn provider_name(&self) -> &'static str;
 
+    /// A cheap reachability check the worker runs once before fetching, so a dead provider
+    /// fails in seconds instead of after the fetch's retries. Defaults to `Ok`.
+    async fn health_check(&self) -> Result<(), IngestError> {
+        Ok(())
+    }
+
     async fn fetch_daily_features(
         &self,
         as_of_date: NaiveDate,
-    ) -> Result<(DailyFeaturesResponse, Value)>;
+    ) -> Result<(DailyFeaturesResponse, Value), IngestError>;
+
+    /// Like [`Self::fetch_daily_features`], but a provider that supports conditional requests may
+    /// answer [`Fetched::NotModified`] when its payload still matches `etag`. Others always fetch.
+    async fn fetch_daily_features_if_changed(
+        &self,
+        as_of_date: NaiveDate,
+        _etag: Option<&str>,
+    ) -> Result<Fetched, IngestError> {
+        let (resp, raw) = self.fetch_daily_features(as_of_date).await?;
+        Ok(Fetched::Modified {
+            resp,
+            raw,
+            etag: None,
+        })
+    }
+}
+
+/// What [`DataProviderClient::fetch_daily_features_if_changed`] got.
+#[derive(Debug)]
+pub enum Fetched {
+    Modified {
+        resp: DailyFeaturesResponse,
+        raw: Value,
+        /// The response's ETag, to send back next time.
+        etag: Option<String>,
+    },
+    /// HTTP 304: the payload behind `etag` is still current.
+    NotModified,
 }
 
 #[derive(Debug, Clone)]
@@ -26,7 +67,9 @@ pub struct HttpJsonDataProvider {
     base_url: String,
     api_key: Option<String>,
     path: String,
+    health_path: String,
     retries: u32,
+    feature_validator: FeatureValidator,
 }
 
 impl HttpJsonDataProvider {
@@ -49,6 +92,11 @@ impl HttpJsonDataProvider {
             .filter(|s| !s.trim().is_empty())
             .unwrap_or_else(|| DEFAULT_PATH.to_string());
 
+        let health_path = std::env::var("DATA_PROVIDER_HEALTH_PATH")
+            .ok()
+            .filter(|s| !s.trim().is_empty())
+            .unwrap_or_else(|| DEFAULT_HEALTH_PATH.to_string());
+
         let http = reqwest::Client::builder()
             .timeout(Duration::from_secs(timeout_secs))
             .build()
@@ -59,15 +107,17 @@ impl HttpJsonDataProvider {
             base_url,
             api_key,
             path,
+            health_path,
             retries,
+            feature_validator: FeatureValidator::from_env(),
         })
     }
 
-    fn url(&self) -> String {
-        let path = if self.path.starts_with('/') {
-            self.path.clone()
+    fn url(&self, path: &str) -> String {
+        let path = if path.starts_with('/') {
+            path.to_string()
         } else {
-            format!("/{}", self.path)
+            format!("/{path}")
         };
 
         format!("{}{}", self.base_url.trim_end_matches('/'), path)
@@ -81,9 +131,16 @@ impl HttpJsonDataProvider {
         Ok(headers)
     }
 
-    async fn fetch_once(&self, as_of_date: NaiveDate) -> Result<(DailyFeaturesResponse, Value)> {
-        let url = self.url();
-        let headers = self.headers()?;
+    async fn fetch_once(
+        &self,
+        as_of_date: NaiveDate,
+        etag: Option<&str>,
+    ) -> Result<Fetched, IngestError> {
+        let url = self.url(&self.path);
+        let mut headers = self.headers().map_err(IngestError::Validation)?;
+        if let Some(etag) = etag.and_then(|e| HeaderValue::from_str(e).ok()) {
+            headers.insert(IF_NONE_MATCH, etag);
+        }
 
         let res = self
             .http
@@ -92,37 +149,41 @@ impl HttpJsonDataProvider {
             .query(&[("as_of_date", as_of_date.to_string())])
             .send()
             .await
-            .context("data provider request failed")?;
+            .context("data provider request failed")
+            .map_err(IngestError::Io)?;
 
         let status = res.status();
+        if status == StatusCode::NOT_MODIFIED && etag.is_some() {
+            return Ok(Fetched::NotModified);
+        }
+        let retry_after = parse_retry_after(res.headers());
+        let etag = res
+            .headers()
+            .get(ETAG)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
         let text = res
             .text()
             .await
-            .context("failed to read provider response")?;
-        let raw_json = serde_json::from_str::<Value>(&text)
-            .with_context(|| format!("provider response is not valid JSON: {text}"))?;
+            .context("failed to read provider response")
+            .map_err(IngestError::Io)?;
 
         if !status.is_success() {
-            anyhow::bail!("data provider HTTP {status}: {raw_json}");
+            let err = anyhow::anyhow!("data provider HTTP {status}: {text}");
+            return Err(IngestError::from_status(status, retry_after, err));
         }
 
+        let raw_json = serde_json::from_str::<Value>(&text)
+            .with_context(|| format!("provider response is not valid JSON: {text}"))
+            .map_err(IngestError::Parse)?;
         let parsed = serde_json::from_value::<DailyFeaturesResponse>(raw_json.clone())
-            .context("failed to parse provider response into DailyFeaturesResponse")?;
-        Ok((parsed, raw_json))
-    }
-
-    fn validate(&self, resp: &DailyFeaturesResponse, expected: NaiveDate) -> Result<()> {
-        anyhow::ensure!(
-            resp.as_of_date == expected,
-            "provider as_of_date mismatch: expected {expected}, got {}",
-            resp.as_of_date
-        );
-
-        for item in &resp.items {
-            validate_item(item)?;
-        }
-
-        Ok(())
+            .context("failed to parse provider response into DailyFeaturesResponse")
+            .map_err(IngestError::Parse)?;
+        Ok(Fetched::Modified {
+            resp: parsed,
+            raw: raw_json,
+            etag,
+        })
     }
 }
 
@@ -132,25 +193,75 @@ impl DataProviderClient for HttpJsonDataProvider {
         "external_http_json"
     }
 
+    /// `GET <base_url><DATA_PROVIDER_HEALTH_PATH>` with a 5s timeout and no retries; any 2xx
+    /// passes.
+    async fn health_check(&self) -> Result<(), IngestError> {
+        let url = self.url(&self.health_path);
+        let res = self
+            .http
+            .get(&url)
+            .headers(self.headers().map_err(IngestError::Validation)?)
+            .timeout(HEALTH_CHECK_TIMEOUT)
+            .send()
+            .await
+            .with_context(|| format!("data provider health check {url} failed"))
+            .map_err(IngestError::Io)?;
+        let status = res.status();
+        if !status.is_success() {
+            let retry_after = parse_retry_after(res.headers());
+            let err = anyhow::anyhow!("data provider health check {url}: HTTP {status}");
+            return Err(IngestError::from_status(status, retry_after, err));
+        }
+        Ok(())
+    }
+
     async fn fetch_daily_features(
         &self,
         as_of_date: NaiveDate,
-    ) -> Result<(DailyFeaturesResponse, Value)> {
+    ) -> Result<(DailyFeaturesResponse, Value), IngestError> {
+        match self
+            .fetch_daily_features_if_changed(as_of_date, None)
+            .await?
+        {
+            Fetched::Modified { resp, raw, .. } => Ok((resp, raw)),
+            Fetched::NotModified => Err(IngestError::Http {
+                status: 304,
+                error: anyhow::anyhow!("data provider sent 304 to an unconditional request"),
+            }),
+        }
+    }
+
+    /// Sends `If-None-Match: <etag>` when given, and returns the response's `ETag`.
+    async fn fetch_daily_features_if_changed(
+        &self,
+        as_of_date: NaiveDate,
+        etag: Option<&str>,
+    ) -> Result<Fetched, IngestError> {
         let mut attempt: u32 = 0;
         loop {
             attempt += 1;
-            let res = self.fetch_once(as_of_date).await;
+            let res = self.fetch_once(as_of_date, etag).await;
             match res {
-                Ok((parsed, raw)) => {
-                    self.validate(&parsed, as_of_date)?;
-                    return Ok((parsed, raw));
+                Ok(Fetched::Modified {
+                    mut resp,
+                    mut raw,
+                    etag,
+                }) => {
+                    validate_response(&resp, as_of_date).map_err(IngestError::Validation)?;
+                    self.feature_validator
+                        .validate(&mut resp.items, &mut raw)
+                        .map_err(IngestError::Validation)?;
+                    return Ok(Fetched::Modified { resp, raw, etag });
                 }
+                Ok(Fetched::NotModified) => return Ok(Fetched::NotModified),
                 Err(err) => {
-                    if attempt >= self.retries {
+                    if !err.is_retryable() || attempt >= self.retries {
                         return Err(err);
                     }
-                    let backoff = Duration::from_secs(1 << (attempt - 1));
-                    tracing::warn!(attempt, ?backoff, error = %err, "data provider fetch failed; retrying");
+                    let backoff = err
+                        .retry_after()
+                        .unwrap_or_else(|| Duration::from_secs(1 << (attempt - 1)));
+                    tracing::warn!(attempt, ?backoff, kind = err.variant_name(), error = %err, "data provider fetch failed; retrying");
                     tokio::time::sleep(backoff).await;
                 }
             }
@@ -158,10 +269,51 @@ impl DataProviderClient for HttpJsonDataProvider {
     }
 }
 
-fn validate_item(item: &DailyFeatureItem) -> Result<()> {
+/// Accepts [`SCHEMA_VERSION`]'s major: a newer minor only adds fields (which land in `features`
+/// when numeric), so it is read with a warning. Any other major is rejected.
+pub(crate) fn check_schema_version(version: SchemaVersion) -> Result<()> {
+    anyhow::ensure!(
+        version.major == SCHEMA_VERSION.major,
+        "unsupported provider schema_version {version} (this build reads {}.x)",
+        SCHEMA_VERSION.major
+    );
+    if version.minor > SCHEMA_VERSION.minor {
+        tracing::warn!(
+            %version,
+            supported = %SCHEMA_VERSION,
+            "provider schema_version is newer than this build; unknown fields are kept as features or ignored"
+        );
+    }
+    Ok(())
+}
+
+/// The contract checks every JSON provider's payload goes through: a readable schema version,
+/// the requested as_of_date, and well-formed items.
+pub(crate) fn validate_response(resp: &DailyFeaturesResponse, expected: NaiveDate) -> Result<()> {
+    check_schema_version(resp.schema_version)?;
+    anyhow::ensure!(
+        resp.as_of_date == expected,
+        "provider as_of_date mismatch: expected {expected}, got {}",
+        resp.as_of_date
+    );
+
+    for item in &resp.items {
+        validate_item(item)?;
+    }
+
+    Ok(())
+}
+
+pub(crate) fn validate_item(item: &DailyFeatureItem) -> Result<()> {
     anyhow::ensure!(!item.ticker.trim().is_empty(), "ticker must be non-empty");
     anyhow::ensure!(!item.name.trim().is_empty(), "name must be non-empty");
     anyhow::ensure!(!item.features.is_empty(), "features must be non-empty");
+    if let Some(close) = item.close {
+        anyhow::ensure!(
+            close.is_finite() && close > 0.0,
+            "close must be a positive number (got {close})"
+        );
+    }
     Ok(())
 }
 
@@ -210,4 +362,130 @@ mod tests {
         let res = serde_json::from_value::<DailyFeaturesResponse>(v);
         assert!(res.is_err());
     }
+
+    #[tokio::test]
+    async fn health_check_hits_the_health_path() {
+        use axum::http::StatusCode as Status;
+
+        let app = axum::Router::new()
+            .route("/healthz", axum::routing::get(|| async { "ok" }))
+            .route(
+                "/status",
+                axum::routing::get(|| async { Status::SERVICE_UNAVAILABLE }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        let provider = |base_url: String, health_path: &str| HttpJsonDataProvider {
+            http: reqwest::Client::new(),
+            base_url,
+            api_key: None,
+            path: DEFAULT_PATH.to_string(),
+            health_path: health_path.to_string(),
+            retries: 1,
+            feature_validator: FeatureValidator::default(),
+        };
+
+        provider(format!("http://{addr}"), DEFAULT_HEALTH_PATH)
+            .health_check()
+            .await
+            .unwrap();
+
+        let err = provider(format!("http://{addr}"), "status")
+            .health_check()
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, IngestError::Http { status: 503, .. }),
+            "{err:?}"
+        );
+        assert!(err.to_string().contains("/status: HTTP 503"), "{err}");
+
+        // Nothing listening: a transport error, not a hang.
+        let closed = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let dead = closed.local_addr().unwrap();
+        drop(closed);
+        let err = provider(format!("http://{dead}"), DEFAULT_HEALTH_PATH)
+            .health_check()
+            .await
+            .unwrap_err();
+        assert_eq!(err.variant_name(), "Io");
+    }
+
+    #[tokio::test]
+    async fn sends_the_etag_back_and_short_circuits_on_304() {
+        use axum::http::{header, HeaderMap as Headers, StatusCode as Status};
+        use axum::response::IntoResponse;
+
+        let app = axum::Router::new().route(
+            "/v1/stock_features_daily",
+            axum::routing::get(|headers: Headers| async move {
+                if headers.get(header::IF_NONE_MATCH).map(|v| v.as_bytes()) == Some(b"\"v1\"") {
+                    return Status::NOT_MODIFIED.into_response();
+                }
+                (
+                    [(header::ETAG, "\"v1\"")],
+                    axum::Json(json!({
+                        "as_of_date": "2026-01-27",
+                        "items": [{"ticker": "KRX:005930", "name": "Samsung", "trading_value": 1.0,
+                                   "features": {"ret_1d": 0.01}}]
+                    })),
+                )
+                    .into_response()
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        let provider = HttpJsonDataProvider {
+            http: reqwest::Client::new(),
+            base_url: format!("http://{addr}"),
+            api_key: None,
+            path: DEFAULT_PATH.to_string(),
+            health_path: DEFAULT_HEALTH_PATH.to_string(),
+            retries: 1,
+            feature_validator: FeatureValidator::default(),
+        };
+        let as_of = NaiveDate::from_ymd_opt(2026, 1, 27).unwrap();
+
+        let Fetched::Modified { resp, etag, .. } = provider
+            .fetch_daily_features_if_changed(as_of, None)
+            .await
+            .unwrap()
+        else {
+            panic!("expected a payload");
+        };
+        assert_eq!(resp.items.len(), 1);
+        assert_eq!(etag.as_deref(), Some("\"v1\""));
+
+        let again = provider
+            .fetch_daily_features_if_changed(as_of, etag.as_deref())
+            .await
+            .unwrap();
+        assert!(matches!(again, Fetched::NotModified), "{again:?}");
+        // A stale tag gets the payload.
+        let stale = provider
+            .fetch_daily_features_if_changed(as_of, Some("\"v0\""))
+            .await
+            .unwrap();
+        assert!(matches!(stale, Fetched::Modified { .. }), "{stale:?}");
+    }
+
+    #[test]
+    fn checks_the_schema_major() {
+        let v = |s: &str| s.parse::<SchemaVersion>().unwrap();
+        assert!(check_schema_version(SCHEMA_VERSION).is_ok());
+        // A newer minor is read (with a warning).
+        assert!(check_schema_version(v("1.4")).is_ok());
+        let err = check_schema_version(v("99")).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "unsupported provider schema_version 99.0 (this build reads 1.x)"
+        );
+        assert!(check_schema_version(v("0.9")).is_err());
+    }
 }
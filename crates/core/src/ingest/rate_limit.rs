@@ -0,0 +1,152 @@
+//! Token bucket shared by concurrent fetch tasks. The bucket holds one second of requests and
+//! refills at the effective rate; a 429 halves that rate (and holds every task back for a
+//! pause), after which it climbs back to the configured rate over [`RECOVERY`].
+
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Time for the effective rate to climb from zero back to the configured rate.
+const RECOVERY: Duration = Duration::from_secs(30);
+
+#[derive(Debug)]
+pub struct RateLimiter {
+    /// Configured requests per second; also the bucket capacity.
+    base_rate: f64,
+    state: tokio::sync::Mutex<State>,
+}
+
+#[derive(Debug)]
+struct State {
+    tokens: f64,
+    /// Current requests per second: `base_rate`, or less after a 429.
+    rate: f64,
+    refilled_at: Instant,
+    /// No request starts before this (set by [`RateLimiter::throttle`]).
+    paused_until: Instant,
+}
+
+impl State {
+    fn refill(&mut self, base_rate: f64, now: Instant) {
+        let elapsed = now
+            .saturating_duration_since(self.refilled_at)
+            .as_secs_f64();
+        self.rate = (self.rate + base_rate * elapsed / RECOVERY.as_secs_f64()).min(base_rate);
+        self.tokens = (self.tokens + self.rate * elapsed).min(base_rate.max(1.0));
+        self.refilled_at = now;
+    }
+}
+
+impl RateLimiter {
+    /// `per_sec` requests per second on average, in bursts of up to one second's worth.
+    pub fn new(per_sec: f64) -> Self {
+        let now = Instant::now();
+        Self {
+            base_rate: per_sec,
+            state: tokio::sync::Mutex::new(State {
+                tokens: per_sec.max(1.0),
+                rate: per_sec,
+                refilled_at: now,
+                paused_until: now,
+            }),
+        }
+    }
+
+    /// Waits until a request may start and takes its token.
+    pub async fn acquire(&self) {
+        loop {
+            let wait = {
+                let mut state = self.state.lock().await;
+                let now = Instant::now();
+                state.refill(self.base_rate, now);
+                if now < state.paused_until {
+                    state.paused_until - now
+                } else if state.tokens >= 1.0 {
+                    state.tokens -= 1.0;
+                    return;
+                } else {
+                    Duration::from_secs_f64((1.0 - state.tokens) / state.rate)
+                }
+            };
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Backs off after a 429: halves the effective rate, empties the bucket and holds every
+    /// request for `pause` from now. Overlapping pauses do not add up.
+    pub async fn throttle(&self, pause: Duration) {
+        let mut state = self.state.lock().await;
+        let now = Instant::now();
+        state.refill(self.base_rate, now);
+        state.rate = (state.rate / 2.0).max(self.base_rate / 16.0);
+        state.tokens = 0.0;
+        state.paused_until = state.paused_until.max(now + pause);
+    }
+
+    /// Effective requests per second right now.
+    pub async fn rate(&self) -> f64 {
+        let mut state = self.state.lock().await;
+        state.refill(self.base_rate, Instant::now());
+        state.rate
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn acquire_n(limiter: &RateLimiter, n: usize) -> Duration {
+        let started = Instant::now();
+        for _ in 0..n {
+            limiter.acquire().await;
+        }
+        started.elapsed()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn bursts_up_to_capacity_then_paces_at_the_rate() {
+        let limiter = RateLimiter::new(5.0);
+        assert_eq!(acquire_n(&limiter, 5).await, Duration::ZERO);
+        assert_eq!(acquire_n(&limiter, 1).await, Duration::from_millis(200));
+        assert_eq!(acquire_n(&limiter, 5).await, Duration::from_secs(1));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn refill_is_capped_at_one_second_of_requests() {
+        let limiter = RateLimiter::new(5.0);
+        acquire_n(&limiter, 5).await;
+        tokio::time::sleep(Duration::from_millis(400)).await;
+        assert_eq!(acquire_n(&limiter, 2).await, Duration::ZERO);
+
+        tokio::time::sleep(Duration::from_secs(60)).await;
+        assert_eq!(acquire_n(&limiter, 5).await, Duration::ZERO);
+        assert!(acquire_n(&limiter, 1).await > Duration::ZERO);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn throttle_halves_the_rate_and_recovers_gradually() {
+        let limiter = RateLimiter::new(10.0);
+        limiter.throttle(Duration::ZERO).await;
+        assert_eq!(limiter.rate().await, 5.0);
+        limiter.throttle(Duration::ZERO).await;
+        assert_eq!(limiter.rate().await, 2.5);
+
+        // Recovers at 10/30 per second: 5/s after 7.5s, capped at 10/s after 30s more.
+        tokio::time::sleep(Duration::from_millis(7500)).await;
+        assert!((limiter.rate().await - 5.0).abs() < 1e-9);
+        tokio::time::sleep(Duration::from_secs(30)).await;
+        assert_eq!(limiter.rate().await, 10.0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn throttle_pauses_every_task_once() {
+        let limiter = RateLimiter::new(100.0);
+        let started = Instant::now();
+        // Two tasks hitting a 429 at once: the pauses overlap instead of stacking.
+        limiter.throttle(Duration::from_secs(1)).await;
+        limiter.throttle(Duration::from_secs(1)).await;
+        futures::future::join_all((0..3).map(|_| limiter.acquire())).await;
+        let waited = started.elapsed();
+        assert!(waited >= Duration::from_secs(1), "{waited:?}");
+        assert!(waited < Duration::from_millis(1200), "{waited:?}");
+    }
+}
@@ -0,0 +1,150 @@
+//! Korean sector names for the industry index codes in the KIS master files. Each stock carries
+//! up to three levels (large, mid, small); the most specific level with a known name wins, so a
+//! KOSPI manufacturer reads `전기전자` rather than `제조업`.
+//!
+//! KIS writes KOSDAQ codes with or without their `1` market prefix (`1159` / `0159`), so codes
+//! are matched on their last three digits within a market.
+
+use crate::ingest::kis::KisMarket;
+
+/// KOSPI industry indices (KIS `00xx`, KRX `10xx`).
+const KOSPI: &[(u16, &str)] = &[
+    (5, "음식료품"),
+    (6, "섬유의복"),
+    (7, "종이목재"),
+    (8, "화학"),
+    (9, "의약품"),
+    (10, "비금속광물"),
+    (11, "철강금속"),
+    (12, "기계"),
+    (13, "전기전자"),
+    (14, "의료정밀"),
+    (15, "운수장비"),
+    (16, "유통업"),
+    (17, "전기가스업"),
+    (18, "건설업"),
+    (19, "운수창고업"),
+    (20, "통신업"),
+    (21, "금융업"),
+    (22, "은행"),
+    (24, "증권"),
+    (25, "보험"),
+    (26, "서비스업"),
+    (27, "제조업"),
+];
+
+/// KOSDAQ industry indices (KIS `10xx`/`11xx`, KRX `20xx`/`21xx`).
+const KOSDAQ: &[(u16, &str)] = &[
+    (12, "기타서비스"),
+    (15, "IT종합"),
+    (24, "제조"),
+    (26, "건설"),
+    (27, "유통"),
+    (29, "운송"),
+    (31, "금융"),
+    (37, "오락문화"),
+    (41, "통신방송서비스"),
+    (42, "IT S/W & SVC"),
+    (43, "IT H/W"),
+    (56, "음식료담배"),
+    (58, "섬유의류"),
+    (62, "종이목재"),
+    (63, "출판매체복제"),
+    (65, "화학"),
+    (66, "제약"),
+    (67, "비금속"),
+    (68, "금속"),
+    (70, "기계장비"),
+    (72, "일반전기전자"),
+    (74, "의료정밀기기"),
+    (75, "운송장비부품"),
+    (77, "기타제조"),
+    (151, "통신서비스"),
+    (152, "방송서비스"),
+    (153, "인터넷"),
+    (154, "디지털컨텐츠"),
+    (155, "소프트웨어"),
+    (156, "컴퓨터서비스"),
+    (157, "통신장비"),
+    (158, "정보기기"),
+    (159, "반도체"),
+    (160, "IT부품"),
+];
+
+/// Sector name for one industry code field (`"0013"`, `"1159"`, ...). Blank, zero and unknown
+/// codes, and KONEX (which has no industry indices of its own), give `None`.
+pub fn sector_name(market: KisMarket, code: &str) -> Option<&'static str> {
+    let code: u16 = code.trim().parse().ok()?;
+    let table = match market {
+        KisMarket::Kospi => KOSPI,
+        KisMarket::Kosdaq => KOSDAQ,
+        KisMarket::Konex => return None,
+    };
+    let key = code % 1000;
+    if key == 0 {
+        return None;
+    }
+    table.iter().find(|(k, _)| *k == key).map(|(_, name)| *name)
+}
+
+/// The most specific known sector among a stock's `[large, mid, small]` industry codes.
+pub fn sector(market: KisMarket, codes: [&str; 3]) -> Option<&'static str> {
+    codes
+        .iter()
+        .rev()
+        .find_map(|code| sector_name(market, code))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes_map_per_market() {
+        assert_eq!(sector_name(KisMarket::Kospi, "0013"), Some("전기전자"));
+        assert_eq!(sector_name(KisMarket::Kospi, "13"), Some("전기전자"));
+        assert_eq!(sector_name(KisMarket::Kosdaq, "1159"), Some("반도체"));
+        assert_eq!(sector_name(KisMarket::Kosdaq, "0159"), Some("반도체"));
+        // Same number, different market.
+        assert_eq!(sector_name(KisMarket::Kosdaq, "1024"), Some("제조"));
+        assert_eq!(sector_name(KisMarket::Kospi, "0024"), Some("증권"));
+    }
+
+    #[test]
+    fn blank_zero_unknown_and_konex_codes_have_no_sector() {
+        assert_eq!(sector_name(KisMarket::Kospi, "    "), None);
+        assert_eq!(sector_name(KisMarket::Kospi, "0000"), None);
+        assert_eq!(sector_name(KisMarket::Kosdaq, "1000"), None);
+        assert_eq!(sector_name(KisMarket::Kospi, "0099"), None);
+        assert_eq!(sector_name(KisMarket::Kospi, "00A1"), None);
+        assert_eq!(sector_name(KisMarket::Konex, "0013"), None);
+    }
+
+    #[test]
+    fn most_specific_known_level_wins() {
+        assert_eq!(
+            sector(KisMarket::Kospi, ["0027", "0013", "0000"]),
+            Some("전기전자")
+        );
+        assert_eq!(
+            sector(KisMarket::Kosdaq, ["1015", "1043", "1159"]),
+            Some("반도체")
+        );
+        // An unknown small code falls back to the mid level.
+        assert_eq!(
+            sector(KisMarket::Kosdaq, ["1024", "1065", "1999"]),
+            Some("화학")
+        );
+        assert_eq!(sector(KisMarket::Kospi, ["", "", ""]), None);
+    }
+
+    #[test]
+    fn tables_have_unique_codes() {
+        for table in [KOSPI, KOSDAQ] {
+            let mut codes: Vec<u16> = table.iter().map(|(c, _)| *c).collect();
+            codes.sort_unstable();
+            codes.dedup();
+            assert_eq!(codes.len(), table.len());
+        }
+    }
+}
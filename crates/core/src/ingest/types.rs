@@ -1,17 +1,279 @@
 use chrono::NaiveDate;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
 use std::collections::BTreeMap;
 
+/// The payload schema this build understands. Newer minors only add fields and are read with a
+/// warning; another major is rejected.
+pub const SCHEMA_VERSION: SchemaVersion = SchemaVersion { major: 1, minor: 0 };
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DailyFeaturesResponse {
+    /// `1` when the provider does not send one (payloads from before versioning).
+    #[serde(default)]
+    pub schema_version: SchemaVersion,
     pub as_of_date: NaiveDate,
     pub items: Vec<DailyFeatureItem>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl DailyFeaturesResponse {
+    /// SHA-256 (hex) of the canonical JSON: fields in declaration order and features sorted, so
+    /// the same rows hash the same however the provider ordered or formatted its payload.
+    pub fn digest(&self) -> String {
+        crate::llm::sha256_hex(&serde_json::to_vec(self).unwrap_or_default())
+    }
+}
+
+/// `schema_version` of a provider payload: `"major.minor"`, or a bare major (`1` or `"1"`).
+/// Serializes as `"major.minor"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SchemaVersion {
+    pub major: u32,
+    pub minor: u32,
+}
+
+impl Default for SchemaVersion {
+    fn default() -> Self {
+        Self { major: 1, minor: 0 }
+    }
+}
+
+impl std::fmt::Display for SchemaVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+impl std::str::FromStr for SchemaVersion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (major, minor) = s.split_once('.').unwrap_or((s, "0"));
+        match (major.parse(), minor.parse()) {
+            (Ok(major), Ok(minor)) => Ok(Self { major, minor }),
+            _ => Err(format!(
+                "invalid schema_version {s:?} (expected major.minor)"
+            )),
+        }
+    }
+}
+
+impl Serialize for SchemaVersion {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for SchemaVersion {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match Value::deserialize(deserializer)? {
+            Value::Number(n) => n
+                .as_u64()
+                .and_then(|major| u32::try_from(major).ok())
+                .map(|major| Self { major, minor: 0 })
+                .ok_or_else(|| serde::de::Error::custom(format!("invalid schema_version {n}"))),
+            Value::String(s) => s.parse().map_err(serde::de::Error::custom),
+            other => Err(serde::de::Error::custom(format!(
+                "invalid schema_version {other}"
+            ))),
+        }
+    }
+}
+
+/// One `stock_features_daily` row (without its date).
+///
+/// Deserializing also accepts numeric top-level fields it does not know (a provider's new
+/// `market_cap` column, say) and files them under `features`, so new provider columns flow
+/// through without code changes; an explicit `features` entry of the same name wins. Unknown
+/// non-numeric fields are ignored. A `close` in `features` is moved to the `close` field.
+#[derive(Debug, Clone, PartialEq, Serialize, utoipa::ToSchema)]
 pub struct DailyFeatureItem {
     pub ticker: String,
     pub name: String,
+    /// English company name when the source has one.
+    #[serde(default)]
+    pub name_en: Option<String>,
+    /// `stock`, `preferred`, `etf`, `etn`, `reit`, `fund`, `dr` or `other`, when the source
+    /// reports it.
+    #[serde(default)]
+    pub instrument_type: Option<String>,
+    /// Korean sector name (`전기전자`, `반도체`, ...) when the source classifies the stock.
+    #[serde(default)]
+    pub sector: Option<String>,
     pub trading_value: Option<f64>,
+    /// Closing price on the as_of_date, when the source has it. Omitted when unknown, so
+    /// payloads without it keep their digest.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub close: Option<f64>,
     pub features: BTreeMap<String, f64>,
 }
+
+/// The declared fields of [`DailyFeatureItem`] plus whatever else the payload has.
+#[derive(Deserialize)]
+struct DailyFeatureItemFields {
+    ticker: String,
+    name: String,
+    #[serde(default)]
+    name_en: Option<String>,
+    #[serde(default)]
+    instrument_type: Option<String>,
+    #[serde(default)]
+    sector: Option<String>,
+    #[serde(default)]
+    trading_value: Option<f64>,
+    #[serde(default)]
+    close: Option<f64>,
+    features: BTreeMap<String, f64>,
+    #[serde(flatten)]
+    extra: BTreeMap<String, Value>,
+}
+
+impl<'de> Deserialize<'de> for DailyFeatureItem {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let fields = DailyFeatureItemFields::deserialize(deserializer)?;
+        let mut features = fields.features;
+        for (key, value) in fields.extra {
+            if let Some(v) = value.as_f64().filter(|v| v.is_finite()) {
+                features.entry(key).or_insert(v);
+            }
+        }
+        let close_feature = features.remove("close");
+        Ok(Self {
+            ticker: fields.ticker,
+            name: fields.name,
+            name_en: fields.name_en,
+            instrument_type: fields.instrument_type,
+            sector: fields.sector,
+            trading_value: fields.trading_value,
+            close: fields.close.or(close_feature),
+            features,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn item(extra: Value) -> Value {
+        let mut v = json!({
+            "ticker": "KRX:005930",
+            "name": "Samsung",
+            "trading_value": 123.0,
+            "features": {"ret_1d": 0.01}
+        });
+        v.as_object_mut()
+            .unwrap()
+            .extend(extra.as_object().unwrap().clone());
+        v
+    }
+
+    #[test]
+    fn v1_payload_round_trips() {
+        let v = json!({"as_of_date": "2026-01-27", "items": [item(json!({}))]});
+        let parsed: DailyFeaturesResponse = serde_json::from_value(v).unwrap();
+        assert_eq!(parsed.schema_version, SCHEMA_VERSION);
+        assert_eq!(parsed.items[0].features.len(), 1);
+
+        let again: DailyFeaturesResponse =
+            serde_json::from_value(serde_json::to_value(&parsed).unwrap()).unwrap();
+        assert_eq!(again.schema_version, SCHEMA_VERSION);
+        assert_eq!(again.items, parsed.items);
+        assert_eq!(
+            serde_json::to_value(&parsed).unwrap()["schema_version"],
+            "1.0"
+        );
+    }
+
+    #[test]
+    fn digest_ignores_key_order_and_formatting() {
+        let a = r#"{"as_of_date":"2026-01-27","items":[{"ticker":"KRX:005930","name":"Samsung",
+            "trading_value":1.0,"features":{"ret_1d":0.01,"mom_5d":0.02}}]}"#;
+        let b = r#"{"items":[{"features":{"mom_5d":2e-2,"ret_1d":0.01},"name":"Samsung",
+            "ticker":"KRX:005930","trading_value":1}],"schema_version":1,"as_of_date":"2026-01-27"}"#;
+        let parse = |s: &str| serde_json::from_str::<DailyFeaturesResponse>(s).unwrap();
+        assert_eq!(parse(a).digest(), parse(b).digest());
+        assert_eq!(parse(a).digest().len(), 64);
+
+        let mut changed = parse(a);
+        changed.items[0]
+            .features
+            .insert("ret_1d".to_string(), 0.011);
+        assert_ne!(changed.digest(), parse(a).digest());
+    }
+
+    #[test]
+    fn unknown_numeric_fields_become_features() {
+        let v = item(json!({
+            "sector": "전기전자",
+            "market_cap": 4.5e14,
+            "ret_1d": 0.5,
+            "exchange": "KOSPI",
+            "listed": true,
+            "per": null
+        }));
+        let parsed: DailyFeatureItem = serde_json::from_value(v).unwrap();
+        assert_eq!(parsed.sector.as_deref(), Some("전기전자"));
+        assert_eq!(parsed.features["market_cap"], 4.5e14);
+        // `features` wins over a top-level field of the same name.
+        assert_eq!(parsed.features["ret_1d"], 0.01);
+        assert_eq!(
+            parsed.features.keys().collect::<Vec<_>>(),
+            ["market_cap", "ret_1d"]
+        );
+
+        assert_eq!(parsed.close, None);
+
+        // Round trip: the extras are now plain features.
+        let again: DailyFeatureItem =
+            serde_json::from_value(serde_json::to_value(&parsed).unwrap()).unwrap();
+        assert_eq!(again, parsed);
+    }
+
+    #[test]
+    fn close_is_a_field_not_a_feature() {
+        let parse = |extra: Value| serde_json::from_value::<DailyFeatureItem>(item(extra)).unwrap();
+        let top_level = parse(json!({"close": 71500.0}));
+        assert_eq!(top_level.close, Some(71500.0));
+        assert!(!top_level.features.contains_key("close"));
+        assert_eq!(serde_json::to_value(&top_level).unwrap()["close"], 71500.0);
+
+        let mut in_features = item(json!({}));
+        in_features["features"]["close"] = json!(70000.0);
+        let in_features: DailyFeatureItem = serde_json::from_value(in_features).unwrap();
+        assert_eq!(in_features.close, Some(70000.0));
+        assert_eq!(in_features.features.keys().collect::<Vec<_>>(), ["ret_1d"]);
+
+        // Absent: not serialized, so older payloads hash as before.
+        let without = parse(json!({}));
+        assert!(serde_json::to_value(&without)
+            .unwrap()
+            .get("close")
+            .is_none());
+    }
+
+    #[test]
+    fn schema_versions_parse_from_numbers_and_strings() {
+        let parse = |v: Value| serde_json::from_value::<SchemaVersion>(v);
+        assert_eq!(
+            parse(json!(1)).unwrap(),
+            SchemaVersion { major: 1, minor: 0 }
+        );
+        assert_eq!(
+            parse(json!("1.3")).unwrap(),
+            SchemaVersion { major: 1, minor: 3 }
+        );
+        assert_eq!(
+            parse(json!("99")).unwrap(),
+            SchemaVersion {
+                major: 99,
+                minor: 0
+            }
+        );
+        assert!(parse(json!("v2")).is_err());
+        assert!(parse(json!(1.5)).is_err());
+        assert!(parse(json!(null)).is_err());
+    }
+}
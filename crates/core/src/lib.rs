@@ -1,12 +1,41 @@
+pub mod dev_seed;
 pub mod domain;
 pub mod ingest;
 pub mod llm;
+pub mod metrics;
+pub mod notify;
+pub mod report;
 pub mod storage;
 pub mod time;
 
 pub mod config {
     use anyhow::Context;
 
+    /// Secret `name` from the environment, or from the file named by `<name>_FILE` (contents
+    /// trimmed) for platforms that mount secrets as files. A non-empty direct variable wins; a
+    /// `_FILE` path that cannot be read is an error.
+    pub fn env_secret(name: &str) -> anyhow::Result<Option<String>> {
+        secret_from(name, |key| std::env::var(key).ok())
+    }
+
+    fn secret_from(
+        name: &str,
+        var: impl Fn(&str) -> Option<String>,
+    ) -> anyhow::Result<Option<String>> {
+        let direct = var(name);
+        if direct.as_deref().is_some_and(|v| !v.trim().is_empty()) {
+            return Ok(direct);
+        }
+        let file_var = format!("{name}_FILE");
+        let Some(path) = var(&file_var).filter(|p| !p.trim().is_empty()) else {
+            return Ok(direct);
+        };
+        let contents = std::fs::read_to_string(path.trim())
+            .with_context(|| format!("{file_var}={path}: cannot read secret file"))?;
+        let secret = contents.trim();
+        Ok((!secret.is_empty()).then(|| secret.to_string()))
+    }
+
     #[derive(Debug, Clone)]
     pub struct Settings {
         pub database_url: Option<String>,
@@ -14,35 +43,54 @@ pub mod config {
         pub supabase_service_role_key: Option<String>,
         pub anthropic_api_key: Option<String>,
         pub openai_api_key: Option<String>,
+        pub gemini_api_key: Option<String>,
         pub sentry_dsn: Option<String>,
         pub data_provider_base_url: Option<String>,
         pub data_provider_api_key: Option<String>,
+        pub api_auth_key: Option<String>,
+        /// `ADMIN_API_KEYS` (CSV); admin endpoints accept only these.
+        pub admin_api_keys: Vec<String>,
     }
 
     impl Settings {
         pub fn from_env() -> anyhow::Result<Self> {
             Ok(Self {
-                database_url: std::env::var("DATABASE_URL").ok(),
+                database_url: env_secret("DATABASE_URL")?,
                 supabase_url: std::env::var("SUPABASE_URL").ok(),
-                supabase_service_role_key: std::env::var("SUPABASE_SERVICE_ROLE_KEY").ok(),
-                anthropic_api_key: std::env::var("ANTHROPIC_API_KEY").ok(),
-                openai_api_key: std::env::var("OPENAI_API_KEY").ok(),
-                sentry_dsn: std::env::var("SENTRY_DSN").ok(),
+                supabase_service_role_key: env_secret("SUPABASE_SERVICE_ROLE_KEY")?,
+                anthropic_api_key: env_secret("ANTHROPIC_API_KEY")?,
+                openai_api_key: env_secret("OPENAI_API_KEY")?,
+                gemini_api_key: env_secret("GEMINI_API_KEY")?,
+                sentry_dsn: env_secret("SENTRY_DSN")?,
                 data_provider_base_url: std::env::var("DATA_PROVIDER_BASE_URL").ok(),
-                data_provider_api_key: std::env::var("DATA_PROVIDER_API_KEY").ok(),
+                data_provider_api_key: env_secret("DATA_PROVIDER_API_KEY")?,
+                api_auth_key: env_secret("API_AUTH_KEY")?.filter(|s| !s.trim().is_empty()),
+                admin_api_keys: env_secret("ADMIN_API_KEYS")?
+                    .unwrap_or_default()
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|s| !s.is_empty())
+                    .map(str::to_string)
+                    .collect(),
             })
         }
 
         pub fn require_database_url(&self) -> anyhow::Result<&str> {
             self.database_url
                 .as_deref()
-                .context("DATABASE_URL is required")
+                .context("DATABASE_URL (or DATABASE_URL_FILE) is required")
         }
 
         pub fn require_anthropic_api_key(&self) -> anyhow::Result<&str> {
             self.anthropic_api_key
                 .as_deref()
-                .context("ANTHROPIC_API_KEY is required")
+                .context("ANTHROPIC_API_KEY (or ANTHROPIC_API_KEY_FILE) is required")
+        }
+
+        pub fn require_gemini_api_key(&self) -> anyhow::Result<&str> {
+            self.gemini_api_key
+                .as_deref()
+                .context("GEMINI_API_KEY (or GEMINI_API_KEY_FILE) is required")
         }
 
         pub fn require_data_provider_base_url(&self) -> anyhow::Result<&str> {
@@ -51,4 +99,67 @@ pub mod config {
                 .context("DATA_PROVIDER_BASE_URL is required")
         }
     }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::collections::HashMap;
+        use std::io::Write;
+
+        fn lookup(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+            let vars: HashMap<String, String> = vars
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect();
+            move |key| vars.get(key).cloned()
+        }
+
+        #[test]
+        fn reads_and_trims_the_file_variant() {
+            let mut file = tempfile::NamedTempFile::new().unwrap();
+            writeln!(file, "  sk-from-file  ").unwrap();
+            let path = file.path().to_str().unwrap();
+
+            let vars = lookup(&[("ANTHROPIC_API_KEY_FILE", path)]);
+            assert_eq!(
+                secret_from("ANTHROPIC_API_KEY", &vars).unwrap().as_deref(),
+                Some("sk-from-file")
+            );
+            // An empty direct variable (e.g. from .env.example) does not hide the file.
+            let vars = lookup(&[("ANTHROPIC_API_KEY", ""), ("ANTHROPIC_API_KEY_FILE", path)]);
+            assert_eq!(
+                secret_from("ANTHROPIC_API_KEY", &vars).unwrap().as_deref(),
+                Some("sk-from-file")
+            );
+        }
+
+        #[test]
+        fn direct_variable_takes_precedence() {
+            let vars = lookup(&[
+                ("KIS_APPKEY", "from-env"),
+                ("KIS_APPKEY_FILE", "/nonexistent/kis_appkey"),
+            ]);
+            assert_eq!(
+                secret_from("KIS_APPKEY", &vars).unwrap().as_deref(),
+                Some("from-env")
+            );
+            assert_eq!(secret_from("KIS_APPKEY", lookup(&[])).unwrap(), None);
+        }
+
+        #[test]
+        fn unreadable_file_is_an_error_naming_the_variable() {
+            let dir = tempfile::tempdir().unwrap();
+            let missing = dir.path().join("anthropic_api_key");
+            let vars = lookup(&[("ANTHROPIC_API_KEY_FILE", missing.to_str().unwrap())]);
+            let err = secret_from("ANTHROPIC_API_KEY", vars).unwrap_err();
+            assert!(
+                format!("{err:#}").starts_with("ANTHROPIC_API_KEY_FILE="),
+                "{err:#}"
+            );
+
+            // A directory is not a readable secret either.
+            let vars = lookup(&[("ANTHROPIC_API_KEY_FILE", dir.path().to_str().unwrap())]);
+            assert!(secret_from("ANTHROPIC_API_KEY", vars).is_err());
+        }
+    }
 }
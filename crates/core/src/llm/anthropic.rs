@@ -1,12 +1,20 @@
 use crate::config::Settings;
 use crate::domain::contract::LlmRecommendationSnapshot;
 use crate::domain::recommendation::RecommendationSnapshot;
-use crate::llm::error::LlmDiagnosticsError;
-use crate::llm::json;
-use crate::llm::{GenerateInput, LlmClient, Provider};
+use crate::llm::attempts::{AttemptSink, LlmAttempt};
+use crate::llm::budget::PromptBudget;
+use crate::llm::deadline::{self, PartialRaw};
+use crate::llm::error::{self, LlmDiagnosticsError};
+use crate::llm::models::ModelRegistry;
+use crate::llm::prompt;
+use crate::llm::redact;
+use crate::llm::sse::{SseDecoder, SseEvent};
+use crate::llm::{GenerateInput, LlmClient, PromptPreview, Provider};
 use anyhow::Context;
-use reqwest::header::{HeaderMap, HeaderValue};
+use reqwest::header::{HeaderMap, HeaderValue, RETRY_AFTER};
+use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 use std::time::Duration;
 
 const ANTHROPIC_VERSION: &str = "2023-06-01";
@@ -14,9 +22,19 @@ const DEFAULT_BASE_URL: &str = "https://api.anthropic.com";
 const DEFAULT_MODEL: &str = "claude-3-5-sonnet-latest";
 const DEFAULT_MAX_TOKENS: u32 = 2048;
 const DEFAULT_TIMEOUT_SECS: u64 = 60;
+const DEFAULT_MAX_RETRIES: u32 = 3;
+const DEFAULT_RETRY_BASE_MS: u64 = 1000;
+const MAX_RETRY_BACKOFF_MS: u64 = 60_000;
+
+const DEFAULT_BATCH_POLL_SECS: u64 = 30;
+const DEFAULT_BATCH_TIMEOUT_SECS: u64 = 3600;
+const BATCH_CUSTOM_ID: &str = "snapshot";
 
 const TOOL_NAME_EMIT_SNAPSHOT: &str = "emit_snapshot";
 
+// API minimum for `thinking.budget_tokens`.
+const MIN_THINKING_BUDGET: u32 = 1024;
+
 #[derive(Debug, Clone)]
 pub struct AnthropicClient {
     http: reqwest::Client,
@@ -24,6 +42,100 @@ pub struct AnthropicClient {
     base_url: String,
     model: String,
     max_tokens: u32,
+    max_retries: u32,
+    retry_base: Duration,
+    stream: bool,
+    prompt_budget: PromptBudget,
+    prompt_cache: bool,
+    sampling: Sampling,
+    /// Extended thinking budget (`ANTHROPIC_THINKING_BUDGET`); `None` disables thinking.
+    thinking_budget: Option<u32>,
+    system_prompt: prompt::PromptTemplate,
+    total_deadline: Duration,
+    batch: Option<BatchConfig>,
+    /// Debug-log (redacted) request/response bodies (`LLM_LOG_BODIES`).
+    log_bodies: bool,
+    attempt_sink: Option<Arc<dyn AttemptSink>>,
+}
+
+/// Message Batches mode (half price, asynchronous): submit, poll, download results.
+#[derive(Debug, Clone)]
+struct BatchConfig {
+    poll_interval: Duration,
+    timeout: Duration,
+}
+
+/// The total deadline in batch mode, which must outlast the batch poll. Unset, it is the batch
+/// timeout plus the default budget (for the max_tokens bump and repairs); set shorter than the
+/// batch timeout, the run could never finish, so it is a configuration error.
+fn batch_total_deadline(
+    configured: Option<Duration>,
+    batch_timeout: Duration,
+) -> anyhow::Result<Duration> {
+    match configured {
+        None => Ok(batch_timeout + deadline::DEFAULT_TOTAL_DEADLINE),
+        Some(d) if d < batch_timeout => anyhow::bail!(
+            "LLM_TOTAL_DEADLINE_SECS ({}) is shorter than ANTHROPIC_BATCH_TIMEOUT_SECS ({}); raise it or unset it to cover the batch",
+            d.as_secs(),
+            batch_timeout.as_secs()
+        ),
+        Some(d) => Ok(d),
+    }
+}
+
+/// Optional sampling parameters; unset fields are omitted so the model defaults apply.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct Sampling {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_k: Option<u32>,
+}
+
+impl Sampling {
+    /// Reads `ANTHROPIC_TEMPERATURE`, `ANTHROPIC_TOP_P`, `ANTHROPIC_TOP_K`. Invalid values are an
+    /// error rather than silently falling back to model defaults.
+    pub fn from_env() -> anyhow::Result<Self> {
+        fn var<T: std::str::FromStr>(key: &str) -> anyhow::Result<Option<T>> {
+            match std::env::var(key) {
+                Ok(s) if !s.trim().is_empty() => s
+                    .trim()
+                    .parse::<T>()
+                    .map(Some)
+                    .map_err(|_| anyhow::anyhow!("{key} is not a valid number: {s}")),
+                _ => Ok(None),
+            }
+        }
+
+        let out = Self {
+            temperature: var("ANTHROPIC_TEMPERATURE")?,
+            top_p: var("ANTHROPIC_TOP_P")?,
+            top_k: var("ANTHROPIC_TOP_K")?,
+        };
+        out.validate()?;
+        Ok(out)
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if let Some(t) = self.temperature {
+            anyhow::ensure!(
+                (0.0..=1.0).contains(&t),
+                "ANTHROPIC_TEMPERATURE must be within 0..=1 (got {t})"
+            );
+        }
+        if let Some(p) = self.top_p {
+            anyhow::ensure!(
+                p > 0.0 && p <= 1.0,
+                "ANTHROPIC_TOP_P must be within (0, 1] (got {p})"
+            );
+        }
+        if let Some(k) = self.top_k {
+            anyhow::ensure!(k >= 1, "ANTHROPIC_TOP_K must be >= 1 (got {k})");
+        }
+        Ok(())
+    }
 }
 
 impl AnthropicClient {
@@ -32,20 +144,84 @@ impl AnthropicClient {
         let base_url =
             std::env::var("ANTHROPIC_BASE_URL").unwrap_or_else(|_| DEFAULT_BASE_URL.to_string());
         let model = std::env::var("ANTHROPIC_MODEL").unwrap_or_else(|_| DEFAULT_MODEL.to_string());
+        let model_info = ModelRegistry::default().validate(Provider::Anthropic, &model)?;
         let max_tokens = std::env::var("ANTHROPIC_MAX_TOKENS")
             .ok()
             .and_then(|s| s.parse::<u32>().ok())
             .unwrap_or(DEFAULT_MAX_TOKENS);
+        if let Some(info) = model_info.filter(|m| max_tokens > m.max_output_tokens) {
+            tracing::warn!(
+                model = %model,
+                max_tokens,
+                model_max_output_tokens = info.max_output_tokens,
+                "ANTHROPIC_MAX_TOKENS exceeds the model's max output tokens"
+            );
+        }
 
         let timeout_secs = std::env::var("ANTHROPIC_TIMEOUT_SECS")
             .ok()
             .and_then(|s| s.parse::<u64>().ok())
             .unwrap_or(DEFAULT_TIMEOUT_SECS);
 
-        let http = reqwest::Client::builder()
-            .timeout(Duration::from_secs(timeout_secs))
-            .build()
-            .context("failed to build reqwest client")?;
+        let max_retries = std::env::var("ANTHROPIC_MAX_RETRIES")
+            .ok()
+            .and_then(|s| s.parse::<u32>().ok())
+            .unwrap_or(DEFAULT_MAX_RETRIES);
+
+        let retry_base_ms = std::env::var("ANTHROPIC_RETRY_BASE_MS")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(DEFAULT_RETRY_BASE_MS);
+
+        let stream = env_flag("ANTHROPIC_STREAM");
+        let prompt_cache = env_flag("ANTHROPIC_PROMPT_CACHE");
+        let sampling = Sampling::from_env()?;
+        let thinking_budget = thinking_budget_from_env()?;
+        if thinking_budget.is_some() {
+            // The API rejects these sampling overrides when thinking is enabled.
+            anyhow::ensure!(
+                sampling.temperature.is_none() && sampling.top_k.is_none(),
+                "ANTHROPIC_TEMPERATURE / ANTHROPIC_TOP_K cannot be combined with ANTHROPIC_THINKING_BUDGET"
+            );
+            if let Some(p) = sampling.top_p {
+                anyhow::ensure!(
+                    p >= 0.95,
+                    "ANTHROPIC_TOP_P must be within [0.95, 1] when ANTHROPIC_THINKING_BUDGET is set (got {p})"
+                );
+            }
+        }
+
+        let batch = env_flag("ANTHROPIC_USE_BATCH").then(|| {
+            let secs = |key: &str, default: u64| {
+                std::env::var(key)
+                    .ok()
+                    .and_then(|s| s.parse::<u64>().ok())
+                    .unwrap_or(default)
+            };
+            BatchConfig {
+                poll_interval: Duration::from_secs(
+                    secs("ANTHROPIC_BATCH_POLL_SECS", DEFAULT_BATCH_POLL_SECS).max(1),
+                ),
+                timeout: Duration::from_secs(secs(
+                    "ANTHROPIC_BATCH_TIMEOUT_SECS",
+                    DEFAULT_BATCH_TIMEOUT_SECS,
+                )),
+            }
+        });
+        let total_deadline = match &batch {
+            Some(b) => batch_total_deadline(deadline::configured_total_deadline(), b.timeout)?,
+            None => deadline::total_deadline_from_env(),
+        };
+
+        // When streaming, the timeout applies per read rather than to the whole response so that
+        // long generations are not cut off mid-body.
+        let builder = reqwest::Client::builder();
+        let builder = if stream {
+            builder.read_timeout(Duration::from_secs(timeout_secs))
+        } else {
+            builder.timeout(Duration::from_secs(timeout_secs))
+        };
+        let http = builder.build().context("failed to build reqwest client")?;
 
         Ok(Self {
             http,
@@ -53,54 +229,374 @@ impl AnthropicClient {
             base_url,
             model,
             max_tokens,
+            max_retries,
+            retry_base: Duration::from_millis(retry_base_ms),
+            stream,
+            prompt_budget: PromptBudget::from_env()
+                .with_context_window(model_info.map(|m| m.context_window)),
+            prompt_cache,
+            sampling,
+            thinking_budget,
+            system_prompt: prompt::PromptTemplate::from_env()?,
+            total_deadline,
+            batch,
+            log_bodies: env_flag("LLM_LOG_BODIES"),
+            attempt_sink: None,
         })
     }
 
-    async fn create_message(
+    /// Records every provider call (including rejected outputs) to `sink`.
+    pub fn with_attempt_sink(mut self, sink: Arc<dyn AttemptSink>) -> Self {
+        self.attempt_sink = Some(sink);
+        self
+    }
+
+    async fn record_attempt(
         &self,
-        req: CreateMessageRequest,
-    ) -> anyhow::Result<(serde_json::Value, CreateMessageResponse)> {
+        input: &GenerateInput,
+        attempt_no: u32,
+        stage: &'static str,
+        raw_response: &serde_json::Value,
+        error: Option<String>,
+    ) {
+        let Some(sink) = &self.attempt_sink else {
+            return;
+        };
+        sink.record(LlmAttempt {
+            as_of_date: input.as_of_date,
+            provider: Provider::Anthropic,
+            model: self.model.clone(),
+            attempt_no,
+            stage,
+            raw_response: Some(raw_response.clone()),
+            error,
+        })
+        .await;
+    }
+
+    fn build_request(
+        &self,
+        input: &GenerateInput,
+        max_tokens: u32,
+        user_content: String,
+    ) -> CreateMessageRequest {
+        // Thinking tokens count against max_tokens, so the budget is added on top of the
+        // configured output ceiling. Forced tool use is not allowed with thinking; fall back to
+        // `auto` (the text path still parses a plain JSON answer).
+        let (max_tokens, thinking, tool_choice) = match self.thinking_budget {
+            Some(budget_tokens) => (
+                max_tokens.saturating_add(budget_tokens),
+                Some(ThinkingConfig::Enabled { budget_tokens }),
+                ToolChoice::Auto,
+            ),
+            None => (max_tokens, None, Self::tool_choice()),
+        };
+        CreateMessageRequest {
+            model: self.model.clone(),
+            max_tokens,
+            system: Some(self.system_blocks(input)),
+            messages: vec![Message {
+                role: "user",
+                content: user_content,
+            }],
+            tools: Some(Self::tools()),
+            tool_choice: Some(tool_choice),
+            thinking,
+            sampling: self.sampling,
+            stream: false,
+        }
+    }
+
+    fn system_blocks(&self, input: &GenerateInput) -> Vec<SystemBlock> {
+        // The cache breakpoint on the system block covers tools + system (the static prefix).
+        vec![SystemBlock::Text {
+            text: self.system_prompt.render(input),
+            cache_control: self.prompt_cache.then_some(CacheControl::Ephemeral),
+        }]
+    }
+
+    fn headers(&self) -> anyhow::Result<HeaderMap> {
         let mut headers = HeaderMap::new();
         headers.insert("x-api-key", HeaderValue::from_str(&self.api_key)?);
         headers.insert(
             "anthropic-version",
             HeaderValue::from_static(ANTHROPIC_VERSION),
         );
+        Ok(headers)
+    }
 
-        let url = format!("{}/v1/messages", self.base_url.trim_end_matches('/'));
-        let res = self
-            .http
-            .post(url)
-            .headers(headers)
-            .json(&req)
-            .send()
-            .await
-            .context("Anthropic request failed")?;
+    /// Sends the request built by `build`, retrying transient failures (rate limits, overloaded,
+    /// network) with exponential backoff; client errors (400/401/403/...) fail immediately.
+    async fn send_with_retry(
+        &self,
+        build: impl Fn() -> reqwest::RequestBuilder,
+    ) -> anyhow::Result<reqwest::Response> {
+        let max_attempts = self.max_retries.saturating_add(1);
+        let mut attempt: u32 = 0;
+        loop {
+            attempt += 1;
+
+            let res = match build().send().await {
+                Ok(r) => r,
+                Err(err) => {
+                    if attempt >= max_attempts {
+                        return Err(LlmDiagnosticsError {
+                            provider: Provider::Anthropic,
+                            stage: if err.is_timeout() {
+                                "transport_timeout"
+                            } else {
+                                "transport"
+                            },
+                            detail: format!(
+                                "attempts={attempt} error={:#}",
+                                anyhow::Error::from(err)
+                            ),
+                            raw_output: None,
+                            raw_response_json: None,
+                            http_status: None,
+                        }
+                        .into());
+                    }
+                    let backoff = retry_backoff(self.retry_base, attempt, None);
+                    tracing::warn!(
+                        attempt,
+                        ?backoff,
+                        error = %err,
+                        "Anthropic request failed; retrying"
+                    );
+                    tokio::time::sleep(backoff).await;
+                    continue;
+                }
+            };
+
+            let status = res.status();
+            if status.is_success() {
+                return Ok(res);
+            }
+
+            let retry_after = parse_retry_after(res.headers());
+            let text = res
+                .text()
+                .await
+                .context("failed to read Anthropic response body")?;
+            if is_retryable_status(status) && attempt < max_attempts {
+                let backoff = retry_backoff(self.retry_base, attempt, retry_after);
+                tracing::warn!(
+                    attempt,
+                    ?backoff,
+                    http_status = %status,
+                    "Anthropic HTTP error; retrying"
+                );
+                tokio::time::sleep(backoff).await;
+                continue;
+            }
 
-        let status = res.status();
-        let text = res
-            .text()
-            .await
-            .context("failed to read Anthropic response body")?;
-        if !status.is_success() {
             let raw_response_json = serde_json::from_str::<serde_json::Value>(&text).ok();
             return Err(LlmDiagnosticsError {
                 provider: Provider::Anthropic,
                 stage: "http",
-                detail: format!("status={status}"),
+                detail: format!("status={status} attempts={attempt}"),
                 raw_output: Some(text),
                 raw_response_json,
+                http_status: Some(status.as_u16()),
             }
             .into());
         }
+    }
+
+    async fn read_json(res: reqwest::Response) -> anyhow::Result<serde_json::Value> {
+        let text = res
+            .text()
+            .await
+            .context("failed to read Anthropic response body")?;
+        serde_json::from_str::<serde_json::Value>(&text)
+            .with_context(|| format!("failed to parse Anthropic response JSON: {text}"))
+    }
+
+    async fn create_message(
+        &self,
+        mut req: CreateMessageRequest,
+    ) -> anyhow::Result<(serde_json::Value, CreateMessageResponse)> {
+        if let Some(batch) = &self.batch {
+            return self.create_message_batch(req, batch).await;
+        }
+        req.stream = self.stream;
+
+        let headers = self.headers()?;
+        let url = format!("{}/v1/messages", self.base_url.trim_end_matches('/'));
+        if self.log_bodies {
+            tracing::debug!(
+                %url,
+                headers = %redact::format_headers(&headers),
+                body = %redact::loggable_request(&serde_json::to_value(&req)?),
+                "Anthropic request"
+            );
+        }
+        let started = std::time::Instant::now();
+        let res = self
+            .send_with_retry(|| self.http.post(&url).headers(headers.clone()).json(&req))
+            .await?;
+        let status = res.status();
+
+        // Mid-stream failures are not retried: the partial output is surfaced for diagnosis.
+        let raw_json = if self.stream {
+            read_message_stream(res).await?
+        } else {
+            Self::read_json(res).await?
+        };
+        let (raw_json, parsed) = self.decode_message(raw_json)?;
+        tracing::debug!(
+            %status,
+            stop_reason = parsed.stop_reason.as_deref().unwrap_or(""),
+            input_tokens = parsed.usage.as_ref().map(|u| u.input_tokens),
+            output_tokens = parsed.usage.as_ref().map(|u| u.output_tokens),
+            elapsed_ms = started.elapsed().as_millis() as u64,
+            stream = self.stream,
+            "Anthropic response"
+        );
+        if self.log_bodies {
+            tracing::debug!(body = %redact::redact_json(&raw_json), "Anthropic response body");
+        }
+        Ok((raw_json, parsed))
+    }
 
-        let raw_json = serde_json::from_str::<serde_json::Value>(&text)
-            .with_context(|| format!("failed to parse Anthropic response JSON: {text}"))?;
+    fn decode_message(
+        &self,
+        raw_json: serde_json::Value,
+    ) -> anyhow::Result<(serde_json::Value, CreateMessageResponse)> {
         let parsed = serde_json::from_value::<CreateMessageResponse>(raw_json.clone())
             .context("failed to decode Anthropic response into CreateMessageResponse")?;
+        if let Some(usage) = &parsed.usage {
+            tracing::info!(
+                model = %self.model,
+                input_tokens = usage.input_tokens,
+                output_tokens = usage.output_tokens,
+                cache_creation_input_tokens = usage.cache_creation_input_tokens.unwrap_or(0),
+                cache_read_input_tokens = usage.cache_read_input_tokens.unwrap_or(0),
+                batch = self.batch.is_some(),
+                "Anthropic usage"
+            );
+        }
         Ok((raw_json, parsed))
     }
 
+    /// Submits a single-request Message Batch, polls until it ends, and returns the result message
+    /// in the same shape as a synchronous `/v1/messages` call.
+    async fn create_message_batch(
+        &self,
+        req: CreateMessageRequest,
+        batch: &BatchConfig,
+    ) -> anyhow::Result<(serde_json::Value, CreateMessageResponse)> {
+        let headers = self.headers()?;
+        let url = format!(
+            "{}/v1/messages/batches",
+            self.base_url.trim_end_matches('/')
+        );
+        let body = serde_json::json!({
+            "requests": [{"custom_id": BATCH_CUSTOM_ID, "params": req}],
+        });
+
+        let diag = |stage: &'static str, detail: String, raw: Option<serde_json::Value>| {
+            anyhow::Error::from(LlmDiagnosticsError {
+                provider: Provider::Anthropic,
+                stage,
+                detail,
+                raw_output: None,
+                raw_response_json: raw,
+                http_status: None,
+            })
+        };
+
+        let res = self
+            .send_with_retry(|| self.http.post(&url).headers(headers.clone()).json(&body))
+            .await?;
+        let mut raw_batch = Self::read_json(res).await?;
+        let mut status = serde_json::from_value::<MessageBatch>(raw_batch.clone())
+            .context("failed to decode Anthropic message batch")?;
+        tracing::info!(batch_id = %status.id, "Anthropic batch submitted");
+
+        let started = std::time::Instant::now();
+        while status.processing_status != "ended" {
+            if started.elapsed() >= batch.timeout {
+                return Err(diag(
+                    "batch_timeout",
+                    format!(
+                        "batch_id={} still {} after {}s",
+                        status.id,
+                        status.processing_status,
+                        batch.timeout.as_secs()
+                    ),
+                    Some(raw_batch),
+                ));
+            }
+            tokio::time::sleep(batch.poll_interval).await;
+
+            let status_url = format!("{url}/{}", status.id);
+            let res = self
+                .send_with_retry(|| self.http.get(&status_url).headers(headers.clone()))
+                .await?;
+            raw_batch = Self::read_json(res).await?;
+            status = serde_json::from_value::<MessageBatch>(raw_batch.clone())
+                .context("failed to decode Anthropic message batch")?;
+            tracing::info!(
+                batch_id = %status.id,
+                processing_status = %status.processing_status,
+                elapsed_secs = started.elapsed().as_secs(),
+                "Anthropic batch polling"
+            );
+        }
+
+        let Some(results_url) = status.results_url.as_deref() else {
+            return Err(diag(
+                "batch_results",
+                format!("batch_id={} ended without results_url", status.id),
+                Some(raw_batch),
+            ));
+        };
+        let res = self
+            .send_with_retry(|| self.http.get(results_url).headers(headers.clone()))
+            .await?;
+        let results = res
+            .text()
+            .await
+            .context("failed to read Anthropic batch results")?;
+
+        // Results are JSONL, one line per request.
+        let Some(line) = results
+            .lines()
+            .filter_map(|l| serde_json::from_str::<serde_json::Value>(l).ok())
+            .find(|v| v["custom_id"] == BATCH_CUSTOM_ID)
+        else {
+            return Err(diag(
+                "batch_results",
+                format!(
+                    "batch_id={} results missing custom_id={BATCH_CUSTOM_ID}",
+                    status.id
+                ),
+                Some(raw_batch),
+            ));
+        };
+
+        match line["result"]["type"].as_str().unwrap_or_default() {
+            "succeeded" => self.decode_message(line["result"]["message"].clone()),
+            "expired" => Err(diag(
+                "batch_expired",
+                format!("batch_id={}", status.id),
+                Some(line),
+            )),
+            "canceled" => Err(diag(
+                "batch_canceled",
+                format!("batch_id={}", status.id),
+                Some(line),
+            )),
+            other => Err(diag(
+                "batch_errored",
+                format!("batch_id={} result_type={other}", status.id),
+                Some(line),
+            )),
+        }
+    }
+
     fn tools() -> Vec<Tool> {
         // Minimal JSON schema for the exact snapshot contract.
         // Keep it strict and explicit to maximize compliance.
@@ -150,87 +646,6 @@ impl AnthropicClient {
         }
     }
 
-    fn system_prompt() -> String {
-        // Keep strict and provider-agnostic: JSON only, no prose.
-        [
-            "You are a stock recommendation engine for KRX.",
-            "Return ONLY valid JSON. Do not wrap in markdown. Do not include any extra keys.",
-            "No trailing commas. No comments. No semicolons. Use double quotes for all JSON strings.",
-            "Output schema:",
-            "{",
-            "  \"as_of_date\": \"YYYY-MM-DD\",",
-            "  \"generated_at\": \"ISO-8601\",",
-            "  \"items\": [",
-            "    {",
-            "      \"rank\": 1,",
-            "      \"ticker\": \"KRX:005930\",",
-            "      \"name\": \"삼성전자\",",
-            "      \"rationale\": [\"line1\", \"line2\", \"line3\"],",
-            "      \"risk_notes\": \"optional\",",
-            "      \"confidence\": 0.0",
-            "    }",
-            "  ]",
-            "}",
-            "Rules:",
-            "- items must have exactly 20 entries, ranks 1..20 unique",
-            "- rationale must have exactly 3 short lines per item",
-            "- risk_notes key MUST be present (use null if none)",
-            "- confidence key MUST be present (use null if unknown)",
-            "- confidence (if present) must be in [0, 1]",
-            "- Use only the provided candidates (ticker/name)",
-        ]
-        .join("\n")
-    }
-
-    fn user_prompt(input: &GenerateInput) -> String {
-        format!(
-            "Task: Select the top 20 short-term (<= 1 week) recommendations for as_of_date={}.\n\nCandidates JSON:\n{}",
-            input.as_of_date,
-            input.candidates_json()
-        )
-    }
-
-    fn repair_prompt(previous_output: &str, expected_as_of_date: chrono::NaiveDate) -> String {
-        let schema = [
-            "{",
-            "  \"as_of_date\": \"YYYY-MM-DD\",",
-            "  \"generated_at\": \"ISO-8601\",",
-            "  \"items\": [",
-            "    {",
-            "      \"rank\": 1,",
-            "      \"ticker\": \"KRX:005930\",",
-            "      \"name\": \"삼성전자\",",
-            "      \"rationale\": [\"line1\", \"line2\", \"line3\"],",
-            "      \"risk_notes\": null,",
-            "      \"confidence\": null",
-            "    }",
-            "  ]",
-            "}",
-        ]
-        .join("\n");
-
-        format!(
-            "Your previous message was NOT valid JSON.\n\n\
-TASK: Output ONLY a single JSON object that exactly matches the schema and rules.\n\
-- Do NOT include any markdown, prose, or code fences.\n\
-- Do NOT include trailing commas, comments, or semicolons.\n\
-- Use double quotes for all JSON strings.\n\
-- The JSON MUST have as_of_date=\"{expected_as_of_date}\".\n\
-- The JSON MUST have exactly 20 items with ranks 1..20.\n\
-- Each item MUST include keys: rank, ticker, name, rationale, risk_notes, confidence.\n\
-- rationale MUST have exactly 3 strings.\n\n\
-SCHEMA:\n{schema}\n\n\
-INVALID OUTPUT (for reference only; DO NOT copy verbatim):\n{previous_output}"
-        )
-    }
-
-    fn parse_snapshot(
-        text: &str,
-        expected_as_of_date: chrono::NaiveDate,
-    ) -> anyhow::Result<RecommendationSnapshot> {
-        json::parse_snapshot(text, expected_as_of_date)
-    }
-
     fn response_text(res: &CreateMessageResponse) -> anyhow::Result<String> {
         let mut out = String::new();
         for block in &res.content {
@@ -246,7 +661,7 @@ INVALID OUTPUT (for reference only; DO NOT copy verbatim):\n{previous_output}"
                     // Callers should use `response_tool_snapshot`.
                     continue;
                 }
-                ContentBlock::Thinking { .. } | ContentBlock::RedactedThinking { .. } => {
+                ContentBlock::Thinking | ContentBlock::RedactedThinking => {
                     // Ignore.
                 }
                 ContentBlock::Unknown => {
@@ -257,21 +672,75 @@ INVALID OUTPUT (for reference only; DO NOT copy verbatim):\n{previous_output}"
         Ok(out)
     }
 
+    fn response_tool_input(res: &CreateMessageResponse) -> Option<&serde_json::Value> {
+        res.content.iter().find_map(|block| match block {
+            ContentBlock::ToolUse { name, input, .. } if name == TOOL_NAME_EMIT_SNAPSHOT => {
+                Some(input)
+            }
+            _ => None,
+        })
+    }
+
     fn response_tool_snapshot(
         res: &CreateMessageResponse,
     ) -> anyhow::Result<Option<LlmRecommendationSnapshot>> {
-        for block in &res.content {
-            if let ContentBlock::ToolUse { name, input, .. } = block {
-                if name == TOOL_NAME_EMIT_SNAPSHOT {
-                    let parsed = serde_json::from_value::<LlmRecommendationSnapshot>(input.clone())
-                        .context(
-                            "failed to decode tool_use.input into LlmRecommendationSnapshot",
-                        )?;
-                    return Ok(Some(parsed));
-                }
-            }
+        let Some(input) = Self::response_tool_input(res) else {
+            return Ok(None);
+        };
+        let parsed = serde_json::from_value::<LlmRecommendationSnapshot>(input.clone())
+            .context("failed to decode tool_use.input into LlmRecommendationSnapshot")?;
+        Ok(Some(parsed))
+    }
+
+    /// Parses a response via the tool_use block when present, otherwise via text blocks.
+    fn parse_response(
+        res: &CreateMessageResponse,
+        input: &GenerateInput,
+    ) -> anyhow::Result<(RecommendationSnapshot, OutputPath)> {
+        if let Some(tool_snapshot) = Self::response_tool_snapshot(res)? {
+            let snapshot = tool_snapshot
+                .validate_and_into_snapshot_with(input.as_of_date, input.validation)?;
+            input.ensure_tickers_in_universe(&snapshot)?;
+            return Ok((snapshot, OutputPath::Tool));
+        }
+
+        // Fallback to text (should be rare when tool_choice is forced).
+        let text = Self::response_text(res)?;
+        Ok((prompt::parse_snapshot(&text, input)?, OutputPath::Text))
+    }
+
+    /// Concatenated `thinking` block text of a raw response (streamed or not).
+    fn thinking_text(raw: &serde_json::Value) -> Option<String> {
+        let parts: Vec<&str> = raw["content"]
+            .as_array()?
+            .iter()
+            .filter(|b| b["type"] == "thinking")
+            .filter_map(|b| b["thinking"].as_str())
+            .collect();
+        (!parts.is_empty()).then(|| parts.join("\n"))
+    }
+
+    /// What the repair prompt shows as the invalid previous output.
+    fn previous_output(res: &CreateMessageResponse) -> anyhow::Result<String> {
+        match Self::response_tool_input(res) {
+            Some(input) => Ok(input.to_string()),
+            None => Self::response_text(res),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OutputPath {
+    Tool,
+    Text,
+}
+
+impl OutputPath {
+    fn as_str(self) -> &'static str {
+        match self {
+            OutputPath::Tool => "tool",
+            OutputPath::Text => "text",
         }
-        Ok(None)
     }
 }
 
@@ -281,6 +750,23 @@ impl LlmClient for AnthropicClient {
         Provider::Anthropic
     }
 
+    fn prompt_hash(&self, input: &GenerateInput) -> Option<String> {
+        Some(self.system_prompt.hash(input))
+    }
+
+    fn preview_prompt(&self, input: &GenerateInput) -> anyhow::Result<PromptPreview> {
+        let user_prompt = prompt::user_prompt(input, &self.prompt_budget);
+        let req = self.build_request(input, self.max_tokens, user_prompt.clone());
+        Ok(PromptPreview {
+            provider: Provider::Anthropic,
+            model: self.model.clone(),
+            system_prompt: self.system_prompt.render(input),
+            user_prompt,
+            tool_schema: serde_json::to_value(Self::tools())?,
+            request: serde_json::to_value(req)?,
+        })
+    }
+
     async fn generate_recommendations(
         &self,
         input: GenerateInput,
@@ -288,86 +774,134 @@ impl LlmClient for AnthropicClient {
         let (snapshot, _raw) = self.generate_recommendations_with_raw(input).await?;
         Ok(snapshot)
     }
+
+    async fn generate_recommendations_with_raw(
+        &self,
+        input: GenerateInput,
+    ) -> anyhow::Result<(RecommendationSnapshot, serde_json::Value)> {
+        AnthropicClient::generate_recommendations_with_raw(self, input).await
+    }
 }
 
 impl AnthropicClient {
     async fn try_parse_with_repairs(
         &self,
         input: &GenerateInput,
-        initial_text: String,
+        first_err: anyhow::Error,
+        initial_output: String,
         initial_raw_json: serde_json::Value,
+        mut attempt_no: u32,
+        partial: &PartialRaw,
     ) -> anyhow::Result<(RecommendationSnapshot, serde_json::Value)> {
-        match Self::parse_snapshot(&initial_text, input.as_of_date) {
-            Ok(snapshot) => return Ok((snapshot, initial_raw_json)),
-            Err(first_err) => {
-                let mut last_err = first_err;
-                let mut last_text = initial_text;
-                let mut last_raw_json = initial_raw_json;
-
-                // Repair attempts: 2
-                for attempt in 1..=2u32 {
-                    let repair_req = CreateMessageRequest {
-                        model: self.model.clone(),
-                        max_tokens: self.max_tokens,
-                        system: Some(Self::system_prompt()),
-                        messages: vec![Message {
-                            role: "user",
-                            content: Self::repair_prompt(&last_text, input.as_of_date),
-                        }],
-                        tools: Some(Self::tools()),
-                        tool_choice: Some(Self::tool_choice()),
-                    };
+        let mut last_err = first_err;
+        let mut last_output = initial_output;
+        let mut last_raw_json = initial_raw_json;
 
-                    let (repair_raw_json, repair_res) = self.create_message(repair_req).await?;
-                    let repair_text = Self::response_text(&repair_res)?;
-                    match Self::parse_snapshot(&repair_text, input.as_of_date) {
-                        Ok(snapshot) => return Ok((snapshot, repair_raw_json)),
-                        Err(err) => {
-                            last_err = err;
-                            last_text = repair_text;
-                            last_raw_json = repair_raw_json;
-                            tracing::warn!(
-                                attempt,
-                                %input.as_of_date,
-                                error = %last_err,
-                                "LLM output still invalid after repair attempt"
-                            );
-                        }
-                    }
-                }
+        // Repair attempts: 2. Keep forcing the tool so the repaired output comes back as tool_use.
+        for attempt in 1..=2u32 {
+            let repair_req = self.build_request(
+                input,
+                self.max_tokens,
+                prompt::repair_prompt(&last_output, input, &last_err),
+            );
 
-                Err(LlmDiagnosticsError {
-                    provider: Provider::Anthropic,
-                    stage: "parse_after_repair",
-                    detail: format!("final_error={last_err}"),
-                    raw_output: Some(last_text),
-                    raw_response_json: Some(last_raw_json),
+            let (repair_raw_json, repair_res) = self.create_message(repair_req).await?;
+            partial.set(&repair_raw_json);
+            attempt_no += 1;
+            let parsed = Self::parse_response(&repair_res, input);
+            self.record_attempt(
+                input,
+                attempt_no,
+                "repair",
+                &repair_raw_json,
+                parsed.as_ref().err().map(|e| format!("{e:#}")),
+            )
+            .await;
+            match parsed {
+                Ok((snapshot, path)) => {
+                    tracing::info!(
+                        %input.as_of_date,
+                        path = "repair",
+                        repair_attempt = attempt,
+                        response_path = path.as_str(),
+                        "LLM snapshot accepted"
+                    );
+                    return Ok((snapshot, repair_raw_json));
+                }
+                Err(err) => {
+                    last_err = err;
+                    last_output = Self::previous_output(&repair_res)?;
+                    last_raw_json = repair_raw_json;
+                    tracing::warn!(
+                        attempt,
+                        %input.as_of_date,
+                        error = %last_err,
+                        "LLM output still invalid after repair attempt"
+                    );
                 }
-                .into())
             }
         }
+
+        Err(LlmDiagnosticsError {
+            provider: Provider::Anthropic,
+            stage: error::after_repair_stage(&last_err),
+            detail: format!("final_error={last_err}"),
+            raw_output: Some(last_output),
+            raw_response_json: Some(last_raw_json),
+            http_status: None,
+        }
+        .into())
     }
 
+    /// Generates a snapshot; the whole run (including the max_tokens bump and repairs) is bounded
+    /// by `LLM_TOTAL_DEADLINE_SECS`.
     pub async fn generate_recommendations_with_raw(
         &self,
         input: GenerateInput,
     ) -> anyhow::Result<(RecommendationSnapshot, serde_json::Value)> {
-        let make_req = |max_tokens: u32| CreateMessageRequest {
-            model: self.model.clone(),
-            max_tokens,
-            system: Some(Self::system_prompt()),
-            messages: vec![Message {
-                role: "user",
-                content: Self::user_prompt(&input),
-            }],
-            tools: Some(Self::tools()),
-            tool_choice: Some(Self::tool_choice()),
-        };
+        let partial = PartialRaw::default();
+        deadline::run_with_deadline(
+            Provider::Anthropic,
+            self.total_deadline,
+            &partial,
+            self.generate_with_partial(input, &partial),
+        )
+        .await
+    }
+
+    async fn generate_with_partial(
+        &self,
+        input: GenerateInput,
+        partial: &PartialRaw,
+    ) -> anyhow::Result<(RecommendationSnapshot, serde_json::Value)> {
+        tracing::info!(
+            %input.as_of_date,
+            model = %self.model,
+            temperature = ?self.sampling.temperature,
+            top_p = ?self.sampling.top_p,
+            top_k = ?self.sampling.top_k,
+            "Anthropic sampling config"
+        );
+
+        let user_prompt = prompt::user_prompt(&input, &self.prompt_budget);
+        let make_req =
+            |max_tokens: u32| self.build_request(&input, max_tokens, user_prompt.clone());
 
         let (mut raw_json, mut res) = self.create_message(make_req(self.max_tokens)).await?;
+        partial.set(&raw_json);
+        let mut attempt_no = 1;
+        let mut stage = "initial";
 
         // If the model hit max_tokens, retry once with a higher ceiling.
         if matches!(res.stop_reason.as_deref(), Some("max_tokens")) {
+            self.record_attempt(
+                &input,
+                attempt_no,
+                stage,
+                &raw_json,
+                Some("stop_reason=max_tokens".to_string()),
+            )
+            .await;
             let bumped = self.max_tokens.saturating_mul(2).max(4096);
             tracing::warn!(
                 %input.as_of_date,
@@ -376,20 +910,279 @@ impl AnthropicClient {
                 "Anthropic stop_reason=max_tokens; retrying once with higher max_tokens"
             );
             let (rj, r) = self.create_message(make_req(bumped)).await?;
+            partial.set(&rj);
             raw_json = rj;
             res = r;
+            attempt_no += 1;
+            stage = "max_tokens_retry";
         }
 
-        // Tool output path.
-        if let Some(tool_snapshot) = Self::response_tool_snapshot(&res)? {
-            let snapshot = tool_snapshot.validate_and_into_snapshot(input.as_of_date)?;
-            return Ok((snapshot, raw_json));
+        let parsed = Self::parse_response(&res, &input);
+        self.record_attempt(
+            &input,
+            attempt_no,
+            stage,
+            &raw_json,
+            parsed.as_ref().err().map(|e| format!("{e:#}")),
+        )
+        .await;
+
+        let (snapshot, mut raw_json) = match parsed {
+            Ok((snapshot, path)) => {
+                tracing::info!(%input.as_of_date, path = path.as_str(), "LLM snapshot accepted");
+                (snapshot, raw_json)
+            }
+            Err(err) => {
+                tracing::warn!(
+                    %input.as_of_date,
+                    error = %err,
+                    "LLM output invalid; attempting repair"
+                );
+                let previous_output = Self::previous_output(&res)?;
+                self.try_parse_with_repairs(
+                    &input,
+                    err,
+                    previous_output,
+                    raw_json,
+                    attempt_no,
+                    partial,
+                )
+                .await?
+            }
+        };
+
+        // Persisted alongside the snapshot so outcomes can be correlated with sampling settings.
+        if let Some(obj) = raw_json.as_object_mut() {
+            obj.insert("sampling".to_string(), serde_json::to_value(self.sampling)?);
+        }
+        if let Some(budget_tokens) = self.thinking_budget {
+            let text = Self::thinking_text(&raw_json);
+            if let Some(obj) = raw_json.as_object_mut() {
+                obj.insert(
+                    "thinking".to_string(),
+                    serde_json::json!({ "budget_tokens": budget_tokens, "text": text }),
+                );
+            }
         }
+        Ok((snapshot, raw_json))
+    }
+}
+
+async fn read_message_stream(mut res: reqwest::Response) -> anyhow::Result<serde_json::Value> {
+    let mut decoder = SseDecoder::default();
+    let mut assembler = StreamAssembler::default();
 
-        // Fallback to text (should be rare).
-        let text = Self::response_text(&res)?;
-        self.try_parse_with_repairs(&input, text, raw_json).await
+    loop {
+        let chunk = match res.chunk().await {
+            Ok(Some(chunk)) => chunk,
+            Ok(None) => break,
+            Err(err) => return Err(assembler.into_error(format!("stream read failed: {err}"))),
+        };
+        for event in decoder.push(&chunk) {
+            if let Err(err) = assembler.apply(&event) {
+                return Err(assembler.into_error(format!("{err:#}")));
+            }
+        }
+    }
+    if let Some(event) = decoder.finish() {
+        if let Err(err) = assembler.apply(&event) {
+            return Err(assembler.into_error(format!("{err:#}")));
+        }
     }
+
+    assembler.finish()
+}
+
+/// Rebuilds a non-streaming Messages API response body from `stream: true` SSE events.
+#[derive(Debug, Default)]
+struct StreamAssembler {
+    message: Option<serde_json::Value>,
+    blocks: Vec<serde_json::Value>,
+    partial_json: Vec<String>,
+    // Raw text/JSON deltas in arrival order (attached to diagnostics on failure).
+    partial_output: String,
+    done: bool,
+}
+
+impl StreamAssembler {
+    fn apply(&mut self, event: &SseEvent) -> anyhow::Result<()> {
+        if event.data.is_empty() {
+            return Ok(());
+        }
+        let v = serde_json::from_str::<serde_json::Value>(&event.data)
+            .with_context(|| format!("invalid stream event JSON: {}", event.data))?;
+
+        match v["type"].as_str().unwrap_or_default() {
+            "message_start" => {
+                self.message = Some(v["message"].clone());
+            }
+            "content_block_start" => {
+                let idx = Self::index(&v)?;
+                if self.blocks.len() <= idx {
+                    self.blocks.resize(idx + 1, serde_json::Value::Null);
+                    self.partial_json.resize(idx + 1, String::new());
+                }
+                self.blocks[idx] = v["content_block"].clone();
+            }
+            "content_block_delta" => {
+                let idx = Self::index(&v)?;
+                let block = self
+                    .blocks
+                    .get_mut(idx)
+                    .with_context(|| format!("delta for unknown content block {idx}"))?;
+                let delta = &v["delta"];
+                match delta["type"].as_str().unwrap_or_default() {
+                    "text_delta" => {
+                        let text = delta["text"].as_str().unwrap_or_default();
+                        append_str(block, "text", text);
+                        self.partial_output.push_str(text);
+                    }
+                    "input_json_delta" => {
+                        let part = delta["partial_json"].as_str().unwrap_or_default();
+                        self.partial_json[idx].push_str(part);
+                        self.partial_output.push_str(part);
+                    }
+                    "thinking_delta" => {
+                        append_str(
+                            block,
+                            "thinking",
+                            delta["thinking"].as_str().unwrap_or_default(),
+                        );
+                    }
+                    "signature_delta" => {
+                        block["signature"] = delta["signature"].clone();
+                    }
+                    _ => {}
+                }
+            }
+            "content_block_stop" => {
+                let idx = Self::index(&v)?;
+                let partial = self.partial_json.get(idx).map(String::as_str).unwrap_or("");
+                if !partial.is_empty() {
+                    let input = serde_json::from_str::<serde_json::Value>(partial)
+                        .context("tool_use input_json_delta did not form valid JSON")?;
+                    self.blocks[idx]["input"] = input;
+                }
+            }
+            "message_delta" => {
+                let message = self
+                    .message
+                    .as_mut()
+                    .context("message_delta before message_start")?;
+                if let Some(delta) = v["delta"].as_object() {
+                    for (k, val) in delta {
+                        message[k] = val.clone();
+                    }
+                }
+                if let Some(usage) = v["usage"].as_object() {
+                    for (k, val) in usage {
+                        message["usage"][k] = val.clone();
+                    }
+                }
+            }
+            "message_stop" => {
+                self.done = true;
+            }
+            "error" => {
+                anyhow::bail!("stream error event: {}", v["error"]);
+            }
+            // "ping" and future event types.
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn index(v: &serde_json::Value) -> anyhow::Result<usize> {
+        v["index"]
+            .as_u64()
+            .map(|i| i as usize)
+            .context("stream event missing index")
+    }
+
+    fn assembled(&self) -> Option<serde_json::Value> {
+        let mut message = self.message.clone()?;
+        message["content"] = serde_json::Value::Array(self.blocks.clone());
+        Some(message)
+    }
+
+    fn finish(self) -> anyhow::Result<serde_json::Value> {
+        if !self.done {
+            return Err(self.into_error("stream ended before message_stop".to_string()));
+        }
+        match self.assembled() {
+            Some(message) => Ok(message),
+            None => Err(self.into_error("stream had no message_start".to_string())),
+        }
+    }
+
+    fn into_error(self, detail: String) -> anyhow::Error {
+        LlmDiagnosticsError {
+            provider: Provider::Anthropic,
+            stage: "stream",
+            detail,
+            raw_response_json: self.assembled(),
+            raw_output: Some(self.partial_output),
+            http_status: None,
+        }
+        .into()
+    }
+}
+
+fn append_str(block: &mut serde_json::Value, key: &str, s: &str) {
+    let mut cur = block[key].as_str().unwrap_or_default().to_string();
+    cur.push_str(s);
+    block[key] = serde_json::Value::String(cur);
+}
+
+/// Reads `ANTHROPIC_THINKING_BUDGET`; unset/empty/`0` disables extended thinking.
+fn thinking_budget_from_env() -> anyhow::Result<Option<u32>> {
+    let budget = match std::env::var("ANTHROPIC_THINKING_BUDGET") {
+        Ok(s) if !s.trim().is_empty() => s
+            .trim()
+            .parse::<u32>()
+            .map_err(|_| anyhow::anyhow!("ANTHROPIC_THINKING_BUDGET is not a valid number: {s}"))?,
+        _ => return Ok(None),
+    };
+    if budget == 0 {
+        return Ok(None);
+    }
+    anyhow::ensure!(
+        budget >= MIN_THINKING_BUDGET,
+        "ANTHROPIC_THINKING_BUDGET must be >= {MIN_THINKING_BUDGET} (got {budget})"
+    );
+    Ok(Some(budget))
+}
+
+fn env_flag(key: &str) -> bool {
+    std::env::var(key)
+        .map(|s| matches!(s.trim().to_ascii_lowercase().as_str(), "1" | "true"))
+        .unwrap_or(false)
+}
+
+fn is_retryable_status(status: StatusCode) -> bool {
+    // 529 is Anthropic's "overloaded" status (not a registered StatusCode constant).
+    matches!(status.as_u16(), 429 | 500 | 502 | 503 | 504 | 529)
+}
+
+fn parse_retry_after(headers: &HeaderMap) -> Option<Duration> {
+    // Anthropic sends delta-seconds; HTTP-date values are ignored (fall back to backoff).
+    let v = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    let secs = v.parse::<f64>().ok()?;
+    if secs.is_nan() || secs < 0.0 {
+        return None;
+    }
+    // Too large for a `Duration` (e.g. `1e20`) just means "the longest we would wait".
+    let cap = Duration::from_millis(MAX_RETRY_BACKOFF_MS);
+    Some(Duration::try_from_secs_f64(secs).map_or(cap, |d| d.min(cap)))
+}
+
+fn retry_backoff(base: Duration, attempt: u32, retry_after: Option<Duration>) -> Duration {
+    if let Some(d) = retry_after {
+        return d.min(Duration::from_millis(MAX_RETRY_BACKOFF_MS));
+    }
+    let exp = attempt.saturating_sub(1).min(16);
+    let ms = (base.as_millis() as u64).saturating_mul(1u64 << exp);
+    Duration::from_millis(ms.min(MAX_RETRY_BACKOFF_MS))
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -397,13 +1190,22 @@ struct CreateMessageRequest {
     model: String,
     max_tokens: u32,
     #[serde(skip_serializing_if = "Option::is_none")]
-    system: Option<String>,
+    system: Option<Vec<SystemBlock>>,
     messages: Vec<Message>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
     tools: Option<Vec<Tool>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     tool_choice: Option<ToolChoice>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    thinking: Option<ThinkingConfig>,
+
+    #[serde(flatten)]
+    sampling: Sampling,
+
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -412,12 +1214,53 @@ struct Message {
     content: String,
 }
 
+#[derive(Debug, Clone, Deserialize)]
+struct MessageBatch {
+    id: String,
+    processing_status: String,
+    #[serde(default)]
+    results_url: Option<String>,
+}
+
 #[derive(Debug, Clone, Deserialize)]
 struct CreateMessageResponse {
     content: Vec<ContentBlock>,
 
     #[serde(default)]
     stop_reason: Option<String>,
+
+    #[serde(default)]
+    usage: Option<Usage>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type")]
+enum SystemBlock {
+    #[serde(rename = "text")]
+    Text {
+        text: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        cache_control: Option<CacheControl>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type")]
+enum CacheControl {
+    #[serde(rename = "ephemeral")]
+    Ephemeral,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct Usage {
+    #[serde(default)]
+    input_tokens: u64,
+    #[serde(default)]
+    output_tokens: u64,
+    #[serde(default)]
+    cache_creation_input_tokens: Option<u64>,
+    #[serde(default)]
+    cache_read_input_tokens: Option<u64>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -432,6 +1275,15 @@ struct Tool {
 enum ToolChoice {
     #[serde(rename = "tool")]
     Tool { name: &'static str },
+    #[serde(rename = "auto")]
+    Auto,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type")]
+enum ThinkingConfig {
+    #[serde(rename = "enabled")]
+    Enabled { budget_tokens: u32 },
 }
 
 #[cfg(test)]
@@ -440,9 +1292,7 @@ mod tests {
     use chrono::{NaiveDate, TimeZone, Utc};
     use serde_json::json;
 
-    #[test]
-    fn parses_tool_use_snapshot_input() {
-        let as_of = NaiveDate::from_ymd_opt(2026, 1, 28).unwrap();
+    fn valid_tool_input(as_of: NaiveDate) -> serde_json::Value {
         let generated_at = Utc.with_ymd_and_hms(2026, 1, 28, 9, 0, 0).unwrap();
         let items: Vec<_> = (1..=20)
             .map(|rank| {
@@ -457,19 +1307,25 @@ mod tests {
             })
             .collect();
 
-        let tool_input = json!({
+        json!({
             "as_of_date": as_of,
             "generated_at": generated_at,
             "items": items,
-        });
+        })
+    }
+
+    #[test]
+    fn parses_tool_use_snapshot_input() {
+        let as_of = NaiveDate::from_ymd_opt(2026, 1, 28).unwrap();
+        let tool_input = valid_tool_input(as_of);
 
         let res = CreateMessageResponse {
             content: vec![ContentBlock::ToolUse {
-                id: "toolu_1".to_string(),
                 name: TOOL_NAME_EMIT_SNAPSHOT.to_string(),
                 input: tool_input,
             }],
             stop_reason: None,
+            usage: None,
         };
 
         let parsed = AnthropicClient::response_tool_snapshot(&res)
@@ -480,8 +1336,715 @@ mod tests {
         assert_eq!(snapshot.items.len(), 20);
         assert_eq!(snapshot.items[0].rank, 1);
     }
+
+    #[test]
+    fn finds_tool_use_after_thinking_blocks() {
+        let as_of = NaiveDate::from_ymd_opt(2026, 1, 28).unwrap();
+        let res: CreateMessageResponse = serde_json::from_value(json!({
+            "content": [
+                {"type": "thinking", "thinking": "Compare momentum first.", "signature": "sig"},
+                {"type": "redacted_thinking", "data": "opaque"},
+                {"type": "text", "text": "Emitting the snapshot."},
+                {
+                    "type": "tool_use",
+                    "id": "toolu_1",
+                    "name": TOOL_NAME_EMIT_SNAPSHOT,
+                    "input": valid_tool_input(as_of),
+                },
+            ],
+            "stop_reason": "tool_use",
+        }))
+        .unwrap();
+
+        let parsed = AnthropicClient::response_tool_snapshot(&res)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            parsed
+                .validate_and_into_snapshot(as_of)
+                .unwrap()
+                .items
+                .len(),
+            20
+        );
+        assert_eq!(
+            AnthropicClient::response_text(&res).unwrap(),
+            "Emitting the snapshot."
+        );
+    }
+
+    #[test]
+    fn retryable_statuses() {
+        assert!(is_retryable_status(StatusCode::TOO_MANY_REQUESTS));
+        assert!(is_retryable_status(StatusCode::INTERNAL_SERVER_ERROR));
+        assert!(is_retryable_status(StatusCode::from_u16(529).unwrap()));
+        assert!(!is_retryable_status(StatusCode::BAD_REQUEST));
+        assert!(!is_retryable_status(StatusCode::UNAUTHORIZED));
+        assert!(!is_retryable_status(StatusCode::FORBIDDEN));
+    }
+
+    #[test]
+    fn batch_mode_deadline_covers_the_batch_timeout() {
+        let hour = Duration::from_secs(3600);
+        assert_eq!(
+            batch_total_deadline(None, hour).unwrap(),
+            hour + deadline::DEFAULT_TOTAL_DEADLINE
+        );
+        assert_eq!(
+            batch_total_deadline(Some(Duration::from_secs(4000)), hour).unwrap(),
+            Duration::from_secs(4000)
+        );
+        let err = batch_total_deadline(Some(Duration::from_secs(300)), hour).unwrap_err();
+        assert!(
+            err.to_string().contains("LLM_TOTAL_DEADLINE_SECS (300)"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn retry_backoff_prefers_retry_after() {
+        let base = Duration::from_millis(500);
+        assert_eq!(retry_backoff(base, 1, None), Duration::from_millis(500));
+        assert_eq!(retry_backoff(base, 3, None), Duration::from_millis(2000));
+        assert_eq!(
+            retry_backoff(base, 3, Some(Duration::from_secs(7))),
+            Duration::from_secs(7)
+        );
+        assert_eq!(
+            retry_backoff(base, 30, None),
+            Duration::from_millis(MAX_RETRY_BACKOFF_MS)
+        );
+        // A server-supplied wait is capped like our own.
+        assert_eq!(
+            retry_backoff(base, 1, Some(Duration::from_secs(3600))),
+            Duration::from_millis(MAX_RETRY_BACKOFF_MS)
+        );
+
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("12"));
+        assert_eq!(parse_retry_after(&headers), Some(Duration::from_secs(12)));
+        headers.insert(
+            RETRY_AFTER,
+            HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"),
+        );
+        assert_eq!(parse_retry_after(&headers), None);
+        for (value, want) in [
+            ("1e20", Some(Duration::from_millis(MAX_RETRY_BACKOFF_MS))),
+            ("-1", None),
+            ("NaN", None),
+            ("inf", Some(Duration::from_millis(MAX_RETRY_BACKOFF_MS))),
+        ] {
+            headers.insert(RETRY_AFTER, HeaderValue::from_static(value));
+            assert_eq!(parse_retry_after(&headers), want, "{value}");
+        }
+    }
+
+    fn test_client(base_url: String, stream: bool) -> AnthropicClient {
+        AnthropicClient {
+            http: reqwest::Client::new(),
+            api_key: "test-key".to_string(),
+            base_url,
+            model: DEFAULT_MODEL.to_string(),
+            max_tokens: DEFAULT_MAX_TOKENS,
+            max_retries: 0,
+            retry_base: Duration::from_millis(1),
+            stream,
+            prompt_budget: PromptBudget::default(),
+            prompt_cache: false,
+            sampling: Sampling::default(),
+            thinking_budget: None,
+            system_prompt: prompt::PromptTemplate::default(),
+            total_deadline: Duration::from_secs(300),
+            batch: None,
+            log_bodies: false,
+            attempt_sink: None,
+        }
+    }
+
+    #[test]
+    fn system_serializes_as_block_list() {
+        let mut client = test_client("http://localhost".to_string(), false);
+        let req = serde_json::to_value(client.build_request(
+            &test_input(NaiveDate::MIN),
+            100,
+            "hi".to_string(),
+        ))
+        .unwrap();
+        assert_eq!(req["system"][0]["type"], "text");
+        assert!(req["system"][0]["text"].as_str().unwrap().contains("KRX"));
+        assert!(req["system"][0].get("cache_control").is_none());
+        assert!(req.get("stream").is_none());
+
+        client.prompt_cache = true;
+        let req = serde_json::to_value(client.build_request(
+            &test_input(NaiveDate::MIN),
+            100,
+            "hi".to_string(),
+        ))
+        .unwrap();
+        assert_eq!(
+            req["system"][0]["cache_control"],
+            json!({"type": "ephemeral"})
+        );
+        assert_eq!(req["system"].as_array().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn preview_matches_the_initial_request() {
+        let client = test_client("http://localhost".to_string(), false);
+        let input = test_input(NaiveDate::from_ymd_opt(2026, 1, 28).unwrap());
+        let preview = client.preview_prompt(&input).unwrap();
+        assert_eq!(preview.request["system"][0]["text"], preview.system_prompt);
+        assert_eq!(
+            preview.request["messages"][0]["content"],
+            preview.user_prompt
+        );
+        assert!(preview.user_prompt.contains("as_of_date=2026-01-28"));
+        assert_eq!(preview.tool_schema[0]["name"], TOOL_NAME_EMIT_SNAPSHOT);
+        assert_eq!(preview.request["tools"], preview.tool_schema);
+        assert!(preview.estimated_tokens() > 0);
+    }
+
+    #[test]
+    fn sampling_is_omitted_unless_set() {
+        let mut client = test_client("http://localhost".to_string(), false);
+        let req = serde_json::to_value(client.build_request(
+            &test_input(NaiveDate::MIN),
+            100,
+            "hi".to_string(),
+        ))
+        .unwrap();
+        assert!(req.get("temperature").is_none());
+        assert!(req.get("top_p").is_none());
+        assert!(req.get("top_k").is_none());
+
+        client.sampling = Sampling {
+            temperature: Some(0.25),
+            top_p: None,
+            top_k: Some(40),
+        };
+        let req = serde_json::to_value(client.build_request(
+            &test_input(NaiveDate::MIN),
+            100,
+            "hi".to_string(),
+        ))
+        .unwrap();
+        assert_eq!(req["temperature"], 0.25);
+        assert!(req.get("top_p").is_none());
+        assert_eq!(req["top_k"], 40);
+    }
+
+    #[test]
+    fn sampling_rejects_out_of_range_values() {
+        let bad = [
+            Sampling {
+                temperature: Some(1.5),
+                ..Default::default()
+            },
+            Sampling {
+                top_p: Some(0.0),
+                ..Default::default()
+            },
+            Sampling {
+                top_k: Some(0),
+                ..Default::default()
+            },
+        ];
+        for s in bad {
+            assert!(s.validate().is_err(), "{s:?}");
+        }
+        Sampling {
+            temperature: Some(0.0),
+            top_p: Some(1.0),
+            top_k: Some(1),
+        }
+        .validate()
+        .unwrap();
+    }
+
+    #[test]
+    fn parses_cache_usage_fields() {
+        let res: CreateMessageResponse = serde_json::from_value(json!({
+            "content": [],
+            "stop_reason": "tool_use",
+            "usage": {
+                "input_tokens": 12,
+                "output_tokens": 34,
+                "cache_creation_input_tokens": 1000,
+                "cache_read_input_tokens": 0
+            }
+        }))
+        .unwrap();
+        let usage = res.usage.unwrap();
+        assert_eq!(usage.input_tokens, 12);
+        assert_eq!(usage.cache_creation_input_tokens, Some(1000));
+        assert_eq!(usage.cache_read_input_tokens, Some(0));
+
+        let res: CreateMessageResponse =
+            serde_json::from_value(json!({"content": [], "usage": {"input_tokens": 1}})).unwrap();
+        assert_eq!(res.usage.unwrap().cache_read_input_tokens, None);
+    }
+
+    fn test_input(as_of: NaiveDate) -> GenerateInput {
+        let candidates = (1..=GenerateInput::MIN_CANDIDATES)
+            .map(|i| crate::domain::recommendation::Candidate {
+                ticker: format!("KRX:{i:06}"),
+                name: format!("Name {i}"),
+                name_en: None,
+                sector: None,
+                features: [("ret_1d".to_string(), 0.01)].into_iter().collect(),
+            })
+            .collect();
+        GenerateInput::try_new(as_of, candidates).unwrap()
+    }
+
+    fn sse_body(events: &[serde_json::Value]) -> String {
+        events
+            .iter()
+            .map(|e| format!("event: {}\ndata: {}\n\n", e["type"].as_str().unwrap(), e))
+            .collect()
+    }
+
+    fn stream_prefix(tool_input: &str) -> Vec<serde_json::Value> {
+        let (a, b) = tool_input.split_at(tool_input.len() / 2);
+        vec![
+            json!({"type": "message_start", "message": {
+                "id": "msg_1", "type": "message", "role": "assistant", "content": [],
+                "model": DEFAULT_MODEL, "stop_reason": null, "usage": {"input_tokens": 10, "output_tokens": 1}
+            }}),
+            json!({"type": "ping"}),
+            json!({"type": "content_block_start", "index": 0, "content_block": {
+                "type": "tool_use", "id": "toolu_1", "name": TOOL_NAME_EMIT_SNAPSHOT, "input": {}
+            }}),
+            json!({"type": "content_block_delta", "index": 0, "delta": {"type": "input_json_delta", "partial_json": a}}),
+            json!({"type": "content_block_delta", "index": 0, "delta": {"type": "input_json_delta", "partial_json": b}}),
+        ]
+    }
+
+    async fn serve_sse(body: String) -> String {
+        let app = axum::Router::new().route(
+            "/v1/messages",
+            axum::routing::post(move || {
+                let body = body.clone();
+                async move {
+                    (
+                        [(axum::http::header::CONTENT_TYPE, "text/event-stream")],
+                        body,
+                    )
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        format!("http://{addr}")
+    }
+
+    fn tool_use_message(tool_input: serde_json::Value) -> serde_json::Value {
+        json!({
+            "id": "msg_1",
+            "type": "message",
+            "role": "assistant",
+            "model": DEFAULT_MODEL,
+            "content": [{
+                "type": "tool_use",
+                "id": "toolu_1",
+                "name": TOOL_NAME_EMIT_SNAPSHOT,
+                "input": tool_input,
+            }],
+            "stop_reason": "tool_use",
+        })
+    }
+
+    /// Stubs the messages endpoint with canned JSON responses (in order) and records request bodies.
+    async fn serve_json_sequence(
+        responses: Vec<serde_json::Value>,
+    ) -> (
+        String,
+        std::sync::Arc<std::sync::Mutex<Vec<serde_json::Value>>>,
+    ) {
+        let requests = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorded = requests.clone();
+        let responses = std::sync::Arc::new(responses);
+        let app = axum::Router::new().route(
+            "/v1/messages",
+            axum::routing::post(move |axum::Json(body): axum::Json<serde_json::Value>| {
+                let requests = recorded.clone();
+                let responses = responses.clone();
+                async move {
+                    let mut requests = requests.lock().unwrap();
+                    let idx = requests.len().min(responses.len() - 1);
+                    requests.push(body);
+                    axum::Json(responses[idx].clone())
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        (format!("http://{addr}"), requests)
+    }
+
+    #[tokio::test]
+    async fn rate_limited_and_overloaded_responses_are_retried() {
+        let as_of = NaiveDate::from_ymd_opt(2026, 1, 28).unwrap();
+        let message = tool_use_message(valid_tool_input(as_of));
+        let calls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counted = calls.clone();
+        let app = axum::Router::new().route(
+            "/v1/messages",
+            axum::routing::post(move || {
+                let n = counted.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                let message = message.clone();
+                async move {
+                    use axum::http::{header, StatusCode};
+                    use axum::response::IntoResponse;
+                    let error = |kind: &str| json!({"type": "error", "error": {"type": kind}});
+                    match n {
+                        0 => (
+                            StatusCode::TOO_MANY_REQUESTS,
+                            [(header::RETRY_AFTER, "0.2")],
+                            axum::Json(error("rate_limit_error")),
+                        )
+                            .into_response(),
+                        1 => (
+                            StatusCode::from_u16(529).unwrap(),
+                            axum::Json(error("overloaded_error")),
+                        )
+                            .into_response(),
+                        _ => axum::Json(message).into_response(),
+                    }
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        let mut client = test_client(base_url.clone(), false);
+        client.max_retries = 2;
+        let started = std::time::Instant::now();
+        let (snapshot, _) = client
+            .generate_recommendations_with_raw(test_input(as_of))
+            .await
+            .unwrap();
+        assert_eq!(snapshot.items.len(), 20);
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 3);
+        // The 429's Retry-After wins over the 1ms backoff base.
+        assert!(started.elapsed() >= Duration::from_millis(200));
+
+        // Without retries left the overload surfaces as an HTTP diagnostics error.
+        calls.store(1, std::sync::atomic::Ordering::SeqCst);
+        let err = test_client(base_url, false)
+            .generate_recommendations_with_raw(test_input(as_of))
+            .await
+            .unwrap_err();
+        let diag = err.downcast_ref::<LlmDiagnosticsError>().unwrap();
+        assert_eq!(diag.http_status, Some(529));
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn exhausted_transport_retries_surface_as_diagnostics() {
+        // Nothing listens on a port that was bound and released.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+
+        let mut client = test_client(base_url, false);
+        client.max_retries = 2;
+        let err = client
+            .generate_recommendations_with_raw(test_input(NaiveDate::MIN))
+            .await
+            .unwrap_err();
+        let diag = err.downcast_ref::<LlmDiagnosticsError>().unwrap();
+        assert_eq!(diag.stage, "transport", "{diag}");
+        assert!(diag.detail.starts_with("attempts=3 "), "{diag}");
+        assert_eq!(diag.http_status, None);
+        assert_eq!(
+            error::LlmFailureKind::classify(&err),
+            error::LlmFailureKind::HttpError
+        );
+    }
+
+    /// Stub Message Batches API: `pending_polls` in_progress polls, then ended with `result`.
+    async fn serve_batch(
+        pending_polls: usize,
+        result: serde_json::Value,
+    ) -> (String, Arc<std::sync::Mutex<Vec<serde_json::Value>>>) {
+        use axum::routing::{get, post};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let submitted = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let polls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+
+        let recorded = submitted.clone();
+        let results_url = format!("{base_url}/v1/messages/batches/msgbatch_1/results");
+        let app = axum::Router::new()
+            .route(
+                "/v1/messages/batches",
+                post(move |axum::Json(body): axum::Json<serde_json::Value>| {
+                    recorded.lock().unwrap().push(body);
+                    async {
+                        axum::Json(json!({"id": "msgbatch_1", "processing_status": "in_progress"}))
+                    }
+                }),
+            )
+            .route(
+                "/v1/messages/batches/msgbatch_1",
+                get(move || {
+                    let n = polls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                    let body = if n < pending_polls {
+                        json!({"id": "msgbatch_1", "processing_status": "in_progress"})
+                    } else {
+                        json!({"id": "msgbatch_1", "processing_status": "ended", "results_url": results_url})
+                    };
+                    async move { axum::Json(body) }
+                }),
+            )
+            .route(
+                "/v1/messages/batches/msgbatch_1/results",
+                get(move || {
+                    let line = json!({"custom_id": BATCH_CUSTOM_ID, "result": result}).to_string();
+                    async move { format!("{line}\n") }
+                }),
+            );
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        (base_url, submitted)
+    }
+
+    fn batch_client(base_url: String) -> AnthropicClient {
+        let mut client = test_client(base_url, false);
+        client.batch = Some(BatchConfig {
+            poll_interval: Duration::from_millis(5),
+            timeout: Duration::from_secs(5),
+        });
+        client
+    }
+
+    #[tokio::test]
+    async fn batch_mode_polls_until_ended_and_extracts_result() {
+        let as_of = NaiveDate::from_ymd_opt(2026, 1, 28).unwrap();
+        let message = tool_use_message(valid_tool_input(as_of));
+        let (base_url, submitted) =
+            serve_batch(2, json!({"type": "succeeded", "message": message})).await;
+
+        let (snapshot, raw) = batch_client(base_url)
+            .generate_recommendations_with_raw(test_input(as_of))
+            .await
+            .unwrap();
+        assert_eq!(snapshot.items.len(), 20);
+        assert_eq!(raw["stop_reason"], "tool_use");
+
+        let submitted = submitted.lock().unwrap();
+        assert_eq!(submitted.len(), 1);
+        let request = &submitted[0]["requests"][0];
+        assert_eq!(request["custom_id"], BATCH_CUSTOM_ID);
+        assert_eq!(
+            request["params"]["tool_choice"]["name"],
+            TOOL_NAME_EMIT_SNAPSHOT
+        );
+        assert!(request["params"].get("stream").is_none());
+    }
+
+    #[tokio::test]
+    async fn batch_mode_maps_expired_result_to_diagnostics() {
+        let as_of = NaiveDate::from_ymd_opt(2026, 1, 28).unwrap();
+        let (base_url, _) = serve_batch(0, json!({"type": "expired"})).await;
+
+        let err = batch_client(base_url)
+            .generate_recommendations_with_raw(test_input(as_of))
+            .await
+            .unwrap_err();
+        let diag = err.downcast_ref::<LlmDiagnosticsError>().unwrap();
+        assert_eq!(diag.stage, "batch_expired");
+        assert_eq!(
+            diag.raw_response_json.as_ref().unwrap()["result"]["type"],
+            "expired"
+        );
+    }
+
+    #[tokio::test]
+    async fn total_deadline_covers_repairs_and_keeps_partial_raw() {
+        let as_of = NaiveDate::from_ymd_opt(2026, 1, 28).unwrap();
+        let mut short = valid_tool_input(as_of);
+        short["items"].as_array_mut().unwrap().pop();
+        let first = tool_use_message(short);
+
+        // First call answers immediately (invalid), the repair call hangs.
+        let calls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let app = axum::Router::new().route(
+            "/v1/messages",
+            axum::routing::post(move || {
+                let calls = calls.clone();
+                let first = first.clone();
+                async move {
+                    if calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst) > 0 {
+                        tokio::time::sleep(Duration::from_secs(30)).await;
+                    }
+                    axum::Json(first)
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        let mut client = test_client(format!("http://{addr}"), false);
+        client.total_deadline = Duration::from_millis(300);
+        let err = client
+            .generate_recommendations_with_raw(test_input(as_of))
+            .await
+            .unwrap_err();
+        let diag = err.downcast_ref::<LlmDiagnosticsError>().unwrap();
+        assert_eq!(diag.stage, "deadline");
+        let raw = diag.raw_response_json.as_ref().unwrap();
+        assert_eq!(
+            raw["content"][0]["input"]["items"]
+                .as_array()
+                .unwrap()
+                .len(),
+            19
+        );
+    }
+
+    #[derive(Debug, Default)]
+    struct RecordingSink {
+        attempts: std::sync::Mutex<Vec<LlmAttempt>>,
+    }
+
+    #[async_trait::async_trait]
+    impl AttemptSink for RecordingSink {
+        async fn record(&self, attempt: LlmAttempt) {
+            self.attempts.lock().unwrap().push(attempt);
+        }
+    }
+
+    #[tokio::test]
+    async fn thinking_raises_max_tokens_and_persists_thinking_text() {
+        let as_of = NaiveDate::from_ymd_opt(2026, 1, 28).unwrap();
+        let mut message = tool_use_message(valid_tool_input(as_of));
+        message["content"].as_array_mut().unwrap().insert(
+            0,
+            json!({"type": "thinking", "thinking": "Liquidity first.", "signature": "sig"}),
+        );
+        let (base_url, requests) = serve_json_sequence(vec![message]).await;
+
+        let mut client = test_client(base_url, false);
+        client.thinking_budget = Some(2048);
+        let (snapshot, raw) = client
+            .generate_recommendations_with_raw(test_input(as_of))
+            .await
+            .unwrap();
+        assert_eq!(snapshot.items.len(), 20);
+        assert_eq!(
+            raw["thinking"],
+            json!({"budget_tokens": 2048, "text": "Liquidity first."})
+        );
+
+        let requests = requests.lock().unwrap();
+        let req = &requests[0];
+        assert_eq!(
+            req["thinking"],
+            json!({"type": "enabled", "budget_tokens": 2048})
+        );
+        assert_eq!(req["max_tokens"], DEFAULT_MAX_TOKENS + 2048);
+        assert_eq!(req["tool_choice"], json!({"type": "auto"}));
+    }
+
+    #[tokio::test]
+    async fn repairs_invalid_tool_payload_via_tool_use() {
+        let as_of = NaiveDate::from_ymd_opt(2026, 1, 28).unwrap();
+        let mut short = valid_tool_input(as_of);
+        short["items"].as_array_mut().unwrap().pop();
+        assert_eq!(short["items"].as_array().unwrap().len(), 19);
+
+        let (base_url, requests) = serve_json_sequence(vec![
+            tool_use_message(short),
+            tool_use_message(valid_tool_input(as_of)),
+        ])
+        .await;
+
+        let sink = Arc::new(RecordingSink::default());
+        let client = test_client(base_url, false).with_attempt_sink(sink.clone());
+        let (snapshot, raw) = client
+            .generate_recommendations_with_raw(test_input(as_of))
+            .await
+            .unwrap();
+        assert_eq!(snapshot.items.len(), 20);
+        assert_eq!(raw["content"][0]["input"]["items"][19]["rank"], 20);
+
+        let attempts = sink.attempts.lock().unwrap();
+        let stages: Vec<(u32, &str)> = attempts.iter().map(|a| (a.attempt_no, a.stage)).collect();
+        assert_eq!(stages, vec![(1, "initial"), (2, "repair")]);
+        assert!(attempts[0].error.as_deref().unwrap().contains("20 items"));
+        assert!(attempts[1].error.is_none());
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        let repair = &requests[1];
+        assert_eq!(repair["tool_choice"]["name"], TOOL_NAME_EMIT_SNAPSHOT);
+        let content = repair["messages"][0]["content"].as_str().unwrap();
+        assert!(content.contains("\"ticker\":\"KRX:000019\""));
+        assert!(!content.contains("\"ticker\":\"KRX:000020\""));
+    }
+
+    #[tokio::test]
+    async fn streaming_assembles_tool_use_snapshot() {
+        let as_of = NaiveDate::from_ymd_opt(2026, 1, 28).unwrap();
+        let tool_input = valid_tool_input(as_of).to_string();
+
+        let mut events = stream_prefix(&tool_input);
+        events.push(json!({"type": "content_block_stop", "index": 0}));
+        events.push(json!({"type": "message_delta", "delta": {"stop_reason": "tool_use", "stop_sequence": null}, "usage": {"output_tokens": 500}}));
+        events.push(json!({"type": "message_stop"}));
+
+        let base_url = serve_sse(sse_body(&events)).await;
+        let client = test_client(base_url, true);
+        let (snapshot, raw) = client
+            .generate_recommendations_with_raw(test_input(as_of))
+            .await
+            .unwrap();
+
+        assert_eq!(snapshot.items.len(), 20);
+        assert_eq!(raw["stop_reason"], "tool_use");
+        assert_eq!(raw["usage"]["output_tokens"], 500);
+        assert_eq!(raw["content"][0]["type"], "tool_use");
+        assert_eq!(raw["content"][0]["input"]["items"][0]["rank"], 1);
+    }
+
+    #[tokio::test]
+    async fn truncated_stream_attaches_partial_output() {
+        let as_of = NaiveDate::from_ymd_opt(2026, 1, 28).unwrap();
+        let tool_input = valid_tool_input(as_of).to_string();
+
+        let base_url = serve_sse(sse_body(&stream_prefix(&tool_input))).await;
+        let client = test_client(base_url, true);
+        let err = client
+            .generate_recommendations_with_raw(test_input(as_of))
+            .await
+            .unwrap_err();
+
+        let diag = err.downcast_ref::<LlmDiagnosticsError>().unwrap();
+        assert_eq!(diag.stage, "stream");
+        assert_eq!(diag.raw_output.as_deref(), Some(tool_input.as_str()));
+        assert_eq!(diag.raw_response_json.as_ref().unwrap()["id"], "msg_1");
+    }
 }
 
+/// The parts of a content block the client reads; the raw response JSON keeps the rest (tool
+/// ids, thinking text and signatures) for diagnostics.
 #[derive(Debug, Clone, Deserialize)]
 #[serde(tag = "type")]
 enum ContentBlock {
@@ -490,8 +2053,6 @@ enum ContentBlock {
 
     #[serde(rename = "tool_use")]
     ToolUse {
-        #[serde(default)]
-        id: String,
         #[serde(default)]
         name: String,
         #[serde(default)]
@@ -499,18 +2060,10 @@ enum ContentBlock {
     },
 
     #[serde(rename = "thinking")]
-    Thinking {
-        #[serde(default)]
-        thinking: String,
-        #[serde(default)]
-        signature: String,
-    },
+    Thinking,
 
     #[serde(rename = "redacted_thinking")]
-    RedactedThinking {
-        #[serde(default)]
-        data: String,
-    },
+    RedactedThinking,
 
     #[serde(other)]
     Unknown,
@@ -0,0 +1,23 @@
+use crate::llm::Provider;
+
+/// One provider call made while producing a snapshot.
+#[derive(Debug, Clone)]
+pub struct LlmAttempt {
+    pub as_of_date: chrono::NaiveDate,
+    pub provider: Provider,
+    pub model: String,
+    /// 1-based, in call order within a single `generate_recommendations_with_raw`.
+    pub attempt_no: u32,
+    /// `initial`, `max_tokens_retry` or `repair`.
+    pub stage: &'static str,
+    pub raw_response: Option<serde_json::Value>,
+    /// Why the attempt was not accepted; `None` for the accepted attempt.
+    pub error: Option<String>,
+}
+
+/// Receives every LLM attempt so callers can keep an audit trail without the LLM layer depending
+/// on a storage implementation. Recording is best-effort: sinks handle their own errors.
+#[async_trait::async_trait]
+pub trait AttemptSink: Send + Sync + std::fmt::Debug {
+    async fn record(&self, attempt: LlmAttempt);
+}
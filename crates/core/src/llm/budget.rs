@@ -0,0 +1,264 @@
+use crate::domain::recommendation::Candidate;
+use std::collections::BTreeSet;
+
+const DEFAULT_MAX_TOKENS: usize = 60_000;
+const DEFAULT_FLOAT_PRECISION: u32 = 4;
+const DEFAULT_FEATURE_PRIORITY: &[&str] = &[
+    "ret_1d",
+    "trading_value",
+    "mom_5d",
+    "volume",
+    "vol_20d",
+    "mom_20d",
+    "ma20_gap",
+    "pct_off_52w_high",
+    "pct_off_52w_low",
+    "days_to_earnings",
+    "per",
+    "pbr",
+    "eps",
+    "dividend_yield",
+];
+
+/// Caps the estimated size of the candidates JSON passed to the LLM.
+///
+/// When over budget, floats are rounded to `float_precision` and features are dropped from every
+/// candidate in reverse priority order (features not listed in `feature_priority` go first).
+/// The highest-priority feature is always kept.
+#[derive(Debug, Clone)]
+pub struct PromptBudget {
+    pub max_tokens: usize,
+    pub feature_priority: Vec<String>,
+    pub float_precision: u32,
+    /// Context window of the configured model (from the model registry), when known.
+    pub context_window: Option<usize>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BudgetReport {
+    pub estimated_tokens_before: usize,
+    pub estimated_tokens_after: usize,
+    pub rounded: bool,
+    pub dropped_features: Vec<String>,
+    pub dropped_values: usize,
+}
+
+impl Default for PromptBudget {
+    fn default() -> Self {
+        Self {
+            max_tokens: DEFAULT_MAX_TOKENS,
+            feature_priority: DEFAULT_FEATURE_PRIORITY
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            float_precision: DEFAULT_FLOAT_PRECISION,
+            context_window: None,
+        }
+    }
+}
+
+impl PromptBudget {
+    pub fn from_env() -> Self {
+        let mut out = Self::default();
+
+        if let Ok(s) = std::env::var("LLM_PROMPT_BUDGET_TOKENS") {
+            if let Ok(n) = s.parse::<usize>() {
+                out.max_tokens = n;
+            }
+        }
+
+        if let Ok(s) = std::env::var("LLM_FEATURE_PRIORITY") {
+            let keys: Vec<String> = s
+                .split(',')
+                .map(|k| k.trim().to_string())
+                .filter(|k| !k.is_empty())
+                .collect();
+            if !keys.is_empty() {
+                out.feature_priority = keys;
+            }
+        }
+
+        if let Ok(s) = std::env::var("LLM_FLOAT_PRECISION") {
+            if let Ok(n) = s.parse::<u32>() {
+                out.float_precision = n.min(12);
+            }
+        }
+
+        out
+    }
+
+    pub fn with_context_window(mut self, context_window: Option<usize>) -> Self {
+        self.context_window = context_window;
+        self
+    }
+
+    /// chars/4 heuristic; good enough for budgeting, not billing.
+    pub fn estimate_tokens(s: &str) -> usize {
+        s.chars().count().div_ceil(4)
+    }
+
+    pub fn fit(
+        &self,
+        as_of_date: chrono::NaiveDate,
+        candidates: &[Candidate],
+    ) -> (serde_json::Value, BudgetReport) {
+        let render = |cs: &[Candidate]| {
+            serde_json::json!({
+                "as_of_date": as_of_date,
+                "candidates": cs,
+            })
+        };
+
+        let mut value = render(candidates);
+        let mut estimate = Self::estimate_tokens(&value.to_string());
+        let mut report = BudgetReport {
+            estimated_tokens_before: estimate,
+            estimated_tokens_after: estimate,
+            ..Default::default()
+        };
+        if estimate <= self.max_tokens {
+            return (value, report);
+        }
+
+        let mut working: Vec<Candidate> = candidates.to_vec();
+        let scale = 10f64.powi(self.float_precision as i32);
+        for c in &mut working {
+            for v in c.features.values_mut() {
+                *v = (*v * scale).round() / scale;
+            }
+        }
+        report.rounded = true;
+        value = render(&working);
+        estimate = Self::estimate_tokens(&value.to_string());
+
+        for key in self.drop_order(&working) {
+            if estimate <= self.max_tokens {
+                break;
+            }
+            for c in &mut working {
+                if c.features.remove(&key).is_some() {
+                    report.dropped_values += 1;
+                }
+            }
+            report.dropped_features.push(key);
+            value = render(&working);
+            estimate = Self::estimate_tokens(&value.to_string());
+        }
+
+        report.estimated_tokens_after = estimate;
+        (value, report)
+    }
+
+    fn drop_order(&self, candidates: &[Candidate]) -> Vec<String> {
+        let present: BTreeSet<&str> = candidates
+            .iter()
+            .flat_map(|c| c.features.keys().map(String::as_str))
+            .collect();
+
+        // Unlisted features first (alphabetical for determinism), then the allow-list tail-first.
+        let mut out: Vec<String> = present
+            .iter()
+            .filter(|k| !self.feature_priority.iter().any(|p| p == *k))
+            .map(|k| k.to_string())
+            .collect();
+        out.extend(
+            self.feature_priority
+                .iter()
+                .skip(1)
+                .rev()
+                .filter(|p| present.contains(p.as_str()))
+                .cloned(),
+        );
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use std::collections::BTreeMap;
+
+    fn candidates(n: usize) -> Vec<Candidate> {
+        (1..=n)
+            .map(|i| {
+                let features: BTreeMap<String, f64> = [
+                    ("ret_1d", 0.0123456789),
+                    ("mom_5d", 0.0234567891),
+                    ("trading_value", 123456789.123),
+                    ("zzz_extra", 1.0),
+                ]
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v))
+                .collect();
+                Candidate {
+                    ticker: format!("KRX:{i:06}"),
+                    name: format!("Name {i}"),
+                    name_en: None,
+                    sector: None,
+                    features,
+                }
+            })
+            .collect()
+    }
+
+    fn budget(max_tokens: usize) -> PromptBudget {
+        PromptBudget {
+            max_tokens,
+            feature_priority: vec![
+                "ret_1d".to_string(),
+                "trading_value".to_string(),
+                "mom_5d".to_string(),
+            ],
+            float_precision: 2,
+            context_window: None,
+        }
+    }
+
+    #[test]
+    fn under_budget_is_unchanged() {
+        let as_of = NaiveDate::from_ymd_opt(2026, 1, 27).unwrap();
+        let cs = candidates(3);
+        let (v, report) = budget(usize::MAX).fit(as_of, &cs);
+        assert!(!report.rounded);
+        assert!(report.dropped_features.is_empty());
+        assert_eq!(v["candidates"][0]["features"]["ret_1d"], 0.0123456789);
+    }
+
+    #[test]
+    fn over_budget_rounds_then_drops_lowest_priority_first() {
+        let as_of = NaiveDate::from_ymd_opt(2026, 1, 27).unwrap();
+        let cs = candidates(10);
+        let b = budget(1);
+        let (v, report) = b.fit(as_of, &cs);
+
+        assert!(report.rounded);
+        // The top-priority feature is never dropped, even if still over budget.
+        assert_eq!(
+            report.dropped_features,
+            vec!["zzz_extra", "mom_5d", "trading_value"]
+        );
+        assert_eq!(report.dropped_values, 30);
+        let features = v["candidates"][0]["features"].as_object().unwrap();
+        assert_eq!(features.len(), 1);
+        assert_eq!(features["ret_1d"], 0.01);
+    }
+
+    #[test]
+    fn stops_dropping_once_within_budget() {
+        let as_of = NaiveDate::from_ymd_opt(2026, 1, 27).unwrap();
+        // Values already at the target precision, so rounding alone cannot fit the budget.
+        let mut cs = candidates(10);
+        for c in &mut cs {
+            for v in c.features.values_mut() {
+                *v = 1.5;
+            }
+        }
+        let (full, _) = budget(usize::MAX).fit(as_of, &cs);
+        let full_tokens = PromptBudget::estimate_tokens(&full.to_string());
+
+        let (_, report) = budget(full_tokens - 10).fit(as_of, &cs);
+        assert_eq!(report.dropped_features, vec!["zzz_extra"]);
+        assert!(report.estimated_tokens_after <= full_tokens - 10);
+    }
+}
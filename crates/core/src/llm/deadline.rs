@@ -0,0 +1,69 @@
+// Overall wall-clock budget for one generation (initial call + max_tokens bump + repairs).
+// Distinct from the per-request HTTP timeout: that bounds a single call, this bounds the run.
+
+use crate::llm::error::LlmDiagnosticsError;
+use crate::llm::Provider;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::Duration;
+
+pub const DEFAULT_TOTAL_DEADLINE: Duration = Duration::from_secs(300);
+
+/// Reads `LLM_TOTAL_DEADLINE_SECS` (default: `300`).
+pub fn total_deadline_from_env() -> Duration {
+    configured_total_deadline().unwrap_or(DEFAULT_TOTAL_DEADLINE)
+}
+
+/// `LLM_TOTAL_DEADLINE_SECS`, when set.
+pub fn configured_total_deadline() -> Option<Duration> {
+    std::env::var("LLM_TOTAL_DEADLINE_SECS")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .map(Duration::from_secs)
+}
+
+/// Latest raw provider response seen during a run, kept so a deadline error can carry it.
+#[derive(Debug, Default)]
+pub struct PartialRaw(Mutex<Option<serde_json::Value>>);
+
+impl PartialRaw {
+    pub fn set(&self, raw: &serde_json::Value) {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner()) = Some(raw.clone());
+    }
+
+    fn take(&self) -> Option<serde_json::Value> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).take()
+    }
+}
+
+/// Runs `fut` under `deadline`; on expiry returns an `LlmDiagnosticsError` (stage `deadline`)
+/// carrying the last raw response recorded in `partial`.
+pub async fn run_with_deadline<T>(
+    provider: Provider,
+    deadline: Duration,
+    partial: &PartialRaw,
+    fut: impl Future<Output = anyhow::Result<T>>,
+) -> anyhow::Result<T> {
+    match tokio::time::timeout(deadline, fut).await {
+        Ok(res) => res,
+        Err(_) => {
+            tracing::error!(
+                provider = provider.as_str(),
+                deadline_secs = deadline.as_secs_f64(),
+                "LLM generation exceeded total deadline"
+            );
+            Err(LlmDiagnosticsError {
+                provider,
+                stage: "deadline",
+                detail: format!(
+                    "generation exceeded total deadline of {:.1}s",
+                    deadline.as_secs_f64()
+                ),
+                raw_output: None,
+                raw_response_json: partial.take(),
+                http_status: None,
+            }
+            .into())
+        }
+    }
+}
@@ -0,0 +1,366 @@
+use crate::domain::recommendation::{RecommendationItem, RecommendationSnapshot};
+use crate::llm::error::LlmDiagnosticsError;
+use crate::llm::{GenerateInput, LlmClient, PromptPreview, Provider};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+const TOP_N: usize = 20;
+
+/// Self-consistency wrapper: calls the inner client `runs` times and merges the picks by vote.
+///
+/// Tickers are ordered by appearance count, ties broken by average rank (then ticker), and the
+/// top 20 are re-ranked. Each merged item's `confidence` is its appearance fraction over the
+/// successful runs. At least `ceil(runs / 2)` runs must succeed.
+pub struct EnsembleLlmClient {
+    inner: Box<dyn LlmClient>,
+    runs: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TallyEntry {
+    pub ticker: String,
+    pub appearances: usize,
+    pub avg_rank: f64,
+}
+
+impl EnsembleLlmClient {
+    pub fn new(inner: Box<dyn LlmClient>, runs: usize) -> Self {
+        Self {
+            inner,
+            runs: runs.max(1),
+        }
+    }
+
+    /// Reads `LLM_ENSEMBLE_RUNS` (default: `1`, i.e. disabled).
+    pub fn runs_from_env() -> usize {
+        std::env::var("LLM_ENSEMBLE_RUNS")
+            .ok()
+            .and_then(|s| s.parse::<usize>().ok())
+            .unwrap_or(1)
+            .max(1)
+    }
+
+    fn min_successes(&self) -> usize {
+        self.runs.div_ceil(2)
+    }
+}
+
+/// Merges successful runs by vote. Each run is expected to hold distinct tickers.
+pub fn merge_by_vote(
+    as_of_date: chrono::NaiveDate,
+    runs: &[RecommendationSnapshot],
+) -> (RecommendationSnapshot, Vec<TallyEntry>) {
+    struct Acc<'a> {
+        appearances: usize,
+        rank_sum: i64,
+        best: &'a RecommendationItem,
+    }
+
+    let mut acc: BTreeMap<&str, Acc<'_>> = BTreeMap::new();
+    for snapshot in runs {
+        for item in &snapshot.items {
+            let e = acc.entry(item.ticker.as_str()).or_insert(Acc {
+                appearances: 0,
+                rank_sum: 0,
+                best: item,
+            });
+            e.appearances += 1;
+            e.rank_sum += i64::from(item.rank);
+            // Keep the rationale from the run that ranked the ticker highest.
+            if item.rank < e.best.rank {
+                e.best = item;
+            }
+        }
+    }
+
+    let mut ranked: Vec<(TallyEntry, &RecommendationItem)> = acc
+        .into_iter()
+        .map(|(ticker, a)| {
+            (
+                TallyEntry {
+                    ticker: ticker.to_string(),
+                    appearances: a.appearances,
+                    avg_rank: a.rank_sum as f64 / a.appearances as f64,
+                },
+                a.best,
+            )
+        })
+        .collect();
+    ranked.sort_by(|(a, _), (b, _)| {
+        b.appearances
+            .cmp(&a.appearances)
+            .then(a.avg_rank.total_cmp(&b.avg_rank))
+            .then_with(|| a.ticker.cmp(&b.ticker))
+    });
+
+    let total = runs.len().max(1) as f64;
+    let items = ranked
+        .iter()
+        .take(TOP_N)
+        .enumerate()
+        .map(|(i, (tally, best))| RecommendationItem {
+            rank: i as i32 + 1,
+            ticker: best.ticker.clone(),
+            name: best.name.clone(),
+            name_en: best.name_en.clone(),
+            rationale: best.rationale.clone(),
+            risk_notes: best.risk_notes.clone(),
+            confidence: Some(tally.appearances as f64 / total),
+        })
+        .collect();
+
+    let tally = ranked.into_iter().map(|(t, _)| t).collect();
+    let snapshot = RecommendationSnapshot {
+        as_of_date,
+        generated_at: chrono::Utc::now(),
+        items,
+    };
+    (snapshot, tally)
+}
+
+#[async_trait::async_trait]
+impl LlmClient for EnsembleLlmClient {
+    fn provider(&self) -> Provider {
+        self.inner.provider()
+    }
+
+    fn prompt_hash(&self, input: &GenerateInput) -> Option<String> {
+        self.inner.prompt_hash(input)
+    }
+
+    fn preview_prompt(&self, input: &GenerateInput) -> anyhow::Result<PromptPreview> {
+        self.inner.preview_prompt(input)
+    }
+
+    async fn generate_recommendations(
+        &self,
+        input: GenerateInput,
+    ) -> anyhow::Result<RecommendationSnapshot> {
+        let (snapshot, _raw) = self.generate_recommendations_with_raw(input).await?;
+        Ok(snapshot)
+    }
+
+    async fn generate_recommendations_with_raw(
+        &self,
+        input: GenerateInput,
+    ) -> anyhow::Result<(RecommendationSnapshot, serde_json::Value)> {
+        let mut snapshots = Vec::new();
+        let mut raw_responses = Vec::new();
+        let mut failures = Vec::new();
+
+        // Sequential on purpose: provider rate limits make parallel runs counterproductive.
+        for run in 1..=self.runs {
+            match self
+                .inner
+                .generate_recommendations_with_raw(input.clone())
+                .await
+            {
+                Ok((snapshot, raw)) => {
+                    snapshots.push(snapshot);
+                    raw_responses.push(raw);
+                }
+                Err(err) => {
+                    tracing::warn!(
+                        %input.as_of_date,
+                        run,
+                        runs = self.runs,
+                        error = %err,
+                        "ensemble run failed"
+                    );
+                    failures.push(serde_json::json!({ "run": run, "error": format!("{err:#}") }));
+                }
+            }
+        }
+
+        if snapshots.len() < self.min_successes() {
+            return Err(LlmDiagnosticsError {
+                provider: self.provider(),
+                stage: "ensemble",
+                detail: format!(
+                    "only {}/{} runs succeeded (need {})",
+                    snapshots.len(),
+                    self.runs,
+                    self.min_successes()
+                ),
+                raw_output: None,
+                raw_response_json: Some(serde_json::json!({
+                    "runs": self.runs,
+                    "failures": failures,
+                    "raw_responses": raw_responses,
+                })),
+                http_status: None,
+            }
+            .into());
+        }
+
+        let (mut snapshot, tally) = merge_by_vote(input.as_of_date, &snapshots);
+        // The merge can only surface tickers the runs returned, but keep the guard explicit.
+        input.ensure_tickers_in_universe(&snapshot)?;
+        snapshot.generated_at = snapshots
+            .iter()
+            .map(|s| s.generated_at)
+            .max()
+            .unwrap_or(snapshot.generated_at);
+
+        tracing::info!(
+            %input.as_of_date,
+            runs = self.runs,
+            succeeded = snapshots.len(),
+            "ensemble merged by vote"
+        );
+
+        let raw = serde_json::json!({
+            "ensemble": {
+                "runs": self.runs,
+                "succeeded": snapshots.len(),
+                "failures": failures,
+                "tally": tally,
+            },
+            "raw_responses": raw_responses,
+        });
+        Ok((snapshot, raw))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::recommendation::Candidate;
+    use chrono::{NaiveDate, TimeZone, Utc};
+    use std::sync::Mutex;
+
+    fn snapshot(as_of: NaiveDate, ids: &[usize]) -> RecommendationSnapshot {
+        RecommendationSnapshot {
+            as_of_date: as_of,
+            generated_at: Utc.with_ymd_and_hms(2026, 1, 27, 10, 0, 0).unwrap(),
+            items: ids
+                .iter()
+                .enumerate()
+                .map(|(i, id)| RecommendationItem {
+                    rank: i as i32 + 1,
+                    ticker: format!("KRX:{id:06}"),
+                    name: format!("Name {id}"),
+                    name_en: None,
+                    rationale: ["a".to_string(), "b".to_string(), "c".to_string()],
+                    risk_notes: None,
+                    confidence: None,
+                })
+                .collect(),
+        }
+    }
+
+    struct Scripted {
+        results: Mutex<Vec<anyhow::Result<RecommendationSnapshot>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl LlmClient for Scripted {
+        fn provider(&self) -> Provider {
+            Provider::Anthropic
+        }
+
+        async fn generate_recommendations(
+            &self,
+            input: GenerateInput,
+        ) -> anyhow::Result<RecommendationSnapshot> {
+            Ok(self.generate_recommendations_with_raw(input).await?.0)
+        }
+
+        async fn generate_recommendations_with_raw(
+            &self,
+            _input: GenerateInput,
+        ) -> anyhow::Result<(RecommendationSnapshot, serde_json::Value)> {
+            let s = self.results.lock().unwrap().remove(0)?;
+            Ok((s, serde_json::json!({ "stub": true })))
+        }
+    }
+
+    fn input(as_of: NaiveDate) -> GenerateInput {
+        let candidates = (1..=GenerateInput::MIN_CANDIDATES)
+            .map(|i| Candidate {
+                ticker: format!("KRX:{i:06}"),
+                name: format!("Name {i}"),
+                name_en: None,
+                sector: None,
+                features: Default::default(),
+            })
+            .collect();
+        GenerateInput::try_new(as_of, candidates).unwrap()
+    }
+
+    #[test]
+    fn merges_by_count_then_average_rank() {
+        let as_of = NaiveDate::from_ymd_opt(2026, 1, 27).unwrap();
+        let a: Vec<usize> = (1..=20).collect();
+        // 21 replaces 1; 2 moves to the front.
+        let mut b: Vec<usize> = vec![2];
+        b.extend((3..=20).chain([21]));
+        let mut c: Vec<usize> = vec![2];
+        c.extend((3..=20).chain([22]));
+
+        let (merged, tally) = merge_by_vote(
+            as_of,
+            &[
+                snapshot(as_of, &a),
+                snapshot(as_of, &b),
+                snapshot(as_of, &c),
+            ],
+        );
+
+        assert_eq!(merged.items.len(), 20);
+        assert_eq!(merged.items[0].ticker, "KRX:000002");
+        assert_eq!(merged.items[0].confidence, Some(1.0));
+        // 1 appears once at rank 1; 21/22 once at rank 20 → 1 wins the last slot.
+        assert_eq!(merged.items[19].ticker, "KRX:000001");
+        assert!((merged.items[19].confidence.unwrap() - 1.0 / 3.0).abs() < 1e-9);
+        assert_eq!(tally.len(), 22);
+        assert_eq!(tally[21].ticker, "KRX:000022");
+        let ranks: Vec<i32> = merged.items.iter().map(|i| i.rank).collect();
+        assert_eq!(ranks, (1..=20).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn tolerates_minority_failures() {
+        let as_of = NaiveDate::from_ymd_opt(2026, 1, 27).unwrap();
+        let ids: Vec<usize> = (1..=20).collect();
+        let inner = Scripted {
+            results: Mutex::new(vec![
+                Ok(snapshot(as_of, &ids)),
+                Err(anyhow::anyhow!("boom")),
+                Ok(snapshot(as_of, &ids)),
+            ]),
+        };
+        let client = EnsembleLlmClient::new(Box::new(inner), 3);
+
+        let (snapshot, raw) = client
+            .generate_recommendations_with_raw(input(as_of))
+            .await
+            .unwrap();
+        assert_eq!(snapshot.items[0].confidence, Some(1.0));
+        assert_eq!(raw["ensemble"]["succeeded"], 2);
+        assert_eq!(raw["ensemble"]["failures"][0]["run"], 2);
+        assert_eq!(raw["raw_responses"].as_array().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn fails_when_majority_of_runs_fail() {
+        let as_of = NaiveDate::from_ymd_opt(2026, 1, 27).unwrap();
+        let ids: Vec<usize> = (1..=20).collect();
+        let inner = Scripted {
+            results: Mutex::new(vec![
+                Err(anyhow::anyhow!("boom")),
+                Ok(snapshot(as_of, &ids)),
+                Err(anyhow::anyhow!("boom")),
+            ]),
+        };
+        let client = EnsembleLlmClient::new(Box::new(inner), 3);
+
+        let err = client
+            .generate_recommendations_with_raw(input(as_of))
+            .await
+            .unwrap_err();
+        let diag = err.downcast_ref::<LlmDiagnosticsError>().unwrap();
+        assert_eq!(diag.stage, "ensemble");
+        assert!(diag.detail.contains("1/3"));
+    }
+}
@@ -9,6 +9,8 @@ pub struct LlmDiagnosticsError {
     pub detail: String,
     pub raw_output: Option<String>,
     pub raw_response_json: Option<Value>,
+    /// Final HTTP status for `http` stage errors.
+    pub http_status: Option<u16>,
 }
 
 impl fmt::Display for LlmDiagnosticsError {
@@ -22,3 +24,159 @@ impl fmt::Display for LlmDiagnosticsError {
 }
 
 impl std::error::Error for LlmDiagnosticsError {}
+
+/// Stage for an output that is still invalid after the repair budget is spent: undecodable JSON
+/// (`parse_after_repair`) vs. well-formed JSON that breaks the snapshot contract.
+pub(crate) fn after_repair_stage(last_err: &anyhow::Error) -> &'static str {
+    if last_err.chain().any(|e| e.is::<serde_json::Error>()) {
+        "parse_after_repair"
+    } else {
+        "contract_after_repair"
+    }
+}
+
+/// Coarse failure cause persisted as `recommendation_snapshots.error_kind` for aggregation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LlmFailureKind {
+    HttpError,
+    RateLimited,
+    Timeout,
+    ParseError,
+    ContractViolation,
+    Deadline,
+    ProviderRefused,
+}
+
+impl LlmFailureKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LlmFailureKind::HttpError => "http_error",
+            LlmFailureKind::RateLimited => "rate_limited",
+            LlmFailureKind::Timeout => "timeout",
+            LlmFailureKind::ParseError => "parse_error",
+            LlmFailureKind::ContractViolation => "contract_violation",
+            LlmFailureKind::Deadline => "deadline",
+            LlmFailureKind::ProviderRefused => "provider_refused",
+        }
+    }
+
+    /// Maps an `LlmDiagnosticsError` stage (and HTTP status, for `http`) to a kind.
+    pub fn from_stage(stage: &str, http_status: Option<u16>) -> Self {
+        match stage {
+            "http" => match http_status {
+                Some(429 | 529) => LlmFailureKind::RateLimited,
+                Some(408 | 504) => LlmFailureKind::Timeout,
+                _ => LlmFailureKind::HttpError,
+            },
+            "deadline" => LlmFailureKind::Deadline,
+            "batch_timeout" | "batch_expired" | "transport_timeout" => LlmFailureKind::Timeout,
+            "safety_block" | "finish_reason" => LlmFailureKind::ProviderRefused,
+            "parse_after_repair" | "truncated" | "empty_response" => LlmFailureKind::ParseError,
+            "contract_after_repair" | "sanity" | "ensemble" => LlmFailureKind::ContractViolation,
+            // transport, stream, batch_results, batch_errored, batch_canceled, and anything new.
+            _ => LlmFailureKind::HttpError,
+        }
+    }
+
+    /// Classifies any generation error: diagnostics by stage, otherwise by the error chain.
+    pub fn classify(err: &anyhow::Error) -> Self {
+        if let Some(diag) = err.downcast_ref::<LlmDiagnosticsError>() {
+            return Self::from_stage(diag.stage, diag.http_status);
+        }
+        for cause in err.chain() {
+            if let Some(e) = cause.downcast_ref::<reqwest::Error>() {
+                return if e.is_timeout() {
+                    LlmFailureKind::Timeout
+                } else {
+                    LlmFailureKind::HttpError
+                };
+            }
+            if cause.is::<serde_json::Error>() {
+                return LlmFailureKind::ParseError;
+            }
+        }
+        LlmFailureKind::ContractViolation
+    }
+}
+
+impl fmt::Display for LlmFailureKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::contract::UnknownTickersError;
+
+    fn diag(stage: &'static str, http_status: Option<u16>) -> anyhow::Error {
+        LlmDiagnosticsError {
+            provider: Provider::Anthropic,
+            stage,
+            detail: String::new(),
+            raw_output: None,
+            raw_response_json: None,
+            http_status,
+        }
+        .into()
+    }
+
+    #[test]
+    fn maps_every_stage_and_status() {
+        use LlmFailureKind::*;
+        let cases: &[(&'static str, Option<u16>, LlmFailureKind)] = &[
+            ("http", Some(429), RateLimited),
+            ("http", Some(529), RateLimited),
+            ("http", Some(408), Timeout),
+            ("http", Some(504), Timeout),
+            ("http", Some(400), HttpError),
+            ("http", Some(401), HttpError),
+            ("http", Some(500), HttpError),
+            ("http", None, HttpError),
+            ("stream", None, HttpError),
+            ("batch_results", None, HttpError),
+            ("batch_errored", None, HttpError),
+            ("batch_canceled", None, HttpError),
+            ("batch_timeout", None, Timeout),
+            ("batch_expired", None, Timeout),
+            ("transport", None, HttpError),
+            ("transport_timeout", None, Timeout),
+            ("deadline", None, Deadline),
+            ("safety_block", None, ProviderRefused),
+            ("finish_reason", None, ProviderRefused),
+            ("parse_after_repair", None, ParseError),
+            ("truncated", None, ParseError),
+            ("empty_response", None, ParseError),
+            ("contract_after_repair", None, ContractViolation),
+            ("sanity", None, ContractViolation),
+            ("ensemble", None, ContractViolation),
+            ("something_new", None, HttpError),
+        ];
+        for &(stage, status, want) in cases {
+            assert_eq!(
+                LlmFailureKind::classify(&diag(stage, status)),
+                want,
+                "stage={stage} status={status:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn classifies_plain_errors_by_chain() {
+        let json_err = serde_json::from_str::<serde_json::Value>("{").unwrap_err();
+        let err = anyhow::Error::from(json_err).context("failed to parse response JSON");
+        assert_eq!(LlmFailureKind::classify(&err), LlmFailureKind::ParseError);
+        assert_eq!(after_repair_stage(&err), "parse_after_repair");
+
+        let err: anyhow::Error = UnknownTickersError {
+            tickers: vec!["KRX:999999".to_string()],
+        }
+        .into();
+        assert_eq!(
+            LlmFailureKind::classify(&err),
+            LlmFailureKind::ContractViolation
+        );
+        assert_eq!(after_repair_stage(&err), "contract_after_repair");
+    }
+}
@@ -0,0 +1,532 @@
+use crate::config::Settings;
+use crate::domain::recommendation::RecommendationSnapshot;
+use crate::llm::attempts::{AttemptSink, LlmAttempt};
+use crate::llm::budget::PromptBudget;
+use crate::llm::deadline::{self, PartialRaw};
+use crate::llm::error::{self, LlmDiagnosticsError};
+use crate::llm::models::ModelRegistry;
+use crate::llm::prompt;
+use crate::llm::{GenerateInput, LlmClient, PromptPreview, Provider};
+use anyhow::Context;
+use reqwest::header::{HeaderMap, HeaderValue};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+
+const DEFAULT_BASE_URL: &str = "https://generativelanguage.googleapis.com";
+const DEFAULT_MODEL: &str = "gemini-2.5-pro";
+const DEFAULT_MAX_OUTPUT_TOKENS: u32 = 8192;
+const DEFAULT_TIMEOUT_SECS: u64 = 60;
+
+#[derive(Debug, Clone)]
+pub struct GeminiClient {
+    http: reqwest::Client,
+    api_key: String,
+    base_url: String,
+    model: String,
+    max_output_tokens: u32,
+    prompt_budget: PromptBudget,
+    system_prompt: prompt::PromptTemplate,
+    total_deadline: Duration,
+    attempt_sink: Option<Arc<dyn AttemptSink>>,
+}
+
+impl GeminiClient {
+    pub fn from_settings(settings: &Settings) -> anyhow::Result<Self> {
+        let api_key = settings.require_gemini_api_key()?.to_string();
+        let base_url =
+            std::env::var("GEMINI_BASE_URL").unwrap_or_else(|_| DEFAULT_BASE_URL.to_string());
+        let model = std::env::var("GEMINI_MODEL").unwrap_or_else(|_| DEFAULT_MODEL.to_string());
+        let model_info = ModelRegistry::default().validate(Provider::Gemini, &model)?;
+        let max_output_tokens = std::env::var("GEMINI_MAX_OUTPUT_TOKENS")
+            .ok()
+            .and_then(|s| s.parse::<u32>().ok())
+            .unwrap_or(DEFAULT_MAX_OUTPUT_TOKENS);
+        if let Some(info) = model_info.filter(|m| max_output_tokens > m.max_output_tokens) {
+            tracing::warn!(
+                model = %model,
+                max_output_tokens,
+                model_max_output_tokens = info.max_output_tokens,
+                "GEMINI_MAX_OUTPUT_TOKENS exceeds the model's max output tokens"
+            );
+        }
+
+        let timeout_secs = std::env::var("GEMINI_TIMEOUT_SECS")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(DEFAULT_TIMEOUT_SECS);
+
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(timeout_secs))
+            .build()
+            .context("failed to build reqwest client")?;
+
+        Ok(Self {
+            http,
+            api_key,
+            base_url,
+            model,
+            max_output_tokens,
+            prompt_budget: PromptBudget::from_env()
+                .with_context_window(model_info.map(|m| m.context_window)),
+            system_prompt: prompt::PromptTemplate::from_env()?,
+            total_deadline: deadline::total_deadline_from_env(),
+            attempt_sink: None,
+        })
+    }
+
+    /// Records every provider call (including rejected outputs) to `sink`.
+    pub fn with_attempt_sink(mut self, sink: Arc<dyn AttemptSink>) -> Self {
+        self.attempt_sink = Some(sink);
+        self
+    }
+
+    /// Extracts and parses one response, recording the attempt either way.
+    async fn parse_attempt(
+        &self,
+        input: &GenerateInput,
+        attempt_no: u32,
+        stage: &'static str,
+        raw_json: &serde_json::Value,
+        res: &GenerateContentResponse,
+    ) -> anyhow::Result<(String, anyhow::Result<RecommendationSnapshot>)> {
+        let outcome = Self::response_text(raw_json, res).map(|text| {
+            let parsed = prompt::parse_snapshot(&text, input);
+            (text, parsed)
+        });
+
+        if let Some(sink) = &self.attempt_sink {
+            let error = match &outcome {
+                Err(err) | Ok((_, Err(err))) => Some(format!("{err:#}")),
+                Ok((_, Ok(_))) => None,
+            };
+            sink.record(LlmAttempt {
+                as_of_date: input.as_of_date,
+                provider: Provider::Gemini,
+                model: self.model.clone(),
+                attempt_no,
+                stage,
+                raw_response: Some(raw_json.clone()),
+                error,
+            })
+            .await;
+        }
+
+        outcome
+    }
+
+    async fn generate_content(
+        &self,
+        req: &GenerateContentRequest,
+    ) -> anyhow::Result<(serde_json::Value, GenerateContentResponse)> {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-goog-api-key", HeaderValue::from_str(&self.api_key)?);
+
+        let url = format!(
+            "{}/v1beta/models/{}:generateContent",
+            self.base_url.trim_end_matches('/'),
+            self.model
+        );
+        let res = self
+            .http
+            .post(url)
+            .headers(headers)
+            .json(req)
+            .send()
+            .await
+            .context("Gemini request failed")?;
+
+        let status = res.status();
+        let text = res
+            .text()
+            .await
+            .context("failed to read Gemini response body")?;
+        if !status.is_success() {
+            let raw_response_json = serde_json::from_str::<serde_json::Value>(&text).ok();
+            return Err(LlmDiagnosticsError {
+                provider: Provider::Gemini,
+                stage: "http",
+                detail: format!("status={status}"),
+                raw_output: Some(text),
+                raw_response_json,
+                http_status: Some(status.as_u16()),
+            }
+            .into());
+        }
+
+        let raw_json = serde_json::from_str::<serde_json::Value>(&text)
+            .with_context(|| format!("failed to parse Gemini response JSON: {text}"))?;
+        let parsed = serde_json::from_value::<GenerateContentResponse>(raw_json.clone())
+            .context("failed to decode Gemini response into GenerateContentResponse")?;
+        Ok((raw_json, parsed))
+    }
+
+    fn build_request(&self, input: &GenerateInput, user_content: String) -> GenerateContentRequest {
+        GenerateContentRequest {
+            system_instruction: Content {
+                role: None,
+                parts: vec![Part {
+                    text: self.system_prompt.render(input),
+                }],
+            },
+            contents: vec![Content {
+                role: Some("user"),
+                parts: vec![Part { text: user_content }],
+            }],
+            generation_config: GenerationConfig {
+                response_mime_type: "application/json",
+                response_schema: Self::response_schema(),
+                max_output_tokens: self.max_output_tokens,
+            },
+        }
+    }
+
+    fn response_schema() -> serde_json::Value {
+        // OpenAPI-subset schema mirroring the snapshot contract (Gemini uses upper-case types and
+        // `nullable` instead of type unions).
+        serde_json::json!({
+            "type": "OBJECT",
+            "required": ["as_of_date", "generated_at", "items"],
+            "properties": {
+                "as_of_date": {"type": "STRING"},
+                "generated_at": {"type": "STRING"},
+                "items": {
+                    "type": "ARRAY",
+                    "minItems": 20,
+                    "maxItems": 20,
+                    "items": {
+                        "type": "OBJECT",
+                        "required": ["rank", "ticker", "name", "rationale", "risk_notes", "confidence"],
+                        "properties": {
+                            "rank": {"type": "INTEGER"},
+                            "ticker": {"type": "STRING"},
+                            "name": {"type": "STRING"},
+                            "rationale": {
+                                "type": "ARRAY",
+                                "minItems": 3,
+                                "maxItems": 3,
+                                "items": {"type": "STRING"}
+                            },
+                            "risk_notes": {"type": "STRING", "nullable": true},
+                            "confidence": {"type": "NUMBER", "nullable": true}
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    /// Extracts the JSON text, mapping blocked/truncated generations into diagnostics errors.
+    fn response_text(
+        raw_json: &serde_json::Value,
+        res: &GenerateContentResponse,
+    ) -> anyhow::Result<String> {
+        let diag = |stage: &'static str, detail: String, raw_output: Option<String>| {
+            anyhow::Error::from(LlmDiagnosticsError {
+                provider: Provider::Gemini,
+                stage,
+                detail,
+                raw_output,
+                raw_response_json: Some(raw_json.clone()),
+                http_status: None,
+            })
+        };
+
+        if let Some(reason) = res
+            .prompt_feedback
+            .as_ref()
+            .and_then(|f| f.block_reason.as_deref())
+        {
+            return Err(diag(
+                "safety_block",
+                format!("prompt blocked: block_reason={reason}"),
+                None,
+            ));
+        }
+
+        let Some(candidate) = res.candidates.first() else {
+            return Err(diag("empty_response", "no candidates".to_string(), None));
+        };
+
+        let text: String = candidate
+            .content
+            .iter()
+            .flat_map(|c| c.parts.iter())
+            .filter_map(|p| p.text.as_deref())
+            .collect();
+
+        match candidate.finish_reason.as_deref() {
+            None | Some("STOP") => Ok(text),
+            Some("MAX_TOKENS") => Err(diag(
+                "truncated",
+                "finish_reason=MAX_TOKENS".to_string(),
+                Some(text),
+            )),
+            Some(
+                reason @ ("SAFETY" | "RECITATION" | "BLOCKLIST" | "PROHIBITED_CONTENT" | "SPII"),
+            ) => Err(diag(
+                "safety_block",
+                format!("finish_reason={reason}"),
+                Some(text),
+            )),
+            Some(other) => Err(diag(
+                "finish_reason",
+                format!("finish_reason={other}"),
+                Some(text),
+            )),
+        }
+    }
+
+    /// Bounded by `LLM_TOTAL_DEADLINE_SECS` across the initial call and the repair pass.
+    pub async fn generate_recommendations_with_raw(
+        &self,
+        input: GenerateInput,
+    ) -> anyhow::Result<(RecommendationSnapshot, serde_json::Value)> {
+        let partial = PartialRaw::default();
+        deadline::run_with_deadline(
+            Provider::Gemini,
+            self.total_deadline,
+            &partial,
+            self.generate_with_partial(input, &partial),
+        )
+        .await
+    }
+
+    async fn generate_with_partial(
+        &self,
+        input: GenerateInput,
+        partial: &PartialRaw,
+    ) -> anyhow::Result<(RecommendationSnapshot, serde_json::Value)> {
+        let req = self.build_request(&input, prompt::user_prompt(&input, &self.prompt_budget));
+        let (raw_json, res) = self.generate_content(&req).await?;
+        partial.set(&raw_json);
+        let (text, parsed) = self
+            .parse_attempt(&input, 1, "initial", &raw_json, &res)
+            .await?;
+
+        let err = match parsed {
+            Ok(snapshot) => return Ok((snapshot, raw_json)),
+            Err(err) => err,
+        };
+
+        // Single repair pass.
+        tracing::warn!(%input.as_of_date, error = %err, "Gemini output invalid; attempting repair");
+        let repair_req = self.build_request(&input, prompt::repair_prompt(&text, &input, &err));
+        let (repair_raw_json, repair_res) = self.generate_content(&repair_req).await?;
+        partial.set(&repair_raw_json);
+        let (repair_text, parsed) = self
+            .parse_attempt(&input, 2, "repair", &repair_raw_json, &repair_res)
+            .await?;
+        match parsed {
+            Ok(snapshot) => Ok((snapshot, repair_raw_json)),
+            Err(err) => Err(LlmDiagnosticsError {
+                provider: Provider::Gemini,
+                stage: error::after_repair_stage(&err),
+                detail: format!("final_error={err}"),
+                raw_output: Some(repair_text),
+                raw_response_json: Some(repair_raw_json),
+                http_status: None,
+            }
+            .into()),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl LlmClient for GeminiClient {
+    fn provider(&self) -> Provider {
+        Provider::Gemini
+    }
+
+    fn prompt_hash(&self, input: &GenerateInput) -> Option<String> {
+        Some(self.system_prompt.hash(input))
+    }
+
+    fn preview_prompt(&self, input: &GenerateInput) -> anyhow::Result<PromptPreview> {
+        let user_prompt = prompt::user_prompt(input, &self.prompt_budget);
+        let req = self.build_request(input, user_prompt.clone());
+        Ok(PromptPreview {
+            provider: Provider::Gemini,
+            model: self.model.clone(),
+            system_prompt: self.system_prompt.render(input),
+            user_prompt,
+            tool_schema: Self::response_schema(),
+            request: serde_json::to_value(req)?,
+        })
+    }
+
+    async fn generate_recommendations(
+        &self,
+        input: GenerateInput,
+    ) -> anyhow::Result<RecommendationSnapshot> {
+        let (snapshot, _raw) = self.generate_recommendations_with_raw(input).await?;
+        Ok(snapshot)
+    }
+
+    async fn generate_recommendations_with_raw(
+        &self,
+        input: GenerateInput,
+    ) -> anyhow::Result<(RecommendationSnapshot, serde_json::Value)> {
+        GeminiClient::generate_recommendations_with_raw(self, input).await
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GenerateContentRequest {
+    system_instruction: Content,
+    contents: Vec<Content>,
+    generation_config: GenerationConfig,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct Content {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    role: Option<&'static str>,
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct Part {
+    text: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GenerationConfig {
+    response_mime_type: &'static str,
+    response_schema: serde_json::Value,
+    max_output_tokens: u32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GenerateContentResponse {
+    #[serde(default)]
+    candidates: Vec<ResponseCandidate>,
+    #[serde(default)]
+    prompt_feedback: Option<PromptFeedback>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ResponseCandidate {
+    #[serde(default)]
+    content: Option<ResponseContent>,
+    #[serde(default)]
+    finish_reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ResponseContent {
+    #[serde(default)]
+    parts: Vec<ResponsePart>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ResponsePart {
+    #[serde(default)]
+    text: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PromptFeedback {
+    #[serde(default)]
+    block_reason: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn test_client() -> GeminiClient {
+        GeminiClient {
+            http: reqwest::Client::new(),
+            api_key: "test-key".to_string(),
+            base_url: DEFAULT_BASE_URL.to_string(),
+            model: DEFAULT_MODEL.to_string(),
+            max_output_tokens: 123,
+            prompt_budget: PromptBudget::default(),
+            system_prompt: prompt::PromptTemplate::default(),
+            total_deadline: Duration::from_secs(300),
+            attempt_sink: None,
+        }
+    }
+
+    fn decode(v: serde_json::Value) -> (serde_json::Value, GenerateContentResponse) {
+        let parsed = serde_json::from_value::<GenerateContentResponse>(v.clone()).unwrap();
+        (v, parsed)
+    }
+
+    #[test]
+    fn request_uses_json_mime_type_and_schema() {
+        let req = serde_json::to_value(test_client().build_request(
+            &GenerateInput {
+                as_of_date: chrono::NaiveDate::MIN,
+                candidates: Vec::new(),
+                validation: Default::default(),
+                previous_snapshot: None,
+                market_context: Default::default(),
+            },
+            "hi".to_string(),
+        ))
+        .unwrap();
+        assert_eq!(
+            req["generationConfig"]["responseMimeType"],
+            "application/json"
+        );
+        assert_eq!(req["generationConfig"]["maxOutputTokens"], 123);
+        assert_eq!(
+            req["generationConfig"]["responseSchema"]["properties"]["items"]["maxItems"],
+            20
+        );
+        assert!(req["systemInstruction"].get("role").is_none());
+        assert_eq!(req["contents"][0]["role"], "user");
+        assert_eq!(req["contents"][0]["parts"][0]["text"], "hi");
+    }
+
+    #[test]
+    fn stop_returns_concatenated_text() {
+        let (raw, res) = decode(json!({
+            "candidates": [{
+                "content": {"role": "model", "parts": [{"text": "{\"a\":"}, {"text": "1}"}]},
+                "finishReason": "STOP"
+            }]
+        }));
+        assert_eq!(
+            GeminiClient::response_text(&raw, &res).unwrap(),
+            "{\"a\":1}"
+        );
+    }
+
+    #[test]
+    fn maps_truncation_and_safety_into_diagnostics() {
+        let (raw, res) = decode(json!({
+            "candidates": [{
+                "content": {"parts": [{"text": "{\"as_of_date\":"}]},
+                "finishReason": "MAX_TOKENS"
+            }]
+        }));
+        let err = GeminiClient::response_text(&raw, &res).unwrap_err();
+        let diag = err.downcast_ref::<LlmDiagnosticsError>().unwrap();
+        assert_eq!(diag.stage, "truncated");
+        assert_eq!(diag.raw_output.as_deref(), Some("{\"as_of_date\":"));
+        assert_eq!(diag.raw_response_json.as_ref(), Some(&raw));
+
+        let (raw, res) = decode(json!({
+            "candidates": [{"finishReason": "SAFETY", "safetyRatings": []}]
+        }));
+        let err = GeminiClient::response_text(&raw, &res).unwrap_err();
+        let diag = err.downcast_ref::<LlmDiagnosticsError>().unwrap();
+        assert_eq!(diag.stage, "safety_block");
+        assert_eq!(diag.detail, "finish_reason=SAFETY");
+
+        let (raw, res) = decode(json!({"promptFeedback": {"blockReason": "OTHER"}}));
+        let err = GeminiClient::response_text(&raw, &res).unwrap_err();
+        let diag = err.downcast_ref::<LlmDiagnosticsError>().unwrap();
+        assert_eq!(diag.stage, "safety_block");
+        assert!(diag.detail.contains("block_reason=OTHER"));
+    }
+}
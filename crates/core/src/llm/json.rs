@@ -1,4 +1,4 @@
-use crate::domain::contract::LlmRecommendationSnapshot;
+use crate::domain::contract::{LlmRecommendationSnapshot, ValidationOptions};
 use crate::domain::recommendation::RecommendationSnapshot;
 use anyhow::Context;
 
@@ -7,7 +7,7 @@ pub fn extract_json(text: &str) -> Option<String> {
     if trimmed.starts_with("```") {
         // Remove Markdown fences (```json ... ``` or ``` ... ```).
         let mut inner = trimmed;
-        if let Some(after_first) = inner.splitn(2, '\n').nth(1) {
+        if let Some((_, after_first)) = inner.split_once('\n') {
             inner = after_first;
         }
         if let Some(end) = inner.rfind("```") {
@@ -28,11 +28,19 @@ pub fn extract_json(text: &str) -> Option<String> {
 pub fn parse_snapshot(
     text: &str,
     expected_as_of_date: chrono::NaiveDate,
+) -> anyhow::Result<RecommendationSnapshot> {
+    parse_snapshot_with(text, expected_as_of_date, ValidationOptions::default())
+}
+
+pub fn parse_snapshot_with(
+    text: &str,
+    expected_as_of_date: chrono::NaiveDate,
+    opts: ValidationOptions,
 ) -> anyhow::Result<RecommendationSnapshot> {
     let json_str = extract_json(text).unwrap_or_else(|| text.trim().to_string());
     let parsed = serde_json::from_str::<LlmRecommendationSnapshot>(&json_str)
         .with_context(|| format!("LLM output is not valid JSON for snapshot schema: {json_str}"))?;
-    parsed.validate_and_into_snapshot(expected_as_of_date)
+    parsed.validate_and_into_snapshot_with(expected_as_of_date, opts)
 }
 
 #[cfg(test)]
@@ -108,6 +116,15 @@ mod tests {
         assert!(parse_snapshot(&json, as_of).is_err());
     }
 
+    #[test]
+    fn parse_snapshot_rejects_duplicate_tickers() {
+        let as_of = NaiveDate::from_ymd_opt(2026, 1, 27).unwrap();
+        let mut v: serde_json::Value = serde_json::from_str(&valid_snapshot_json(as_of)).unwrap();
+        v["items"][5]["ticker"] = json!("KRX:000001");
+        let err = parse_snapshot(&v.to_string(), as_of).unwrap_err();
+        assert!(err.to_string().contains("duplicate ticker: KRX:000001"));
+    }
+
     #[test]
     fn parse_snapshot_accepts_missing_optional_keys() {
         let as_of = NaiveDate::from_ymd_opt(2026, 1, 27).unwrap();
@@ -134,4 +151,122 @@ mod tests {
         let snapshot = parse_snapshot(&json, as_of).unwrap();
         assert_eq!(snapshot.items.len(), 20);
     }
+
+    fn with_rationale(as_of: NaiveDate, lines: [&str; 3]) -> String {
+        let mut v: serde_json::Value = serde_json::from_str(&valid_snapshot_json(as_of)).unwrap();
+        for item in v["items"].as_array_mut().unwrap() {
+            item["rationale"] = json!(["외국인 순매수 지속", "거래대금 증가", "실적 개선 기대"]);
+        }
+        v["items"][2]["rationale"] = json!(lines);
+        v["items"][7]["rationale"] = json!(lines);
+        v.to_string()
+    }
+
+    #[test]
+    fn korean_rationale_check_rejects_pure_english() {
+        let as_of = NaiveDate::from_ymd_opt(2026, 1, 27).unwrap();
+        let opts = ValidationOptions {
+            require_korean_rationale: true,
+            ..Default::default()
+        };
+        let json = with_rationale(
+            as_of,
+            ["Strong momentum", "Foreign buying", "Earnings beat"],
+        );
+
+        // Off by default.
+        parse_snapshot(&json, as_of).unwrap();
+
+        let err = parse_snapshot_with(&json, as_of, opts).unwrap_err();
+        let lang = err
+            .downcast_ref::<crate::domain::contract::NonKoreanRationaleError>()
+            .unwrap();
+        assert_eq!(lang.tickers, vec!["KRX:000003", "KRX:000008"]);
+    }
+
+    #[test]
+    fn rationale_length_and_duplicate_limits() {
+        use crate::domain::contract::{RationaleConstraintError, RationaleViolation};
+
+        let as_of = NaiveDate::from_ymd_opt(2026, 1, 27).unwrap();
+        let opts = ValidationOptions {
+            rationale_max_chars: Some(20),
+            rationale_min_chars: Some(5),
+            rationale_max_duplicates: Some(2),
+            ..Default::default()
+        };
+        let base = |v: &mut serde_json::Value| {
+            for (i, item) in v["items"].as_array_mut().unwrap().iter_mut().enumerate() {
+                item["rationale"] = json!([
+                    format!("종목 {i} 외국인 순매수"),
+                    format!("종목 {i} 거래대금 증가"),
+                    format!("종목 {i} 실적 개선"),
+                ]);
+            }
+        };
+
+        let mut v: serde_json::Value = serde_json::from_str(&valid_snapshot_json(as_of)).unwrap();
+        base(&mut v);
+        parse_snapshot_with(&v.to_string(), as_of, opts).unwrap();
+
+        // Same line (modulo case/whitespace/trailing period) in three items; two is allowed.
+        v["items"][0]["rationale"][0] = json!("Strong momentum.");
+        v["items"][1]["rationale"][1] = json!("strong  momentum");
+        parse_snapshot_with(&v.to_string(), as_of, opts).unwrap();
+        v["items"][2]["rationale"][2] = json!("Strong Momentum");
+        v["items"][3]["rationale"][0] = json!("짧음");
+        v["items"][4]["rationale"][0] = json!("x".repeat(21));
+
+        let err = parse_snapshot_with(&v.to_string(), as_of, opts).unwrap_err();
+        let rc = err.downcast_ref::<RationaleConstraintError>().unwrap();
+        assert_eq!(
+            rc.violations,
+            vec![
+                RationaleViolation::TooShort {
+                    ticker: "KRX:000004".to_string(),
+                    line: "짧음".to_string(),
+                    chars: 2,
+                    min: 5,
+                },
+                RationaleViolation::TooLong {
+                    ticker: "KRX:000005".to_string(),
+                    line: "x".repeat(21),
+                    chars: 21,
+                    max: 20,
+                },
+                RationaleViolation::Duplicated {
+                    line: "Strong momentum.".to_string(),
+                    tickers: vec![
+                        "KRX:000001".to_string(),
+                        "KRX:000002".to_string(),
+                        "KRX:000003".to_string(),
+                    ],
+                    max: 2,
+                },
+            ]
+        );
+
+        // Limits are off by default.
+        parse_snapshot(&v.to_string(), as_of).unwrap();
+    }
+
+    #[test]
+    fn korean_rationale_check_handles_mixed_and_korean() {
+        let as_of = NaiveDate::from_ymd_opt(2026, 1, 27).unwrap();
+        let opts = ValidationOptions {
+            require_korean_rationale: true,
+            ..Default::default()
+        };
+
+        // Korean with English tickers/metrics is fine.
+        let mixed = with_rationale(
+            as_of,
+            ["PER 8.5배로 저평가", "HBM 수요 증가", "ret_1d +3% 상승"],
+        );
+        parse_snapshot_with(&mixed, as_of, opts).unwrap();
+
+        // One English-only line is enough to fail.
+        let partly = with_rationale(as_of, ["PER 8.5배로 저평가", "HBM demand", "상승 추세"]);
+        assert!(parse_snapshot_with(&partly, as_of, opts).is_err());
+    }
 }
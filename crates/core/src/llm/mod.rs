@@ -1,13 +1,32 @@
+use crate::domain::contract::{UnknownTickersError, ValidationOptions};
 use crate::domain::recommendation::{Candidate, RecommendationSnapshot};
+use std::collections::{BTreeMap, BTreeSet};
 
 pub mod anthropic;
+pub mod attempts;
+pub mod budget;
+pub mod deadline;
+pub mod ensemble;
 pub mod error;
+pub mod gemini;
 pub mod json;
+pub mod models;
+pub mod openai_compat;
+pub mod prompt;
+pub mod redact;
+pub mod sanity;
+pub mod sse;
 
 #[derive(Debug, Clone)]
 pub struct GenerateInput {
     pub as_of_date: chrono::NaiveDate,
     pub candidates: Vec<Candidate>,
+    /// Extra output checks; also reflected in the system prompt.
+    pub validation: ValidationOptions,
+    /// Latest successful snapshot before `as_of_date`, shown to the model for continuity.
+    pub previous_snapshot: Option<RecommendationSnapshot>,
+    /// Index-level context for the date (`kospi_ret_1d`, `kosdaq_ret_1d`); empty when unknown.
+    pub market_context: BTreeMap<String, f64>,
 }
 
 impl GenerateInput {
@@ -29,29 +48,355 @@ impl GenerateInput {
         Ok(Self {
             as_of_date,
             candidates,
+            validation: ValidationOptions::default(),
+            previous_snapshot: None,
+            market_context: BTreeMap::new(),
         })
     }
 
+    pub fn with_validation(mut self, validation: ValidationOptions) -> Self {
+        self.validation = validation;
+        self
+    }
+
+    pub fn with_previous_snapshot(mut self, previous: Option<RecommendationSnapshot>) -> Self {
+        self.previous_snapshot = previous;
+        self
+    }
+
+    pub fn with_market_context(mut self, context: BTreeMap<String, f64>) -> Self {
+        self.market_context = context;
+        self
+    }
+
+    /// SHA-256 (hex) of the canonical candidates JSON; identifies exactly what the model saw.
+    pub fn universe_digest(&self) -> String {
+        // Struct fields serialize in declaration order and features are a BTreeMap, so this is
+        // stable for identical inputs.
+        let canonical = serde_json::to_vec(&self.candidates).unwrap_or_default();
+        sha256_hex(&canonical)
+    }
+
+    pub fn universe_size(&self) -> usize {
+        self.candidates.len()
+    }
+
+    /// Adds `universe: {digest, size}` to a raw LLM payload before it is persisted. Non-object
+    /// payloads are wrapped as `{raw, universe}`.
+    pub fn attach_universe(&self, raw: Option<serde_json::Value>) -> serde_json::Value {
+        let meta = serde_json::json!({
+            "digest": self.universe_digest(),
+            "size": self.universe_size(),
+        });
+        match raw {
+            Some(serde_json::Value::Object(mut obj)) => {
+                obj.insert("universe".to_string(), meta);
+                serde_json::Value::Object(obj)
+            }
+            Some(other) => serde_json::json!({ "raw": other, "universe": meta }),
+            None => serde_json::json!({ "universe": meta }),
+        }
+    }
+
     pub fn candidates_json(&self) -> serde_json::Value {
         serde_json::json!({
             "as_of_date": self.as_of_date,
             "candidates": self.candidates,
         })
     }
+
+    /// Rejects snapshots that reference tickers outside the candidate universe.
+    pub fn ensure_tickers_in_universe(
+        &self,
+        snapshot: &RecommendationSnapshot,
+    ) -> anyhow::Result<()> {
+        let universe: BTreeSet<&str> = self.candidates.iter().map(|c| c.ticker.as_str()).collect();
+        let unknown: Vec<String> = snapshot
+            .items
+            .iter()
+            .filter(|item| !universe.contains(item.ticker.as_str()))
+            .map(|item| item.ticker.clone())
+            .collect();
+        if !unknown.is_empty() {
+            return Err(UnknownTickersError { tickers: unknown }.into());
+        }
+        Ok(())
+    }
+
+    /// Same shape as `candidates_json`, pruned to fit the prompt budget. Prompt builders should
+    /// use this variant.
+    pub fn candidates_json_budgeted(&self, budget: &budget::PromptBudget) -> serde_json::Value {
+        let (value, report) = budget.fit(self.as_of_date, &self.candidates);
+        if report.rounded {
+            tracing::info!(
+                as_of_date = %self.as_of_date,
+                budget_tokens = budget.max_tokens,
+                estimated_tokens_before = report.estimated_tokens_before,
+                estimated_tokens = report.estimated_tokens_after,
+                dropped_features = ?report.dropped_features,
+                dropped_values = report.dropped_values,
+                "candidates JSON over prompt budget; rounded and pruned features"
+            );
+        }
+        if report.estimated_tokens_after > budget.max_tokens {
+            tracing::warn!(
+                as_of_date = %self.as_of_date,
+                budget_tokens = budget.max_tokens,
+                estimated_tokens = report.estimated_tokens_after,
+                "candidates JSON still over prompt budget after pruning"
+            );
+        }
+        if let Some(context_window) = budget.context_window {
+            // Leave headroom for the system prompt, tool schema and output.
+            if report.estimated_tokens_after * 10 >= context_window * 8 {
+                tracing::warn!(
+                    as_of_date = %self.as_of_date,
+                    context_window,
+                    estimated_tokens = report.estimated_tokens_after,
+                    "candidates JSON is at or above 80% of the model context window"
+                );
+            }
+        }
+        value
+    }
 }
 
-#[derive(Debug, Clone)]
+/// Lowercase hex SHA-256 of `bytes`.
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    openssl::sha::sha256(bytes)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Provider {
     Anthropic,
     OpenAI,
+    Gemini,
+    /// Local OpenAI-compatible server (Ollama, vLLM, ...).
+    OpenAiCompatible,
+}
+
+impl Provider {
+    /// Stable identifier persisted in `recommendation_snapshots.provider`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Provider::Anthropic => "anthropic",
+            Provider::OpenAI => "openai",
+            Provider::Gemini => "gemini",
+            Provider::OpenAiCompatible => "openai-compatible",
+        }
+    }
+
+    pub fn parse(s: &str) -> anyhow::Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "anthropic" => Ok(Provider::Anthropic),
+            "openai" => Ok(Provider::OpenAI),
+            "gemini" => Ok(Provider::Gemini),
+            "openai-compatible" | "openai_compatible" => Ok(Provider::OpenAiCompatible),
+            other => anyhow::bail!("unknown LLM provider: {other}"),
+        }
+    }
+}
+
+/// Exactly what a client would send for an input, for `--dry-run` prompt review.
+#[derive(Debug, Clone)]
+pub struct PromptPreview {
+    pub provider: Provider,
+    pub model: String,
+    pub system_prompt: String,
+    pub user_prompt: String,
+    /// Tool input schema (Anthropic) or response schema (Gemini).
+    pub tool_schema: serde_json::Value,
+    /// Full request body as serialized for the provider.
+    pub request: serde_json::Value,
+}
+
+impl PromptPreview {
+    /// Estimated input tokens of the full request body (chars/4 heuristic).
+    pub fn estimated_tokens(&self) -> usize {
+        budget::PromptBudget::estimate_tokens(&self.request.to_string())
+    }
 }
 
 #[async_trait::async_trait]
 pub trait LlmClient: Send + Sync {
     fn provider(&self) -> Provider;
 
+    /// Hash of the system prompt sent for `input`, date aside (persisted as `prompt_hash`); see
+    /// [`prompt::PromptTemplate::hash`].
+    fn prompt_hash(&self, _input: &GenerateInput) -> Option<String> {
+        None
+    }
+
+    /// Renders the initial request for `input` without sending it.
+    fn preview_prompt(&self, _input: &GenerateInput) -> anyhow::Result<PromptPreview> {
+        anyhow::bail!(
+            "prompt preview is not supported for provider {}",
+            self.provider().as_str()
+        )
+    }
+
     async fn generate_recommendations(
         &self,
         input: GenerateInput,
     ) -> anyhow::Result<RecommendationSnapshot>;
+
+    /// Like `generate_recommendations`, but also returns the raw provider response for persistence.
+    async fn generate_recommendations_with_raw(
+        &self,
+        input: GenerateInput,
+    ) -> anyhow::Result<(RecommendationSnapshot, serde_json::Value)>;
+}
+
+/// Builds the client selected by `LLM_PROVIDER` (default: `anthropic`), wrapped in an
+/// [`ensemble::EnsembleLlmClient`] when `LLM_ENSEMBLE_RUNS` > 1. Every provider call is reported
+/// to `attempt_sink` when given.
+pub fn client_from_settings(
+    settings: &crate::config::Settings,
+    attempt_sink: Option<std::sync::Arc<dyn attempts::AttemptSink>>,
+) -> anyhow::Result<Box<dyn LlmClient>> {
+    client_for_provider(settings, provider_from_env()?, attempt_sink)
+}
+
+/// Reads `LLM_PROVIDER` (default: `anthropic`).
+pub fn provider_from_env() -> anyhow::Result<Provider> {
+    match std::env::var("LLM_PROVIDER") {
+        Ok(s) if !s.trim().is_empty() => Provider::parse(&s),
+        _ => Ok(Provider::Anthropic),
+    }
+}
+
+/// Like [`client_from_settings`], with the provider chosen by the caller (e.g. a CLI flag).
+pub fn client_for_provider(
+    settings: &crate::config::Settings,
+    provider: Provider,
+    attempt_sink: Option<std::sync::Arc<dyn attempts::AttemptSink>>,
+) -> anyhow::Result<Box<dyn LlmClient>> {
+    let client: Box<dyn LlmClient> = match provider {
+        Provider::Anthropic => {
+            let client = anthropic::AnthropicClient::from_settings(settings)?;
+            match attempt_sink {
+                Some(sink) => Box::new(client.with_attempt_sink(sink)),
+                None => Box::new(client),
+            }
+        }
+        Provider::Gemini => {
+            let client = gemini::GeminiClient::from_settings(settings)?;
+            match attempt_sink {
+                Some(sink) => Box::new(client.with_attempt_sink(sink)),
+                None => Box::new(client),
+            }
+        }
+        Provider::OpenAiCompatible => {
+            let client = openai_compat::OpenAiCompatibleClient::from_env()?;
+            match attempt_sink {
+                Some(sink) => Box::new(client.with_attempt_sink(sink)),
+                None => Box::new(client),
+            }
+        }
+        Provider::OpenAI => anyhow::bail!("LLM provider openai is not implemented yet"),
+    };
+
+    let runs = ensemble::EnsembleLlmClient::runs_from_env();
+    if runs > 1 {
+        return Ok(Box::new(ensemble::EnsembleLlmClient::new(client, runs)));
+    }
+    Ok(client)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::recommendation::RecommendationItem;
+    use chrono::{NaiveDate, TimeZone, Utc};
+
+    fn input(as_of: NaiveDate) -> GenerateInput {
+        let candidates = (1..=GenerateInput::MIN_CANDIDATES)
+            .map(|i| Candidate {
+                ticker: format!("KRX:{i:06}"),
+                name: format!("Name {i}"),
+                name_en: None,
+                sector: None,
+                features: Default::default(),
+            })
+            .collect();
+        GenerateInput::try_new(as_of, candidates).unwrap()
+    }
+
+    fn snapshot(as_of: NaiveDate, tickers: &[String]) -> RecommendationSnapshot {
+        RecommendationSnapshot {
+            as_of_date: as_of,
+            generated_at: Utc.with_ymd_and_hms(2026, 1, 27, 10, 0, 0).unwrap(),
+            items: tickers
+                .iter()
+                .enumerate()
+                .map(|(i, t)| RecommendationItem {
+                    rank: i as i32 + 1,
+                    ticker: t.clone(),
+                    name: t.clone(),
+                    name_en: None,
+                    rationale: ["a".to_string(), "b".to_string(), "c".to_string()],
+                    risk_notes: None,
+                    confidence: None,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn accepts_tickers_from_universe() {
+        let as_of = NaiveDate::from_ymd_opt(2026, 1, 27).unwrap();
+        let tickers: Vec<String> = (1..=20).map(|i| format!("KRX:{i:06}")).collect();
+        input(as_of)
+            .ensure_tickers_in_universe(&snapshot(as_of, &tickers))
+            .unwrap();
+    }
+
+    #[test]
+    fn rejects_hallucinated_tickers() {
+        let as_of = NaiveDate::from_ymd_opt(2026, 1, 27).unwrap();
+        let mut tickers: Vec<String> = (1..=20).map(|i| format!("KRX:{i:06}")).collect();
+        tickers[3] = "KRX:999999".to_string();
+        tickers[7] = "KRX:888888".to_string();
+
+        let err = input(as_of)
+            .ensure_tickers_in_universe(&snapshot(as_of, &tickers))
+            .unwrap_err();
+        let unknown = err.downcast_ref::<UnknownTickersError>().unwrap();
+        assert_eq!(unknown.tickers, vec!["KRX:999999", "KRX:888888"]);
+        assert!(err.to_string().contains("KRX:999999, KRX:888888"));
+    }
+
+    #[test]
+    fn universe_digest_is_stable_and_attached_to_raw_payloads() {
+        let as_of = NaiveDate::from_ymd_opt(2026, 1, 27).unwrap();
+        let a = input(as_of);
+        let digest = a.universe_digest();
+        assert_eq!(digest.len(), 64);
+        assert_eq!(digest, input(as_of).universe_digest());
+
+        let mut b = input(as_of);
+        b.candidates[0].features.insert("ret_1d".to_string(), 0.01);
+        assert_ne!(digest, b.universe_digest());
+
+        // Display-only fields stay out of what the model sees.
+        let mut c = input(as_of);
+        c.candidates[0].name_en = Some("Name One".to_string());
+        assert_eq!(digest, c.universe_digest());
+        assert!(!c.candidates_json().to_string().contains("Name One"));
+
+        let raw = a.attach_universe(Some(serde_json::json!({"id": "msg_1"})));
+        assert_eq!(raw["id"], "msg_1");
+        assert_eq!(raw["universe"]["digest"], digest);
+        assert_eq!(raw["universe"]["size"], 200);
+
+        let raw = a.attach_universe(None);
+        assert_eq!(raw["universe"]["size"], 200);
+
+        let raw = a.attach_universe(Some(serde_json::json!("text")));
+        assert_eq!(raw["raw"], "text");
+        assert_eq!(raw["universe"]["digest"], digest);
+    }
 }
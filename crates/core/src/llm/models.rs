@@ -0,0 +1,141 @@
+use crate::llm::Provider;
+
+/// Known model identifiers with the limits we budget against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModelInfo {
+    pub provider: Provider,
+    pub id: &'static str,
+    /// Input context window, in tokens.
+    pub context_window: usize,
+    pub max_output_tokens: u32,
+}
+
+const fn model(
+    provider: Provider,
+    id: &'static str,
+    context_window: usize,
+    max_output_tokens: u32,
+) -> ModelInfo {
+    ModelInfo {
+        provider,
+        id,
+        context_window,
+        max_output_tokens,
+    }
+}
+
+use Provider::{Anthropic, Gemini};
+
+const BUILTIN_MODELS: &[ModelInfo] = &[
+    model(Anthropic, "claude-3-opus-20240229", 200_000, 4_096),
+    model(Anthropic, "claude-3-5-sonnet-20241022", 200_000, 8_192),
+    model(Anthropic, "claude-3-5-sonnet-latest", 200_000, 8_192),
+    model(Anthropic, "claude-3-5-haiku-20241022", 200_000, 8_192),
+    model(Anthropic, "claude-3-5-haiku-latest", 200_000, 8_192),
+    model(Anthropic, "claude-3-7-sonnet-20250219", 200_000, 64_000),
+    model(Anthropic, "claude-sonnet-4-20250514", 200_000, 64_000),
+    model(Anthropic, "claude-opus-4-20250514", 200_000, 32_000),
+    model(Anthropic, "claude-opus-4-1-20250805", 200_000, 32_000),
+    model(Anthropic, "claude-sonnet-4-5-20250929", 200_000, 64_000),
+    model(Anthropic, "claude-haiku-4-5-20251001", 200_000, 64_000),
+    model(Anthropic, "claude-opus-4-5-20251101", 200_000, 64_000),
+    model(Gemini, "gemini-1.5-pro", 2_097_152, 8_192),
+    model(Gemini, "gemini-2.0-flash", 1_048_576, 8_192),
+    model(Gemini, "gemini-2.5-flash", 1_048_576, 65_536),
+    model(Gemini, "gemini-2.5-pro", 1_048_576, 65_536),
+];
+
+#[derive(Debug, Clone, Copy)]
+pub struct ModelRegistry {
+    models: &'static [ModelInfo],
+}
+
+impl Default for ModelRegistry {
+    fn default() -> Self {
+        Self {
+            models: BUILTIN_MODELS,
+        }
+    }
+}
+
+impl ModelRegistry {
+    pub fn lookup(&self, provider: Provider, id: &str) -> Option<&'static ModelInfo> {
+        self.models
+            .iter()
+            .find(|m| m.provider == provider && m.id == id)
+    }
+
+    pub fn known_ids(&self, provider: Provider) -> impl Iterator<Item = &'static str> {
+        self.models
+            .iter()
+            .filter(move |m| m.provider == provider)
+            .map(|m| m.id)
+    }
+
+    /// Rejects unknown model ids unless `LLM_ALLOW_UNKNOWN_MODEL=true` (for newly released models).
+    pub fn validate(
+        &self,
+        provider: Provider,
+        id: &str,
+    ) -> anyhow::Result<Option<&'static ModelInfo>> {
+        let allow_unknown = std::env::var("LLM_ALLOW_UNKNOWN_MODEL")
+            .map(|s| matches!(s.trim().to_ascii_lowercase().as_str(), "1" | "true"))
+            .unwrap_or(false);
+        self.validate_with(provider, id, allow_unknown)
+    }
+
+    pub fn validate_with(
+        &self,
+        provider: Provider,
+        id: &str,
+        allow_unknown: bool,
+    ) -> anyhow::Result<Option<&'static ModelInfo>> {
+        if let Some(info) = self.lookup(provider, id) {
+            return Ok(Some(info));
+        }
+        if allow_unknown {
+            tracing::warn!(
+                provider = provider.as_str(),
+                model = id,
+                "model not in registry; continuing because LLM_ALLOW_UNKNOWN_MODEL is set"
+            );
+            return Ok(None);
+        }
+        anyhow::bail!(
+            "unknown {} model {id:?} (known: {}); set LLM_ALLOW_UNKNOWN_MODEL=true to bypass",
+            provider.as_str(),
+            self.known_ids(provider).collect::<Vec<_>>().join(", ")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_known_models_and_rejects_typos() {
+        let registry = ModelRegistry::default();
+        let info = registry
+            .validate_with(Provider::Anthropic, "claude-3-5-sonnet-20241022", false)
+            .unwrap()
+            .unwrap();
+        assert_eq!(info.context_window, 200_000);
+
+        let err = registry
+            .validate_with(Provider::Anthropic, "claude-3-5-sonet-20241022", false)
+            .unwrap_err();
+        assert!(err.to_string().contains("claude-3-5-sonnet-20241022"));
+        assert!(err.to_string().contains("LLM_ALLOW_UNKNOWN_MODEL"));
+
+        // Provider-scoped: a Gemini id is not a valid Anthropic model.
+        assert!(registry
+            .validate_with(Provider::Anthropic, "gemini-2.5-pro", false)
+            .is_err());
+
+        assert!(registry
+            .validate_with(Provider::Anthropic, "claude-next", true)
+            .unwrap()
+            .is_none());
+    }
+}
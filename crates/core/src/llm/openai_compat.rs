@@ -0,0 +1,492 @@
+// Chat Completions client for local OpenAI-compatible servers (Ollama, vLLM, llama.cpp) so the
+// pipeline runs without provider API keys. No tools / response_format: local servers support
+// them unevenly, so the contract is enforced by text parsing plus the repair loop.
+
+use crate::domain::recommendation::RecommendationSnapshot;
+use crate::llm::attempts::{AttemptSink, LlmAttempt};
+use crate::llm::budget::PromptBudget;
+use crate::llm::deadline::{self, PartialRaw};
+use crate::llm::error::{self, LlmDiagnosticsError};
+use crate::llm::prompt;
+use crate::llm::{GenerateInput, LlmClient, PromptPreview, Provider};
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+
+const DEFAULT_MODEL: &str = "llama3.1";
+const DEFAULT_MAX_TOKENS: u32 = 4096;
+// Local models on a laptop are slow; a full snapshot can take minutes.
+const DEFAULT_TIMEOUT_SECS: u64 = 300;
+const MAX_REPAIRS: u32 = 2;
+
+#[derive(Debug, Clone)]
+pub struct OpenAiCompatibleClient {
+    http: reqwest::Client,
+    base_url: String,
+    api_key: Option<String>,
+    model: String,
+    max_tokens: u32,
+    prompt_budget: PromptBudget,
+    system_prompt: prompt::PromptTemplate,
+    total_deadline: Duration,
+    attempt_sink: Option<Arc<dyn AttemptSink>>,
+}
+
+impl OpenAiCompatibleClient {
+    /// Reads `LLM_BASE_URL` (required, e.g. `http://localhost:11434/v1`), `LLM_API_KEY`
+    /// (optional), `LLM_MODEL`, `LLM_MAX_TOKENS`, `LLM_TIMEOUT_SECS`.
+    pub fn from_env() -> anyhow::Result<Self> {
+        let base_url = match std::env::var("LLM_BASE_URL") {
+            Ok(s) if !s.trim().is_empty() => s.trim().to_string(),
+            _ => anyhow::bail!("LLM_BASE_URL is required for LLM_PROVIDER=openai-compatible"),
+        };
+        let api_key = std::env::var("LLM_API_KEY")
+            .ok()
+            .filter(|s| !s.trim().is_empty());
+        let model = std::env::var("LLM_MODEL")
+            .ok()
+            .filter(|s| !s.trim().is_empty())
+            .unwrap_or_else(|| DEFAULT_MODEL.to_string());
+        let max_tokens = std::env::var("LLM_MAX_TOKENS")
+            .ok()
+            .and_then(|s| s.parse::<u32>().ok())
+            .unwrap_or(DEFAULT_MAX_TOKENS);
+        let timeout_secs = std::env::var("LLM_TIMEOUT_SECS")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(DEFAULT_TIMEOUT_SECS);
+
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(timeout_secs))
+            .build()
+            .context("failed to build reqwest client")?;
+
+        Ok(Self {
+            http,
+            base_url,
+            api_key,
+            model,
+            max_tokens,
+            prompt_budget: PromptBudget::from_env(),
+            system_prompt: prompt::PromptTemplate::from_env()?,
+            total_deadline: deadline::total_deadline_from_env(),
+            attempt_sink: None,
+        })
+    }
+
+    /// Records every provider call (including rejected outputs) to `sink`.
+    pub fn with_attempt_sink(mut self, sink: Arc<dyn AttemptSink>) -> Self {
+        self.attempt_sink = Some(sink);
+        self
+    }
+
+    fn build_request(&self, input: &GenerateInput, user_content: String) -> ChatRequest {
+        ChatRequest {
+            model: self.model.clone(),
+            messages: vec![
+                ChatMessage {
+                    role: "system",
+                    content: self.system_prompt.render(input),
+                },
+                ChatMessage {
+                    role: "user",
+                    content: user_content,
+                },
+            ],
+            max_tokens: self.max_tokens,
+            stream: false,
+        }
+    }
+
+    async fn chat(&self, req: &ChatRequest) -> anyhow::Result<(serde_json::Value, ChatResponse)> {
+        let url = format!("{}/chat/completions", self.base_url.trim_end_matches('/'));
+        let mut builder = self.http.post(url).json(req);
+        if let Some(key) = &self.api_key {
+            builder = builder.bearer_auth(key);
+        }
+        let res = builder
+            .send()
+            .await
+            .context("OpenAI-compatible request failed")?;
+
+        let status = res.status();
+        let text = res
+            .text()
+            .await
+            .context("failed to read OpenAI-compatible response body")?;
+        if !status.is_success() {
+            let raw_response_json = serde_json::from_str::<serde_json::Value>(&text).ok();
+            return Err(LlmDiagnosticsError {
+                provider: Provider::OpenAiCompatible,
+                stage: "http",
+                detail: format!("status={status}"),
+                raw_output: Some(text),
+                raw_response_json,
+                http_status: Some(status.as_u16()),
+            }
+            .into());
+        }
+
+        let raw_json = serde_json::from_str::<serde_json::Value>(&text)
+            .with_context(|| format!("failed to parse OpenAI-compatible response JSON: {text}"))?;
+        let parsed = serde_json::from_value::<ChatResponse>(raw_json.clone())
+            .context("failed to decode OpenAI-compatible response into ChatResponse")?;
+        Ok((raw_json, parsed))
+    }
+
+    fn response_text(raw_json: &serde_json::Value, res: &ChatResponse) -> anyhow::Result<String> {
+        let diag = |stage: &'static str, detail: String, raw_output: Option<String>| {
+            anyhow::Error::from(LlmDiagnosticsError {
+                provider: Provider::OpenAiCompatible,
+                stage,
+                detail,
+                raw_output,
+                raw_response_json: Some(raw_json.clone()),
+                http_status: None,
+            })
+        };
+
+        let Some(choice) = res.choices.first() else {
+            return Err(diag("empty_response", "no choices".to_string(), None));
+        };
+        let text = choice.message.content.clone().unwrap_or_default();
+        if choice.finish_reason.as_deref() == Some("length") {
+            return Err(diag(
+                "truncated",
+                "finish_reason=length".to_string(),
+                Some(text),
+            ));
+        }
+        if text.trim().is_empty() {
+            return Err(diag("empty_response", "empty content".to_string(), None));
+        }
+        Ok(text)
+    }
+
+    /// Extracts and parses one response, recording the attempt either way.
+    async fn parse_attempt(
+        &self,
+        input: &GenerateInput,
+        attempt_no: u32,
+        stage: &'static str,
+        raw_json: &serde_json::Value,
+        res: &ChatResponse,
+    ) -> anyhow::Result<(String, anyhow::Result<RecommendationSnapshot>)> {
+        let outcome = Self::response_text(raw_json, res).map(|text| {
+            let parsed = prompt::parse_snapshot(&text, input);
+            (text, parsed)
+        });
+
+        if let Some(sink) = &self.attempt_sink {
+            let error = match &outcome {
+                Err(err) | Ok((_, Err(err))) => Some(format!("{err:#}")),
+                Ok((_, Ok(_))) => None,
+            };
+            sink.record(LlmAttempt {
+                as_of_date: input.as_of_date,
+                provider: Provider::OpenAiCompatible,
+                model: self.model.clone(),
+                attempt_no,
+                stage,
+                raw_response: Some(raw_json.clone()),
+                error,
+            })
+            .await;
+        }
+
+        outcome
+    }
+
+    /// Bounded by `LLM_TOTAL_DEADLINE_SECS` across the initial call and the repair passes.
+    pub async fn generate_recommendations_with_raw(
+        &self,
+        input: GenerateInput,
+    ) -> anyhow::Result<(RecommendationSnapshot, serde_json::Value)> {
+        let partial = PartialRaw::default();
+        deadline::run_with_deadline(
+            Provider::OpenAiCompatible,
+            self.total_deadline,
+            &partial,
+            self.generate_with_partial(input, &partial),
+        )
+        .await
+    }
+
+    async fn generate_with_partial(
+        &self,
+        input: GenerateInput,
+        partial: &PartialRaw,
+    ) -> anyhow::Result<(RecommendationSnapshot, serde_json::Value)> {
+        let req = self.build_request(&input, prompt::user_prompt(&input, &self.prompt_budget));
+        let (mut raw_json, res) = self.chat(&req).await?;
+        partial.set(&raw_json);
+        let (mut text, parsed) = self
+            .parse_attempt(&input, 1, "initial", &raw_json, &res)
+            .await?;
+        let mut err = match parsed {
+            Ok(snapshot) => return Ok((snapshot, raw_json)),
+            Err(err) => err,
+        };
+
+        for repair in 1..=MAX_REPAIRS {
+            tracing::warn!(
+                %input.as_of_date,
+                repair,
+                error = %err,
+                "OpenAI-compatible output invalid; attempting repair"
+            );
+            let repair_req = self.build_request(&input, prompt::repair_prompt(&text, &input, &err));
+            let (repair_raw_json, repair_res) = self.chat(&repair_req).await?;
+            partial.set(&repair_raw_json);
+            let (repair_text, parsed) = self
+                .parse_attempt(&input, repair + 1, "repair", &repair_raw_json, &repair_res)
+                .await?;
+            match parsed {
+                Ok(snapshot) => return Ok((snapshot, repair_raw_json)),
+                Err(e) => {
+                    err = e;
+                    text = repair_text;
+                    raw_json = repair_raw_json;
+                }
+            }
+        }
+
+        Err(LlmDiagnosticsError {
+            provider: Provider::OpenAiCompatible,
+            stage: error::after_repair_stage(&err),
+            detail: format!("final_error={err}"),
+            raw_output: Some(text),
+            raw_response_json: Some(raw_json),
+            http_status: None,
+        }
+        .into())
+    }
+}
+
+#[async_trait::async_trait]
+impl LlmClient for OpenAiCompatibleClient {
+    fn provider(&self) -> Provider {
+        Provider::OpenAiCompatible
+    }
+
+    fn prompt_hash(&self, input: &GenerateInput) -> Option<String> {
+        Some(self.system_prompt.hash(input))
+    }
+
+    fn preview_prompt(&self, input: &GenerateInput) -> anyhow::Result<PromptPreview> {
+        let user_prompt = prompt::user_prompt(input, &self.prompt_budget);
+        let req = self.build_request(input, user_prompt.clone());
+        Ok(PromptPreview {
+            provider: Provider::OpenAiCompatible,
+            model: self.model.clone(),
+            system_prompt: self.system_prompt.render(input),
+            user_prompt,
+            tool_schema: serde_json::Value::Null,
+            request: serde_json::to_value(req)?,
+        })
+    }
+
+    async fn generate_recommendations(
+        &self,
+        input: GenerateInput,
+    ) -> anyhow::Result<RecommendationSnapshot> {
+        let (snapshot, _raw) = self.generate_recommendations_with_raw(input).await?;
+        Ok(snapshot)
+    }
+
+    async fn generate_recommendations_with_raw(
+        &self,
+        input: GenerateInput,
+    ) -> anyhow::Result<(RecommendationSnapshot, serde_json::Value)> {
+        OpenAiCompatibleClient::generate_recommendations_with_raw(self, input).await
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ChatRequest {
+    model: String,
+    messages: Vec<ChatMessage>,
+    max_tokens: u32,
+    stream: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ChatMessage {
+    role: &'static str,
+    content: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ChatResponse {
+    #[serde(default)]
+    choices: Vec<ChatChoice>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ChatChoice {
+    message: ChatResponseMessage,
+    #[serde(default)]
+    finish_reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ChatResponseMessage {
+    #[serde(default)]
+    content: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::recommendation::Candidate;
+    use chrono::{NaiveDate, TimeZone, Utc};
+    use serde_json::json;
+
+    fn test_input(as_of: NaiveDate) -> GenerateInput {
+        let candidates = (1..=GenerateInput::MIN_CANDIDATES)
+            .map(|i| Candidate {
+                ticker: format!("KRX:{i:06}"),
+                name: format!("Name {i}"),
+                name_en: None,
+                sector: None,
+                features: Default::default(),
+            })
+            .collect();
+        GenerateInput::try_new(as_of, candidates).unwrap()
+    }
+
+    fn snapshot_json(as_of: NaiveDate, items: usize) -> serde_json::Value {
+        let generated_at = Utc.with_ymd_and_hms(2026, 1, 28, 9, 0, 0).unwrap();
+        let items: Vec<_> = (1..=items)
+            .map(|rank| {
+                json!({
+                    "rank": rank,
+                    "ticker": format!("KRX:{rank:06}"),
+                    "name": format!("Name {rank}"),
+                    "rationale": ["a", "b", "c"],
+                    "risk_notes": null,
+                    "confidence": 0.5,
+                })
+            })
+            .collect();
+        json!({"as_of_date": as_of, "generated_at": generated_at, "items": items})
+    }
+
+    /// Chat completion whose content is a fenced ```json block, as local models tend to emit.
+    fn fenced_completion(snapshot: &serde_json::Value) -> serde_json::Value {
+        json!({
+            "id": "chatcmpl-1",
+            "object": "chat.completion",
+            "model": DEFAULT_MODEL,
+            "choices": [{
+                "index": 0,
+                "message": {
+                    "role": "assistant",
+                    "content": format!("Here are the picks:\n```json\n{snapshot:#}\n```"),
+                },
+                "finish_reason": "stop",
+            }],
+        })
+    }
+
+    /// Stubs `/v1/chat/completions` with canned responses (in order) and records requests.
+    async fn serve_chat(
+        responses: Vec<serde_json::Value>,
+    ) -> (
+        String,
+        Arc<std::sync::Mutex<Vec<(Option<String>, serde_json::Value)>>>,
+    ) {
+        let requests = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorded = requests.clone();
+        let responses = Arc::new(responses);
+        let app = axum::Router::new().route(
+            "/v1/chat/completions",
+            axum::routing::post(
+                move |headers: axum::http::HeaderMap,
+                      axum::Json(body): axum::Json<serde_json::Value>| {
+                    let requests = recorded.clone();
+                    let responses = responses.clone();
+                    async move {
+                        let auth = headers
+                            .get("authorization")
+                            .and_then(|v| v.to_str().ok())
+                            .map(str::to_string);
+                        let mut requests = requests.lock().unwrap();
+                        let idx = requests.len().min(responses.len() - 1);
+                        requests.push((auth, body));
+                        axum::Json(responses[idx].clone())
+                    }
+                },
+            ),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        (format!("http://{addr}/v1"), requests)
+    }
+
+    fn test_client(base_url: String) -> OpenAiCompatibleClient {
+        OpenAiCompatibleClient {
+            http: reqwest::Client::new(),
+            base_url,
+            api_key: None,
+            model: DEFAULT_MODEL.to_string(),
+            max_tokens: DEFAULT_MAX_TOKENS,
+            prompt_budget: PromptBudget::default(),
+            system_prompt: prompt::PromptTemplate::default(),
+            total_deadline: Duration::from_secs(300),
+            attempt_sink: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn parses_fenced_json_and_repairs_via_text_path() {
+        let as_of = NaiveDate::from_ymd_opt(2026, 1, 28).unwrap();
+        let (base_url, requests) = serve_chat(vec![
+            fenced_completion(&snapshot_json(as_of, 19)),
+            fenced_completion(&snapshot_json(as_of, 20)),
+        ])
+        .await;
+
+        let (snapshot, raw) = test_client(base_url)
+            .generate_recommendations_with_raw(test_input(as_of))
+            .await
+            .unwrap();
+        assert_eq!(snapshot.items.len(), 20);
+        assert_eq!(raw["object"], "chat.completion");
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        let (auth, first) = &requests[0];
+        assert!(auth.is_none());
+        assert_eq!(first["messages"][0]["role"], "system");
+        assert!(first.get("response_format").is_none());
+        assert!(first.get("tools").is_none());
+        let repair = requests[1].1["messages"][1]["content"].as_str().unwrap();
+        assert!(repair.contains("exactly 20 items"));
+    }
+
+    #[tokio::test]
+    async fn truncated_completion_maps_to_diagnostics() {
+        let as_of = NaiveDate::from_ymd_opt(2026, 1, 28).unwrap();
+        let mut truncated = fenced_completion(&snapshot_json(as_of, 20));
+        truncated["choices"][0]["finish_reason"] = json!("length");
+        let (base_url, requests) = serve_chat(vec![truncated]).await;
+
+        let mut client = test_client(base_url);
+        client.api_key = Some("local-key".to_string());
+        let err = client
+            .generate_recommendations_with_raw(test_input(as_of))
+            .await
+            .unwrap_err();
+        let diag = err.downcast_ref::<LlmDiagnosticsError>().unwrap();
+        assert_eq!(diag.stage, "truncated");
+        assert_eq!(diag.provider, Provider::OpenAiCompatible);
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests[0].0.as_deref(), Some("Bearer local-key"));
+    }
+}
@@ -0,0 +1,533 @@
+// Provider-agnostic prompts shared by every LLM client (schema rules and repair live here).
+
+use crate::domain::contract::{
+    NonKoreanRationaleError, RationaleConstraintError, UnknownTickersError,
+};
+use crate::domain::recommendation::RecommendationSnapshot;
+use crate::llm::budget::PromptBudget;
+use crate::llm::json;
+use crate::llm::GenerateInput;
+use anyhow::Context;
+
+/// Number of items every snapshot must contain (mirrors the contract validation).
+pub const ITEM_COUNT: usize = 20;
+
+/// Every system prompt template must contain this line; the JSON contract follows it.
+pub const SCHEMA_MARKER: &str = "Output schema:";
+
+const MAX_REPAIR_EXAMPLES: usize = 10;
+
+/// Precedes the candidates JSON in the user prompt.
+pub const CANDIDATES_MARKER: &str = "Candidates JSON:";
+
+const DEFAULT_SYSTEM_PROMPT: &str = "\
+You are a stock recommendation engine for KRX.
+Return ONLY valid JSON. Do not wrap in markdown. Do not include any extra keys.
+No trailing commas. No comments. No semicolons. Use double quotes for all JSON strings.
+Output schema:
+{
+  \"as_of_date\": \"YYYY-MM-DD\",
+  \"generated_at\": \"ISO-8601\",
+  \"items\": [
+    {
+      \"rank\": 1,
+      \"ticker\": \"KRX:005930\",
+      \"name\": \"삼성전자\",
+      \"rationale\": [\"line1\", \"line2\", \"line3\"],
+      \"risk_notes\": \"optional\",
+      \"confidence\": 0.0
+    }
+  ]
+}
+Rules:
+- as_of_date must be {{as_of_date}}
+- items must have exactly {{item_count}} entries, ranks 1..{{item_count}} unique
+- rationale must have exactly 3 short lines per item
+- risk_notes key MUST be present (use null if none)
+- confidence key MUST be present (use null if unknown)
+- confidence (if present) must be in [0, 1]
+- Use only the provided candidates (ticker/name)";
+
+/// System prompt template with `{{as_of_date}}` / `{{item_count}}` placeholders.
+///
+/// Loaded from `LLM_SYSTEM_PROMPT_PATH` when set, otherwise the built-in default. See
+/// [`PromptTemplate::hash`] for what is persisted with each snapshot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PromptTemplate {
+    source: String,
+}
+
+impl Default for PromptTemplate {
+    fn default() -> Self {
+        Self::parse(DEFAULT_SYSTEM_PROMPT.to_string()).expect("built-in template is valid")
+    }
+}
+
+impl PromptTemplate {
+    pub fn from_env() -> anyhow::Result<Self> {
+        match std::env::var("LLM_SYSTEM_PROMPT_PATH") {
+            Ok(path) if !path.trim().is_empty() => Self::load(path.trim()),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    pub fn load(path: impl AsRef<std::path::Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read system prompt template {}", path.display()))?;
+        Self::parse(source)
+            .with_context(|| format!("invalid system prompt template {}", path.display()))
+    }
+
+    pub fn parse(source: String) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            source.lines().any(|l| l.trim() == SCHEMA_MARKER),
+            "template is missing the mandatory `{SCHEMA_MARKER}` section"
+        );
+        Ok(Self { source })
+    }
+
+    /// SHA-256 of the template plus the rules [`PromptTemplate::render`] appends for `input`:
+    /// everything in the system prompt except the date, so two snapshots share a hash exactly
+    /// when the model was given the same instructions.
+    pub fn hash(&self, input: &GenerateInput) -> String {
+        let mut hashed = self.source.clone();
+        hashed.push_str(&Self::rules(input));
+        super::sha256_hex(hashed.as_bytes())
+    }
+
+    pub fn render(&self, input: &GenerateInput) -> String {
+        let mut out = self
+            .source
+            .replace("{{as_of_date}}", &input.as_of_date.to_string())
+            .replace("{{item_count}}", &ITEM_COUNT.to_string());
+        out.push_str(&Self::rules(input));
+        out
+    }
+
+    /// Rule lines appended to the template, from `input`'s validation options and contents.
+    fn rules(input: &GenerateInput) -> String {
+        let mut out = String::new();
+        let v = &input.validation;
+        if v.require_korean_rationale {
+            out.push_str("\n- rationale lines MUST be written in Korean (한국어)");
+        }
+        match (v.rationale_min_chars, v.rationale_max_chars) {
+            (Some(min), Some(max)) => out.push_str(&format!(
+                "\n- each rationale line must be {min}..{max} characters"
+            )),
+            (Some(min), None) => out.push_str(&format!(
+                "\n- each rationale line must be at least {min} characters"
+            )),
+            (None, Some(max)) => out.push_str(&format!(
+                "\n- each rationale line must be at most {max} characters"
+            )),
+            (None, None) => {}
+        }
+        if v.rationale_max_duplicates.is_some() {
+            out.push_str(
+                "\n- rationale lines must be specific to each stock; do not reuse the same line across items",
+            );
+        }
+        if !input.market_context.is_empty() {
+            out.push_str(
+                "\n- MARKET CONTEXT gives the day's index returns; keep rationale consistent with \
+the overall market direction",
+            );
+        }
+        if input.candidates.iter().any(|c| c.sector.is_some()) {
+            out.push_str(
+                "\n- a candidate's sector (when given) is its industry; rationale may cite it but \
+must not invent one for candidates without it",
+            );
+        }
+        if input
+            .candidates
+            .iter()
+            .any(|c| c.features.contains_key("days_to_earnings"))
+        {
+            out.push_str(
+                "\n- days_to_earnings is calendar days to the nearest earnings announcement \
+(negative = days since); mention the event risk in risk_notes when it is within 7 days either way",
+            );
+        }
+        if input.previous_snapshot.is_some() {
+            out.push_str(
+                "\n- PREVIOUS PICKS are informational only (continuity context); \
+they must NOT override the provided candidate features",
+            );
+        }
+        out
+    }
+}
+
+pub fn user_prompt(input: &GenerateInput, budget: &PromptBudget) -> String {
+    format!(
+        "Task: Select the top 20 short-term (<= 1 week) recommendations for as_of_date={}.\n\n{}{}{CANDIDATES_MARKER}\n{}",
+        input.as_of_date,
+        market_context_section(input, budget),
+        previous_picks_section(input),
+        input.candidates_json_budgeted(budget)
+    )
+}
+
+/// Index returns for the date, one `key: value` line each (empty when there are none).
+fn market_context_section(input: &GenerateInput, budget: &PromptBudget) -> String {
+    if input.market_context.is_empty() {
+        return String::new();
+    }
+    let precision = budget.float_precision as usize;
+    let lines: Vec<String> = input
+        .market_context
+        .iter()
+        .map(|(k, v)| format!("{k}: {v:.precision$}"))
+        .collect();
+    format!(
+        "=== MARKET CONTEXT ===\n{}\n=== END MARKET CONTEXT ===\n\n",
+        lines.join("\n")
+    )
+}
+
+/// Compact rank/ticker/name listing of the previous snapshot (empty when there is none).
+fn previous_picks_section(input: &GenerateInput) -> String {
+    let Some(prev) = &input.previous_snapshot else {
+        return String::new();
+    };
+    let lines: Vec<String> = prev
+        .items
+        .iter()
+        .map(|i| format!("{}. {} {}", i.rank, i.ticker, i.name))
+        .collect();
+    format!(
+        "=== PREVIOUS PICKS (as_of_date={}) ===\n{}\n=== END PREVIOUS PICKS ===\n\n",
+        prev.as_of_date,
+        lines.join("\n")
+    )
+}
+
+pub fn repair_prompt(
+    previous_output: &str,
+    input: &GenerateInput,
+    last_err: &anyhow::Error,
+) -> String {
+    let expected_as_of_date = input.as_of_date;
+    let schema = [
+        "{",
+        "  \"as_of_date\": \"YYYY-MM-DD\",",
+        "  \"generated_at\": \"ISO-8601\",",
+        "  \"items\": [",
+        "    {",
+        "      \"rank\": 1,",
+        "      \"ticker\": \"KRX:005930\",",
+        "      \"name\": \"삼성전자\",",
+        "      \"rationale\": [\"line1\", \"line2\", \"line3\"],",
+        "      \"risk_notes\": null,",
+        "      \"confidence\": null",
+        "    }",
+        "  ]",
+        "}",
+    ]
+    .join("\n");
+
+    // Hallucinated tickers: name them and list the allowed set so the retry can fix them.
+    let ticker_fix = match last_err.downcast_ref::<UnknownTickersError>() {
+        Some(unknown) => {
+            let allowed: Vec<&str> = input.candidates.iter().map(|c| c.ticker.as_str()).collect();
+            format!(
+                "INVALID TICKERS (not in the provided candidates; replace them): {}\n\
+ALLOWED TICKERS: {}\n\n",
+                unknown.tickers.join(", "),
+                allowed.join(", ")
+            )
+        }
+        None => String::new(),
+    };
+
+    let language_fix = match last_err.downcast_ref::<NonKoreanRationaleError>() {
+        Some(lang) => format!(
+            "NON-KOREAN RATIONALE (rewrite these items): {}\n\
+- rationale MUST be written in Korean (한국어).\n\n",
+            lang.tickers.join(", ")
+        ),
+        None => String::new(),
+    };
+
+    let rationale_fix = match last_err.downcast_ref::<RationaleConstraintError>() {
+        Some(rc) => {
+            // Concrete offending lines, capped so the prompt stays small.
+            let examples: Vec<String> = rc
+                .violations
+                .iter()
+                .take(MAX_REPAIR_EXAMPLES)
+                .map(|v| format!("- {v}"))
+                .collect();
+            let more = rc.violations.len().saturating_sub(MAX_REPAIR_EXAMPLES);
+            format!(
+                "RATIONALE PROBLEMS (rewrite these lines; keep them specific to each stock):\n{}{}\n\n",
+                examples.join("\n"),
+                if more > 0 {
+                    format!("\n- ... and {more} more")
+                } else {
+                    String::new()
+                }
+            )
+        }
+        None => String::new(),
+    };
+
+    format!(
+        "Your previous output was NOT valid for the required schema.\n\
+VALIDATION ERROR: {last_err}\n\n\
+TASK: Output ONLY a single JSON object that exactly matches the schema and rules.\n\
+- Do NOT include any markdown, prose, or code fences.\n\
+- Do NOT include trailing commas, comments, or semicolons.\n\
+- Use double quotes for all JSON strings.\n\
+- The JSON MUST have as_of_date=\"{expected_as_of_date}\".\n\
+- The JSON MUST have exactly 20 items with ranks 1..20.\n\
+- Each item MUST include keys: rank, ticker, name, rationale, risk_notes, confidence.\n\
+- rationale MUST have exactly 3 strings.\n\
+- Each ticker MUST be unique and MUST be one of the provided candidates.\n\n\
+{ticker_fix}\
+{language_fix}\
+{rationale_fix}\
+SCHEMA:\n{schema}\n\n\
+INVALID OUTPUT (for reference only; DO NOT copy verbatim):\n{previous_output}"
+    )
+}
+
+/// Parses LLM text output and enforces the snapshot contract plus the candidate universe.
+pub fn parse_snapshot(text: &str, input: &GenerateInput) -> anyhow::Result<RecommendationSnapshot> {
+    let snapshot = json::parse_snapshot_with(text, input.as_of_date, input.validation)?;
+    input.ensure_tickers_in_universe(&snapshot)?;
+    Ok(snapshot)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::contract::ValidationOptions;
+    use crate::domain::recommendation::Candidate;
+    use chrono::NaiveDate;
+
+    fn test_input(as_of: NaiveDate) -> GenerateInput {
+        let candidates = (1..=GenerateInput::MIN_CANDIDATES)
+            .map(|i| Candidate {
+                ticker: format!("KRX:{i:06}"),
+                name: format!("Name {i}"),
+                name_en: None,
+                sector: None,
+                features: Default::default(),
+            })
+            .collect();
+        GenerateInput::try_new(as_of, candidates).unwrap()
+    }
+
+    #[test]
+    fn repair_prompt_names_invalid_tickers() {
+        let as_of = NaiveDate::from_ymd_opt(2026, 1, 28).unwrap();
+        let input = test_input(as_of);
+        let err: anyhow::Error = UnknownTickersError {
+            tickers: vec!["KRX:999999".to_string()],
+        }
+        .into();
+
+        let prompt = repair_prompt("{}", &input, &err);
+        assert!(prompt.contains(
+            "INVALID TICKERS (not in the provided candidates; replace them): KRX:999999"
+        ));
+        assert!(prompt.contains("KRX:000001, KRX:000002"));
+
+        let other = anyhow::anyhow!("duplicate rank: 3");
+        let prompt = repair_prompt("{}", &input, &other);
+        assert!(!prompt.contains("INVALID TICKERS"));
+    }
+
+    #[test]
+    fn template_renders_placeholders_and_requires_schema_marker() {
+        let as_of = NaiveDate::from_ymd_opt(2026, 1, 28).unwrap();
+        let t = PromptTemplate::parse(format!(
+            "Pick {{{{item_count}}}} for {{{{as_of_date}}}}.\n{SCHEMA_MARKER}\n{{}}"
+        ))
+        .unwrap();
+        assert_eq!(
+            t.render(&test_input(as_of)),
+            "Pick 20 for 2026-01-28.\nOutput schema:\n{}"
+        );
+        let input = test_input(as_of);
+        assert_eq!(t.hash(&input).len(), 64);
+        assert_ne!(t.hash(&input), PromptTemplate::default().hash(&input));
+
+        let err = PromptTemplate::parse("Pick 20. Return JSON.".to_string()).unwrap_err();
+        assert!(err.to_string().contains(SCHEMA_MARKER));
+
+        let rendered = PromptTemplate::default().render(&test_input(as_of));
+        assert!(rendered.contains("exactly 20 entries, ranks 1..20"));
+        assert!(!rendered.contains("{{"));
+        assert!(!rendered.contains("Korean"));
+
+        let korean = test_input(as_of).with_validation(ValidationOptions {
+            require_korean_rationale: true,
+            ..Default::default()
+        });
+        let rendered = PromptTemplate::default().render(&korean);
+        assert!(rendered.ends_with("- rationale lines MUST be written in Korean (한국어)"));
+
+        // Appended rules change the hash; the date does not.
+        let t = PromptTemplate::default();
+        let plain = test_input(as_of);
+        assert_ne!(t.hash(&korean), t.hash(&plain));
+        let next_day = test_input(as_of.succ_opt().unwrap());
+        assert_eq!(t.hash(&next_day), t.hash(&plain));
+    }
+
+    #[test]
+    fn user_prompt_includes_previous_picks_only_when_present() {
+        use crate::domain::recommendation::RecommendationItem;
+        use chrono::{TimeZone, Utc};
+
+        let as_of = NaiveDate::from_ymd_opt(2026, 1, 28).unwrap();
+        let budget = PromptBudget::default();
+        let none = test_input(as_of);
+        assert!(!user_prompt(&none, &budget).contains("PREVIOUS PICKS"));
+        assert!(!PromptTemplate::default()
+            .render(&none)
+            .contains("PREVIOUS PICKS"));
+
+        let prev = RecommendationSnapshot {
+            as_of_date: NaiveDate::from_ymd_opt(2026, 1, 27).unwrap(),
+            generated_at: Utc.with_ymd_and_hms(2026, 1, 27, 10, 0, 0).unwrap(),
+            items: (1..=2)
+                .map(|i| RecommendationItem {
+                    rank: i,
+                    ticker: format!("KRX:{i:06}"),
+                    name: format!("Name {i}"),
+                    name_en: None,
+                    rationale: ["secret a".into(), "b".into(), "c".into()],
+                    risk_notes: None,
+                    confidence: Some(0.5),
+                })
+                .collect(),
+        };
+        let with = test_input(as_of).with_previous_snapshot(Some(prev));
+        let prompt = user_prompt(&with, &budget);
+        assert!(prompt.contains(
+            "=== PREVIOUS PICKS (as_of_date=2026-01-27) ===\n1. KRX:000001 Name 1\n2. KRX:000002 Name 2\n=== END PREVIOUS PICKS ==="
+        ));
+        assert!(!prompt.contains("secret a"));
+        assert!(prompt.find("PREVIOUS PICKS") < prompt.find("Candidates JSON:"));
+        assert!(PromptTemplate::default()
+            .render(&with)
+            .contains("must NOT override the provided candidate features"));
+    }
+
+    #[test]
+    fn user_prompt_includes_market_context_only_when_present() {
+        let as_of = NaiveDate::from_ymd_opt(2026, 1, 28).unwrap();
+        let budget = PromptBudget::default();
+        let none = test_input(as_of);
+        assert!(!user_prompt(&none, &budget).contains("MARKET CONTEXT"));
+        assert!(!PromptTemplate::default()
+            .render(&none)
+            .contains("MARKET CONTEXT"));
+
+        let with = test_input(as_of).with_market_context(
+            [("kosdaq_ret_1d", 0.004), ("kospi_ret_1d", -0.031234)]
+                .map(|(k, v)| (k.to_string(), v))
+                .into(),
+        );
+        let prompt = user_prompt(&with, &budget);
+        assert!(prompt.contains(
+            "=== MARKET CONTEXT ===\nkosdaq_ret_1d: 0.0040\nkospi_ret_1d: -0.0312\n=== END MARKET CONTEXT ==="
+        ));
+        assert!(prompt.find("MARKET CONTEXT") < prompt.find("Candidates JSON:"));
+        assert!(PromptTemplate::default()
+            .render(&with)
+            .contains("keep rationale consistent with the overall market direction"));
+    }
+
+    #[test]
+    fn sector_reaches_the_prompt_outside_the_features() {
+        let as_of = NaiveDate::from_ymd_opt(2026, 1, 28).unwrap();
+        let none = test_input(as_of);
+        assert!(!user_prompt(&none, &PromptBudget::default()).contains("\"sector\""));
+        assert!(!PromptTemplate::default().render(&none).contains("sector"));
+
+        let mut with = test_input(as_of);
+        with.candidates[0].sector = Some("반도체".to_string());
+        with.candidates[0].features = [("ret_1d".to_string(), 0.01)].into();
+        // A budget that prunes every feature but the first still keeps the sector.
+        let budget = PromptBudget {
+            max_tokens: 1,
+            ..Default::default()
+        };
+        let prompt = user_prompt(&with, &budget);
+        assert!(prompt.contains("\"name\":\"Name 1\",\"sector\":\"반도체\""));
+        assert!(PromptTemplate::default()
+            .render(&with)
+            .contains("must not invent one for candidates without it"));
+    }
+
+    #[test]
+    fn earnings_rule_only_with_an_earnings_feature() {
+        let as_of = NaiveDate::from_ymd_opt(2026, 1, 28).unwrap();
+        let rule = "mention the event risk in risk_notes";
+        assert!(!PromptTemplate::default()
+            .render(&test_input(as_of))
+            .contains(rule));
+        let mut with = test_input(as_of);
+        with.candidates[3]
+            .features
+            .insert("days_to_earnings".to_string(), 2.0);
+        assert!(PromptTemplate::default().render(&with).contains(rule));
+    }
+
+    #[test]
+    fn repair_prompt_lists_offending_rationale_lines() {
+        use crate::domain::contract::RationaleViolation;
+
+        let as_of = NaiveDate::from_ymd_opt(2026, 1, 28).unwrap();
+        let mut violations = vec![RationaleViolation::Duplicated {
+            line: "Strong momentum.".to_string(),
+            tickers: vec!["KRX:000001".to_string(), "KRX:000002".to_string()],
+            max: 1,
+        }];
+        violations.extend((0..12).map(|i| RationaleViolation::TooShort {
+            ticker: format!("KRX:{i:06}"),
+            line: "상승".to_string(),
+            chars: 2,
+            min: 10,
+        }));
+        let err: anyhow::Error = RationaleConstraintError { violations }.into();
+
+        let prompt = repair_prompt("{}", &test_input(as_of), &err);
+        assert!(prompt.contains("RATIONALE PROBLEMS"));
+        assert!(prompt.contains(
+            "- line repeated in 2 items (max 1): \"Strong momentum.\" (KRX:000001, KRX:000002)"
+        ));
+        assert!(prompt.contains("- KRX:000000: line too short (2 < 10 chars): \"상승\""));
+        assert!(prompt.contains("- ... and 3 more"));
+
+        let rendered = PromptTemplate::default().render(&test_input(as_of).with_validation(
+            ValidationOptions {
+                rationale_min_chars: Some(10),
+                rationale_max_chars: Some(120),
+                rationale_max_duplicates: Some(3),
+                ..Default::default()
+            },
+        ));
+        assert!(rendered.contains("each rationale line must be 10..120 characters"));
+        assert!(rendered.contains("do not reuse the same line across items"));
+    }
+
+    #[test]
+    fn repair_prompt_asks_for_korean_rationale() {
+        let as_of = NaiveDate::from_ymd_opt(2026, 1, 28).unwrap();
+        let err: anyhow::Error = NonKoreanRationaleError {
+            tickers: vec!["KRX:000003".to_string()],
+        }
+        .into();
+        let prompt = repair_prompt("{}", &test_input(as_of), &err);
+        assert!(prompt.contains("NON-KOREAN RATIONALE (rewrite these items): KRX:000003"));
+        assert!(prompt.contains("rationale MUST be written in Korean"));
+    }
+}
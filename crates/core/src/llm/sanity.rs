@@ -0,0 +1,268 @@
+// Post-validation cross-check of LLM picks against the candidate features they were given.
+// Heuristic by design: flags are annotations unless the caller opts into strict mode.
+
+use crate::domain::recommendation::{Candidate, RecommendationSnapshot};
+use crate::llm::GenerateInput;
+use std::collections::BTreeMap;
+
+const DEFAULT_MAX_ILLIQUID_CONFIDENCE: f64 = 0.8;
+const DEFAULT_RET_TOLERANCE: f64 = 0.02;
+
+// Rationale phrases claiming a move in one direction (Korean first; the prompt asks for Korean).
+const UP_CLAIMS: &[&str] = &[
+    "상승",
+    "강세",
+    "반등",
+    "급등",
+    "uptrend",
+    "rally",
+    "rallied",
+    "positive momentum",
+    "rising",
+];
+const DOWN_CLAIMS: &[&str] = &[
+    "하락",
+    "약세",
+    "급락",
+    "downtrend",
+    "sell-off",
+    "selloff",
+    "negative momentum",
+    "falling",
+];
+
+#[derive(Debug, Clone)]
+pub struct SanityOptions {
+    /// Confidence above this on a bottom-decile liquidity name is flagged.
+    pub max_illiquid_confidence: f64,
+    /// `ret_1d` must contradict a rationale claim by more than this (fraction, 0.02 = 2%).
+    pub ret_tolerance: f64,
+}
+
+impl Default for SanityOptions {
+    fn default() -> Self {
+        Self {
+            max_illiquid_confidence: DEFAULT_MAX_ILLIQUID_CONFIDENCE,
+            ret_tolerance: DEFAULT_RET_TOLERANCE,
+        }
+    }
+}
+
+impl SanityOptions {
+    pub fn from_env() -> Self {
+        let mut out = Self::default();
+        if let Some(v) = std::env::var("LLM_SANITY_MAX_ILLIQUID_CONFIDENCE")
+            .ok()
+            .and_then(|s| s.parse::<f64>().ok())
+        {
+            out.max_illiquid_confidence = v;
+        }
+        if let Some(v) = std::env::var("LLM_SANITY_RET_TOLERANCE")
+            .ok()
+            .and_then(|s| s.parse::<f64>().ok())
+        {
+            out.ret_tolerance = v.abs();
+        }
+        out
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SanityFlagKind {
+    IlliquidHighConfidence,
+    MomentumContradiction,
+}
+
+impl SanityFlagKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SanityFlagKind::IlliquidHighConfidence => "illiquid_high_confidence",
+            SanityFlagKind::MomentumContradiction => "momentum_contradiction",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SanityFlag {
+    pub ticker: String,
+    pub kind: SanityFlagKind,
+    pub message: String,
+}
+
+/// Flags suspicious items in `snapshot`. Tickers without features are skipped.
+pub fn check(
+    input: &GenerateInput,
+    snapshot: &RecommendationSnapshot,
+    opts: &SanityOptions,
+) -> Vec<SanityFlag> {
+    let by_ticker: BTreeMap<&str, &Candidate> = input
+        .candidates
+        .iter()
+        .map(|c| (c.ticker.as_str(), c))
+        .collect();
+    let illiquid_cutoff = bottom_decile_cutoff(&input.candidates, "trading_value");
+
+    let mut flags = Vec::new();
+    for item in &snapshot.items {
+        let Some(candidate) = by_ticker.get(item.ticker.as_str()) else {
+            continue;
+        };
+
+        if let (Some(cutoff), Some(tv), Some(conf)) = (
+            illiquid_cutoff,
+            candidate.features.get("trading_value").copied(),
+            item.confidence,
+        ) {
+            if tv < cutoff && conf > opts.max_illiquid_confidence {
+                flags.push(SanityFlag {
+                    ticker: item.ticker.clone(),
+                    kind: SanityFlagKind::IlliquidHighConfidence,
+                    message: format!(
+                        "confidence {conf:.2} on a bottom-decile liquidity name (trading_value={tv:.0})"
+                    ),
+                });
+            }
+        }
+
+        if let Some(ret_1d) = candidate.features.get("ret_1d").copied() {
+            let text = item.rationale.join(" ").to_lowercase();
+            let claims_up = UP_CLAIMS.iter().any(|k| text.contains(k));
+            let claims_down = DOWN_CLAIMS.iter().any(|k| text.contains(k));
+            let contradiction = match (claims_up, claims_down) {
+                (true, false) if ret_1d < -opts.ret_tolerance => Some("upward"),
+                (false, true) if ret_1d > opts.ret_tolerance => Some("downward"),
+                _ => None,
+            };
+            if let Some(direction) = contradiction {
+                flags.push(SanityFlag {
+                    ticker: item.ticker.clone(),
+                    kind: SanityFlagKind::MomentumContradiction,
+                    message: format!(
+                        "rationale claims {direction} momentum but ret_1d={:+.2}%",
+                        ret_1d * 100.0
+                    ),
+                });
+            }
+        }
+    }
+    flags
+}
+
+/// Appends each flag to its item's `risk_notes` as a `[sanity]` warning.
+pub fn annotate(snapshot: &mut RecommendationSnapshot, flags: &[SanityFlag]) {
+    for flag in flags {
+        let Some(item) = snapshot.items.iter_mut().find(|i| i.ticker == flag.ticker) else {
+            continue;
+        };
+        let warning = format!("[sanity] {}", flag.message);
+        item.risk_notes = Some(match item.risk_notes.take() {
+            Some(notes) if !notes.trim().is_empty() => format!("{notes} | {warning}"),
+            _ => warning,
+        });
+    }
+}
+
+/// Flag counts per kind, for the run summary log.
+pub fn summarize(flags: &[SanityFlag]) -> BTreeMap<&'static str, usize> {
+    let mut out = BTreeMap::new();
+    for flag in flags {
+        *out.entry(flag.kind.as_str()).or_insert(0) += 1;
+    }
+    out
+}
+
+fn bottom_decile_cutoff(candidates: &[Candidate], feature: &str) -> Option<f64> {
+    let mut values: Vec<f64> = candidates
+        .iter()
+        .filter_map(|c| c.features.get(feature).copied())
+        .filter(|v| v.is_finite())
+        .collect();
+    if values.len() < 10 {
+        return None;
+    }
+    values.sort_by(|a, b| a.total_cmp(b));
+    Some(values[values.len() / 10])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::recommendation::RecommendationItem;
+    use chrono::{NaiveDate, TimeZone, Utc};
+
+    fn input(as_of: NaiveDate) -> GenerateInput {
+        let candidates = (1..=GenerateInput::MIN_CANDIDATES)
+            .map(|i| Candidate {
+                ticker: format!("KRX:{i:06}"),
+                name: format!("Name {i}"),
+                name_en: None,
+                sector: None,
+                features: [
+                    ("trading_value".to_string(), i as f64 * 1_000_000.0),
+                    ("ret_1d".to_string(), if i % 2 == 0 { 0.05 } else { -0.05 }),
+                ]
+                .into_iter()
+                .collect(),
+            })
+            .collect();
+        GenerateInput::try_new(as_of, candidates).unwrap()
+    }
+
+    fn item(id: usize, rationale: [&str; 3], confidence: Option<f64>) -> RecommendationItem {
+        RecommendationItem {
+            rank: 1,
+            ticker: format!("KRX:{id:06}"),
+            name: format!("Name {id}"),
+            name_en: None,
+            rationale: rationale.map(str::to_string),
+            risk_notes: Some("변동성 주의".to_string()),
+            confidence,
+        }
+    }
+
+    fn snapshot(as_of: NaiveDate, items: Vec<RecommendationItem>) -> RecommendationSnapshot {
+        RecommendationSnapshot {
+            as_of_date: as_of,
+            generated_at: Utc.with_ymd_and_hms(2026, 1, 27, 10, 0, 0).unwrap(),
+            items,
+        }
+    }
+
+    #[test]
+    fn flags_illiquid_high_confidence_and_momentum_contradictions() {
+        let as_of = NaiveDate::from_ymd_opt(2026, 1, 27).unwrap();
+        let input = input(as_of);
+        let mut snap = snapshot(
+            as_of,
+            vec![
+                // Bottom decile (tv rank 3 of 200) with high confidence.
+                item(4, ["거래대금 증가", "b", "c"], Some(0.95)),
+                // ret_1d = -5% but claims a rally.
+                item(101, ["전일 강한 상승 모멘텀", "b", "c"], Some(0.5)),
+                // Consistent: ret_1d = +5% and claims upward move; liquid.
+                item(150, ["상승 추세", "b", "c"], Some(0.95)),
+            ],
+        );
+
+        let flags = check(&input, &snap, &SanityOptions::default());
+        let kinds: Vec<(&str, SanityFlagKind)> =
+            flags.iter().map(|f| (f.ticker.as_str(), f.kind)).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ("KRX:000004", SanityFlagKind::IlliquidHighConfidence),
+                ("KRX:000101", SanityFlagKind::MomentumContradiction),
+            ]
+        );
+
+        annotate(&mut snap, &flags);
+        let notes = snap.items[1].risk_notes.as_deref().unwrap();
+        assert!(notes.starts_with("변동성 주의 | [sanity] rationale claims upward"));
+        assert!(notes.contains("-5.00%"));
+        assert_eq!(snap.items[2].risk_notes.as_deref(), Some("변동성 주의"));
+
+        let summary = summarize(&flags);
+        assert_eq!(summary["illiquid_high_confidence"], 1);
+        assert_eq!(summary["momentum_contradiction"], 1);
+    }
+}
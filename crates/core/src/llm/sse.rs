@@ -0,0 +1,108 @@
+// Minimal Server-Sent Events decoder (enough for provider streaming APIs).
+// Frames are separated by a blank line; only `event:` and `data:` fields are kept.
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SseEvent {
+    pub event: Option<String>,
+    pub data: String,
+}
+
+#[derive(Debug, Default)]
+pub struct SseDecoder {
+    buf: Vec<u8>,
+}
+
+impl SseDecoder {
+    /// Feed raw bytes; returns every frame completed by this chunk.
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        // Normalize CRLF line endings by dropping CR bytes.
+        self.buf
+            .extend(chunk.iter().copied().filter(|b| *b != b'\r'));
+
+        let mut out = Vec::new();
+        while let Some(end) = find_frame_end(&self.buf) {
+            let frame: Vec<u8> = self.buf.drain(..end + 2).collect();
+            if let Some(ev) = parse_frame(&frame[..end]) {
+                out.push(ev);
+            }
+        }
+        out
+    }
+
+    /// Flush a trailing frame that was not terminated by a blank line.
+    pub fn finish(&mut self) -> Option<SseEvent> {
+        let frame = std::mem::take(&mut self.buf);
+        parse_frame(&frame)
+    }
+}
+
+fn find_frame_end(buf: &[u8]) -> Option<usize> {
+    buf.windows(2).position(|w| w == b"\n\n")
+}
+
+fn parse_frame(frame: &[u8]) -> Option<SseEvent> {
+    let text = String::from_utf8_lossy(frame);
+    let mut event: Option<String> = None;
+    let mut data_lines: Vec<&str> = Vec::new();
+
+    for line in text.split('\n') {
+        if line.is_empty() || line.starts_with(':') {
+            continue;
+        }
+        let (field, value) = line.split_once(':').unwrap_or((line, ""));
+        let value = value.strip_prefix(' ').unwrap_or(value);
+        match field {
+            "event" => event = Some(value.to_string()),
+            "data" => data_lines.push(value),
+            _ => {}
+        }
+    }
+
+    if event.is_none() && data_lines.is_empty() {
+        return None;
+    }
+
+    Some(SseEvent {
+        event,
+        data: data_lines.join("\n"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_frames_split_across_chunks() {
+        let mut d = SseDecoder::default();
+        assert!(d.push(b"event: ping\r\ndata: {\"type\"").is_empty());
+        let evs = d.push(b":\"ping\"}\r\n\r\n: comment\n\nevent: x\ndata: a\ndata: b\n\n");
+        assert_eq!(
+            evs,
+            vec![
+                SseEvent {
+                    event: Some("ping".to_string()),
+                    data: "{\"type\":\"ping\"}".to_string(),
+                },
+                SseEvent {
+                    event: Some("x".to_string()),
+                    data: "a\nb".to_string(),
+                },
+            ]
+        );
+        assert!(d.finish().is_none());
+    }
+
+    #[test]
+    fn finish_flushes_unterminated_frame() {
+        let mut d = SseDecoder::default();
+        assert!(d.push(b"data: tail").is_empty());
+        assert_eq!(
+            d.finish(),
+            Some(SseEvent {
+                event: None,
+                data: "tail".to_string(),
+            })
+        );
+    }
+}
@@ -0,0 +1,76 @@
+//! Metric names shared by the API and worker, so dashboards don't break when either binary
+//! changes. Names follow Prometheus conventions (`_total` counters, `_seconds` durations).
+
+use ::metrics::{describe_counter, describe_gauge, describe_histogram, Unit};
+use chrono::NaiveDate;
+use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// API requests, labelled `method`, `route` (matched path pattern) and `status`.
+pub const API_REQUESTS_TOTAL: &str = "tootoo_api_requests_total";
+/// API request latency, same labels as [`API_REQUESTS_TOTAL`].
+pub const API_REQUEST_DURATION_SECONDS: &str = "tootoo_api_request_duration_seconds";
+/// Snapshot lookups by date/latest, labelled `result` (`hit` | `miss`).
+pub const API_SNAPSHOT_FETCH_TOTAL: &str = "tootoo_api_snapshot_fetch_total";
+/// Failed database queries in the API.
+pub const API_DB_ERRORS_TOTAL: &str = "tootoo_api_db_errors_total";
+
+/// Feature rows received from an ingest source, labelled `as_of_date` and `source`.
+pub const INGEST_ITEMS_TOTAL: &str = "tootoo_ingest_items_total";
+/// Failed ingest runs, labelled `as_of_date` and `source`.
+pub const INGEST_FAILURES_TOTAL: &str = "tootoo_ingest_failures_total";
+/// LLM provider calls, labelled `as_of_date`, `provider`, `stage` and `outcome`
+/// (`accepted` | `rejected`).
+pub const LLM_ATTEMPTS_TOTAL: &str = "tootoo_llm_attempts_total";
+/// Wall-clock duration of one worker invocation, labelled `as_of_date`, `mode` and `outcome`.
+pub const WORKER_RUN_DURATION_SECONDS: &str = "tootoo_worker_run_duration_seconds";
+
+static DATE_LABELS: AtomicBool = AtomicBool::new(true);
+
+/// Blanks the `as_of_date` label for the rest of the process. A one-shot run covers a handful of
+/// dates, but `worker --daemon` would add a series per trading day for as long as it stays up;
+/// its logs and run reports still carry the date.
+pub fn disable_date_labels() {
+    DATE_LABELS.store(false, Ordering::Relaxed);
+}
+
+/// The `as_of_date` label value: the date, or empty (which Prometheus treats as no label) once
+/// [`disable_date_labels`] was called.
+pub fn as_of_date_label(as_of_date: NaiveDate) -> String {
+    if DATE_LABELS.load(Ordering::Relaxed) {
+        as_of_date.to_string()
+    } else {
+        String::new()
+    }
+}
+
+/// Installs the process-wide Prometheus recorder and registers metric descriptions.
+///
+/// Without a recorder the `metrics` macros are no-ops, so libraries can record unconditionally.
+pub fn install_prometheus_recorder() -> anyhow::Result<PrometheusHandle> {
+    let handle = PrometheusBuilder::new().install_recorder()?;
+    describe();
+    Ok(handle)
+}
+
+fn describe() {
+    describe_counter!(API_REQUESTS_TOTAL, "HTTP requests handled by the API");
+    describe_histogram!(
+        API_REQUEST_DURATION_SECONDS,
+        Unit::Seconds,
+        "HTTP request latency"
+    );
+    describe_counter!(API_SNAPSHOT_FETCH_TOTAL, "Snapshot lookups, by hit or miss");
+    describe_counter!(API_DB_ERRORS_TOTAL, "Failed database queries in the API");
+    describe_counter!(
+        INGEST_ITEMS_TOTAL,
+        "Feature rows received from ingest sources"
+    );
+    describe_counter!(INGEST_FAILURES_TOTAL, "Failed ingest runs");
+    describe_counter!(LLM_ATTEMPTS_TOTAL, "LLM provider calls");
+    describe_gauge!(
+        WORKER_RUN_DURATION_SECONDS,
+        Unit::Seconds,
+        "Duration of the last worker run"
+    );
+}
@@ -0,0 +1,364 @@
+//! Webhook notifications sent by the worker after a snapshot is persisted.
+//!
+//! Each URL in `SNAPSHOT_WEBHOOK_URLS` gets a JSON POST. When `SNAPSHOT_WEBHOOK_SECRET` is set,
+//! the body is signed with HMAC-SHA256 and sent as `x-tootoo-signature: sha256=<hex>`.
+
+use crate::domain::recommendation::RecommendationSnapshot;
+use anyhow::Context;
+use std::time::Duration;
+
+pub const SIGNATURE_HEADER: &str = "x-tootoo-signature";
+pub const EVENT_SNAPSHOT_PERSISTED: &str = "snapshot.persisted";
+
+const DEFAULT_TIMEOUT_SECS: u64 = 10;
+const DEFAULT_RETRIES: u32 = 3;
+const DEFAULT_BACKOFF: Duration = Duration::from_secs(1);
+const TOP_TICKERS: usize = 5;
+
+/// Body POSTed to every webhook URL.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SnapshotPersisted {
+    pub event: String,
+    pub as_of_date: chrono::NaiveDate,
+    pub snapshot_id: uuid::Uuid,
+    /// Up to 5 tickers, best rank first.
+    pub top_tickers: Vec<String>,
+}
+
+impl SnapshotPersisted {
+    pub fn new(snapshot_id: uuid::Uuid, snapshot: &RecommendationSnapshot) -> Self {
+        let mut ranked: Vec<_> = snapshot.items.iter().collect();
+        ranked.sort_by_key(|item| item.rank);
+        Self {
+            event: EVENT_SNAPSHOT_PERSISTED.to_string(),
+            as_of_date: snapshot.as_of_date,
+            snapshot_id,
+            top_tickers: ranked
+                .into_iter()
+                .take(TOP_TICKERS)
+                .map(|item| item.ticker.clone())
+                .collect(),
+        }
+    }
+}
+
+/// One HTTP attempt against one URL, kept for the `webhook_deliveries` audit table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WebhookAttempt {
+    /// Position of the URL in `SNAPSHOT_WEBHOOK_URLS`.
+    pub url_index: usize,
+    /// Scheme, host and port only; paths of e.g. Slack webhooks are credentials.
+    pub url: String,
+    pub attempt_no: u32,
+    pub status_code: Option<u16>,
+    pub error: Option<String>,
+    pub delivered: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct WebhookNotifier {
+    http: reqwest::Client,
+    urls: Vec<String>,
+    secret: Option<String>,
+    retries: u32,
+    backoff: Duration,
+}
+
+impl WebhookNotifier {
+    /// `None` when `SNAPSHOT_WEBHOOK_URLS` is unset or empty.
+    pub fn from_env() -> anyhow::Result<Option<Self>> {
+        let urls: Vec<String> = std::env::var("SNAPSHOT_WEBHOOK_URLS")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+            .collect();
+        if urls.is_empty() {
+            return Ok(None);
+        }
+        let secret = std::env::var("SNAPSHOT_WEBHOOK_SECRET")
+            .ok()
+            .filter(|s| !s.is_empty());
+        if secret.is_none() {
+            tracing::warn!("SNAPSHOT_WEBHOOK_SECRET is unset; webhooks will be sent unsigned");
+        }
+        Self::new(urls, secret).map(Some)
+    }
+
+    pub fn new(urls: Vec<String>, secret: Option<String>) -> anyhow::Result<Self> {
+        for url in &urls {
+            reqwest::Url::parse(url)
+                .with_context(|| format!("invalid webhook URL {:?}", redact_url(url)))?;
+        }
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(DEFAULT_TIMEOUT_SECS))
+            .build()
+            .context("failed to build webhook http client")?;
+        Ok(Self {
+            http,
+            urls,
+            secret,
+            retries: DEFAULT_RETRIES,
+            backoff: DEFAULT_BACKOFF,
+        })
+    }
+
+    /// First retry delay; doubles on each further retry.
+    pub fn with_backoff(mut self, backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// POSTs `payload` to every URL, retrying transport errors, 429 and 5xx up to 3 times.
+    /// Never fails: the outcome of each attempt is logged and returned.
+    pub async fn deliver(&self, payload: &SnapshotPersisted) -> Vec<WebhookAttempt> {
+        let body = match serde_json::to_vec(payload) {
+            Ok(body) => body,
+            Err(err) => {
+                tracing::error!(error = %err, "failed to serialize webhook payload");
+                return Vec::new();
+            }
+        };
+        let signature = self
+            .secret
+            .as_deref()
+            .map(|secret| sign(secret.as_bytes(), &body));
+
+        let mut attempts = Vec::new();
+        for (url_index, url) in self.urls.iter().enumerate() {
+            let last = self
+                .deliver_one(url_index, url, &body, signature.as_deref(), &mut attempts)
+                .await;
+            if last.delivered {
+                tracing::info!(
+                    url_index,
+                    url = %last.url,
+                    attempts = last.attempt_no,
+                    snapshot_id = %payload.snapshot_id,
+                    "webhook delivered"
+                );
+            } else {
+                tracing::warn!(
+                    url_index,
+                    url = %last.url,
+                    attempts = last.attempt_no,
+                    status = ?last.status_code,
+                    error = ?last.error,
+                    snapshot_id = %payload.snapshot_id,
+                    "webhook delivery failed"
+                );
+            }
+        }
+        attempts
+    }
+
+    /// Appends every attempt at `url` to `out` and returns the last one.
+    async fn deliver_one(
+        &self,
+        url_index: usize,
+        url: &str,
+        body: &[u8],
+        signature: Option<&str>,
+        out: &mut Vec<WebhookAttempt>,
+    ) -> WebhookAttempt {
+        let display_url = redact_url(url);
+        let mut attempt_no = 0;
+        loop {
+            attempt_no += 1;
+            let mut req = self
+                .http
+                .post(url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .body(body.to_vec());
+            if let Some(signature) = signature {
+                req = req.header(SIGNATURE_HEADER, signature);
+            }
+
+            let (status_code, error, retryable) = match req.send().await {
+                Ok(res) if res.status().is_success() => (Some(res.status().as_u16()), None, false),
+                Ok(res) => {
+                    let status = res.status();
+                    let retryable = status.is_server_error()
+                        || status == reqwest::StatusCode::TOO_MANY_REQUESTS;
+                    (
+                        Some(status.as_u16()),
+                        Some(format!("HTTP {status}")),
+                        retryable,
+                    )
+                }
+                Err(err) => (None, Some(err.without_url().to_string()), true),
+            };
+            let attempt = WebhookAttempt {
+                url_index,
+                url: display_url.clone(),
+                attempt_no,
+                status_code,
+                delivered: error.is_none(),
+                error,
+            };
+            out.push(attempt.clone());
+
+            if attempt.delivered || !retryable || attempt_no > self.retries {
+                return attempt;
+            }
+            let backoff = self.backoff * (1 << (attempt_no - 1));
+            tracing::debug!(url_index, url = %display_url, attempt_no, ?backoff, "retrying webhook");
+            tokio::time::sleep(backoff).await;
+        }
+    }
+}
+
+/// `sha256=<hex HMAC-SHA256 of body>`, the `x-tootoo-signature` header value.
+pub fn sign(secret: &[u8], body: &[u8]) -> String {
+    let mac = hmac_sha256(secret, body).expect("HMAC-SHA256 is always available");
+    let hex: String = mac.iter().map(|b| format!("{b:02x}")).collect();
+    format!("sha256={hex}")
+}
+
+/// Constant-time check of a received `x-tootoo-signature` header against `body`.
+pub fn verify(secret: &[u8], body: &[u8], header: &str) -> bool {
+    let expected = sign(secret, body);
+    expected.len() == header.len() && openssl::memcmp::eq(expected.as_bytes(), header.as_bytes())
+}
+
+fn hmac_sha256(secret: &[u8], body: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let key = openssl::pkey::PKey::hmac(secret)?;
+    let mut signer = openssl::sign::Signer::new(openssl::hash::MessageDigest::sha256(), &key)?;
+    signer.update(body)?;
+    Ok(signer.sign_to_vec()?)
+}
+
+/// Scheme, host and port of `url`; the path and query are dropped.
+fn redact_url(url: &str) -> String {
+    match reqwest::Url::parse(url) {
+        Ok(parsed) => parsed.origin().ascii_serialization(),
+        Err(_) => "<invalid url>".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::recommendation::RecommendationItem;
+    use axum::http::{HeaderMap, StatusCode};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    fn snapshot() -> RecommendationSnapshot {
+        RecommendationSnapshot {
+            as_of_date: chrono::NaiveDate::from_ymd_opt(2026, 10, 16).unwrap(),
+            generated_at: chrono::Utc::now(),
+            items: (1..=20)
+                .rev()
+                .map(|rank| RecommendationItem {
+                    rank,
+                    ticker: format!("{rank:06}"),
+                    name: format!("Stock {rank}"),
+                    name_en: None,
+                    rationale: ["a".into(), "b".into(), "c".into()],
+                    risk_notes: None,
+                    confidence: None,
+                })
+                .collect(),
+        }
+    }
+
+    /// Receiver that fails the first `failures` requests with 503, then verifies signatures.
+    async fn serve_receiver(
+        secret: &'static str,
+        failures: usize,
+    ) -> (String, Arc<Mutex<Vec<(bool, SnapshotPersisted)>>>) {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let hits = Arc::new(AtomicUsize::new(0));
+        let recorded = received.clone();
+        let app = axum::Router::new().route(
+            "/hook",
+            axum::routing::post(move |headers: HeaderMap, body: axum::body::Bytes| {
+                let recorded = recorded.clone();
+                let hits = hits.clone();
+                async move {
+                    if hits.fetch_add(1, Ordering::SeqCst) < failures {
+                        return StatusCode::SERVICE_UNAVAILABLE;
+                    }
+                    let signature = headers
+                        .get(SIGNATURE_HEADER)
+                        .and_then(|v| v.to_str().ok())
+                        .unwrap_or_default();
+                    let valid = verify(secret.as_bytes(), &body, signature);
+                    let payload = serde_json::from_slice(&body).unwrap();
+                    recorded.lock().unwrap().push((valid, payload));
+                    if valid {
+                        StatusCode::NO_CONTENT
+                    } else {
+                        StatusCode::UNAUTHORIZED
+                    }
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        (format!("http://{addr}/hook"), received)
+    }
+
+    #[test]
+    fn payload_lists_the_top_five_by_rank() {
+        let payload = SnapshotPersisted::new(uuid::Uuid::nil(), &snapshot());
+        assert_eq!(payload.event, "snapshot.persisted");
+        assert_eq!(
+            payload.top_tickers,
+            vec!["000001", "000002", "000003", "000004", "000005"]
+        );
+    }
+
+    #[tokio::test]
+    async fn delivers_signed_payload_with_retries_and_never_fails() {
+        let (url, received) = serve_receiver("s3cret", 2).await;
+        let payload = SnapshotPersisted::new(uuid::Uuid::from_u128(7), &snapshot());
+        let notifier = WebhookNotifier::new(
+            vec![url.clone(), "http://127.0.0.1:1/unreachable".to_string()],
+            Some("s3cret".to_string()),
+        )
+        .unwrap()
+        .with_backoff(Duration::from_millis(5));
+
+        let attempts = notifier.deliver(&payload).await;
+
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 1);
+        assert!(received[0].0, "signature did not verify");
+        assert_eq!(received[0].1, payload);
+
+        let first: Vec<_> = attempts.iter().filter(|a| a.url_index == 0).collect();
+        assert_eq!(first.len(), 3);
+        assert_eq!(first[0].status_code, Some(503));
+        assert!(first[2].delivered);
+        assert_eq!(first[2].url, url.trim_end_matches("/hook"));
+
+        let second: Vec<_> = attempts.iter().filter(|a| a.url_index == 1).collect();
+        assert_eq!(second.len(), 4, "1 attempt + 3 retries");
+        assert!(second
+            .iter()
+            .all(|a| !a.delivered && a.status_code.is_none()));
+    }
+
+    #[tokio::test]
+    async fn wrong_secret_is_rejected_without_retrying() {
+        let (url, received) = serve_receiver("s3cret", 0).await;
+        let notifier = WebhookNotifier::new(vec![url], Some("other".to_string()))
+            .unwrap()
+            .with_backoff(Duration::from_millis(5));
+
+        let attempts = notifier
+            .deliver(&SnapshotPersisted::new(uuid::Uuid::nil(), &snapshot()))
+            .await;
+
+        assert!(!received.lock().unwrap()[0].0);
+        assert_eq!(attempts.len(), 1);
+        assert_eq!(attempts[0].status_code, Some(401));
+        assert!(!attempts[0].delivered);
+    }
+}
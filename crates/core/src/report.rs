@@ -0,0 +1,271 @@
+//! Machine-readable summary of one worker execution.
+//!
+//! The worker fills a [`RunReport`] as it goes, logs it as a single tracing event at exit and,
+//! with `--report-path`, writes it as JSON. Field names are part of the artifact's contract;
+//! the serialization test below pins them.
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunStatus {
+    Success,
+    /// Nothing to do: a success snapshot already existed or another run held the lock.
+    Skipped,
+    Error,
+    /// The process exited (or panicked) before the run recorded a final status.
+    Aborted,
+}
+
+/// CLI flags that shaped the run.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunFlags {
+    pub dry_run: bool,
+    pub force: bool,
+    pub strict_sanity: bool,
+    pub fail_fast: bool,
+    pub llm_provider: Option<String>,
+}
+
+/// Wall-clock milliseconds per phase; summed across dates in multi-date modes. `None` when the
+/// phase did not run.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PhaseTimings {
+    pub ingest_ms: Option<u64>,
+    pub universe_ms: Option<u64>,
+    /// The whole LLM call, including retries and repairs.
+    pub llm_ms: Option<u64>,
+    pub persist_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Ingest,
+    Universe,
+    Llm,
+    Persist,
+}
+
+impl PhaseTimings {
+    pub fn add(&mut self, phase: Phase, elapsed: Duration) {
+        let slot = match phase {
+            Phase::Ingest => &mut self.ingest_ms,
+            Phase::Universe => &mut self.universe_ms,
+            Phase::Llm => &mut self.llm_ms,
+            Phase::Persist => &mut self.persist_ms,
+        };
+        *slot = Some(slot.unwrap_or(0) + elapsed.as_millis() as u64);
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunCounts {
+    /// Candidates sent to the LLM.
+    pub candidates: Option<usize>,
+    /// Items in the persisted snapshot.
+    pub items: Option<usize>,
+    pub ingest_items: Option<usize>,
+    /// Tickers the ingest source failed to fetch (KIS only).
+    pub ingest_failures: Option<usize>,
+    /// Tickers missing the as-of bar that the KIS late-data pass then fetched.
+    pub ingest_late_recovered: Option<usize>,
+    /// LLM calls made, including retries and repairs.
+    pub llm_attempts: u32,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenUsage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+}
+
+impl TokenUsage {
+    /// Reads usage from a raw provider response: Anthropic (`usage.input_tokens`),
+    /// OpenAI-compatible (`usage.prompt_tokens`) or Gemini (`usageMetadata.promptTokenCount`).
+    pub fn from_raw_response(raw: &serde_json::Value) -> Option<Self> {
+        let pair = |obj: &serde_json::Value, input: &str, output: &str| {
+            let input = obj.get(input)?.as_u64()?;
+            Some(Self {
+                input_tokens: input,
+                output_tokens: obj.get(output).and_then(|v| v.as_u64()).unwrap_or(0),
+            })
+        };
+        if let Some(usage) = raw.get("usage") {
+            return pair(usage, "input_tokens", "output_tokens")
+                .or_else(|| pair(usage, "prompt_tokens", "completion_tokens"));
+        }
+        pair(
+            raw.get("usageMetadata")?,
+            "promptTokenCount",
+            "candidatesTokenCount",
+        )
+    }
+
+    pub fn add(&mut self, other: Self) {
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+    }
+}
+
+/// Outcome of one date in backfill / retry-failed runs and multi-date ingests.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DateReport {
+    pub as_of_date: NaiveDate,
+    /// `success`, `skipped`, `locked`, `no_universe`, `error` or `not_run`.
+    pub status: String,
+    pub snapshot_id: Option<uuid::Uuid>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunReport {
+    /// `recommend`, `full_run`, `dry_run`, `ingest_stub`, `ingest_external`, `ingest_kis`,
+    /// `backfill`, `retry_failed`, `prune`, `verify`, `list_pending`, `seed_dev` or `daemon` (one
+    /// report per scheduled run).
+    pub mode: String,
+    /// Resolved market date; the last date of the range in multi-date modes.
+    pub as_of_date: Option<NaiveDate>,
+    pub flags: RunFlags,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub phases: PhaseTimings,
+    pub counts: RunCounts,
+    /// Summed over every LLM attempt whose raw response carried usage.
+    pub token_usage: Option<TokenUsage>,
+    pub status: RunStatus,
+    pub snapshot_id: Option<uuid::Uuid>,
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dates: Vec<DateReport>,
+}
+
+impl RunReport {
+    pub fn new(mode: &str, flags: RunFlags, started_at: DateTime<Utc>) -> Self {
+        Self {
+            mode: mode.to_string(),
+            as_of_date: None,
+            flags,
+            started_at,
+            finished_at: None,
+            phases: PhaseTimings::default(),
+            counts: RunCounts::default(),
+            token_usage: None,
+            status: RunStatus::Aborted,
+            snapshot_id: None,
+            error: None,
+            dates: Vec::new(),
+        }
+    }
+
+    pub fn add_token_usage(&mut self, usage: TokenUsage) {
+        self.token_usage
+            .get_or_insert_with(TokenUsage::default)
+            .add(usage);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn report_serialization_is_stable() {
+        let mut report = RunReport::new(
+            "recommend",
+            RunFlags {
+                llm_provider: Some("anthropic".to_string()),
+                ..RunFlags::default()
+            },
+            Utc.with_ymd_and_hms(2026, 1, 5, 7, 20, 0).unwrap(),
+        );
+        report.as_of_date = NaiveDate::from_ymd_opt(2026, 1, 5);
+        report.finished_at = Some(Utc.with_ymd_and_hms(2026, 1, 5, 7, 21, 0).unwrap());
+        report
+            .phases
+            .add(Phase::Universe, Duration::from_millis(120));
+        report.phases.add(Phase::Llm, Duration::from_millis(40_000));
+        report.phases.add(Phase::Llm, Duration::from_millis(500));
+        report.counts.candidates = Some(200);
+        report.counts.items = Some(20);
+        report.counts.llm_attempts = 2;
+        report.add_token_usage(TokenUsage {
+            input_tokens: 1000,
+            output_tokens: 300,
+        });
+        report.status = RunStatus::Success;
+        report.snapshot_id = Some(uuid::Uuid::nil());
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "mode": "recommend",
+                "as_of_date": "2026-01-05",
+                "flags": {
+                    "dry_run": false,
+                    "force": false,
+                    "strict_sanity": false,
+                    "fail_fast": false,
+                    "llm_provider": "anthropic"
+                },
+                "started_at": "2026-01-05T07:20:00Z",
+                "finished_at": "2026-01-05T07:21:00Z",
+                "phases": {
+                    "ingest_ms": null,
+                    "universe_ms": 120,
+                    "llm_ms": 40500,
+                    "persist_ms": null
+                },
+                "counts": {
+                    "candidates": 200,
+                    "items": 20,
+                    "ingest_items": null,
+                    "ingest_failures": null,
+                    "ingest_late_recovered": null,
+                    "llm_attempts": 2
+                },
+                "token_usage": {"input_tokens": 1000, "output_tokens": 300},
+                "status": "success",
+                "snapshot_id": "00000000-0000-0000-0000-000000000000",
+                "error": null
+            })
+        );
+        let back: RunReport = serde_json::from_value(json).unwrap();
+        assert_eq!(back, report);
+    }
+
+    #[test]
+    fn reads_token_usage_from_each_provider_shape() {
+        let anthropic = serde_json::json!({"usage": {"input_tokens": 10, "output_tokens": 2}});
+        let openai = serde_json::json!({"usage": {"prompt_tokens": 7, "completion_tokens": 3}});
+        let gemini = serde_json::json!({"usageMetadata": {"promptTokenCount": 5, "candidatesTokenCount": 1}});
+        assert_eq!(
+            TokenUsage::from_raw_response(&anthropic),
+            Some(TokenUsage {
+                input_tokens: 10,
+                output_tokens: 2
+            })
+        );
+        assert_eq!(
+            TokenUsage::from_raw_response(&openai),
+            Some(TokenUsage {
+                input_tokens: 7,
+                output_tokens: 3
+            })
+        );
+        assert_eq!(
+            TokenUsage::from_raw_response(&gemini),
+            Some(TokenUsage {
+                input_tokens: 5,
+                output_tokens: 1
+            })
+        );
+        assert_eq!(
+            TokenUsage::from_raw_response(&serde_json::json!({"content": []})),
+            None
+        );
+    }
+}
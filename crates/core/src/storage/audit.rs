@@ -0,0 +1,199 @@
+//! Data-integrity checks for one as_of_date (worker `--verify`). Each check is a separate
+//! function so other surfaces (e.g. an admin endpoint) can run them individually.
+
+use anyhow::Context;
+use chrono::NaiveDate;
+use serde::Serialize;
+
+/// One failed check. `check` is a stable identifier; `detail` is for humans.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Violation {
+    pub check: &'static str,
+    pub detail: String,
+}
+
+impl Violation {
+    fn new(check: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            check,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// `(rank, ticker, rationale length)` of one stored item.
+pub type AuditItem = (i32, String, i32);
+
+/// Runs every check for `as_of_date`. Snapshot checks are skipped when the date has no valid
+/// success snapshot; the ingest check always runs.
+pub async fn audit_date(
+    pool: &sqlx::PgPool,
+    as_of_date: NaiveDate,
+    expected_items: usize,
+) -> anyhow::Result<Vec<Violation>> {
+    let mut out = Vec::new();
+    if let Some(snapshot_id) = success_snapshot_id(pool, as_of_date).await? {
+        let items = fetch_audit_items(pool, snapshot_id).await?;
+        out.extend(check_items(&items, expected_items));
+        out.extend(check_tickers_have_features(pool, snapshot_id, as_of_date).await?);
+    }
+    out.extend(check_ingest_run(pool, as_of_date).await?);
+    Ok(out)
+}
+
+/// The date's valid success snapshot, if any.
+pub async fn success_snapshot_id(
+    pool: &sqlx::PgPool,
+    as_of_date: NaiveDate,
+) -> anyhow::Result<Option<uuid::Uuid>> {
+    sqlx::query_scalar(
+        "SELECT id FROM current_recommendation_snapshots \
+         WHERE as_of_date = $1 AND status = 'success' \
+         ORDER BY generated_at DESC LIMIT 1",
+    )
+    .persistent(false)
+    .bind(as_of_date)
+    .fetch_optional(pool)
+    .await
+    .context("select success snapshot for audit failed")
+}
+
+pub async fn fetch_audit_items(
+    pool: &sqlx::PgPool,
+    snapshot_id: uuid::Uuid,
+) -> anyhow::Result<Vec<AuditItem>> {
+    sqlx::query_as::<_, (i32, String, i32)>(
+        "SELECT rank, ticker, COALESCE(cardinality(rationale), 0) \
+         FROM recommendation_items WHERE snapshot_id = $1 ORDER BY rank",
+    )
+    .persistent(false)
+    .bind(snapshot_id)
+    .fetch_all(pool)
+    .await
+    .context("select recommendation_items for audit failed")
+}
+
+/// Item count, contiguous ranks `1..=N` and three rationale lines per item.
+pub fn check_items(items: &[AuditItem], expected_items: usize) -> Vec<Violation> {
+    let mut out = Vec::new();
+    if items.len() != expected_items {
+        out.push(Violation::new(
+            "item_count",
+            format!("expected {expected_items} items, found {}", items.len()),
+        ));
+    }
+
+    let mut ranks: Vec<i32> = items.iter().map(|(rank, _, _)| *rank).collect();
+    ranks.sort_unstable();
+    let contiguous = ranks.iter().zip(1..).all(|(rank, want)| *rank == want);
+    if !contiguous {
+        out.push(Violation::new(
+            "rank_contiguity",
+            format!("ranks are not 1..={}: {ranks:?}", ranks.len()),
+        ));
+    }
+
+    for (rank, ticker, rationale_len) in items {
+        if *rationale_len != 3 {
+            out.push(Violation::new(
+                "rationale_length",
+                format!("rank {rank} ({ticker}) has {rationale_len} rationale lines, expected 3"),
+            ));
+        }
+    }
+    out
+}
+
+/// Every recommended ticker has a `stock_features_daily` row for the date.
+pub async fn check_tickers_have_features(
+    pool: &sqlx::PgPool,
+    snapshot_id: uuid::Uuid,
+    as_of_date: NaiveDate,
+) -> anyhow::Result<Vec<Violation>> {
+    let missing: Vec<String> = sqlx::query_scalar(
+        "SELECT i.ticker FROM recommendation_items i \
+         WHERE i.snapshot_id = $1 AND NOT EXISTS ( \
+           SELECT 1 FROM stock_features_daily f \
+           WHERE f.as_of_date = $2 AND f.ticker = i.ticker \
+         ) \
+         ORDER BY i.rank",
+    )
+    .persistent(false)
+    .bind(snapshot_id)
+    .bind(as_of_date)
+    .fetch_all(pool)
+    .await
+    .context("select tickers missing features for audit failed")?;
+
+    Ok(missing
+        .into_iter()
+        .map(|ticker| {
+            Violation::new(
+                "ticker_missing_features",
+                format!("{ticker} has no stock_features_daily row for {as_of_date}"),
+            )
+        })
+        .collect())
+}
+
+/// The date's most recent ingest run exists and succeeded (or found the payload unchanged).
+pub async fn check_ingest_run(
+    pool: &sqlx::PgPool,
+    as_of_date: NaiveDate,
+) -> anyhow::Result<Vec<Violation>> {
+    let latest: Option<(String, String)> = sqlx::query_as(
+        "SELECT provider, status FROM stock_features_ingest_runs \
+         WHERE as_of_date = $1 ORDER BY generated_at DESC LIMIT 1",
+    )
+    .persistent(false)
+    .bind(as_of_date)
+    .fetch_optional(pool)
+    .await
+    .context("select ingest run for audit failed")?;
+
+    Ok(match latest {
+        None => vec![Violation::new(
+            "ingest_run_missing",
+            format!("no ingest run recorded for {as_of_date}"),
+        )],
+        Some((_, status)) if status == "success" || status == "skipped_duplicate" => Vec::new(),
+        Some((provider, status)) => vec![Violation::new(
+            "ingest_run_not_success",
+            format!("latest ingest run for {as_of_date} ({provider}) has status {status}"),
+        )],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn items(ranks: &[i32]) -> Vec<AuditItem> {
+        ranks
+            .iter()
+            .map(|r| (*r, format!("KRX:{r:06}"), 3))
+            .collect()
+    }
+
+    #[test]
+    fn clean_items_pass() {
+        let all: Vec<i32> = (1..=20).collect();
+        assert_eq!(check_items(&items(&all), 20), Vec::new());
+    }
+
+    #[test]
+    fn flags_count_gaps_and_rationale_length() {
+        let mut rows = items(&[1, 2, 4]);
+        rows[1].2 = 2;
+        let checks: Vec<&str> = check_items(&rows, 20).iter().map(|v| v.check).collect();
+        assert_eq!(
+            checks,
+            vec!["item_count", "rank_contiguity", "rationale_length"]
+        );
+
+        let dup = items(&[1, 1, 2]);
+        let v = check_items(&dup, 3);
+        assert_eq!(v.len(), 1);
+        assert_eq!(v[0].check, "rank_contiguity");
+    }
+}
@@ -0,0 +1,26 @@
+use crate::llm::GenerateInput;
+use anyhow::Context;
+
+/// Stores the candidates behind `input.universe_digest()`. Returns false if already stored.
+pub async fn persist_candidate_universe(
+    pool: &sqlx::PgPool,
+    input: &GenerateInput,
+) -> anyhow::Result<bool> {
+    let candidates = crate::domain::universe::candidates_json(&input.candidates)?;
+
+    let res = sqlx::query(
+        "INSERT INTO candidate_universes (as_of_date, digest, universe_size, candidates) \
+         VALUES ($1, $2, $3, $4) \
+         ON CONFLICT (as_of_date, digest) DO NOTHING",
+    )
+    .persistent(false)
+    .bind(input.as_of_date)
+    .bind(input.universe_digest())
+    .bind(input.universe_size() as i32)
+    .bind(candidates)
+    .execute(pool)
+    .await
+    .context("insert candidate_universes failed")?;
+
+    Ok(res.rows_affected() > 0)
+}
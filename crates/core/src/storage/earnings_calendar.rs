@@ -0,0 +1,220 @@
+//! `earnings_calendar`: earnings announcement dates per ticker, bulk-loaded from a `date,ticker`
+//! CSV by worker `--load-earnings-calendar`. Ingest turns them into the `days_to_earnings`
+//! feature so the model can flag event risk.
+
+use anyhow::Context;
+use chrono::NaiveDate;
+use std::collections::BTreeMap;
+
+/// Announcements further than this many calendar days from the as_of_date are ignored.
+pub const EARNINGS_WINDOW_DAYS: i64 = 30;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EarningsDate {
+    pub announce_date: NaiveDate,
+    /// `KRX:`-prefixed, like every stored ticker.
+    pub ticker: String,
+}
+
+/// Parses `date,ticker` lines (`2026-02-12,KRX:005930`). A leading `date,ticker` header, blank
+/// lines and `#` comments are skipped. Tickers must already carry the `KRX:` prefix; every bad
+/// line is reported, not just the first.
+pub fn parse_csv(text: &str) -> anyhow::Result<Vec<EarningsDate>> {
+    let mut rows = Vec::new();
+    let mut errors = Vec::new();
+    for (idx, line) in text.lines().enumerate() {
+        let lineno = idx + 1;
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() || (rows.is_empty() && line.eq_ignore_ascii_case("date,ticker")) {
+            continue;
+        }
+        match parse_line(line) {
+            Ok(row) => rows.push(row),
+            Err(err) => errors.push(format!("line {lineno}: {err}")),
+        }
+    }
+    anyhow::ensure!(errors.is_empty(), "{}", errors.join("; "));
+    Ok(rows)
+}
+
+fn parse_line(line: &str) -> Result<EarningsDate, String> {
+    let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+    let [date, ticker] = fields[..] else {
+        return Err(format!("expected `date,ticker`, got {line:?}"));
+    };
+    let announce_date = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|_| format!("invalid date {date:?}"))?;
+    let Some(code) = ticker.strip_prefix("KRX:") else {
+        return Err(format!(
+            "ticker {ticker:?} must use the KRX: prefix (e.g. KRX:005930)"
+        ));
+    };
+    if code.len() != 6 || !code.bytes().all(|b| b.is_ascii_alphanumeric()) {
+        return Err(format!(
+            "ticker {ticker:?} is not KRX: plus a 6-character code"
+        ));
+    }
+    Ok(EarningsDate {
+        announce_date,
+        ticker: ticker.to_string(),
+    })
+}
+
+/// Upserts `rows` tagged with `source` (e.g. the CSV file name). Returns the rows written.
+pub async fn upsert(
+    pool: &sqlx::PgPool,
+    rows: &[EarningsDate],
+    source: &str,
+) -> anyhow::Result<u64> {
+    let tickers: Vec<&str> = rows.iter().map(|r| r.ticker.as_str()).collect();
+    let dates: Vec<NaiveDate> = rows.iter().map(|r| r.announce_date).collect();
+    let res = sqlx::query(
+        "INSERT INTO earnings_calendar (ticker, announce_date, source) \
+         SELECT t, d, $3 FROM UNNEST($1::text[], $2::date[]) AS u(t, d) \
+         ON CONFLICT (ticker, announce_date) DO UPDATE SET \
+           source = EXCLUDED.source, loaded_at = now()",
+    )
+    .persistent(false)
+    .bind(&tickers)
+    .bind(&dates)
+    .bind(source)
+    .execute(pool)
+    .await
+    .context("upsert earnings_calendar failed")?;
+    Ok(res.rows_affected())
+}
+
+/// Signed calendar days from `as_of_date` to the nearest announcement within
+/// [`EARNINGS_WINDOW_DAYS`]: positive before it, negative after, 0 on the day. An upcoming date
+/// wins a tie. `None` when nothing is in the window.
+pub fn days_to_earnings(as_of_date: NaiveDate, dates: &[NaiveDate]) -> Option<i64> {
+    dates
+        .iter()
+        .map(|d| (*d - as_of_date).num_days())
+        .filter(|days| days.abs() <= EARNINGS_WINDOW_DAYS)
+        .min_by_key(|days| (days.abs(), *days < 0))
+}
+
+/// `days_to_earnings` per ticker for `as_of_date`; tickers without a date in the window are
+/// absent.
+pub async fn days_to_earnings_by_ticker(
+    pool: &sqlx::PgPool,
+    as_of_date: NaiveDate,
+) -> anyhow::Result<BTreeMap<String, i64>> {
+    let window = chrono::Duration::days(EARNINGS_WINDOW_DAYS);
+    let rows = sqlx::query_as::<_, (String, NaiveDate)>(
+        "SELECT ticker, announce_date FROM earnings_calendar \
+         WHERE announce_date BETWEEN $1 AND $2",
+    )
+    .persistent(false)
+    .bind(as_of_date - window)
+    .bind(as_of_date + window)
+    .fetch_all(pool)
+    .await
+    .context("load earnings_calendar failed")?;
+
+    let mut by_ticker: BTreeMap<String, Vec<NaiveDate>> = BTreeMap::new();
+    for (ticker, date) in rows {
+        by_ticker.entry(ticker).or_default().push(date);
+    }
+    Ok(by_ticker
+        .into_iter()
+        .filter_map(|(ticker, dates)| Some((ticker, days_to_earnings(as_of_date, &dates)?)))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn d(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn parses_rows_skipping_header_blanks_and_comments() {
+        let rows = parse_csv(
+            "date,ticker\n\
+             2026-02-12,KRX:005930\n\
+             \n\
+             # Q4 guidance\n\
+             2026-02-05 , KRX:0001A0  # new-style code\n",
+        )
+        .unwrap();
+        assert_eq!(
+            rows,
+            [
+                EarningsDate {
+                    announce_date: d("2026-02-12"),
+                    ticker: "KRX:005930".to_string()
+                },
+                EarningsDate {
+                    announce_date: d("2026-02-05"),
+                    ticker: "KRX:0001A0".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_tickers_without_the_krx_prefix() {
+        let err = parse_csv("2026-02-12,005930\n2026-02-12,KRX:5930\n2026-13-01,KRX:005930\nx")
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            err,
+            "line 1: ticker \"005930\" must use the KRX: prefix (e.g. KRX:005930); \
+             line 2: ticker \"KRX:5930\" is not KRX: plus a 6-character code; \
+             line 3: invalid date \"2026-13-01\"; \
+             line 4: expected `date,ticker`, got \"x\""
+        );
+    }
+
+    #[test]
+    fn days_to_the_nearest_announcement_in_the_window() {
+        let as_of = d("2026-02-10");
+        assert_eq!(days_to_earnings(as_of, &[d("2026-02-12")]), Some(2));
+        assert_eq!(days_to_earnings(as_of, &[d("2026-02-10")]), Some(0));
+        assert_eq!(
+            days_to_earnings(as_of, &[d("2026-02-07"), d("2026-03-01")]),
+            Some(-3)
+        );
+        // Equally far: the upcoming one.
+        assert_eq!(
+            days_to_earnings(as_of, &[d("2026-02-08"), d("2026-02-12")]),
+            Some(2)
+        );
+        assert_eq!(days_to_earnings(as_of, &[d("2026-03-13")]), None);
+        assert_eq!(days_to_earnings(as_of, &[d("2026-03-12")]), Some(30));
+        assert_eq!(days_to_earnings(as_of, &[]), None);
+    }
+
+    #[tokio::test]
+    async fn upsert_and_load_round_trip() {
+        let Some(pool) =
+            crate::storage::test_support::database("earnings calendar round trip").await
+        else {
+            return;
+        };
+        sqlx::query("DELETE FROM earnings_calendar WHERE ticker LIKE 'KRX:9E%'")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let rows = parse_csv(
+            "2031-03-10,KRX:9E0001\n\
+             2031-03-01,KRX:9E0001\n\
+             2031-06-01,KRX:9E0002\n",
+        )
+        .unwrap();
+        assert_eq!(upsert(&pool, &rows, "test.csv").await.unwrap(), 3);
+        // Reloading the same file updates in place.
+        assert_eq!(upsert(&pool, &rows, "test.csv").await.unwrap(), 3);
+
+        let days = days_to_earnings_by_ticker(&pool, d("2031-03-04"))
+            .await
+            .unwrap();
+        assert_eq!(days.get("KRX:9E0001"), Some(&-3));
+        assert!(!days.contains_key("KRX:9E0002"));
+    }
+}
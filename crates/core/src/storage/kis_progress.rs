@@ -0,0 +1,71 @@
+//! `kis_ingest_progress`: checkpoints of a resumable KIS ingest, one row per ticker already
+//! upserted into `stock_features_daily` for the date.
+
+use crate::ingest::types::DailyFeatureItem;
+use anyhow::Context;
+use chrono::NaiveDate;
+use std::collections::HashSet;
+
+/// Tickers (`KRX:...`) already checkpointed for `as_of_date`.
+pub async fn completed_tickers(
+    pool: &sqlx::PgPool,
+    as_of_date: NaiveDate,
+) -> anyhow::Result<HashSet<String>> {
+    let rows = sqlx::query_scalar::<_, String>(
+        "SELECT ticker FROM kis_ingest_progress WHERE as_of_date = $1",
+    )
+    .persistent(false)
+    .bind(as_of_date)
+    .fetch_all(pool)
+    .await
+    .context("load kis_ingest_progress failed")?;
+    Ok(rows.into_iter().collect())
+}
+
+/// Upserts `items` into `stock_features_daily` and marks their tickers done, in one transaction,
+/// so a checkpoint never claims a row that was not written.
+pub async fn checkpoint(
+    pool: &sqlx::PgPool,
+    as_of_date: NaiveDate,
+    items: &[DailyFeatureItem],
+) -> anyhow::Result<u64> {
+    if items.is_empty() {
+        return Ok(0);
+    }
+    let mut tx = pool.begin().await.context("begin transaction failed")?;
+    let affected = crate::storage::stock_features::upsert_daily_features(
+        &mut tx,
+        as_of_date,
+        items,
+        crate::storage::stock_features::UpsertMode::Overwrite,
+    )
+    .await?
+    .affected();
+
+    let tickers: Vec<&str> = items.iter().map(|i| i.ticker.trim()).collect();
+    sqlx::query(
+        "INSERT INTO kis_ingest_progress (as_of_date, ticker) \
+         SELECT $1, t FROM UNNEST($2::text[]) AS t \
+         ON CONFLICT (as_of_date, ticker) DO NOTHING",
+    )
+    .persistent(false)
+    .bind(as_of_date)
+    .bind(&tickers)
+    .execute(&mut *tx)
+    .await
+    .context("insert kis_ingest_progress failed")?;
+
+    tx.commit().await.context("commit transaction failed")?;
+    Ok(affected)
+}
+
+/// Drops the date's checkpoints once its ingest has succeeded. Returns the rows removed.
+pub async fn clear(pool: &sqlx::PgPool, as_of_date: NaiveDate) -> anyhow::Result<u64> {
+    let res = sqlx::query("DELETE FROM kis_ingest_progress WHERE as_of_date = $1")
+        .persistent(false)
+        .bind(as_of_date)
+        .execute(pool)
+        .await
+        .context("clear kis_ingest_progress failed")?;
+    Ok(res.rows_affected())
+}
@@ -0,0 +1,90 @@
+use crate::llm::attempts::{AttemptSink, LlmAttempt};
+use anyhow::Context;
+
+pub async fn record_llm_attempt(
+    pool: &sqlx::PgPool,
+    run_id: uuid::Uuid,
+    attempt: &LlmAttempt,
+) -> anyhow::Result<uuid::Uuid> {
+    let id: uuid::Uuid = sqlx::query_scalar(
+        "INSERT INTO llm_attempts (run_id, as_of_date, provider, model, attempt_no, stage, raw_response, error) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8) \
+         RETURNING id",
+    )
+    .persistent(false)
+    .bind(run_id)
+    .bind(attempt.as_of_date)
+    .bind(attempt.provider.as_str())
+    .bind(&attempt.model)
+    .bind(attempt.attempt_no as i32)
+    .bind(attempt.stage)
+    .bind(&attempt.raw_response)
+    .bind(&attempt.error)
+    .fetch_one(pool)
+    .await
+    .context("insert llm_attempts failed")?;
+
+    Ok(id)
+}
+
+/// Links a run's attempts to the snapshot row it produced (success or error).
+pub async fn attach_llm_attempts(
+    pool: &sqlx::PgPool,
+    run_id: uuid::Uuid,
+    snapshot_id: uuid::Uuid,
+) -> anyhow::Result<u64> {
+    let res = sqlx::query(
+        "UPDATE llm_attempts SET snapshot_id = $2 WHERE run_id = $1 AND snapshot_id IS NULL",
+    )
+    .persistent(false)
+    .bind(run_id)
+    .bind(snapshot_id)
+    .execute(pool)
+    .await
+    .context("update llm_attempts snapshot_id failed")?;
+
+    Ok(res.rows_affected())
+}
+
+/// `AttemptSink` writing to `llm_attempts` under a fresh run id.
+#[derive(Debug, Clone)]
+pub struct PgAttemptSink {
+    pool: sqlx::PgPool,
+    run_id: uuid::Uuid,
+}
+
+impl PgAttemptSink {
+    pub fn new(pool: sqlx::PgPool) -> Self {
+        Self {
+            pool,
+            run_id: uuid::Uuid::new_v4(),
+        }
+    }
+
+    pub fn run_id(&self) -> uuid::Uuid {
+        self.run_id
+    }
+}
+
+#[async_trait::async_trait]
+impl AttemptSink for PgAttemptSink {
+    async fn record(&self, attempt: LlmAttempt) {
+        ::metrics::counter!(
+            crate::metrics::LLM_ATTEMPTS_TOTAL,
+            "as_of_date" => crate::metrics::as_of_date_label(attempt.as_of_date),
+            "provider" => attempt.provider.as_str(),
+            "stage" => attempt.stage,
+            "outcome" => if attempt.error.is_none() { "accepted" } else { "rejected" },
+        )
+        .increment(1);
+        if let Err(err) = record_llm_attempt(&self.pool, self.run_id, &attempt).await {
+            tracing::warn!(
+                run_id = %self.run_id,
+                attempt_no = attempt.attempt_no,
+                stage = attempt.stage,
+                error = %err,
+                "failed to record LLM attempt"
+            );
+        }
+    }
+}
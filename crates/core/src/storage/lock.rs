@@ -1,5 +1,7 @@
 use anyhow::Context;
 use chrono::{Datelike, NaiveDate};
+use sqlx::pool::PoolConnection;
+use sqlx::Postgres;
 
 // Advisory locks are scoped to the Postgres session. This is used as a best-effort guard against
 // concurrent EOD runs for the same as-of date.
@@ -17,7 +19,7 @@ pub async fn try_acquire_as_of_date_lock(
         .acquire()
         .await
         .context("acquire connection for advisory lock failed")?;
-    try_acquire_as_of_date_lock_conn(&mut *conn, as_of_date).await
+    try_acquire_as_of_date_lock_conn(&mut conn, as_of_date).await
 }
 
 pub async fn try_acquire_as_of_date_lock_conn(
@@ -42,7 +44,7 @@ pub async fn release_as_of_date_lock(
         .acquire()
         .await
         .context("acquire connection for advisory unlock failed")?;
-    release_as_of_date_lock_conn(&mut *conn, as_of_date).await
+    release_as_of_date_lock_conn(&mut conn, as_of_date).await
 }
 
 pub async fn release_as_of_date_lock_conn(
@@ -58,3 +60,181 @@ pub async fn release_as_of_date_lock_conn(
         .with_context(|| format!("failed to release advisory lock (key={key})"))?;
     Ok(())
 }
+
+/// The as-of-date lock held on its own pooled connection. Give it back with
+/// [`release`](Self::release); a guard dropped without it (an early return, a cancelled future)
+/// logs a warning and unlocks from a spawned task, so the lock never rides the connection back
+/// into the pool.
+#[derive(Debug)]
+pub struct AsOfDateLockGuard {
+    as_of_date: NaiveDate,
+    conn: Option<PoolConnection<Postgres>>,
+}
+
+impl AsOfDateLockGuard {
+    /// `None` when another session holds the lock.
+    pub async fn try_acquire(
+        pool: &sqlx::PgPool,
+        as_of_date: NaiveDate,
+    ) -> anyhow::Result<Option<Self>> {
+        // Session-scoped, so the lock lives and dies with this connection.
+        let mut conn = pool
+            .acquire()
+            .await
+            .context("acquire connection for advisory lock failed")?;
+        let acquired = try_acquire_as_of_date_lock_conn(&mut conn, as_of_date).await?;
+        Ok(acquired.then(|| Self {
+            as_of_date,
+            conn: Some(conn),
+        }))
+    }
+
+    pub fn as_of_date(&self) -> NaiveDate {
+        self.as_of_date
+    }
+
+    /// Unlocks and returns the connection to the pool. If the unlock fails the connection is
+    /// closed instead, which ends the session and the lock with it.
+    pub async fn release(mut self) -> anyhow::Result<()> {
+        let mut conn = self.conn.take().expect("lock guard holds its connection");
+        let released = release_as_of_date_lock_conn(&mut conn, self.as_of_date).await;
+        if released.is_err() {
+            drop(conn.detach());
+        }
+        released
+    }
+
+    /// Closes the session without unlocking, for when the database cannot be reached; the
+    /// server drops the lock with the session.
+    pub fn detach(mut self) {
+        if let Some(conn) = self.conn.take() {
+            drop(conn.detach());
+        }
+    }
+}
+
+impl Drop for AsOfDateLockGuard {
+    fn drop(&mut self) {
+        let Some(mut conn) = self.conn.take() else {
+            return;
+        };
+        let as_of_date = self.as_of_date;
+        tracing::warn!(%as_of_date, "as_of_date lock dropped without release; unlocking in the background");
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            drop(conn.detach());
+            return;
+        };
+        runtime.spawn(async move {
+            if let Err(e) = release_as_of_date_lock_conn(&mut conn, as_of_date).await {
+                tracing::warn!(%as_of_date, error = %e, "background as_of_date unlock failed; closing its connection");
+                drop(conn.detach());
+            }
+        });
+    }
+}
+
+// KIS token issuance is limited per app key, so processes sharing a `kis_access_tokens` row take
+// this lock before calling `/oauth2/tokenP`. Its namespace sits above the date keys' range.
+const KIS_TOKEN_LOCK_NAMESPACE: i64 = 0x4B49_5354_0000_0000; // "KIST"
+
+fn kis_token_lock_key(env: &str) -> i64 {
+    // FNV-1a: stable across processes and releases, unlike `DefaultHasher`.
+    let hash = env.bytes().fold(0x811C_9DC5_u32, |h, b| {
+        (h ^ u32::from(b)).wrapping_mul(0x0100_0193)
+    });
+    KIS_TOKEN_LOCK_NAMESPACE | i64::from(hash)
+}
+
+/// Session-scoped, like the as-of-date lock: hold `conn` until
+/// [`release_kis_token_lock_conn`] (or the session ends).
+pub async fn try_acquire_kis_token_lock_conn(
+    conn: &mut sqlx::PgConnection,
+    env: &str,
+) -> anyhow::Result<bool> {
+    let key = kis_token_lock_key(env);
+    let acquired: (bool,) = sqlx::query_as("SELECT pg_try_advisory_lock($1)")
+        .persistent(false)
+        .bind(key)
+        .fetch_one(conn)
+        .await
+        .with_context(|| format!("failed to acquire KIS token lock (key={key})"))?;
+    Ok(acquired.0)
+}
+
+pub async fn release_kis_token_lock_conn(
+    conn: &mut sqlx::PgConnection,
+    env: &str,
+) -> anyhow::Result<()> {
+    let key = kis_token_lock_key(env);
+    sqlx::query("SELECT pg_advisory_unlock($1)")
+        .persistent(false)
+        .bind(key)
+        .execute(conn)
+        .await
+        .with_context(|| format!("failed to release KIS token lock (key={key})"))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kis_token_keys_do_not_collide_with_date_keys() {
+        let date_key = lock_key_for_date(NaiveDate::from_ymd_opt(2026, 1, 5).unwrap());
+        assert_ne!(kis_token_lock_key("prod"), date_key);
+        assert_ne!(kis_token_lock_key("prod"), kis_token_lock_key("test"));
+        assert_eq!(kis_token_lock_key("prod"), kis_token_lock_key("prod"));
+        assert_eq!(
+            kis_token_lock_key("prod") >> 32,
+            KIS_TOKEN_LOCK_NAMESPACE >> 32
+        );
+    }
+
+    #[tokio::test]
+    async fn lock_guard_is_released_after_an_early_bail() {
+        let Some(url) = crate::storage::test_support::database_url("lock guard test") else {
+            return;
+        };
+        let pool = sqlx::PgPool::connect(&url).await.unwrap();
+        let as_of = NaiveDate::from_ymd_opt(2097, 6, 3).unwrap();
+        // A separate session, like another worker's.
+        let other = sqlx::PgPool::connect(&url).await.unwrap();
+        let free = || async {
+            let guard = AsOfDateLockGuard::try_acquire(&other, as_of).await.unwrap();
+            match guard {
+                Some(guard) => {
+                    guard.release().await.unwrap();
+                    true
+                }
+                None => false,
+            }
+        };
+
+        let guard = AsOfDateLockGuard::try_acquire(&pool, as_of)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(!free().await);
+        guard.release().await.unwrap();
+        assert!(free().await);
+
+        async fn run(pool: &sqlx::PgPool, as_of: NaiveDate) -> anyhow::Result<()> {
+            let _lock = AsOfDateLockGuard::try_acquire(pool, as_of)
+                .await?
+                .context("lock held")?;
+            anyhow::bail!("early bail");
+        }
+        assert!(run(&pool, as_of).await.is_err());
+        // The unlock runs on a spawned task.
+        let mut released = false;
+        for _ in 0..50 {
+            if free().await {
+                released = true;
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        assert!(released, "lock still held after the guard was dropped");
+    }
+}
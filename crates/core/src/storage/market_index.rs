@@ -0,0 +1,163 @@
+//! `market_index_daily`: KOSPI/KOSDAQ daily closes and returns, passed to the LLM as market context
+//! so rationale can account for the day's overall direction.
+
+use anyhow::Context;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MarketIndexDaily {
+    pub as_of_date: NaiveDate,
+    /// `KOSPI` or `KOSDAQ`.
+    pub market: String,
+    pub close: f64,
+    /// `None` when the previous trading day's close was not available.
+    pub ret_1d: Option<f64>,
+}
+
+pub async fn upsert(
+    pool: &sqlx::PgPool,
+    rows: &[MarketIndexDaily],
+    source: &str,
+) -> anyhow::Result<u64> {
+    let mut affected = 0;
+    for row in rows {
+        let res = sqlx::query(
+            "INSERT INTO market_index_daily (as_of_date, market, close, ret_1d, source) \
+             VALUES ($1, $2, $3, $4, $5) \
+             ON CONFLICT (as_of_date, market) DO UPDATE SET \
+               close = EXCLUDED.close, ret_1d = EXCLUDED.ret_1d, source = EXCLUDED.source, \
+               updated_at = now()",
+        )
+        .persistent(false)
+        .bind(row.as_of_date)
+        .bind(&row.market)
+        .bind(row.close)
+        .bind(row.ret_1d)
+        .bind(source)
+        .execute(pool)
+        .await
+        .context("upsert market_index_daily failed")?;
+        affected += res.rows_affected();
+    }
+    Ok(affected)
+}
+
+pub async fn fetch_by_date(
+    pool: &sqlx::PgPool,
+    as_of_date: NaiveDate,
+) -> anyhow::Result<Vec<MarketIndexDaily>> {
+    let rows = sqlx::query_as::<_, (String, f64, Option<f64>)>(
+        "SELECT market, close, ret_1d FROM market_index_daily WHERE as_of_date = $1 ORDER BY market",
+    )
+    .persistent(false)
+    .bind(as_of_date)
+    .fetch_all(pool)
+    .await
+    .context("load market_index_daily failed")?;
+    Ok(rows
+        .into_iter()
+        .map(|(market, close, ret_1d)| MarketIndexDaily {
+            as_of_date,
+            market,
+            close,
+            ret_1d,
+        })
+        .collect())
+}
+
+/// `kospi_ret_1d` / `kosdaq_ret_1d` for the LLM input. An index without a return (or without a
+/// row at all) is simply left out; no rows give an empty context.
+pub fn market_context(rows: &[MarketIndexDaily]) -> BTreeMap<String, f64> {
+    rows.iter()
+        .filter_map(|r| {
+            let ret = r.ret_1d.filter(|v| v.is_finite())?;
+            Some((
+                format!("{}_ret_1d", r.market.trim().to_ascii_lowercase()),
+                ret,
+            ))
+        })
+        .collect()
+}
+
+/// [`market_context`] for `as_of_date` from the database.
+pub async fn load_market_context(
+    pool: &sqlx::PgPool,
+    as_of_date: NaiveDate,
+) -> anyhow::Result<BTreeMap<String, f64>> {
+    Ok(market_context(&fetch_by_date(pool, as_of_date).await?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(market: &str, ret_1d: Option<f64>) -> MarketIndexDaily {
+        MarketIndexDaily {
+            as_of_date: NaiveDate::from_ymd_opt(2026, 1, 6).unwrap(),
+            market: market.to_string(),
+            close: 2_500.0,
+            ret_1d,
+        }
+    }
+
+    #[test]
+    fn context_keys_follow_the_market() {
+        let ctx = market_context(&[row("KOSPI", Some(-0.031)), row("KOSDAQ", Some(0.004))]);
+        assert_eq!(
+            ctx.into_iter().collect::<Vec<_>>(),
+            [
+                ("kosdaq_ret_1d".to_string(), 0.004),
+                ("kospi_ret_1d".to_string(), -0.031)
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_index_data_is_left_out() {
+        assert!(market_context(&[]).is_empty());
+        let ctx = market_context(&[row("KOSPI", None), row("KOSDAQ", Some(0.01))]);
+        assert_eq!(ctx.len(), 1);
+        assert_eq!(ctx["kosdaq_ret_1d"], 0.01);
+        assert!(market_context(&[row("KOSPI", Some(f64::NAN))]).is_empty());
+    }
+
+    #[tokio::test]
+    async fn upsert_and_load_round_trip() {
+        let Some(pool) = crate::storage::test_support::database("market index round trip").await
+        else {
+            return;
+        };
+        let as_of = NaiveDate::from_ymd_opt(2031, 3, 4).unwrap();
+        sqlx::query("DELETE FROM market_index_daily WHERE as_of_date = $1")
+            .bind(as_of)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        assert!(load_market_context(&pool, as_of).await.unwrap().is_empty());
+
+        let mut rows = vec![
+            MarketIndexDaily {
+                as_of_date: as_of,
+                market: "KOSPI".to_string(),
+                close: 2_400.0,
+                ret_1d: Some(-0.03),
+            },
+            MarketIndexDaily {
+                as_of_date: as_of,
+                market: "KOSDAQ".to_string(),
+                close: 800.0,
+                ret_1d: None,
+            },
+        ];
+        upsert(&pool, &rows, "kis").await.unwrap();
+        rows[1].ret_1d = Some(0.01);
+        upsert(&pool, &rows[1..], "kis").await.unwrap();
+
+        let ctx = load_market_context(&pool, as_of).await.unwrap();
+        assert_eq!(ctx["kospi_ret_1d"], -0.03);
+        assert_eq!(ctx["kosdaq_ret_1d"], 0.01);
+    }
+}
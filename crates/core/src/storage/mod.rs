@@ -1,8 +1,18 @@
 use anyhow::Context;
 
+pub mod audit;
+pub mod candidate_universes;
+pub mod earnings_calendar;
+pub mod kis_progress;
+pub mod llm_attempts;
 pub mod lock;
+pub mod market_index;
+pub mod outcomes;
+pub mod prune;
 pub mod recommendations;
+pub mod retry;
 pub mod stock_features;
+pub mod webhook_deliveries;
 
 pub async fn migrate(pool: &sqlx::PgPool) -> anyhow::Result<()> {
     // For Supabase connection pooler, prepared statements can be unsafe.
@@ -19,3 +29,81 @@ pub async fn migrate(pool: &sqlx::PgPool) -> anyhow::Result<()> {
         .context("sqlx migrations failed")?;
     Ok(())
 }
+
+/// Shared test helpers: the database for DB tests, and Postgres errors for classification tests.
+#[cfg(test)]
+pub(crate) mod test_support {
+    use anyhow::Context;
+    use std::borrow::Cow;
+
+    /// `TEST_DATABASE_URL`, or `None` after a note so a database test can return early. With
+    /// `TEST_DATABASE_REQUIRED` set (as in CI) a missing URL fails the test instead of skipping.
+    ///
+    /// Database tests share this one disposable Postgres rather than getting a fresh database
+    /// each: every test works on its own far-future dates or key prefixes, clears them first, and
+    /// may run concurrently with the others. Never point it at a database whose data matters.
+    pub(crate) fn database_url(test: &str) -> Option<String> {
+        match std::env::var("TEST_DATABASE_URL") {
+            Ok(url) => Some(url),
+            Err(_) if std::env::var_os("TEST_DATABASE_REQUIRED").is_some() => {
+                panic!("TEST_DATABASE_REQUIRED is set but TEST_DATABASE_URL is not ({test})")
+            }
+            Err(_) => {
+                eprintln!("TEST_DATABASE_URL unset; skipping {test}");
+                None
+            }
+        }
+    }
+
+    /// A pool on [`database_url`] with the migrations applied, or `None` when the test is skipped.
+    pub(crate) async fn database(test: &str) -> Option<sqlx::PgPool> {
+        let pool = sqlx::PgPool::connect(&database_url(test)?).await.unwrap();
+        crate::storage::migrate(&pool).await.unwrap();
+        Some(pool)
+    }
+
+    /// A Postgres error with just a SQLSTATE.
+    #[derive(Debug)]
+    struct PgError(&'static str);
+
+    impl std::fmt::Display for PgError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "SQLSTATE {}", self.0)
+        }
+    }
+
+    impl std::error::Error for PgError {}
+
+    impl sqlx::error::DatabaseError for PgError {
+        fn message(&self) -> &str {
+            self.0
+        }
+
+        fn code(&self) -> Option<Cow<'_, str>> {
+            Some(Cow::Borrowed(self.0))
+        }
+
+        fn as_error(&self) -> &(dyn std::error::Error + Send + Sync + 'static) {
+            self
+        }
+
+        fn as_error_mut(&mut self) -> &mut (dyn std::error::Error + Send + Sync + 'static) {
+            self
+        }
+
+        fn into_error(self: Box<Self>) -> Box<dyn std::error::Error + Send + Sync + 'static> {
+            self
+        }
+
+        fn kind(&self) -> sqlx::error::ErrorKind {
+            sqlx::error::ErrorKind::Other
+        }
+    }
+
+    /// [`PgError`] `code` under a context, as storage functions return it.
+    pub(crate) fn db_error(code: &'static str) -> anyhow::Error {
+        Err::<(), _>(sqlx::Error::Database(Box::new(PgError(code))))
+            .context("insert recommendation_snapshots failed")
+            .unwrap_err()
+    }
+}
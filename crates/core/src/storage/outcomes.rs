@@ -0,0 +1,315 @@
+//! `recommendation_outcomes`: forward returns of recommended items, for judging whether the picks
+//! work. Closes come from the `close` column of `stock_features_daily`.
+
+use anyhow::Context;
+use chrono::NaiveDate;
+use uuid::Uuid;
+
+/// Why an item has no return.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MissingPrice {
+    /// No usable close on the snapshot's as_of_date.
+    NoEntryClose,
+    /// No close on the exit date, typically a delisted or halted name.
+    NoExitClose,
+}
+
+impl MissingPrice {
+    /// The `missing_reason` code.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::NoEntryClose => "no_entry_close",
+            Self::NoExitClose => "no_exit_close",
+        }
+    }
+}
+
+/// One item's forward return over one horizon.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outcome {
+    pub ticker: String,
+    pub entry_close: Option<f64>,
+    pub exit_close: Option<f64>,
+    /// `(exit / entry - 1) * 100`; `None` exactly when `missing` is set.
+    pub return_pct: Option<f64>,
+    pub missing: Option<MissingPrice>,
+}
+
+impl Outcome {
+    /// A close that is absent, non-finite or not positive counts as missing.
+    pub fn compute(ticker: String, entry_close: Option<f64>, exit_close: Option<f64>) -> Self {
+        let usable = |c: Option<f64>| c.filter(|c| c.is_finite() && *c > 0.0);
+        let (return_pct, missing) = match (usable(entry_close), usable(exit_close)) {
+            (None, _) => (None, Some(MissingPrice::NoEntryClose)),
+            (Some(_), None) => (None, Some(MissingPrice::NoExitClose)),
+            (Some(entry), Some(exit)) => (Some((exit / entry - 1.0) * 100.0), None),
+        };
+        Self {
+            ticker,
+            entry_close,
+            exit_close,
+            return_pct,
+            missing,
+        }
+    }
+}
+
+/// Valid `success` snapshots to evaluate over `horizon_days`, oldest first: those without
+/// outcomes for the horizon, or every one when `include_evaluated`.
+pub async fn snapshots_to_evaluate(
+    pool: &sqlx::PgPool,
+    horizon_days: u32,
+    include_evaluated: bool,
+) -> anyhow::Result<Vec<(Uuid, NaiveDate)>> {
+    sqlx::query_as(
+        "SELECT s.id, s.as_of_date \
+         FROM current_recommendation_snapshots s \
+         WHERE s.status = 'success' \
+           AND ($2 OR NOT EXISTS ( \
+             SELECT 1 FROM recommendation_outcomes o \
+             WHERE o.snapshot_id = s.id AND o.horizon_days = $1 \
+           )) \
+         ORDER BY s.as_of_date ASC",
+    )
+    .persistent(false)
+    .bind(horizon_days as i32)
+    .bind(include_evaluated)
+    .fetch_all(pool)
+    .await
+    .context("select recommendation_snapshots to evaluate failed")
+}
+
+/// Computes and upserts the outcome of every item of `snapshot_id` (taken on `as_of_date`) at
+/// `exit_date`. Rerunning replaces the earlier outcomes, so it is safe to repeat once late
+/// closes arrive.
+pub async fn evaluate_snapshot(
+    pool: &sqlx::PgPool,
+    snapshot_id: Uuid,
+    as_of_date: NaiveDate,
+    horizon_days: u32,
+    exit_date: NaiveDate,
+) -> anyhow::Result<Vec<Outcome>> {
+    let rows = sqlx::query_as::<_, (String, Option<f64>, Option<f64>)>(
+        "SELECT i.ticker, e.close, x.close \
+         FROM recommendation_items i \
+         LEFT JOIN stock_features_daily e ON e.as_of_date = $2 AND e.ticker = i.ticker \
+         LEFT JOIN stock_features_daily x ON x.as_of_date = $3 AND x.ticker = i.ticker \
+         WHERE i.snapshot_id = $1 \
+         ORDER BY i.rank ASC",
+    )
+    .persistent(false)
+    .bind(snapshot_id)
+    .bind(as_of_date)
+    .bind(exit_date)
+    .fetch_all(pool)
+    .await
+    .context("select closes for recommendation_items failed")?;
+    let outcomes: Vec<Outcome> = rows
+        .into_iter()
+        .map(|(ticker, entry, exit)| Outcome::compute(ticker, entry, exit))
+        .collect();
+
+    sqlx::query(
+        "INSERT INTO recommendation_outcomes \
+           (snapshot_id, ticker, horizon_days, exit_date, entry_close, exit_close, return_pct, \
+            missing_reason, computed_at) \
+         SELECT $1, t, $2, $3, e, x, r, m, now() \
+         FROM UNNEST($4::text[], $5::float8[], $6::float8[], $7::float8[], $8::text[]) \
+           AS u(t, e, x, r, m) \
+         ON CONFLICT (snapshot_id, horizon_days, ticker) DO UPDATE SET \
+           exit_date = EXCLUDED.exit_date, entry_close = EXCLUDED.entry_close, \
+           exit_close = EXCLUDED.exit_close, return_pct = EXCLUDED.return_pct, \
+           missing_reason = EXCLUDED.missing_reason, computed_at = EXCLUDED.computed_at",
+    )
+    .persistent(false)
+    .bind(snapshot_id)
+    .bind(horizon_days as i32)
+    .bind(exit_date)
+    .bind(
+        outcomes
+            .iter()
+            .map(|o| o.ticker.clone())
+            .collect::<Vec<_>>(),
+    )
+    .bind(outcomes.iter().map(|o| o.entry_close).collect::<Vec<_>>())
+    .bind(outcomes.iter().map(|o| o.exit_close).collect::<Vec<_>>())
+    .bind(outcomes.iter().map(|o| o.return_pct).collect::<Vec<_>>())
+    .bind(
+        outcomes
+            .iter()
+            .map(|o| o.missing.map(MissingPrice::as_str))
+            .collect::<Vec<_>>(),
+    )
+    .execute(pool)
+    .await
+    .context("upsert recommendation_outcomes failed")?;
+    Ok(outcomes)
+}
+
+/// Aggregate outcome of one snapshot over one horizon.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct OutcomeSummary {
+    pub snapshot_id: Uuid,
+    pub as_of_date: NaiveDate,
+    pub horizon_days: i32,
+    pub items: i64,
+    /// Items with a return (both closes known).
+    pub priced: i64,
+    /// Share of priced items with a positive return; `None` when none are priced.
+    pub hit_rate: Option<f64>,
+    pub mean_return_pct: Option<f64>,
+}
+
+/// Per-snapshot, per-horizon summaries for snapshots taken in `from..=to`, by date then horizon.
+pub async fn outcome_summaries(
+    pool: &sqlx::PgPool,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> anyhow::Result<Vec<OutcomeSummary>> {
+    let rows = sqlx::query_as::<_, (Uuid, NaiveDate, i32, i64, i64, Option<f64>, Option<f64>)>(
+        "SELECT s.id, s.as_of_date, o.horizon_days, COUNT(*), COUNT(o.return_pct), \
+                AVG(CASE WHEN o.return_pct > 0 THEN 1.0 ELSE 0.0 END::double precision) \
+                  FILTER (WHERE o.return_pct IS NOT NULL), \
+                AVG(o.return_pct) \
+         FROM recommendation_outcomes o \
+         JOIN recommendation_snapshots s ON s.id = o.snapshot_id \
+         WHERE s.as_of_date BETWEEN $1 AND $2 \
+         GROUP BY s.id, s.as_of_date, o.horizon_days \
+         ORDER BY s.as_of_date ASC, o.horizon_days ASC",
+    )
+    .persistent(false)
+    .bind(from)
+    .bind(to)
+    .fetch_all(pool)
+    .await
+    .context("summarize recommendation_outcomes failed")?;
+    Ok(rows
+        .into_iter()
+        .map(
+            |(snapshot_id, as_of_date, horizon_days, items, priced, hit_rate, mean_return_pct)| {
+                OutcomeSummary {
+                    snapshot_id,
+                    as_of_date,
+                    horizon_days,
+                    items,
+                    priced,
+                    hit_rate,
+                    mean_return_pct,
+                }
+            },
+        )
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn returns_need_both_closes() {
+        let o = Outcome::compute("KRX:005930".into(), Some(100.0), Some(103.0));
+        assert!((o.return_pct.unwrap() - 3.0).abs() < 1e-9);
+        assert_eq!(o.missing, None);
+
+        let delisted = Outcome::compute("KRX:000001".into(), Some(100.0), None);
+        assert_eq!(delisted.return_pct, None);
+        assert_eq!(delisted.missing, Some(MissingPrice::NoExitClose));
+        assert_eq!(delisted.missing.unwrap().as_str(), "no_exit_close");
+
+        let bad_entry = Outcome::compute("KRX:000002".into(), Some(0.0), Some(5.0));
+        assert_eq!(bad_entry.missing, Some(MissingPrice::NoEntryClose));
+        assert_eq!(bad_entry.entry_close, Some(0.0));
+        let neither = Outcome::compute("KRX:000003".into(), None, None);
+        assert_eq!(neither.missing, Some(MissingPrice::NoEntryClose));
+    }
+
+    #[tokio::test]
+    async fn evaluation_is_idempotent_and_summarized() {
+        use crate::domain::recommendation::{RecommendationItem, RecommendationSnapshot};
+
+        let Some(pool) =
+            crate::storage::test_support::database("recommendation outcome test").await
+        else {
+            return;
+        };
+        let entry = NaiveDate::from_ymd_opt(2098, 3, 2).unwrap();
+        let exit = NaiveDate::from_ymd_opt(2098, 3, 3).unwrap();
+        for sql in [
+            "DELETE FROM recommendation_outcomes WHERE snapshot_id IN \
+             (SELECT id FROM recommendation_snapshots WHERE as_of_date = $1)",
+            "DELETE FROM recommendation_items WHERE snapshot_id IN \
+             (SELECT id FROM recommendation_snapshots WHERE as_of_date = $1)",
+            "DELETE FROM recommendation_snapshots WHERE as_of_date = $1",
+            "DELETE FROM stock_features_daily WHERE as_of_date IN ($1, $1 + 1)",
+        ] {
+            sqlx::query(sql).bind(entry).execute(&pool).await.unwrap();
+        }
+
+        let items: Vec<RecommendationItem> = (1..=20)
+            .map(|rank| RecommendationItem {
+                rank,
+                ticker: format!("KRX:9O{rank:04}"),
+                name: format!("Stock {rank}"),
+                name_en: None,
+                rationale: ["a".into(), "b".into(), "c".into()],
+                risk_notes: None,
+                confidence: None,
+            })
+            .collect();
+        let snapshot = RecommendationSnapshot {
+            as_of_date: entry,
+            generated_at: chrono::Utc::now(),
+            items,
+        };
+        let id =
+            crate::storage::recommendations::persist_success(&pool, &snapshot, "test", None, None)
+                .await
+                .unwrap();
+        // Ranks 1-10 rise 10%, 11-19 fall 5%; rank 20 is delisted before the exit date.
+        for rank in 1..=20 {
+            let ticker = format!("KRX:9O{rank:04}");
+            let exit_close = match rank {
+                1..=10 => Some(110.0),
+                11..=19 => Some(95.0),
+                _ => None,
+            };
+            let closes = [(entry, Some(100.0)), (exit, exit_close)];
+            for (date, close) in closes {
+                let Some(close) = close else { continue };
+                sqlx::query(
+                    "INSERT INTO stock_features_daily (as_of_date, ticker, name, close) \
+                     VALUES ($1, $2, $2, $3)",
+                )
+                .bind(date)
+                .bind(&ticker)
+                .bind(close)
+                .execute(&pool)
+                .await
+                .unwrap();
+            }
+        }
+
+        let pending = snapshots_to_evaluate(&pool, 1, false).await.unwrap();
+        assert!(pending.contains(&(id, entry)));
+        for _ in 0..2 {
+            let outcomes = evaluate_snapshot(&pool, id, entry, 1, exit).await.unwrap();
+            assert_eq!(outcomes.len(), 20);
+            assert_eq!(outcomes[19].missing, Some(MissingPrice::NoExitClose));
+        }
+        let pending = snapshots_to_evaluate(&pool, 1, false).await.unwrap();
+        assert!(!pending.contains(&(id, entry)));
+        assert!(snapshots_to_evaluate(&pool, 1, true)
+            .await
+            .unwrap()
+            .contains(&(id, entry)));
+
+        let summaries = outcome_summaries(&pool, entry, entry).await.unwrap();
+        assert_eq!(summaries.len(), 1);
+        let s = &summaries[0];
+        assert_eq!((s.snapshot_id, s.horizon_days), (id, 1));
+        assert_eq!((s.items, s.priced), (20, 19));
+        assert!((s.hit_rate.unwrap() - 10.0 / 19.0).abs() < 1e-9);
+        let mean = (10.0 * 10.0 - 9.0 * 5.0) / 19.0;
+        assert!((s.mean_return_pct.unwrap() - mean).abs() < 1e-9);
+    }
+}
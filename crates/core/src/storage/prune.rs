@@ -0,0 +1,266 @@
+//! Retention for large payload columns and old feature rows (worker `--prune`).
+
+use anyhow::Context;
+use chrono::NaiveDate;
+
+/// Retention below this many days is refused unless explicitly confirmed.
+pub const MIN_KEEP_DAYS: u32 = 7;
+
+/// One prunable target: the statement that prunes it and the `COUNT(*)` that previews it.
+/// Both share the same `WHERE` so `--dry-run` reports exactly what `prune` touches. `$1` is the
+/// cutoff date; rows with `as_of_date < $1` are pruned.
+struct Target {
+    table: &'static str,
+    prune_sql: &'static str,
+    count_sql: &'static str,
+}
+
+const SNAPSHOT_RAW: Target = Target {
+    table: "recommendation_snapshots.raw_llm_response",
+    prune_sql: "UPDATE recommendation_snapshots SET raw_llm_response = NULL \
+                WHERE as_of_date < $1 AND raw_llm_response IS NOT NULL",
+    count_sql: "SELECT COUNT(*) FROM recommendation_snapshots \
+                WHERE as_of_date < $1 AND raw_llm_response IS NOT NULL",
+};
+
+const INGEST_RUN_RAW: Target = Target {
+    table: "stock_features_ingest_runs.raw_response",
+    prune_sql: "UPDATE stock_features_ingest_runs SET raw_response = NULL \
+                WHERE as_of_date < $1 AND raw_response IS NOT NULL",
+    count_sql: "SELECT COUNT(*) FROM stock_features_ingest_runs \
+                WHERE as_of_date < $1 AND raw_response IS NOT NULL",
+};
+
+const STOCK_FEATURES: Target = Target {
+    table: "stock_features_daily",
+    prune_sql: "DELETE FROM stock_features_daily WHERE as_of_date < $1",
+    count_sql: "SELECT COUNT(*) FROM stock_features_daily WHERE as_of_date < $1",
+};
+
+/// Cutoff dates derived from the retention flags.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PrunePolicy {
+    /// Raw payloads of snapshots and ingest runs before this date are nulled (rows are kept).
+    pub raw_cutoff: NaiveDate,
+    /// `stock_features_daily` rows before this date are deleted; `None` keeps them all.
+    pub features_cutoff: Option<NaiveDate>,
+}
+
+impl PrunePolicy {
+    /// `keep_days` / `features_keep_days` are counted back from `today`. Either below
+    /// [`MIN_KEEP_DAYS`] is an error unless `confirmed`.
+    pub fn new(
+        today: NaiveDate,
+        keep_days: u32,
+        features_keep_days: Option<u32>,
+        confirmed: bool,
+    ) -> anyhow::Result<Self> {
+        for days in std::iter::once(keep_days).chain(features_keep_days) {
+            anyhow::ensure!(
+                days >= MIN_KEEP_DAYS || confirmed,
+                "refusing to prune with a retention of {days} days (< {MIN_KEEP_DAYS}); pass --yes-really to confirm"
+            );
+        }
+        let cutoff = |days: u32| today - chrono::Days::new(days.into());
+        Ok(Self {
+            raw_cutoff: cutoff(keep_days),
+            features_cutoff: features_keep_days.map(cutoff),
+        })
+    }
+
+    fn targets(&self) -> Vec<(&'static Target, NaiveDate)> {
+        let mut out = vec![
+            (&SNAPSHOT_RAW, self.raw_cutoff),
+            (&INGEST_RUN_RAW, self.raw_cutoff),
+        ];
+        if let Some(cutoff) = self.features_cutoff {
+            out.push((&STOCK_FEATURES, cutoff));
+        }
+        out
+    }
+}
+
+/// Rows affected (or, for a preview, that would be affected) per target.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PruneCount {
+    pub table: &'static str,
+    pub cutoff: NaiveDate,
+    pub rows: u64,
+}
+
+/// What [`prune`] would touch, without changing anything.
+pub async fn count_prunable(
+    pool: &sqlx::PgPool,
+    policy: &PrunePolicy,
+) -> anyhow::Result<Vec<PruneCount>> {
+    let mut out = Vec::new();
+    for (target, cutoff) in policy.targets() {
+        let rows: i64 = sqlx::query_scalar(target.count_sql)
+            .persistent(false)
+            .bind(cutoff)
+            .fetch_one(pool)
+            .await
+            .with_context(|| format!("count prunable {} failed", target.table))?;
+        out.push(PruneCount {
+            table: target.table,
+            cutoff,
+            rows: rows.max(0) as u64,
+        });
+    }
+    Ok(out)
+}
+
+/// Applies `policy`, one transaction per table, and returns the affected row counts.
+pub async fn prune(pool: &sqlx::PgPool, policy: &PrunePolicy) -> anyhow::Result<Vec<PruneCount>> {
+    let mut out = Vec::new();
+    for (target, cutoff) in policy.targets() {
+        let mut tx = pool.begin().await.context("begin transaction failed")?;
+        let res = sqlx::query(target.prune_sql)
+            .persistent(false)
+            .bind(cutoff)
+            .execute(&mut *tx)
+            .await
+            .with_context(|| format!("prune {} failed", target.table))?;
+        tx.commit().await.context("commit transaction failed")?;
+        out.push(PruneCount {
+            table: target.table,
+            cutoff,
+            rows: res.rows_affected(),
+        });
+    }
+    Ok(out)
+}
+
+/// `running` ingest runs older than this many hours are taken to have crashed.
+pub const DEFAULT_ABANDON_AFTER_HOURS: u32 = 6;
+
+/// Shared by [`abandon_stale_ingest_runs`] and its preview; `$1` is the start-time cutoff.
+const STALE_INGEST_RUNS: &str = "FROM stock_features_ingest_runs \
+                                 WHERE status = 'running' AND started_at < $1";
+
+/// Marks ingest runs still `running` `max_age` after they started as `abandoned` (the worker
+/// died before recording an outcome), or with `dry_run` only counts them.
+pub async fn abandon_stale_ingest_runs(
+    pool: &sqlx::PgPool,
+    max_age: chrono::Duration,
+    dry_run: bool,
+) -> anyhow::Result<u64> {
+    let cutoff = chrono::Utc::now() - max_age;
+    if dry_run {
+        let rows: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) {STALE_INGEST_RUNS}"))
+            .persistent(false)
+            .bind(cutoff)
+            .fetch_one(pool)
+            .await
+            .context("count stale ingest runs failed")?;
+        return Ok(rows.max(0) as u64);
+    }
+    let res = sqlx::query(&format!(
+        "UPDATE stock_features_ingest_runs SET status = 'abandoned', \
+             error = coalesce(error, 'abandoned: no outcome recorded'), finished_at = now() \
+         WHERE id IN (SELECT id {STALE_INGEST_RUNS})"
+    ))
+    .persistent(false)
+    .bind(cutoff)
+    .execute(pool)
+    .await
+    .context("abandon stale ingest runs failed")?;
+    Ok(res.rows_affected())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn policy_counts_back_from_today() {
+        let policy = PrunePolicy::new(date(2026, 10, 16), 30, Some(365), false).unwrap();
+        assert_eq!(policy.raw_cutoff, date(2026, 9, 16));
+        assert_eq!(policy.features_cutoff, Some(date(2025, 10, 16)));
+
+        let tables: Vec<_> = policy.targets().iter().map(|(t, _)| t.table).collect();
+        assert_eq!(
+            tables,
+            vec![
+                "recommendation_snapshots.raw_llm_response",
+                "stock_features_ingest_runs.raw_response",
+                "stock_features_daily"
+            ]
+        );
+
+        let raw_only = PrunePolicy::new(date(2026, 10, 16), 30, None, false).unwrap();
+        assert_eq!(raw_only.targets().len(), 2);
+    }
+
+    #[test]
+    fn short_retention_needs_confirmation() {
+        let today = date(2026, 10, 16);
+        let err = PrunePolicy::new(today, 3, None, false).unwrap_err();
+        assert!(err.to_string().contains("--yes-really"), "{err}");
+        assert!(PrunePolicy::new(today, 30, Some(1), false).is_err());
+        assert!(PrunePolicy::new(today, MIN_KEEP_DAYS, None, false).is_ok());
+        assert_eq!(
+            PrunePolicy::new(today, 0, None, true).unwrap().raw_cutoff,
+            today
+        );
+    }
+
+    #[test]
+    fn preview_and_prune_share_the_same_filter() {
+        for target in [SNAPSHOT_RAW, INGEST_RUN_RAW, STOCK_FEATURES] {
+            let filter = |sql: &str| sql.split("WHERE").nth(1).map(str::trim).map(String::from);
+            assert!(filter(target.prune_sql).is_some(), "{}", target.table);
+            assert_eq!(filter(target.prune_sql), filter(target.count_sql));
+        }
+    }
+
+    #[tokio::test]
+    async fn stale_running_ingest_runs_are_abandoned() {
+        use crate::storage::stock_features::{fetch_ingest_run, start_ingest_run};
+
+        let Some(pool) = crate::storage::test_support::database("stale ingest run test").await
+        else {
+            return;
+        };
+        let as_of = date(2031, 5, 9);
+        let stale = start_ingest_run(&pool, as_of, "kis").await.unwrap();
+        let fresh = start_ingest_run(&pool, as_of, "kis").await.unwrap();
+        sqlx::query(
+            "UPDATE stock_features_ingest_runs SET started_at = now() - interval '7 hours' \
+             WHERE id = $1",
+        )
+        .bind(stale)
+        .execute(&pool)
+        .await
+        .unwrap();
+        let max_age = chrono::Duration::hours(DEFAULT_ABANDON_AFTER_HOURS.into());
+
+        assert!(
+            abandon_stale_ingest_runs(&pool, max_age, true)
+                .await
+                .unwrap()
+                >= 1
+        );
+        let status = |id| {
+            let pool = pool.clone();
+            async move { fetch_ingest_run(&pool, id).await.unwrap().unwrap().status }
+        };
+        assert_eq!(status(stale).await, "running");
+
+        assert!(
+            abandon_stale_ingest_runs(&pool, max_age, false)
+                .await
+                .unwrap()
+                >= 1
+        );
+        assert_eq!(status(stale).await, "abandoned");
+        assert_eq!(status(fresh).await, "running");
+        let run = fetch_ingest_run(&pool, stale).await.unwrap().unwrap();
+        assert!(run.finished_at.is_some());
+        assert_eq!(run.error.as_deref(), Some("abandoned: no outcome recorded"));
+    }
+}
@@ -1,52 +1,209 @@
 use crate::domain::recommendation::{RecommendationItem, RecommendationSnapshot};
+use crate::llm::error::LlmFailureKind;
+use crate::storage::retry::with_db_retry;
 use anyhow::Context;
 
 pub async fn persist_success(
     pool: &sqlx::PgPool,
     snapshot: &RecommendationSnapshot,
     provider: &str,
+    prompt_hash: Option<&str>,
     raw_llm_response: Option<serde_json::Value>,
+) -> anyhow::Result<uuid::Uuid> {
+    persist_success_as(
+        pool,
+        uuid::Uuid::new_v4(),
+        snapshot,
+        provider,
+        prompt_hash,
+        raw_llm_response.as_ref(),
+        None,
+    )
+    .await
+}
+
+/// Like [`persist_success`], but records the new snapshot as superseding the date's current
+/// valid success (if any) in the same transaction, so the date is never left without one. The
+/// old row is not touched: the supersession goes to `recommendation_supersessions`. Used by
+/// forced worker reruns.
+pub async fn persist_success_replacing(
+    pool: &sqlx::PgPool,
+    snapshot: &RecommendationSnapshot,
+    provider: &str,
+    prompt_hash: Option<&str>,
+    raw_llm_response: Option<serde_json::Value>,
+    reason: &str,
+) -> anyhow::Result<uuid::Uuid> {
+    persist_success_as(
+        pool,
+        uuid::Uuid::new_v4(),
+        snapshot,
+        provider,
+        prompt_hash,
+        raw_llm_response.as_ref(),
+        Some(reason),
+    )
+    .await
+}
+
+/// [`persist_success`] found a valid success for the date already, e.g. persisted by a run that
+/// did not hold the date's advisory lock. Nothing was written.
+#[derive(Debug)]
+pub struct SuccessSnapshotExists {
+    pub as_of_date: chrono::NaiveDate,
+}
+
+impl std::fmt::Display for SuccessSnapshotExists {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "a valid success snapshot for {} already exists",
+            self.as_of_date
+        )
+    }
+}
+
+impl std::error::Error for SuccessSnapshotExists {}
+
+/// Persists `snapshot` as success row `snapshot_id`, superseding the date's current success when
+/// `replacing` gives a reason. Transient errors restart the transaction; since a
+/// commit can land with its reply lost, every attempt first looks for `snapshot_id` and stops
+/// there if an earlier one already wrote it.
+///
+/// The transaction is SERIALIZABLE: no index can say "one current success per date" once
+/// superseded rows stay valid, so two persists that both saw the date free must not both commit.
+/// The loser fails with a serialization error, is retried, and then finds the winner's row.
+async fn persist_success_as(
+    pool: &sqlx::PgPool,
+    snapshot_id: uuid::Uuid,
+    snapshot: &RecommendationSnapshot,
+    provider: &str,
+    prompt_hash: Option<&str>,
+    raw_llm_response: Option<&serde_json::Value>,
+    replacing: Option<&str>,
 ) -> anyhow::Result<uuid::Uuid> {
     anyhow::ensure!(
         snapshot.items.len() == 20,
         "snapshot must have exactly 20 items"
     );
 
-    let mut tx = pool.begin().await.context("begin transaction failed")?;
+    let op = match replacing {
+        Some(_) => "persist_success_replacing",
+        None => "persist_success",
+    };
+    with_db_retry(op, || async {
+        let mut tx = pool.begin().await.context("begin transaction failed")?;
+        sqlx::query("SET TRANSACTION ISOLATION LEVEL SERIALIZABLE")
+            .persistent(false)
+            .execute(&mut *tx)
+            .await
+            .context("set transaction isolation failed")?;
+        let committed: bool = sqlx::query_scalar(
+            "SELECT EXISTS (SELECT 1 FROM recommendation_snapshots WHERE id = $1)",
+        )
+        .persistent(false)
+        .bind(snapshot_id)
+        .fetch_one(&mut *tx)
+        .await
+        .context("look up recommendation_snapshots failed")?;
+        if committed {
+            tracing::info!(%snapshot_id, "snapshot already committed by an earlier attempt");
+            return Ok(snapshot_id);
+        }
+        if replacing.is_none() {
+            let current: bool = sqlx::query_scalar(
+                "SELECT EXISTS ( \
+                   SELECT 1 FROM current_recommendation_snapshots \
+                   WHERE as_of_date = $1 AND status = 'success' \
+                 )",
+            )
+            .persistent(false)
+            .bind(snapshot.as_of_date)
+            .fetch_one(&mut *tx)
+            .await
+            .context("look up current recommendation_snapshots failed")?;
+            if current {
+                return Err(SuccessSnapshotExists {
+                    as_of_date: snapshot.as_of_date,
+                }
+                .into());
+            }
+        }
+        insert_success(
+            &mut tx,
+            snapshot_id,
+            snapshot,
+            provider,
+            prompt_hash,
+            raw_llm_response,
+        )
+        .await?;
+        if let Some(reason) = replacing {
+            // The primary key on `snapshot_id` and the unique `superseded_by` keep supersession
+            // one-to-one, so a racing forced rerun is a unique violation, not a second current row.
+            sqlx::query(
+                "INSERT INTO recommendation_supersessions (snapshot_id, superseded_by, reason) \
+                 SELECT id, $2, $3 FROM current_recommendation_snapshots \
+                 WHERE as_of_date = $1 AND status = 'success' AND id <> $2",
+            )
+            .persistent(false)
+            .bind(snapshot.as_of_date)
+            .bind(snapshot_id)
+            .bind(reason)
+            .execute(&mut *tx)
+            .await
+            .context("insert recommendation_supersessions failed")?;
+        }
+        tx.commit().await.context("commit transaction failed")?;
+        Ok(snapshot_id)
+    })
+    .await
+}
 
-    let snapshot_id: uuid::Uuid = sqlx::query_scalar(
-        "INSERT INTO recommendation_snapshots (as_of_date, generated_at, provider, status, error, raw_llm_response) \
-         VALUES ($1, $2, $3, 'success', NULL, $4) \
-         RETURNING id",
+async fn insert_success(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    snapshot_id: uuid::Uuid,
+    snapshot: &RecommendationSnapshot,
+    provider: &str,
+    prompt_hash: Option<&str>,
+    raw_llm_response: Option<&serde_json::Value>,
+) -> anyhow::Result<()> {
+    sqlx::query(
+        "INSERT INTO recommendation_snapshots (id, as_of_date, generated_at, provider, status, error, raw_llm_response, prompt_hash) \
+         VALUES ($1, $2, $3, $4, 'success', NULL, $5, $6)",
     )
     .persistent(false)
+    .bind(snapshot_id)
     .bind(snapshot.as_of_date)
     .bind(snapshot.generated_at)
     .bind(provider)
     .bind(raw_llm_response)
-    .fetch_one(&mut *tx)
+    .bind(prompt_hash)
+    .execute(&mut **tx)
     .await
     .context("insert recommendation_snapshots failed")?;
 
     for item in &snapshot.items {
-        insert_item(&mut tx, snapshot_id, item).await?;
+        insert_item(tx, snapshot_id, item).await?;
     }
 
-    tx.commit().await.context("commit transaction failed")?;
-    Ok(snapshot_id)
+    Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn persist_failure(
     pool: &sqlx::PgPool,
     as_of_date: chrono::NaiveDate,
     generated_at: chrono::DateTime<chrono::Utc>,
     provider: &str,
+    prompt_hash: Option<&str>,
     error: &str,
+    error_kind: Option<LlmFailureKind>,
     raw_llm_response: Option<serde_json::Value>,
 ) -> anyhow::Result<uuid::Uuid> {
     let snapshot_id: uuid::Uuid = sqlx::query_scalar(
-        "INSERT INTO recommendation_snapshots (as_of_date, generated_at, provider, status, error, raw_llm_response) \
-         VALUES ($1, $2, $3, 'error', $4, $5) \
+        "INSERT INTO recommendation_snapshots (as_of_date, generated_at, provider, status, error, raw_llm_response, prompt_hash, error_kind) \
+         VALUES ($1, $2, $3, 'error', $4, $5, $6, $7) \
          RETURNING id",
     )
     .persistent(false)
@@ -55,6 +212,8 @@ pub async fn persist_failure(
     .bind(provider)
     .bind(error)
     .bind(raw_llm_response)
+    .bind(prompt_hash)
+    .bind(error_kind.map(LlmFailureKind::as_str))
     .fetch_one(pool)
     .await
     .context("insert error recommendation_snapshots failed")?;
@@ -67,17 +226,18 @@ async fn insert_item(
     snapshot_id: uuid::Uuid,
     item: &RecommendationItem,
 ) -> anyhow::Result<()> {
-    let rationale: Vec<String> = item.rationale.iter().cloned().collect();
+    let rationale: Vec<String> = item.rationale.to_vec();
 
     sqlx::query(
-        "INSERT INTO recommendation_items (snapshot_id, rank, ticker, name, rationale, risk_notes, confidence) \
-         VALUES ($1, $2, $3, $4, $5, $6, $7)",
+        "INSERT INTO recommendation_items (snapshot_id, rank, ticker, name, name_en, rationale, risk_notes, confidence) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
     )
     .persistent(false)
     .bind(snapshot_id)
     .bind(item.rank)
     .bind(&item.ticker)
     .bind(&item.name)
+    .bind(&item.name_en)
     .bind(rationale)
     .bind(&item.risk_notes)
     .bind(item.confidence)
@@ -87,3 +247,1151 @@ async fn insert_item(
 
     Ok(())
 }
+
+/// Dates on or after `since` whose most recent snapshot is an `error` and that have no valid
+/// `success` snapshot, oldest first.
+pub async fn find_failed_dates(
+    pool: &sqlx::PgPool,
+    since: chrono::NaiveDate,
+) -> anyhow::Result<Vec<chrono::NaiveDate>> {
+    sqlx::query_scalar(
+        "SELECT latest.as_of_date FROM ( \
+           SELECT DISTINCT ON (as_of_date) as_of_date, status \
+           FROM current_recommendation_snapshots \
+           WHERE as_of_date >= $1 \
+           ORDER BY as_of_date, generated_at DESC \
+         ) latest \
+         WHERE latest.status = 'error' \
+           AND NOT EXISTS ( \
+             SELECT 1 FROM current_recommendation_snapshots s \
+             WHERE s.as_of_date = latest.as_of_date AND s.status = 'success' \
+           ) \
+         ORDER BY latest.as_of_date",
+    )
+    .persistent(false)
+    .bind(since)
+    .fetch_all(pool)
+    .await
+    .context("select failed recommendation dates failed")
+}
+
+/// Best snapshot status per date in `from..=to`: `success` when a valid success exists,
+/// otherwise the latest row's status. Dates with no (valid) snapshot are absent.
+pub async fn snapshot_status_by_date(
+    pool: &sqlx::PgPool,
+    from: chrono::NaiveDate,
+    to: chrono::NaiveDate,
+) -> anyhow::Result<std::collections::BTreeMap<chrono::NaiveDate, String>> {
+    let rows = sqlx::query_as::<_, (chrono::NaiveDate, String)>(
+        "SELECT DISTINCT ON (as_of_date) as_of_date, status \
+         FROM current_recommendation_snapshots \
+         WHERE as_of_date BETWEEN $1 AND $2 \
+         ORDER BY as_of_date, (status = 'success') DESC, generated_at DESC",
+    )
+    .persistent(false)
+    .bind(from)
+    .bind(to)
+    .fetch_all(pool)
+    .await
+    .context("select snapshot status by date failed")?;
+    Ok(rows.into_iter().collect())
+}
+
+/// Number of valid `success` snapshots for `as_of_date`: neither invalidated nor superseded.
+/// This is what a forced rerun of the date supersedes.
+pub async fn count_success_snapshots(
+    pool: &sqlx::PgPool,
+    as_of_date: chrono::NaiveDate,
+) -> anyhow::Result<i64> {
+    sqlx::query_scalar(
+        "SELECT COUNT(*) FROM current_recommendation_snapshots \
+         WHERE as_of_date = $1 AND status = 'success'",
+    )
+    .persistent(false)
+    .bind(as_of_date)
+    .fetch_one(pool)
+    .await
+    .context("count success recommendation_snapshots failed")
+}
+
+/// Latest `success` snapshot strictly before `as_of_date`, with items ordered by rank.
+pub async fn fetch_latest_success_before(
+    pool: &sqlx::PgPool,
+    as_of_date: chrono::NaiveDate,
+) -> anyhow::Result<Option<RecommendationSnapshot>> {
+    let row: Option<(uuid::Uuid, chrono::NaiveDate, chrono::DateTime<chrono::Utc>)> =
+        sqlx::query_as(
+            "SELECT id, as_of_date, generated_at \
+             FROM current_recommendation_snapshots \
+             WHERE status = 'success' AND as_of_date < $1 \
+             ORDER BY as_of_date DESC \
+             LIMIT 1",
+        )
+        .persistent(false)
+        .bind(as_of_date)
+        .fetch_optional(pool)
+        .await
+        .context("select previous recommendation_snapshots failed")?;
+    let Some((snapshot_id, prev_as_of, generated_at)) = row else {
+        return Ok(None);
+    };
+
+    let items = fetch_items_for_snapshot(pool, snapshot_id).await?;
+
+    Ok(Some(RecommendationSnapshot {
+        as_of_date: prev_as_of,
+        generated_at,
+        items,
+    }))
+}
+
+/// `ticker -> rank` of the latest `success` snapshot strictly before `as_of_date`, in one round
+/// trip; empty when there is none. Used for per-item rank movement on snapshot reads.
+pub async fn fetch_previous_ranks(
+    pool: &sqlx::PgPool,
+    as_of_date: chrono::NaiveDate,
+) -> anyhow::Result<std::collections::BTreeMap<String, i32>> {
+    let rows = sqlx::query_as::<_, (String, i32)>(
+        "WITH p AS ( \
+           SELECT id \
+           FROM current_recommendation_snapshots \
+           WHERE status = 'success' AND as_of_date < $1 \
+           ORDER BY as_of_date DESC, generated_at DESC \
+           LIMIT 1 \
+         ) \
+         SELECT i.ticker, i.rank \
+         FROM p \
+         JOIN recommendation_items i ON i.snapshot_id = p.id",
+    )
+    .persistent(false)
+    .bind(as_of_date)
+    .fetch_all(pool)
+    .await
+    .context("select previous recommendation_items ranks failed")?;
+
+    Ok(rows.into_iter().collect())
+}
+
+/// Identity of a stored `success` snapshot, returned next to its items by the read functions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnapshotHeader {
+    pub snapshot_id: uuid::Uuid,
+    pub as_of_date: chrono::NaiveDate,
+    pub generated_at: chrono::DateTime<chrono::Utc>,
+    pub provider: String,
+}
+
+impl SnapshotHeader {
+    pub fn snapshot(&self, items: Vec<RecommendationItem>) -> RecommendationSnapshot {
+        RecommendationSnapshot {
+            as_of_date: self.as_of_date,
+            generated_at: self.generated_at,
+            items,
+        }
+    }
+}
+
+/// A `recommendation_items` row that cannot be read back as a [`RecommendationItem`] (missing
+/// columns, or a rationale that is not 3 lines). Every read function fails with this in the
+/// chain rather than skipping the row, so callers can tell corruption from absence.
+#[derive(Debug, thiserror::Error)]
+#[error(
+    "degraded recommendation_items row for snapshot_id={snapshot_id}, ticker={ticker}: {reason}"
+)]
+pub struct DegradedItem {
+    pub snapshot_id: uuid::Uuid,
+    /// `?` when the ticker itself is missing.
+    pub ticker: String,
+    pub reason: String,
+}
+
+/// The latest valid `success` snapshot (newest date, then newest generation) with its items
+/// ordered by rank, in one round trip.
+pub async fn fetch_latest_success(
+    pool: &sqlx::PgPool,
+) -> anyhow::Result<Option<(SnapshotHeader, Vec<RecommendationItem>)>> {
+    fetch_success(pool, None).await
+}
+
+/// The valid `success` snapshot for `as_of_date` (newest generation wins) with its items
+/// ordered by rank, in one round trip.
+pub async fn fetch_success_by_date(
+    pool: &sqlx::PgPool,
+    as_of_date: chrono::NaiveDate,
+) -> anyhow::Result<Option<(SnapshotHeader, Vec<RecommendationItem>)>> {
+    fetch_success(pool, Some(as_of_date)).await
+}
+
+async fn fetch_success(
+    pool: &sqlx::PgPool,
+    as_of_date: Option<chrono::NaiveDate>,
+) -> anyhow::Result<Option<(SnapshotHeader, Vec<RecommendationItem>)>> {
+    let rows = sqlx::query_as::<
+        _,
+        (
+            uuid::Uuid,
+            chrono::NaiveDate,
+            chrono::DateTime<chrono::Utc>,
+            String,
+            Option<i32>,
+            Option<String>,
+            Option<String>,
+            Option<String>,
+            Option<Vec<String>>,
+            Option<String>,
+            Option<f64>,
+        ),
+    >(
+        "WITH s AS ( \
+           SELECT id, as_of_date, generated_at, provider \
+           FROM current_recommendation_snapshots \
+           WHERE status = 'success' \
+             AND ($1::date IS NULL OR as_of_date = $1) \
+           ORDER BY as_of_date DESC, generated_at DESC \
+           LIMIT 1 \
+         ) \
+         SELECT s.id, s.as_of_date, s.generated_at, s.provider, \
+                i.rank, i.ticker, i.name, i.name_en, i.rationale, i.risk_notes, i.confidence \
+         FROM s \
+         LEFT JOIN recommendation_items i ON i.snapshot_id = s.id \
+         ORDER BY i.rank ASC",
+    )
+    .persistent(false)
+    .bind(as_of_date)
+    .fetch_all(pool)
+    .await
+    .context("select recommendation_snapshots with items failed")?;
+
+    let Some((snapshot_id, as_of_date, generated_at, provider, ..)) = rows.first().cloned() else {
+        return Ok(None);
+    };
+    let mut items = Vec::with_capacity(rows.len());
+    for (_, _, _, _, rank, ticker, name, name_en, rationale, risk_notes, confidence) in rows {
+        let joined = (
+            rank, ticker, name, name_en, rationale, risk_notes, confidence,
+        );
+        if let Some(item) = hydrate_joined_item(snapshot_id, joined)? {
+            items.push(item);
+        }
+    }
+
+    let header = SnapshotHeader {
+        snapshot_id,
+        as_of_date,
+        generated_at,
+        provider,
+    };
+    Ok(Some((header, items)))
+}
+
+/// One item of the `success` snapshot for `as_of_date`, resolved together with the snapshot so
+/// the two cannot come from different generations. `snapshot_id` pins the snapshot; otherwise
+/// the newest generation wins, as in [`fetch_success_by_date`].
+///
+/// `None` when there is no matching snapshot; `Some((id, None))` when it lacks `ticker`.
+pub async fn fetch_item_by_ticker(
+    pool: &sqlx::PgPool,
+    as_of_date: chrono::NaiveDate,
+    snapshot_id: Option<uuid::Uuid>,
+    ticker: &str,
+) -> anyhow::Result<Option<(uuid::Uuid, Option<RecommendationItem>)>> {
+    let row = sqlx::query_as::<
+        _,
+        (
+            uuid::Uuid,
+            Option<i32>,
+            Option<String>,
+            Option<String>,
+            Option<String>,
+            Option<Vec<String>>,
+            Option<String>,
+            Option<f64>,
+        ),
+    >(
+        "WITH s AS ( \
+           SELECT id \
+           FROM current_recommendation_snapshots \
+           WHERE status = 'success' \
+             AND as_of_date = $1 AND ($2::uuid IS NULL OR id = $2) \
+           ORDER BY generated_at DESC \
+           LIMIT 1 \
+         ) \
+         SELECT s.id, i.rank, i.ticker, i.name, i.name_en, i.rationale, i.risk_notes, i.confidence \
+         FROM s \
+         LEFT JOIN recommendation_items i ON i.snapshot_id = s.id AND i.ticker = $3",
+    )
+    .persistent(false)
+    .bind(as_of_date)
+    .bind(snapshot_id)
+    .bind(ticker)
+    .fetch_optional(pool)
+    .await
+    .context("select recommendation_item with snapshot failed")?;
+
+    let Some((snapshot_id, rank, ticker, name, name_en, rationale, risk_notes, confidence)) = row
+    else {
+        return Ok(None);
+    };
+    let item = hydrate_joined_item(
+        snapshot_id,
+        (
+            rank, ticker, name, name_en, rationale, risk_notes, confidence,
+        ),
+    )?;
+    Ok(Some((snapshot_id, item)))
+}
+
+/// A snapshot looked up by primary key, whatever its status.
+#[derive(Debug, Clone)]
+pub struct StoredSnapshot {
+    pub snapshot_id: uuid::Uuid,
+    pub provider: String,
+    /// `success` or `error`.
+    pub status: String,
+    pub error_kind: Option<String>,
+    /// Set when an admin hid the snapshot (see [`invalidate_snapshot`]) or a forced rerun
+    /// superseded it (see [`persist_success_replacing`]).
+    pub invalidated_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub invalidated_reason: Option<String>,
+    /// Items are only loaded for valid `success` snapshots; others have none.
+    pub snapshot: RecommendationSnapshot,
+}
+
+pub async fn fetch_snapshot_by_id(
+    pool: &sqlx::PgPool,
+    snapshot_id: uuid::Uuid,
+) -> anyhow::Result<Option<StoredSnapshot>> {
+    let row = sqlx::query_as::<
+        _,
+        (
+            chrono::NaiveDate,
+            chrono::DateTime<chrono::Utc>,
+            String,
+            String,
+            Option<String>,
+            Option<chrono::DateTime<chrono::Utc>>,
+            Option<String>,
+        ),
+    >(
+        "SELECT s.as_of_date, s.generated_at, s.provider, s.status, s.error_kind, \
+                COALESCE(s.invalidated_at, x.created_at), \
+                COALESCE(s.invalidated_reason, x.reason) \
+         FROM recommendation_snapshots s \
+         LEFT JOIN recommendation_supersessions x ON x.snapshot_id = s.id \
+         WHERE s.id = $1",
+    )
+    .persistent(false)
+    .bind(snapshot_id)
+    .fetch_optional(pool)
+    .await
+    .context("select recommendation_snapshots by id failed")?;
+    let Some((
+        as_of_date,
+        generated_at,
+        provider,
+        status,
+        error_kind,
+        invalidated_at,
+        invalidated_reason,
+    )) = row
+    else {
+        return Ok(None);
+    };
+
+    let items = if status == "success" && invalidated_at.is_none() {
+        fetch_items_for_snapshot(pool, snapshot_id).await?
+    } else {
+        Vec::new()
+    };
+
+    Ok(Some(StoredSnapshot {
+        snapshot_id,
+        provider,
+        status,
+        error_kind,
+        invalidated_at,
+        invalidated_reason,
+        snapshot: RecommendationSnapshot {
+            as_of_date,
+            generated_at,
+            items,
+        },
+    }))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum InvalidateOutcome {
+    Invalidated(chrono::DateTime<chrono::Utc>),
+    NotFound,
+    /// Only `success` snapshots can be invalidated.
+    NotSuccess,
+    AlreadyInvalidated(chrono::DateTime<chrono::Utc>),
+}
+
+/// Hides a successful snapshot from every read and frees its date for a rerun. The row is kept.
+pub async fn invalidate_snapshot(
+    pool: &sqlx::PgPool,
+    snapshot_id: uuid::Uuid,
+    reason: &str,
+) -> anyhow::Result<InvalidateOutcome> {
+    let updated: Option<chrono::DateTime<chrono::Utc>> = sqlx::query_scalar(
+        "UPDATE recommendation_snapshots \
+         SET invalidated_at = now(), invalidated_reason = $2 \
+         WHERE id = $1 AND status = 'success' \
+           AND id IN (SELECT id FROM current_recommendation_snapshots) \
+         RETURNING invalidated_at",
+    )
+    .persistent(false)
+    .bind(snapshot_id)
+    .bind(reason)
+    .fetch_optional(pool)
+    .await
+    .context("invalidate recommendation_snapshots failed")?;
+    if let Some(at) = updated {
+        return Ok(InvalidateOutcome::Invalidated(at));
+    }
+
+    let row = sqlx::query_as::<_, (String, Option<chrono::DateTime<chrono::Utc>>)>(
+        "SELECT s.status, COALESCE(s.invalidated_at, x.created_at) \
+         FROM recommendation_snapshots s \
+         LEFT JOIN recommendation_supersessions x ON x.snapshot_id = s.id \
+         WHERE s.id = $1",
+    )
+    .persistent(false)
+    .bind(snapshot_id)
+    .fetch_optional(pool)
+    .await
+    .context("select recommendation_snapshots for invalidation failed")?;
+    Ok(match row {
+        None => InvalidateOutcome::NotFound,
+        Some((_, Some(at))) => InvalidateOutcome::AlreadyInvalidated(at),
+        Some(_) => InvalidateOutcome::NotSuccess,
+    })
+}
+
+/// `recommendation_items` columns in `SELECT rank, ticker, name, name_en, rationale,
+/// risk_notes, confidence` order.
+type ItemRow = (
+    i32,
+    String,
+    String,
+    Option<String>,
+    Vec<String>,
+    Option<String>,
+    Option<f64>,
+);
+
+fn hydrate_item(snapshot_id: uuid::Uuid, row: ItemRow) -> Result<RecommendationItem, DegradedItem> {
+    let (rank, ticker, name, name_en, rationale, risk_notes, confidence) = row;
+    let rationale: [String; 3] = match rationale.try_into() {
+        Ok(rationale) => rationale,
+        Err(lines) => {
+            return Err(DegradedItem {
+                snapshot_id,
+                ticker,
+                reason: format!("rationale has {} lines, expected 3", lines.len()),
+            })
+        }
+    };
+    Ok(RecommendationItem {
+        rank,
+        ticker,
+        name,
+        name_en,
+        rationale,
+        risk_notes,
+        confidence,
+    })
+}
+
+/// [`ItemRow`] from a `LEFT JOIN recommendation_items`; all `None` when no item matched.
+type JoinedItemRow = (
+    Option<i32>,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    Option<Vec<String>>,
+    Option<String>,
+    Option<f64>,
+);
+
+/// `Ok(None)` only when the join matched no item; a matched row missing a required column is a
+/// [`DegradedItem`] like any other.
+fn hydrate_joined_item(
+    snapshot_id: uuid::Uuid,
+    row: JoinedItemRow,
+) -> Result<Option<RecommendationItem>, DegradedItem> {
+    match row {
+        (None, None, None, None, None, None, None) => Ok(None),
+        (
+            Some(rank),
+            Some(ticker),
+            Some(name),
+            name_en,
+            Some(rationale),
+            risk_notes,
+            confidence,
+        ) => hydrate_item(
+            snapshot_id,
+            (
+                rank, ticker, name, name_en, rationale, risk_notes, confidence,
+            ),
+        )
+        .map(Some),
+        (_, ticker, ..) => Err(DegradedItem {
+            snapshot_id,
+            ticker: ticker.unwrap_or_else(|| "?".to_string()),
+            reason: "rank, ticker, name or rationale is NULL".to_string(),
+        }),
+    }
+}
+
+/// All items of a snapshot, ordered by rank.
+pub async fn fetch_items_for_snapshot(
+    pool: &sqlx::PgPool,
+    snapshot_id: uuid::Uuid,
+) -> anyhow::Result<Vec<RecommendationItem>> {
+    let rows = sqlx::query_as::<_, ItemRow>(
+        "SELECT rank, ticker, name, name_en, rationale, risk_notes, confidence \
+         FROM recommendation_items \
+         WHERE snapshot_id = $1 \
+         ORDER BY rank ASC",
+    )
+    .persistent(false)
+    .bind(snapshot_id)
+    .fetch_all(pool)
+    .await
+    .context("select recommendation_items failed")?;
+
+    let items = rows
+        .into_iter()
+        .map(|row| hydrate_item(snapshot_id, row))
+        .collect::<Result<_, _>>()?;
+    Ok(items)
+}
+
+pub const DEFAULT_LIST_LIMIT: i64 = 20;
+pub const MAX_LIST_LIMIT: i64 = 100;
+
+/// One row of the snapshot history listing (no items).
+#[derive(Debug, Clone, PartialEq, serde::Serialize, utoipa::ToSchema)]
+pub struct SnapshotSummary {
+    pub snapshot_id: uuid::Uuid,
+    pub as_of_date: chrono::NaiveDate,
+    pub generated_at: chrono::DateTime<chrono::Utc>,
+    pub provider: String,
+    pub status: String,
+    /// `LlmFailureKind` of error snapshots.
+    pub error_kind: Option<String>,
+    pub item_count: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, utoipa::ToSchema)]
+pub struct SnapshotPage {
+    pub items: Vec<SnapshotSummary>,
+    pub total: i64,
+    /// Offset of the next page, or `None` on the last page.
+    pub next_offset: Option<i64>,
+}
+
+impl SnapshotPage {
+    pub fn new(items: Vec<SnapshotSummary>, total: i64, offset: i64) -> Self {
+        Self {
+            next_offset: next_offset(items.len(), total, offset),
+            items,
+            total,
+        }
+    }
+}
+
+fn next_offset(page_len: usize, total: i64, offset: i64) -> Option<i64> {
+    let end = offset + page_len as i64;
+    (page_len > 0 && end < total).then_some(end)
+}
+
+/// Snapshot history ordered by `as_of_date DESC`, optionally filtered by `status`; invalidated
+/// snapshots are left out. `limit` is
+/// clamped to `1..=MAX_LIST_LIMIT`.
+pub async fn list_snapshots(
+    pool: &sqlx::PgPool,
+    status: Option<&str>,
+    limit: i64,
+    offset: i64,
+) -> anyhow::Result<SnapshotPage> {
+    let limit = limit.clamp(1, MAX_LIST_LIMIT);
+    let offset = offset.max(0);
+
+    let total: i64 = sqlx::query_scalar(
+        "SELECT count(*) FROM current_recommendation_snapshots \
+         WHERE $1::text IS NULL OR status = $1",
+    )
+    .persistent(false)
+    .bind(status)
+    .fetch_one(pool)
+    .await
+    .context("count recommendation_snapshots failed")?;
+
+    let rows = sqlx::query_as::<
+        _,
+        (
+            uuid::Uuid,
+            chrono::NaiveDate,
+            chrono::DateTime<chrono::Utc>,
+            String,
+            String,
+            Option<String>,
+            i64,
+        ),
+    >(
+        "SELECT s.id, s.as_of_date, s.generated_at, s.provider, s.status, s.error_kind, \
+                (SELECT count(*) FROM recommendation_items i WHERE i.snapshot_id = s.id) \
+         FROM current_recommendation_snapshots s \
+         WHERE $1::text IS NULL OR s.status = $1 \
+         ORDER BY s.as_of_date DESC, s.generated_at DESC \
+         LIMIT $2 OFFSET $3",
+    )
+    .persistent(false)
+    .bind(status)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await
+    .context("list recommendation_snapshots failed")?;
+
+    let items = rows
+        .into_iter()
+        .map(
+            |(snapshot_id, as_of_date, generated_at, provider, status, error_kind, item_count)| {
+                SnapshotSummary {
+                    snapshot_id,
+                    as_of_date,
+                    generated_at,
+                    provider,
+                    status,
+                    error_kind,
+                    item_count,
+                }
+            },
+        )
+        .collect();
+    Ok(SnapshotPage::new(items, total, offset))
+}
+
+pub const DEFAULT_ITEMS_LIMIT: i64 = 50;
+pub const MAX_ITEMS_LIMIT: i64 = 200;
+
+/// Filters for [`list_items`]; `None` fields don't filter. Dates are inclusive.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ItemFilter {
+    pub ticker: Option<String>,
+    pub from: Option<chrono::NaiveDate>,
+    pub to: Option<chrono::NaiveDate>,
+    pub min_confidence: Option<f64>,
+}
+
+/// An item of a successful snapshot, annotated with the snapshot it belongs to.
+#[derive(Debug, Clone, serde::Serialize, utoipa::ToSchema)]
+pub struct DatedItem {
+    pub as_of_date: chrono::NaiveDate,
+    pub snapshot_id: uuid::Uuid,
+    #[serde(flatten)]
+    pub item: RecommendationItem,
+}
+
+#[derive(Debug, Clone, serde::Serialize, utoipa::ToSchema)]
+pub struct ItemPage {
+    pub items: Vec<DatedItem>,
+    pub total: i64,
+    /// Offset of the next page, or `None` on the last page.
+    pub next_offset: Option<i64>,
+}
+
+impl ItemPage {
+    pub fn new(items: Vec<DatedItem>, total: i64, offset: i64) -> Self {
+        Self {
+            next_offset: next_offset(items.len(), total, offset),
+            items,
+            total,
+        }
+    }
+}
+
+/// Items of successful snapshots matching `filter`, ordered by `as_of_date` then rank. `limit`
+/// is clamped to `1..=MAX_ITEMS_LIMIT`. Filters use `$n IS NULL OR ...`; the statements are
+/// unnamed (`persistent(false)`), so Postgres plans them with the actual values and can use
+/// `recommendation_items_ticker_snapshot_idx` when `ticker` is given.
+pub async fn list_items(
+    pool: &sqlx::PgPool,
+    filter: &ItemFilter,
+    limit: i64,
+    offset: i64,
+) -> anyhow::Result<ItemPage> {
+    const WHERE: &str = "WHERE s.status = 'success' \
+           AND ($1::text IS NULL OR i.ticker = $1) \
+           AND ($2::date IS NULL OR s.as_of_date >= $2) \
+           AND ($3::date IS NULL OR s.as_of_date <= $3) \
+           AND ($4::float8 IS NULL OR i.confidence >= $4)";
+    let limit = limit.clamp(1, MAX_ITEMS_LIMIT);
+    let offset = offset.max(0);
+
+    let total: i64 = sqlx::query_scalar(&format!(
+        "SELECT count(*) \
+         FROM recommendation_items i \
+         JOIN current_recommendation_snapshots s ON s.id = i.snapshot_id \
+         {WHERE}"
+    ))
+    .persistent(false)
+    .bind(filter.ticker.as_deref())
+    .bind(filter.from)
+    .bind(filter.to)
+    .bind(filter.min_confidence)
+    .fetch_one(pool)
+    .await
+    .context("count recommendation_items failed")?;
+
+    let rows = sqlx::query_as::<
+        _,
+        (
+            chrono::NaiveDate,
+            uuid::Uuid,
+            i32,
+            String,
+            String,
+            Option<String>,
+            Vec<String>,
+            Option<String>,
+            Option<f64>,
+        ),
+    >(&format!(
+        "SELECT s.as_of_date, s.id, i.rank, i.ticker, i.name, i.name_en, i.rationale, \
+                i.risk_notes, i.confidence \
+         FROM recommendation_items i \
+         JOIN current_recommendation_snapshots s ON s.id = i.snapshot_id \
+         {WHERE} \
+         ORDER BY s.as_of_date ASC, i.rank ASC \
+         LIMIT $5 OFFSET $6"
+    ))
+    .persistent(false)
+    .bind(filter.ticker.as_deref())
+    .bind(filter.from)
+    .bind(filter.to)
+    .bind(filter.min_confidence)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await
+    .context("list recommendation_items failed")?;
+
+    let mut items = Vec::with_capacity(rows.len());
+    for (as_of_date, snapshot_id, rank, ticker, name, name_en, rationale, risk_notes, confidence) in
+        rows
+    {
+        let item = hydrate_item(
+            snapshot_id,
+            (
+                rank, ticker, name, name_en, rationale, risk_notes, confidence,
+            ),
+        )?;
+        items.push(DatedItem {
+            as_of_date,
+            snapshot_id,
+            item,
+        });
+    }
+    Ok(ItemPage::new(items, total, offset))
+}
+
+/// The newest snapshot row of any status, for "today's run failed" reporting.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LatestRun {
+    pub snapshot_id: uuid::Uuid,
+    pub as_of_date: chrono::NaiveDate,
+    pub generated_at: chrono::DateTime<chrono::Utc>,
+    pub provider: String,
+    pub status: String,
+    pub error_kind: Option<String>,
+    /// Raw stored error; may contain provider output. Sanitize before exposing it.
+    pub error: Option<String>,
+}
+
+/// Most recent `recommendation_snapshots` row for the most recent `as_of_date`, regardless of
+/// status. Invalidated snapshots are skipped.
+pub async fn fetch_latest_run(pool: &sqlx::PgPool) -> anyhow::Result<Option<LatestRun>> {
+    let row = sqlx::query_as::<
+        _,
+        (
+            uuid::Uuid,
+            chrono::NaiveDate,
+            chrono::DateTime<chrono::Utc>,
+            String,
+            String,
+            Option<String>,
+            Option<String>,
+        ),
+    >(
+        "SELECT id, as_of_date, generated_at, provider, status, error_kind, error \
+         FROM current_recommendation_snapshots \
+         ORDER BY as_of_date DESC, generated_at DESC \
+         LIMIT 1",
+    )
+    .persistent(false)
+    .fetch_optional(pool)
+    .await
+    .context("select latest recommendation_snapshots failed")?;
+
+    Ok(row.map(
+        |(snapshot_id, as_of_date, generated_at, provider, status, error_kind, error)| LatestRun {
+            snapshot_id,
+            as_of_date,
+            generated_at,
+            provider,
+            status,
+            error_kind,
+            error,
+        },
+    ))
+}
+
+/// Longest `from..=to` span accepted by [`list_snapshot_range`], in days.
+pub const MAX_RANGE_DAYS: i64 = 370;
+
+/// Per-day aggregate of a successful snapshot, for charting.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, utoipa::ToSchema)]
+pub struct SnapshotRangeRow {
+    pub as_of_date: chrono::NaiveDate,
+    pub snapshot_id: uuid::Uuid,
+    pub provider: String,
+    pub item_count: i64,
+    /// `None` when no item carries a confidence.
+    pub avg_confidence: Option<f64>,
+    pub top_ticker: Option<String>,
+}
+
+/// One row per successful snapshot with `from <= as_of_date <= to`, ascending. Days without a
+/// snapshot are absent. Aggregates are computed in SQL.
+pub async fn list_snapshot_range(
+    pool: &sqlx::PgPool,
+    from: chrono::NaiveDate,
+    to: chrono::NaiveDate,
+) -> anyhow::Result<Vec<SnapshotRangeRow>> {
+    anyhow::ensure!(from <= to, "from ({from}) must not be after to ({to})");
+    anyhow::ensure!(
+        (to - from).num_days() <= MAX_RANGE_DAYS,
+        "range {from}..={to} exceeds {MAX_RANGE_DAYS} days"
+    );
+
+    let rows = sqlx::query_as::<
+        _,
+        (
+            chrono::NaiveDate,
+            uuid::Uuid,
+            String,
+            i64,
+            Option<f64>,
+            Option<String>,
+        ),
+    >(
+        "SELECT s.as_of_date, s.id, s.provider, count(i.id), avg(i.confidence), \
+                min(i.ticker) FILTER (WHERE i.rank = 1) \
+         FROM current_recommendation_snapshots s \
+         LEFT JOIN recommendation_items i ON i.snapshot_id = s.id \
+         WHERE s.status = 'success' \
+           AND s.as_of_date BETWEEN $1 AND $2 \
+         GROUP BY s.id, s.as_of_date, s.provider \
+         ORDER BY s.as_of_date ASC",
+    )
+    .persistent(false)
+    .bind(from)
+    .bind(to)
+    .fetch_all(pool)
+    .await
+    .context("select recommendation_snapshots range failed")?;
+
+    Ok(rows
+        .into_iter()
+        .map(
+            |(as_of_date, snapshot_id, provider, item_count, avg_confidence, top_ticker)| {
+                SnapshotRangeRow {
+                    as_of_date,
+                    snapshot_id,
+                    provider,
+                    item_count,
+                    avg_confidence,
+                    top_ticker,
+                }
+            },
+        )
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(rank: i32) -> RecommendationItem {
+        RecommendationItem {
+            rank,
+            ticker: format!("KRX:9R{rank:04}"),
+            name: format!("Stock {rank}"),
+            name_en: None,
+            rationale: ["a".into(), "b".into(), "c".into()],
+            risk_notes: None,
+            confidence: Some(0.5),
+        }
+    }
+
+    #[test]
+    fn short_rationales_and_null_columns_are_degraded() {
+        let id = uuid::Uuid::from_u128(1);
+        let row = |rationale: Vec<String>| {
+            (
+                1,
+                "KRX:005930".to_string(),
+                "Samsung".to_string(),
+                None,
+                rationale,
+                None,
+                None,
+            )
+        };
+        let err = hydrate_item(id, row(vec!["a".into(), "b".into()])).unwrap_err();
+        assert_eq!(err.ticker, "KRX:005930");
+        assert!(err.to_string().contains("2 lines"), "{err}");
+        assert!(hydrate_item(id, row(vec!["a".into(); 3])).is_ok());
+
+        let nothing_joined = (None, None, None, None, None, None, None);
+        assert!(hydrate_joined_item(id, nothing_joined).unwrap().is_none());
+        let null_rationale = (
+            Some(1),
+            Some("KRX:005930".to_string()),
+            Some("Samsung".to_string()),
+            None,
+            None,
+            None,
+            None,
+        );
+        let err = hydrate_joined_item(id, null_rationale).unwrap_err();
+        assert_eq!(err.snapshot_id, id);
+        assert!(err.to_string().contains("NULL"), "{err}");
+    }
+
+    /// Deletes every snapshot of `day` (a far-future date only these tests write).
+    async fn clear_day(pool: &sqlx::PgPool, day: chrono::NaiveDate) {
+        for sql in [
+            "DELETE FROM recommendation_supersessions WHERE snapshot_id IN \
+             (SELECT id FROM recommendation_snapshots WHERE as_of_date = $1)",
+            "DELETE FROM recommendation_items WHERE snapshot_id IN \
+             (SELECT id FROM recommendation_snapshots WHERE as_of_date = $1)",
+            "DELETE FROM recommendation_snapshots WHERE as_of_date = $1",
+        ] {
+            sqlx::query(sql).bind(day).execute(pool).await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn a_retried_persist_finds_its_own_commit() {
+        let Some(pool) = crate::storage::test_support::database("persist retry test").await else {
+            return;
+        };
+        let day = chrono::NaiveDate::from_ymd_opt(2099, 12, 30).unwrap();
+        clear_day(&pool, day).await;
+        let snapshot = RecommendationSnapshot {
+            as_of_date: day,
+            generated_at: chrono::Utc::now(),
+            items: (1..=20).map(item).collect(),
+        };
+        let first = persist_success(&pool, &snapshot, "test", None, None)
+            .await
+            .unwrap();
+
+        // A replacing persist whose commit landed is attempted again with the same id: the
+        // second attempt must neither supersede the row again nor insert another.
+        let id = uuid::Uuid::new_v4();
+        for _ in 0..2 {
+            let persisted =
+                persist_success_as(&pool, id, &snapshot, "test", None, None, Some("rerun"))
+                    .await
+                    .unwrap();
+            assert_eq!(persisted, id);
+        }
+        let rows: Vec<(uuid::Uuid, Option<uuid::Uuid>)> = sqlx::query_as(
+            "SELECT s.id, x.superseded_by FROM recommendation_snapshots s \
+             LEFT JOIN recommendation_supersessions x ON x.snapshot_id = s.id \
+             WHERE s.as_of_date = $1 ORDER BY s.created_at",
+        )
+        .bind(day)
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(rows, vec![(first, Some(id)), (id, None)]);
+    }
+
+    #[tokio::test]
+    async fn forced_reruns_keep_every_success_and_serve_the_newest() {
+        let Some(pool) = crate::storage::test_support::database("supersession test").await else {
+            return;
+        };
+        let day = chrono::NaiveDate::from_ymd_opt(2099, 12, 29).unwrap();
+        clear_day(&pool, day).await;
+        let snapshot = RecommendationSnapshot {
+            as_of_date: day,
+            generated_at: chrono::Utc::now(),
+            items: (1..=20).map(item).collect(),
+        };
+
+        let first = persist_success(&pool, &snapshot, "test", None, None)
+            .await
+            .unwrap();
+        assert_eq!(count_success_snapshots(&pool, day).await.unwrap(), 1);
+        // Without --force a second success for the date is refused.
+        let err = persist_success(&pool, &snapshot, "test", None, None)
+            .await
+            .unwrap_err();
+        assert!(
+            err.downcast_ref::<SuccessSnapshotExists>().is_some(),
+            "{err:#}"
+        );
+
+        // A rerun wins even when its model-written `generated_at` is older.
+        let older = RecommendationSnapshot {
+            generated_at: snapshot.generated_at - chrono::Duration::days(1),
+            ..snapshot.clone()
+        };
+        let second = persist_success_replacing(&pool, &older, "test", None, None, "rerun 1")
+            .await
+            .unwrap();
+        let third = persist_success_replacing(&pool, &snapshot, "test", None, None, "rerun 2")
+            .await
+            .unwrap();
+        assert_eq!(count_success_snapshots(&pool, day).await.unwrap(), 1);
+        let (header, items) = fetch_success_by_date(&pool, day).await.unwrap().unwrap();
+        assert_eq!(header.snapshot_id, third);
+        assert_eq!(items.len(), 20);
+
+        // Every success row is still stored unmodified.
+        let rows: Vec<(uuid::Uuid, bool)> = sqlx::query_as(
+            "SELECT id, invalidated_at IS NULL FROM recommendation_snapshots \
+             WHERE as_of_date = $1 AND status = 'success' ORDER BY created_at",
+        )
+        .bind(day)
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(rows, vec![(first, true), (second, true), (third, true)]);
+
+        // Superseded snapshots read back like invalidated ones, with the rerun's reason.
+        for (id, reason) in [(first, "rerun 1"), (second, "rerun 2")] {
+            let stored = fetch_snapshot_by_id(&pool, id).await.unwrap().unwrap();
+            assert!(stored.invalidated_at.is_some());
+            assert_eq!(stored.invalidated_reason.as_deref(), Some(reason));
+            assert!(stored.snapshot.items.is_empty());
+            assert!(matches!(
+                invalidate_snapshot(&pool, id, "bad").await.unwrap(),
+                InvalidateOutcome::AlreadyInvalidated(_)
+            ));
+        }
+        let status = snapshot_status_by_date(&pool, day, day).await.unwrap();
+        assert_eq!(status.get(&day).map(String::as_str), Some("success"));
+
+        // Invalidating the current one leaves the date without a success.
+        assert!(matches!(
+            invalidate_snapshot(&pool, third, "bad").await.unwrap(),
+            InvalidateOutcome::Invalidated(_)
+        ));
+        assert_eq!(count_success_snapshots(&pool, day).await.unwrap(), 0);
+        assert!(fetch_success_by_date(&pool, day).await.unwrap().is_none());
+        clear_day(&pool, day).await;
+    }
+
+    #[tokio::test]
+    async fn racing_persists_leave_one_current_success() {
+        let Some(pool) = crate::storage::test_support::database("racing persist test").await else {
+            return;
+        };
+        let day = chrono::NaiveDate::from_ymd_opt(2099, 12, 28).unwrap();
+        let snapshot = RecommendationSnapshot {
+            as_of_date: day,
+            generated_at: chrono::Utc::now(),
+            items: (1..=20).map(item).collect(),
+        };
+
+        // No advisory lock here: the database alone has to keep the date to one current success.
+        for _ in 0..5 {
+            clear_day(&pool, day).await;
+            let (a, b) = tokio::join!(
+                persist_success(&pool, &snapshot, "test", None, None),
+                persist_success(&pool, &snapshot, "test", None, None),
+            );
+            let refused = [&a, &b]
+                .into_iter()
+                .filter_map(|r| r.as_ref().err())
+                .inspect(|err| {
+                    assert!(
+                        err.downcast_ref::<SuccessSnapshotExists>().is_some(),
+                        "{err:#}"
+                    )
+                })
+                .count();
+            assert_eq!(refused, 1);
+            assert_eq!(count_success_snapshots(&pool, day).await.unwrap(), 1);
+
+            let (a, b) = tokio::join!(
+                persist_success_replacing(&pool, &snapshot, "test", None, None, "rerun a"),
+                persist_success_replacing(&pool, &snapshot, "test", None, None, "rerun b"),
+            );
+            assert!(a.is_ok() || b.is_ok(), "{a:?} {b:?}");
+            assert_eq!(count_success_snapshots(&pool, day).await.unwrap(), 1);
+        }
+        clear_day(&pool, day).await;
+    }
+
+    #[tokio::test]
+    async fn read_functions_agree_on_the_stored_snapshot() {
+        let Some(pool) = crate::storage::test_support::database("recommendation read test").await
+        else {
+            return;
+        };
+        let day = chrono::NaiveDate::from_ymd_opt(2099, 12, 31).unwrap();
+        clear_day(&pool, day).await;
+
+        assert!(fetch_success_by_date(&pool, day).await.unwrap().is_none());
+        let snapshot = RecommendationSnapshot {
+            as_of_date: day,
+            generated_at: chrono::Utc::now(),
+            items: (1..=20).rev().map(item).collect(),
+        };
+        let id = persist_success(&pool, &snapshot, "test", None, None)
+            .await
+            .unwrap();
+
+        let (header, items) = fetch_success_by_date(&pool, day).await.unwrap().unwrap();
+        assert_eq!(header.snapshot_id, id);
+        assert_eq!(header.provider, "test");
+        assert_eq!(items.len(), 20);
+        assert_eq!(items[0].rank, 1);
+        let (latest, _) = fetch_latest_success(&pool).await.unwrap().unwrap();
+        assert_eq!(latest, header);
+        let by_id = fetch_items_for_snapshot(&pool, id).await.unwrap();
+        assert_eq!(
+            by_id.iter().map(|i| &i.ticker).collect::<Vec<_>>(),
+            items.iter().map(|i| &i.ticker).collect::<Vec<_>>()
+        );
+
+        let (found_id, found) = fetch_item_by_ticker(&pool, day, None, "KRX:9R0007")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(found_id, id);
+        assert_eq!(found.unwrap().rank, 7);
+        let (_, missing) = fetch_item_by_ticker(&pool, day, Some(id), "KRX:000000")
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(missing.is_none());
+        let other = uuid::Uuid::from_u128(1);
+        assert!(fetch_item_by_ticker(&pool, day, Some(other), "KRX:9R0007")
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
@@ -0,0 +1,201 @@
+//! Retries for database work the connection pooler can cut off mid-flight ("connection closed",
+//! `57P01` admin shutdown). Only whole operations that are safe to run again go through
+//! [`with_db_retry`]: a transaction is begun inside the retried closure, so a retry restarts it.
+
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+/// SQLSTATEs worth another attempt: lost or refused connections (class 08, `57P0x`) and the
+/// serialization failures Postgres expects clients to retry.
+const TRANSIENT_CODES: &[&str] = &[
+    "08000", "08001", "08003", "08004", "08006", "57P01", "57P02", "57P03", "40001", "40P01",
+];
+
+/// Whether `err` (or an error in its context chain) is a database error that a fresh attempt
+/// may not hit: a transient SQLSTATE, a broken socket, or a pool timeout.
+pub fn is_transient(err: &anyhow::Error) -> bool {
+    err.chain()
+        .find_map(|e| e.downcast_ref::<sqlx::Error>())
+        .is_some_and(|e| match e {
+            sqlx::Error::Io(_) | sqlx::Error::PoolTimedOut => true,
+            sqlx::Error::Database(db) => db
+                .code()
+                .is_some_and(|code| TRANSIENT_CODES.contains(&code.as_ref())),
+            _ => false,
+        })
+}
+
+/// How often and how patiently [`with_db_retry`] retries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DbRetryPolicy {
+    /// Attempts in total, including the first; 1 disables retries.
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for DbRetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(200),
+            max_delay: Duration::from_secs(2),
+        }
+    }
+}
+
+impl DbRetryPolicy {
+    /// The default with `DB_RETRY_MAX_ATTEMPTS`, when set.
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        Self {
+            max_attempts: std::env::var("DB_RETRY_MAX_ATTEMPTS")
+                .ok()
+                .and_then(|s| s.trim().parse::<u32>().ok())
+                .unwrap_or(default.max_attempts)
+                .max(1),
+            ..default
+        }
+    }
+
+    /// Sleep before attempt `attempt + 1`: the exponential delay, capped, with the upper half
+    /// jittered so workers cut off together do not reconnect together.
+    fn delay(&self, attempt: u32) -> Duration {
+        let exp = self
+            .base_delay
+            .saturating_mul(1 << attempt.saturating_sub(1).min(16))
+            .min(self.max_delay);
+        let jitter = std::collections::hash_map::RandomState::new()
+            .build_hasher()
+            .finish();
+        exp / 2 + exp.mul_f64((jitter % 1000) as f64 / 2000.0)
+    }
+
+    /// Runs `f` until it succeeds, fails with an error [`is_transient`] rejects, or
+    /// `max_attempts` are used up. `op` names the operation in logs.
+    pub async fn run<T, F, Fut>(&self, op: &str, mut f: F) -> anyhow::Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = anyhow::Result<T>>,
+    {
+        let mut attempt: u32 = 0;
+        loop {
+            attempt += 1;
+            match f().await {
+                Ok(value) => {
+                    if attempt > 1 {
+                        tracing::info!(
+                            op,
+                            retries = attempt - 1,
+                            "database operation succeeded after retrying"
+                        );
+                    }
+                    return Ok(value);
+                }
+                Err(err) if attempt < self.max_attempts && is_transient(&err) => {
+                    let delay = self.delay(attempt);
+                    tracing::warn!(op, attempt, ?delay, error = %format!("{err:#}"), "transient database error; retrying");
+                    tokio::time::sleep(delay).await;
+                }
+                Err(err) => {
+                    if attempt > 1 {
+                        tracing::warn!(
+                            op,
+                            retries = attempt - 1,
+                            "database operation failed after retrying"
+                        );
+                    }
+                    return Err(err);
+                }
+            }
+        }
+    }
+}
+
+/// [`DbRetryPolicy::run`] with [`DbRetryPolicy::from_env`].
+pub async fn with_db_retry<T, F, Fut>(op: &str, f: F) -> anyhow::Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = anyhow::Result<T>>,
+{
+    DbRetryPolicy::from_env().run(op, f).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::test_support::db_error;
+
+    #[test]
+    fn only_connection_and_serialization_errors_are_transient() {
+        assert!(is_transient(&db_error("57P01")));
+        assert!(is_transient(&db_error("08006")));
+        assert!(is_transient(&db_error("40001")));
+        assert!(!is_transient(&db_error("23505")));
+        assert!(!is_transient(&db_error("42P01")));
+
+        let closed = std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "connection closed");
+        assert!(is_transient(&anyhow::Error::new(sqlx::Error::Io(closed))));
+        assert!(is_transient(&anyhow::Error::new(sqlx::Error::PoolTimedOut)));
+        assert!(!is_transient(&anyhow::Error::new(sqlx::Error::RowNotFound)));
+        assert!(!is_transient(&anyhow::anyhow!(
+            "snapshot must have exactly 20 items"
+        )));
+    }
+
+    #[test]
+    fn delays_grow_up_to_the_cap_with_jitter() {
+        let policy = DbRetryPolicy::default();
+        for _ in 0..20 {
+            let first = policy.delay(1);
+            assert!((100..=200).contains(&first.as_millis()), "{first:?}");
+            let late = policy.delay(10);
+            assert!((1000..=2000).contains(&late.as_millis()), "{late:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn retries_transient_errors_only() {
+        let policy = DbRetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::ZERO,
+            max_delay: Duration::ZERO,
+        };
+
+        let mut calls = 0;
+        let out = policy
+            .run("test", || {
+                calls += 1;
+                let attempt = calls;
+                async move {
+                    match attempt {
+                        1 => Err(db_error("57P01")),
+                        _ => Ok(attempt),
+                    }
+                }
+            })
+            .await;
+        assert_eq!(out.unwrap(), 2);
+
+        let mut calls = 0;
+        let out = policy
+            .run("test", || {
+                calls += 1;
+                async { Err::<(), _>(db_error("23505")) }
+            })
+            .await;
+        assert!(out.is_err());
+        assert_eq!(calls, 1);
+
+        let mut calls = 0;
+        let out = policy
+            .run("test", || {
+                calls += 1;
+                async { Err::<(), _>(db_error("08006")) }
+            })
+            .await;
+        assert!(out.is_err());
+        assert_eq!(calls, 3);
+    }
+}
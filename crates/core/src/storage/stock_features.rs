@@ -1,7 +1,9 @@
 use crate::ingest::types::DailyFeatureItem;
+use crate::storage::retry::with_db_retry;
 use anyhow::Context;
 use chrono::{DateTime, NaiveDate, Utc};
 use serde_json::Value;
+use std::collections::BTreeMap;
 use uuid::Uuid;
 
 pub async fn upsert_daily_features_atomic(
@@ -11,11 +13,109 @@ pub async fn upsert_daily_features_atomic(
 ) -> anyhow::Result<u64> {
     anyhow::ensure!(!items.is_empty(), "items must be non-empty");
 
-    let mut tx = pool.begin().await.context("begin transaction failed")?;
+    let counts = with_db_retry("upsert_daily_features_atomic", || async {
+        let mut tx = pool.begin().await.context("begin transaction failed")?;
+        let counts =
+            upsert_daily_features(&mut tx, as_of_date, items, UpsertMode::Overwrite).await?;
+        tx.commit().await.context("commit transaction failed")?;
+        Ok(counts)
+    })
+    .await?;
+    Ok(counts.affected())
+}
+
+/// Like [`upsert_daily_features_atomic`], but stored rows whose columns all equal the new ones
+/// are left untouched (no new row version, no WAL, `updated_at` kept), so re-ingesting a mostly
+/// unchanged date writes only what changed.
+pub async fn upsert_daily_features_incremental(
+    pool: &sqlx::PgPool,
+    as_of_date: NaiveDate,
+    items: &[DailyFeatureItem],
+) -> anyhow::Result<UpsertCounts> {
+    anyhow::ensure!(!items.is_empty(), "items must be non-empty");
 
+    with_db_retry("upsert_daily_features_incremental", || async {
+        let mut tx = pool.begin().await.context("begin transaction failed")?;
+        let counts =
+            upsert_daily_features(&mut tx, as_of_date, items, UpsertMode::Incremental).await?;
+        tx.commit().await.context("commit transaction failed")?;
+        Ok(counts)
+    })
+    .await
+}
+
+/// How [`upsert_daily_features`] treats a row that is already stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpsertMode {
+    /// Always rewrite it.
+    Overwrite,
+    /// Rewrite it only when a column differs.
+    Incremental,
+}
+
+/// Rows an upsert inserted, updated, and (incremental only) left as they were.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UpsertCounts {
+    pub inserted: u64,
+    pub updated: u64,
+    pub unchanged: u64,
+    /// How the rows were written; [`bulk_load_daily_features`] may fall back to batches.
+    pub path: LoadPath,
+}
+
+impl UpsertCounts {
+    /// Rows written.
+    pub fn affected(&self) -> u64 {
+        self.inserted + self.updated
+    }
+}
+
+/// How an upsert reached `stock_features_daily`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LoadPath {
+    /// `INSERT ... ON CONFLICT` per `STOCK_FEATURES_UPSERT_BATCH` rows.
+    #[default]
+    Batched,
+    /// COPY into a temp table and one merge.
+    Copy,
+}
+
+impl LoadPath {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LoadPath::Batched => "batched",
+            LoadPath::Copy => "copy",
+        }
+    }
+}
+
+/// The upsert's conflict action, shared by the batched and COPY paths.
+const ON_CONFLICT_UPDATE: &str = " ON CONFLICT (as_of_date, ticker) DO UPDATE \
+       SET name = EXCLUDED.name, name_en = EXCLUDED.name_en, \
+           instrument_type = EXCLUDED.instrument_type, sector = EXCLUDED.sector, \
+           trading_value = EXCLUDED.trading_value, close = EXCLUDED.close, \
+           market_cap = EXCLUDED.market_cap, status_flags = EXCLUDED.status_flags, \
+           features = EXCLUDED.features, updated_at = now()";
+
+/// Appended to [`ON_CONFLICT_UPDATE`] in [`UpsertMode::Incremental`].
+const UNLESS_UNCHANGED: &str = " WHERE (stock_features_daily.name, stock_features_daily.name_en, \
+             stock_features_daily.instrument_type, stock_features_daily.sector, \
+             stock_features_daily.trading_value, stock_features_daily.close, \
+             stock_features_daily.market_cap, stock_features_daily.status_flags, \
+             stock_features_daily.features) \
+       IS DISTINCT FROM (EXCLUDED.name, EXCLUDED.name_en, EXCLUDED.instrument_type, \
+             EXCLUDED.sector, EXCLUDED.trading_value, EXCLUDED.close, \
+             EXCLUDED.market_cap, EXCLUDED.status_flags, EXCLUDED.features)";
+
+/// Batched `stock_features_daily` upsert on `conn`; the caller owns the transaction.
+pub(crate) async fn upsert_daily_features(
+    conn: &mut sqlx::PgConnection,
+    as_of_date: NaiveDate,
+    items: &[DailyFeatureItem],
+    mode: UpsertMode,
+) -> anyhow::Result<UpsertCounts> {
     // Batch the upsert to reduce round trips (critical for CI runners / remote DB).
-    // Keep it transactional.
-    let mut affected: u64 = 0;
+    let mut counts = UpsertCounts::default();
     let chunk_size: usize = std::env::var("STOCK_FEATURES_UPSERT_BATCH")
         .ok()
         .and_then(|s| s.parse::<usize>().ok())
@@ -28,7 +128,9 @@ pub async fn upsert_daily_features_atomic(
         batch_idx += 1;
         let t0 = std::time::Instant::now();
         let mut qb = sqlx::QueryBuilder::new(
-            "INSERT INTO stock_features_daily (as_of_date, ticker, name, trading_value, features) ",
+            "INSERT INTO stock_features_daily \
+             (as_of_date, ticker, name, name_en, instrument_type, sector, trading_value, \
+              close, market_cap, status_flags, features) ",
         );
         qb.push_values(chunk, |mut b, item| {
             // This should not fail because features are numeric-only (enforced upstream).
@@ -36,61 +138,916 @@ pub async fn upsert_daily_features_atomic(
             b.push_bind(as_of_date)
                 .push_bind(item.ticker.trim())
                 .push_bind(item.name.trim())
+                .push_bind(item.name_en.as_deref().map(str::trim))
+                .push_bind(item.instrument_type.as_deref())
+                .push_bind(item.sector.as_deref())
                 .push_bind(item.trading_value)
+                .push_bind(item.close)
+                // Mirrored into a column so the universe can filter on it in SQL.
+                .push_bind(item.features.get("market_cap").copied())
+                .push_bind(status_flags(&item.features))
                 .push_bind(features);
         });
-        qb.push(
-            " ON CONFLICT (as_of_date, ticker) DO UPDATE \
-               SET name = EXCLUDED.name, trading_value = EXCLUDED.trading_value, features = EXCLUDED.features",
-        );
+        qb.push(ON_CONFLICT_UPDATE);
+        if mode == UpsertMode::Incremental {
+            qb.push(UNLESS_UNCHANGED);
+        }
+        // xmax is 0 on a freshly inserted row version; rows the WHERE skipped return nothing.
+        qb.push(" RETURNING (xmax = 0)");
 
-        let res = qb
-            .build()
+        let written: Vec<bool> = qb
+            .build_query_scalar()
             .persistent(false)
-            .execute(&mut *tx)
+            .fetch_all(&mut *conn)
             .await
             .context("batch upsert stock_features_daily failed")?;
-        affected += res.rows_affected();
+        let inserted = written.iter().filter(|inserted| **inserted).count();
+        counts.inserted += inserted as u64;
+        counts.updated += (written.len() - inserted) as u64;
+        counts.unchanged += (chunk.len() - written.len()) as u64;
 
         tracing::debug!(
             %as_of_date,
             batch_idx,
             batch_size = chunk.len(),
+            written = written.len(),
             elapsed_ms = t0.elapsed().as_millis(),
             "stock_features_daily batch upsert"
         );
     }
+    Ok(counts)
+}
+
+/// Rows per COPY data message in [`bulk_load_daily_features`].
+const COPY_ROWS_PER_SEND: usize = 1000;
+
+/// SQLSTATEs a connection answers COPY with when it cannot do COPY at all: `0A000`
+/// (feature_not_supported) and `08P01` (protocol_violation, PgBouncer's "unsupported pkt type"
+/// in transaction mode).
+const COPY_UNSUPPORTED_CODES: &[&str] = &["0A000", "08P01"];
+
+/// Whether staging failed because the connection rejects COPY, rather than for a reason the
+/// batched path would hit too.
+fn copy_unsupported(err: &anyhow::Error) -> bool {
+    err.chain()
+        .find_map(|e| e.downcast_ref::<sqlx::Error>())
+        .is_some_and(|e| match e {
+            sqlx::Error::Database(db) => db
+                .code()
+                .is_some_and(|code| COPY_UNSUPPORTED_CODES.contains(&code.as_ref())),
+            _ => false,
+        })
+}
+
+/// [`upsert_daily_features`] for large loads: streams the rows over the COPY protocol into a
+/// temp table and merges them with a single `INSERT ... SELECT ... ON CONFLICT`, instead of one
+/// round trip per `STOCK_FEATURES_UPSERT_BATCH` rows. Where the connection rejects COPY (see
+/// [`COPY_UNSUPPORTED_CODES`]) the rows go through the batched path instead, with a warning;
+/// [`UpsertCounts::path`] says which ran. Any other staging error is returned.
+pub async fn bulk_load_daily_features(
+    pool: &sqlx::PgPool,
+    as_of_date: NaiveDate,
+    items: &[DailyFeatureItem],
+    mode: UpsertMode,
+) -> anyhow::Result<UpsertCounts> {
+    anyhow::ensure!(!items.is_empty(), "items must be non-empty");
+    with_db_retry("bulk_load_daily_features", || {
+        bulk_load_once(pool, as_of_date, items, mode)
+    })
+    .await
+}
+
+async fn bulk_load_once(
+    pool: &sqlx::PgPool,
+    as_of_date: NaiveDate,
+    items: &[DailyFeatureItem],
+    mode: UpsertMode,
+) -> anyhow::Result<UpsertCounts> {
+    let t0 = std::time::Instant::now();
+    let mut tx = pool.begin().await.context("begin transaction failed")?;
+    if let Err(err) = stage_with_copy(&mut tx, items).await {
+        // Anything else (a dropped connection, bad data) is not a lack of COPY support; it is
+        // retried as a whole or returned.
+        if !copy_unsupported(&err) {
+            return Err(err);
+        }
+        tracing::warn!(%as_of_date, error = %format!("{err:#}"), "connection rejected COPY; falling back to batched upsert");
+        tx.rollback().await.ok();
+        let mut tx = pool.begin().await.context("begin transaction failed")?;
+        let counts = upsert_daily_features(&mut tx, as_of_date, items, mode).await?;
+        tx.commit().await.context("commit transaction failed")?;
+        return Ok(counts);
+    }
+    let copy_ms = t0.elapsed().as_millis();
 
+    let t1 = std::time::Instant::now();
+    let mut merge = String::from(
+        "INSERT INTO stock_features_daily \
+         (as_of_date, ticker, name, name_en, instrument_type, sector, trading_value, \
+          close, market_cap, status_flags, features) \
+         SELECT $1, ticker, name, name_en, instrument_type, sector, trading_value, \
+                close, market_cap, status_flags, features \
+         FROM stock_features_stage",
+    );
+    merge.push_str(ON_CONFLICT_UPDATE);
+    if mode == UpsertMode::Incremental {
+        merge.push_str(UNLESS_UNCHANGED);
+    }
+    merge.push_str(" RETURNING (xmax = 0)");
+    let written: Vec<bool> = sqlx::query_scalar(&merge)
+        .persistent(false)
+        .bind(as_of_date)
+        .fetch_all(&mut *tx)
+        .await
+        .context("merge staged stock_features_daily rows failed")?;
     tx.commit().await.context("commit transaction failed")?;
-    Ok(affected)
+
+    let inserted = written.iter().filter(|inserted| **inserted).count();
+    let counts = UpsertCounts {
+        inserted: inserted as u64,
+        updated: (written.len() - inserted) as u64,
+        unchanged: (items.len() - written.len()) as u64,
+        path: LoadPath::Copy,
+    };
+    tracing::debug!(
+        %as_of_date,
+        rows = items.len(),
+        copy_ms,
+        merge_ms = t1.elapsed().as_millis(),
+        "stock_features_daily COPY load"
+    );
+    Ok(counts)
+}
+
+/// Creates the transaction's `stock_features_stage` temp table and COPYs `items` into it.
+async fn stage_with_copy(
+    conn: &mut sqlx::PgConnection,
+    items: &[DailyFeatureItem],
+) -> anyhow::Result<()> {
+    sqlx::query(
+        "CREATE TEMP TABLE stock_features_stage \
+         (ticker text, name text, name_en text, instrument_type text, sector text, \
+          trading_value double precision, close double precision, market_cap double precision, \
+          status_flags text[], features jsonb) \
+         ON COMMIT DROP",
+    )
+    .persistent(false)
+    .execute(&mut *conn)
+    .await
+    .context("create stock_features_stage failed")?;
+
+    let mut copy = conn
+        .copy_in_raw("COPY stock_features_stage FROM STDIN (FORMAT csv)")
+        .await
+        .context("COPY stock_features_stage failed")?;
+    for chunk in items.chunks(COPY_ROWS_PER_SEND) {
+        let sent = copy.send(copy_csv(chunk).into_bytes()).await.map(|_| ());
+        if let Err(err) = sent {
+            copy.abort("send failed").await.ok();
+            return Err(anyhow::Error::new(err).context("COPY stock_features_stage failed"));
+        }
+    }
+    copy.finish()
+        .await
+        .context("COPY stock_features_stage failed")?;
+    Ok(())
 }
 
-pub async fn record_ingest_run(
+/// `items` as `COPY ... (FORMAT csv)` lines in `stock_features_stage` column order, with the
+/// same trimming and derived columns as the batched path. Text is always quoted, so only an
+/// absent value becomes NULL.
+fn copy_csv(items: &[DailyFeatureItem]) -> String {
+    let text = |s: &str| format!("\"{}\"", s.replace('"', "\"\""));
+    let opt_text = |s: Option<&str>| s.map(text).unwrap_or_default();
+    let number = |v: Option<f64>| v.map(|v| v.to_string()).unwrap_or_default();
+    let mut out = String::new();
+    for item in items {
+        let features = serde_json::to_string(&item.features).expect("features serialize failed");
+        let fields = [
+            text(item.ticker.trim()),
+            text(item.name.trim()),
+            opt_text(item.name_en.as_deref().map(str::trim)),
+            opt_text(item.instrument_type.as_deref()),
+            opt_text(item.sector.as_deref()),
+            number(item.trading_value),
+            number(item.close),
+            number(item.features.get("market_cap").copied()),
+            text(&format!("{{{}}}", status_flags(&item.features).join(","))),
+            text(&features),
+        ];
+        out.push_str(&fields.join(","));
+        out.push('\n');
+    }
+    out
+}
+
+/// Exchange flags that keep a stock out of the candidate universe, from the ingest's features:
+/// `managed` (관리종목), `halted`, and `warning` (market warning level 2+, 투자경고/위험; plain
+/// 투자주의 stays a feature only).
+pub fn status_flags(features: &BTreeMap<String, f64>) -> Vec<&'static str> {
+    let set = |key: &str| features.get(key).is_some_and(|v| *v > 0.0);
+    let mut out = Vec::new();
+    if set("is_administrative") {
+        out.push("managed");
+    }
+    if set("is_halted") {
+        out.push("halted");
+    }
+    if features.get("market_warning").is_some_and(|v| *v >= 2.0) {
+        out.push("warning");
+    }
+    out
+}
+
+/// What identifies an ingested payload: the SHA-256 of its canonical form
+/// ([`DailyFeaturesResponse::digest`](crate::ingest::types::DailyFeaturesResponse::digest)) and
+/// the provider's ETag, when it sent one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PayloadFingerprint {
+    pub digest: String,
+    pub etag: Option<String>,
+}
+
+/// Inserts a `running` ingest run for `as_of_date`, before the fetch, and returns its id. A run
+/// that never reaches [`finish_ingest_run`] (the worker crashed) stays `running` until `--prune`
+/// marks it `abandoned`.
+pub async fn start_ingest_run(
     pool: &sqlx::PgPool,
     as_of_date: NaiveDate,
     provider: &str,
-    status: &str,
-    error: Option<&str>,
-    raw_response: Option<Value>,
 ) -> anyhow::Result<Uuid> {
     let id = Uuid::new_v4();
-    let generated_at: DateTime<Utc> = Utc::now();
+    let started_at: DateTime<Utc> = Utc::now();
 
     sqlx::query(
-        "INSERT INTO stock_features_ingest_runs (id, as_of_date, generated_at, provider, status, error, raw_response) \
-         VALUES ($1, $2, $3, $4, $5, $6, $7)",
+        "INSERT INTO stock_features_ingest_runs \
+         (id, as_of_date, generated_at, started_at, provider, status) \
+         VALUES ($1, $2, $3, $3, $4, 'running')",
     )
     .persistent(false)
     .bind(id)
     .bind(as_of_date)
-    .bind(generated_at)
+    .bind(started_at)
     .bind(provider)
+    .execute(pool)
+    .await
+    .context("insert stock_features_ingest_runs failed")?;
+
+    Ok(id)
+}
+
+/// Records the outcome of the run [`start_ingest_run`] returned `id` for, and its finish time.
+pub async fn finish_ingest_run(
+    pool: &sqlx::PgPool,
+    id: Uuid,
+    status: &str,
+    error: Option<&str>,
+    raw_response: Option<Value>,
+    fingerprint: Option<&PayloadFingerprint>,
+) -> anyhow::Result<()> {
+    let res = sqlx::query(
+        "UPDATE stock_features_ingest_runs \
+         SET status = $2, error = $3, raw_response = $4, payload_digest = $5, \
+             payload_etag = $6, finished_at = now() \
+         WHERE id = $1",
+    )
+    .persistent(false)
+    .bind(id)
     .bind(status)
     .bind(error)
     .bind(raw_response)
+    .bind(fingerprint.map(|f| f.digest.as_str()))
+    .bind(fingerprint.and_then(|f| f.etag.as_deref()))
     .execute(pool)
     .await
-    .context("insert stock_features_ingest_runs failed")?;
+    .context("update stock_features_ingest_runs failed")?;
+    anyhow::ensure!(res.rows_affected() == 1, "ingest run {id} not found");
+    Ok(())
+}
 
-    Ok(id)
+/// The fingerprint of the payload behind the date's rows: that of its latest `success` run, when
+/// `provider` ran it. `None` when another provider wrote the rows last, or the run predates
+/// fingerprints.
+pub async fn current_payload_fingerprint(
+    pool: &sqlx::PgPool,
+    as_of_date: NaiveDate,
+    provider: &str,
+) -> anyhow::Result<Option<PayloadFingerprint>> {
+    let row = sqlx::query_as::<_, (String, Option<String>, Option<String>)>(
+        "SELECT provider, payload_digest, payload_etag FROM stock_features_ingest_runs \
+         WHERE as_of_date = $1 AND status = 'success' \
+         ORDER BY generated_at DESC LIMIT 1",
+    )
+    .persistent(false)
+    .bind(as_of_date)
+    .fetch_optional(pool)
+    .await
+    .context("select ingest run payload digest failed")?;
+    Ok(match row {
+        Some((p, Some(digest), etag)) if p == provider => Some(PayloadFingerprint { digest, etag }),
+        _ => None,
+    })
+}
+
+pub const DEFAULT_INGEST_RUNS_LIMIT: i64 = 20;
+pub const MAX_INGEST_RUNS_LIMIT: i64 = 100;
+/// `error` is cut to this many characters in listings; the detail query returns it in full.
+pub const INGEST_RUN_ERROR_PREVIEW_CHARS: i32 = 500;
+
+/// One row of the ingest run history (no `raw_response`).
+#[derive(Debug, Clone, PartialEq, serde::Serialize, utoipa::ToSchema)]
+pub struct IngestRunSummary {
+    pub id: Uuid,
+    pub as_of_date: NaiveDate,
+    pub generated_at: DateTime<Utc>,
+    pub provider: String,
+    /// `running` until the run finishes; `abandoned` when it never did.
+    pub status: String,
+    /// Truncated to `INGEST_RUN_ERROR_PREVIEW_CHARS`.
+    pub error: Option<String>,
+    /// `None` for runs recorded before start/finish timing.
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+    /// `finished_at - started_at`, when both are known.
+    pub duration_ms: Option<i64>,
+}
+
+/// A full `stock_features_ingest_runs` row.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, utoipa::ToSchema)]
+pub struct IngestRun {
+    pub id: Uuid,
+    pub as_of_date: NaiveDate,
+    pub generated_at: DateTime<Utc>,
+    pub provider: String,
+    pub status: String,
+    pub error: Option<String>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub duration_ms: Option<i64>,
+    #[schema(value_type = Option<Object>)]
+    pub raw_response: Option<Value>,
+}
+
+type IngestRunRow = (
+    Uuid,
+    NaiveDate,
+    DateTime<Utc>,
+    String,
+    String,
+    Option<String>,
+    Option<DateTime<Utc>>,
+    Option<DateTime<Utc>>,
+    Option<i64>,
+);
+
+/// `duration_ms` of an ingest run row.
+const INGEST_RUN_DURATION_MS: &str =
+    "(EXTRACT(EPOCH FROM finished_at - started_at) * 1000)::bigint";
+
+/// Most recent ingest runs first, optionally filtered by `provider` and `status`. `limit` is
+/// clamped to `1..=MAX_INGEST_RUNS_LIMIT`.
+pub async fn list_ingest_runs(
+    pool: &sqlx::PgPool,
+    provider: Option<&str>,
+    status: Option<&str>,
+    limit: i64,
+) -> anyhow::Result<Vec<IngestRunSummary>> {
+    let limit = limit.clamp(1, MAX_INGEST_RUNS_LIMIT);
+
+    let rows = sqlx::query_as::<_, IngestRunRow>(&format!(
+        "SELECT id, as_of_date, generated_at, provider, status, left(error, $4), started_at, \
+                finished_at, {INGEST_RUN_DURATION_MS} \
+         FROM stock_features_ingest_runs \
+         WHERE ($1::text IS NULL OR provider = $1) AND ($2::text IS NULL OR status = $2) \
+         ORDER BY generated_at DESC \
+         LIMIT $3"
+    ))
+    .persistent(false)
+    .bind(provider)
+    .bind(status)
+    .bind(limit)
+    .bind(INGEST_RUN_ERROR_PREVIEW_CHARS)
+    .fetch_all(pool)
+    .await
+    .context("list stock_features_ingest_runs failed")?;
+
+    Ok(rows
+        .into_iter()
+        .map(
+            |(
+                id,
+                as_of_date,
+                generated_at,
+                provider,
+                status,
+                error,
+                started_at,
+                finished_at,
+                duration_ms,
+            )| IngestRunSummary {
+                id,
+                as_of_date,
+                generated_at,
+                provider,
+                status,
+                error,
+                started_at,
+                finished_at,
+                duration_ms,
+            },
+        )
+        .collect())
+}
+
+pub async fn fetch_ingest_run(pool: &sqlx::PgPool, id: Uuid) -> anyhow::Result<Option<IngestRun>> {
+    let row = sqlx::query_as::<
+        _,
+        (
+            Uuid,
+            NaiveDate,
+            DateTime<Utc>,
+            String,
+            String,
+            Option<String>,
+            Option<DateTime<Utc>>,
+            Option<DateTime<Utc>>,
+            Option<i64>,
+            Option<Value>,
+        ),
+    >(&format!(
+        "SELECT id, as_of_date, generated_at, provider, status, error, started_at, finished_at, \
+                {INGEST_RUN_DURATION_MS}, raw_response \
+         FROM stock_features_ingest_runs \
+         WHERE id = $1"
+    ))
+    .persistent(false)
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+    .context("fetch stock_features_ingest_runs failed")?;
+
+    Ok(row.map(
+        |(
+            id,
+            as_of_date,
+            generated_at,
+            provider,
+            status,
+            error,
+            started_at,
+            finished_at,
+            duration_ms,
+            raw_response,
+        )| IngestRun {
+            id,
+            as_of_date,
+            generated_at,
+            provider,
+            status,
+            error,
+            started_at,
+            finished_at,
+            duration_ms,
+            raw_response,
+        },
+    ))
+}
+
+/// Most tickers accepted by [`fetch_daily_features_batch`].
+pub const MAX_FEATURE_BATCH: usize = 50;
+
+/// Numeric entries of a `features` JSONB object. Anything else (strings, nulls, nested
+/// values, or a non-object) is skipped rather than failing the read.
+pub fn json_to_feature_map(v: Value) -> BTreeMap<String, f64> {
+    let mut out = BTreeMap::new();
+    let obj = match v {
+        Value::Object(o) => o,
+        _ => return out,
+    };
+
+    for (k, val) in obj {
+        if let Some(n) = val.as_f64() {
+            out.insert(k, n);
+        }
+    }
+
+    out
+}
+
+type FeatureRow = (
+    String,
+    String,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    Option<f64>,
+    Option<f64>,
+    Value,
+);
+
+pub async fn fetch_daily_features(
+    pool: &sqlx::PgPool,
+    as_of_date: NaiveDate,
+    ticker: &str,
+) -> anyhow::Result<Option<DailyFeatureItem>> {
+    let row = sqlx::query_as::<_, FeatureRow>(
+        "SELECT ticker, name, name_en, instrument_type, sector, trading_value, close, features \
+         FROM stock_features_daily \
+         WHERE as_of_date = $1 AND ticker = $2",
+    )
+    .persistent(false)
+    .bind(as_of_date)
+    .bind(ticker)
+    .fetch_optional(pool)
+    .await
+    .context("select stock_features_daily failed")?;
+
+    Ok(row.map(
+        |(ticker, name, name_en, instrument_type, sector, trading_value, close, features)| {
+            DailyFeatureItem {
+                ticker,
+                name,
+                name_en,
+                instrument_type,
+                sector,
+                trading_value,
+                close,
+                features: json_to_feature_map(features),
+            }
+        },
+    ))
+}
+
+/// Rows for the given tickers on `as_of_date`, ordered by ticker; absent tickers are skipped.
+pub async fn fetch_daily_features_batch(
+    pool: &sqlx::PgPool,
+    as_of_date: NaiveDate,
+    tickers: &[String],
+) -> anyhow::Result<Vec<DailyFeatureItem>> {
+    anyhow::ensure!(
+        tickers.len() <= MAX_FEATURE_BATCH,
+        "at most {MAX_FEATURE_BATCH} tickers per batch (got {})",
+        tickers.len()
+    );
+
+    let rows = sqlx::query_as::<_, FeatureRow>(
+        "SELECT ticker, name, name_en, instrument_type, sector, trading_value, close, features \
+         FROM stock_features_daily \
+         WHERE as_of_date = $1 AND ticker = ANY($2) \
+         ORDER BY ticker ASC",
+    )
+    .persistent(false)
+    .bind(as_of_date)
+    .bind(tickers)
+    .fetch_all(pool)
+    .await
+    .context("select stock_features_daily batch failed")?;
+
+    Ok(rows
+        .into_iter()
+        .map(
+            |(ticker, name, name_en, instrument_type, sector, trading_value, close, features)| {
+                DailyFeatureItem {
+                    ticker,
+                    name,
+                    name_en,
+                    instrument_type,
+                    sector,
+                    trading_value,
+                    close,
+                    features: json_to_feature_map(features),
+                }
+            },
+        )
+        .collect())
+}
+
+/// `stock_features_daily` row counts per date in `from..=to`; dates without rows are absent.
+pub async fn count_features_by_date(
+    pool: &sqlx::PgPool,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> anyhow::Result<BTreeMap<NaiveDate, i64>> {
+    let rows = sqlx::query_as::<_, (NaiveDate, i64)>(
+        "SELECT as_of_date, COUNT(*) FROM stock_features_daily \
+         WHERE as_of_date BETWEEN $1 AND $2 \
+         GROUP BY as_of_date",
+    )
+    .persistent(false)
+    .bind(from)
+    .bind(to)
+    .fetch_all(pool)
+    .await
+    .context("count stock_features_daily by date failed")?;
+    Ok(rows.into_iter().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_flags_follow_the_exchange_features() {
+        let features = |pairs: &[(&str, f64)]| {
+            pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), *v))
+                .collect::<BTreeMap<_, _>>()
+        };
+        assert!(status_flags(&features(&[("ret_1d", 0.1)])).is_empty());
+        // Investment caution alone is not a flag.
+        assert!(status_flags(&features(&[("market_warning", 1.0)])).is_empty());
+        assert_eq!(
+            status_flags(&features(&[
+                ("is_administrative", 1.0),
+                ("is_halted", 1.0),
+                ("market_warning", 3.0),
+            ])),
+            ["managed", "halted", "warning"]
+        );
+    }
+
+    #[tokio::test]
+    async fn incremental_upsert_leaves_unchanged_rows_alone() {
+        let Some(pool) = crate::storage::test_support::database("incremental upsert test").await
+        else {
+            return;
+        };
+        let as_of = NaiveDate::from_ymd_opt(2031, 5, 7).unwrap();
+        sqlx::query("DELETE FROM stock_features_daily WHERE as_of_date = $1")
+            .bind(as_of)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let item = |ticker: &str, ret_1d: f64| DailyFeatureItem {
+            ticker: ticker.to_string(),
+            name: ticker.to_string(),
+            name_en: None,
+            instrument_type: None,
+            sector: None,
+            trading_value: Some(1e9),
+            close: None,
+            features: BTreeMap::from([("ret_1d".to_string(), ret_1d)]),
+        };
+        let updated_at = || async {
+            sqlx::query_as::<_, (String, DateTime<Utc>)>(
+                "SELECT ticker, updated_at FROM stock_features_daily \
+                 WHERE as_of_date = $1 ORDER BY ticker",
+            )
+            .bind(as_of)
+            .fetch_all(&pool)
+            .await
+            .unwrap()
+        };
+
+        let first = [item("KRX:9U0001", 0.01), item("KRX:9U0002", 0.02)];
+        let counts = upsert_daily_features_incremental(&pool, as_of, &first)
+            .await
+            .unwrap();
+        assert_eq!(
+            counts,
+            UpsertCounts {
+                inserted: 2,
+                updated: 0,
+                unchanged: 0,
+                path: LoadPath::Batched,
+            }
+        );
+        let before = updated_at().await;
+
+        let second = [
+            item("KRX:9U0001", 0.01),
+            item("KRX:9U0002", 0.03),
+            item("KRX:9U0003", 0.04),
+        ];
+        let counts = upsert_daily_features_incremental(&pool, as_of, &second)
+            .await
+            .unwrap();
+        assert_eq!(
+            counts,
+            UpsertCounts {
+                inserted: 1,
+                updated: 1,
+                unchanged: 1,
+                path: LoadPath::Batched,
+            }
+        );
+        assert_eq!(counts.affected(), 2);
+        let after = updated_at().await;
+        assert_eq!(after[0], before[0]);
+        assert!(after[1].1 > before[1].1);
+        assert_eq!(after.len(), 3);
+
+        // The overwriting upsert still rewrites every row.
+        assert_eq!(
+            upsert_daily_features_atomic(&pool, as_of, &second)
+                .await
+                .unwrap(),
+            3
+        );
+        assert!(updated_at().await[0].1 > before[0].1);
+    }
+
+    #[test]
+    fn copy_rows_quote_text_and_leave_absent_values_null() {
+        let item = DailyFeatureItem {
+            ticker: " KRX:005930 ".to_string(),
+            name: "Samsung \"Pref\", Co\nLtd".to_string(),
+            name_en: Some(String::new()),
+            instrument_type: Some("stock".to_string()),
+            sector: None,
+            trading_value: Some(1.5e9),
+            close: None,
+            features: BTreeMap::from([
+                ("is_halted".to_string(), 1.0),
+                ("market_cap".to_string(), 4e14),
+            ]),
+        };
+        assert_eq!(
+            copy_csv(&[item]),
+            "\"KRX:005930\",\"Samsung \"\"Pref\"\", Co\nLtd\",\"\",\"stock\",,1500000000,,\
+             400000000000000,\"{halted}\",\"{\"\"is_halted\"\":1.0,\"\"market_cap\"\":400000000000000.0}\"\n"
+        );
+    }
+
+    #[test]
+    fn only_a_rejected_copy_falls_back_to_batches() {
+        use crate::storage::test_support::db_error;
+
+        assert!(copy_unsupported(&db_error("0A000")));
+        assert!(copy_unsupported(&db_error("08P01")));
+        // Bad rows, a missing table and lost connections fail the load instead.
+        assert!(!copy_unsupported(&db_error("22P02")));
+        assert!(!copy_unsupported(&db_error("42P01")));
+        assert!(!copy_unsupported(&db_error("57P01")));
+        let closed = std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "connection closed");
+        assert!(!copy_unsupported(&anyhow::Error::new(sqlx::Error::Io(
+            closed
+        ))));
+    }
+
+    #[tokio::test]
+    async fn copy_load_matches_the_batched_upsert() {
+        let Some(pool) = crate::storage::test_support::database("COPY load test").await else {
+            return;
+        };
+        let batched = NaiveDate::from_ymd_opt(2031, 5, 9).unwrap();
+        let copied = NaiveDate::from_ymd_opt(2031, 5, 12).unwrap();
+        sqlx::query("DELETE FROM stock_features_daily WHERE as_of_date = ANY($1)")
+            .bind(vec![batched, copied])
+            .execute(&pool)
+            .await
+            .unwrap();
+        let items: Vec<DailyFeatureItem> = (0..2500)
+            .map(|i| DailyFeatureItem {
+                ticker: format!("KRX:9C{i:04}"),
+                name: format!("Stock \"{i}\", Inc\t"),
+                name_en: (i % 3 == 0).then(|| format!(" Stock {i} ")),
+                instrument_type: Some("stock".to_string()),
+                sector: (i % 2 == 0).then(|| "반도체".to_string()),
+                trading_value: (i % 5 != 0).then_some(1e9 / (i + 1) as f64),
+                close: (i % 7 != 0).then_some(10_000.0 + i as f64 * 0.1),
+                features: BTreeMap::from([
+                    ("ret_1d".to_string(), 0.001 * i as f64),
+                    ("market_cap".to_string(), 1e12 + i as f64),
+                    ("is_halted".to_string(), (i % 11 == 0) as u8 as f64),
+                ]),
+            })
+            .collect();
+        let rows = |as_of_date| {
+            let pool = pool.clone();
+            async move {
+                sqlx::query_as::<_, FullRow>(
+                    "SELECT ticker, name, name_en, instrument_type, sector, trading_value, close, \
+                            market_cap, status_flags, features \
+                     FROM stock_features_daily WHERE as_of_date = $1 ORDER BY ticker",
+                )
+                .bind(as_of_date)
+                .fetch_all(&pool)
+                .await
+                .unwrap()
+            }
+        };
+        type FullRow = (
+            String,
+            String,
+            Option<String>,
+            Option<String>,
+            Option<String>,
+            Option<f64>,
+            Option<f64>,
+            Option<f64>,
+            Vec<String>,
+            Value,
+        );
+
+        upsert_daily_features_atomic(&pool, batched, &items)
+            .await
+            .unwrap();
+        let counts = bulk_load_daily_features(&pool, copied, &items, UpsertMode::Incremental)
+            .await
+            .unwrap();
+        assert_eq!(counts.inserted, 2500);
+        assert_eq!(counts.path, LoadPath::Copy);
+        let expected = rows(batched).await;
+        assert_eq!(expected.len(), 2500);
+        assert_eq!(rows(copied).await, expected);
+
+        let mut changed = items.clone();
+        changed[1].close = Some(1.0);
+        let counts = bulk_load_daily_features(&pool, copied, &changed, UpsertMode::Incremental)
+            .await
+            .unwrap();
+        assert_eq!(
+            counts,
+            UpsertCounts {
+                inserted: 0,
+                updated: 1,
+                unchanged: 2499,
+                path: LoadPath::Copy,
+            }
+        );
+        let counts = bulk_load_daily_features(&pool, copied, &changed, UpsertMode::Overwrite)
+            .await
+            .unwrap();
+        assert_eq!(counts.updated, 2500);
+    }
+
+    #[tokio::test]
+    async fn ingest_runs_are_timed_from_start_to_finish() {
+        let Some(pool) = crate::storage::test_support::database("ingest run timing test").await
+        else {
+            return;
+        };
+        let as_of = NaiveDate::from_ymd_opt(2031, 5, 8).unwrap();
+
+        let id = start_ingest_run(&pool, as_of, "kis").await.unwrap();
+        let running = fetch_ingest_run(&pool, id).await.unwrap().unwrap();
+        assert_eq!(running.status, "running");
+        assert_eq!(running.started_at, Some(running.generated_at));
+        assert_eq!((running.finished_at, running.duration_ms), (None, None));
+
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        finish_ingest_run(&pool, id, "error", Some("Io: reset"), None, None)
+            .await
+            .unwrap();
+        let run = fetch_ingest_run(&pool, id).await.unwrap().unwrap();
+        assert_eq!(run.status, "error");
+        assert_eq!(run.error.as_deref(), Some("Io: reset"));
+        assert!(run.finished_at > run.started_at);
+        assert!(run.duration_ms.unwrap() >= 20, "{:?}", run.duration_ms);
+
+        let listed = list_ingest_runs(&pool, Some("kis"), Some("error"), 100)
+            .await
+            .unwrap();
+        let summary = listed.iter().find(|r| r.id == id).unwrap();
+        assert_eq!(summary.duration_ms, run.duration_ms);
+
+        assert!(
+            finish_ingest_run(&pool, Uuid::new_v4(), "success", None, None, None)
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn payload_fingerprint_of_the_latest_success_run() {
+        let Some(pool) = crate::storage::test_support::database("payload fingerprint test").await
+        else {
+            return;
+        };
+        let as_of = NaiveDate::from_ymd_opt(2031, 5, 6).unwrap();
+        sqlx::query("DELETE FROM stock_features_ingest_runs WHERE as_of_date = $1")
+            .bind(as_of)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let fp = |digest: &str| PayloadFingerprint {
+            digest: digest.to_string(),
+            etag: Some(format!("\"{digest}\"")),
+        };
+        let current = || current_payload_fingerprint(&pool, as_of, "external_http_json");
+
+        let record =
+            |provider: &'static str, status: &'static str, fp: Option<PayloadFingerprint>| {
+                let pool = pool.clone();
+                async move {
+                    let id = start_ingest_run(&pool, as_of, provider).await.unwrap();
+                    finish_ingest_run(&pool, id, status, None, None, fp.as_ref())
+                        .await
+                        .unwrap();
+                }
+            };
+
+        assert_eq!(current().await.unwrap(), None);
+        record("external_http_json", "success", Some(fp("a"))).await;
+        // Failed and duplicate runs do not change what the rows came from.
+        record("external_http_json", "error", None).await;
+        record("external_http_json", "skipped_duplicate", Some(fp("b"))).await;
+        assert_eq!(current().await.unwrap(), Some(fp("a")));
+
+        // Another provider overwrote the rows since.
+        record("kis", "success", None).await;
+        assert_eq!(current().await.unwrap(), None);
+    }
 }
@@ -0,0 +1,28 @@
+use crate::notify::WebhookAttempt;
+use anyhow::Context;
+
+/// Audit rows for the webhook attempts made for `snapshot_id`.
+pub async fn record_webhook_deliveries(
+    pool: &sqlx::PgPool,
+    snapshot_id: uuid::Uuid,
+    attempts: &[WebhookAttempt],
+) -> anyhow::Result<()> {
+    for attempt in attempts {
+        sqlx::query(
+            "INSERT INTO webhook_deliveries (snapshot_id, url_index, url, attempt_no, status_code, error, delivered) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7)",
+        )
+        .persistent(false)
+        .bind(snapshot_id)
+        .bind(attempt.url_index as i32)
+        .bind(&attempt.url)
+        .bind(attempt.attempt_no as i32)
+        .bind(attempt.status_code.map(i32::from))
+        .bind(&attempt.error)
+        .bind(attempt.delivered)
+        .execute(pool)
+        .await
+        .context("insert webhook_deliveries failed")?;
+    }
+    Ok(())
+}
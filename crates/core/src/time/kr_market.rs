@@ -24,48 +24,77 @@ pub fn resolve_as_of_date(
         (now_kst.hour(), now_kst.minute()) >= (CLOSE_CUTOFF_HOUR_KST, CLOSE_CUTOFF_MINUTE_KST);
     let mut date = now_kst.date_naive();
     if !cutoff_reached {
-        date = date - Duration::days(1);
+        date -= Duration::days(1);
     }
 
     // Roll back to previous business day.
     let holidays = configured_holidays();
     while is_weekend(date) || holidays.contains(&date) {
-        date = date - Duration::days(1);
+        date -= Duration::days(1);
     }
 
     Ok(date)
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use chrono::TimeZone;
+/// Whether KRX trades on `date` (not a weekend or configured holiday).
+pub fn is_trading_day(date: NaiveDate) -> bool {
+    !is_weekend(date) && !configured_holidays().contains(&date)
+}
 
-    #[test]
-    fn rolls_back_on_weekend() {
-        // 2026-01-03 is Saturday.
-        let now = Utc.with_ymd_and_hms(2026, 1, 3, 8, 0, 0).unwrap();
-        let d = resolve_as_of_date(None, now).unwrap();
-        // Before cutoff, base is 2026-01-02 (Friday) and weekend rollback shouldn't change it.
-        assert_eq!(d, NaiveDate::from_ymd_opt(2026, 1, 2).unwrap());
-    }
+/// The calendar date in KST at `now_utc`, without the close cutoff or holiday rollback of
+/// [`resolve_as_of_date`].
+pub fn today_kst(now_utc: DateTime<Utc>) -> anyhow::Result<NaiveDate> {
+    let kst = chrono::FixedOffset::east_opt(KST_OFFSET_SECS).context("invalid KST offset")?;
+    Ok(now_utc.with_timezone(&kst).date_naive())
+}
 
-    #[test]
-    fn uses_previous_day_before_cutoff() {
-        // 2026-01-05 06:00 UTC = 15:00 KST (<16:00 cutoff)
-        let now = Utc.with_ymd_and_hms(2026, 1, 5, 6, 0, 0).unwrap();
-        let d = resolve_as_of_date(None, now).unwrap();
-        // Rolls back to Sunday, then to Friday.
-        assert_eq!(d, NaiveDate::from_ymd_opt(2026, 1, 2).unwrap());
+/// The first trading day's `hour:minute` KST strictly after `now_utc`.
+pub fn next_trading_time_kst(
+    now_utc: DateTime<Utc>,
+    hour: u32,
+    minute: u32,
+) -> anyhow::Result<DateTime<Utc>> {
+    let kst = chrono::FixedOffset::east_opt(KST_OFFSET_SECS).context("invalid KST offset")?;
+    let holidays = configured_holidays();
+    let mut date = now_utc.with_timezone(&kst).date_naive();
+    // Long holiday runs (e.g. Chuseok plus a weekend) are well under a month.
+    for _ in 0..31 {
+        if !is_weekend(date) && !holidays.contains(&date) {
+            let at = date
+                .and_hms_opt(hour, minute, 0)
+                .and_then(|t| t.and_local_timezone(kst).single())
+                .with_context(|| format!("invalid KST schedule time {hour:02}:{minute:02}"))?
+                .with_timezone(&Utc);
+            if at > now_utc {
+                return Ok(at);
+            }
+        }
+        date += Duration::days(1);
     }
+    anyhow::bail!("no trading day within 31 days of {now_utc}")
+}
 
-    #[test]
-    fn uses_same_day_after_cutoff() {
-        // 2026-01-05 08:00 UTC = 17:00 KST (>=16:00 cutoff)
-        let now = Utc.with_ymd_and_hms(2026, 1, 5, 8, 0, 0).unwrap();
-        let d = resolve_as_of_date(None, now).unwrap();
-        assert_eq!(d, NaiveDate::from_ymd_opt(2026, 1, 5).unwrap());
+/// Trading days in `from..=to` (inclusive), skipping weekends and configured holidays.
+pub fn trading_days(from: NaiveDate, to: NaiveDate) -> Vec<NaiveDate> {
+    let holidays = configured_holidays();
+    from.iter_days()
+        .take_while(|d| *d <= to)
+        .filter(|d| !is_weekend(*d) && !holidays.contains(d))
+        .collect()
+}
+
+/// The `n`th trading day after `date` (`n = 1` is the next trading day).
+pub fn nth_trading_day_after(date: NaiveDate, n: u32) -> NaiveDate {
+    let holidays = configured_holidays();
+    let mut day = date;
+    let mut left = n;
+    while left > 0 {
+        day += Duration::days(1);
+        if !is_weekend(day) && !holidays.contains(&day) {
+            left -= 1;
+        }
     }
+    day
 }
 
 fn is_weekend(date: NaiveDate) -> bool {
@@ -100,3 +129,92 @@ fn configured_holidays() -> HashSet<NaiveDate> {
 
     out
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn rolls_back_on_weekend() {
+        // 2026-01-03 is Saturday.
+        let now = Utc.with_ymd_and_hms(2026, 1, 3, 8, 0, 0).unwrap();
+        let d = resolve_as_of_date(None, now).unwrap();
+        // Before cutoff, base is 2026-01-02 (Friday) and weekend rollback shouldn't change it.
+        assert_eq!(d, NaiveDate::from_ymd_opt(2026, 1, 2).unwrap());
+    }
+
+    #[test]
+    fn uses_previous_day_before_cutoff() {
+        // 2026-01-05 06:00 UTC = 15:00 KST (<16:00 cutoff)
+        let now = Utc.with_ymd_and_hms(2026, 1, 5, 6, 0, 0).unwrap();
+        let d = resolve_as_of_date(None, now).unwrap();
+        // Rolls back to Sunday, then to Friday.
+        assert_eq!(d, NaiveDate::from_ymd_opt(2026, 1, 2).unwrap());
+    }
+
+    #[test]
+    fn uses_same_day_after_cutoff() {
+        // 2026-01-05 08:00 UTC = 17:00 KST (>=16:00 cutoff)
+        let now = Utc.with_ymd_and_hms(2026, 1, 5, 8, 0, 0).unwrap();
+        let d = resolve_as_of_date(None, now).unwrap();
+        assert_eq!(d, NaiveDate::from_ymd_opt(2026, 1, 5).unwrap());
+    }
+
+    #[test]
+    fn weekends_and_holidays_are_not_trading_days() {
+        assert!(is_trading_day(NaiveDate::from_ymd_opt(2026, 1, 2).unwrap()));
+        assert!(!is_trading_day(
+            NaiveDate::from_ymd_opt(2026, 1, 3).unwrap()
+        ));
+        assert!(!is_trading_day(
+            NaiveDate::from_ymd_opt(2026, 1, 1).unwrap()
+        ));
+        // 2026-12-24 23:30 UTC is already Christmas in KST.
+        let now = Utc.with_ymd_and_hms(2026, 12, 24, 23, 30, 0).unwrap();
+        assert_eq!(
+            today_kst(now).unwrap(),
+            NaiveDate::from_ymd_opt(2026, 12, 25).unwrap()
+        );
+    }
+
+    #[test]
+    fn next_trading_time_skips_weekends_and_passed_times() {
+        // Friday 2026-01-02 08:00 UTC = 17:00 KST, after the 16:20 slot -> Monday 16:20 KST.
+        let now = Utc.with_ymd_and_hms(2026, 1, 2, 8, 0, 0).unwrap();
+        let next = next_trading_time_kst(now, 16, 20).unwrap();
+        assert_eq!(next, Utc.with_ymd_and_hms(2026, 1, 5, 7, 20, 0).unwrap());
+
+        // Monday 2026-01-05 06:00 UTC = 15:00 KST -> same day.
+        let now = Utc.with_ymd_and_hms(2026, 1, 5, 6, 0, 0).unwrap();
+        let next = next_trading_time_kst(now, 16, 20).unwrap();
+        assert_eq!(next, Utc.with_ymd_and_hms(2026, 1, 5, 7, 20, 0).unwrap());
+
+        // 2025-12-31 after the slot -> skips the 2026-01-01 holiday.
+        let now = Utc.with_ymd_and_hms(2025, 12, 31, 9, 0, 0).unwrap();
+        let next = next_trading_time_kst(now, 16, 20).unwrap();
+        assert_eq!(next, Utc.with_ymd_and_hms(2026, 1, 2, 7, 20, 0).unwrap());
+        assert!(!is_trading_day(
+            NaiveDate::from_ymd_opt(2026, 1, 1).unwrap()
+        ));
+    }
+
+    #[test]
+    fn trading_days_skip_weekends_and_holidays() {
+        let d = |day| NaiveDate::from_ymd_opt(2025, 12, day).unwrap();
+        // 2025-12-25 is a holiday; 12-27/28 are a weekend.
+        assert_eq!(trading_days(d(24), d(29)), vec![d(24), d(26), d(29)]);
+        assert_eq!(trading_days(d(27), d(28)), Vec::<NaiveDate>::new());
+        assert_eq!(trading_days(d(29), d(24)), Vec::<NaiveDate>::new());
+    }
+
+    #[test]
+    fn nth_trading_day_skips_weekends_and_holidays() {
+        let d = |m, day| NaiveDate::from_ymd_opt(2025, m, day).unwrap();
+        // Wednesday 12-24: Thursday 12-25 is a holiday, then Friday, then the weekend.
+        assert_eq!(nth_trading_day_after(d(12, 24), 1), d(12, 26));
+        assert_eq!(nth_trading_day_after(d(12, 24), 2), d(12, 29));
+        assert_eq!(nth_trading_day_after(d(12, 24), 0), d(12, 24));
+        assert_eq!(nth_trading_day_after(d(12, 1), 5), d(12, 8));
+    }
+}
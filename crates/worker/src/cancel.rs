@@ -0,0 +1,398 @@
+//! `--max-runtime-secs` and ctrl-c/SIGTERM for one-shot runs. An interrupted pipeline future is
+//! dropped mid-flight, so whatever it has open (the as_of_date lock connection, a run or ingest
+//! that has not recorded its outcome yet) is registered in [`IN_FLIGHT`] and closed out by
+//! [`cleanup`] instead.
+
+use chrono::NaiveDate;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::Duration;
+use tootoo_core::llm::error::LlmFailureKind;
+use uuid::Uuid;
+
+/// Why a run was cut short. Also the error the run ends with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interrupt {
+    Timeout(Duration),
+    Signal,
+}
+
+impl Interrupt {
+    /// `error_kind` for the failure snapshot of an interrupted recommendation run.
+    fn failure_kind(self) -> Option<LlmFailureKind> {
+        match self {
+            Self::Timeout(_) => Some(LlmFailureKind::Deadline),
+            Self::Signal => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Interrupt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Timeout(d) => write!(f, "timeout: run exceeded {}s max runtime", d.as_secs()),
+            Self::Signal => f.write_str("interrupted by shutdown signal"),
+        }
+    }
+}
+
+impl std::error::Error for Interrupt {}
+
+/// `--max-runtime-secs`, else `WORKER_MAX_RUNTIME_SECS`; unset or 0 means no limit.
+pub fn max_runtime(flag: Option<u64>) -> Option<Duration> {
+    flag.or_else(|| {
+        std::env::var("WORKER_MAX_RUNTIME_SECS")
+            .ok()
+            .and_then(|s| s.trim().parse().ok())
+    })
+    .filter(|secs| *secs > 0)
+    .map(Duration::from_secs)
+}
+
+/// Runs `fut` until it completes, `max_runtime` elapses or `shutdown` resolves, whichever is
+/// first. On interruption `fut` is dropped before this returns.
+pub async fn run_until<F: Future>(
+    fut: F,
+    max_runtime: Option<Duration>,
+    shutdown: impl Future<Output = ()>,
+) -> Result<F::Output, Interrupt> {
+    let deadline = async {
+        match max_runtime {
+            Some(d) => tokio::time::sleep(d).await,
+            None => std::future::pending().await,
+        }
+    };
+    tokio::select! {
+        out = fut => Ok(out),
+        () = deadline => Err(Interrupt::Timeout(max_runtime.unwrap_or_default())),
+        () = shutdown => Err(Interrupt::Signal),
+    }
+}
+
+/// Work the current run has started but not finished. `C` is the lock connection type.
+#[derive(Debug)]
+pub struct InFlight<C> {
+    lock: Option<(NaiveDate, C)>,
+    /// Recommendation run without a persisted outcome: `(as_of_date, llm provider)`.
+    run: Option<(NaiveDate, &'static str)>,
+    /// Ingest whose `running` ingest run has no outcome yet: `(as_of_date, data provider, run
+    /// id)`.
+    ingest: Option<(NaiveDate, &'static str, Uuid)>,
+}
+
+impl<C> InFlight<C> {
+    pub const fn new() -> Self {
+        Self {
+            lock: None,
+            run: None,
+            ingest: None,
+        }
+    }
+
+    fn begin_run(&mut self, as_of_date: NaiveDate, provider: &'static str) {
+        self.run = Some((as_of_date, provider));
+    }
+
+    /// The run's outcome is persisted; nothing is left to record on an interrupt.
+    fn end_run(&mut self) {
+        self.run = None;
+    }
+}
+
+type LockConn = tootoo_core::storage::lock::AsOfDateLockGuard;
+
+pub static IN_FLIGHT: Mutex<InFlight<LockConn>> = Mutex::new(InFlight::new());
+
+fn in_flight() -> std::sync::MutexGuard<'static, InFlight<LockConn>> {
+    IN_FLIGHT.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Parks the advisory-lock connection so [`cleanup`] can release it.
+pub fn hold_lock(as_of_date: NaiveDate, conn: LockConn) {
+    in_flight().lock = Some((as_of_date, conn));
+}
+
+pub fn take_lock() -> Option<LockConn> {
+    in_flight().lock.take().map(|(_, conn)| conn)
+}
+
+/// Marks a recommendation run that has no persisted outcome yet. Only set once the run will
+/// write one (after the existing-success check).
+pub fn begin_run(as_of_date: NaiveDate, provider: &'static str) {
+    in_flight().begin_run(as_of_date, provider);
+}
+
+/// Clears the marker as soon as the outcome is persisted, before notifications, so an interrupt
+/// during them does not add a failure snapshot for a date that already has its outcome.
+pub fn end_run() {
+    in_flight().end_run();
+}
+
+pub fn begin_ingest(as_of_date: NaiveDate, provider: &'static str, run_id: Uuid) {
+    in_flight().ingest = Some((as_of_date, provider, run_id));
+}
+
+pub fn end_ingest() {
+    in_flight().ingest = None;
+}
+
+/// Side effects of [`cleanup`]; the worker's implementation is [`PgCleanup`].
+#[async_trait::async_trait]
+pub trait CleanupStore {
+    type Conn: Send;
+
+    async fn record_failed_run(
+        &self,
+        as_of_date: NaiveDate,
+        provider: &str,
+        error: &str,
+        error_kind: Option<LlmFailureKind>,
+    ) -> anyhow::Result<()>;
+
+    async fn record_failed_ingest(
+        &self,
+        as_of_date: NaiveDate,
+        provider: &str,
+        run_id: Uuid,
+        error: &str,
+    ) -> anyhow::Result<()>;
+
+    async fn release_lock(&self, conn: Self::Conn, as_of_date: NaiveDate) -> anyhow::Result<()>;
+}
+
+/// Records unfinished work as failed with `interrupt` as the error, then releases the lock (so
+/// the next run to take it sees the failure). Failures are logged, not returned: the run is
+/// already failing with `interrupt`.
+pub async fn cleanup<S: CleanupStore>(
+    store: &S,
+    in_flight: &Mutex<InFlight<S::Conn>>,
+    interrupt: Interrupt,
+) {
+    let (lock, run, ingest) = {
+        let mut state = in_flight.lock().unwrap_or_else(|e| e.into_inner());
+        (state.lock.take(), state.run.take(), state.ingest.take())
+    };
+    let error = interrupt.to_string();
+
+    if let Some((as_of_date, provider)) = run {
+        match store
+            .record_failed_run(as_of_date, provider, &error, interrupt.failure_kind())
+            .await
+        {
+            Ok(()) => tracing::info!(%as_of_date, %error, "recorded interrupted run as failed"),
+            Err(e) => {
+                tracing::warn!(%as_of_date, error = %e, "failed to record interrupted run")
+            }
+        }
+    }
+    if let Some((as_of_date, provider, run_id)) = ingest {
+        match store
+            .record_failed_ingest(as_of_date, provider, run_id, &error)
+            .await
+        {
+            Ok(()) => tracing::info!(%as_of_date, provider, %error, "recorded interrupted ingest"),
+            Err(e) => {
+                tracing::warn!(%as_of_date, error = %e, "failed to record interrupted ingest")
+            }
+        }
+    }
+    if let Some((as_of_date, conn)) = lock {
+        match store.release_lock(conn, as_of_date).await {
+            Ok(()) => tracing::info!(%as_of_date, "released as_of_date lock after interrupt"),
+            Err(e) => tracing::warn!(%as_of_date, error = %e, "failed to release as_of_date lock"),
+        }
+    }
+}
+
+pub struct PgCleanup {
+    pub pool: sqlx::PgPool,
+}
+
+#[async_trait::async_trait]
+impl CleanupStore for PgCleanup {
+    type Conn = LockConn;
+
+    async fn record_failed_run(
+        &self,
+        as_of_date: NaiveDate,
+        provider: &str,
+        error: &str,
+        error_kind: Option<LlmFailureKind>,
+    ) -> anyhow::Result<()> {
+        tootoo_core::storage::recommendations::persist_failure(
+            &self.pool,
+            as_of_date,
+            chrono::Utc::now(),
+            provider,
+            None,
+            error,
+            error_kind,
+            None,
+        )
+        .await
+        .map(|_| ())
+    }
+
+    async fn record_failed_ingest(
+        &self,
+        _as_of_date: NaiveDate,
+        _provider: &str,
+        run_id: Uuid,
+        error: &str,
+    ) -> anyhow::Result<()> {
+        tootoo_core::storage::stock_features::finish_ingest_run(
+            &self.pool,
+            run_id,
+            "error",
+            Some(error),
+            None,
+            None,
+        )
+        .await
+    }
+
+    async fn release_lock(&self, lock: Self::Conn, _as_of_date: NaiveDate) -> anyhow::Result<()> {
+        lock.release().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct FakeStore {
+        calls: Mutex<Vec<String>>,
+    }
+
+    #[async_trait::async_trait]
+    impl CleanupStore for FakeStore {
+        type Conn = u32;
+
+        async fn record_failed_run(
+            &self,
+            as_of_date: NaiveDate,
+            provider: &str,
+            error: &str,
+            error_kind: Option<LlmFailureKind>,
+        ) -> anyhow::Result<()> {
+            let kind = error_kind.map_or("-", LlmFailureKind::as_str);
+            self.calls
+                .lock()
+                .unwrap()
+                .push(format!("run {as_of_date} {provider} {kind} {error}"));
+            Ok(())
+        }
+
+        async fn record_failed_ingest(
+            &self,
+            as_of_date: NaiveDate,
+            provider: &str,
+            run_id: Uuid,
+            error: &str,
+        ) -> anyhow::Result<()> {
+            self.calls
+                .lock()
+                .unwrap()
+                .push(format!("ingest {as_of_date} {provider} {run_id} {error}"));
+            Ok(())
+        }
+
+        async fn release_lock(&self, conn: u32, as_of_date: NaiveDate) -> anyhow::Result<()> {
+            self.calls
+                .lock()
+                .unwrap()
+                .push(format!("unlock {as_of_date} conn={conn}"));
+            Ok(())
+        }
+    }
+
+    fn date() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 1, 27).unwrap()
+    }
+
+    #[tokio::test]
+    async fn cleanup_records_failures_before_releasing_the_lock() {
+        let state = Mutex::new(InFlight {
+            lock: Some((date(), 7)),
+            run: Some((date(), "anthropic")),
+            ingest: Some((date(), "kis", Uuid::from_u128(9))),
+        });
+        let store = FakeStore::default();
+        cleanup(&store, &state, Interrupt::Timeout(Duration::from_secs(900))).await;
+
+        assert_eq!(
+            *store.calls.lock().unwrap(),
+            vec![
+                "run 2026-01-27 anthropic deadline timeout: run exceeded 900s max runtime",
+                "ingest 2026-01-27 kis 00000000-0000-0000-0000-000000000009 timeout: run exceeded 900s max runtime",
+                "unlock 2026-01-27 conn=7",
+            ]
+        );
+        let state = state.lock().unwrap();
+        assert!(state.lock.is_none() && state.run.is_none() && state.ingest.is_none());
+    }
+
+    #[tokio::test]
+    async fn interrupted_after_persist_records_nothing() {
+        let state = Mutex::new(InFlight {
+            lock: Some((date(), 7)),
+            run: None,
+            ingest: None,
+        });
+        state.lock().unwrap().begin_run(date(), "anthropic");
+        // The outcome is persisted, then the notifications hang past the deadline.
+        state.lock().unwrap().end_run();
+        let limit = Duration::from_millis(1);
+        let interrupt = run_until(
+            std::future::pending::<()>(),
+            Some(limit),
+            std::future::pending(),
+        )
+        .await
+        .unwrap_err();
+
+        let store = FakeStore::default();
+        cleanup(&store, &state, interrupt).await;
+        assert_eq!(
+            *store.calls.lock().unwrap(),
+            vec!["unlock 2026-01-27 conn=7"]
+        );
+    }
+
+    #[tokio::test]
+    async fn cleanup_without_in_flight_work_is_a_no_op() {
+        let state = Mutex::new(InFlight::<u32>::new());
+        let store = FakeStore::default();
+        cleanup(&store, &state, Interrupt::Signal).await;
+        assert!(store.calls.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn run_until_reports_which_branch_won() {
+        assert_eq!(
+            run_until(async { 1 }, None, std::future::pending()).await,
+            Ok(1)
+        );
+        assert_eq!(
+            run_until(std::future::pending::<()>(), None, async {}).await,
+            Err(Interrupt::Signal)
+        );
+        let limit = Duration::from_millis(1);
+        assert_eq!(
+            run_until(
+                std::future::pending::<()>(),
+                Some(limit),
+                std::future::pending()
+            )
+            .await,
+            Err(Interrupt::Timeout(limit))
+        );
+    }
+
+    #[test]
+    fn flag_overrides_env_and_zero_disables() {
+        assert_eq!(max_runtime(Some(60)), Some(Duration::from_secs(60)));
+        assert_eq!(max_runtime(Some(0)), None);
+    }
+}
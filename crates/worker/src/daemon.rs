@@ -0,0 +1,123 @@
+use anyhow::Context;
+use chrono::Timelike;
+use std::time::Duration;
+
+/// Settings for `--daemon` mode, read from `WORKER_DAEMON_*`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DaemonOptions {
+    /// Scheduled run time (KST) on each trading day.
+    pub hour: u32,
+    pub minute: u32,
+    /// Extra attempts after a failed run before waiting for the next scheduled day.
+    pub max_retries: u32,
+    /// Base spacing between retries; each retry adds up to half of it as jitter.
+    pub retry_spacing: Duration,
+}
+
+impl Default for DaemonOptions {
+    fn default() -> Self {
+        Self {
+            hour: 16,
+            minute: 20,
+            max_retries: 3,
+            retry_spacing: Duration::from_secs(300),
+        }
+    }
+}
+
+impl DaemonOptions {
+    pub fn from_env() -> anyhow::Result<Self> {
+        let mut out = Self::default();
+
+        if let Ok(s) = std::env::var("WORKER_DAEMON_SCHEDULE_KST") {
+            if !s.trim().is_empty() {
+                (out.hour, out.minute) = parse_schedule(&s)?;
+            }
+        }
+
+        if let Ok(s) = std::env::var("WORKER_DAEMON_MAX_RETRIES") {
+            if let Ok(n) = s.parse::<u32>() {
+                out.max_retries = n;
+            }
+        }
+
+        if let Ok(s) = std::env::var("WORKER_DAEMON_RETRY_SECS") {
+            if let Ok(n) = s.parse::<u64>() {
+                out.retry_spacing = Duration::from_secs(n);
+            }
+        }
+
+        Ok(out)
+    }
+
+    /// Delay before the next retry: `retry_spacing` plus up to half of it, so several workers
+    /// retrying the same failure do not hit the LLM provider in lockstep.
+    pub fn retry_delay(&self) -> Duration {
+        jittered(self.retry_spacing, uuid::Uuid::new_v4().as_u128() as u64)
+    }
+}
+
+/// `HH:MM` (24h) -> `(hour, minute)`.
+fn parse_schedule(s: &str) -> anyhow::Result<(u32, u32)> {
+    let time = chrono::NaiveTime::parse_from_str(s.trim(), "%H:%M")
+        .with_context(|| format!("WORKER_DAEMON_SCHEDULE_KST must be HH:MM, got {s:?}"))?;
+    Ok((time.hour(), time.minute()))
+}
+
+fn jittered(base: Duration, seed: u64) -> Duration {
+    let span = base.as_millis() as u64 / 2;
+    if span == 0 {
+        return base;
+    }
+    base + Duration::from_millis(seed % (span + 1))
+}
+
+/// Resolves on ctrl-c or (on unix) SIGTERM.
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        let _ = tokio::signal::ctrl_c().await;
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut sig) => {
+                sig.recv().await;
+            }
+            Err(e) => {
+                tracing::warn!(error = %e, "SIGTERM handler install failed; only ctrl-c stops the daemon");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_schedule() {
+        assert_eq!(parse_schedule("16:20").unwrap(), (16, 20));
+        assert_eq!(parse_schedule(" 07:05 ").unwrap(), (7, 5));
+        assert!(parse_schedule("25:00").is_err());
+        assert!(parse_schedule("1620").is_err());
+    }
+
+    #[test]
+    fn jitter_stays_within_half_the_spacing() {
+        let base = Duration::from_secs(300);
+        for seed in [0, 1, 149_999, 150_000, u64::MAX] {
+            let d = jittered(base, seed);
+            assert!(d >= base && d <= base + Duration::from_secs(150), "{d:?}");
+        }
+        assert_eq!(jittered(Duration::ZERO, 42), Duration::ZERO);
+    }
+}
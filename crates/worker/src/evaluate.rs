@@ -0,0 +1,115 @@
+use chrono::NaiveDate;
+use std::collections::BTreeMap;
+use tootoo_core::storage::outcomes::OutcomeSummary;
+use uuid::Uuid;
+
+/// A snapshot whose exit date for the horizon has features, so it can be evaluated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Target {
+    pub snapshot_id: Uuid,
+    pub as_of_date: NaiveDate,
+    pub exit_date: NaiveDate,
+}
+
+/// Splits `snapshots` into the ones ready for `horizon_days` (their exit date has feature rows
+/// in `features_rows`) and the number still waiting for it.
+pub fn plan(
+    snapshots: &[(Uuid, NaiveDate)],
+    horizon_days: u32,
+    features_rows: &BTreeMap<NaiveDate, i64>,
+) -> (Vec<Target>, usize) {
+    let mut ready = Vec::new();
+    let mut waiting = 0;
+    for (snapshot_id, as_of_date) in snapshots {
+        let exit_date =
+            tootoo_core::time::kr_market::nth_trading_day_after(*as_of_date, horizon_days);
+        if features_rows.get(&exit_date).is_some_and(|rows| *rows > 0) {
+            ready.push(Target {
+                snapshot_id: *snapshot_id,
+                as_of_date: *as_of_date,
+                exit_date,
+            });
+        } else {
+            waiting += 1;
+        }
+    }
+    (ready, waiting)
+}
+
+pub fn format_table(summaries: &[OutcomeSummary]) -> String {
+    let pct = |v: Option<f64>| v.map(|v| format!("{v:.2}")).unwrap_or_else(|| "-".into());
+    let mut out = format!(
+        "{:<10}  {:>7}  {:>6}  {:>8}  {:>15}\n",
+        "date", "horizon", "priced", "hit_rate", "mean_return_pct"
+    );
+    for s in summaries {
+        out.push_str(&format!(
+            "{}  {:>7}  {:>6}  {:>8}  {:>15}\n",
+            s.as_of_date,
+            s.horizon_days,
+            format!("{}/{}", s.priced, s.items),
+            pct(s.hit_rate),
+            pct(s.mean_return_pct),
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_snapshots_with_exit_features_are_ready() {
+        let d = |day| NaiveDate::from_ymd_opt(2026, 1, day).unwrap();
+        let snapshots = [
+            (Uuid::from_u128(1), d(2)),
+            (Uuid::from_u128(2), d(5)),
+            (Uuid::from_u128(3), d(9)),
+        ];
+        // Friday 01-02 exits on Monday 01-05; 01-05 on 01-06; 01-09 on 01-12, not ingested.
+        let features = BTreeMap::from([(d(5), 2500), (d(6), 2480), (d(12), 0)]);
+
+        let (ready, waiting) = plan(&snapshots, 1, &features);
+        assert_eq!(
+            ready,
+            vec![
+                Target {
+                    snapshot_id: Uuid::from_u128(1),
+                    as_of_date: d(2),
+                    exit_date: d(5),
+                },
+                Target {
+                    snapshot_id: Uuid::from_u128(2),
+                    as_of_date: d(5),
+                    exit_date: d(6),
+                },
+            ]
+        );
+        assert_eq!(waiting, 1);
+    }
+
+    #[test]
+    fn table_shows_missing_aggregates_as_dashes() {
+        let summary = |hit_rate, mean_return_pct| OutcomeSummary {
+            snapshot_id: Uuid::nil(),
+            as_of_date: NaiveDate::from_ymd_opt(2026, 1, 2).unwrap(),
+            horizon_days: 5,
+            items: 20,
+            priced: 19,
+            hit_rate,
+            mean_return_pct,
+        };
+        let table = format_table(&[summary(Some(0.55), Some(1.234)), summary(None, None)]);
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(
+            lines[1],
+            "2026-01-02        5   19/20      0.55             1.23"
+        );
+        assert!(
+            lines[2].ends_with("       -                -"),
+            "{}",
+            lines[2]
+        );
+    }
+}
@@ -0,0 +1,150 @@
+//! Process exit codes. Orchestration decides whether (and when) to retry from these, so a
+//! lock-contention no-op and a real failure must not share a code.
+
+use crate::full_run::InsufficientFeatureRows;
+use crate::universe::InsufficientUniverse;
+use crate::RunOutcome;
+
+/// Success, or a no-op because the date already has a success snapshot.
+pub const SUCCESS: u8 = 0;
+/// Any failure without a more specific code.
+pub const FAILURE: u8 = 1;
+/// Another run holds the as_of_date advisory lock.
+pub const LOCK_NOT_ACQUIRED: u8 = 2;
+/// Too few feature rows to build the candidate universe (or, with `--full-run`, after the
+/// ingest).
+pub const INSUFFICIENT_UNIVERSE: u8 = 3;
+/// The LLM run failed and the failure was persisted (only with `--fail-on-llm-error`).
+pub const LLM_FAILURE: u8 = 4;
+/// An `--ingest-*` run (or the ingest step of `--full-run`) failed.
+pub const INGEST_FAILURE: u8 = 5;
+/// The run's day is a weekend or holiday (without `--allow-non-trading-day`); nothing was done.
+pub const NON_TRADING_DAY: u8 = 6;
+/// The data provider failed its preflight health check; no date was fetched.
+pub const PROVIDER_UNAVAILABLE: u8 = 7;
+/// Bad flags, environment or settings; retrying will not help.
+pub const CONFIG_ERROR: u8 = 10;
+
+/// Context marker for errors that should exit with [`CONFIG_ERROR`]:
+/// `result.context(ConfigError)?`.
+#[derive(Debug)]
+pub struct ConfigError;
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("configuration error")
+    }
+}
+
+/// Context marker for a failed ingest step outside the `ingest_*` modes (`--full-run`), so it
+/// exits with [`INGEST_FAILURE`].
+#[derive(Debug)]
+pub struct IngestError;
+
+impl std::fmt::Display for IngestError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ingest failed")
+    }
+}
+
+/// Context marker for a failed provider preflight, so it exits with [`PROVIDER_UNAVAILABLE`]
+/// rather than [`INGEST_FAILURE`].
+#[derive(Debug)]
+pub struct PreflightError;
+
+impl std::fmt::Display for PreflightError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("data provider preflight failed")
+    }
+}
+
+pub fn for_outcome(outcome: &RunOutcome, fail_on_llm_error: bool) -> u8 {
+    match outcome {
+        RunOutcome::Persisted(_) | RunOutcome::Skipped => SUCCESS,
+        RunOutcome::Locked => LOCK_NOT_ACQUIRED,
+        RunOutcome::NoUniverse(_) => INSUFFICIENT_UNIVERSE,
+        RunOutcome::Failed { .. } if fail_on_llm_error => LLM_FAILURE,
+        RunOutcome::Failed { .. } => SUCCESS,
+    }
+}
+
+/// `mode` is [`crate::run_mode`]'s label for the run that produced `err`.
+pub fn for_error(err: &anyhow::Error, mode: &str) -> u8 {
+    if err.downcast_ref::<ConfigError>().is_some() {
+        CONFIG_ERROR
+    } else if err.downcast_ref::<InsufficientUniverse>().is_some()
+        || err.downcast_ref::<InsufficientFeatureRows>().is_some()
+    {
+        INSUFFICIENT_UNIVERSE
+    } else if err.downcast_ref::<PreflightError>().is_some() {
+        PROVIDER_UNAVAILABLE
+    } else if mode.starts_with("ingest_") || err.downcast_ref::<IngestError>().is_some() {
+        INGEST_FAILURE
+    } else {
+        FAILURE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn classifies_errors() {
+        let config = Err::<(), _>(anyhow::anyhow!("KIS_APPKEY is required"))
+            .context(ConfigError)
+            .context("build provider")
+            .unwrap_err();
+        assert_eq!(for_error(&config, "ingest_kis"), CONFIG_ERROR);
+
+        let universe = anyhow::Error::new(InsufficientUniverse {
+            as_of_date: chrono::NaiveDate::from_ymd_opt(2026, 1, 5).unwrap(),
+            expected: 200,
+            got: 12,
+        });
+        assert_eq!(for_error(&universe, "recommend"), INSUFFICIENT_UNIVERSE);
+
+        let rows = anyhow::Error::new(InsufficientFeatureRows {
+            as_of_date: chrono::NaiveDate::from_ymd_opt(2026, 1, 5).unwrap(),
+            min: 200,
+            got: 12,
+        });
+        assert_eq!(for_error(&rows, "full_run"), INSUFFICIENT_UNIVERSE);
+
+        let ingest = Err::<(), _>(anyhow::anyhow!("KIS 500"))
+            .context(IngestError)
+            .unwrap_err();
+        assert_eq!(for_error(&ingest, "full_run"), INGEST_FAILURE);
+
+        let preflight = Err::<(), _>(anyhow::anyhow!("connection refused"))
+            .context(PreflightError)
+            .context(IngestError)
+            .unwrap_err();
+        assert_eq!(
+            for_error(&preflight, "ingest_external"),
+            PROVIDER_UNAVAILABLE
+        );
+        assert_eq!(for_error(&preflight, "full_run"), PROVIDER_UNAVAILABLE);
+
+        let other = anyhow::anyhow!("connection reset");
+        assert_eq!(for_error(&other, "ingest_external"), INGEST_FAILURE);
+        assert_eq!(for_error(&other, "recommend"), FAILURE);
+    }
+
+    #[test]
+    fn maps_outcomes() {
+        let failed = RunOutcome::Failed {
+            snapshot_id: None,
+            error: "boom".to_string(),
+        };
+        assert_eq!(for_outcome(&failed, false), SUCCESS);
+        assert_eq!(for_outcome(&failed, true), LLM_FAILURE);
+        assert_eq!(for_outcome(&RunOutcome::Locked, false), LOCK_NOT_ACQUIRED);
+        assert_eq!(for_outcome(&RunOutcome::Skipped, true), SUCCESS);
+        assert_eq!(
+            for_outcome(&RunOutcome::NoUniverse("few rows".to_string()), false),
+            INSUFFICIENT_UNIVERSE
+        );
+    }
+}
@@ -0,0 +1,73 @@
+//! `--full-run`: ingest then recommend for one as_of_date under a single advisory lock, so the
+//! two steps cannot resolve different dates across the close cutoff.
+
+use chrono::NaiveDate;
+
+/// Minimum `stock_features_daily` rows after the ingest before the LLM is called:
+/// `--min-feature-rows`, else `WORKER_MIN_FEATURE_ROWS`, else `universe_size`.
+pub fn min_feature_rows(flag: Option<usize>, universe_size: usize) -> usize {
+    flag.or_else(|| {
+        std::env::var("WORKER_MIN_FEATURE_ROWS")
+            .ok()
+            .and_then(|s| s.trim().parse().ok())
+    })
+    .unwrap_or(universe_size)
+}
+
+/// The ingest left fewer feature rows for the date than the configured minimum.
+#[derive(Debug, PartialEq, Eq)]
+pub struct InsufficientFeatureRows {
+    pub as_of_date: NaiveDate,
+    pub min: usize,
+    pub got: usize,
+}
+
+impl std::fmt::Display for InsufficientFeatureRows {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "stock_features_daily has {} rows for as_of_date={} after ingest; need at least {}",
+            self.got, self.as_of_date, self.min
+        )
+    }
+}
+
+impl std::error::Error for InsufficientFeatureRows {}
+
+pub fn check_feature_rows(
+    as_of_date: NaiveDate,
+    rows: usize,
+    min: usize,
+) -> Result<(), InsufficientFeatureRows> {
+    if rows < min {
+        return Err(InsufficientFeatureRows {
+            as_of_date,
+            min,
+            got: rows,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rows_below_the_minimum_stop_the_run() {
+        let d = NaiveDate::from_ymd_opt(2026, 1, 5).unwrap();
+        assert_eq!(check_feature_rows(d, 2500, 200), Ok(()));
+        assert_eq!(check_feature_rows(d, 200, 200), Ok(()));
+        let err = check_feature_rows(d, 12, 200).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "stock_features_daily has 12 rows for as_of_date=2026-01-05 after ingest; need at least 200"
+        );
+    }
+
+    #[test]
+    fn flag_overrides_the_universe_size_default() {
+        assert_eq!(min_feature_rows(Some(1000), 200), 1000);
+        assert_eq!(min_feature_rows(Some(0), 200), 0);
+    }
+}
@@ -1,53 +1,127 @@
 use anyhow::Context;
-use chrono::{Datelike, NaiveDate};
-use serde_json::json;
-
-pub async fn ingest_stub_stock_features(
-    pool: &sqlx::PgPool,
-    as_of_date: NaiveDate,
-    size: usize,
-) -> anyhow::Result<u64> {
-    anyhow::ensure!(
-        (1..=5000).contains(&size),
-        "ingest size must be 1..=5000 (got {size})"
-    );
-
-    let mut tx = pool.begin().await.context("begin transaction failed")?;
-
-    let base = (as_of_date.num_days_from_ce() % 10_000) as f64;
-    let mut inserted: u64 = 0;
-
-    for i in 1..=size {
-        let ticker = format!("KRX:{i:06}");
-        let name = format!("Stub {i:06}");
-        let trading_value = ((size - i + 1) as f64) * 1.0e8;
-
-        // Compact numeric features only.
-        let features = json!({
-            "ret_1d": ((i as f64) % 200.0 - 100.0) / 1000.0,
-            "mom_5d": (base + (i as f64)) / 1000.0,
-            "vol_20d": ((i as f64) % 50.0) / 100.0,
-            "value_score": ((size - i + 1) as f64) / (size as f64),
-        });
-
-        let res = sqlx::query(
-            "INSERT INTO stock_features_daily (as_of_date, ticker, name, trading_value, features) \
-             VALUES ($1, $2, $3, $4, $5) \
-             ON CONFLICT (as_of_date, ticker) DO NOTHING",
-        )
-        .persistent(false)
-        .bind(as_of_date)
-        .bind(ticker)
-        .bind(name)
-        .bind(trading_value)
-        .bind(features)
-        .execute(&mut *tx)
-        .await
-        .context("insert stock_features_daily failed")?;
-
-        inserted += res.rows_affected();
+use chrono::NaiveDate;
+
+/// Why a date given to a multi-date ingest is not fetched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkipReason {
+    Duplicate,
+    NotTradingDay,
+}
+
+impl SkipReason {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Duplicate => "duplicate",
+            Self::NotTradingDay => "not_trading_day",
+        }
+    }
+}
+
+/// Rows a date needs before a multi-date ingest loads it over COPY, unless
+/// `STOCK_FEATURES_COPY_MIN_ROWS` says otherwise.
+pub const DEFAULT_COPY_MIN_ROWS: usize = 1000;
+
+/// `STOCK_FEATURES_COPY_MIN_ROWS`; `None` (set to `0`) keeps every date on the batched upsert.
+pub fn copy_min_rows() -> Option<usize> {
+    let rows = std::env::var("STOCK_FEATURES_COPY_MIN_ROWS")
+        .ok()
+        .and_then(|s| s.trim().parse::<usize>().ok())
+        .unwrap_or(DEFAULT_COPY_MIN_ROWS);
+    (rows > 0).then_some(rows)
+}
+
+/// `--dates-file`: one `YYYY-MM-DD` per line; blank lines and `#` comments are ignored.
+pub fn parse_dates_file(text: &str) -> anyhow::Result<Vec<NaiveDate>> {
+    text.lines()
+        .enumerate()
+        .map(|(idx, line)| (idx + 1, line.split('#').next().unwrap_or("").trim()))
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(lineno, line)| {
+            NaiveDate::parse_from_str(line, "%Y-%m-%d")
+                .with_context(|| format!("line {lineno}: invalid date {line:?}"))
+        })
+        .collect()
+}
+
+/// Splits the requested dates into those to ingest (first occurrence order) and those skipped.
+pub fn plan_ingest_dates(
+    requested: &[NaiveDate],
+) -> (Vec<NaiveDate>, Vec<(NaiveDate, SkipReason)>) {
+    let mut seen = std::collections::BTreeSet::new();
+    let mut dates = Vec::new();
+    let mut skipped = Vec::new();
+    for &date in requested {
+        if !seen.insert(date) {
+            skipped.push((date, SkipReason::Duplicate));
+        } else if !tootoo_core::time::kr_market::is_trading_day(date) {
+            skipped.push((date, SkipReason::NotTradingDay));
+        } else {
+            dates.push(date);
+        }
     }
+    (dates, skipped)
+}
 
-    tx.commit().await.context("commit transaction failed")?;
-    Ok(inserted)
+/// Per-date result of a multi-date ingest: upserted item count or the error.
+pub fn format_ingest_summary(results: &[(NaiveDate, Result<usize, String>)]) -> String {
+    let mut out = format!("{:<10}  {:<7}  {}\n", "date", "status", "items / error");
+    for (date, result) in results {
+        let (status, detail) = match result {
+            Ok(items) => ("success", items.to_string()),
+            Err(error) => ("error", error.clone()),
+        };
+        out.push_str(&format!("{date}  {status:<7}  {detail}\n"));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn d(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn parses_dates_file_with_comments() {
+        let text = "# January\n2026-01-02\n\n2026-01-05  # Monday\n";
+        assert_eq!(
+            parse_dates_file(text).unwrap(),
+            vec![d("2026-01-02"), d("2026-01-05")]
+        );
+        let err = parse_dates_file("2026-01-02\n2026/01/05\n").unwrap_err();
+        assert!(err.to_string().contains("line 2"), "{err}");
+    }
+
+    #[test]
+    fn skips_duplicates_and_non_trading_days() {
+        // 2026-01-03 is a Saturday.
+        let requested = [
+            d("2026-01-05"),
+            d("2026-01-02"),
+            d("2026-01-03"),
+            d("2026-01-05"),
+        ];
+        let (dates, skipped) = plan_ingest_dates(&requested);
+        assert_eq!(dates, vec![d("2026-01-05"), d("2026-01-02")]);
+        assert_eq!(
+            skipped,
+            vec![
+                (d("2026-01-03"), SkipReason::NotTradingDay),
+                (d("2026-01-05"), SkipReason::Duplicate),
+            ]
+        );
+    }
+
+    #[test]
+    fn summary_lists_items_or_error() {
+        let summary = format_ingest_summary(&[
+            (d("2026-01-02"), Ok(2480)),
+            (d("2026-01-05"), Err("HTTP 503".to_string())),
+        ]);
+        let lines: Vec<&str> = summary.lines().collect();
+        assert_eq!(lines[1], "2026-01-02  success  2480");
+        assert_eq!(lines[2], "2026-01-05  error    HTTP 503");
+    }
 }
@@ -1,25 +1,50 @@
 use anyhow::Context;
+use chrono::NaiveDate;
 use clap::Parser;
 use sqlx::postgres::PgConnectOptions;
 use std::str::FromStr;
-use tootoo_core::ingest::provider::DataProviderClient;
+use tootoo_core::domain::universe::{ExportFormat, UniverseExport};
+use tootoo_core::ingest::error::IngestError;
+use tootoo_core::ingest::provider::{DataProviderClient, Fetched};
+use tootoo_core::metrics::{
+    as_of_date_label, INGEST_FAILURES_TOTAL, INGEST_ITEMS_TOTAL, WORKER_RUN_DURATION_SECONDS,
+};
+use tootoo_core::report::{DateReport, Phase, RunReport, RunStatus};
+use tootoo_core::storage::lock::AsOfDateLockGuard;
+use tootoo_core::storage::stock_features::{PayloadFingerprint, UpsertCounts, UpsertMode};
 use tracing_subscriber::EnvFilter;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
+mod cancel;
+mod daemon;
+mod evaluate;
+mod exit;
+mod full_run;
 mod ingest;
+mod notifier;
+mod pending;
+mod report;
+mod trading_day;
 mod universe;
 
 #[derive(Debug, Parser)]
 #[command(name = "tootoo_worker")]
+#[command(group(clap::ArgGroup::new("ingest_source").args(["ingest_external", "ingest_kis"]).multiple(true)))]
 struct Args {
     /// Market as-of date (YYYY-MM-DD). Defaults to resolved KR market date (KST, close cutoff).
     #[arg(long)]
     as_of_date: Option<String>,
 
-    /// Do everything except writing to the database.
-    #[arg(long)]
+    /// Build the real candidate universe and print the exact prompt payload (system prompt, user
+    /// prompt, tool schema, token estimate) without calling the LLM or writing to the database.
+    /// With --ingest-kis, fetch a capped KIS universe and print it instead.
+    #[arg(long, alias = "print-prompt")]
     dry_run: bool,
 
+    /// Write the --dry-run prompt payload to this file instead of stdout.
+    #[arg(long, requires = "dry_run")]
+    out: Option<std::path::PathBuf>,
+
     /// Seed stock_features_daily with deterministic stub rows for the resolved as_of_date.
     #[arg(long)]
     ingest_features: bool,
@@ -28,229 +53,1988 @@ struct Args {
     #[arg(long)]
     ingest_external: bool,
 
-    /// Fetch stock_features_daily from KIS (Korea Investment) OpenAPI and upsert into DB.
-    #[arg(long)]
-    ingest_kis: bool,
+    /// Data provider for --ingest-external (`http-json` | `kis` | `file` | `csv` | `object-store`).
+    #[arg(long, default_value = "http-json", requires = "ingest_external")]
+    provider: String,
+
+    /// Saved `DailyFeaturesResponse` JSON to replay with `--provider file`, or a directory of
+    /// per-date `YYYY-MM-DD.csv` files for `--provider csv` (or `file`).
+    #[arg(long, requires = "ingest_external")]
+    provider_file: Option<std::path::PathBuf>,
+
+    /// Ingest these dates (comma-separated YYYY-MM-DD) with --ingest-external/--ingest-kis over
+    /// one connection and provider, one ingest run per date. Duplicates and non-trading days are
+    /// skipped with a warning.
+    #[arg(
+        long,
+        value_delimiter = ',',
+        requires = "ingest_source",
+        conflicts_with_all = ["as_of_date", "dates_file", "dry_run"]
+    )]
+    as_of_dates: Vec<NaiveDate>,
+
+    /// Like --as-of-dates, read from a file with one date per line (`#` starts a comment).
+    #[arg(
+        long,
+        requires = "ingest_source",
+        conflicts_with_all = ["as_of_date", "dry_run"]
+    )]
+    dates_file: Option<std::path::PathBuf>,
+
+    /// Ingest (with --ingest-external or --ingest-kis) and then generate the recommendation for
+    /// one resolved as_of_date under a single advisory lock. A failed ingest, or fewer than
+    /// --min-feature-rows rows afterwards, stops before the LLM call.
+    #[arg(
+        long,
+        requires = "ingest_source",
+        conflicts_with_all = ["as_of_dates", "dates_file", "dry_run", "ingest_features"]
+    )]
+    full_run: bool,
+
+    /// Minimum stock_features_daily rows for the date after the --full-run ingest (default
+    /// `WORKER_MIN_FEATURE_ROWS`, else the universe size).
+    #[arg(long, requires = "full_run")]
+    min_feature_rows: Option<usize>,
+
+    /// Fetch stock_features_daily from KIS (Korea Investment) OpenAPI and upsert into DB.
+    /// Failures are recorded as an `error` ingest run.
+    #[arg(long)]
+    ingest_kis: bool,
+
+    /// Make --ingest-kis resumable: skip tickers a previous run for the date already upserted
+    /// (tracked in kis_ingest_progress) and checkpoint as it goes. Checkpoints are cleared once
+    /// the date's ingest succeeds.
+    #[arg(long, requires = "ingest_kis", conflicts_with = "dry_run")]
+    resume: bool,
+
+    /// Number of stub rows to insert when using --ingest-features.
+    #[arg(long)]
+    ingest_size: Option<usize>,
+
+    /// LLM provider (`anthropic` | `gemini` | `openai-compatible`); overrides `LLM_PROVIDER`.
+    #[arg(long)]
+    llm_provider: Option<String>,
+
+    /// Fail the run (instead of annotating risk_notes) when sanity checks flag any item.
+    #[arg(long)]
+    strict_sanity: bool,
+
+    /// Regenerate even when a valid success snapshot exists; the old snapshot stays stored but
+    /// is recorded as superseded in the same transaction that persists the new one. With
+    /// --evaluate, recompute snapshots that already have outcomes.
+    #[arg(long, conflicts_with = "dry_run")]
+    force: bool,
+
+    /// First date (YYYY-MM-DD, inclusive) of a backfill over KR trading days.
+    #[arg(
+        long,
+        requires = "backfill_to",
+        conflicts_with_all = ["as_of_date", "dry_run", "ingest_features", "ingest_external", "ingest_kis"]
+    )]
+    backfill_from: Option<NaiveDate>,
+
+    /// Last date (YYYY-MM-DD, inclusive) of a backfill.
+    #[arg(long, requires = "backfill_from")]
+    backfill_to: Option<NaiveDate>,
+
+    /// Stop a backfill at the first failed date instead of continuing with the rest.
+    #[arg(long, requires = "backfill_from")]
+    fail_fast: bool,
+
+    /// Rerun dates within --max-age-days whose latest snapshot is an error and that have no
+    /// valid success snapshot.
+    #[arg(
+        long,
+        conflicts_with_all = ["as_of_date", "dry_run", "ingest_features", "ingest_external", "ingest_kis", "backfill_from", "force"]
+    )]
+    retry_failed: bool,
+
+    /// How far back (days before the resolved market date) --retry-failed looks.
+    #[arg(long, default_value_t = 7, requires = "retry_failed")]
+    max_age_days: u32,
+
+    /// Stay resident and run the EOD pipeline every trading day at `WORKER_DAEMON_SCHEDULE_KST`
+    /// (default 16:20 KST): ingest (KIS, or --ingest-external) then recommend, like --full-run,
+    /// retrying failed runs; stops on SIGTERM/ctrl-c.
+    #[arg(
+        long,
+        conflicts_with_all = ["as_of_date", "dry_run", "ingest_features", "backfill_from", "retry_failed", "force"]
+    )]
+    daemon: bool,
+
+    /// Audit the resolved as_of_date (snapshot item count, rank contiguity, rationale lengths,
+    /// tickers present in stock_features_daily, ingest run status); prints the violations as
+    /// JSON and exits non-zero if there are any.
+    #[arg(
+        long,
+        conflicts_with_all = ["dry_run", "ingest_features", "ingest_external", "ingest_kis", "backfill_from", "retry_failed", "daemon", "prune"]
+    )]
+    verify: bool,
+
+    /// List trading days in --from..=--to that lack a success snapshot, with their feature row
+    /// counts and latest snapshot status. Read-only.
+    #[arg(
+        long,
+        requires_all = ["from", "to"],
+        conflicts_with_all = ["as_of_date", "dry_run", "ingest_features", "ingest_external", "ingest_kis", "backfill_from", "retry_failed", "daemon", "verify"]
+    )]
+    list_pending: bool,
+
+    /// Compute forward returns of past success snapshots into recommendation_outcomes: the
+    /// `close` column on the as_of_date against the close --horizon-days trading days later.
+    /// Snapshots whose exit date has no features yet are left for a later run.
+    #[arg(
+        long,
+        conflicts_with_all = ["as_of_date", "dry_run", "ingest_features", "ingest_external", "ingest_kis", "backfill_from", "retry_failed", "daemon", "verify", "list_pending", "prune", "seed_dev"]
+    )]
+    evaluate: bool,
+
+    /// Horizons (comma-separated trading days) for --evaluate.
+    #[arg(
+        long,
+        value_delimiter = ',',
+        default_values_t = [1, 5],
+        value_parser = clap::value_parser!(u32).range(1..),
+        requires = "evaluate"
+    )]
+    horizon_days: Vec<u32>,
+
+    /// First date (YYYY-MM-DD, inclusive) for --list-pending.
+    #[arg(long, requires = "list_pending")]
+    from: Option<NaiveDate>,
+
+    /// Last date (YYYY-MM-DD, inclusive) for --list-pending.
+    #[arg(long, requires = "list_pending")]
+    to: Option<NaiveDate>,
+
+    /// Print --list-pending output as JSON instead of a table.
+    #[arg(long, requires = "list_pending")]
+    json: bool,
+
+    /// Null out raw payloads (snapshots, ingest runs) older than --keep-days and, with
+    /// --features-keep-days, delete old stock_features_daily rows. With --dry-run, only counts.
+    #[arg(
+        long,
+        requires = "keep_days",
+        conflicts_with_all = ["as_of_date", "ingest_features", "ingest_external", "ingest_kis", "backfill_from", "retry_failed", "daemon"]
+    )]
+    prune: bool,
+
+    /// Retention (days) for raw payload columns when pruning.
+    #[arg(long, requires = "prune")]
+    keep_days: Option<u32>,
+
+    /// Retention (days) for stock_features_daily rows; they are kept when unset.
+    #[arg(long, requires = "prune")]
+    features_keep_days: Option<u32>,
+
+    /// Allow a prune retention below 7 days.
+    #[arg(long, requires = "prune")]
+    yes_really: bool,
+
+    /// Age (hours) after which a prune marks ingest runs still `running` as `abandoned`.
+    #[arg(long, requires = "prune", default_value_t = tootoo_core::storage::prune::DEFAULT_ABANDON_AFTER_HOURS)]
+    abandon_ingest_after_hours: u32,
+
+    /// Exit with code 4 when the LLM run fails (the failure is still persisted). Without it a
+    /// persisted LLM failure exits 0, as before the exit-code contract.
+    #[arg(long)]
+    fail_on_llm_error: bool,
+
+    /// Write a JSON run report (phases, counts, token usage, status) to this file at exit, even
+    /// when the run fails. The same report is always logged as one `worker run report` event.
+    #[arg(long, conflicts_with = "daemon")]
+    report_path: Option<std::path::PathBuf>,
+
+    /// Write the candidate universe (as_of_date, universe options, candidates with features) to
+    /// this `.json` or `.csv` file. Works with --dry-run; on a real run it is written before the
+    /// LLM call, so it exists even when generation fails.
+    #[arg(
+        long,
+        value_name = "PATH",
+        conflicts_with_all = ["ingest_features", "ingest_external", "ingest_kis", "backfill_from", "retry_failed", "daemon", "verify", "list_pending", "prune"]
+    )]
+    export_universe: Option<std::path::PathBuf>,
+
+    /// Seed a local database for development: migrations, stub features for the last 10 trading
+    /// days up to the as_of_date and two synthetic success snapshots. Refuses non-local
+    /// databases unless --allow-remote.
+    #[arg(
+        long,
+        conflicts_with_all = ["dry_run", "ingest_features", "ingest_external", "ingest_kis", "backfill_from", "retry_failed", "daemon", "verify", "list_pending", "prune", "force"]
+    )]
+    seed_dev: bool,
+
+    /// Let --seed-dev write to a database that is not on localhost.
+    #[arg(long, requires = "seed_dev")]
+    allow_remote: bool,
+
+    /// Load earnings announcement dates from a `date,ticker` CSV (tickers with the `KRX:`
+    /// prefix) into earnings_calendar, then exit. Later ingests derive `days_to_earnings` from it.
+    #[arg(
+        long,
+        value_name = "PATH",
+        conflicts_with_all = ["dry_run", "ingest_features", "ingest_external", "ingest_kis", "full_run", "backfill_from", "retry_failed", "daemon", "verify", "list_pending", "prune", "seed_dev", "export_universe"]
+    )]
+    load_earnings_calendar: Option<std::path::PathBuf>,
+
+    /// Run even when the as_of_date (or, without --as-of-date, today in KST) is a weekend or
+    /// configured holiday; for ad-hoc tests. Without it such runs exit early with code 6.
+    #[arg(
+        long,
+        conflicts_with_all = ["as_of_dates", "dates_file", "backfill_from", "retry_failed", "daemon", "verify", "list_pending", "prune", "seed_dev"]
+    )]
+    allow_non_trading_day: bool,
+
+    /// Abort the run after this many seconds (default `WORKER_MAX_RUNTIME_SECS`; 0 = no limit).
+    /// A timeout or ctrl-c/SIGTERM records the in-flight run (failure snapshot or `error` ingest
+    /// run) and releases the as_of_date lock before exiting.
+    #[arg(long, conflicts_with = "daemon")]
+    max_runtime_secs: Option<u64>,
+}
+
+#[tokio::main]
+async fn main() -> std::process::ExitCode {
+    dotenvy::dotenv().ok();
+
+    let settings = match tootoo_core::config::Settings::from_env() {
+        Ok(settings) => settings,
+        Err(err) => {
+            eprintln!("Error: {err:?}");
+            return exit::CONFIG_ERROR.into();
+        }
+    };
+    let _sentry_guard = init_sentry(&settings);
+
+    tracing_subscriber::registry()
+        .with(EnvFilter::from_default_env())
+        .with(tracing_subscriber::fmt::layer())
+        .with(sentry_tracing::layer())
+        .init();
+
+    // clap's own usage-error code (2) would collide with LOCK_NOT_ACQUIRED.
+    let args = match Args::try_parse() {
+        Ok(args) => args,
+        Err(err) => {
+            let _ = err.print();
+            return if err.use_stderr() {
+                exit::CONFIG_ERROR
+            } else {
+                exit::SUCCESS
+            }
+            .into();
+        }
+    };
+
+    match run_main(&settings, &args).await {
+        Ok(code) => code.into(),
+        Err(err) => {
+            eprintln!("Error: {err:?}");
+            exit::for_error(&err, run_mode(&args)).into()
+        }
+    }
+}
+
+/// Everything after flag parsing; returns the [`exit`] code for a completed run.
+async fn run_main(settings: &tootoo_core::config::Settings, args: &Args) -> anyhow::Result<u8> {
+    let mut report = report::ReportGuard::new(
+        args.report_path.clone(),
+        RunReport::new(run_mode(args), run_flags(args), chrono::Utc::now()),
+    );
+
+    let as_of_date = tootoo_core::time::kr_market::resolve_as_of_date(
+        args.as_of_date.as_deref(),
+        chrono::Utc::now(),
+    )
+    .context(exit::ConfigError)?;
+    report.report().as_of_date = Some(args.backfill_to.unwrap_or(as_of_date));
+
+    let llm_provider = match args.llm_provider.as_deref() {
+        Some(s) => tootoo_core::llm::Provider::parse(s),
+        None => tootoo_core::llm::provider_from_env(),
+    }
+    .context(exit::ConfigError)?;
+    if let Some(path) = &args.export_universe {
+        ExportFormat::from_path(path).context(exit::ConfigError)?;
+    }
+
+    if args.seed_dev {
+        let result = seed_dev(settings, as_of_date, args.allow_remote).await;
+        return finish_report(&mut report, result).map(|()| exit::SUCCESS);
+    }
+    if args.prune {
+        let result = prune(settings, args).await;
+        return finish_report(&mut report, result).map(|()| exit::SUCCESS);
+    }
+    if let Some(path) = &args.load_earnings_calendar {
+        let result = load_earnings_calendar(settings, path).await;
+        return finish_report(&mut report, result).map(|()| exit::SUCCESS);
+    }
+    if let (true, Some(from), Some(to)) = (args.list_pending, args.from, args.to) {
+        let result = list_pending(settings, from, to, args.json).await;
+        return finish_report(&mut report, result).map(|()| exit::SUCCESS);
+    }
+    if args.verify {
+        let result = verify(settings, as_of_date).await;
+        return finish_report(&mut report, result).map(|()| exit::SUCCESS);
+    }
+    if args.evaluate {
+        let result = evaluate(settings, &args.horizon_days, args.force).await;
+        return finish_report(&mut report, result).map(|()| exit::SUCCESS);
+    }
+
+    if is_single_date_run(args) {
+        let today = tootoo_core::time::kr_market::today_kst(chrono::Utc::now())?;
+        let explicit = args.as_of_date.is_some();
+        match trading_day::check(as_of_date, explicit, today, args.allow_non_trading_day) {
+            trading_day::Gate::Run => {}
+            trading_day::Gate::RunAnyway { day } => {
+                tracing::warn!(%as_of_date, %day, "not a trading day; running anyway (--allow-non-trading-day)");
+            }
+            trading_day::Gate::Refuse { day } => {
+                tracing::info!(%as_of_date, %day, explicit, "non-trading day; nothing to do");
+                report.finish(RunStatus::Skipped, None);
+                return Ok(exit::NON_TRADING_DAY);
+            }
+        }
+    }
+
+    if args.dry_run && args.ingest_kis {
+        tracing::info!(%as_of_date, dry_run = true, "worker: KIS ingest (dry-run)");
+        let result = print_kis_dry_run(settings, as_of_date).await;
+        return finish_report(&mut report, result).map(|()| exit::SUCCESS);
+    }
+
+    if args.dry_run {
+        tracing::info!(
+            %as_of_date,
+            dry_run = true,
+            "worker: EOD run (dry-run)"
+        );
+        let result = print_prompt(
+            settings,
+            llm_provider,
+            as_of_date,
+            args.out.as_deref(),
+            args.export_universe.as_deref(),
+        )
+        .await;
+        return finish_report(&mut report, result).map(|()| exit::SUCCESS);
+    }
+
+    let metrics = match tootoo_core::metrics::install_prometheus_recorder() {
+        Ok(handle) => Some(handle),
+        Err(e) => {
+            tracing::warn!(error = %e, "metrics recorder install failed; metrics disabled");
+            None
+        }
+    };
+    let max_runtime = cancel::max_runtime(args.max_runtime_secs);
+    if let (Some(from), Some(to)) = (args.backfill_from, args.backfill_to) {
+        let result = interruptible(
+            settings,
+            max_runtime,
+            backfill(
+                settings,
+                args,
+                from,
+                to,
+                llm_provider,
+                metrics.as_ref(),
+                report.report(),
+            ),
+        )
+        .await;
+        return finish_report(&mut report, result).map(|()| exit::SUCCESS);
+    }
+    if args.retry_failed {
+        let result = interruptible(
+            settings,
+            max_runtime,
+            retry_failed(
+                settings,
+                args,
+                llm_provider,
+                metrics.as_ref(),
+                report.report(),
+            ),
+        )
+        .await;
+        return finish_report(&mut report, result).map(|()| exit::SUCCESS);
+    }
+    if args.daemon {
+        let result = run_daemon(settings, args, llm_provider, metrics.as_ref()).await;
+        return finish_report(&mut report, result).map(|()| exit::SUCCESS);
+    }
+
+    let mode = run_mode(args);
+    let started = std::time::Instant::now();
+
+    let result = interruptible(
+        settings,
+        max_runtime,
+        run(settings, args, as_of_date, llm_provider, report.report()),
+    )
+    .await;
+    if mode.starts_with("ingest_") {
+        report.report().phases.add(Phase::Ingest, started.elapsed());
+    }
+
+    let outcome = if result.is_ok() { "success" } else { "error" };
+    if result.is_err() {
+        if let Some(source) = mode.strip_prefix("ingest_") {
+            metrics::counter!(
+                INGEST_FAILURES_TOTAL,
+                "as_of_date" => as_of_date_label(as_of_date),
+                "source" => source,
+            )
+            .increment(1);
+        }
+    }
+    metrics::gauge!(
+        WORKER_RUN_DURATION_SECONDS,
+        "as_of_date" => as_of_date_label(as_of_date),
+        "mode" => mode,
+        "outcome" => outcome,
+    )
+    .set(started.elapsed().as_secs_f64());
+    if let Some(handle) = &metrics {
+        flush_metrics(handle, as_of_date);
+    }
+
+    let code = match &result {
+        Ok(Some(outcome)) => exit::for_outcome(outcome, args.fail_on_llm_error),
+        _ => exit::SUCCESS,
+    };
+    finish_report(&mut report, result.map(|_| ()))?;
+    Ok(code)
+}
+
+/// Sets the report's final status from `result` (keeping a status the run already recorded,
+/// e.g. `skipped`) and passes `result` through.
+fn finish_report(
+    report: &mut report::ReportGuard,
+    result: anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    match &result {
+        Ok(()) if report.report().status == RunStatus::Aborted => {
+            report.finish(RunStatus::Success, None)
+        }
+        Ok(()) => {}
+        Err(e) => report.finish(RunStatus::Error, Some(format!("{e:#}"))),
+    }
+    result
+}
+
+fn run_flags(args: &Args) -> tootoo_core::report::RunFlags {
+    tootoo_core::report::RunFlags {
+        dry_run: args.dry_run,
+        force: args.force,
+        strict_sanity: args.strict_sanity,
+        fail_fast: args.fail_fast,
+        llm_provider: args.llm_provider.clone(),
+    }
+}
+
+/// `--seed-dev`: local development data through the real persist path.
+async fn seed_dev(
+    settings: &tootoo_core::config::Settings,
+    as_of_date: NaiveDate,
+    allow_remote: bool,
+) -> anyhow::Result<()> {
+    let db_url = database_url(settings)?;
+    if !allow_remote && !tootoo_core::dev_seed::is_local_database_url(&db_url) {
+        return Err(anyhow::anyhow!(
+            "refusing to seed a non-local database; pass --allow-remote to override"
+        ))
+        .context(exit::ConfigError);
+    }
+    let pool = connect_pool(settings).await?;
+    let summary = tootoo_core::dev_seed::seed_dev(&pool, as_of_date).await?;
+
+    println!(
+        "seeded stock_features_daily for {} trading days ({} .. {}), {} new rows",
+        summary.dates.len(),
+        summary
+            .dates
+            .first()
+            .map_or_else(String::new, |d| d.to_string()),
+        summary
+            .dates
+            .last()
+            .map_or_else(String::new, |d| d.to_string()),
+        summary.feature_rows
+    );
+    for (date, snapshot_id) in &summary.snapshots {
+        match snapshot_id {
+            Some(id) => println!("{date}  snapshot {id}"),
+            None => println!("{date}  success snapshot already exists; kept"),
+        }
+    }
+    Ok(())
+}
+
+/// `--load-earnings-calendar`: validates the whole file before writing any of it.
+async fn load_earnings_calendar(
+    settings: &tootoo_core::config::Settings,
+    path: &std::path::Path,
+) -> anyhow::Result<()> {
+    use tootoo_core::storage::earnings_calendar;
+
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read earnings calendar {}", path.display()))
+        .context(exit::ConfigError)?;
+    let rows = earnings_calendar::parse_csv(&text)
+        .with_context(|| format!("invalid earnings calendar {}", path.display()))
+        .context(exit::ConfigError)?;
+    anyhow::ensure!(
+        !rows.is_empty(),
+        "{} lists no earnings dates",
+        path.display()
+    );
+
+    let pool = connect_pool(settings).await?;
+    tootoo_core::storage::migrate(&pool).await?;
+    let source = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string());
+    let affected = earnings_calendar::upsert(&pool, &rows, &source).await?;
+
+    let tickers: std::collections::BTreeSet<&str> =
+        rows.iter().map(|r| r.ticker.as_str()).collect();
+    println!(
+        "loaded {} earnings dates for {} tickers from {source}",
+        rows.len(),
+        tickers.len()
+    );
+    tracing::info!(
+        path = %path.display(),
+        rows = rows.len(),
+        tickers = tickers.len(),
+        affected,
+        "loaded earnings calendar"
+    );
+    Ok(())
+}
+
+/// Adds `days_to_earnings` to the items with an announcement near `as_of_date`. The calendar is
+/// optional, so a failed lookup only costs the feature.
+async fn apply_earnings_calendar(
+    pool: &sqlx::PgPool,
+    as_of_date: NaiveDate,
+    items: &mut [tootoo_core::ingest::types::DailyFeatureItem],
+) {
+    let days = match tootoo_core::storage::earnings_calendar::days_to_earnings_by_ticker(
+        pool, as_of_date,
+    )
+    .await
+    {
+        Ok(days) => days,
+        Err(err) => {
+            tracing::warn!(%as_of_date, error = %err, "earnings calendar unavailable; skipping days_to_earnings");
+            return;
+        }
+    };
+    if days.is_empty() {
+        return;
+    }
+    let mut tagged: usize = 0;
+    for item in items.iter_mut() {
+        if let Some(d) = days.get(item.ticker.trim()) {
+            item.features
+                .insert("days_to_earnings".to_string(), *d as f64);
+            tagged += 1;
+        }
+    }
+    tracing::info!(%as_of_date, tagged, "applied earnings calendar");
+}
+
+/// `--list-pending`: trading days in `from..=to` without a success snapshot.
+async fn list_pending(
+    settings: &tootoo_core::config::Settings,
+    from: NaiveDate,
+    to: NaiveDate,
+    json: bool,
+) -> anyhow::Result<()> {
+    anyhow::ensure!(from <= to, "--from {from} is after --to {to}");
+    let days = tootoo_core::time::kr_market::trading_days(from, to);
+
+    let pool = connect_pool(settings).await?;
+    let features =
+        tootoo_core::storage::stock_features::count_features_by_date(&pool, from, to).await?;
+    let status =
+        tootoo_core::storage::recommendations::snapshot_status_by_date(&pool, from, to).await?;
+    let pending = pending::pending_dates(&days, &features, &status);
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&pending)?);
+    } else {
+        print!("{}", pending::format_table(&pending));
+    }
+    tracing::info!(%from, %to, trading_days = days.len(), pending = pending.len(), "listed pending dates");
+    Ok(())
+}
+
+/// `--verify`: runs the `storage::audit` checks for `as_of_date` and prints the violations.
+async fn verify(
+    settings: &tootoo_core::config::Settings,
+    as_of_date: NaiveDate,
+) -> anyhow::Result<()> {
+    let pool = connect_pool(settings).await?;
+    let violations = tootoo_core::storage::audit::audit_date(
+        &pool,
+        as_of_date,
+        tootoo_core::llm::prompt::ITEM_COUNT,
+    )
+    .await?;
+
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&serde_json::json!({
+            "as_of_date": as_of_date,
+            "violations": violations,
+        }))?
+    );
+    anyhow::ensure!(
+        violations.is_empty(),
+        "verify: {} integrity violation(s) for {as_of_date}",
+        violations.len()
+    );
+    tracing::info!(%as_of_date, "verify: no integrity violations");
+    Ok(())
+}
+
+/// `--evaluate`: upserts the outcomes of every snapshot whose exit date is ingested, per
+/// horizon, and prints their hit rate and mean return.
+async fn evaluate(
+    settings: &tootoo_core::config::Settings,
+    horizons: &[u32],
+    recompute: bool,
+) -> anyhow::Result<()> {
+    use tootoo_core::storage::outcomes;
+    use tootoo_core::time::kr_market::nth_trading_day_after;
+
+    let pool = connect_pool(settings).await?;
+    tootoo_core::storage::migrate(&pool).await?;
+
+    let mut evaluated = std::collections::BTreeSet::new();
+    for &horizon_days in horizons {
+        let snapshots = outcomes::snapshots_to_evaluate(&pool, horizon_days, recompute).await?;
+        let (Some((_, first)), Some((_, last))) = (snapshots.first(), snapshots.last()) else {
+            continue;
+        };
+        let features = tootoo_core::storage::stock_features::count_features_by_date(
+            &pool,
+            nth_trading_day_after(*first, horizon_days),
+            nth_trading_day_after(*last, horizon_days),
+        )
+        .await?;
+        let (ready, waiting) = evaluate::plan(&snapshots, horizon_days, &features);
+        let mut missing = 0;
+        for target in &ready {
+            let items = outcomes::evaluate_snapshot(
+                &pool,
+                target.snapshot_id,
+                target.as_of_date,
+                horizon_days,
+                target.exit_date,
+            )
+            .await?;
+            missing += items.iter().filter(|o| o.missing.is_some()).count();
+            evaluated.insert((target.as_of_date, horizon_days, target.snapshot_id));
+        }
+        tracing::info!(
+            horizon_days,
+            evaluated = ready.len(),
+            waiting,
+            missing_prices = missing,
+            "evaluated recommendation outcomes"
+        );
+    }
+
+    let (Some((from, ..)), Some((to, ..))) = (evaluated.first(), evaluated.last()) else {
+        println!("no snapshots ready to evaluate");
+        return Ok(());
+    };
+    let summaries: Vec<_> = outcomes::outcome_summaries(&pool, *from, *to)
+        .await?
+        .into_iter()
+        .filter(|s| evaluated.contains(&(s.as_of_date, s.horizon_days as u32, s.snapshot_id)))
+        .collect();
+    print!("{}", evaluate::format_table(&summaries));
+    Ok(())
+}
+
+/// `--prune`: applies the retention flags (or, with `--dry-run`, only counts what they would
+/// touch), marks stale `running` ingest runs `abandoned` and prints the row counts.
+async fn prune(settings: &tootoo_core::config::Settings, args: &Args) -> anyhow::Result<()> {
+    let today = tootoo_core::time::kr_market::resolve_as_of_date(None, chrono::Utc::now())?;
+    let policy = tootoo_core::storage::prune::PrunePolicy::new(
+        today,
+        args.keep_days.context("--prune requires --keep-days")?,
+        args.features_keep_days,
+        args.yes_really,
+    )
+    .context(exit::ConfigError)?;
+
+    let pool = connect_pool(settings).await?;
+    tootoo_core::storage::migrate(&pool).await?;
+
+    let counts = if args.dry_run {
+        tootoo_core::storage::prune::count_prunable(&pool, &policy).await?
+    } else {
+        tootoo_core::storage::prune::prune(&pool, &policy).await?
+    };
+    let abandoned = tootoo_core::storage::prune::abandon_stale_ingest_runs(
+        &pool,
+        chrono::Duration::hours(args.abandon_ingest_after_hours.into()),
+        args.dry_run,
+    )
+    .await?;
+
+    let verb = if args.dry_run {
+        "would prune"
+    } else {
+        "pruned"
+    };
+    println!("{:<42}  {:<10}  {verb}", "table", "before");
+    for c in &counts {
+        println!("{:<42}  {}  {}", c.table, c.cutoff, c.rows);
+        tracing::info!(
+            table = c.table,
+            cutoff = %c.cutoff,
+            rows = c.rows,
+            dry_run = args.dry_run,
+            "prune"
+        );
+    }
+    let verb = if args.dry_run {
+        "would abandon"
+    } else {
+        "abandoned"
+    };
+    println!(
+        "{abandoned} ingest run(s) running for over {}h: {verb}",
+        args.abandon_ingest_after_hours
+    );
+    tracing::info!(
+        abandoned,
+        after_hours = args.abandon_ingest_after_hours,
+        dry_run = args.dry_run,
+        "abandon stale ingest runs"
+    );
+    Ok(())
+}
+
+/// Runs [`run_for_date`] for every trading day in `from..=to` and prints a per-date summary.
+/// A failed date does not stop the remaining ones unless `--fail-fast`; either way the process
+/// exits non-zero when any date failed.
+async fn backfill(
+    settings: &tootoo_core::config::Settings,
+    args: &Args,
+    from: NaiveDate,
+    to: NaiveDate,
+    llm_provider: tootoo_core::llm::Provider,
+    metrics: Option<&metrics_exporter_prometheus::PrometheusHandle>,
+    report: &mut RunReport,
+) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        from <= to,
+        "--backfill-from {from} is after --backfill-to {to}"
+    );
+    let dates = tootoo_core::time::kr_market::trading_days(from, to);
+    tracing::info!(%from, %to, dates = dates.len(), force = args.force, "worker: backfill");
+
+    let pool = connect_pool(settings).await?;
+    tootoo_core::storage::migrate(&pool).await?;
+
+    let outcomes = run_dates(
+        settings,
+        &pool,
+        &dates,
+        llm_provider,
+        RunOptions::from_args(args),
+        "backfill",
+        report,
+    )
+    .await;
+
+    print!("{}", format_date_summary(&dates, &outcomes));
+    if let Some(handle) = metrics {
+        flush_metrics(handle, to);
+    }
+
+    let failed = outcomes.iter().filter(|o| o.is_failed()).count();
+    anyhow::ensure!(
+        failed == 0,
+        "backfill: {failed} of {} dates failed",
+        dates.len()
+    );
+    Ok(())
+}
+
+/// `--retry-failed`: reruns dates in the last `--max-age-days` whose latest snapshot is an
+/// error and that have no valid success. Dates whose universe can no longer be built are
+/// logged and skipped; the process exits non-zero only when every retried date failed.
+async fn retry_failed(
+    settings: &tootoo_core::config::Settings,
+    args: &Args,
+    llm_provider: tootoo_core::llm::Provider,
+    metrics: Option<&metrics_exporter_prometheus::PrometheusHandle>,
+    report: &mut RunReport,
+) -> anyhow::Result<()> {
+    let today = tootoo_core::time::kr_market::resolve_as_of_date(None, chrono::Utc::now())?;
+    let since = today - chrono::Days::new(args.max_age_days.into());
+
+    let pool = connect_pool(settings).await?;
+    tootoo_core::storage::migrate(&pool).await?;
+
+    let dates = tootoo_core::storage::recommendations::find_failed_dates(&pool, since).await?;
+    tracing::info!(%since, dates = dates.len(), "worker: retry failed dates");
+    if dates.is_empty() {
+        return Ok(());
+    }
+
+    let outcomes = run_dates(
+        settings,
+        &pool,
+        &dates,
+        llm_provider,
+        RunOptions::from_args(args),
+        "retry_failed",
+        report,
+    )
+    .await;
+
+    print!("{}", format_date_summary(&dates, &outcomes));
+    if let Some(handle) = metrics {
+        flush_metrics(handle, today);
+    }
+
+    let failed = outcomes.iter().filter(|o| o.is_failed()).count();
+    anyhow::ensure!(
+        failed < dates.len(),
+        "retry-failed: all {failed} retried dates failed again"
+    );
+    Ok(())
+}
+
+/// Runs [`run_for_date`] for each date in order, recording the run-duration gauge under `mode`.
+/// Errors become [`RunOutcome::Failed`] (or [`RunOutcome::NoUniverse`]) so one date cannot
+/// abort the rest; `--fail-fast` stops after the first failure instead.
+async fn run_dates(
+    settings: &tootoo_core::config::Settings,
+    pool: &sqlx::PgPool,
+    dates: &[NaiveDate],
+    llm_provider: tootoo_core::llm::Provider,
+    opts: RunOptions<'_>,
+    mode: &'static str,
+    report: &mut RunReport,
+) -> Vec<RunOutcome> {
+    let mut outcomes = Vec::with_capacity(dates.len());
+    for &as_of_date in dates {
+        let started = std::time::Instant::now();
+        let outcome = match run_for_date(settings, pool, as_of_date, llm_provider, opts, report)
+            .await
+        {
+            Ok(outcome) => outcome,
+            Err(e) if e.is::<universe::InsufficientUniverse>() => {
+                tracing::warn!(%as_of_date, error = %e, "cannot build candidate universe; skipping date");
+                RunOutcome::NoUniverse(e.to_string())
+            }
+            Err(e) => {
+                sentry_anyhow::capture_anyhow(&e);
+                tracing::error!(%as_of_date, mode, error = %format!("{e:#}"), "date run failed");
+                RunOutcome::Failed {
+                    snapshot_id: None,
+                    error: format!("{e:#}"),
+                }
+            }
+        };
+        let failed = outcome.is_failed();
+        metrics::gauge!(
+            WORKER_RUN_DURATION_SECONDS,
+            "as_of_date" => as_of_date_label(as_of_date),
+            "mode" => mode,
+            "outcome" => if failed { "error" } else { "success" },
+        )
+        .set(started.elapsed().as_secs_f64());
+        report.dates.push(outcome.date_report(as_of_date));
+        outcomes.push(outcome);
+
+        if failed && opts.fail_fast {
+            tracing::warn!(%as_of_date, mode, "stopping after failed date (--fail-fast)");
+            break;
+        }
+    }
+    outcomes
+}
+
+/// `--daemon`: sleep until the next scheduled trading-day slot, ingest and recommend through
+/// [`run_for_date`] (retrying failures with jittered spacing), repeat. A shutdown signal is honored while waiting; a run
+/// in progress is allowed to finish first.
+async fn run_daemon(
+    settings: &tootoo_core::config::Settings,
+    args: &Args,
+    llm_provider: tootoo_core::llm::Provider,
+    metrics: Option<&metrics_exporter_prometheus::PrometheusHandle>,
+) -> anyhow::Result<()> {
+    let daemon_opts = daemon::DaemonOptions::from_env().context(exit::ConfigError)?;
+    let opts = RunOptions::from_args(args);
+    tootoo_core::metrics::disable_date_labels();
+    let shutdown = daemon::shutdown_signal();
+    tokio::pin!(shutdown);
+
+    loop {
+        let now = chrono::Utc::now();
+        let next = tootoo_core::time::kr_market::next_trading_time_kst(
+            now,
+            daemon_opts.hour,
+            daemon_opts.minute,
+        )?;
+        let as_of_date = tootoo_core::time::kr_market::resolve_as_of_date(None, next)?;
+        tracing::info!(
+            next_run_at = %next,
+            %as_of_date,
+            "daemon: next scheduled run"
+        );
+
+        let wait = (next - now).to_std().unwrap_or_default();
+        tokio::select! {
+            _ = &mut shutdown => {
+                tracing::info!("daemon: shutdown signal received; exiting");
+                return Ok(());
+            }
+            _ = tokio::time::sleep(wait) => {}
+        }
+
+        for attempt in 0..=daemon_opts.max_retries {
+            let started = std::time::Instant::now();
+            let outcome = daemon_attempt(settings, args, as_of_date, llm_provider, opts)
+                .await
+                .unwrap_or_else(|e| {
+                    sentry_anyhow::capture_anyhow(&e);
+                    RunOutcome::Failed {
+                        snapshot_id: None,
+                        error: format!("{e:#}"),
+                    }
+                });
+            let failed = outcome.is_failed();
+            metrics::gauge!(
+                WORKER_RUN_DURATION_SECONDS,
+                "as_of_date" => as_of_date_label(as_of_date),
+                "mode" => "daemon",
+                "outcome" => if failed { "error" } else { "success" },
+            )
+            .set(started.elapsed().as_secs_f64());
+            tracing::info!(
+                %as_of_date,
+                attempt,
+                status = outcome.status(),
+                detail = %outcome.detail(),
+                "daemon: run finished"
+            );
+            if !failed {
+                break;
+            }
+            if attempt == daemon_opts.max_retries {
+                tracing::error!(
+                    %as_of_date,
+                    attempts = attempt + 1,
+                    "daemon: run failed; giving up until the next scheduled day"
+                );
+                break;
+            }
+
+            let delay = daemon_opts.retry_delay();
+            tracing::warn!(
+                %as_of_date,
+                attempt,
+                retry_in_secs = delay.as_secs(),
+                "daemon: run failed; retrying"
+            );
+            tokio::select! {
+                _ = &mut shutdown => {
+                    tracing::info!("daemon: shutdown signal received; exiting");
+                    return Ok(());
+                }
+                _ = tokio::time::sleep(delay) => {}
+            }
+        }
+
+        if let Some(handle) = metrics {
+            flush_metrics(handle, as_of_date);
+        }
+    }
+}
+
+/// Connects per attempt so a database outage at run time is retried like any other failure.
+/// Ingests the date first, the same steps as `--full-run`: a failed preflight or ingest, or too
+/// few feature rows afterwards, fails the attempt before the LLM call.
+async fn daemon_attempt(
+    settings: &tootoo_core::config::Settings,
+    args: &Args,
+    as_of_date: NaiveDate,
+    llm_provider: tootoo_core::llm::Provider,
+    mut opts: RunOptions<'_>,
+) -> anyhow::Result<RunOutcome> {
+    let mut report = report::ReportGuard::new(
+        None,
+        RunReport::new(
+            "daemon",
+            tootoo_core::report::RunFlags::default(),
+            chrono::Utc::now(),
+        ),
+    );
+    report.report().as_of_date = Some(as_of_date);
+    let pool = connect_pool(settings).await?;
+    tootoo_core::storage::migrate(&pool).await?;
+    let (provider, source) = ingest_provider(settings, args, &pool)?;
+    preflight(&pool, provider.as_ref(), &[as_of_date])
+        .await
+        .context(exit::IngestError)?;
+    let universe_size = universe::UniverseOptions::from_env().size;
+    opts.ingest = Some(IngestStep {
+        provider: provider.as_ref(),
+        source,
+        min_rows: full_run::min_feature_rows(args.min_feature_rows, universe_size),
+    });
+    let outcome = run_for_date(
+        settings,
+        &pool,
+        as_of_date,
+        llm_provider,
+        opts,
+        report.report(),
+    )
+    .await;
+    pool.close().await;
+    if let Ok(outcome) = &outcome {
+        outcome.record(report.report());
+    }
+    outcome
+}
+
+/// One line per date: `date  status  snapshot_id or error`. Dates left unrun by `--fail-fast`
+/// are listed as `not_run`.
+fn format_date_summary(dates: &[NaiveDate], outcomes: &[RunOutcome]) -> String {
+    let mut out = format!(
+        "{:<10}  {:<8}  {}\n",
+        "date", "status", "snapshot_id / error"
+    );
+    for (i, date) in dates.iter().enumerate() {
+        let (status, detail) = match outcomes.get(i) {
+            Some(outcome) => (outcome.status(), outcome.detail()),
+            None => ("not_run", String::new()),
+        };
+        out.push_str(format!("{date}  {status:<8}  {detail}").trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+/// Which mode `args` selects; the [`run`] branches double as a metric label.
+fn run_mode(args: &Args) -> &'static str {
+    if args.seed_dev {
+        "seed_dev"
+    } else if args.prune {
+        "prune"
+    } else if args.load_earnings_calendar.is_some() {
+        "load_earnings_calendar"
+    } else if args.verify {
+        "verify"
+    } else if args.list_pending {
+        "list_pending"
+    } else if args.evaluate {
+        "evaluate"
+    } else if args.dry_run {
+        "dry_run"
+    } else if args.backfill_from.is_some() {
+        "backfill"
+    } else if args.retry_failed {
+        "retry_failed"
+    } else if args.daemon {
+        "daemon"
+    } else if args.full_run {
+        "full_run"
+    } else if args.ingest_features {
+        "ingest_stub"
+    } else if args.ingest_external {
+        "ingest_external"
+    } else if args.ingest_kis {
+        "ingest_kis"
+    } else {
+        "recommend"
+    }
+}
+
+/// Whether the run covers only the resolved as_of_date (recommend, one-date ingest, dry-runs);
+/// multi-date modes pick trading days themselves.
+fn is_single_date_run(args: &Args) -> bool {
+    args.backfill_from.is_none()
+        && !args.retry_failed
+        && !args.daemon
+        && args.as_of_dates.is_empty()
+        && args.dates_file.is_none()
+}
+
+/// The worker is a short-lived job, so metrics are written once at exit: to
+/// `WORKER_METRICS_PATH` (e.g. a node_exporter textfile collector directory) when set,
+/// otherwise to the debug log.
+fn flush_metrics(handle: &metrics_exporter_prometheus::PrometheusHandle, as_of_date: NaiveDate) {
+    let text = handle.render();
+    let Some(path) = std::env::var_os("WORKER_METRICS_PATH").filter(|p| !p.is_empty()) else {
+        tracing::debug!(%as_of_date, metrics = %text, "worker metrics");
+        return;
+    };
+    let path = std::path::PathBuf::from(path);
+    // Write-then-rename so a scraper never reads a half-written file.
+    let tmp = path.with_extension("tmp");
+    let res = std::fs::write(&tmp, text).and_then(|()| std::fs::rename(&tmp, &path));
+    match res {
+        Ok(()) => tracing::info!(%as_of_date, path = %path.display(), "wrote worker metrics"),
+        Err(e) => tracing::warn!(
+            %as_of_date,
+            path = %path.display(),
+            error = %e,
+            "failed to write worker metrics"
+        ),
+    }
+}
+
+/// `--as-of-dates` or `--dates-file`, in the order given; `None` for a single-date ingest.
+fn ingest_batch_dates(args: &Args) -> anyhow::Result<Option<Vec<NaiveDate>>> {
+    if let Some(path) = &args.dates_file {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read dates file {}", path.display()))?;
+        let dates = ingest::parse_dates_file(&text)
+            .with_context(|| format!("invalid dates file {}", path.display()))?;
+        anyhow::ensure!(!dates.is_empty(), "{} lists no dates", path.display());
+        return Ok(Some(dates));
+    }
+    Ok((!args.as_of_dates.is_empty()).then(|| args.as_of_dates.clone()))
+}
+
+/// The data provider for `--ingest-external` / `--ingest-kis` and its metric `source` label.
+fn ingest_provider(
+    settings: &tootoo_core::config::Settings,
+    args: &Args,
+    pool: &sqlx::PgPool,
+) -> anyhow::Result<(Box<dyn DataProviderClient>, &'static str)> {
+    if args.ingest_external {
+        let kind =
+            tootoo_core::ingest::ProviderKind::parse(&args.provider).context(exit::ConfigError)?;
+        let provider = tootoo_core::ingest::data_provider(
+            settings,
+            kind,
+            args.provider_file.as_deref(),
+            Some(pool.clone()),
+        )
+        .context(exit::ConfigError)?;
+        return Ok((provider, "external"));
+    }
+    let mut kis = tootoo_core::ingest::kis::KisClient::from_settings_prod(settings)
+        .context(exit::ConfigError)?
+        .with_db_pool(pool.clone());
+    if args.resume {
+        kis = kis.with_resume();
+    }
+    Ok((Box::new(kis), "kis"))
+}
+
+/// Fetches one date, upserts it into stock_features_daily and records the ingest run: inserted
+/// as `running` before the fetch, finished as `success`, or `error` when the fetch or upsert
+/// fails. Returns the number of upserted items.
+///
+/// `--ingest-external` providers republish the same payload several times an evening, so their
+/// payloads are fingerprinted: when the provider answers 304 to the ETag of the payload the
+/// date's rows came from, or sends a payload with the same digest, the upsert is skipped and a
+/// `skipped_duplicate` run recorded instead.
+async fn ingest_date(
+    pool: &sqlx::PgPool,
+    provider: &dyn DataProviderClient,
+    source: &'static str,
+    as_of_date: NaiveDate,
+    copy_min_rows: Option<usize>,
+    report: &mut RunReport,
+) -> anyhow::Result<usize> {
+    let provider_name = provider.provider_name();
+    let dedupe = source == "external";
+    let current = if dedupe {
+        tootoo_core::storage::stock_features::current_payload_fingerprint(
+            pool,
+            as_of_date,
+            provider_name,
+        )
+        .await?
+    } else {
+        None
+    };
+    let run_id =
+        tootoo_core::storage::stock_features::start_ingest_run(pool, as_of_date, provider_name)
+            .await?;
+    cancel::begin_ingest(as_of_date, provider_name, run_id);
+    let current_etag = current.as_ref().and_then(|c| c.etag.as_deref());
+    let fetched = provider
+        .fetch_daily_features_if_changed(as_of_date, current_etag)
+        .await;
+    let (mut resp, raw_json, etag) = match fetched {
+        Ok(Fetched::Modified { resp, raw, etag }) => (resp, raw, etag),
+        Ok(Fetched::NotModified) => {
+            let current = current.context("provider answered 304 to an unconditional request")?;
+            return record_duplicate_ingest(
+                pool,
+                as_of_date,
+                run_id,
+                provider_name,
+                &current,
+                "304",
+            )
+            .await;
+        }
+        Err(err) => {
+            // Too many KIS failures or a market below its minimum: keep the partial stats, failure
+            // breakdown and market coverage with the run.
+            let partial = err
+                .inner()
+                .downcast_ref::<tootoo_core::ingest::kis::KisIngestIncomplete>();
+            if let Some(partial) = partial {
+                *report.counts.ingest_items.get_or_insert(0) += partial.items.len();
+                *report.counts.ingest_failures.get_or_insert(0) += partial.failures;
+            }
+            tootoo_core::storage::stock_features::finish_ingest_run(
+                pool,
+                run_id,
+                "error",
+                Some(&err.run_error()),
+                partial.map(|p| p.raw.clone()),
+                None,
+            )
+            .await?;
+            cancel::end_ingest();
+
+            tracing::error!(%as_of_date, %run_id, provider = provider_name, kind = err.variant_name(), error = %err, "ingest failed");
+            let err = anyhow::Error::from(err);
+            sentry_anyhow::capture_anyhow(&err);
+            return Err(err);
+        }
+    };
+
+    apply_earnings_calendar(pool, as_of_date, &mut resp.items).await;
+    // After the calendar join, so a calendar change still reaches the rows.
+    let fingerprint = dedupe.then(|| PayloadFingerprint {
+        digest: resp.digest(),
+        etag,
+    });
+    if let (Some(new), Some(current)) = (&fingerprint, &current) {
+        if new.digest == current.digest {
+            return record_duplicate_ingest(pool, as_of_date, run_id, provider_name, new, "digest")
+                .await;
+        }
+    }
+    let items = resp.items.len();
+    count_ingest_items(as_of_date, source, items);
+    *report.counts.ingest_items.get_or_insert(0) += items;
+    if let Some(failures) = raw_json["failures"].as_u64() {
+        *report.counts.ingest_failures.get_or_insert(0) += failures as usize;
+    }
+    if let Some(recovered) = raw_json["late_data_recovered"].as_u64() {
+        *report.counts.ingest_late_recovered.get_or_insert(0) += recovered as usize;
+    }
+    tracing::info!(
+        %as_of_date,
+        provider = provider_name,
+        items,
+        "starting stock_features_daily upsert"
+    );
+    let t0 = std::time::Instant::now();
+    let copy = copy_min_rows.is_some_and(|min| resp.items.len() >= min);
+
+    // A resumed KIS ingest whose earlier run fetched every ticker has nothing left to upsert.
+    let resumed = raw_json["resumed"].as_u64().unwrap_or(0);
+    // Rows that did not change since the last ingest of the date are left untouched.
+    let counts = if resp.items.is_empty() && resumed > 0 {
+        UpsertCounts::default()
+    } else {
+        let upserted = if copy {
+            tootoo_core::storage::stock_features::bulk_load_daily_features(
+                pool,
+                as_of_date,
+                &resp.items,
+                UpsertMode::Incremental,
+            )
+            .await
+        } else {
+            tootoo_core::storage::stock_features::upsert_daily_features_incremental(
+                pool,
+                as_of_date,
+                &resp.items,
+            )
+            .await
+        };
+        match upserted {
+            Ok(counts) => counts,
+            Err(err) => {
+                let error = format!("upsert: {err:#}");
+                tootoo_core::storage::stock_features::finish_ingest_run(
+                    pool,
+                    run_id,
+                    "error",
+                    Some(&error),
+                    None,
+                    None,
+                )
+                .await?;
+                cancel::end_ingest();
+                return Err(err);
+            }
+        }
+    };
+    let affected = counts.affected();
+
+    tracing::info!(
+        %as_of_date,
+        provider = provider_name,
+        inserted = counts.inserted,
+        updated = counts.updated,
+        unchanged = counts.unchanged,
+        items,
+        path = counts.path.as_str(),
+        elapsed_ms = t0.elapsed().as_millis(),
+        "finished stock_features_daily upsert"
+    );
 
-    /// Number of stub rows to insert when using --ingest-features.
-    #[arg(long)]
-    ingest_size: Option<usize>,
-}
+    tootoo_core::storage::stock_features::finish_ingest_run(
+        pool,
+        run_id,
+        "success",
+        None,
+        Some(raw_json),
+        fingerprint.as_ref(),
+    )
+    .await?;
+    cancel::end_ingest();
 
-#[tokio::main]
-async fn main() -> anyhow::Result<()> {
-    dotenvy::dotenv().ok();
+    if provider_name == "kis" {
+        match tootoo_core::storage::kis_progress::clear(pool, as_of_date).await {
+            Ok(0) => {}
+            Ok(cleared) => tracing::info!(%as_of_date, cleared, "cleared KIS ingest checkpoints"),
+            Err(e) => {
+                tracing::warn!(%as_of_date, error = %e, "failed to clear KIS ingest checkpoints")
+            }
+        }
+    }
 
-    let settings = tootoo_core::config::Settings::from_env()?;
-    let _sentry_guard = init_sentry(&settings);
+    tracing::info!(%as_of_date, %run_id, provider = provider_name, affected, items, resumed, "ingest complete");
+    Ok(items)
+}
 
-    tracing_subscriber::registry()
-        .with(EnvFilter::from_default_env())
-        .with(tracing_subscriber::fmt::layer())
-        .with(sentry_tracing::layer())
-        .init();
+/// Runs the provider's health check once before any fetch. On failure every date in `dates` gets
+/// an `error` ingest run whose raw JSON is `{"stage": "preflight"}`, and the error carries
+/// [`exit::PreflightError`].
+async fn preflight(
+    pool: &sqlx::PgPool,
+    provider: &dyn DataProviderClient,
+    dates: &[NaiveDate],
+) -> anyhow::Result<()> {
+    let provider_name = provider.provider_name();
+    let t0 = std::time::Instant::now();
+    let Err(err) = provider.health_check().await else {
+        tracing::info!(
+            provider = provider_name,
+            elapsed_ms = t0.elapsed().as_millis(),
+            "provider preflight ok"
+        );
+        return Ok(());
+    };
+    for as_of_date in dates {
+        let run_id = tootoo_core::storage::stock_features::start_ingest_run(
+            pool,
+            *as_of_date,
+            provider_name,
+        )
+        .await?;
+        tootoo_core::storage::stock_features::finish_ingest_run(
+            pool,
+            run_id,
+            "error",
+            Some(&err.run_error()),
+            Some(serde_json::json!({"stage": "preflight"})),
+            None,
+        )
+        .await?;
+    }
+    tracing::error!(provider = provider_name, kind = err.variant_name(), error = %err, dates = dates.len(), "provider preflight failed");
+    let err = anyhow::Error::from(err).context(exit::PreflightError);
+    sentry_anyhow::capture_anyhow(&err);
+    Err(err)
+}
 
-    let args = Args::parse();
+/// Finishes `run_id` as `skipped_duplicate`: its payload is one the date's rows already came
+/// from (`matched` on the provider's 304 or on the digest). The payload itself stays with the
+/// earlier success run.
+async fn record_duplicate_ingest(
+    pool: &sqlx::PgPool,
+    as_of_date: NaiveDate,
+    run_id: uuid::Uuid,
+    provider_name: &str,
+    fingerprint: &PayloadFingerprint,
+    matched: &str,
+) -> anyhow::Result<usize> {
+    tootoo_core::storage::stock_features::finish_ingest_run(
+        pool,
+        run_id,
+        "skipped_duplicate",
+        None,
+        None,
+        Some(fingerprint),
+    )
+    .await?;
+    cancel::end_ingest();
+    tracing::info!(
+        %as_of_date,
+        %run_id,
+        provider = provider_name,
+        digest = %fingerprint.digest,
+        matched,
+        "provider payload unchanged; skipped the upsert"
+    );
+    Ok(0)
+}
 
-    let as_of_date = tootoo_core::time::kr_market::resolve_as_of_date(
-        args.as_of_date.as_deref(),
-        chrono::Utc::now(),
-    )?;
+/// Multi-date ingest over one pool and provider. Duplicate and non-trading dates are skipped
+/// with a warning; a failed date does not stop the rest, but fails the run at the end. Rejected
+/// credentials would fail every date alike, so an `Auth` error skips the remaining ones.
+async fn ingest_dates(
+    pool: &sqlx::PgPool,
+    provider: &dyn DataProviderClient,
+    source: &'static str,
+    requested: &[NaiveDate],
+    report: &mut RunReport,
+) -> anyhow::Result<()> {
+    let (dates, skipped) = ingest::plan_ingest_dates(requested);
+    preflight(pool, provider, &dates).await?;
+    for (as_of_date, reason) in &skipped {
+        tracing::warn!(%as_of_date, reason = reason.as_str(), "skipping ingest date");
+        report.dates.push(DateReport {
+            as_of_date: *as_of_date,
+            status: "skipped".to_string(),
+            snapshot_id: None,
+            error: Some(reason.as_str().to_string()),
+        });
+    }
 
-    if args.dry_run {
-        tracing::info!(
-            %as_of_date,
-            dry_run = true,
-            "worker: EOD run (dry-run)"
+    let copy_min_rows = ingest::copy_min_rows();
+    let mut results = Vec::with_capacity(dates.len());
+    let mut auth_failed = false;
+    for as_of_date in dates {
+        if auth_failed {
+            let reason = "not attempted after an Auth failure";
+            tracing::warn!(%as_of_date, reason, "skipping ingest date");
+            report.dates.push(DateReport {
+                as_of_date,
+                status: "skipped".to_string(),
+                snapshot_id: None,
+                error: Some(reason.to_string()),
+            });
+            results.push((as_of_date, Err(reason.to_string())));
+            continue;
+        }
+        let result = ingest_date(pool, provider, source, as_of_date, copy_min_rows, report).await;
+        auth_failed = matches!(
+            result
+                .as_ref()
+                .err()
+                .and_then(|e| e.downcast_ref::<IngestError>()),
+            Some(IngestError::Auth(_))
         );
-        return Ok(());
+        let result = result.map_err(|e| format!("{e:#}"));
+        report.dates.push(DateReport {
+            as_of_date,
+            status: if result.is_ok() { "success" } else { "error" }.to_string(),
+            snapshot_id: None,
+            error: result.as_ref().err().cloned(),
+        });
+        results.push((as_of_date, result));
     }
 
-    // Allow a worker-only override so we can bypass Supabase pooler if needed.
-    let db_url = match std::env::var("WORKER_DATABASE_URL") {
-        Ok(v) if !v.trim().is_empty() => v,
-        _ => settings.require_database_url()?.to_string(),
-    };
+    print!("{}", ingest::format_ingest_summary(&results));
+    let failed = results.iter().filter(|(_, r)| r.is_err()).count();
+    tracing::info!(
+        dates = results.len(),
+        failed,
+        skipped = skipped.len(),
+        provider = provider.provider_name(),
+        "multi-date ingest complete"
+    );
+    anyhow::ensure!(
+        failed == 0,
+        "{failed} of {} ingest dates failed",
+        results.len()
+    );
+    Ok(())
+}
 
-    let mut connect_options =
-        PgConnectOptions::from_str(&db_url).context("parse DATABASE_URL failed")?;
-    connect_options = connect_options.statement_cache_capacity(0);
+/// Cleanup after an interrupt gets this long before the process exits anyway.
+const INTERRUPT_CLEANUP_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(15);
 
-    let pool = sqlx::postgres::PgPoolOptions::new()
-        .max_connections(5)
-        .connect_with(connect_options)
+/// Runs `pipeline` under `--max-runtime-secs` and ctrl-c/SIGTERM. On interruption, in-flight work
+/// is recorded as failed and the as_of_date lock released ([`cancel::cleanup`]) before the
+/// interrupt is returned as the run's error.
+async fn interruptible<T>(
+    settings: &tootoo_core::config::Settings,
+    max_runtime: Option<std::time::Duration>,
+    pipeline: impl std::future::Future<Output = anyhow::Result<T>>,
+) -> anyhow::Result<T> {
+    let interrupt = match cancel::run_until(pipeline, max_runtime, daemon::shutdown_signal()).await
+    {
+        Ok(result) => return result,
+        Err(interrupt) => interrupt,
+    };
+    tracing::error!(error = %interrupt, "run interrupted; cleaning up");
+    let cleanup = async {
+        match connect_pool(settings).await {
+            Ok(pool) => {
+                let store = cancel::PgCleanup { pool };
+                cancel::cleanup(&store, &cancel::IN_FLIGHT, interrupt).await;
+            }
+            Err(e) => {
+                tracing::warn!(error = %e, "cleanup could not connect; in-flight work not recorded");
+                // Closing the lock's session releases the lock.
+                if let Some(lock) = cancel::take_lock() {
+                    lock.detach();
+                }
+            }
+        }
+    };
+    if tokio::time::timeout(INTERRUPT_CLEANUP_TIMEOUT, cleanup)
         .await
-        .context("connect DATABASE_URL failed")?;
+        .is_err()
+    {
+        tracing::warn!("interrupt cleanup timed out");
+    }
+    Err(interrupt.into())
+}
+
+async fn run(
+    settings: &tootoo_core::config::Settings,
+    args: &Args,
+    as_of_date: NaiveDate,
+    llm_provider: tootoo_core::llm::Provider,
+    report: &mut RunReport,
+) -> anyhow::Result<Option<RunOutcome>> {
+    let batch_dates = ingest_batch_dates(args).context(exit::ConfigError)?;
+    let pool = connect_pool(settings).await?;
 
     tootoo_core::storage::migrate(&pool).await?;
 
     if args.ingest_features {
         let size = args.ingest_size.unwrap_or(500);
-        let inserted = ingest::ingest_stub_stock_features(&pool, as_of_date, size).await?;
+        let inserted =
+            tootoo_core::ingest::stub::ingest_stub_stock_features(&pool, as_of_date, size).await?;
+        count_ingest_items(as_of_date, "stub", size);
+        report.counts.ingest_items = Some(size);
         tracing::info!(%as_of_date, size, inserted, "seeded stock_features_daily (stub)");
-        return Ok(());
+        return Ok(None);
     }
 
-    if args.ingest_external {
-        let provider =
-            tootoo_core::ingest::provider::HttpJsonDataProvider::from_settings(&settings)?;
-        let provider_name = provider.provider_name();
-
-        let fetched = provider.fetch_daily_features(as_of_date).await;
-        match fetched {
-            Ok((resp, raw_json)) => {
-                let affected = tootoo_core::storage::stock_features::upsert_daily_features_atomic(
-                    &pool,
-                    as_of_date,
-                    &resp.items,
-                )
-                .await?;
-
-                let run_id = tootoo_core::storage::stock_features::record_ingest_run(
-                    &pool,
-                    as_of_date,
-                    provider_name,
-                    "success",
-                    None,
-                    Some(raw_json),
-                )
-                .await?;
-
-                tracing::info!(%as_of_date, %run_id, affected, items = resp.items.len(), "external ingest complete");
-                return Ok(());
+    if (args.ingest_external || args.ingest_kis) && !args.full_run {
+        let (provider, source) = ingest_provider(settings, args, &pool)?;
+        match batch_dates {
+            Some(dates) => {
+                ingest_dates(&pool, provider.as_ref(), source, &dates, report).await?;
             }
-            Err(err) => {
-                sentry_anyhow::capture_anyhow(&err);
-                let run_id = tootoo_core::storage::stock_features::record_ingest_run(
-                    &pool,
-                    as_of_date,
-                    provider_name,
-                    "error",
-                    Some(&format!("{:#}", err)),
-                    None,
-                )
-                .await?;
-
-                tracing::error!(%as_of_date, %run_id, error = %err, "external ingest failed");
-                return Err(err);
+            None => {
+                preflight(&pool, provider.as_ref(), &[as_of_date]).await?;
+                ingest_date(&pool, provider.as_ref(), source, as_of_date, None, report).await?;
             }
         }
+        return Ok(None);
     }
 
-    if args.ingest_kis {
-        let kis = tootoo_core::ingest::kis::KisClient::from_settings_prod(&settings)?
-            .with_db_pool(pool.clone());
-        let (resp, raw_json) = kis.fetch_daily_features_krx(as_of_date).await?;
+    let full_run = match args.full_run {
+        true => Some(ingest_provider(settings, args, &pool)?),
+        false => None,
+    };
+    let mut opts = RunOptions::from_args(args);
+    if let Some((provider, source)) = &full_run {
+        preflight(&pool, provider.as_ref(), &[as_of_date])
+            .await
+            .context(exit::IngestError)?;
+        let universe_size = universe::UniverseOptions::from_env().size;
+        opts.ingest = Some(IngestStep {
+            provider: provider.as_ref(),
+            source,
+            min_rows: full_run::min_feature_rows(args.min_feature_rows, universe_size),
+        });
+    }
 
-        let upsert_items = resp.items.len();
-        tracing::info!(
-            %as_of_date,
-            items = upsert_items,
-            "starting stock_features_daily upsert (kis)"
-        );
-        let t0 = std::time::Instant::now();
+    let outcome = run_for_date(settings, &pool, as_of_date, llm_provider, opts, report).await?;
+    outcome.record(report);
+    Ok(Some(outcome))
+}
 
-        let affected = tootoo_core::storage::stock_features::upsert_daily_features_atomic(
-            &pool,
-            as_of_date,
-            &resp.items,
-        )
-        .await?;
+/// Flags shared by single-date and backfill runs of [`run_for_date`].
+#[derive(Debug, Clone, Copy)]
+struct RunOptions<'a> {
+    strict_sanity: bool,
+    /// Regenerate even when a valid success snapshot exists; it is invalidated only once the
+    /// new one is persisted.
+    force: bool,
+    /// Multi-date runs only: stop after the first failed date.
+    fail_fast: bool,
+    /// Single-date runs only: `--export-universe` target.
+    export_universe: Option<&'a std::path::Path>,
+    /// `--full-run` only: ingest the date before building the universe.
+    ingest: Option<IngestStep<'a>>,
+}
 
-        tracing::info!(
-            %as_of_date,
-            affected,
-            items = upsert_items,
-            elapsed_ms = t0.elapsed().as_millis(),
-            "finished stock_features_daily upsert (kis)"
-        );
+/// The ingest half of `--full-run`, run under the as_of_date lock.
+#[derive(Clone, Copy)]
+struct IngestStep<'a> {
+    provider: &'a dyn DataProviderClient,
+    source: &'static str,
+    min_rows: usize,
+}
 
-        let t1 = std::time::Instant::now();
-        let run_id = tootoo_core::storage::stock_features::record_ingest_run(
-            &pool,
+impl std::fmt::Debug for IngestStep<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("IngestStep")
+            .field("provider", &self.provider.provider_name())
+            .field("source", &self.source)
+            .field("min_rows", &self.min_rows)
+            .finish()
+    }
+}
+
+impl<'a> RunOptions<'a> {
+    fn from_args(args: &'a Args) -> Self {
+        Self {
+            strict_sanity: args.strict_sanity,
+            force: args.force,
+            fail_fast: args.fail_fast,
+            export_universe: args.export_universe.as_deref(),
+            ingest: None,
+        }
+    }
+}
+
+/// What [`run_for_date`] did for one as_of_date.
+#[derive(Debug)]
+enum RunOutcome {
+    Persisted(uuid::Uuid),
+    /// A valid success snapshot already exists (and `--force` was not passed).
+    Skipped,
+    /// Another run holds the as_of_date advisory lock.
+    Locked,
+    /// Too few feature rows to build the candidate universe (retry/backfill modes only).
+    NoUniverse(String),
+    /// The LLM call or persist failed; `snapshot_id` is the recorded error row, if any.
+    Failed {
+        snapshot_id: Option<uuid::Uuid>,
+        error: String,
+    },
+}
+
+impl RunOutcome {
+    /// Copies a single-date outcome into the top-level report fields.
+    fn record(&self, report: &mut RunReport) {
+        report.status = match self {
+            Self::Persisted(_) => RunStatus::Success,
+            Self::Skipped | Self::Locked => RunStatus::Skipped,
+            Self::NoUniverse(_) | Self::Failed { .. } => RunStatus::Error,
+        };
+        let entry = self.date_report(report.as_of_date.unwrap_or_default());
+        report.snapshot_id = entry.snapshot_id;
+        report.error = entry.error;
+    }
+
+    fn date_report(&self, as_of_date: NaiveDate) -> DateReport {
+        let (snapshot_id, error) = match self {
+            Self::Persisted(id) => (Some(*id), None),
+            Self::Skipped | Self::Locked => (None, None),
+            Self::NoUniverse(error) => (None, Some(error.clone())),
+            Self::Failed { snapshot_id, error } => (*snapshot_id, Some(error.clone())),
+        };
+        DateReport {
             as_of_date,
-            "kis",
-            "success",
-            None,
-            Some(raw_json),
-        )
-        .await?;
+            status: self.status().to_string(),
+            snapshot_id,
+            error,
+        }
+    }
 
-        tracing::info!(
-            %as_of_date,
-            %run_id,
-            elapsed_ms = t1.elapsed().as_millis(),
-            "recorded ingest_run (kis)"
-        );
+    fn is_failed(&self) -> bool {
+        matches!(self, Self::Failed { .. })
+    }
 
-        tracing::info!(%as_of_date, %run_id, affected, items = resp.items.len(), "KIS ingest complete");
-        return Ok(());
+    fn status(&self) -> &'static str {
+        match self {
+            Self::Persisted(_) => "success",
+            Self::Skipped => "skipped",
+            Self::Locked => "locked",
+            Self::NoUniverse(_) => "no_universe",
+            Self::Failed { .. } => "error",
+        }
     }
 
-    // Advisory locks are session-scoped, so we must acquire and release on the same connection.
-    let mut lock_conn = pool
-        .acquire()
-        .await
-        .context("acquire connection for advisory lock failed")?;
-    let acquired =
-        tootoo_core::storage::lock::try_acquire_as_of_date_lock_conn(&mut *lock_conn, as_of_date)
-            .await?;
-    if !acquired {
+    fn detail(&self) -> String {
+        match self {
+            Self::Persisted(id) => id.to_string(),
+            Self::Skipped => "success snapshot exists".to_string(),
+            Self::Locked => "another run in progress".to_string(),
+            Self::NoUniverse(error) => error.clone(),
+            Self::Failed {
+                snapshot_id: Some(id),
+                error,
+            } => format!("{id}: {error}"),
+            Self::Failed {
+                snapshot_id: None,
+                error,
+            } => error.clone(),
+        }
+    }
+}
+
+/// One recommendation run: lock the date, skip if already done, build the universe, call the
+/// LLM and persist the snapshot (or the failure). Shared by single-date and backfill runs.
+async fn run_for_date(
+    settings: &tootoo_core::config::Settings,
+    pool: &sqlx::PgPool,
+    as_of_date: NaiveDate,
+    llm_provider: tootoo_core::llm::Provider,
+    opts: RunOptions<'_>,
+    report: &mut RunReport,
+) -> anyhow::Result<RunOutcome> {
+    let Some(lock) = AsOfDateLockGuard::try_acquire(pool, as_of_date).await? else {
         tracing::warn!(%as_of_date, "as_of_date lock not acquired; another run in progress");
-        return Ok(());
+        return Ok(RunOutcome::Locked);
+    };
+
+    // Parked while the run is in flight so an interrupt can still release it.
+    cancel::hold_lock(as_of_date, lock);
+
+    let outcome = run_locked(settings, pool, as_of_date, llm_provider, opts, report).await;
+    if let Err(err) = &outcome {
+        notify_run(&notifier::RunNotice::failure(as_of_date, None, err)).await;
     }
 
-    if success_snapshot_exists(&pool, as_of_date).await? {
-        tracing::info!(%as_of_date, "successful snapshot already exists; exiting (no-op)");
-        let _ =
-            tootoo_core::storage::lock::release_as_of_date_lock_conn(&mut *lock_conn, as_of_date)
-                .await;
-        return Ok(());
+    cancel::end_run();
+    if let Some(lock) = cancel::take_lock() {
+        if let Err(e) = lock.release().await {
+            tracing::warn!(%as_of_date, error = %e, "failed to release as_of_date lock");
+        }
+    }
+    outcome
+}
+
+async fn run_locked(
+    settings: &tootoo_core::config::Settings,
+    pool: &sqlx::PgPool,
+    as_of_date: NaiveDate,
+    llm_provider: tootoo_core::llm::Provider,
+    opts: RunOptions<'_>,
+    report: &mut RunReport,
+) -> anyhow::Result<RunOutcome> {
+    if success_snapshot_exists(pool, as_of_date).await? {
+        if !opts.force {
+            tracing::info!(%as_of_date, "successful snapshot already exists; exiting (no-op)");
+            return Ok(RunOutcome::Skipped);
+        }
+        let superseding =
+            tootoo_core::storage::recommendations::count_success_snapshots(pool, as_of_date)
+                .await?;
+        tracing::warn!(
+            %as_of_date,
+            superseding,
+            "--force: superseding existing success snapshot; it stays stored but is hidden once the new one is persisted"
+        );
+    }
+    // From here until an outcome is persisted, an interrupt records the run as failed.
+    cancel::begin_run(as_of_date, llm_provider.as_str());
+
+    if let Some(step) = opts.ingest {
+        let t_ingest = std::time::Instant::now();
+        ingest_date(pool, step.provider, step.source, as_of_date, None, report)
+            .await
+            .context(exit::IngestError)?;
+        report.phases.add(Phase::Ingest, t_ingest.elapsed());
+        let rows = tootoo_core::storage::stock_features::count_features_by_date(
+            pool, as_of_date, as_of_date,
+        )
+        .await?
+        .get(&as_of_date)
+        .copied()
+        .unwrap_or(0);
+        full_run::check_feature_rows(as_of_date, rows as usize, step.min_rows)?;
+        tracing::info!(%as_of_date, rows, min_rows = step.min_rows, "full run: ingest complete");
     }
 
     let universe_opts = universe::UniverseOptions::from_env();
-    let use_stub = std::env::var("TOOTOO_USE_STUB_UNIVERSE").ok().is_some();
-    let candidates = if use_stub {
-        universe::build_candidate_universe_stub(as_of_date, universe_opts)?
+    let t_universe = std::time::Instant::now();
+    let candidates = if use_stub_universe() {
+        universe::build_candidate_universe_stub(as_of_date, universe_opts.clone())?
     } else {
-        universe::build_candidate_universe_db(&pool, as_of_date, universe_opts).await?
+        universe::build_candidate_universe_db(pool, as_of_date, universe_opts.clone()).await?
     };
+    report.phases.add(Phase::Universe, t_universe.elapsed());
+    *report.counts.candidates.get_or_insert(0) += candidates.len();
+
+    let pg_sink = tootoo_core::storage::llm_attempts::PgAttemptSink::new(pool.clone());
+    let run_id = pg_sink.run_id();
+    let (attempt_sink, tally) = report::TallyingSink::new(pg_sink);
+    let llm = tootoo_core::llm::client_for_provider(
+        settings,
+        llm_provider,
+        Some(std::sync::Arc::new(attempt_sink)),
+    )
+    .context(exit::ConfigError)?;
+    // Continuity context only; a lookup failure should not block today's run.
+    let previous =
+        match tootoo_core::storage::recommendations::fetch_latest_success_before(pool, as_of_date)
+            .await
+        {
+            Ok(prev) => prev,
+            Err(e) => {
+                tracing::warn!(%as_of_date, error = %e, "failed to load previous snapshot");
+                None
+            }
+        };
+    match &previous {
+        Some(prev) => tracing::info!(
+            %as_of_date,
+            previous_as_of_date = %prev.as_of_date,
+            "including previous picks in prompt"
+        ),
+        None => {
+            tracing::info!(%as_of_date, "no previous snapshot; prompt has no continuity section")
+        }
+    }
+    // Likewise context only: without index data the prompt just has no market section.
+    let market_context =
+        match tootoo_core::storage::market_index::load_market_context(pool, as_of_date).await {
+            Ok(ctx) => ctx,
+            Err(e) => {
+                tracing::warn!(%as_of_date, error = %e, "failed to load market index context");
+                Default::default()
+            }
+        };
+    if market_context.is_empty() {
+        tracing::info!(%as_of_date, "no market index data; prompt has no market context section");
+    }
+    let input = generate_input(as_of_date, candidates, previous, market_context)?;
+    if let Some(path) = opts.export_universe {
+        export_universe(path, &input, &universe_opts)?;
+    }
 
-    let llm = tootoo_core::llm::anthropic::AnthropicClient::from_settings(&settings)?;
-    let input = tootoo_core::llm::GenerateInput::try_new(as_of_date, candidates)?;
+    if env_flag("PERSIST_UNIVERSE") {
+        match tootoo_core::storage::candidate_universes::persist_candidate_universe(pool, &input)
+            .await
+        {
+            Ok(inserted) => tracing::info!(
+                %as_of_date,
+                digest = %input.universe_digest(),
+                inserted,
+                "persisted candidate universe"
+            ),
+            Err(err) => {
+                tracing::warn!(%as_of_date, error = %err, "failed to persist candidate universe")
+            }
+        }
+    }
 
-    let provider = "anthropic";
-    let llm_result = llm.generate_recommendations_with_raw(input).await;
+    let provider = llm.provider().as_str();
+    let prompt_hash = llm.prompt_hash(&input);
+    let t_llm = std::time::Instant::now();
+    let llm_result = llm
+        .generate_recommendations_with_raw(input.clone())
+        .await
+        .and_then(|(snapshot, raw_json)| {
+            apply_sanity_checks(
+                &input,
+                snapshot,
+                raw_json,
+                llm.provider(),
+                opts.strict_sanity,
+            )
+        });
+    report.phases.add(Phase::Llm, t_llm.elapsed());
+    {
+        let tally = tally.lock().unwrap_or_else(|e| e.into_inner());
+        report.counts.llm_attempts += tally.attempts;
+        if let Some(usage) = tally.usage {
+            report.add_token_usage(usage);
+        }
+    }
+    let t_persist = std::time::Instant::now();
 
     match llm_result {
-        Ok((snapshot, raw_json)) => {
-            match tootoo_core::storage::recommendations::persist_success(
-                &pool,
-                &snapshot,
-                provider,
-                Some(raw_json),
-            )
-            .await
-            {
+        Ok((mut snapshot, raw_json)) => {
+            attach_english_names(&mut snapshot, &input.candidates);
+            let raw_llm_response = Some(input.attach_universe(Some(raw_json)));
+            let persisted = if opts.force {
+                tootoo_core::storage::recommendations::persist_success_replacing(
+                    pool,
+                    &snapshot,
+                    provider,
+                    prompt_hash.as_deref(),
+                    raw_llm_response,
+                    "superseded by worker --force rerun",
+                )
+                .await
+            } else {
+                tootoo_core::storage::recommendations::persist_success(
+                    pool,
+                    &snapshot,
+                    provider,
+                    prompt_hash.as_deref(),
+                    raw_llm_response,
+                )
+                .await
+            };
+            report.phases.add(Phase::Persist, t_persist.elapsed());
+            match persisted {
                 Ok(snapshot_id) => {
+                    // Persisted: an interrupt during the notifications below records nothing.
+                    cancel::end_run();
+                    *report.counts.items.get_or_insert(0) += snapshot.items.len();
                     tracing::info!(%as_of_date, %snapshot_id, "persisted recommendation snapshot");
+                    attach_llm_attempts(pool, run_id, snapshot_id).await;
+                    notify_snapshot(pool, snapshot_id, &snapshot).await;
+                    notify_run(&notifier::RunNotice::success(&snapshot)).await;
+                    Ok(RunOutcome::Persisted(snapshot_id))
                 }
                 Err(e) => {
-                    if is_unique_violation(&e) {
-                        tracing::info!(%as_of_date, "snapshot already exists (unique constraint); treating as no-op");
+                    let exists = e
+                        .downcast_ref::<tootoo_core::storage::recommendations::SuccessSnapshotExists>()
+                        .is_some();
+                    if exists || is_unique_violation(&e) {
+                        cancel::end_run();
+                        tracing::info!(%as_of_date, error = %e, "snapshot already exists; treating as no-op");
+                        Ok(RunOutcome::Skipped)
                     } else {
                         let generated_at = chrono::Utc::now();
-                        let _ = tootoo_core::storage::recommendations::persist_failure(
-                            &pool,
+                        let snapshot_id = tootoo_core::storage::recommendations::persist_failure(
+                            pool,
                             as_of_date,
                             generated_at,
                             provider,
+                            prompt_hash.as_deref(),
                             &format!("persist_success failed: {:#}", e),
                             None,
+                            Some(input.attach_universe(None)),
                         )
-                        .await;
+                        .await
+                        .ok();
+                        cancel::end_run();
 
                         tracing::error!(%as_of_date, error = %e, "persist_success failed");
+                        notify_run(&notifier::RunNotice::failure(as_of_date, None, &e)).await;
+                        Ok(RunOutcome::Failed {
+                            snapshot_id,
+                            error: format!("persist_success failed: {e:#}"),
+                        })
                     }
                 }
             }
@@ -270,25 +2054,340 @@ async fn main() -> anyhow::Result<()> {
                 }
             }
 
+            let error_kind = tootoo_core::llm::error::LlmFailureKind::classify(&err);
             let snapshot_id = tootoo_core::storage::recommendations::persist_failure(
-                &pool,
+                pool,
                 as_of_date,
                 generated_at,
                 provider,
+                prompt_hash.as_deref(),
                 &format!("{:#}", err),
-                raw_llm_response,
+                Some(error_kind),
+                Some(input.attach_universe(raw_llm_response)),
             )
             .await?;
+            cancel::end_run();
+            report.phases.add(Phase::Persist, t_persist.elapsed());
+            attach_llm_attempts(pool, run_id, snapshot_id).await;
+
+            tracing::error!(
+                %as_of_date,
+                %snapshot_id,
+                error_kind = error_kind.as_str(),
+                error = %err,
+                "recommendation run failed"
+            );
+            notify_run(&notifier::RunNotice::failure(
+                as_of_date,
+                Some(error_kind.as_str()),
+                &err,
+            ))
+            .await;
+            Ok(RunOutcome::Failed {
+                snapshot_id: Some(snapshot_id),
+                error: format!("{err:#}"),
+            })
+        }
+    }
+}
+
+fn count_ingest_items(as_of_date: NaiveDate, source: &'static str, items: usize) {
+    metrics::counter!(
+        INGEST_ITEMS_TOTAL,
+        "as_of_date" => as_of_date_label(as_of_date),
+        "source" => source,
+    )
+    .increment(items as u64);
+}
+
+/// `WORKER_DATABASE_URL`, else `DATABASE_URL`.
+fn database_url(settings: &tootoo_core::config::Settings) -> anyhow::Result<String> {
+    // Allow a worker-only override so we can bypass Supabase pooler if needed.
+    Ok(match std::env::var("WORKER_DATABASE_URL") {
+        Ok(v) if !v.trim().is_empty() => v,
+        _ => settings
+            .require_database_url()
+            .context(exit::ConfigError)?
+            .to_string(),
+    })
+}
+
+async fn connect_pool(settings: &tootoo_core::config::Settings) -> anyhow::Result<sqlx::PgPool> {
+    let db_url = database_url(settings)?;
 
-            tracing::error!(%as_of_date, %snapshot_id, error = %err, "recommendation run failed");
+    let mut connect_options = PgConnectOptions::from_str(&db_url)
+        .context("parse DATABASE_URL failed")
+        .context(exit::ConfigError)?;
+    connect_options = connect_options.statement_cache_capacity(0);
+
+    sqlx::postgres::PgPoolOptions::new()
+        .max_connections(5)
+        .connect_with(connect_options)
+        .await
+        .context("connect DATABASE_URL failed")
+}
+
+fn use_stub_universe() -> bool {
+    std::env::var("TOOTOO_USE_STUB_UNIVERSE").ok().is_some()
+}
+
+/// The LLM input exactly as a real run builds it (shared with `--dry-run`).
+fn generate_input(
+    as_of_date: chrono::NaiveDate,
+    candidates: Vec<tootoo_core::domain::recommendation::Candidate>,
+    previous: Option<tootoo_core::domain::recommendation::RecommendationSnapshot>,
+    market_context: std::collections::BTreeMap<String, f64>,
+) -> anyhow::Result<tootoo_core::llm::GenerateInput> {
+    Ok(
+        tootoo_core::llm::GenerateInput::try_new(as_of_date, candidates)?
+            .with_validation(tootoo_core::domain::contract::ValidationOptions::from_env())
+            .with_previous_snapshot(previous)
+            .with_market_context(market_context),
+    )
+}
+
+/// `--dry-run`: builds the real universe and input, then prints the request the LLM client would
+/// send. Reads from the DB (universe, previous snapshot) but never migrates or writes.
+async fn print_prompt(
+    settings: &tootoo_core::config::Settings,
+    llm_provider: tootoo_core::llm::Provider,
+    as_of_date: chrono::NaiveDate,
+    out: Option<&std::path::Path>,
+    export: Option<&std::path::Path>,
+) -> anyhow::Result<()> {
+    let universe_opts = universe::UniverseOptions::from_env();
+    let (candidates, previous, market_context) = if use_stub_universe() {
+        (
+            universe::build_candidate_universe_stub(as_of_date, universe_opts.clone())?,
+            None,
+            Default::default(),
+        )
+    } else {
+        let pool = connect_pool(settings).await?;
+        let candidates =
+            universe::build_candidate_universe_db(&pool, as_of_date, universe_opts.clone()).await?;
+        let previous =
+            tootoo_core::storage::recommendations::fetch_latest_success_before(&pool, as_of_date)
+                .await?;
+        let market_context =
+            tootoo_core::storage::market_index::load_market_context(&pool, as_of_date).await?;
+        (candidates, previous, market_context)
+    };
+
+    let input = generate_input(as_of_date, candidates, previous, market_context)?;
+    if let Some(path) = export {
+        export_universe(path, &input, &universe_opts)?;
+    }
+    let llm = tootoo_core::llm::client_for_provider(settings, llm_provider, None)?;
+    let preview = llm.preview_prompt(&input)?;
+    let text = format_prompt_preview(&preview, llm.prompt_hash(&input).as_deref())?;
+
+    match out {
+        Some(path) => {
+            std::fs::write(path, &text)
+                .with_context(|| format!("failed to write prompt to {}", path.display()))?;
+            tracing::info!(%as_of_date, path = %path.display(), "wrote prompt payload");
         }
+        None => print!("{text}"),
+    }
+    Ok(())
+}
+
+/// Writes `--export-universe`; the format follows the path's extension.
+fn export_universe(
+    path: &std::path::Path,
+    input: &tootoo_core::llm::GenerateInput,
+    universe_opts: &universe::UniverseOptions,
+) -> anyhow::Result<()> {
+    let options = serde_json::json!({
+        "size": universe_opts.size,
+        "min_trading_value": universe_opts.min_trading_value,
+        "min_market_cap": universe_opts.min_market_cap,
+        "include_flagged": universe_opts.include_flagged,
+        "include_preferred": universe_opts.include_preferred,
+        "oversample": universe_opts.oversample,
+        "stub": use_stub_universe(),
+    });
+    let text = UniverseExport::from_input(input, options).render(ExportFormat::from_path(path)?)?;
+    std::fs::write(path, text)
+        .with_context(|| format!("failed to write universe export to {}", path.display()))?;
+    tracing::info!(
+        as_of_date = %input.as_of_date,
+        path = %path.display(),
+        candidates = input.universe_size(),
+        "wrote candidate universe export"
+    );
+    Ok(())
+}
+
+/// Tickers fetched by `--ingest-kis --dry-run` when `KIS_MAX_TICKERS` is unset.
+const KIS_DRY_RUN_MAX_TICKERS: usize = 20;
+
+/// Fetches a capped KIS universe and prints what would be upserted. No DB connection is made,
+/// so the access token is neither read from nor written to the DB cache.
+async fn print_kis_dry_run(
+    settings: &tootoo_core::config::Settings,
+    as_of_date: NaiveDate,
+) -> anyhow::Result<()> {
+    let mut kis = tootoo_core::ingest::kis::KisClient::from_settings_prod(settings)?;
+    if std::env::var("KIS_MAX_TICKERS").is_err() {
+        kis = kis.with_max_tickers(KIS_DRY_RUN_MAX_TICKERS);
     }
+    // Through the provider trait, so the printout is what the feature validator would let through.
+    let (resp, raw_json) =
+        tootoo_core::ingest::provider::DataProviderClient::fetch_daily_features(&kis, as_of_date)
+            .await?;
 
-    let _ =
-        tootoo_core::storage::lock::release_as_of_date_lock_conn(&mut *lock_conn, as_of_date).await;
+    println!("{}", serde_json::to_string_pretty(&raw_json)?);
+    for item in &resp.items {
+        println!(
+            "{}  {}  trading_value={}  features={}",
+            item.ticker,
+            item.name,
+            item.trading_value
+                .map_or_else(|| "-".to_string(), |v| v.to_string()),
+            item.features.len()
+        );
+    }
     Ok(())
 }
 
+fn format_prompt_preview(
+    preview: &tootoo_core::llm::PromptPreview,
+    prompt_hash: Option<&str>,
+) -> anyhow::Result<String> {
+    Ok(format!(
+        "=== SYSTEM PROMPT ===\n{}\n\n=== USER PROMPT ===\n{}\n\n=== TOOL SCHEMA ===\n{}\n\n\
+=== ESTIMATE ===\nprovider={} model={} prompt_hash={} estimated_input_tokens={}\n",
+        preview.system_prompt,
+        preview.user_prompt,
+        serde_json::to_string_pretty(&preview.tool_schema)?,
+        preview.provider.as_str(),
+        preview.model,
+        prompt_hash.unwrap_or("-"),
+        preview.estimated_tokens(),
+    ))
+}
+
+/// Cross-checks the picks against candidate features. Flags are appended to `risk_notes`, or turned
+/// into an `LlmDiagnosticsError` (stage `sanity`) under `--strict-sanity`.
+fn apply_sanity_checks(
+    input: &tootoo_core::llm::GenerateInput,
+    mut snapshot: tootoo_core::domain::recommendation::RecommendationSnapshot,
+    raw_json: serde_json::Value,
+    provider: tootoo_core::llm::Provider,
+    strict: bool,
+) -> anyhow::Result<(
+    tootoo_core::domain::recommendation::RecommendationSnapshot,
+    serde_json::Value,
+)> {
+    use tootoo_core::llm::sanity;
+
+    let flags = sanity::check(input, &snapshot, &sanity::SanityOptions::from_env());
+    tracing::info!(
+        as_of_date = %input.as_of_date,
+        flagged = flags.len(),
+        by_kind = ?sanity::summarize(&flags),
+        strict,
+        "sanity check summary"
+    );
+    if flags.is_empty() {
+        return Ok((snapshot, raw_json));
+    }
+
+    if strict {
+        let detail = flags
+            .iter()
+            .map(|f| format!("{}: {}", f.ticker, f.message))
+            .collect::<Vec<_>>()
+            .join("; ");
+        return Err(tootoo_core::llm::error::LlmDiagnosticsError {
+            provider,
+            stage: "sanity",
+            detail,
+            raw_output: None,
+            raw_response_json: Some(raw_json),
+            http_status: None,
+        }
+        .into());
+    }
+
+    sanity::annotate(&mut snapshot, &flags);
+    Ok((snapshot, raw_json))
+}
+
+/// Best-effort: the attempts stay queryable by `run_id` even if linking fails.
+/// Copies `name_en` from the candidates (i.e. `stock_features_daily`) onto the picks. The model
+/// is never asked for it, so whatever it returned is overwritten.
+fn attach_english_names(
+    snapshot: &mut tootoo_core::domain::recommendation::RecommendationSnapshot,
+    candidates: &[tootoo_core::domain::recommendation::Candidate],
+) {
+    let by_ticker: std::collections::HashMap<&str, Option<&String>> = candidates
+        .iter()
+        .map(|c| (c.ticker.as_str(), c.name_en.as_ref()))
+        .collect();
+    for item in &mut snapshot.items {
+        item.name_en = by_ticker
+            .get(item.ticker.as_str())
+            .copied()
+            .flatten()
+            .cloned();
+    }
+}
+
+async fn attach_llm_attempts(pool: &sqlx::PgPool, run_id: uuid::Uuid, snapshot_id: uuid::Uuid) {
+    if let Err(err) =
+        tootoo_core::storage::llm_attempts::attach_llm_attempts(pool, run_id, snapshot_id).await
+    {
+        tracing::warn!(%run_id, %snapshot_id, error = %err, "failed to link LLM attempts");
+    }
+}
+
+/// Best-effort: posts to `SNAPSHOT_WEBHOOK_URLS` and records the attempts; never fails the run.
+async fn notify_snapshot(
+    pool: &sqlx::PgPool,
+    snapshot_id: uuid::Uuid,
+    snapshot: &tootoo_core::domain::recommendation::RecommendationSnapshot,
+) {
+    let notifier = match tootoo_core::notify::WebhookNotifier::from_env() {
+        Ok(Some(notifier)) => notifier,
+        Ok(None) => return,
+        Err(err) => {
+            tracing::warn!(%snapshot_id, error = %err, "webhooks misconfigured; skipping");
+            return;
+        }
+    };
+    let payload = tootoo_core::notify::SnapshotPersisted::new(snapshot_id, snapshot);
+    let attempts = notifier.deliver(&payload).await;
+    if let Err(err) = tootoo_core::storage::webhook_deliveries::record_webhook_deliveries(
+        pool,
+        snapshot_id,
+        &attempts,
+    )
+    .await
+    {
+        tracing::warn!(%snapshot_id, error = %err, "failed to record webhook deliveries");
+    }
+}
+
+/// Best-effort: posts the run outcome to Slack/Telegram when configured; never fails the run.
+async fn notify_run(notice: &notifier::RunNotice) {
+    match notifier::Notifier::from_env() {
+        Ok(Some(notifier)) => {
+            notifier.send(notice).await;
+        }
+        Ok(None) => {}
+        Err(err) => tracing::warn!(error = %err, "run notifier misconfigured; skipping"),
+    }
+}
+
+fn env_flag(key: &str) -> bool {
+    std::env::var(key)
+        .map(|s| matches!(s.trim().to_ascii_lowercase().as_str(), "1" | "true"))
+        .unwrap_or(false)
+}
+
 fn is_unique_violation(err: &anyhow::Error) -> bool {
     let Some(sqlx_err) = err.downcast_ref::<sqlx::Error>() else {
         return false;
@@ -317,7 +2416,9 @@ async fn success_snapshot_exists(
     as_of_date: chrono::NaiveDate,
 ) -> anyhow::Result<bool> {
     let exists: Option<(i32,)> = sqlx::query_as(
-        "SELECT 1 FROM recommendation_snapshots WHERE status = 'success' AND as_of_date = $1 LIMIT 1",
+        "SELECT 1 FROM current_recommendation_snapshots \
+         WHERE status = 'success' AND as_of_date = $1 \
+         LIMIT 1",
     )
     .persistent(false)
     .bind(as_of_date)
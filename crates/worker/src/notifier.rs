@@ -0,0 +1,457 @@
+//! Chat notifications after each recommendation run, so failed runs are noticed the same day.
+//!
+//! `SLACK_WEBHOOK_URL` gets a Slack incoming-webhook message; `TELEGRAM_BOT_TOKEN` plus
+//! `TELEGRAM_CHAT_ID` get a Bot API `sendMessage` (against `TELEGRAM_API_URL` when set).
+//! Delivery is best-effort: failures are logged and never fail the run.
+
+use anyhow::Context;
+use chrono::NaiveDate;
+use std::time::Duration;
+use tootoo_core::domain::recommendation::RecommendationSnapshot;
+
+const TELEGRAM_API: &str = "https://api.telegram.org";
+const TIMEOUT_SECS: u64 = 10;
+const RETRIES: u32 = 3;
+const BACKOFF: Duration = Duration::from_secs(1);
+const TOP_TICKERS: usize = 3;
+/// Longer errors are cut; the full text is in the failure snapshot and Sentry.
+const MAX_ERROR_CHARS: usize = 300;
+
+/// What one run reports to chat.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunNotice {
+    pub as_of_date: NaiveDate,
+    pub outcome: NoticeOutcome,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum NoticeOutcome {
+    /// `ticker name`, best rank first.
+    Success { top: Vec<String> },
+    Failure {
+        /// `LlmFailureKind` label, when the failure came from the LLM call.
+        error_kind: Option<&'static str>,
+        error: String,
+        /// Where to look the failure up, e.g. a Sentry event id or link.
+        sentry_hint: Option<String>,
+    },
+}
+
+impl RunNotice {
+    pub fn success(snapshot: &RecommendationSnapshot) -> Self {
+        let mut ranked: Vec<_> = snapshot.items.iter().collect();
+        ranked.sort_by_key(|item| item.rank);
+        Self {
+            as_of_date: snapshot.as_of_date,
+            outcome: NoticeOutcome::Success {
+                top: ranked
+                    .into_iter()
+                    .take(TOP_TICKERS)
+                    .map(|item| format!("{} {}", item.ticker, item.name))
+                    .collect(),
+            },
+        }
+    }
+
+    pub fn failure(
+        as_of_date: NaiveDate,
+        error_kind: Option<&'static str>,
+        error: &anyhow::Error,
+    ) -> Self {
+        let error = format!("{error:#}");
+        let mut error: String = error.lines().next().unwrap_or_default().to_string();
+        if error.chars().count() > MAX_ERROR_CHARS {
+            error = error.chars().take(MAX_ERROR_CHARS).collect::<String>() + "…";
+        }
+        Self {
+            as_of_date,
+            outcome: NoticeOutcome::Failure {
+                error_kind,
+                error,
+                sentry_hint: sentry_hint(),
+            },
+        }
+    }
+
+    /// Slack `mrkdwn`.
+    pub fn slack_text(&self) -> String {
+        match &self.outcome {
+            NoticeOutcome::Success { top } => format!(
+                ":white_check_mark: *tootoo* {} recommendation run succeeded\nTop {}: {}",
+                self.as_of_date,
+                top.len(),
+                top.join(", ")
+            ),
+            NoticeOutcome::Failure {
+                error_kind,
+                error,
+                sentry_hint,
+            } => {
+                let mut text = format!(
+                    ":rotating_light: *tootoo* {} recommendation run failed (`{}`)\n```{}```",
+                    self.as_of_date,
+                    error_kind.unwrap_or("error"),
+                    error.replace("```", "'''")
+                );
+                if let Some(hint) = sentry_hint {
+                    text.push_str(&format!("\nSentry: {hint}"));
+                }
+                text
+            }
+        }
+    }
+
+    /// Plain text (no `parse_mode`, so nothing in the error needs escaping).
+    pub fn telegram_text(&self) -> String {
+        match &self.outcome {
+            NoticeOutcome::Success { top } => format!(
+                "[OK] tootoo {} recommendation run succeeded\nTop {}: {}",
+                self.as_of_date,
+                top.len(),
+                top.join(", ")
+            ),
+            NoticeOutcome::Failure {
+                error_kind,
+                error,
+                sentry_hint,
+            } => {
+                let mut text = format!(
+                    "[FAILED] tootoo {} recommendation run failed ({})\n{}",
+                    self.as_of_date,
+                    error_kind.unwrap_or("error"),
+                    error
+                );
+                if let Some(hint) = sentry_hint {
+                    text.push_str(&format!("\nSentry: {hint}"));
+                }
+                text
+            }
+        }
+    }
+}
+
+/// The last captured Sentry event, as a link when `SENTRY_ISSUES_URL` is set (the event id is
+/// appended as a search query).
+fn sentry_hint() -> Option<String> {
+    let event_id = sentry::last_event_id()?;
+    Some(match std::env::var("SENTRY_ISSUES_URL") {
+        Ok(base) if !base.trim().is_empty() => format!("{}?query={event_id}", base.trim()),
+        _ => format!("event {event_id}"),
+    })
+}
+
+/// Result of delivering one notice to one channel.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Delivery {
+    pub channel: &'static str,
+    pub attempts: u32,
+    pub status_code: Option<u16>,
+    pub error: Option<String>,
+    pub delivered: bool,
+}
+
+#[derive(Debug, Clone)]
+struct Telegram {
+    api: String,
+    token: String,
+    chat_id: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Notifier {
+    http: reqwest::Client,
+    slack_webhook_url: Option<String>,
+    telegram: Option<Telegram>,
+    backoff: Duration,
+}
+
+impl Notifier {
+    /// `None` when no channel is configured.
+    pub fn from_env() -> anyhow::Result<Option<Self>> {
+        let var = |key: &str| std::env::var(key).ok().filter(|s| !s.trim().is_empty());
+        let slack = var("SLACK_WEBHOOK_URL");
+        let telegram = match (var("TELEGRAM_BOT_TOKEN"), var("TELEGRAM_CHAT_ID")) {
+            (Some(token), Some(chat_id)) => Some((token, chat_id)),
+            (None, None) => None,
+            _ => {
+                tracing::warn!(
+                    "TELEGRAM_BOT_TOKEN and TELEGRAM_CHAT_ID must both be set; Telegram disabled"
+                );
+                None
+            }
+        };
+        if slack.is_none() && telegram.is_none() {
+            return Ok(None);
+        }
+        let notifier = Self::new(slack, telegram)?;
+        Ok(Some(match var("TELEGRAM_API_URL") {
+            Some(api) => notifier.with_telegram_api(api.trim_end_matches('/')),
+            None => notifier,
+        }))
+    }
+
+    pub fn new(
+        slack_webhook_url: Option<String>,
+        telegram: Option<(String, String)>,
+    ) -> anyhow::Result<Self> {
+        if let Some(url) = &slack_webhook_url {
+            reqwest::Url::parse(url).context("invalid SLACK_WEBHOOK_URL")?;
+        }
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(TIMEOUT_SECS))
+            .build()
+            .context("failed to build notifier http client")?;
+        Ok(Self {
+            http,
+            slack_webhook_url,
+            telegram: telegram.map(|(token, chat_id)| Telegram {
+                api: TELEGRAM_API.to_string(),
+                token,
+                chat_id,
+            }),
+            backoff: BACKOFF,
+        })
+    }
+
+    /// Bot API base URL, e.g. a self-hosted Bot API server.
+    pub fn with_telegram_api(mut self, api: impl Into<String>) -> Self {
+        if let Some(telegram) = &mut self.telegram {
+            telegram.api = api.into();
+        }
+        self
+    }
+
+    /// First retry delay; doubles on each further retry.
+    #[cfg(test)]
+    pub fn with_backoff(mut self, backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Sends `notice` to every configured channel, retrying transport errors, 429 and 5xx up to
+    /// 3 times. Never fails: each channel's outcome is logged and returned.
+    pub async fn send(&self, notice: &RunNotice) -> Vec<Delivery> {
+        let mut out = Vec::new();
+        if let Some(url) = &self.slack_webhook_url {
+            let body = serde_json::json!({ "text": notice.slack_text() });
+            out.push(self.post("slack", url, &body).await);
+        }
+        if let Some(telegram) = &self.telegram {
+            let url = format!("{}/bot{}/sendMessage", telegram.api, telegram.token);
+            let body = serde_json::json!({
+                "chat_id": telegram.chat_id,
+                "text": notice.telegram_text(),
+                "disable_web_page_preview": true,
+            });
+            out.push(self.post("telegram", &url, &body).await);
+        }
+        for delivery in &out {
+            if delivery.delivered {
+                tracing::info!(
+                    channel = delivery.channel,
+                    as_of_date = %notice.as_of_date,
+                    "run notification sent"
+                );
+            } else {
+                tracing::warn!(
+                    channel = delivery.channel,
+                    as_of_date = %notice.as_of_date,
+                    attempts = delivery.attempts,
+                    status = ?delivery.status_code,
+                    error = ?delivery.error,
+                    "run notification failed"
+                );
+            }
+        }
+        out
+    }
+
+    /// Both URLs carry credentials, so errors are stripped of them before logging.
+    async fn post(&self, channel: &'static str, url: &str, body: &serde_json::Value) -> Delivery {
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            let (status_code, error, retryable) = match self.http.post(url).json(body).send().await
+            {
+                Ok(res) if res.status().is_success() => (Some(res.status().as_u16()), None, false),
+                Ok(res) => {
+                    let status = res.status();
+                    let retryable = status.is_server_error()
+                        || status == reqwest::StatusCode::TOO_MANY_REQUESTS;
+                    (
+                        Some(status.as_u16()),
+                        Some(format!("HTTP {status}")),
+                        retryable,
+                    )
+                }
+                Err(err) => (None, Some(err.without_url().to_string()), true),
+            };
+            if error.is_none() || !retryable || attempts > RETRIES {
+                return Delivery {
+                    channel,
+                    attempts,
+                    status_code,
+                    delivered: error.is_none(),
+                    error,
+                };
+            }
+            let backoff = self.backoff * (1 << (attempts - 1));
+            tracing::debug!(channel, attempts, ?backoff, "retrying run notification");
+            tokio::time::sleep(backoff).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::StatusCode;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use tootoo_core::domain::recommendation::RecommendationItem;
+
+    type Received = Arc<Mutex<Vec<(String, serde_json::Value)>>>;
+
+    fn date() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 10, 16).unwrap()
+    }
+
+    fn snapshot() -> RecommendationSnapshot {
+        RecommendationSnapshot {
+            as_of_date: date(),
+            generated_at: chrono::Utc::now(),
+            items: (1..=20)
+                .rev()
+                .map(|rank| RecommendationItem {
+                    rank,
+                    ticker: format!("{rank:06}"),
+                    name: format!("Stock {rank}"),
+                    name_en: None,
+                    rationale: ["a".into(), "b".into(), "c".into()],
+                    risk_notes: None,
+                    confidence: None,
+                })
+                .collect(),
+        }
+    }
+
+    /// Records `(path, json body)` of every request; the first `failures` get a 503.
+    async fn serve_stub(failures: usize) -> (String, Received) {
+        let received: Received = Arc::new(Mutex::new(Vec::new()));
+        let hits = Arc::new(AtomicUsize::new(0));
+        let recorded = received.clone();
+        let app = axum::Router::new().fallback(axum::routing::post(
+            move |uri: axum::http::Uri, body: axum::body::Bytes| {
+                let recorded = recorded.clone();
+                let hits = hits.clone();
+                async move {
+                    if hits.fetch_add(1, Ordering::SeqCst) < failures {
+                        return StatusCode::SERVICE_UNAVAILABLE;
+                    }
+                    let json = serde_json::from_slice(&body).unwrap();
+                    recorded
+                        .lock()
+                        .unwrap()
+                        .push((uri.path().to_string(), json));
+                    StatusCode::OK
+                }
+            },
+        ));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        (format!("http://{addr}"), received)
+    }
+
+    fn notifier(base: &str) -> Notifier {
+        Notifier::new(
+            Some(format!("{base}/services/T000/B000/secret")),
+            Some(("123:abc".to_string(), "-1001".to_string())),
+        )
+        .unwrap()
+        .with_telegram_api(base)
+        .with_backoff(Duration::from_millis(5))
+    }
+
+    #[tokio::test]
+    async fn success_payloads_list_top_three() {
+        let (base, received) = serve_stub(0).await;
+        let deliveries = notifier(&base).send(&RunNotice::success(&snapshot())).await;
+        assert!(deliveries.iter().all(|d| d.delivered && d.attempts == 1));
+
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 2);
+        let (path, slack) = &received[0];
+        assert_eq!(path, "/services/T000/B000/secret");
+        assert_eq!(
+            slack,
+            &serde_json::json!({
+                "text": ":white_check_mark: *tootoo* 2026-10-16 recommendation run succeeded\nTop 3: 000001 Stock 1, 000002 Stock 2, 000003 Stock 3"
+            })
+        );
+        let (path, telegram) = &received[1];
+        assert_eq!(path, "/bot123:abc/sendMessage");
+        assert_eq!(telegram["chat_id"], "-1001");
+        assert_eq!(
+            telegram["text"],
+            "[OK] tootoo 2026-10-16 recommendation run succeeded\nTop 3: 000001 Stock 1, 000002 Stock 2, 000003 Stock 3"
+        );
+    }
+
+    #[tokio::test]
+    async fn failure_payloads_carry_kind_error_and_hint() {
+        let (base, received) = serve_stub(0).await;
+        let notice = RunNotice {
+            as_of_date: date(),
+            outcome: NoticeOutcome::Failure {
+                error_kind: Some("rate_limited"),
+                error: "HTTP 429 from anthropic".to_string(),
+                sentry_hint: Some("event 0123abcd".to_string()),
+            },
+        };
+        notifier(&base).send(&notice).await;
+
+        let received = received.lock().unwrap();
+        assert_eq!(
+            received[0].1["text"],
+            ":rotating_light: *tootoo* 2026-10-16 recommendation run failed (`rate_limited`)\n```HTTP 429 from anthropic```\nSentry: event 0123abcd"
+        );
+        assert_eq!(
+            received[1].1["text"],
+            "[FAILED] tootoo 2026-10-16 recommendation run failed (rate_limited)\nHTTP 429 from anthropic\nSentry: event 0123abcd"
+        );
+    }
+
+    #[tokio::test]
+    async fn retries_5xx_and_never_fails() {
+        let (base, received) = serve_stub(2).await;
+        let only_slack = Notifier::new(Some(format!("{base}/hook")), None)
+            .unwrap()
+            .with_backoff(Duration::from_millis(5));
+        let deliveries = only_slack.send(&RunNotice::success(&snapshot())).await;
+        assert_eq!(deliveries.len(), 1);
+        assert_eq!(deliveries[0].attempts, 3);
+        assert!(deliveries[0].delivered);
+        assert_eq!(received.lock().unwrap().len(), 1);
+
+        let unreachable = Notifier::new(Some("http://127.0.0.1:1/hook".to_string()), None)
+            .unwrap()
+            .with_backoff(Duration::from_millis(5));
+        let deliveries = unreachable.send(&RunNotice::success(&snapshot())).await;
+        assert_eq!(deliveries[0].attempts, 4, "1 attempt + 3 retries");
+        assert!(!deliveries[0].delivered);
+    }
+
+    #[test]
+    fn failure_error_is_first_line_and_truncated() {
+        let err = anyhow::anyhow!("{}\nsecond line", "x".repeat(400)).context("llm call");
+        let notice = RunNotice::failure(date(), None, &err);
+        let NoticeOutcome::Failure { error, .. } = notice.outcome else {
+            panic!("expected failure");
+        };
+        assert!(error.starts_with("llm call: xxx"));
+        assert_eq!(error.chars().count(), MAX_ERROR_CHARS + 1);
+    }
+}
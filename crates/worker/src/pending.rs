@@ -0,0 +1,79 @@
+use chrono::NaiveDate;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// A trading day without a valid success snapshot (`--list-pending`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PendingDate {
+    pub as_of_date: NaiveDate,
+    pub features_rows: i64,
+    /// Latest snapshot status (`error`), or `none` when the date was never run.
+    pub snapshot_status: String,
+}
+
+/// Trading days from `days` that lack a success snapshot, in order.
+pub fn pending_dates(
+    days: &[NaiveDate],
+    features_rows: &BTreeMap<NaiveDate, i64>,
+    snapshot_status: &BTreeMap<NaiveDate, String>,
+) -> Vec<PendingDate> {
+    days.iter()
+        .filter(|d| snapshot_status.get(d).map(String::as_str) != Some("success"))
+        .map(|d| PendingDate {
+            as_of_date: *d,
+            features_rows: features_rows.get(d).copied().unwrap_or(0),
+            snapshot_status: snapshot_status
+                .get(d)
+                .cloned()
+                .unwrap_or_else(|| "none".to_string()),
+        })
+        .collect()
+}
+
+pub fn format_table(pending: &[PendingDate]) -> String {
+    let mut out = format!(
+        "{:<10}  {:>13}  {}\n",
+        "date", "features_rows", "snapshot_status"
+    );
+    for p in pending {
+        out.push_str(&format!(
+            "{}  {:>13}  {}\n",
+            p.as_of_date, p.features_rows, p.snapshot_status
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lists_days_without_success() {
+        let d = |day| NaiveDate::from_ymd_opt(2026, 1, day).unwrap();
+        let days = [d(5), d(6), d(7)];
+        let features = BTreeMap::from([(d(5), 2500), (d(6), 2480)]);
+        let status = BTreeMap::from([(d(5), "success".to_string()), (d(6), "error".to_string())]);
+
+        let pending = pending_dates(&days, &features, &status);
+        assert_eq!(
+            pending,
+            vec![
+                PendingDate {
+                    as_of_date: d(6),
+                    features_rows: 2480,
+                    snapshot_status: "error".to_string(),
+                },
+                PendingDate {
+                    as_of_date: d(7),
+                    features_rows: 0,
+                    snapshot_status: "none".to_string(),
+                },
+            ]
+        );
+        assert_eq!(
+            format_table(&pending).lines().nth(1),
+            Some("2026-01-06           2480  error")
+        );
+    }
+}
@@ -0,0 +1,100 @@
+use std::sync::{Arc, Mutex};
+use tootoo_core::llm::attempts::{AttemptSink, LlmAttempt};
+use tootoo_core::report::{RunReport, RunStatus, TokenUsage};
+
+/// Owns the run's [`RunReport`] and emits it when dropped, so the report is logged (and written
+/// to `--report-path`) on every exit path, including `?` early returns and panics.
+pub struct ReportGuard {
+    path: Option<std::path::PathBuf>,
+    report: RunReport,
+}
+
+impl ReportGuard {
+    pub fn new(path: Option<std::path::PathBuf>, report: RunReport) -> Self {
+        Self { path, report }
+    }
+
+    pub fn report(&mut self) -> &mut RunReport {
+        &mut self.report
+    }
+
+    /// Records the final status; anything not finished by drop time is reported as `aborted`.
+    pub fn finish(&mut self, status: RunStatus, error: Option<String>) {
+        self.report.status = status;
+        if error.is_some() {
+            self.report.error = error;
+        }
+    }
+}
+
+impl Drop for ReportGuard {
+    fn drop(&mut self) {
+        self.report.finished_at = Some(chrono::Utc::now());
+        let json = match serde_json::to_string(&self.report) {
+            Ok(json) => json,
+            Err(e) => {
+                tracing::warn!(error = %e, "failed to serialize run report");
+                return;
+            }
+        };
+        tracing::info!(report = %json, "worker run report");
+
+        let Some(path) = &self.path else {
+            return;
+        };
+        // Write-then-rename so a reader never sees a half-written report.
+        let tmp = path.with_extension("tmp");
+        let res = std::fs::write(&tmp, &json).and_then(|()| std::fs::rename(&tmp, path));
+        if let Err(e) = res {
+            tracing::warn!(path = %path.display(), error = %e, "failed to write run report");
+        }
+    }
+}
+
+/// Attempts and token usage seen by a [`TallyingSink`].
+#[derive(Debug, Default)]
+pub struct AttemptTally {
+    pub attempts: u32,
+    pub usage: Option<TokenUsage>,
+}
+
+/// Forwards every attempt to `inner` and counts it (with any token usage) for the run report.
+#[derive(Debug)]
+pub struct TallyingSink<S> {
+    inner: S,
+    tally: Arc<Mutex<AttemptTally>>,
+}
+
+impl<S> TallyingSink<S> {
+    pub fn new(inner: S) -> (Self, Arc<Mutex<AttemptTally>>) {
+        let tally = Arc::new(Mutex::new(AttemptTally::default()));
+        (
+            Self {
+                inner,
+                tally: tally.clone(),
+            },
+            tally,
+        )
+    }
+}
+
+#[async_trait::async_trait]
+impl<S: AttemptSink> AttemptSink for TallyingSink<S> {
+    async fn record(&self, attempt: LlmAttempt) {
+        {
+            let mut tally = self.tally.lock().unwrap_or_else(|e| e.into_inner());
+            tally.attempts += 1;
+            if let Some(usage) = attempt
+                .raw_response
+                .as_ref()
+                .and_then(TokenUsage::from_raw_response)
+            {
+                tally
+                    .usage
+                    .get_or_insert_with(TokenUsage::default)
+                    .add(usage);
+            }
+        }
+        self.inner.record(attempt).await;
+    }
+}
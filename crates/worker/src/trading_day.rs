@@ -0,0 +1,79 @@
+//! Early trading-day check for one-shot single-date runs, so a scheduler misfire on a KRX holiday
+//! exits before the KIS master download and per-ticker fetches.
+
+use chrono::NaiveDate;
+use tootoo_core::time::kr_market;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Gate {
+    Run,
+    /// `day` is not a trading day but `--allow-non-trading-day` was passed.
+    RunAnyway {
+        day: NaiveDate,
+    },
+    /// `day` is not a trading day; exit with [`crate::exit::NON_TRADING_DAY`].
+    Refuse {
+        day: NaiveDate,
+    },
+}
+
+/// Checks the day a run is for. An explicit `--as-of-date` is checked as given. A resolved date
+/// always rolls back to a trading day, so a scheduled run checks `today_kst` instead: a cron
+/// firing on a holiday would otherwise redo the previous trading day.
+pub fn check(as_of_date: NaiveDate, explicit: bool, today_kst: NaiveDate, allow: bool) -> Gate {
+    let day = if explicit { as_of_date } else { today_kst };
+    if kr_market::is_trading_day(day) {
+        Gate::Run
+    } else if allow {
+        Gate::RunAnyway { day }
+    } else {
+        Gate::Refuse { day }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn d(month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, month, day).unwrap()
+    }
+
+    #[test]
+    fn scheduled_run_on_a_weekend_is_refused() {
+        // Saturday 2026-01-03 resolves to Friday 01-02, but nothing traded today.
+        assert_eq!(
+            check(d(1, 2), false, d(1, 3), false),
+            Gate::Refuse { day: d(1, 3) }
+        );
+        assert_eq!(
+            check(d(1, 2), false, d(1, 3), true),
+            Gate::RunAnyway { day: d(1, 3) }
+        );
+        // Monday morning before the close cutoff resolves to Friday; that is a real run.
+        assert_eq!(check(d(1, 2), false, d(1, 5), false), Gate::Run);
+    }
+
+    #[test]
+    fn scheduled_run_on_a_configured_holiday_is_refused() {
+        // Christmas 2026 is a Friday.
+        assert_eq!(
+            check(d(12, 24), false, d(12, 25), false),
+            Gate::Refuse { day: d(12, 25) }
+        );
+    }
+
+    #[test]
+    fn explicit_date_is_checked_as_given() {
+        // Backfilling a trading day on a weekend is fine.
+        assert_eq!(check(d(1, 2), true, d(1, 3), false), Gate::Run);
+        assert_eq!(
+            check(d(1, 1), true, d(1, 5), false),
+            Gate::Refuse { day: d(1, 1) }
+        );
+        assert_eq!(
+            check(d(1, 4), true, d(1, 5), true),
+            Gate::RunAnyway { day: d(1, 4) }
+        );
+    }
+}
@@ -1,6 +1,7 @@
 use chrono::{Datelike, NaiveDate};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use tootoo_core::domain::recommendation::Candidate;
+use tootoo_core::storage::stock_features::json_to_feature_map;
 
 #[derive(Debug, Clone)]
 pub struct UniverseOptions {
@@ -10,6 +11,15 @@ pub struct UniverseOptions {
     /// Optional placeholder for a future liquidity filter.
     pub min_trading_value: Option<f64>,
 
+    /// Minimum market cap (KRW). Rows without one are excluded while it is set.
+    pub min_market_cap: Option<f64>,
+
+    /// Keep rows with `status_flags` (managed, halted, warning); excluded by default.
+    pub include_flagged: bool,
+
+    /// Keep both listings of a common/preferred pair; by default only the more liquid one stays.
+    pub include_preferred: bool,
+
     /// Oversampling factor for the initial liquidity screen.
     /// We fetch (size * oversample) rows by trading value, then rescore and select top `size`.
     pub oversample: usize,
@@ -20,6 +30,9 @@ impl Default for UniverseOptions {
         Self {
             size: 200,
             min_trading_value: None,
+            min_market_cap: None,
+            include_flagged: false,
+            include_preferred: false,
             oversample: 5,
         }
     }
@@ -41,6 +54,20 @@ impl UniverseOptions {
             }
         }
 
+        if let Ok(s) = std::env::var("UNIVERSE_MIN_MARKET_CAP") {
+            if let Ok(n) = s.parse::<f64>() {
+                out.min_market_cap = Some(n);
+            }
+        }
+
+        if let Ok(s) = std::env::var("UNIVERSE_INCLUDE_FLAGGED") {
+            out.include_flagged = matches!(s.trim().to_ascii_lowercase().as_str(), "1" | "true");
+        }
+
+        if let Ok(s) = std::env::var("UNIVERSE_INCLUDE_PREFERRED") {
+            out.include_preferred = matches!(s.trim().to_ascii_lowercase().as_str(), "1" | "true");
+        }
+
         if let Ok(s) = std::env::var("UNIVERSE_OVERSAMPLE") {
             if let Ok(n) = s.parse::<usize>() {
                 out.oversample = n;
@@ -77,6 +104,8 @@ pub fn build_candidate_universe_stub(
         out.push(Candidate {
             ticker: format!("KRX:{i:06}"),
             name: format!("Stub {i:06}"),
+            name_en: None,
+            sector: None,
             features,
         });
     }
@@ -84,6 +113,26 @@ pub fn build_candidate_universe_stub(
     Ok(out)
 }
 
+/// Too few eligible `stock_features_daily` rows to fill the universe for a date.
+#[derive(Debug)]
+pub struct InsufficientUniverse {
+    pub as_of_date: NaiveDate,
+    pub expected: usize,
+    pub got: usize,
+}
+
+impl std::fmt::Display for InsufficientUniverse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "insufficient candidates for as_of_date={} after ETF/ETN, flagged-stock and preferred-share exclusion: expected at least {}, got {}",
+            self.as_of_date, self.expected, self.got
+        )
+    }
+}
+
+impl std::error::Error for InsufficientUniverse {}
+
 pub async fn build_candidate_universe_db(
     pool: &sqlx::PgPool,
     as_of_date: NaiveDate,
@@ -98,56 +147,106 @@ pub async fn build_candidate_universe_db(
     anyhow::ensure!(opts.oversample >= 1, "UNIVERSE_OVERSAMPLE must be >= 1");
     let limit = (opts.size.saturating_mul(opts.oversample)).max(opts.size);
 
-    let rows = match opts.min_trading_value {
-        Some(min_tv) => {
-            sqlx::query_as::<_, (String, String, serde_json::Value, Option<f64>)>(
-                "SELECT ticker, name, features, trading_value \
-                 FROM stock_features_daily \
-                 WHERE as_of_date = $1 AND trading_value IS NOT NULL AND trading_value >= $2 \
-                 ORDER BY trading_value DESC NULLS LAST, ticker ASC \
-                 LIMIT $3",
-            )
-            .persistent(false)
-            .bind(as_of_date)
-            .bind(min_tv)
-            .bind(limit as i64)
-            .fetch_all(pool)
-            .await?
-        }
-        None => {
-            sqlx::query_as::<_, (String, String, serde_json::Value, Option<f64>)>(
-                "SELECT ticker, name, features, trading_value \
-                 FROM stock_features_daily \
-                 WHERE as_of_date = $1 \
-                 ORDER BY trading_value DESC NULLS LAST, ticker ASC \
-                 LIMIT $2",
-            )
-            .persistent(false)
-            .bind(as_of_date)
-            .bind(limit as i64)
-            .fetch_all(pool)
-            .await?
-        }
-    };
-
-    // Filter out ETFs/ETNs (we only want single-name equities).
-    // KIS master does not currently provide an explicit instrument type, so use a conservative
-    // name-based heuristic.
+    // NULL thresholds disable their filter.
+    let rows = sqlx::query_as::<
+        _,
+        (
+            String,
+            String,
+            Option<String>,
+            Option<String>,
+            Option<String>,
+            serde_json::Value,
+            Option<f64>,
+            Vec<String>,
+        ),
+    >(
+        "SELECT ticker, name, name_en, instrument_type, sector, features, trading_value, \
+                status_flags \
+         FROM stock_features_daily \
+         WHERE as_of_date = $1 \
+           AND ($2::float8 IS NULL OR trading_value >= $2) \
+           AND ($3::float8 IS NULL OR market_cap >= $3) \
+         ORDER BY trading_value DESC NULLS LAST, ticker ASC \
+         LIMIT $4",
+    )
+    .persistent(false)
+    .bind(as_of_date)
+    .bind(opts.min_trading_value)
+    .bind(opts.min_market_cap)
+    .bind(limit as i64)
+    .fetch_all(pool)
+    .await?;
+
+    // Filter out ETFs/ETNs (we only want single-name equities) and, unless opted in, stocks the
+    // exchange has flagged.
+    let screened = rows.len();
+    let mut etf_or_etn: usize = 0;
+    let mut flagged = FlagCounts::default();
     let rows: Vec<_> = rows
         .into_iter()
-        .filter(|(_ticker, name, _features, _tv)| !is_etf_or_etn_name(name))
+        .filter(
+            |(_ticker, name, _name_en, instrument_type, _sector, _features, _tv, status_flags)| {
+                if is_etf_or_etn(instrument_type.as_deref(), name) {
+                    etf_or_etn += 1;
+                    return false;
+                }
+                opts.include_flagged || !flagged.exclude(status_flags)
+            },
+        )
         .collect();
 
-    anyhow::ensure!(
-        rows.len() >= opts.size,
-        "insufficient candidates for as_of_date={as_of_date} after ETF/ETN exclusion: expected at least {}, got {}",
-        opts.size,
-        rows.len()
+    // One listing per common/preferred family (삼성전자 vs 삼성전자우): near-identical exposure.
+    let mut preferred_dups: usize = 0;
+    let rows: Vec<_> = if opts.include_preferred {
+        rows
+    } else {
+        let shares: Vec<ShareRow> = rows
+            .iter()
+            .map(|(ticker, name, _, instrument_type, _, _, tv, _)| ShareRow {
+                ticker,
+                name,
+                instrument_type: instrument_type.as_deref(),
+                trading_value: *tv,
+            })
+            .collect();
+        let drop = preferred_duplicates(&shares);
+        preferred_dups = drop.len();
+        rows.into_iter()
+            .enumerate()
+            .filter(|(i, _)| !drop.contains(i))
+            .map(|(_, row)| row)
+            .collect()
+    };
+    tracing::info!(
+        %as_of_date,
+        screened,
+        excluded_etf_or_etn = etf_or_etn,
+        excluded_flagged = flagged.total,
+        excluded_managed = flagged.managed,
+        excluded_halted = flagged.halted,
+        excluded_warning = flagged.warning,
+        include_flagged = opts.include_flagged,
+        excluded_preferred_duplicates = preferred_dups,
+        include_preferred = opts.include_preferred,
+        eligible = rows.len(),
+        "candidate universe screened"
     );
 
+    if rows.len() < opts.size {
+        return Err(InsufficientUniverse {
+            as_of_date,
+            expected: opts.size,
+            got: rows.len(),
+        }
+        .into());
+    }
+
     // Score candidates: liquidity dominates (trading_value), then a small 1d return tilt.
     let mut scored: Vec<(f64, Candidate)> = Vec::with_capacity(rows.len());
-    for (ticker, name, features_json, trading_value) in rows {
+    for (ticker, name, name_en, _instrument_type, sector, features_json, trading_value, _flags) in
+        rows
+    {
         let features = json_to_feature_map(features_json);
         let tv = trading_value.unwrap_or(0.0);
         let ret_1d = features.get("ret_1d").copied().unwrap_or(0.0);
@@ -160,6 +259,8 @@ pub async fn build_candidate_universe_db(
             Candidate {
                 ticker,
                 name,
+                name_en,
+                sector,
                 features,
             },
         ));
@@ -179,6 +280,121 @@ pub async fn build_candidate_universe_db(
     Ok(out)
 }
 
+/// Rows dropped for their `status_flags`; a row counts once in `total` and under each flag it has.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct FlagCounts {
+    total: usize,
+    managed: usize,
+    halted: usize,
+    warning: usize,
+}
+
+impl FlagCounts {
+    /// Whether a row with `flags` is excluded, counting it if so.
+    fn exclude(&mut self, flags: &[String]) -> bool {
+        if flags.is_empty() {
+            return false;
+        }
+        self.total += 1;
+        for flag in flags {
+            match flag.as_str() {
+                "managed" => self.managed += 1,
+                "halted" => self.halted += 1,
+                "warning" => self.warning += 1,
+                _ => {}
+            }
+        }
+        true
+    }
+}
+
+/// The fields of a universe row the preferred-share dedup looks at.
+#[derive(Debug, Clone, Copy)]
+struct ShareRow<'a> {
+    ticker: &'a str,
+    name: &'a str,
+    instrument_type: Option<&'a str>,
+    trading_value: Option<f64>,
+}
+
+/// Indices of rows to drop so that each common share and its preferreds keep only the most liquid
+/// listing (by trading value; a tie keeps the common share). Families with one row present are
+/// untouched, so a preferred whose common share is not in `rows` stays.
+fn preferred_duplicates(rows: &[ShareRow]) -> BTreeSet<usize> {
+    let mut families: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+    for (i, row) in rows.iter().enumerate() {
+        let code = row.ticker.trim().trim_start_matches("KRX:");
+        let family = if is_preferred(row.instrument_type, row.name, code) {
+            common_code(code).unwrap_or_else(|| code.to_string())
+        } else {
+            code.to_string()
+        };
+        families.entry(family).or_default().push(i);
+    }
+
+    let mut drop = BTreeSet::new();
+    for members in families.into_values().filter(|m| m.len() > 1) {
+        let keep = members
+            .iter()
+            .copied()
+            .max_by(|&a, &b| {
+                let tv = |i: usize| rows[i].trading_value.unwrap_or(0.0);
+                let common = |i: usize| {
+                    let code = rows[i].ticker.trim().trim_start_matches("KRX:");
+                    !is_preferred(rows[i].instrument_type, rows[i].name, code)
+                };
+                tv(a)
+                    .partial_cmp(&tv(b))
+                    .unwrap_or(std::cmp::Ordering::Equal)
+                    .then_with(|| common(a).cmp(&common(b)))
+                    // Lower ticker wins a full tie, deterministically.
+                    .then_with(|| rows[b].ticker.cmp(rows[a].ticker))
+            })
+            .expect("family has members");
+        drop.extend(members.into_iter().filter(|&i| i != keep));
+    }
+    drop
+}
+
+/// By the stored instrument type; rows without one fall back to the name (`...우`, `...우B`,
+/// `...우(전환)`) together with a preferred-looking code, since some common shares' names end in
+/// `우` too (미래에셋대우).
+fn is_preferred(instrument_type: Option<&str>, name: &str, code: &str) -> bool {
+    match instrument_type {
+        Some(t) => t == "preferred",
+        None => {
+            let name = name.trim();
+            let name = name.strip_suffix("(전환)").unwrap_or(name);
+            let name = name.strip_suffix('B').unwrap_or(name);
+            name.ends_with('우') && common_code(code).is_some()
+        }
+    }
+}
+
+/// The common share's code for a preferred share's code: the same first five characters with a
+/// trailing `0`. Preferreds end in `5` (우), `7`/`9` (2우B/3우B, older listings) or, since the
+/// numeric suffixes ran out, a letter (`K`, `L`, `M`, ...). `None` for a code that does not look
+/// like a preferred share, i.e. one already ending in `0` or not six characters long.
+fn common_code(code: &str) -> Option<String> {
+    let code = code.trim();
+    if code.len() != 6 || !code.is_ascii() {
+        return None;
+    }
+    let (prefix, suffix) = code.split_at(5);
+    let suffix = suffix.chars().next()?;
+    let preferred = matches!(suffix, '5' | '7' | '9') || suffix.is_ascii_uppercase();
+    (preferred && prefix.chars().all(|c| c.is_ascii_alphanumeric())).then(|| format!("{prefix}0"))
+}
+
+/// By the stored instrument type; rows without one (history, providers that don't report it) fall
+/// back to a conservative name-based heuristic.
+fn is_etf_or_etn(instrument_type: Option<&str>, name: &str) -> bool {
+    match instrument_type {
+        Some(t) => matches!(t, "etf" | "etn"),
+        None => is_etf_or_etn_name(name),
+    }
+}
+
 fn is_etf_or_etn_name(name: &str) -> bool {
     let s = name.trim();
     if s.is_empty() {
@@ -209,22 +425,6 @@ fn is_etf_or_etn_name(name: &str) -> bool {
         || s.contains("RISE")
 }
 
-fn json_to_feature_map(v: serde_json::Value) -> BTreeMap<String, f64> {
-    let mut out = BTreeMap::new();
-    let obj = match v {
-        serde_json::Value::Object(o) => o,
-        _ => return out,
-    };
-
-    for (k, val) in obj {
-        if let Some(n) = val.as_f64() {
-            out.insert(k, n);
-        }
-    }
-
-    out
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -243,6 +443,8 @@ mod tests {
             Candidate {
                 ticker: "KRX:000001".to_string(),
                 name: "A".to_string(),
+                name_en: None,
+                sector: None,
                 features: json_to_feature_map(json!({"ret_1d": 0.02})),
             },
         );
@@ -251,6 +453,8 @@ mod tests {
             Candidate {
                 ticker: "KRX:000002".to_string(),
                 name: "B".to_string(),
+                name_en: None,
+                sector: None,
                 features: json_to_feature_map(json!({"ret_1d": -0.01})),
             },
         );
@@ -275,4 +479,110 @@ mod tests {
         assert!(is_etf_or_etn_name("Bar ETN"));
         assert!(!is_etf_or_etn_name("삼성전자"));
     }
+
+    #[test]
+    fn flagged_rows_are_excluded_and_counted() {
+        let mut counts = FlagCounts::default();
+        let flags = |f: &[&str]| f.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert!(!counts.exclude(&flags(&[])));
+        assert!(counts.exclude(&flags(&["managed"])));
+        assert!(counts.exclude(&flags(&["managed", "halted"])));
+        assert!(counts.exclude(&flags(&["warning"])));
+        assert_eq!(
+            counts,
+            FlagCounts {
+                total: 3,
+                managed: 2,
+                halted: 1,
+                warning: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn preferred_codes_map_to_their_common_share() {
+        let cases = [
+            // (preferred code, common code)
+            ("005935", Some("005930")), // 삼성전자우
+            ("005385", Some("005380")), // 현대차우
+            ("005387", Some("005380")), // 현대차2우B
+            ("005389", Some("005380")), // 현대차3우B
+            ("00088K", Some("000880")), // 한화3우B
+            ("00104K", Some("001040")), // CJ4우(전환)
+            ("00680K", Some("006800")), // 미래에셋증권2우B
+            (" 051915 ", Some("051910")),
+            // Not preferred-looking.
+            ("005930", None),
+            ("006800", None),
+            ("00593", None),
+            ("0059350", None),
+            ("00593k", None),
+            ("", None),
+        ];
+        for (code, common) in cases {
+            assert_eq!(common_code(code).as_deref(), common, "{code:?}");
+        }
+    }
+
+    #[test]
+    fn preferred_detection_prefers_the_stored_type() {
+        let cases = [
+            // (instrument_type, name, code, preferred)
+            (Some("preferred"), "삼성전자우", "005935", true),
+            (Some("stock"), "삼성전자우", "005935", false),
+            (None, "삼성전자우", "005935", true),
+            (None, "현대차2우B", "005387", true),
+            (None, "CJ4우(전환)", "00104K", true),
+            // A common share whose name ends in 우.
+            (None, "미래에셋대우", "006800", false),
+            // A preferred-looking code without a preferred name.
+            (None, "삼성전자", "005935", false),
+        ];
+        for (instrument_type, name, code, preferred) in cases {
+            assert_eq!(
+                is_preferred(instrument_type, name, code),
+                preferred,
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn preferred_dedup_keeps_the_more_liquid_listing() {
+        let row = |ticker, name, instrument_type, tv| ShareRow {
+            ticker,
+            name,
+            instrument_type,
+            trading_value: Some(tv),
+        };
+        let rows = [
+            row("KRX:005930", "삼성전자", Some("stock"), 900.0),
+            row("KRX:005935", "삼성전자우", Some("preferred"), 100.0),
+            // Preferred trades more than its common share.
+            row("KRX:005380", "현대차", Some("stock"), 50.0),
+            row("KRX:005385", "현대차우", Some("preferred"), 80.0),
+            row("KRX:005387", "현대차2우B", Some("preferred"), 60.0),
+            // Tie: the common share stays.
+            row("KRX:051910", "LG화학", None, 10.0),
+            row("KRX:051915", "LG화학우", None, 10.0),
+            // Common share not in the universe: the preferred stays.
+            row("KRX:00104K", "CJ4우(전환)", Some("preferred"), 5.0),
+            row("KRX:006800", "미래에셋대우", None, 5.0),
+        ];
+        assert_eq!(
+            preferred_duplicates(&rows).into_iter().collect::<Vec<_>>(),
+            [1, 2, 4, 6]
+        );
+    }
+
+    #[test]
+    fn stored_instrument_type_overrides_the_name_heuristic() {
+        assert!(is_etf_or_etn(Some("etf"), "삼성전자"));
+        assert!(is_etf_or_etn(Some("etn"), "삼성 레버리지 WTI원유 선물"));
+        // A company whose name merely looks like a fund wrapper stays in.
+        assert!(!is_etf_or_etn(Some("stock"), "KODEX 코스닥150레버리지"));
+        assert!(!is_etf_or_etn(Some("preferred"), "삼성전자우"));
+        assert!(is_etf_or_etn(None, "TIGER 미국S&P500"));
+        assert!(!is_etf_or_etn(None, "삼성전자"));
+    }
 }